@@ -1,20 +1,30 @@
 use crate::error::ChainbillsError;
 use crate::messages::{
-  AddressMessage, CountMessage, IdMessage, InstantiateMessage,
+  AddressMessage, ChainIdentifier, ChainbillsPaymentNotification, CountMessage,
+  Cw721ExecuteMessage, IdMessage, InstantiateMessage, MigrateMessage,
+  PaymentHookExecuteMessage,
 };
 use crate::state::{
-  ActivityRecord, ActivityType, ChainStats, Config, Payable, PayablePayment,
-  TokenAndAmount, TokenDetails, User, UserPayment, Withdrawal,
+  ActivityRecord, ActivityType, CbChain, ChainStats, Config, ConfigV0,
+  EscrowState, EscrowStatus, MigrationProgress, MigrationStage, Payable,
+  PayableEscrow, PayableHook, PayableOperator, PayablePayment,
+  PaymentHookFailure, Subscription, TokenAndAmount, TokenDetails, TokenGroup,
+  User, UserPayment, Withdrawal,
+};
+use chainbills_payload::{
+  is_allowed_payer, PayablePayload, TokenAndAmountForeign, OPEN_TO_ALL_PAYERS,
+  PAYLOAD_VERSION,
 };
 use cw2::set_contract_version;
 use cw20::Cw20ExecuteMsg;
 use cw_storage_plus::{Item, Map};
 use sha2::{Digest, Sha256};
+use sha3::Keccak256;
 use sylvia::cw_std::{
-  to_json_binary, Addr, Api, Attribute, BankMsg, Coin, Env, HexBinary,
-  Response, StdResult, Storage, Uint128, WasmMsg,
+  to_json_binary, Addr, Api, Attribute, BankMsg, Coin, DepsMut, Env, HexBinary,
+  Reply, Response, StdResult, Storage, SubMsg, Uint128, WasmMsg,
 };
-use sylvia::types::{ExecCtx, InstantiateCtx, QueryCtx};
+use sylvia::types::{ExecCtx, InstantiateCtx, MigrateCtx, QueryCtx, ReplyCtx};
 #[allow(unused_imports)]
 // RustRover IDE doesn't see the use of `entry_points` macro.
 use sylvia::{contract, entry_points};
@@ -22,10 +32,17 @@ use sylvia::{contract, entry_points};
 const CONTRACT_NAME: &str = "crates.io:chainbills";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// A payable's ID and the cbChainId of the chain involved in its payments.
+pub type PerChainKey = (Vec<u8>, [u8; 32]);
+
 pub struct Chainbills {
   pub config: Item<Config>,
   pub chain_stats: Item<ChainStats>,
+  pub cb_chains: Map<[u8; 32], CbChain>,
+  pub wormhole_chain_cb_chain_ids: Map<u16, [u8; 32]>,
+  pub circle_domain_cb_chain_ids: Map<u32, [u8; 32]>,
   pub token_details: Map<String, TokenDetails>,
+  pub token_groups: Map<String, TokenGroup>,
   pub chain_user_addresses: Item<Vec<Addr>>,
   pub chain_payable_ids: Item<Vec<[u8; 32]>>,
   pub chain_user_payment_ids: Item<Vec<[u8; 32]>>,
@@ -40,21 +57,43 @@ pub struct Chainbills {
   pub user_withdrawal_ids: Map<&'static Addr, Vec<[u8; 32]>>,
   pub user_activity_ids: Map<&'static Addr, Vec<[u8; 32]>>,
   pub payables: Map<[u8; 32], Payable>,
+  pub pending_payable_hosts: Map<[u8; 32], Addr>,
+  pub payable_operators: Map<(Vec<u8>, &'static Addr), PayableOperator>,
+  pub payable_allowed_payers_roots: Map<[u8; 32], [u8; 32]>,
+  pub subscriptions: Map<(Vec<u8>, &'static Addr), Subscription>,
+  pub payable_escrows: Map<[u8; 32], PayableEscrow>,
+  pub escrow_states: Map<[u8; 32], EscrowState>,
+  pub payable_hooks: Map<[u8; 32], PayableHook>,
+  pub payment_hook_failures: Map<[u8; 32], PaymentHookFailure>,
+  pub receipts_collection: Item<Addr>,
+  pub payable_receipts: Map<[u8; 32], bool>,
+  pub receipts: Map<[u8; 32], Addr>,
+  pub used_payment_intents: Map<(Vec<u8>, u64), [u8; 32]>,
+  pub payment_references: Map<[u8; 32], String>,
   pub payable_payments: Map<[u8; 32], PayablePayment>,
   pub payable_payment_ids: Map<[u8; 32], Vec<[u8; 32]>>,
   pub payable_withdrawal_ids: Map<[u8; 32], Vec<[u8; 32]>>,
   pub payable_activity_ids: Map<[u8; 32], Vec<[u8; 32]>>,
-  pub per_chain_payable_payments_count: Map<(Vec<u8>, u16), u64>,
-  pub per_chain_payable_payment_ids: Map<(Vec<u8>, u16), Vec<[u8; 32]>>,
+  pub per_chain_payable_payments_count: Map<PerChainKey, u64>,
+  pub per_chain_payable_payment_ids: Map<PerChainKey, Vec<[u8; 32]>>,
   pub withdrawals: Map<[u8; 32], Withdrawal>,
+  pub migration_progress: Item<MigrationProgress>,
 }
 
 #[cfg_attr(not(feature = "library"), entry_points)]
 #[contract]
 #[sv::error(crate::error::ChainbillsError)]
 #[sv::messages(crate::interfaces::activities as Activities)]
+#[sv::messages(crate::interfaces::chains as Chains)]
+#[sv::messages(crate::interfaces::escrows as Escrows)]
+#[sv::messages(crate::interfaces::hooks as Hooks)]
+#[sv::messages(crate::interfaces::integrity as Integrity)]
+#[sv::messages(crate::interfaces::intents as Intents)]
+#[sv::messages(crate::interfaces::migration as Migration)]
 #[sv::messages(crate::interfaces::payables as Payables)]
 #[sv::messages(crate::interfaces::payments as Payments)]
+#[sv::messages(crate::interfaces::receipts as Receipts)]
+#[sv::messages(crate::interfaces::subscriptions as Subscriptions)]
 #[sv::messages(crate::interfaces::token_details as TokenDetailsInterface)]
 #[sv::messages(crate::interfaces::withdrawals as Withdrawals)]
 impl Chainbills {
@@ -62,7 +101,11 @@ impl Chainbills {
     Self {
       config: Item::new("config"),
       chain_stats: Item::new("chain_stats"),
+      cb_chains: Map::new("cb_chains"),
+      wormhole_chain_cb_chain_ids: Map::new("wormhole_chain_cb_chain_ids"),
+      circle_domain_cb_chain_ids: Map::new("circle_domain_cb_chain_ids"),
       token_details: Map::new("token_details"),
+      token_groups: Map::new("token_groups"),
       chain_user_addresses: Item::new("chain_user_addresses"),
       chain_payable_ids: Item::new("chain_payable_ids"),
       chain_user_payment_ids: Item::new("chain_user_payment_ids"),
@@ -77,6 +120,19 @@ impl Chainbills {
       user_withdrawal_ids: Map::new("user_withdrawal_ids"),
       user_activity_ids: Map::new("user_activity_ids"),
       payables: Map::new("payables"),
+      pending_payable_hosts: Map::new("pending_payable_hosts"),
+      payable_operators: Map::new("payable_operators"),
+      payable_allowed_payers_roots: Map::new("payable_allowed_payers_roots"),
+      subscriptions: Map::new("subscriptions"),
+      payable_escrows: Map::new("payable_escrows"),
+      escrow_states: Map::new("escrow_states"),
+      payable_hooks: Map::new("payable_hooks"),
+      payment_hook_failures: Map::new("payment_hook_failures"),
+      receipts_collection: Item::new("receipts_collection"),
+      payable_receipts: Map::new("payable_receipts"),
+      receipts: Map::new("receipts"),
+      used_payment_intents: Map::new("used_payment_intents"),
+      payment_references: Map::new("payment_references"),
       payable_payments: Map::new("payable_payments"),
       payable_payment_ids: Map::new("payable_payment_ids"),
       payable_withdrawal_ids: Map::new("payable_withdrawal_ids"),
@@ -86,6 +142,7 @@ impl Chainbills {
       ),
       per_chain_payable_payment_ids: Map::new("per_chain_payable_payment_ids"),
       withdrawals: Map::new("withdrawals"),
+      migration_progress: Item::new("migration_progress"),
     }
   }
 
@@ -94,7 +151,7 @@ impl Chainbills {
     &self,
     ctx: InstantiateCtx,
     msg: InstantiateMessage,
-  ) -> StdResult<Response> {
+  ) -> Result<Response, ChainbillsError> {
     // Set Contract Version
     set_contract_version(ctx.deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
@@ -103,15 +160,21 @@ impl Chainbills {
       .chain_stats
       .save(ctx.deps.storage, &mut ChainStats::initialize())?;
 
+    // Register this chain by its CAIP-2 and Wormhole Chain ID.
+    let cb_chain =
+      self.save_wormhole_cb_chain(ctx.deps.storage, msg.caip2, msg.chain_id)?;
+
     // Initialize Config
     let cbfc = ctx.deps.api.addr_validate(&msg.chainbills_fee_collector)?;
     self.config.save(
       ctx.deps.storage,
       &Config {
         chain_id: msg.chain_id,
+        cb_chain_id: cb_chain.cb_chain_id,
         owner: ctx.info.sender.clone(),
         chainbills_fee_collector: cbfc,
         withdrawal_fee_percentage: Uint128::new(200),
+        token_bridge: None,
       },
     )?;
 
@@ -132,9 +195,72 @@ impl Chainbills {
       ("action", "instantiated"),
       ("owner", &*ctx.info.sender.as_str()),
       ("version", CONTRACT_VERSION),
+      ("caip2", &cb_chain.caip2),
     ]))
   }
 
+  #[sv::msg(migrate)]
+  fn migrate(
+    &self,
+    ctx: MigrateCtx,
+    msg: MigrateMessage,
+  ) -> Result<Response, ChainbillsError> {
+    // Only a Config without a cbChainId is upgraded.
+    if self.config.load(ctx.deps.storage).is_ok() {
+      return Err(ChainbillsError::AlreadyMigrated {});
+    }
+    let legacy_config: Item<ConfigV0> = Item::new("config");
+    let legacy_config = legacy_config.load(ctx.deps.storage)?;
+    set_contract_version(ctx.deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    // Register this chain and the other chains in stored payments.
+    let cb_chain = self.save_wormhole_cb_chain(
+      ctx.deps.storage,
+      msg.caip2,
+      legacy_config.chain_id,
+    )?;
+    for chain in msg.foreign_chains {
+      self.save_wormhole_cb_chain(
+        ctx.deps.storage,
+        chain.caip2,
+        chain.wormhole_chain_id,
+      )?;
+    }
+    self.config.save(
+      ctx.deps.storage,
+      &legacy_config.upgrade(cb_chain.cb_chain_id),
+    )?;
+
+    // Key payments and per-chain counts by cbChainIds instead of Wormhole
+    // Chain IDs. Stored payments are moved in pages with continue_migration,
+    // so that no transaction has to move all of them. Until then, they are
+    // read in their old format.
+    self.migration_progress.save(
+      ctx.deps.storage,
+      &MigrationProgress {
+        stage: MigrationStage::UserPayments,
+        cursor: None,
+      },
+    )?;
+
+    // Emit an event and return a response.
+    Ok(Response::new().add_attributes([
+      ("action", "migrated"),
+      ("version", CONTRACT_VERSION),
+      ("caip2", &cb_chain.caip2),
+    ]))
+  }
+
+  #[sv::msg(reply)]
+  fn reply(
+    &self,
+    ctx: ReplyCtx,
+    reply: Reply,
+  ) -> Result<Response, ChainbillsError> {
+    // Only notifications to payable hooks are replied to.
+    self.record_payment_hook_failure(ctx, reply)
+  }
+
   #[sv::msg(query)]
   fn chain_stats(&self, ctx: QueryCtx) -> StdResult<ChainStats> {
     Ok(self.chain_stats.load(ctx.deps.storage)?)
@@ -243,6 +369,30 @@ impl Chainbills {
     )
   }
 
+  #[sv::msg(exec)]
+  fn update_token_bridge(
+    &self,
+    ctx: ExecCtx,
+    msg: AddressMessage,
+  ) -> Result<Response, ChainbillsError> {
+    // Ensure the caller is the owner.
+    let mut config = self.config.load(ctx.deps.storage)?;
+    if ctx.info.sender != config.owner {
+      return Err(ChainbillsError::OwnerUnauthorized {});
+    }
+
+    // Validate and save the Wormhole Token Bridge.
+    let token_bridge = ctx.deps.api.addr_validate(msg.address.as_str())?;
+    config.token_bridge = Some(token_bridge.clone());
+    self.config.save(ctx.deps.storage, &config)?;
+
+    // Return the Response.
+    Ok(Response::new().add_attributes([
+      ("action", "updated_token_bridge".to_string()),
+      ("token_bridge", token_bridge.to_string()),
+    ]))
+  }
+
   pub fn initialize_user_if_is_new(
     &self,
     storage: &mut dyn Storage,
@@ -330,6 +480,78 @@ impl Chainbills {
     Ok(hasher.finalize().into())
   }
 
+  /// Resolves any form of chain identifier to its cbChainId. CAIP-2 strings
+  /// and cbChainIds resolve without being registered, while Wormhole Chain
+  /// IDs and Circle domains must have been registered.
+  pub fn resolve_cb_chain_id(
+    &self,
+    storage: &dyn Storage,
+    chain: &ChainIdentifier,
+  ) -> Result<[u8; 32], ChainbillsError> {
+    let resolved = match chain {
+      ChainIdentifier::Caip2(caip2) => {
+        if !CbChain::is_valid_caip2(caip2) {
+          return Err(ChainbillsError::InvalidCaip2 {
+            caip2: caip2.clone(),
+          });
+        }
+        Some(CbChain::id_of(caip2))
+      }
+      ChainIdentifier::CbChainId(hex) => Some(
+        <[u8; 32]>::try_from(HexBinary::from_hex(hex)?.as_slice()).map_err(
+          |_| ChainbillsError::InvalidChainId {
+            chain_id: hex.clone(),
+          },
+        )?,
+      ),
+      ChainIdentifier::WormholeChainId(id) => {
+        self.wormhole_chain_cb_chain_ids.may_load(storage, *id)?
+      }
+      ChainIdentifier::CircleDomain(domain) => {
+        self.circle_domain_cb_chain_ids.may_load(storage, *domain)?
+      }
+    };
+    resolved.ok_or_else(|| ChainbillsError::UnregisteredChain {
+      chain: format!("{:?}", chain),
+    })
+  }
+
+  /// Registers a chain by its CAIP-2 and Wormhole Chain ID. Used when
+  /// instantiating and migrating, where the owner can't be checked.
+  fn save_wormhole_cb_chain(
+    &self,
+    storage: &mut dyn Storage,
+    caip2: String,
+    wormhole_chain_id: u16,
+  ) -> Result<CbChain, ChainbillsError> {
+    if !CbChain::is_valid_caip2(&caip2) {
+      return Err(ChainbillsError::InvalidCaip2 { caip2 });
+    }
+    let mut cb_chain = CbChain::initialize(caip2);
+    cb_chain.wormhole_chain_id = Some(wormhole_chain_id);
+    self
+      .cb_chains
+      .save(storage, cb_chain.cb_chain_id, &cb_chain)?;
+    self.wormhole_chain_cb_chain_ids.save(
+      storage,
+      wormhole_chain_id,
+      &cb_chain.cb_chain_id,
+    )?;
+    Ok(cb_chain)
+  }
+
+  /// The cbChainId of a registered Wormhole Chain ID.
+  pub fn cb_chain_id_of_wormhole(
+    &self,
+    storage: &dyn Storage,
+    wormhole_chain_id: u16,
+  ) -> Result<[u8; 32], ChainbillsError> {
+    self.resolve_cb_chain_id(
+      storage,
+      &ChainIdentifier::WormholeChainId(wormhole_chain_id),
+    )
+  }
+
   pub fn address_to_bytes32(&self, addr: &Addr, api: &dyn Api) -> [u8; 32] {
     let slice = api.addr_canonicalize(addr.as_ref()).unwrap();
     let mut result = [0u8; 32];
@@ -338,6 +560,72 @@ impl Chainbills {
     result
   }
 
+  /// The keccak256 of the concatenation of the given slices. Used for the
+  /// Merkle trees of payables' allowed payers.
+  pub fn keccak(data: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    for slice in data {
+      hasher.update(slice);
+    }
+    hasher.finalize().into()
+  }
+
+  /// Wormhole-normalizes a token for cross-chain payloads. Cw20 tokens are
+  /// their canonical address while native tokens are the SHA-256 hash of
+  /// their denom, as denoms (e.g. IBC ones) don't always fit in 32 bytes.
+  /// Token group references are hashed like denoms.
+  pub fn token_to_bytes32(
+    &self,
+    storage: &dyn Storage,
+    api: &dyn Api,
+    token: &str,
+  ) -> StdResult<[u8; 32]> {
+    if TokenGroup::referenced_name(token).is_some() {
+      return Ok(Sha256::digest(token.as_bytes()).into());
+    }
+    let token_details = self.token_details.load(storage, token.to_string())?;
+    if token_details.is_native_token {
+      Ok(Sha256::digest(token.as_bytes()).into())
+    } else {
+      Ok(self.address_to_bytes32(&Addr::unchecked(token), api))
+    }
+  }
+
+  /// Encodes the PayablePayload that tells other chains about a payable's
+  /// creation or update. The payable's activities_count is the nonce.
+  pub fn encode_payable_payload(
+    &self,
+    storage: &dyn Storage,
+    api: &dyn Api,
+    payable_id: [u8; 32],
+    payable: &Payable,
+    action_type: u8,
+  ) -> Result<HexBinary, ChainbillsError> {
+    let mut allowed_tokens_and_amounts = vec![];
+    for taa in payable.allowed_tokens_and_amounts.iter() {
+      allowed_tokens_and_amounts.push(TokenAndAmountForeign {
+        token: self.token_to_bytes32(storage, api, &taa.token)?,
+        amount: u64::try_from(taa.amount.u128()).map_err(|_| {
+          ChainbillsError::AmountTooLargeForPayload { amount: taa.amount }
+        })?,
+      });
+    }
+
+    let payload = PayablePayload {
+      version: PAYLOAD_VERSION,
+      action_type,
+      payable_id,
+      nonce: payable.activities_count,
+      is_closed: payable.is_closed,
+      allowed_tokens_and_amounts,
+      allowed_payers_root: self
+        .payable_allowed_payers_roots
+        .may_load(storage, payable_id)?
+        .unwrap_or(OPEN_TO_ALL_PAYERS),
+    };
+    Ok(HexBinary::from(payload.encode()?))
+  }
+
   pub fn save_activity_id_for_all(
     &self,
     storage: &mut dyn Storage,
@@ -372,6 +660,58 @@ impl Chainbills {
     Ok(())
   }
 
+  /// Ensures that the wallet is the payable's host or an operator to whom
+  /// the host granted the role.
+  pub fn ensure_host_or_operator(
+    &self,
+    storage: &dyn Storage,
+    payable_id: [u8; 32],
+    payable: &Payable,
+    wallet: &Addr,
+    role: u8,
+  ) -> Result<(), ChainbillsError> {
+    if payable.host == wallet {
+      return Ok(());
+    }
+    match self
+      .payable_operators
+      .may_load(storage, (payable_id.to_vec(), wallet))?
+    {
+      Some(operator) if operator.permits(&payable.host, role) => Ok(()),
+      _ => Err(ChainbillsError::NotYourPayable {}),
+    }
+  }
+
+  /// Ensures that the payer is among the payable's allowed payers, if the
+  /// payable restricted its payers. The proof is hex-encoded.
+  pub fn ensure_allowed_payer(
+    &self,
+    storage: &dyn Storage,
+    api: &dyn Api,
+    payable_id: [u8; 32],
+    payer: &Addr,
+    payer_proof: &[String],
+  ) -> Result<(), ChainbillsError> {
+    let Some(root) = self
+      .payable_allowed_payers_roots
+      .may_load(storage, payable_id)?
+    else {
+      return Ok(());
+    };
+    let mut proof = vec![];
+    for node in payer_proof.iter() {
+      match <[u8; 32]>::try_from(HexBinary::from_hex(node)?.as_slice()) {
+        Ok(node) => proof.push(node),
+        Err(_) => return Err(ChainbillsError::PayerNotAllowed {}),
+      }
+    }
+    let payer = self.address_to_bytes32(payer, api);
+    if !is_allowed_payer(&root, &payer, &proof, &Chainbills::keccak) {
+      return Err(ChainbillsError::PayerNotAllowed {});
+    }
+    Ok(())
+  }
+
   pub fn record_update_payable_activity(
     &self,
     storage: &mut dyn Storage,
@@ -423,4 +763,490 @@ impl Chainbills {
 
     Ok(())
   }
+
+  /// Ensures that the token is supported and that the amount isn't zero.
+  /// Returns the token's details.
+  pub fn check_token_and_amount(
+    &self,
+    storage: &dyn Storage,
+    token: &str,
+    amount: Uint128,
+  ) -> Result<TokenDetails, ChainbillsError> {
+    // Fetch the TokenDetails details for the involved token.
+    let token_details = self
+      .token_details
+      .load(storage, token.to_string())
+      .unwrap_or(TokenDetails::initialize(false, false, Uint128::zero()));
+
+    // Return an error if the token isn't supported.
+    if !token_details.is_supported {
+      return Err(ChainbillsError::UnsupportedToken {
+        token: token.to_string(),
+      });
+    }
+
+    // Ensure that amount is greater than zero.
+    if amount.is_zero() {
+      return Err(ChainbillsError::ZeroAmountSpecified {});
+    }
+
+    Ok(token_details)
+  }
+
+  /// Ensures that a payable can accept the allowed tokens and amounts. Each
+  /// must be a supported token or reference an existing token group, with a
+  /// non-zero amount.
+  pub fn check_allowed_tokens_and_amounts(
+    &self,
+    storage: &dyn Storage,
+    allowed_tokens_and_amounts: &[TokenAndAmount],
+  ) -> Result<(), ChainbillsError> {
+    for taa in allowed_tokens_and_amounts.iter() {
+      if let Some(name) = TokenGroup::referenced_name(&taa.token) {
+        // Ensure that the referenced group exists.
+        if !self.token_groups.has(storage, name.to_string()) {
+          return Err(ChainbillsError::InvalidTokenGroup {
+            name: name.to_string(),
+          });
+        }
+        if taa.amount.is_zero() {
+          return Err(ChainbillsError::ZeroAmountSpecified {});
+        }
+      } else {
+        // Ensure that the token is supported and that the amount isn't zero.
+        self.check_token_and_amount(storage, &taa.token, taa.amount)?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Ensures that the payable accepts a payment of the amount in the token.
+  /// Allowed tokens that reference a token group match payments in any of
+  /// its members, for the amount in the member's decimals. Returns the
+  /// token's details.
+  pub fn check_payment(
+    &self,
+    storage: &dyn Storage,
+    payable: &Payable,
+    token: &str,
+    amount: Uint128,
+  ) -> Result<TokenDetails, ChainbillsError> {
+    let token_details = self.check_token_and_amount(storage, token, amount)?;
+
+    // If this payable specified the tokens and amounts it can accept, ensure
+    // that the token and amount are matching.
+    if !payable.allowed_tokens_and_amounts.is_empty() {
+      let mut ataa_it = payable.allowed_tokens_and_amounts.iter().peekable();
+      while let Some(taa) = ataa_it.next() {
+        if taa.token == token && taa.amount == amount {
+          break;
+        }
+        if let Some(name) = TokenGroup::referenced_name(&taa.token) {
+          let group = self.token_groups.may_load(storage, name.to_string())?;
+          if group.and_then(|g| g.member_amount(token, taa.amount))
+            == Some(amount)
+          {
+            break;
+          }
+        }
+        if ataa_it.peek().is_none() {
+          return Err(ChainbillsError::MatchingTokenAndAmountNotFound {});
+        }
+      }
+    }
+
+    Ok(token_details)
+  }
+
+  /// Records a payment that the contract has received (or is receiving in
+  /// the same transaction) from the payer into the payable. Saves the
+  /// UserPayment and PayablePayment pair with their activities and returns
+  /// the Response with their details, with the mint of the payer's receipt
+  /// if the payable mints them, and with the notification to the payable's
+  /// hook contract if it has one.
+  pub fn record_payment(
+    &self,
+    deps: DepsMut,
+    env: &Env,
+    payer: &Addr,
+    payable_id: [u8; 32],
+    payment: TokenAndAmount,
+    reference: Option<String>,
+  ) -> Result<Response, ChainbillsError> {
+    let TokenAndAmount { token, amount } = payment;
+    let mut payable = self.payables.load(deps.storage, payable_id)?;
+
+    // Fetch the TokenDetails details for the involved token.
+    let mut token_details = self
+      .token_details
+      .load(deps.storage, token.clone())
+      .unwrap_or(TokenDetails::initialize(false, false, Uint128::zero()));
+
+    /* COUNTS */
+    // Increment payments and activities count on the payer (address) making
+    // this payable.
+    let user_resp_attrib =
+      self.initialize_user_if_is_new(deps.storage, env, payer)?;
+    let mut user = self.users.load(deps.storage, payer)?;
+    user.payments_count = user.next_payment();
+    user.activities_count = user.next_activity();
+    self.users.save(deps.storage, payer, &user)?;
+
+    // Increment the chain stats for counts of payments.
+    let mut chain_stats = self.chain_stats.load(deps.storage)?;
+    chain_stats.user_payments_count = chain_stats.next_user_payment();
+    chain_stats.payable_payments_count = chain_stats.next_payable_payment();
+
+    // Increment the chain stats for activities_count.
+    //
+    // Incrementing twice to account for recording two activities: one for the
+    // user and one for the payable.
+    chain_stats.activities_count = chain_stats.next_activity();
+    chain_stats.activities_count = chain_stats.next_activity();
+
+    // Save the updated chain stats.
+    self.chain_stats.save(deps.storage, &chain_stats)?;
+
+    // Increment global payments_count and the activities_count on the payable.
+    payable.payments_count = payable.next_payment();
+    payable.activities_count = payable.next_activity();
+
+    // Update payable's balances to add this token and its amount, unless the
+    // payable escrows its payments. Escrowed payments are added on release.
+    let escrow = self.payable_escrows.may_load(deps.storage, payable_id)?;
+    if escrow.is_none() {
+      payable.add_balance(&token, amount);
+    }
+
+    // Save the Updated Payable.
+    self.payables.save(deps.storage, payable_id, &payable)?;
+
+    let config = self.config.load(deps.storage)?;
+
+    // Move the payable's local payments to this chain's cbChainId first, if
+    // the migration hasn't yet.
+    if self.migration_progress.exists(deps.storage) {
+      self.migrate_per_chain_payable_payments(
+        deps.storage,
+        payable_id.to_vec(),
+        config.chain_id,
+      )?;
+    }
+
+    // Increment the local-chain paymentsCount for the payable.
+    let mut local_chain_count = self
+      .per_chain_payable_payments_count
+      .may_load(deps.storage, (payable_id.to_vec(), config.cb_chain_id))?
+      .unwrap_or_default();
+    local_chain_count = local_chain_count.checked_add(1).unwrap();
+    self.per_chain_payable_payments_count.save(
+      deps.storage,
+      (payable_id.to_vec(), config.cb_chain_id),
+      &local_chain_count,
+    )?;
+
+    // Increase the supported token's totals from this payment.
+    token_details.add_user_paid(amount);
+    token_details.add_payable_received(amount);
+    self
+      .token_details
+      .save(deps.storage, token.clone(), &token_details)?;
+
+    /* PAYMENTS DATA STRUCTURES */
+    // Get a new Payment ID for the User
+    let user_payment_id = self.create_id(
+      deps.storage,
+      env,
+      payer.as_str(),
+      "payment",
+      user.payments_count,
+    )?;
+
+    // Add the Payment ID to the chain_user_payment_ids.
+    let mut chain_user_payment_ids =
+      self.chain_user_payment_ids.load(deps.storage)?;
+    chain_user_payment_ids.push(user_payment_id);
+    self
+      .chain_user_payment_ids
+      .save(deps.storage, &chain_user_payment_ids)?;
+
+    // Save the Payment ID to the users_payment_ids.
+    let mut user_payment_ids = self
+      .user_payment_ids
+      .may_load(deps.storage, payer)?
+      .unwrap_or_default();
+    user_payment_ids.push(user_payment_id);
+    self
+      .user_payment_ids
+      .save(deps.storage, payer, &user_payment_ids)?;
+
+    let timestamp = env.block.time.seconds();
+    let details = TokenAndAmount {
+      token: token.clone(),
+      amount,
+    };
+
+    // Create and Save the UserPayment.
+    let user_payment = UserPayment {
+      payable_id,
+      payer: payer.clone(),
+      payable_chain_id: config.cb_chain_id,
+      chain_count: chain_stats.user_payments_count,
+      payer_count: user.payments_count,
+      timestamp,
+      details: details.clone(),
+    };
+    self
+      .user_payments
+      .save(deps.storage, user_payment_id, &user_payment)?;
+
+    // Get a new Payment ID for the Payable.
+    let payable_payment_id = self.create_id(
+      deps.storage,
+      env,
+      &HexBinary::from(&user_payment_id).to_hex(),
+      "payment",
+      payable.payments_count,
+    )?;
+
+    // Add the Payment ID to the chain_payable_payment_ids.
+    let mut chain_payable_payment_ids =
+      self.chain_payable_payment_ids.load(deps.storage)?;
+    chain_payable_payment_ids.push(payable_payment_id);
+    self
+      .chain_payable_payment_ids
+      .save(deps.storage, &chain_payable_payment_ids)?;
+
+    // Save the Payment ID to the payables_payment_ids.
+    let mut payable_payment_ids = self
+      .payable_payment_ids
+      .may_load(deps.storage, payable_id)?
+      .unwrap_or_default();
+    payable_payment_ids.push(payable_payment_id);
+    self.payable_payment_ids.save(
+      deps.storage,
+      payable_id,
+      &payable_payment_ids,
+    )?;
+
+    // Save the Payment ID to the per_chain_payable_payment_ids.
+    let mut per_chain_payable_payment_ids = self
+      .per_chain_payable_payment_ids
+      .may_load(deps.storage, (payable_id.to_vec(), config.cb_chain_id))?
+      .unwrap_or_default();
+    per_chain_payable_payment_ids.push(payable_payment_id);
+    self.per_chain_payable_payment_ids.save(
+      deps.storage,
+      (payable_id.to_vec(), config.cb_chain_id),
+      &per_chain_payable_payment_ids,
+    )?;
+
+    // Create and Save the PayablePayment.
+    let payable_payment = PayablePayment {
+      payable_id,
+      payer: self.address_to_bytes32(payer, deps.api),
+      chain_count: chain_stats.payable_payments_count,
+      payer_chain_id: config.cb_chain_id,
+      local_chain_count,
+      payable_count: payable.payments_count,
+      timestamp,
+      details: details.clone(),
+    };
+    self.payable_payments.save(
+      deps.storage,
+      payable_payment_id,
+      &payable_payment,
+    )?;
+
+    // Record the payment's reference, if any, under the PayablePayment's ID.
+    if let Some(reference) = &reference {
+      self.payment_references.save(
+        deps.storage,
+        payable_payment_id,
+        reference,
+      )?;
+    }
+
+    // Lock the payment in escrow. It shares its ID with the PayablePayment.
+    let mut escrow_attribs = vec![];
+    if let Some(escrow) = escrow {
+      let release_after = timestamp.checked_add(escrow.timeout).unwrap();
+      self.escrow_states.save(
+        deps.storage,
+        payable_payment_id,
+        &EscrowState {
+          payable_id,
+          payer: payer.clone(),
+          details: details.clone(),
+          release_after,
+          arbiter: escrow.arbiter,
+          status: EscrowStatus::Locked,
+        },
+      )?;
+      escrow_attribs.push(("escrow_release_after", release_after.to_string()));
+    }
+
+    // Mint the payer's receipt if the payable mints them. The receipt's
+    // token ID is the UserPayment's ID.
+    let mut receipt_messages = vec![];
+    if self.payable_receipts.has(deps.storage, payable_id) {
+      if let Some(collection) =
+        self.receipts_collection.may_load(deps.storage)?
+      {
+        self
+          .receipts
+          .save(deps.storage, user_payment_id, &collection)?;
+        receipt_messages.push(WasmMsg::Execute {
+          contract_addr: collection.to_string(),
+          funds: vec![],
+          msg: to_json_binary(&Cw721ExecuteMessage::Mint {
+            token_id: HexBinary::from(&user_payment_id).to_hex(),
+            owner: payer.to_string(),
+            token_uri: None,
+            extension: None,
+          })?,
+        });
+      }
+    }
+
+    // Notify the payable's hook contract of the payment.
+    let hook_messages =
+      match self.payable_hooks.may_load(deps.storage, payable_id)? {
+        Some(hook) => {
+          let notification = WasmMsg::Execute {
+            contract_addr: hook.contract.to_string(),
+            funds: vec![],
+            msg: to_json_binary(
+              &PaymentHookExecuteMessage::ChainbillsPaymentNotification(
+                ChainbillsPaymentNotification {
+                  payable_id: HexBinary::from(&payable_id).to_hex(),
+                  payment_id: HexBinary::from(&payable_payment_id).to_hex(),
+                  payer: payer.to_string(),
+                  token: token.clone(),
+                  amount,
+                  reference,
+                },
+              ),
+            )?,
+          };
+          // Cap the hook's gas, so that a hook that burns it all only fails
+          // its notification.
+          if hook.revert_on_error {
+            vec![SubMsg::new(notification).with_gas_limit(hook.gas_limit)]
+          } else {
+            // Failures are recorded in reply. The reply ID is the payment's
+            // chain count, which locates its ID in chain_payable_payment_ids.
+            vec![SubMsg::reply_on_error(
+              notification,
+              chain_stats.payable_payments_count,
+            )
+            .with_gas_limit(hook.gas_limit)]
+          }
+        }
+        None => vec![],
+      };
+
+    /* ACTIVITIES DATA STRUCTURES */
+    // Create a new ActivityRecord ID from user's perspective.
+    let user_activity_id = self.create_id(
+      deps.storage,
+      env,
+      payer.as_str(),
+      "activity",
+      user.activities_count,
+    )?;
+
+    // Create a new ActivityRecord ID from payable's perspective.
+    let payable_activity_id = self.create_id(
+      deps.storage,
+      env,
+      &HexBinary::from(&payable_id).to_hex(),
+      "activity",
+      payable.activities_count,
+    )?;
+
+    // Save the User and Payable ActivityRecord IDs to chain_activity_ids.
+    let mut chain_activity_ids = self.chain_activity_ids.load(deps.storage)?;
+    chain_activity_ids.push(user_activity_id);
+    chain_activity_ids.push(payable_activity_id);
+    self
+      .chain_activity_ids
+      .save(deps.storage, &chain_activity_ids)?;
+
+    // Save the User ActivityRecord ID to user_activity_ids.
+    let mut user_activity_ids = self
+      .user_activity_ids
+      .may_load(deps.storage, payer)?
+      .unwrap_or_default();
+    user_activity_ids.push(user_activity_id);
+    self
+      .user_activity_ids
+      .save(deps.storage, payer, &user_activity_ids)?;
+
+    // Save the Payable ActivityRecord ID to payable_activity_ids.
+    let mut payable_activity_ids =
+      self.payable_activity_ids.load(deps.storage, payable_id)?;
+    payable_activity_ids.push(payable_activity_id);
+    self.payable_activity_ids.save(
+      deps.storage,
+      payable_id,
+      &payable_activity_ids,
+    )?;
+
+    // Create and Save the ActivityRecord for the User.
+    self.activities.save(
+      deps.storage,
+      user_activity_id,
+      &ActivityRecord {
+        // subtracting 1 because we incremented the activities_count twice.
+        chain_count: chain_stats.activities_count.checked_sub(1).unwrap(),
+        user_count: user.activities_count,
+        payable_count: 0, // Setting 0 because it's not a payable activity.
+        timestamp: env.block.time.seconds(),
+        entity: HexBinary::from(&user_payment_id).to_hex(),
+        activity_type: ActivityType::UserPaid,
+      },
+    )?;
+
+    // Create and Save the ActivityRecord for the Payable.
+    self.activities.save(
+      deps.storage,
+      payable_activity_id,
+      &ActivityRecord {
+        chain_count: chain_stats.activities_count,
+        user_count: 0, // Setting 0 because it's not a user activity.
+        payable_count: payable.activities_count,
+        timestamp: env.block.time.seconds(),
+        entity: HexBinary::from(&payable_payment_id).to_hex(),
+        activity_type: ActivityType::PayableReceived,
+      },
+    )?;
+
+    /* FINISH */
+    // Return the Response.
+    Ok(
+      Response::new()
+        .add_attributes(user_resp_attrib) // Add the user init attributes
+        .add_attributes([
+          // Shared Details
+          ("payable_id", HexBinary::from(&payable_id).to_hex()),
+          ("payer_wallet", payer.to_string()),
+          // Details relative to the user
+          ("action", "user_paid".to_string()),
+          ("user_payment_id", HexBinary::from(&user_payment_id).to_hex()),
+          ("user_chain_count", chain_stats.user_payments_count.to_string()),
+          ("payable_chain_id", HexBinary::from(&config.cb_chain_id).to_hex()),
+          ("payer_count", user.payments_count.to_string()),
+          // Details relative to the payable
+          ("action", "payable_received".to_string()),
+          ("payable_payment_id", HexBinary::from(&payable_payment_id).to_hex()),
+          ("payable_chain_count", chain_stats.payable_payments_count.to_string()),
+          ("payer_chain_id", HexBinary::from(&config.cb_chain_id).to_hex()),
+          ("payable_count", payable.payments_count.to_string()),
+        ])
+        .add_attributes(escrow_attribs)
+        .add_messages(receipt_messages)
+        .add_submessages(hook_messages),
+    )
+  }
 }
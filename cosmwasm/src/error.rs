@@ -1,5 +1,6 @@
+use chainbills_payload::PayloadError;
 use cw_utils::PaymentError;
-use sylvia::cw_std::StdError;
+use sylvia::cw_std::{StdError, Uint128};
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -10,6 +11,9 @@ pub enum ChainbillsError {
   #[error("Payment error: {0}")]
   NativePayment(#[from] PaymentError),
 
+  #[error("Payload error: {0}")]
+  Payload(#[from] PayloadError),
+
   #[error("Invalid Chain User Address Count: {count}")]
   InvalidChainUserAddressCount { count: u64 },
 
@@ -65,7 +69,7 @@ pub enum ChainbillsError {
   InvalidPaymentId { id: String },
 
   #[error("Invalid Chain ID: {chain_id}")]
-  InvalidChainId { chain_id: u16 },
+  InvalidChainId { chain_id: String },
 
   #[error("Invalid Per Chain Payable Payment Count: {count}")]
   InvalidPerChainPayablePaymentCount { count: u64 },
@@ -102,4 +106,139 @@ pub enum ChainbillsError {
 
   #[error("Invalid Activity ID: {id}")]
   InvalidActivityId { id: String },
+
+  #[error("Amount Too Large For Payload: {amount}")]
+  AmountTooLargeForPayload { amount: Uint128 },
+
+  #[error("Invalid CAIP-2: {caip2}")]
+  InvalidCaip2 { caip2: String },
+
+  #[error("Unregistered Chain: {chain}")]
+  UnregisteredChain { chain: String },
+
+  #[error("Chain ID Already Registered: {chain}")]
+  ChainIdAlreadyRegistered { chain: String },
+
+  #[error("Token Bridge Not Set")]
+  TokenBridgeNotSet {},
+
+  #[error("Invalid Destination Chain: {chain}")]
+  InvalidDestinationChain { chain: String },
+
+  #[error("Invalid Destination Address: {address}")]
+  InvalidDestinationAddress { address: String },
+
+  #[error("Cannot Transfer Payable To Self")]
+  CannotTransferPayableToSelf {},
+
+  #[error("Not Pending Payable Host")]
+  NotPendingPayableHost {},
+
+  #[error("Invalid Operator Roles: {roles}")]
+  InvalidOperatorRoles { roles: u8 },
+
+  #[error("Not Payable Operator: {operator}")]
+  NotPayableOperator { operator: String },
+
+  #[error("Invalid Allowed Payer: {payer}")]
+  InvalidAllowedPayer { payer: String },
+
+  #[error("Invalid Allowed Payers Root")]
+  InvalidAllowedPayersRoot {},
+
+  #[error("Payer Not Allowed")]
+  PayerNotAllowed {},
+
+  #[error("Invalid Subscription Period")]
+  InvalidSubscriptionPeriod {},
+
+  #[error("Subscription Not Found")]
+  SubscriptionNotFound {},
+
+  #[error("Subscription Already Exists")]
+  SubscriptionAlreadyExists {},
+
+  #[error("Subscription Is Cancelled")]
+  SubscriptionIsCancelled {},
+
+  #[error("Subscription Not Due Until: {next_due}")]
+  SubscriptionNotDue { next_due: u64 },
+
+  #[error("Insufficient Subscription Deposit")]
+  InsufficientSubscriptionDeposit {},
+
+  #[error("Not Your Subscription")]
+  NotYourSubscription {},
+
+  #[error("Escrow Not Found")]
+  EscrowNotFound {},
+
+  #[error("Escrow Is Settled")]
+  EscrowIsSettled {},
+
+  #[error("Escrow Is Disputed")]
+  EscrowIsDisputed {},
+
+  #[error("Escrow Not Releasable Until: {release_after}")]
+  EscrowNotReleasable { release_after: u64 },
+
+  #[error("Escrow Dispute Window Closed")]
+  EscrowDisputeWindowClosed {},
+
+  #[error("Escrow Has No Arbiter")]
+  EscrowHasNoArbiter {},
+
+  #[error("Not Your Escrow")]
+  NotYourEscrow {},
+
+  #[error("Empty Payment Batch")]
+  EmptyPaymentBatch {},
+
+  #[error("Invalid Hook Contract")]
+  InvalidHookContract {},
+
+  #[error("Invalid Hook Gas Limit: {gas_limit}")]
+  InvalidHookGasLimit { gas_limit: u64 },
+
+  #[error("Unknown Reply ID: {id}")]
+  UnknownReplyId { id: u64 },
+
+  #[error("Invalid Receipts Collection")]
+  InvalidReceiptsCollection {},
+
+  #[error("Receipts Collection Not Set")]
+  ReceiptsCollectionNotSet {},
+
+  #[error("Invalid Receipt ID: {id}")]
+  InvalidReceiptId { id: String },
+
+  #[error("Invalid Intent Key")]
+  InvalidIntentKey {},
+
+  #[error("Intent Key Not Host's")]
+  IntentKeyNotHost {},
+
+  #[error("Invalid Intent Signature")]
+  InvalidIntentSignature {},
+
+  #[error("Payment Intent Expired At: {expiry}")]
+  PaymentIntentExpired { expiry: u64 },
+
+  #[error("Payment Intent Already Used: {nonce}")]
+  PaymentIntentAlreadyUsed { nonce: u64 },
+
+  #[error("Invalid Token Group: {name}")]
+  InvalidTokenGroup { name: String },
+
+  #[error("Already Migrated")]
+  AlreadyMigrated {},
+
+  #[error("Subscription First Due In Past: {first_due}")]
+  SubscriptionFirstDueInPast { first_due: u64 },
+
+  #[error("No Migration In Progress")]
+  NoMigrationInProgress {},
+
+  #[error("Invalid Migration Limit")]
+  InvalidMigrationLimit {},
 }
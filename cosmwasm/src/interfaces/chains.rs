@@ -0,0 +1,207 @@
+use crate::contract::Chainbills;
+use crate::error::ChainbillsError;
+use crate::messages::{
+  ChainIdentifier, RegisterCbChainMessage, RegisterChainCircleDomainMessage,
+  RegisterChainWormholeIdMessage,
+};
+use crate::state::CbChain;
+use sylvia::cw_std::{HexBinary, Response, StdError};
+use sylvia::interface;
+use sylvia::types::{ExecCtx, QueryCtx};
+
+#[interface]
+pub trait Chains {
+  type Error: From<StdError>;
+
+  #[sv::msg(query)]
+  fn cb_chain(
+    &self,
+    ctx: QueryCtx,
+    msg: ChainIdentifier,
+  ) -> Result<CbChain, Self::Error>;
+
+  #[sv::msg(exec)]
+  fn register_cb_chain(
+    &self,
+    ctx: ExecCtx,
+    msg: RegisterCbChainMessage,
+  ) -> Result<Response, Self::Error>;
+
+  #[sv::msg(exec)]
+  fn register_chain_wormhole_id(
+    &self,
+    ctx: ExecCtx,
+    msg: RegisterChainWormholeIdMessage,
+  ) -> Result<Response, Self::Error>;
+
+  #[sv::msg(exec)]
+  fn register_chain_circle_domain(
+    &self,
+    ctx: ExecCtx,
+    msg: RegisterChainCircleDomainMessage,
+  ) -> Result<Response, Self::Error>;
+}
+
+impl Chains for Chainbills {
+  type Error = ChainbillsError;
+
+  fn cb_chain(
+    &self,
+    ctx: QueryCtx,
+    msg: ChainIdentifier,
+  ) -> Result<CbChain, Self::Error> {
+    // Resolve the identifier and return the registered chain if any.
+    let cb_chain_id = self.resolve_cb_chain_id(ctx.deps.storage, &msg)?;
+    match self.cb_chains.may_load(ctx.deps.storage, cb_chain_id)? {
+      Some(cb_chain) => Ok(cb_chain),
+      None => Err(ChainbillsError::UnregisteredChain {
+        chain: HexBinary::from(&cb_chain_id).to_hex(),
+      }),
+    }
+  }
+
+  fn register_cb_chain(
+    &self,
+    ctx: ExecCtx,
+    msg: RegisterCbChainMessage,
+  ) -> Result<Response, Self::Error> {
+    // Only the owner can register chains.
+    let owner = self.config.load(ctx.deps.storage)?.owner;
+    if ctx.info.sender != owner {
+      return Err(ChainbillsError::OwnerUnauthorized {});
+    }
+
+    // Ensure the CAIP-2 is valid and not yet registered.
+    let RegisterCbChainMessage { caip2 } = msg;
+    if !CbChain::is_valid_caip2(&caip2) {
+      return Err(ChainbillsError::InvalidCaip2 { caip2 });
+    }
+    let cb_chain = CbChain::initialize(caip2);
+    if self.cb_chains.has(ctx.deps.storage, cb_chain.cb_chain_id) {
+      return Err(ChainbillsError::ChainIdAlreadyRegistered {
+        chain: cb_chain.caip2,
+      });
+    }
+
+    // Save the chain.
+    self
+      .cb_chains
+      .save(ctx.deps.storage, cb_chain.cb_chain_id, &cb_chain)?;
+
+    // Return the Response.
+    Ok(Response::new().add_attributes([
+      ("action", "registered_cb_chain".to_string()),
+      (
+        "cb_chain_id",
+        HexBinary::from(&cb_chain.cb_chain_id).to_hex(),
+      ),
+      ("caip2", cb_chain.caip2),
+    ]))
+  }
+
+  fn register_chain_wormhole_id(
+    &self,
+    ctx: ExecCtx,
+    msg: RegisterChainWormholeIdMessage,
+  ) -> Result<Response, Self::Error> {
+    // Only the owner can register chains.
+    let owner = self.config.load(ctx.deps.storage)?.owner;
+    if ctx.info.sender != owner {
+      return Err(ChainbillsError::OwnerUnauthorized {});
+    }
+
+    // Ensure the chain is registered and that neither it nor the Wormhole
+    // Chain ID has been mapped before.
+    let RegisterChainWormholeIdMessage {
+      chain,
+      wormhole_chain_id,
+    } = msg;
+    let cb_chain_id = self.resolve_cb_chain_id(ctx.deps.storage, &chain)?;
+    let mut cb_chain = self
+      .cb_chains
+      .may_load(ctx.deps.storage, cb_chain_id)?
+      .ok_or_else(|| ChainbillsError::UnregisteredChain {
+        chain: HexBinary::from(&cb_chain_id).to_hex(),
+      })?;
+    if cb_chain.wormhole_chain_id.is_some()
+      || self
+        .wormhole_chain_cb_chain_ids
+        .has(ctx.deps.storage, wormhole_chain_id)
+    {
+      return Err(ChainbillsError::ChainIdAlreadyRegistered {
+        chain: wormhole_chain_id.to_string(),
+      });
+    }
+
+    // Save the mapping both ways.
+    cb_chain.wormhole_chain_id = Some(wormhole_chain_id);
+    self
+      .cb_chains
+      .save(ctx.deps.storage, cb_chain_id, &cb_chain)?;
+    self.wormhole_chain_cb_chain_ids.save(
+      ctx.deps.storage,
+      wormhole_chain_id,
+      &cb_chain_id,
+    )?;
+
+    // Return the Response.
+    Ok(Response::new().add_attributes([
+      ("action", "registered_chain_wormhole_id".to_string()),
+      ("cb_chain_id", HexBinary::from(&cb_chain_id).to_hex()),
+      ("wormhole_chain_id", wormhole_chain_id.to_string()),
+    ]))
+  }
+
+  fn register_chain_circle_domain(
+    &self,
+    ctx: ExecCtx,
+    msg: RegisterChainCircleDomainMessage,
+  ) -> Result<Response, Self::Error> {
+    // Only the owner can register chains.
+    let owner = self.config.load(ctx.deps.storage)?.owner;
+    if ctx.info.sender != owner {
+      return Err(ChainbillsError::OwnerUnauthorized {});
+    }
+
+    // Ensure the chain is registered and that neither it nor the Circle
+    // domain has been mapped before.
+    let RegisterChainCircleDomainMessage {
+      chain,
+      circle_domain,
+    } = msg;
+    let cb_chain_id = self.resolve_cb_chain_id(ctx.deps.storage, &chain)?;
+    let mut cb_chain = self
+      .cb_chains
+      .may_load(ctx.deps.storage, cb_chain_id)?
+      .ok_or_else(|| ChainbillsError::UnregisteredChain {
+        chain: HexBinary::from(&cb_chain_id).to_hex(),
+      })?;
+    if cb_chain.circle_domain.is_some()
+      || self
+        .circle_domain_cb_chain_ids
+        .has(ctx.deps.storage, circle_domain)
+    {
+      return Err(ChainbillsError::ChainIdAlreadyRegistered {
+        chain: circle_domain.to_string(),
+      });
+    }
+
+    // Save the mapping both ways.
+    cb_chain.circle_domain = Some(circle_domain);
+    self
+      .cb_chains
+      .save(ctx.deps.storage, cb_chain_id, &cb_chain)?;
+    self.circle_domain_cb_chain_ids.save(
+      ctx.deps.storage,
+      circle_domain,
+      &cb_chain_id,
+    )?;
+
+    // Return the Response.
+    Ok(Response::new().add_attributes([
+      ("action", "registered_chain_circle_domain".to_string()),
+      ("cb_chain_id", HexBinary::from(&cb_chain_id).to_hex()),
+      ("circle_domain", circle_domain.to_string()),
+    ]))
+  }
+}
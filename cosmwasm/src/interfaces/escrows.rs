@@ -0,0 +1,396 @@
+use crate::contract::Chainbills;
+use crate::error::ChainbillsError;
+use crate::messages::{IdMessage, UpdatePayableEscrowMessage};
+use crate::state::{
+  ActivityType, EscrowState, EscrowStatus, PayableEscrow, PayableOperator,
+};
+use cw20::Cw20ExecuteMsg;
+use sylvia::cw_std::{
+  coins, to_json_binary, BankMsg, CosmosMsg, HexBinary, Response, StdError,
+  Storage, WasmMsg,
+};
+use sylvia::interface;
+use sylvia::types::{ExecCtx, QueryCtx};
+
+#[interface]
+pub trait Escrows {
+  type Error: From<StdError>;
+
+  #[sv::msg(query)]
+  fn payable_escrow(
+    &self,
+    ctx: QueryCtx,
+    msg: IdMessage,
+  ) -> Result<PayableEscrow, Self::Error>;
+
+  #[sv::msg(exec)]
+  fn update_payable_escrow(
+    &self,
+    ctx: ExecCtx,
+    msg: UpdatePayableEscrowMessage,
+  ) -> Result<Response, Self::Error>;
+
+  #[sv::msg(query)]
+  fn escrow_state(
+    &self,
+    ctx: QueryCtx,
+    msg: IdMessage,
+  ) -> Result<EscrowState, Self::Error>;
+
+  #[sv::msg(exec)]
+  fn release_escrow(
+    &self,
+    ctx: ExecCtx,
+    msg: IdMessage,
+  ) -> Result<Response, Self::Error>;
+
+  #[sv::msg(exec)]
+  fn dispute_escrow(
+    &self,
+    ctx: ExecCtx,
+    msg: IdMessage,
+  ) -> Result<Response, Self::Error>;
+
+  #[sv::msg(exec)]
+  fn refund_escrow(
+    &self,
+    ctx: ExecCtx,
+    msg: IdMessage,
+  ) -> Result<Response, Self::Error>;
+}
+
+impl Chainbills {
+  /// Loads the escrowed payment with the given (PayablePayment) ID.
+  fn load_escrow_state(
+    &self,
+    storage: &dyn Storage,
+    id: &str,
+  ) -> Result<([u8; 32], EscrowState), ChainbillsError> {
+    let payment_id = <[u8; 32]>::try_from(HexBinary::from_hex(id)?.as_slice())
+      .map_err(|_| ChainbillsError::EscrowNotFound {})?;
+    match self.escrow_states.may_load(storage, payment_id)? {
+      Some(escrow_state) => Ok((payment_id, escrow_state)),
+      None => Err(ChainbillsError::EscrowNotFound {}),
+    }
+  }
+}
+
+impl Escrows for Chainbills {
+  type Error = ChainbillsError;
+
+  fn payable_escrow(
+    &self,
+    ctx: QueryCtx,
+    msg: IdMessage,
+  ) -> Result<PayableEscrow, Self::Error> {
+    let payable_id =
+      <[u8; 32]>::try_from(HexBinary::from_hex(&msg.id)?.as_slice()).unwrap();
+    if !self.payables.has(ctx.deps.storage, payable_id) {
+      return Err(ChainbillsError::InvalidPayableId { id: msg.id });
+    }
+    Ok(
+      self
+        .payable_escrows
+        .may_load(ctx.deps.storage, payable_id)?
+        .unwrap_or(PayableEscrow {
+          timeout: 0,
+          arbiter: None,
+        }),
+    )
+  }
+
+  fn update_payable_escrow(
+    &self,
+    ctx: ExecCtx,
+    msg: UpdatePayableEscrowMessage,
+  ) -> Result<Response, Self::Error> {
+    /* CHECKS */
+    // Ensure that the payable_id is valid.
+    let payable_id =
+      <[u8; 32]>::try_from(HexBinary::from_hex(&msg.payable_id)?.as_slice())
+        .unwrap();
+    if !self.payables.has(ctx.deps.storage, payable_id) {
+      return Err(ChainbillsError::InvalidPayableId { id: msg.payable_id });
+    }
+    let mut payable = self.payables.load(ctx.deps.storage, payable_id)?;
+
+    // Ensure that the caller owns the payable or can manage it.
+    self.ensure_host_or_operator(
+      ctx.deps.storage,
+      payable_id,
+      &payable,
+      &ctx.info.sender,
+      PayableOperator::ROLE_MANAGE,
+    )?;
+
+    // Ensure that the arbiter is a valid wallet.
+    let arbiter = match msg.arbiter {
+      Some(arbiter) => Some(ctx.deps.api.addr_validate(&arbiter)?),
+      None => None,
+    };
+
+    /* STATE CHANGES */
+    // Save the escrow settings. A zero timeout stops escrowing new payments.
+    // Payments already in escrow keep the settings they were made with.
+    if msg.timeout == 0 {
+      self.payable_escrows.remove(ctx.deps.storage, payable_id);
+    } else {
+      self.payable_escrows.save(
+        ctx.deps.storage,
+        payable_id,
+        &PayableEscrow {
+          timeout: msg.timeout,
+          arbiter: arbiter.clone(),
+        },
+      )?;
+    }
+
+    // Increment the activity count on the payable and save it.
+    payable.activities_count = payable.next_activity();
+    self.payables.save(ctx.deps.storage, payable_id, &payable)?;
+
+    // Record the activity.
+    self.record_update_payable_activity(
+      ctx.deps.storage,
+      &ctx.env,
+      &payable.host,
+      payable_id,
+      payable.activities_count,
+      ActivityType::UpdatedPayableEscrow,
+    )?;
+
+    // Return the Response.
+    Ok(
+      Response::new().add_attributes([
+        ("action", "updated_payable_escrow".to_string()),
+        ("payable_id", HexBinary::from(&payable_id).to_hex()),
+        ("host_wallet", payable.host.to_string()),
+        ("timeout", msg.timeout.to_string()),
+        (
+          "arbiter",
+          arbiter
+            .map(|arbiter| arbiter.to_string())
+            .unwrap_or_default(),
+        ),
+      ]),
+    )
+  }
+
+  fn escrow_state(
+    &self,
+    ctx: QueryCtx,
+    msg: IdMessage,
+  ) -> Result<EscrowState, Self::Error> {
+    Ok(self.load_escrow_state(ctx.deps.storage, &msg.id)?.1)
+  }
+
+  fn release_escrow(
+    &self,
+    ctx: ExecCtx,
+    msg: IdMessage,
+  ) -> Result<Response, Self::Error> {
+    /* CHECKS */
+    let (payment_id, mut escrow_state) =
+      self.load_escrow_state(ctx.deps.storage, &msg.id)?;
+
+    // Ensure that the payment is still held and that the caller can release
+    // it now.
+    if !escrow_state.is_held() {
+      return Err(ChainbillsError::EscrowIsSettled {});
+    }
+    let now = ctx.env.block.time.seconds();
+    if !escrow_state.can_release(&ctx.info.sender, now) {
+      return Err(match escrow_state.status {
+        EscrowStatus::Disputed => ChainbillsError::EscrowIsDisputed {},
+        _ => ChainbillsError::EscrowNotReleasable {
+          release_after: escrow_state.release_after,
+        },
+      });
+    }
+
+    /* STATE CHANGES */
+    // Add the payment to the payable's balances.
+    let payable_id = escrow_state.payable_id;
+    let mut payable = self.payables.load(ctx.deps.storage, payable_id)?;
+    payable
+      .add_balance(&escrow_state.details.token, escrow_state.details.amount);
+    payable.activities_count = payable.next_activity();
+    self.payables.save(ctx.deps.storage, payable_id, &payable)?;
+
+    // Mark the payment as released.
+    escrow_state.status = EscrowStatus::Released;
+    self
+      .escrow_states
+      .save(ctx.deps.storage, payment_id, &escrow_state)?;
+
+    // Record the activity for the payer.
+    self.record_update_payable_activity(
+      ctx.deps.storage,
+      &ctx.env,
+      &escrow_state.payer,
+      payable_id,
+      payable.activities_count,
+      ActivityType::ReleasedEscrow,
+    )?;
+
+    // Return the Response.
+    Ok(Response::new().add_attributes([
+      ("action", "released_escrow".to_string()),
+      ("payable_id", HexBinary::from(&payable_id).to_hex()),
+      ("payable_payment_id", HexBinary::from(&payment_id).to_hex()),
+      ("released_by", ctx.info.sender.to_string()),
+      ("token", escrow_state.details.token),
+      ("amount", escrow_state.details.amount.to_string()),
+    ]))
+  }
+
+  fn dispute_escrow(
+    &self,
+    ctx: ExecCtx,
+    msg: IdMessage,
+  ) -> Result<Response, Self::Error> {
+    /* CHECKS */
+    let (payment_id, mut escrow_state) =
+      self.load_escrow_state(ctx.deps.storage, &msg.id)?;
+
+    // Ensure that the caller made the payment.
+    if ctx.info.sender != escrow_state.payer {
+      return Err(ChainbillsError::NotYourEscrow {});
+    }
+
+    // Ensure that the payment is held and undisputed.
+    match escrow_state.status {
+      EscrowStatus::Locked => {}
+      EscrowStatus::Disputed => {
+        return Err(ChainbillsError::EscrowIsDisputed {})
+      }
+      _ => return Err(ChainbillsError::EscrowIsSettled {}),
+    }
+
+    // Ensure that someone can settle the dispute, and that the payment
+    // isn't yet releasable by anyone.
+    if escrow_state.arbiter.is_none() {
+      return Err(ChainbillsError::EscrowHasNoArbiter {});
+    }
+    if ctx.env.block.time.seconds() >= escrow_state.release_after {
+      return Err(ChainbillsError::EscrowDisputeWindowClosed {});
+    }
+
+    /* STATE CHANGES */
+    // Mark the payment as disputed.
+    escrow_state.status = EscrowStatus::Disputed;
+    self
+      .escrow_states
+      .save(ctx.deps.storage, payment_id, &escrow_state)?;
+
+    // Increment the activity count on the payable and save it.
+    let payable_id = escrow_state.payable_id;
+    let mut payable = self.payables.load(ctx.deps.storage, payable_id)?;
+    payable.activities_count = payable.next_activity();
+    self.payables.save(ctx.deps.storage, payable_id, &payable)?;
+
+    // Record the activity.
+    self.record_update_payable_activity(
+      ctx.deps.storage,
+      &ctx.env,
+      &escrow_state.payer,
+      payable_id,
+      payable.activities_count,
+      ActivityType::DisputedEscrow,
+    )?;
+
+    // Return the Response.
+    Ok(Response::new().add_attributes([
+      ("action", "disputed_escrow".to_string()),
+      ("payable_id", HexBinary::from(&payable_id).to_hex()),
+      ("payable_payment_id", HexBinary::from(&payment_id).to_hex()),
+      ("payer_wallet", escrow_state.payer.to_string()),
+    ]))
+  }
+
+  fn refund_escrow(
+    &self,
+    ctx: ExecCtx,
+    msg: IdMessage,
+  ) -> Result<Response, Self::Error> {
+    /* CHECKS */
+    let (payment_id, mut escrow_state) =
+      self.load_escrow_state(ctx.deps.storage, &msg.id)?;
+
+    // Ensure that the payment is still held.
+    if !escrow_state.is_held() {
+      return Err(ChainbillsError::EscrowIsSettled {});
+    }
+
+    // Ensure that the caller is the arbiter or the payable's host.
+    let payable_id = escrow_state.payable_id;
+    let mut payable = self.payables.load(ctx.deps.storage, payable_id)?;
+    if escrow_state.arbiter.as_ref() != Some(&ctx.info.sender)
+      && payable.host != ctx.info.sender
+    {
+      return Err(ChainbillsError::NotYourEscrow {});
+    }
+
+    /* STATE CHANGES */
+    // Mark the payment as refunded.
+    escrow_state.status = EscrowStatus::Refunded;
+    self
+      .escrow_states
+      .save(ctx.deps.storage, payment_id, &escrow_state)?;
+
+    // Count the refund as withdrawn from the token's totals.
+    let token = escrow_state.details.token.clone();
+    let amount = escrow_state.details.amount;
+    let mut token_details =
+      self.token_details.load(ctx.deps.storage, token.clone())?;
+    token_details.add_withdrawn(amount);
+    self
+      .token_details
+      .save(ctx.deps.storage, token.clone(), &token_details)?;
+
+    // Increment the activity count on the payable and save it.
+    payable.activities_count = payable.next_activity();
+    self.payables.save(ctx.deps.storage, payable_id, &payable)?;
+
+    // Record the activity for the payer.
+    self.record_update_payable_activity(
+      ctx.deps.storage,
+      &ctx.env,
+      &escrow_state.payer,
+      payable_id,
+      payable.activities_count,
+      ActivityType::RefundedEscrow,
+    )?;
+
+    /* FUNDS TRANSFER */
+    // Send the payment back to the payer.
+    let refund: CosmosMsg = if token_details.is_native_token {
+      BankMsg::Send {
+        to_address: escrow_state.payer.to_string(),
+        amount: coins(amount.u128(), token.clone()),
+      }
+      .into()
+    } else {
+      WasmMsg::Execute {
+        contract_addr: token.clone(),
+        msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+          recipient: escrow_state.payer.to_string(),
+          amount,
+        })?,
+        funds: vec![],
+      }
+      .into()
+    };
+
+    // Return the Response.
+    Ok(Response::new().add_message(refund).add_attributes([
+      ("action", "refunded_escrow".to_string()),
+      ("payable_id", HexBinary::from(&payable_id).to_hex()),
+      ("payable_payment_id", HexBinary::from(&payment_id).to_hex()),
+      ("payer_wallet", escrow_state.payer.to_string()),
+      ("refunded_by", ctx.info.sender.to_string()),
+      ("token", token),
+      ("amount", amount.to_string()),
+    ]))
+  }
+}
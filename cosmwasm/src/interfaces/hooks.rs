@@ -0,0 +1,209 @@
+use crate::contract::Chainbills;
+use crate::error::ChainbillsError;
+use crate::messages::{IdMessage, UpdatePayableHookMessage};
+use crate::state::{
+  ActivityType, PayableHook, PayableOperator, PaymentHookFailure,
+};
+use sylvia::cw_std::{HexBinary, Reply, Response, StdError, SubMsgResult};
+use sylvia::interface;
+use sylvia::types::{ExecCtx, QueryCtx, ReplyCtx};
+
+#[interface]
+pub trait Hooks {
+  type Error: From<StdError>;
+
+  #[sv::msg(query)]
+  fn payable_hook(
+    &self,
+    ctx: QueryCtx,
+    msg: IdMessage,
+  ) -> Result<Option<PayableHook>, Self::Error>;
+
+  #[sv::msg(exec)]
+  fn update_payable_hook(
+    &self,
+    ctx: ExecCtx,
+    msg: UpdatePayableHookMessage,
+  ) -> Result<Response, Self::Error>;
+
+  #[sv::msg(query)]
+  fn payment_hook_failure(
+    &self,
+    ctx: QueryCtx,
+    msg: IdMessage,
+  ) -> Result<PaymentHookFailure, Self::Error>;
+}
+
+impl Chainbills {
+  /// Records that a payment's notification to its payable's hook failed, so
+  /// that the payment goes through. The reply's ID is the payment's
+  /// (PayablePayment) chain count.
+  pub fn record_payment_hook_failure(
+    &self,
+    ctx: ReplyCtx,
+    reply: Reply,
+  ) -> Result<Response, ChainbillsError> {
+    let SubMsgResult::Err(error) = reply.result else {
+      return Ok(Response::new());
+    };
+    let payment_id = reply
+      .id
+      .checked_sub(1)
+      .and_then(|index| {
+        self
+          .chain_payable_payment_ids
+          .load(ctx.deps.storage)
+          .ok()?
+          .get(index as usize)
+          .copied()
+      })
+      .ok_or(ChainbillsError::UnknownReplyId { id: reply.id })?;
+    let payment = self.payable_payments.load(ctx.deps.storage, payment_id)?;
+    let hook = self
+      .payable_hooks
+      .load(ctx.deps.storage, payment.payable_id)?
+      .contract;
+
+    self.payment_hook_failures.save(
+      ctx.deps.storage,
+      payment_id,
+      &PaymentHookFailure {
+        payable_id: payment.payable_id,
+        hook: hook.clone(),
+        error: error.clone(),
+      },
+    )?;
+
+    Ok(Response::new().add_attributes([
+      ("action", "payment_hook_failed".to_string()),
+      ("payable_id", HexBinary::from(&payment.payable_id).to_hex()),
+      ("payable_payment_id", HexBinary::from(&payment_id).to_hex()),
+      ("hook", hook.to_string()),
+      ("error", error),
+    ]))
+  }
+}
+
+impl Hooks for Chainbills {
+  type Error = ChainbillsError;
+
+  fn payable_hook(
+    &self,
+    ctx: QueryCtx,
+    msg: IdMessage,
+  ) -> Result<Option<PayableHook>, Self::Error> {
+    let payable_id =
+      <[u8; 32]>::try_from(HexBinary::from_hex(&msg.id)?.as_slice()).unwrap();
+    if !self.payables.has(ctx.deps.storage, payable_id) {
+      return Err(ChainbillsError::InvalidPayableId { id: msg.id });
+    }
+    Ok(self.payable_hooks.may_load(ctx.deps.storage, payable_id)?)
+  }
+
+  fn update_payable_hook(
+    &self,
+    ctx: ExecCtx,
+    msg: UpdatePayableHookMessage,
+  ) -> Result<Response, Self::Error> {
+    /* CHECKS */
+    // Ensure that the payable_id is valid.
+    let payable_id =
+      <[u8; 32]>::try_from(HexBinary::from_hex(&msg.payable_id)?.as_slice())
+        .unwrap();
+    if !self.payables.has(ctx.deps.storage, payable_id) {
+      return Err(ChainbillsError::InvalidPayableId { id: msg.payable_id });
+    }
+    let mut payable = self.payables.load(ctx.deps.storage, payable_id)?;
+
+    // Ensure that the caller owns the payable or can manage it.
+    self.ensure_host_or_operator(
+      ctx.deps.storage,
+      payable_id,
+      &payable,
+      &ctx.info.sender,
+      PayableOperator::ROLE_MANAGE,
+    )?;
+
+    // Ensure that the hook is a contract, and that it gets some gas but not
+    // more than the maximum.
+    let contract = match msg.contract {
+      Some(contract) => {
+        if msg.gas_limit == 0 || msg.gas_limit > PayableHook::MAX_GAS_LIMIT {
+          return Err(ChainbillsError::InvalidHookGasLimit {
+            gas_limit: msg.gas_limit,
+          });
+        }
+        let contract = ctx.deps.api.addr_validate(&contract)?;
+        ctx
+          .deps
+          .querier
+          .query_wasm_contract_info(&contract)
+          .map_err(|_| ChainbillsError::InvalidHookContract {})?;
+        Some(contract)
+      }
+      None => None,
+    };
+
+    /* STATE CHANGES */
+    // Save the hook. No contract stops notifying.
+    match &contract {
+      Some(contract) => self.payable_hooks.save(
+        ctx.deps.storage,
+        payable_id,
+        &PayableHook {
+          contract: contract.clone(),
+          revert_on_error: msg.revert_on_error,
+          gas_limit: msg.gas_limit,
+        },
+      )?,
+      None => self.payable_hooks.remove(ctx.deps.storage, payable_id),
+    }
+
+    // Increment the activity count on the payable and save it.
+    payable.activities_count = payable.next_activity();
+    self.payables.save(ctx.deps.storage, payable_id, &payable)?;
+
+    // Record the activity.
+    self.record_update_payable_activity(
+      ctx.deps.storage,
+      &ctx.env,
+      &payable.host,
+      payable_id,
+      payable.activities_count,
+      ActivityType::UpdatedPayableHook,
+    )?;
+
+    // Return the Response.
+    Ok(
+      Response::new().add_attributes([
+        ("action", "updated_payable_hook".to_string()),
+        ("payable_id", HexBinary::from(&payable_id).to_hex()),
+        ("host_wallet", payable.host.to_string()),
+        (
+          "hook",
+          contract
+            .map(|contract| contract.to_string())
+            .unwrap_or_default(),
+        ),
+        ("revert_on_error", msg.revert_on_error.to_string()),
+        ("gas_limit", msg.gas_limit.to_string()),
+      ]),
+    )
+  }
+
+  fn payment_hook_failure(
+    &self,
+    ctx: QueryCtx,
+    msg: IdMessage,
+  ) -> Result<PaymentHookFailure, Self::Error> {
+    let payment_id =
+      <[u8; 32]>::try_from(HexBinary::from_hex(&msg.id)?.as_slice()).unwrap();
+    match self
+      .payment_hook_failures
+      .may_load(ctx.deps.storage, payment_id)?
+    {
+      Some(failure) => Ok(failure),
+      None => Err(ChainbillsError::InvalidPaymentId { id: msg.id }),
+    }
+  }
+}
@@ -0,0 +1,165 @@
+use crate::contract::Chainbills;
+use crate::error::ChainbillsError;
+use crate::messages::{IdMessage, TokenIntegrityPageMessage};
+use crate::state::TokenIntegrity;
+use cw20::{BalanceResponse, Cw20QueryMsg};
+use sylvia::cw_std::{Order, StdError, Uint128};
+use sylvia::interface;
+use sylvia::types::QueryCtx;
+
+#[interface]
+pub trait Integrity {
+  type Error: From<StdError>;
+
+  /// Compares what the contract holds of the token against the balances,
+  /// escrowed payments and subscription deposits in it of all payables
+  /// created on this chain, and against the token's totals.
+  #[sv::msg(query)]
+  fn token_integrity(
+    &self,
+    ctx: QueryCtx,
+    msg: IdMessage,
+  ) -> Result<TokenIntegrity, Self::Error>;
+
+  /// Like token_integrity, but only checks a page of payables, in their
+  /// creation order. Summing the pages' payables_balance, escrowed and
+  /// subscription_deposits gives those of token_integrity. A page only
+  /// returns these partial sums: its is_solvent and is_consistent are None
+  /// unless it covers all payables, and callers compare the summed pages
+  /// against holdings and the token's totals themselves.
+  #[sv::msg(query)]
+  fn token_integrity_page(
+    &self,
+    ctx: QueryCtx,
+    msg: TokenIntegrityPageMessage,
+  ) -> Result<TokenIntegrity, Self::Error>;
+}
+
+impl Chainbills {
+  /// Checks the token's integrity against the payables created on this
+  /// chain, skipping the first offset ones and checking at most limit ones.
+  fn check_token_integrity(
+    &self,
+    ctx: QueryCtx,
+    token: String,
+    offset: u64,
+    limit: Option<u64>,
+  ) -> Result<TokenIntegrity, ChainbillsError> {
+    let storage = ctx.deps.storage;
+    let token_details =
+      match self.token_details.may_load(storage, token.clone())? {
+        Some(details) => details,
+        None => return Err(ChainbillsError::InvalidToken { token }),
+      };
+
+    // Sum what the payables owe in the token.
+    let mut payables_balance = Uint128::zero();
+    let mut payables_count = 0u64;
+    let mut escrowed = Uint128::zero();
+    let mut subscription_deposits = Uint128::zero();
+    let payable_ids = self.chain_payable_ids.load(storage)?;
+    let page = payable_ids
+      .iter()
+      .skip(usize::try_from(offset).unwrap_or(usize::MAX))
+      .take(limit.map_or(usize::MAX, |l| l.try_into().unwrap_or(usize::MAX)));
+    for payable_id in page {
+      let payable = self.payables.load(storage, *payable_id)?;
+      if let Some(balance) = payable.balances.iter().find(|b| b.token == token)
+      {
+        payables_balance += balance.amount;
+      }
+      payables_count += 1;
+
+      // Escrowed payments aren't in the payable's balances until released.
+      let payment_ids = self
+        .payable_payment_ids
+        .may_load(storage, *payable_id)?
+        .unwrap_or_default();
+      for payment_id in payment_ids {
+        if let Some(escrow_state) =
+          self.escrow_states.may_load(storage, payment_id)?
+        {
+          if escrow_state.is_held() && escrow_state.details.token == token {
+            escrowed += escrow_state.details.amount;
+          }
+        }
+      }
+
+      // Native subscription deposits are held until collected or refunded.
+      for subscription in self.subscriptions.prefix(payable_id.to_vec()).range(
+        storage,
+        None,
+        None,
+        Order::Ascending,
+      ) {
+        let (_, subscription) = subscription?;
+        if subscription.token == token {
+          subscription_deposits += subscription.deposit;
+        }
+      }
+    }
+
+    // Fetch what the contract holds of the token.
+    let contract = ctx.env.contract.address;
+    let holdings = if token_details.is_native_token {
+      ctx
+        .deps
+        .querier
+        .query_balance(contract, token.clone())?
+        .amount
+    } else {
+      let response: BalanceResponse = ctx.deps.querier.query_wasm_smart(
+        token.clone(),
+        &Cw20QueryMsg::Balance {
+          address: contract.to_string(),
+        },
+      )?;
+      response.balance
+    };
+
+    // Only the sums of all payables can be compared with the holdings and the
+    // token's totals.
+    let is_complete =
+      offset == 0 && payables_count as usize == payable_ids.len();
+    let liabilities = payables_balance + escrowed;
+    Ok(TokenIntegrity {
+      token,
+      holdings,
+      total_payable_received: token_details.total_payable_received,
+      total_withdrawn: token_details.total_withdrawn,
+      payables_balance,
+      payables_count,
+      escrowed,
+      subscription_deposits,
+      is_solvent: is_complete
+        .then(|| holdings >= liabilities + subscription_deposits),
+      is_consistent: is_complete.then(|| {
+        token_details
+          .total_payable_received
+          .checked_sub(token_details.total_withdrawn)
+          .ok()
+          == Some(liabilities)
+      }),
+    })
+  }
+}
+
+impl Integrity for Chainbills {
+  type Error = ChainbillsError;
+
+  fn token_integrity(
+    &self,
+    ctx: QueryCtx,
+    msg: IdMessage,
+  ) -> Result<TokenIntegrity, Self::Error> {
+    self.check_token_integrity(ctx, msg.id, 0, None)
+  }
+
+  fn token_integrity_page(
+    &self,
+    ctx: QueryCtx,
+    msg: TokenIntegrityPageMessage,
+  ) -> Result<TokenIntegrity, Self::Error> {
+    self.check_token_integrity(ctx, msg.token, msg.offset, Some(msg.limit))
+  }
+}
@@ -0,0 +1,184 @@
+use crate::contract::Chainbills;
+use crate::error::ChainbillsError;
+use crate::messages::{IdMessage, PayWithIntentMessage, PaymentIntentMessage};
+use crate::state::TokenAndAmount;
+use chainbills_payload::PaymentIntent;
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+use sylvia::cw_std::{HexBinary, Response, StdError};
+use sylvia::interface;
+use sylvia::types::{ExecCtx, QueryCtx};
+
+#[interface]
+pub trait Intents {
+  type Error: From<StdError>;
+
+  /// The ID of the PayablePayment that paid the payable's intent with the
+  /// nonce, if it has been paid.
+  #[sv::msg(query)]
+  fn payment_intent(
+    &self,
+    ctx: QueryCtx,
+    msg: PaymentIntentMessage,
+  ) -> Result<Option<IdMessage>, Self::Error>;
+
+  #[sv::msg(exec)]
+  fn pay_with_intent(
+    &self,
+    ctx: ExecCtx,
+    msg: PayWithIntentMessage,
+  ) -> Result<Response, Self::Error>;
+}
+
+impl Intents for Chainbills {
+  type Error = ChainbillsError;
+
+  fn payment_intent(
+    &self,
+    ctx: QueryCtx,
+    msg: PaymentIntentMessage,
+  ) -> Result<Option<IdMessage>, Self::Error> {
+    let payable_id =
+      <[u8; 32]>::try_from(HexBinary::from_hex(&msg.payable_id)?.as_slice())
+        .unwrap();
+    if !self.payables.has(ctx.deps.storage, payable_id) {
+      return Err(ChainbillsError::InvalidPayableId { id: msg.payable_id });
+    }
+    Ok(
+      self
+        .used_payment_intents
+        .may_load(ctx.deps.storage, (payable_id.to_vec(), msg.nonce))?
+        .map(|id| IdMessage {
+          id: HexBinary::from(&id).to_hex(),
+        }),
+    )
+  }
+
+  fn pay_with_intent(
+    &self,
+    mut ctx: ExecCtx,
+    msg: PayWithIntentMessage,
+  ) -> Result<Response, Self::Error> {
+    /* CHECKS */
+    // Ensure that the payable_id is valid.
+    let payable_id =
+      <[u8; 32]>::try_from(HexBinary::from_hex(&msg.payable_id)?.as_slice())
+        .unwrap();
+    if !self.payables.has(ctx.deps.storage, payable_id) {
+      return Err(ChainbillsError::InvalidPayableId { id: msg.payable_id });
+    }
+    let payable = self.payables.load(ctx.deps.storage, payable_id)?;
+
+    // Ensure that the payable is not closed.
+    if payable.is_closed {
+      return Err(ChainbillsError::PayableIsClosed {});
+    }
+
+    // Ensure that the payer is among the payable's allowed payers.
+    self.ensure_allowed_payer(
+      ctx.deps.storage,
+      ctx.deps.api,
+      payable_id,
+      &ctx.info.sender,
+      &msg.payer_proof,
+    )?;
+
+    // Ensure that the intent hasn't expired and hasn't been paid.
+    if ctx.env.block.time.seconds() > msg.expiry {
+      return Err(ChainbillsError::PaymentIntentExpired { expiry: msg.expiry });
+    }
+    let intent_key = (payable_id.to_vec(), msg.nonce);
+    if self
+      .used_payment_intents
+      .has(ctx.deps.storage, intent_key.clone())
+    {
+      return Err(ChainbillsError::PaymentIntentAlreadyUsed {
+        nonce: msg.nonce,
+      });
+    }
+
+    // Ensure that the token is supported and that the amount isn't zero. The
+    // payable's allowed tokens and amounts don't apply, as the host signed
+    // this exact payment.
+    let token_details =
+      self.check_token_and_amount(ctx.deps.storage, &msg.token, msg.amount)?;
+
+    // Ensure that the key is a compressed secp256k1 key of the payable's
+    // host, whose address is the RIPEMD-160 of the key's SHA-256.
+    let public_key = HexBinary::from_hex(&msg.public_key)
+      .map_err(|_| ChainbillsError::InvalidIntentKey {})?;
+    if !matches!(public_key.first(), Some(0x02 | 0x03))
+      || public_key.len() != 33
+    {
+      return Err(ChainbillsError::InvalidIntentKey {});
+    }
+    let key_address = Ripemd160::digest(Sha256::digest(&public_key));
+    if ctx
+      .deps
+      .api
+      .addr_canonicalize(payable.host.as_str())?
+      .as_slice()
+      != key_address.as_slice()
+    {
+      return Err(ChainbillsError::IntentKeyNotHost {});
+    }
+
+    // Ensure that the payable's host signed the intent.
+    let config = self.config.load(ctx.deps.storage)?;
+    let signing_bytes = PaymentIntent {
+      payable_chain_id: config.cb_chain_id,
+      payable_id,
+      token: self.token_to_bytes32(
+        ctx.deps.storage,
+        ctx.deps.api,
+        &msg.token,
+      )?,
+      amount: msg.amount.u128(),
+      expiry: msg.expiry,
+      nonce: msg.nonce,
+      reference: msg.reference.as_bytes().to_vec(),
+    }
+    .signing_bytes()?;
+    let signature = HexBinary::from_hex(&msg.signature)
+      .map_err(|_| ChainbillsError::InvalidIntentSignature {})?;
+    let is_valid = ctx
+      .deps
+      .api
+      .secp256k1_verify(
+        &Sha256::digest(&signing_bytes),
+        &signature,
+        &public_key,
+      )
+      .unwrap_or(false);
+    if !is_valid {
+      return Err(ChainbillsError::InvalidIntentSignature {});
+    }
+
+    /* STATE CHANGES */
+    let response = self.receive_payment(
+      &mut ctx,
+      payable_id,
+      &token_details,
+      TokenAndAmount {
+        token: msg.token,
+        amount: msg.amount,
+      },
+      Some(msg.reference),
+    )?;
+
+    // Mark the intent as used by the payment that was just recorded.
+    let payable_payment_id = *self
+      .chain_payable_payment_ids
+      .load(ctx.deps.storage)?
+      .last()
+      .unwrap();
+    self.used_payment_intents.save(
+      ctx.deps.storage,
+      intent_key,
+      &payable_payment_id,
+    )?;
+
+    // Return the Response.
+    Ok(response.add_attribute("payment_intent_nonce", msg.nonce.to_string()))
+  }
+}
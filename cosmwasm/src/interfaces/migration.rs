@@ -0,0 +1,345 @@
+use crate::contract::Chainbills;
+use crate::error::ChainbillsError;
+use crate::messages::ContinueMigrationMessage;
+use crate::state::{
+  MigrationProgress, MigrationStage, PayablePayment, PayablePaymentV0,
+  UserPayment, UserPaymentV0,
+};
+use cw_storage_plus::Map;
+use sylvia::cw_std::{
+  from_json, Binary, Order, Record, Response, StdError, Storage,
+};
+use sylvia::interface;
+use sylvia::types::{ExecCtx, QueryCtx};
+
+/// UserPayments as stored before chains were keyed by their cbChainIds.
+const LEGACY_USER_PAYMENTS: Map<[u8; 32], UserPaymentV0> =
+  Map::new("user_payments");
+
+/// PayablePayments as stored before chains were keyed by their cbChainIds.
+const LEGACY_PAYABLE_PAYMENTS: Map<[u8; 32], PayablePaymentV0> =
+  Map::new("payable_payments");
+
+/// Per-chain payments counts as stored before chains were keyed by their
+/// cbChainIds, by payable ID and Wormhole Chain ID.
+const LEGACY_PER_CHAIN_COUNTS: Map<(Vec<u8>, u16), u64> =
+  Map::new("per_chain_payable_payments_count");
+
+/// Per-chain payment IDs as stored before chains were keyed by their
+/// cbChainIds, by payable ID and Wormhole Chain ID.
+const LEGACY_PER_CHAIN_IDS: Map<(Vec<u8>, u16), Vec<[u8; 32]>> =
+  Map::new("per_chain_payable_payment_ids");
+
+/// A payable's payments count and payment IDs from a chain.
+pub type PerChainPayments = (u64, Vec<[u8; 32]>);
+
+#[interface]
+pub trait Migration {
+  type Error: From<StdError>;
+
+  /// How far the migration to cbChainIds has gone. None once it finished.
+  #[sv::msg(query)]
+  fn migration_progress(
+    &self,
+    ctx: QueryCtx,
+  ) -> Result<Option<MigrationProgress>, Self::Error>;
+
+  /// Moves the next page of stored payments to cbChainIds. Anyone can call
+  /// it until the migration finishes.
+  #[sv::msg(exec)]
+  fn continue_migration(
+    &self,
+    ctx: ExecCtx,
+    msg: ContinueMigrationMessage,
+  ) -> Result<Response, Self::Error>;
+}
+
+impl MigrationStage {
+  /// The storage namespace of the stage's entries.
+  fn namespace(&self) -> &'static str {
+    match self {
+      MigrationStage::UserPayments => "user_payments",
+      MigrationStage::PayablePayments => "payable_payments",
+      MigrationStage::PerChainPayablePayments => {
+        "per_chain_payable_payments_count"
+      }
+    }
+  }
+
+  /// The stage after this one. None after the last stage.
+  fn next(&self) -> Option<Self> {
+    match self {
+      MigrationStage::UserPayments => Some(MigrationStage::PayablePayments),
+      MigrationStage::PayablePayments => {
+        Some(MigrationStage::PerChainPayablePayments)
+      }
+      MigrationStage::PerChainPayablePayments => None,
+    }
+  }
+}
+
+impl Chainbills {
+  /// Loads a UserPayment, upgrading it in memory if the migration hasn't
+  /// moved it yet.
+  pub fn may_load_user_payment(
+    &self,
+    storage: &dyn Storage,
+    id: [u8; 32],
+  ) -> Result<Option<UserPayment>, ChainbillsError> {
+    if self.migration_progress.exists(storage) {
+      if let Ok(Some(payment)) = LEGACY_USER_PAYMENTS.may_load(storage, id) {
+        let chain =
+          self.cb_chain_id_of_wormhole(storage, payment.payable_chain_id)?;
+        return Ok(Some(payment.upgrade(chain)));
+      }
+    }
+    Ok(self.user_payments.may_load(storage, id)?)
+  }
+
+  /// Loads a PayablePayment, upgrading it in memory if the migration hasn't
+  /// moved it yet.
+  pub fn may_load_payable_payment(
+    &self,
+    storage: &dyn Storage,
+    id: [u8; 32],
+  ) -> Result<Option<PayablePayment>, ChainbillsError> {
+    if self.migration_progress.exists(storage) {
+      if let Ok(Some(payment)) = LEGACY_PAYABLE_PAYMENTS.may_load(storage, id) {
+        let chain =
+          self.cb_chain_id_of_wormhole(storage, payment.payer_chain_id)?;
+        return Ok(Some(payment.upgrade(chain)));
+      }
+    }
+    Ok(self.payable_payments.may_load(storage, id)?)
+  }
+
+  /// A payable's payments count and payment IDs from a chain, if it has any.
+  /// Includes those that the migration hasn't moved yet.
+  pub fn per_chain_payable_payments(
+    &self,
+    storage: &dyn Storage,
+    payable_id: [u8; 32],
+    chain_id: [u8; 32],
+  ) -> Result<Option<PerChainPayments>, ChainbillsError> {
+    let key = (payable_id.to_vec(), chain_id);
+    let mut count = self
+      .per_chain_payable_payments_count
+      .may_load(storage, key.clone())?;
+    let mut ids = self
+      .per_chain_payable_payment_ids
+      .may_load(storage, key)?
+      .unwrap_or_default();
+
+    let wormhole_chain_id = self
+      .cb_chains
+      .may_load(storage, chain_id)?
+      .and_then(|chain| chain.wormhole_chain_id);
+    if let Some(wormhole_chain_id) =
+      wormhole_chain_id.filter(|_| self.migration_progress.exists(storage))
+    {
+      let legacy_key = (payable_id.to_vec(), wormhole_chain_id);
+      if let Some(legacy_count) =
+        LEGACY_PER_CHAIN_COUNTS.may_load(storage, legacy_key.clone())?
+      {
+        count = Some(count.unwrap_or_default() + legacy_count);
+        let mut legacy_ids = LEGACY_PER_CHAIN_IDS
+          .may_load(storage, legacy_key)?
+          .unwrap_or_default();
+        legacy_ids.extend(ids);
+        ids = legacy_ids;
+      }
+    }
+    Ok(count.map(|count| (count, ids)))
+  }
+
+  /// Moves a payable's per-chain payments count and IDs from a Wormhole
+  /// Chain ID to the chain's cbChainId. Payments recorded under the
+  /// cbChainId since the migration started come after the moved ones.
+  pub fn migrate_per_chain_payable_payments(
+    &self,
+    storage: &mut dyn Storage,
+    payable_id: Vec<u8>,
+    wormhole_chain_id: u16,
+  ) -> Result<(), ChainbillsError> {
+    let legacy_key = (payable_id.clone(), wormhole_chain_id);
+    let Some(legacy_count) =
+      LEGACY_PER_CHAIN_COUNTS.may_load(storage, legacy_key.clone())?
+    else {
+      return Ok(());
+    };
+    let mut ids = LEGACY_PER_CHAIN_IDS
+      .may_load(storage, legacy_key.clone())?
+      .unwrap_or_default();
+    LEGACY_PER_CHAIN_COUNTS.remove(storage, legacy_key.clone());
+    LEGACY_PER_CHAIN_IDS.remove(storage, legacy_key);
+
+    let chain = self.cb_chain_id_of_wormhole(storage, wormhole_chain_id)?;
+    let key = (payable_id, chain);
+    let count = self
+      .per_chain_payable_payments_count
+      .may_load(storage, key.clone())?
+      .unwrap_or_default()
+      .checked_add(legacy_count)
+      .unwrap();
+    ids.extend(
+      self
+        .per_chain_payable_payment_ids
+        .may_load(storage, key.clone())?
+        .unwrap_or_default(),
+    );
+    self
+      .per_chain_payable_payments_count
+      .save(storage, key.clone(), &count)?;
+    self
+      .per_chain_payable_payment_ids
+      .save(storage, key, &ids)?;
+    Ok(())
+  }
+
+  /// Migrates a stored entry of the stage, given its key within the stage's
+  /// namespace. Entries already in the new format are left as they are.
+  fn migrate_entry(
+    &self,
+    storage: &mut dyn Storage,
+    stage: &MigrationStage,
+    key: &[u8],
+    value: &[u8],
+  ) -> Result<bool, ChainbillsError> {
+    match stage {
+      MigrationStage::UserPayments => {
+        let Ok(payment) = from_json::<UserPaymentV0>(value) else {
+          return Ok(false);
+        };
+        let chain =
+          self.cb_chain_id_of_wormhole(storage, payment.payable_chain_id)?;
+        let id = <[u8; 32]>::try_from(key).unwrap();
+        self
+          .user_payments
+          .save(storage, id, &payment.upgrade(chain))?;
+      }
+      MigrationStage::PayablePayments => {
+        let Ok(payment) = from_json::<PayablePaymentV0>(value) else {
+          return Ok(false);
+        };
+        let chain =
+          self.cb_chain_id_of_wormhole(storage, payment.payer_chain_id)?;
+        let id = <[u8; 32]>::try_from(key).unwrap();
+        self
+          .payable_payments
+          .save(storage, id, &payment.upgrade(chain))?;
+      }
+      MigrationStage::PerChainPayablePayments => {
+        // Keys are the length-prefixed payable ID followed by the chain,
+        // which takes 2 bytes as a Wormhole Chain ID and 32 as a cbChainId.
+        let payable_id_len = u16::from_be_bytes([key[0], key[1]]) as usize;
+        let (payable_id, chain) = key[2..].split_at(payable_id_len);
+        let Ok(wormhole_chain_id) = <[u8; 2]>::try_from(chain) else {
+          return Ok(false);
+        };
+        self.migrate_per_chain_payable_payments(
+          storage,
+          payable_id.to_vec(),
+          u16::from_be_bytes(wormhole_chain_id),
+        )?;
+      }
+    }
+    Ok(true)
+  }
+}
+
+/// The next entries of a namespace after the cursor, with their keys within
+/// the namespace.
+fn page_after(
+  storage: &dyn Storage,
+  namespace: &str,
+  cursor: Option<&[u8]>,
+  limit: usize,
+) -> Vec<Record> {
+  let mut prefix = (namespace.len() as u16).to_be_bytes().to_vec();
+  prefix.extend_from_slice(namespace.as_bytes());
+  let start = match cursor {
+    Some(cursor) => [prefix.as_slice(), cursor, &[0]].concat(),
+    None => prefix.clone(),
+  };
+  // Namespaces end in a letter, so this ends the range right after them.
+  let mut end = prefix.clone();
+  *end.last_mut().unwrap() += 1;
+  storage
+    .range(Some(&start), Some(&end), Order::Ascending)
+    .take(limit)
+    .map(|(key, value)| (key[prefix.len()..].to_vec(), value))
+    .collect()
+}
+
+impl Migration for Chainbills {
+  type Error = ChainbillsError;
+
+  fn migration_progress(
+    &self,
+    ctx: QueryCtx,
+  ) -> Result<Option<MigrationProgress>, Self::Error> {
+    Ok(self.migration_progress.may_load(ctx.deps.storage)?)
+  }
+
+  fn continue_migration(
+    &self,
+    ctx: ExecCtx,
+    msg: ContinueMigrationMessage,
+  ) -> Result<Response, Self::Error> {
+    /* CHECKS */
+    let storage = ctx.deps.storage;
+    let Some(mut progress) = self.migration_progress.may_load(storage)? else {
+      return Err(ChainbillsError::NoMigrationInProgress {});
+    };
+    if msg.limit == 0 {
+      return Err(ChainbillsError::InvalidMigrationLimit {});
+    }
+
+    /* STATE CHANGES */
+    // Migrate the stage's next entries.
+    let limit = usize::try_from(msg.limit).unwrap_or(usize::MAX);
+    let entries = page_after(
+      storage,
+      progress.stage.namespace(),
+      progress.cursor.as_deref(),
+      limit,
+    );
+    let mut migrated = 0u64;
+    for (key, value) in entries.iter() {
+      if self.migrate_entry(storage, &progress.stage, key, value)? {
+        migrated += 1;
+      }
+    }
+
+    // Move on to the next stage once this one runs out of entries, and
+    // finish after the last stage.
+    let stage = progress.stage.clone();
+    if entries.len() < limit {
+      match progress.stage.next() {
+        Some(next) => {
+          progress = MigrationProgress {
+            stage: next,
+            cursor: None,
+          };
+          self.migration_progress.save(storage, &progress)?;
+        }
+        None => self.migration_progress.remove(storage),
+      }
+    } else {
+      progress.cursor =
+        entries.last().map(|(key, _)| Binary::from(key.clone()));
+      self.migration_progress.save(storage, &progress)?;
+    }
+
+    // Return the Response.
+    Ok(Response::new().add_attributes([
+      ("action", "continued_migration".to_string()),
+      ("stage", format!("{stage:?}")),
+      ("migrated", migrated.to_string()),
+      (
+        "is_finished",
+        (!self.migration_progress.exists(storage)).to_string(),
+      ),
+    ]))
+  }
+}
@@ -1,5 +1,13 @@
 pub mod activities;
+pub mod chains;
+pub mod escrows;
+pub mod hooks;
+pub mod integrity;
+pub mod intents;
+pub mod migration;
 pub mod payables;
 pub mod payments;
+pub mod receipts;
+pub mod subscriptions;
 pub mod token_details;
 pub mod withdrawals;
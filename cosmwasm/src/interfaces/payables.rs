@@ -1,11 +1,18 @@
 use crate::contract::Chainbills;
 use crate::error::ChainbillsError;
 use crate::messages::{
-  CountMessage, CreatePayableMessage, FetchIdMessage, IdMessage,
+  AddressMessage, AllowedPayersRootMessage, CountMessage, CreatePayableMessage,
+  FetchIdMessage, IdMessage, PayableOperatorMessage, SetPayableOperatorMessage,
+  TransferPayableMessage, UpdatePayableAllowedPayersMessage,
   UpdatePayableTokensAndAmountsMessage,
 };
-use crate::state::{ActivityRecord, ActivityType, Payable, TokenDetails, User};
-use sylvia::cw_std::{HexBinary, Response, StdError, Uint128};
+use crate::state::{
+  ActivityRecord, ActivityType, Payable, PayableOperator, User,
+};
+use chainbills_payload::{
+  allowed_payers_root, PayablePayload, OPEN_TO_ALL_PAYERS,
+};
+use sylvia::cw_std::{HexBinary, Response, StdError};
 use sylvia::interface;
 use sylvia::types::{ExecCtx, QueryCtx};
 
@@ -60,6 +67,62 @@ pub trait Payables {
     ctx: ExecCtx,
     msg: UpdatePayableTokensAndAmountsMessage,
   ) -> Result<Response, Self::Error>;
+
+  #[sv::msg(query)]
+  fn pending_payable_host(
+    &self,
+    ctx: QueryCtx,
+    msg: IdMessage,
+  ) -> Result<AddressMessage, Self::Error>;
+
+  #[sv::msg(exec)]
+  fn transfer_payable(
+    &self,
+    ctx: ExecCtx,
+    msg: TransferPayableMessage,
+  ) -> Result<Response, Self::Error>;
+
+  #[sv::msg(exec)]
+  fn accept_payable(
+    &self,
+    ctx: ExecCtx,
+    msg: IdMessage,
+  ) -> Result<Response, Self::Error>;
+
+  #[sv::msg(query)]
+  fn payable_operator(
+    &self,
+    ctx: QueryCtx,
+    msg: PayableOperatorMessage,
+  ) -> Result<PayableOperator, Self::Error>;
+
+  #[sv::msg(exec)]
+  fn set_payable_operator(
+    &self,
+    ctx: ExecCtx,
+    msg: SetPayableOperatorMessage,
+  ) -> Result<Response, Self::Error>;
+
+  #[sv::msg(exec)]
+  fn remove_payable_operator(
+    &self,
+    ctx: ExecCtx,
+    msg: PayableOperatorMessage,
+  ) -> Result<Response, Self::Error>;
+
+  #[sv::msg(query)]
+  fn payable_allowed_payers_root(
+    &self,
+    ctx: QueryCtx,
+    msg: IdMessage,
+  ) -> Result<AllowedPayersRootMessage, Self::Error>;
+
+  #[sv::msg(exec)]
+  fn update_payable_allowed_payers(
+    &self,
+    ctx: ExecCtx,
+    msg: UpdatePayableAllowedPayersMessage,
+  ) -> Result<Response, Self::Error>;
 }
 
 impl Payables for Chainbills {
@@ -132,23 +195,10 @@ impl Payables for Chainbills {
     let CreatePayableMessage {
       allowed_tokens_and_amounts,
     } = msg;
-    for taa in allowed_tokens_and_amounts.iter() {
-      // Ensure that the token is supported.
-      let token_details = self
-        .token_details
-        .load(ctx.deps.storage, taa.token.clone())
-        .unwrap_or(TokenDetails::initialize(false, false, Uint128::zero()));
-      if !token_details.is_supported {
-        return Err(ChainbillsError::UnsupportedToken {
-          token: taa.token.clone(),
-        });
-      }
-
-      // Ensure that all specified acceptable amounts are greater than zero.
-      if taa.amount.is_zero() {
-        return Err(ChainbillsError::ZeroAmountSpecified {});
-      }
-    }
+    self.check_allowed_tokens_and_amounts(
+      ctx.deps.storage,
+      &allowed_tokens_and_amounts,
+    )?;
 
     /* STATE CHANGES */
     /* COUNTS */
@@ -248,6 +298,15 @@ impl Payables for Chainbills {
     )?;
 
     /* FINISH */
+    // Encode the payload that relayers broadcast to other chains.
+    let payload = self.encode_payable_payload(
+      ctx.deps.storage,
+      ctx.deps.api,
+      payable_id,
+      &payable,
+      PayablePayload::ACTION_CREATED,
+    )?;
+
     // Return the Response.
     Ok(
       Response::new()
@@ -258,6 +317,7 @@ impl Payables for Chainbills {
           ("host_wallet", ctx.info.sender.to_string()),
           ("chain_count", chain_stats.payables_count.to_string()),
           ("host_count", user.payables_count.to_string()),
+          ("payload", payload.to_hex()),
         ]),
     )
   }
@@ -276,10 +336,14 @@ impl Payables for Chainbills {
     }
     let mut payable = self.payables.load(ctx.deps.storage, payable_id)?;
 
-    // Ensure that the caller owns the payable.
-    if payable.host != &ctx.info.sender {
-      return Err(ChainbillsError::NotYourPayable {});
-    }
+    // Ensure that the caller owns the payable or can manage it.
+    self.ensure_host_or_operator(
+      ctx.deps.storage,
+      payable_id,
+      &payable,
+      &ctx.info.sender,
+      PayableOperator::ROLE_MANAGE,
+    )?;
 
     // Ensure that the payable is not already closed.
     if payable.is_closed {
@@ -300,17 +364,27 @@ impl Payables for Chainbills {
     self.record_update_payable_activity(
       ctx.deps.storage,
       &ctx.env,
-      &ctx.info.sender,
+      &payable.host,
       payable_id,
       payable.activities_count,
       ActivityType::ClosedPayable,
     )?;
 
+    // Encode the payload that relayers broadcast to other chains.
+    let payload = self.encode_payable_payload(
+      ctx.deps.storage,
+      ctx.deps.api,
+      payable_id,
+      &payable,
+      PayablePayload::ACTION_CLOSED,
+    )?;
+
     // Return the Response.
     Ok(Response::new().add_attributes([
       ("action", "closed_payable".to_string()),
       ("payable_id", HexBinary::from(&payable_id).to_hex()),
-      ("host_wallet", ctx.info.sender.to_string()),
+      ("host_wallet", payable.host.to_string()),
+      ("payload", payload.to_hex()),
     ]))
   }
 
@@ -328,10 +402,14 @@ impl Payables for Chainbills {
     }
     let mut payable = self.payables.load(ctx.deps.storage, payable_id)?;
 
-    // Ensure that the caller owns the payable.
-    if payable.host != &ctx.info.sender {
-      return Err(ChainbillsError::NotYourPayable {});
-    }
+    // Ensure that the caller owns the payable or can manage it.
+    self.ensure_host_or_operator(
+      ctx.deps.storage,
+      payable_id,
+      &payable,
+      &ctx.info.sender,
+      PayableOperator::ROLE_MANAGE,
+    )?;
 
     // Ensure that the payable is already closed.
     if !payable.is_closed {
@@ -352,17 +430,27 @@ impl Payables for Chainbills {
     self.record_update_payable_activity(
       ctx.deps.storage,
       &ctx.env,
-      &ctx.info.sender,
+      &payable.host,
       payable_id,
       payable.activities_count,
       ActivityType::ReopenedPayable,
     )?;
 
+    // Encode the payload that relayers broadcast to other chains.
+    let payload = self.encode_payable_payload(
+      ctx.deps.storage,
+      ctx.deps.api,
+      payable_id,
+      &payable,
+      PayablePayload::ACTION_REOPENED,
+    )?;
+
     // Return the Response.
     Ok(Response::new().add_attributes([
       ("action", "reopened_payable".to_string()),
       ("payable_id", HexBinary::from(&payable_id).to_hex()),
-      ("host_wallet", ctx.info.sender.to_string()),
+      ("host_wallet", payable.host.to_string()),
+      ("payload", payload.to_hex()),
     ]))
   }
 
@@ -381,32 +469,23 @@ impl Payables for Chainbills {
     }
     let mut payable = self.payables.load(ctx.deps.storage, payable_id)?;
 
-    // Ensure that the caller owns the payable.
-    if payable.host != &ctx.info.sender {
-      return Err(ChainbillsError::NotYourPayable {});
-    }
+    // Ensure that the caller owns the payable or can manage it.
+    self.ensure_host_or_operator(
+      ctx.deps.storage,
+      payable_id,
+      &payable,
+      &ctx.info.sender,
+      PayableOperator::ROLE_MANAGE,
+    )?;
 
     let UpdatePayableTokensAndAmountsMessage {
       allowed_tokens_and_amounts,
       ..
     } = msg;
-    for taa in allowed_tokens_and_amounts.iter() {
-      // Ensure that the token is supported.
-      let token_details = self
-        .token_details
-        .load(ctx.deps.storage, taa.token.clone())
-        .unwrap_or(TokenDetails::initialize(false, false, Uint128::zero()));
-      if !token_details.is_supported {
-        return Err(ChainbillsError::UnsupportedToken {
-          token: taa.token.clone(),
-        });
-      }
-
-      // Ensure that all specified acceptable amounts are greater than zero.
-      if taa.amount.is_zero() {
-        return Err(ChainbillsError::ZeroAmountSpecified {});
-      }
-    }
+    self.check_allowed_tokens_and_amounts(
+      ctx.deps.storage,
+      &allowed_tokens_and_amounts,
+    )?;
 
     /* STATE CHANGES */
     // Update the payable's allowed_tokens_and_amounts.
@@ -422,17 +501,407 @@ impl Payables for Chainbills {
     self.record_update_payable_activity(
       ctx.deps.storage,
       &ctx.env,
-      &ctx.info.sender,
+      &payable.host,
       payable_id,
       payable.activities_count,
       ActivityType::UpdatedPayableAllowedTokensAndAmounts,
     )?;
 
+    // Encode the payload that relayers broadcast to other chains.
+    let payload = self.encode_payable_payload(
+      ctx.deps.storage,
+      ctx.deps.api,
+      payable_id,
+      &payable,
+      PayablePayload::ACTION_UPDATED_ATAA,
+    )?;
+
     // Return the Response.
     Ok(Response::new().add_attributes([
       ("action", "updated_payable_tokens_and_amounts".to_string()),
       ("payable_id", HexBinary::from(&payable_id).to_hex()),
+      ("host_wallet", payable.host.to_string()),
+      ("payload", payload.to_hex()),
+    ]))
+  }
+
+  fn pending_payable_host(
+    &self,
+    ctx: QueryCtx,
+    msg: IdMessage,
+  ) -> Result<AddressMessage, Self::Error> {
+    let payable_id =
+      <[u8; 32]>::try_from(HexBinary::from_hex(&msg.id)?.as_slice()).unwrap();
+    match self
+      .pending_payable_hosts
+      .may_load(ctx.deps.storage, payable_id)?
+    {
+      Some(address) => Ok(AddressMessage { address }),
+      None => Err(ChainbillsError::NotPendingPayableHost {}),
+    }
+  }
+
+  fn transfer_payable(
+    &self,
+    ctx: ExecCtx,
+    msg: TransferPayableMessage,
+  ) -> Result<Response, Self::Error> {
+    /* CHECKS */
+    // Ensure that the payable_id is valid.
+    let payable_id =
+      <[u8; 32]>::try_from(HexBinary::from_hex(&msg.payable_id)?.as_slice())
+        .unwrap();
+    if !self.payables.has(ctx.deps.storage, payable_id) {
+      return Err(ChainbillsError::InvalidPayableId { id: msg.payable_id });
+    }
+    let payable = self.payables.load(ctx.deps.storage, payable_id)?;
+
+    // Ensure that the caller owns the payable.
+    if payable.host != ctx.info.sender {
+      return Err(ChainbillsError::NotYourPayable {});
+    }
+
+    // Ensure that the new host is a valid and different wallet.
+    let new_host = ctx.deps.api.addr_validate(&msg.new_host)?;
+    if new_host == ctx.info.sender {
+      return Err(ChainbillsError::CannotTransferPayableToSelf {});
+    }
+
+    /* STATE CHANGES */
+    // Save the offered wallet. Offering again replaces it.
+    self
+      .pending_payable_hosts
+      .save(ctx.deps.storage, payable_id, &new_host)?;
+
+    // Return the Response.
+    Ok(Response::new().add_attributes([
+      ("action", "initiated_payable_transfer".to_string()),
+      ("payable_id", HexBinary::from(&payable_id).to_hex()),
       ("host_wallet", ctx.info.sender.to_string()),
+      ("new_host", new_host.to_string()),
+    ]))
+  }
+
+  fn accept_payable(
+    &self,
+    ctx: ExecCtx,
+    msg: IdMessage,
+  ) -> Result<Response, Self::Error> {
+    /* CHECKS */
+    // Ensure that the payable_id is valid.
+    let payable_id =
+      <[u8; 32]>::try_from(HexBinary::from_hex(&msg.id)?.as_slice()).unwrap();
+    if !self.payables.has(ctx.deps.storage, payable_id) {
+      return Err(ChainbillsError::InvalidPayableId { id: msg.id });
+    }
+    let mut payable = self.payables.load(ctx.deps.storage, payable_id)?;
+
+    // Ensure that the payable was offered to the caller.
+    match self
+      .pending_payable_hosts
+      .may_load(ctx.deps.storage, payable_id)?
+    {
+      Some(new_host) if new_host == ctx.info.sender => {}
+      _ => return Err(ChainbillsError::NotPendingPayableHost {}),
+    }
+
+    /* STATE CHANGES */
+    self
+      .pending_payable_hosts
+      .remove(ctx.deps.storage, payable_id);
+
+    // Initialize the new host if this is their first interaction.
+    let user_resp_attribs = self.initialize_user_if_is_new(
+      ctx.deps.storage,
+      &ctx.env,
+      &ctx.info.sender,
+    )?;
+
+    // Index the payable for the new host.
+    let mut user = self.users.load(ctx.deps.storage, &ctx.info.sender)?;
+    user.payables_count = user.next_payable();
+    self.users.save(ctx.deps.storage, &ctx.info.sender, &user)?;
+    let mut user_payable_ids = self
+      .user_payable_ids
+      .may_load(ctx.deps.storage, &ctx.info.sender)?
+      .unwrap_or_default();
+    user_payable_ids.push(payable_id);
+    self.user_payable_ids.save(
+      ctx.deps.storage,
+      &ctx.info.sender,
+      &user_payable_ids,
+    )?;
+
+    // Move the payable to the new host.
+    let previous_host = payable.host.clone();
+    payable.host = ctx.info.sender.clone();
+    payable.host_count = user.payables_count;
+
+    // Record the activities of both hosts.
+    payable.activities_count = payable.next_activity();
+    self.record_update_payable_activity(
+      ctx.deps.storage,
+      &ctx.env,
+      &previous_host,
+      payable_id,
+      payable.activities_count,
+      ActivityType::TransferredPayable,
+    )?;
+    payable.activities_count = payable.next_activity();
+    self.record_update_payable_activity(
+      ctx.deps.storage,
+      &ctx.env,
+      &ctx.info.sender,
+      payable_id,
+      payable.activities_count,
+      ActivityType::AcceptedPayable,
+    )?;
+
+    // Save the payable.
+    self.payables.save(ctx.deps.storage, payable_id, &payable)?;
+
+    // Return the Response.
+    Ok(
+      Response::new()
+        .add_attributes(user_resp_attribs)
+        .add_attributes([
+          ("action", "transferred_payable".to_string()),
+          ("payable_id", HexBinary::from(&payable_id).to_hex()),
+          ("previous_host", previous_host.to_string()),
+          ("new_host", ctx.info.sender.to_string()),
+          ("new_host_count", payable.host_count.to_string()),
+        ]),
+    )
+  }
+
+  fn payable_operator(
+    &self,
+    ctx: QueryCtx,
+    msg: PayableOperatorMessage,
+  ) -> Result<PayableOperator, Self::Error> {
+    let payable_id =
+      <[u8; 32]>::try_from(HexBinary::from_hex(&msg.payable_id)?.as_slice())
+        .unwrap();
+    let operator = ctx.deps.api.addr_validate(&msg.operator)?;
+    match self
+      .payable_operators
+      .may_load(ctx.deps.storage, (payable_id.to_vec(), &operator))?
+    {
+      Some(payable_operator) => Ok(payable_operator),
+      None => Err(ChainbillsError::NotPayableOperator {
+        operator: msg.operator,
+      }),
+    }
+  }
+
+  fn set_payable_operator(
+    &self,
+    ctx: ExecCtx,
+    msg: SetPayableOperatorMessage,
+  ) -> Result<Response, Self::Error> {
+    /* CHECKS */
+    // Ensure that the payable_id is valid.
+    let payable_id =
+      <[u8; 32]>::try_from(HexBinary::from_hex(&msg.payable_id)?.as_slice())
+        .unwrap();
+    if !self.payables.has(ctx.deps.storage, payable_id) {
+      return Err(ChainbillsError::InvalidPayableId { id: msg.payable_id });
+    }
+    let payable = self.payables.load(ctx.deps.storage, payable_id)?;
+
+    // Ensure that the caller owns the payable.
+    if payable.host != ctx.info.sender {
+      return Err(ChainbillsError::NotYourPayable {});
+    }
+
+    // Ensure that the operator is a valid wallet and the roles are valid.
+    let operator = ctx.deps.api.addr_validate(&msg.operator)?;
+    if !PayableOperator::are_valid_roles(msg.roles) {
+      return Err(ChainbillsError::InvalidOperatorRoles { roles: msg.roles });
+    }
+
+    /* STATE CHANGES */
+    // Save the operator. Setting again replaces the roles.
+    self.payable_operators.save(
+      ctx.deps.storage,
+      (payable_id.to_vec(), &operator),
+      &PayableOperator {
+        host: ctx.info.sender.clone(),
+        roles: msg.roles,
+      },
+    )?;
+
+    // Return the Response.
+    Ok(Response::new().add_attributes([
+      ("action", "updated_payable_operator".to_string()),
+      ("payable_id", HexBinary::from(&payable_id).to_hex()),
+      ("operator", operator.to_string()),
+      ("roles", msg.roles.to_string()),
+    ]))
+  }
+
+  fn remove_payable_operator(
+    &self,
+    ctx: ExecCtx,
+    msg: PayableOperatorMessage,
+  ) -> Result<Response, Self::Error> {
+    /* CHECKS */
+    // Ensure that the payable_id is valid.
+    let payable_id =
+      <[u8; 32]>::try_from(HexBinary::from_hex(&msg.payable_id)?.as_slice())
+        .unwrap();
+    if !self.payables.has(ctx.deps.storage, payable_id) {
+      return Err(ChainbillsError::InvalidPayableId { id: msg.payable_id });
+    }
+    let payable = self.payables.load(ctx.deps.storage, payable_id)?;
+
+    // Ensure that the caller owns the payable.
+    if payable.host != ctx.info.sender {
+      return Err(ChainbillsError::NotYourPayable {});
+    }
+
+    // Ensure that the wallet is an operator on the payable.
+    let operator = ctx.deps.api.addr_validate(&msg.operator)?;
+    if !self
+      .payable_operators
+      .has(ctx.deps.storage, (payable_id.to_vec(), &operator))
+    {
+      return Err(ChainbillsError::NotPayableOperator {
+        operator: msg.operator,
+      });
+    }
+
+    /* STATE CHANGES */
+    self
+      .payable_operators
+      .remove(ctx.deps.storage, (payable_id.to_vec(), &operator));
+
+    // Return the Response.
+    Ok(Response::new().add_attributes([
+      ("action", "updated_payable_operator".to_string()),
+      ("payable_id", HexBinary::from(&payable_id).to_hex()),
+      ("operator", operator.to_string()),
+      ("roles", "0".to_string()),
+    ]))
+  }
+
+  fn payable_allowed_payers_root(
+    &self,
+    ctx: QueryCtx,
+    msg: IdMessage,
+  ) -> Result<AllowedPayersRootMessage, Self::Error> {
+    let payable_id =
+      <[u8; 32]>::try_from(HexBinary::from_hex(&msg.id)?.as_slice()).unwrap();
+    if !self.payables.has(ctx.deps.storage, payable_id) {
+      return Err(ChainbillsError::InvalidPayableId { id: msg.id });
+    }
+    let root = self
+      .payable_allowed_payers_roots
+      .may_load(ctx.deps.storage, payable_id)?
+      .unwrap_or(OPEN_TO_ALL_PAYERS);
+    Ok(AllowedPayersRootMessage {
+      allowed_payers_root: HexBinary::from(&root).to_hex(),
+    })
+  }
+
+  fn update_payable_allowed_payers(
+    &self,
+    ctx: ExecCtx,
+    msg: UpdatePayableAllowedPayersMessage,
+  ) -> Result<Response, Self::Error> {
+    /* CHECKS */
+    // Ensure that the payable_id is valid.
+    let payable_id =
+      <[u8; 32]>::try_from(HexBinary::from_hex(&msg.payable_id)?.as_slice())
+        .unwrap();
+    if !self.payables.has(ctx.deps.storage, payable_id) {
+      return Err(ChainbillsError::InvalidPayableId { id: msg.payable_id });
+    }
+    let mut payable = self.payables.load(ctx.deps.storage, payable_id)?;
+
+    // Ensure that the caller owns the payable or can manage it.
+    self.ensure_host_or_operator(
+      ctx.deps.storage,
+      payable_id,
+      &payable,
+      &ctx.info.sender,
+      PayableOperator::ROLE_MANAGE,
+    )?;
+
+    // Ensure that each allowed payer is a 32-byte address.
+    let mut allowed_payers = vec![];
+    for payer in msg.allowed_payers.iter() {
+      match HexBinary::from_hex(payer)
+        .map(|p| <[u8; 32]>::try_from(p.as_slice()))
+      {
+        Ok(Ok(payer)) => allowed_payers.push(payer),
+        _ => {
+          return Err(ChainbillsError::InvalidAllowedPayer {
+            payer: payer.clone(),
+          })
+        }
+      }
+    }
+
+    // Take the root of the payers if given, otherwise the provided root.
+    // Both can't be given as they could disagree.
+    let root = match msg.allowed_payers_root {
+      Some(_) if !allowed_payers.is_empty() => {
+        return Err(ChainbillsError::InvalidAllowedPayersRoot {});
+      }
+      Some(root) => HexBinary::from_hex(&root)
+        .ok()
+        .and_then(|r| <[u8; 32]>::try_from(r.as_slice()).ok())
+        .ok_or(ChainbillsError::InvalidAllowedPayersRoot {})?,
+      None => allowed_payers_root(&allowed_payers, &Chainbills::keccak),
+    };
+
+    /* STATE CHANGES */
+    // Save the root. Open payables don't keep one.
+    if root == OPEN_TO_ALL_PAYERS {
+      self
+        .payable_allowed_payers_roots
+        .remove(ctx.deps.storage, payable_id);
+    } else {
+      self.payable_allowed_payers_roots.save(
+        ctx.deps.storage,
+        payable_id,
+        &root,
+      )?;
+    }
+
+    // Increment the activity count on the payable.
+    payable.activities_count = payable.next_activity();
+
+    // Save the payable.
+    self.payables.save(ctx.deps.storage, payable_id, &payable)?;
+
+    // Record the activity.
+    self.record_update_payable_activity(
+      ctx.deps.storage,
+      &ctx.env,
+      &payable.host,
+      payable_id,
+      payable.activities_count,
+      ActivityType::UpdatedPayableAllowedPayers,
+    )?;
+
+    // Encode the payload that relayers broadcast to other chains.
+    let payload = self.encode_payable_payload(
+      ctx.deps.storage,
+      ctx.deps.api,
+      payable_id,
+      &payable,
+      PayablePayload::ACTION_UPDATED_ALLOWED_PAYERS,
+    )?;
+
+    // Return the Response.
+    Ok(Response::new().add_attributes([
+      ("action", "updated_payable_allowed_payers".to_string()),
+      ("payable_id", HexBinary::from(&payable_id).to_hex()),
+      ("host_wallet", payable.host.to_string()),
+      ("allowed_payers_root", HexBinary::from(&root).to_hex()),
+      ("payload", payload.to_hex()),
     ]))
   }
 }
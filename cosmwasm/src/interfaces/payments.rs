@@ -1,14 +1,15 @@
 use crate::contract::Chainbills;
 use crate::error::ChainbillsError;
 use crate::messages::{
-  CountMessage, FetchIdMessage, IdMessage, PerChainPayablePaymentIdMessage,
+  CountMessage, FetchIdMessage, IdMessage, PayBatchMessage,
+  PayWithProofMessage, PerChainPayablePaymentIdMessage,
   PerChainPayablePaymentsCountMessage, TransactionInfoMessage,
 };
 use crate::state::{
-  ActivityRecord, ActivityType, PayablePayment, TokenAndAmount, TokenDetails,
-  User, UserPayment,
+  PayablePayment, TokenAndAmount, TokenDetails, User, UserPayment,
 };
 use cw20::Cw20ExecuteMsg;
+use std::collections::BTreeMap;
 use sylvia::cw_std::{
   to_json_binary, HexBinary, Response, StdError, Uint128, WasmMsg,
 };
@@ -59,6 +60,14 @@ pub trait Payments {
     msg: IdMessage,
   ) -> Result<PayablePayment, Self::Error>;
 
+  /// The reference recorded on a PayablePayment, if any.
+  #[sv::msg(query)]
+  fn payment_reference(
+    &self,
+    ctx: QueryCtx,
+    msg: IdMessage,
+  ) -> Result<Option<String>, Self::Error>;
+
   #[sv::msg(query)]
   fn per_chain_payable_payment_count(
     &self,
@@ -79,6 +88,20 @@ pub trait Payments {
     ctx: ExecCtx,
     data: TransactionInfoMessage,
   ) -> Result<Response, Self::Error>;
+
+  #[sv::msg(exec)]
+  fn pay_with_proof(
+    &self,
+    ctx: ExecCtx,
+    data: PayWithProofMessage,
+  ) -> Result<Response, Self::Error>;
+
+  #[sv::msg(exec)]
+  fn pay_batch(
+    &self,
+    ctx: ExecCtx,
+    data: PayBatchMessage,
+  ) -> Result<Response, Self::Error>;
 }
 
 impl Payments for Chainbills {
@@ -151,7 +174,7 @@ impl Payments for Chainbills {
     ctx: QueryCtx,
     msg: IdMessage,
   ) -> Result<UserPayment, Self::Error> {
-    match self.user_payments.may_load(
+    match self.may_load_user_payment(
       ctx.deps.storage,
       <[u8; 32]>::try_from(HexBinary::from_hex(&msg.id)?.as_slice()).unwrap(),
     )? {
@@ -193,7 +216,7 @@ impl Payments for Chainbills {
     ctx: QueryCtx,
     msg: IdMessage,
   ) -> Result<PayablePayment, Self::Error> {
-    match self.payable_payments.may_load(
+    match self.may_load_payable_payment(
       ctx.deps.storage,
       <[u8; 32]>::try_from(HexBinary::from_hex(&msg.id)?.as_slice()).unwrap(),
     )? {
@@ -202,6 +225,23 @@ impl Payments for Chainbills {
     }
   }
 
+  fn payment_reference(
+    &self,
+    ctx: QueryCtx,
+    msg: IdMessage,
+  ) -> Result<Option<String>, Self::Error> {
+    let payment_id =
+      <[u8; 32]>::try_from(HexBinary::from_hex(&msg.id)?.as_slice()).unwrap();
+    if !self.payable_payments.has(ctx.deps.storage, payment_id) {
+      return Err(ChainbillsError::InvalidPaymentId { id: msg.id });
+    }
+    Ok(
+      self
+        .payment_references
+        .may_load(ctx.deps.storage, payment_id)?,
+    )
+  }
+
   fn per_chain_payable_payment_count(
     &self,
     ctx: QueryCtx,
@@ -215,14 +255,17 @@ impl Payments for Chainbills {
       return Err(ChainbillsError::InvalidPayableId { id: msg.payable_id });
     }
 
-    // Ensure that the chain_id is valid and Return the Count if so.
-    let chain_id = msg.chain_id;
-    match self
-      .per_chain_payable_payments_count
-      .may_load(ctx.deps.storage, (payable_id.to_vec(), chain_id))?
-    {
-      Some(count) => Ok(CountMessage { count }),
-      None => Err(ChainbillsError::InvalidChainId { chain_id }),
+    // Ensure that the chain is valid and Return the Count if so.
+    let chain_id = self.resolve_cb_chain_id(ctx.deps.storage, &msg.chain)?;
+    match self.per_chain_payable_payments(
+      ctx.deps.storage,
+      payable_id,
+      chain_id,
+    )? {
+      Some((count, _)) => Ok(CountMessage { count }),
+      None => Err(ChainbillsError::InvalidChainId {
+        chain_id: HexBinary::from(&chain_id).to_hex(),
+      }),
     }
   }
 
@@ -239,14 +282,17 @@ impl Payments for Chainbills {
       return Err(ChainbillsError::InvalidPayableId { id: msg.payable_id });
     }
 
-    // Ensure that the chain_id is valid and Obtain the current count if so.
-    let chain_id = msg.chain_id;
-    let payments_count = match self
-      .per_chain_payable_payments_count
-      .may_load(ctx.deps.storage, (payable_id.to_vec(), chain_id))?
-    {
-      Some(count) => Ok(count),
-      None => Err(ChainbillsError::InvalidChainId { chain_id }),
+    // Ensure that the chain is valid and Obtain the current count if so.
+    let chain_id = self.resolve_cb_chain_id(ctx.deps.storage, &msg.chain)?;
+    let (payments_count, payment_ids) = match self.per_chain_payable_payments(
+      ctx.deps.storage,
+      payable_id,
+      chain_id,
+    )? {
+      Some(payments) => Ok(payments),
+      None => Err(ChainbillsError::InvalidChainId {
+        chain_id: HexBinary::from(&chain_id).to_hex(),
+      }),
     }?;
 
     // Ensure the requested count is valid.
@@ -257,10 +303,7 @@ impl Payments for Chainbills {
       });
     }
 
-    // Obtains the payment_ids and return the requested one.
-    let payment_ids = self
-      .per_chain_payable_payment_ids
-      .load(ctx.deps.storage, (payable_id.to_vec(), chain_id))?;
+    // Return the requested payment_id.
     let id = HexBinary::from(payment_ids[(count - 1) as usize]).to_hex();
     Ok(IdMessage { id })
   }
@@ -269,6 +312,30 @@ impl Payments for Chainbills {
     &self,
     ctx: ExecCtx,
     msg: TransactionInfoMessage,
+  ) -> Result<Response, Self::Error> {
+    // Payers don't need a proof to pay open payables or payables that only
+    // allow them.
+    let TransactionInfoMessage {
+      payable_id,
+      token,
+      amount,
+    } = msg;
+    self.pay_with_proof(
+      ctx,
+      PayWithProofMessage {
+        payable_id,
+        token,
+        amount,
+        payer_proof: vec![],
+        reference: None,
+      },
+    )
+  }
+
+  fn pay_with_proof(
+    &self,
+    mut ctx: ExecCtx,
+    msg: PayWithProofMessage,
   ) -> Result<Response, Self::Error> {
     /* CHECKS */
     // Ensure that the payable_id is valid.
@@ -278,48 +345,165 @@ impl Payments for Chainbills {
     if !self.payables.has(ctx.deps.storage, payable_id) {
       return Err(ChainbillsError::InvalidPayableId { id: msg.payable_id });
     }
-    let mut payable = self.payables.load(ctx.deps.storage, payable_id)?;
+    let payable = self.payables.load(ctx.deps.storage, payable_id)?;
 
     // Ensure that the payable is not closed.
     if payable.is_closed {
       return Err(ChainbillsError::PayableIsClosed {});
     }
 
-    // Extract the token and amount for the payment.
-    let TransactionInfoMessage { token, amount, .. } = msg;
+    // Ensure that the payer is among the payable's allowed payers.
+    self.ensure_allowed_payer(
+      ctx.deps.storage,
+      ctx.deps.api,
+      payable_id,
+      &ctx.info.sender,
+      &msg.payer_proof,
+    )?;
 
-    // Fetch the TokenDetails details for the involved token.
-    let mut token_details = self
-      .token_details
-      .load(ctx.deps.storage, token.clone())
-      .unwrap_or(TokenDetails::initialize(false, false, Uint128::zero()));
+    // Extract the token, amount, and reference for the payment.
+    let PayWithProofMessage {
+      token,
+      amount,
+      reference,
+      ..
+    } = msg;
 
-    // Return an error if the token isn't supported.
-    if !token_details.is_supported {
-      return Err(ChainbillsError::UnsupportedToken {
-        token: token.clone(),
-      });
-    }
+    // Ensure that the payable accepts the token and amount.
+    let token_details =
+      self.check_payment(ctx.deps.storage, &payable, &token, amount)?;
 
-    // Ensure that amount is greater than zero.
-    if amount.is_zero() {
-      return Err(ChainbillsError::ZeroAmountSpecified {});
+    self.receive_payment(
+      &mut ctx,
+      payable_id,
+      &token_details,
+      TokenAndAmount { token, amount },
+      reference,
+    )
+  }
+
+  fn pay_batch(
+    &self,
+    mut ctx: ExecCtx,
+    msg: PayBatchMessage,
+  ) -> Result<Response, Self::Error> {
+    /* CHECKS */
+    if msg.payments.is_empty() {
+      return Err(ChainbillsError::EmptyPaymentBatch {});
     }
 
-    // If this payable specified the tokens and amounts it can accept, ensure
-    // that the token and amount are matching.
-    if !payable.allowed_tokens_and_amounts.is_empty() {
-      let mut ataa_it = payable.allowed_tokens_and_amounts.iter().peekable();
-      while let Some(taa) = ataa_it.next() {
-        if taa.token == token && taa.amount == amount {
-          break;
-        }
-        if ataa_it.peek().is_none() {
-          return Err(ChainbillsError::MatchingTokenAndAmountNotFound {});
-        }
+    // Ensure that each payment is valid, and total their amounts per token.
+    let mut payable_ids = vec![];
+    let mut native_totals: BTreeMap<String, Uint128> = BTreeMap::new();
+    let mut cw20_totals: BTreeMap<String, Uint128> = BTreeMap::new();
+    for payment in msg.payments.iter() {
+      let invalid_payable_id = || ChainbillsError::InvalidPayableId {
+        id: payment.payable_id.clone(),
+      };
+      let payable_id = <[u8; 32]>::try_from(
+        HexBinary::from_hex(&payment.payable_id)?.as_slice(),
+      )
+      .map_err(|_| invalid_payable_id())?;
+      let Some(payable) =
+        self.payables.may_load(ctx.deps.storage, payable_id)?
+      else {
+        return Err(invalid_payable_id());
+      };
+      if payable.is_closed {
+        return Err(ChainbillsError::PayableIsClosed {});
       }
+      self.ensure_allowed_payer(
+        ctx.deps.storage,
+        ctx.deps.api,
+        payable_id,
+        &ctx.info.sender,
+        &payment.payer_proof,
+      )?;
+      let token_details = self.check_payment(
+        ctx.deps.storage,
+        &payable,
+        &payment.token,
+        payment.amount,
+      )?;
+
+      let totals = if token_details.is_native_token {
+        &mut native_totals
+      } else {
+        &mut cw20_totals
+      };
+      let total = totals.entry(payment.token.clone()).or_default();
+      *total = total.checked_add(payment.amount).unwrap();
+      payable_ids.push(payable_id);
     }
 
+    /* FUNDS TRANSFER */
+    // Verify that the funds sent match the native payments' totals.
+    if ctx.info.funds.len() != native_totals.len()
+      || ctx
+        .info
+        .funds
+        .iter()
+        .any(|coin| native_totals.get(&coin.denom) != Some(&coin.amount))
+    {
+      return Err(ChainbillsError::InvalidNativeTokenPayment {});
+    }
+
+    // Prepare a single CW20 Token Transfer per token for the response.
+    let mut cw20_messages = vec![];
+    for (token, amount) in cw20_totals {
+      cw20_messages.push(WasmMsg::Execute {
+        contract_addr: token,
+        funds: vec![],
+        msg: to_json_binary(&Cw20ExecuteMsg::TransferFrom {
+          owner: ctx.info.sender.to_string(),
+          recipient: ctx.env.contract.address.to_string(),
+          amount,
+        })?,
+      });
+    }
+
+    /* STATE CHANGES */
+    // Record each payment. The cw20 messages run before any payable's hook
+    // is notified.
+    let mut response = Response::new()
+      .add_messages(cw20_messages)
+      .add_attribute("action", "pay_batch")
+      .add_attribute("payments_count", msg.payments.len().to_string());
+    for (payment, payable_id) in msg.payments.into_iter().zip(payable_ids) {
+      let recorded = self.record_payment(
+        ctx.deps.branch(),
+        &ctx.env,
+        &ctx.info.sender,
+        payable_id,
+        TokenAndAmount {
+          token: payment.token,
+          amount: payment.amount,
+        },
+        None,
+      )?;
+      response = response
+        .add_submessages(recorded.messages)
+        .add_attributes(recorded.attributes);
+    }
+
+    Ok(response)
+  }
+}
+
+impl Chainbills {
+  /// Collects a checked payment from the payer and records it. Native tokens
+  /// must have been sent with the message while cw20 tokens are transferred
+  /// from the payer's allowance.
+  pub fn receive_payment(
+    &self,
+    ctx: &mut ExecCtx,
+    payable_id: [u8; 32],
+    token_details: &TokenDetails,
+    payment: TokenAndAmount,
+    reference: Option<String>,
+  ) -> Result<Response, ChainbillsError> {
+    let TokenAndAmount { token, amount } = payment;
+
     /* FUNDS TRANSFER */
     let mut cw20_messages = vec![];
     if token_details.is_native_token {
@@ -342,298 +526,22 @@ impl Payments for Chainbills {
     }
 
     /* STATE CHANGES */
-    /* COUNTS */
-    // Increment payments and activities count on the payer (address) making
-    // this payable.
-    let user_resp_attrib = self.initialize_user_if_is_new(
-      ctx.deps.storage,
-      &ctx.env,
-      &ctx.info.sender,
-    )?;
-    let mut user = self.users.load(ctx.deps.storage, &ctx.info.sender)?;
-    user.payments_count = user.next_payment();
-    user.activities_count = user.next_activity();
-    self.users.save(ctx.deps.storage, &ctx.info.sender, &user)?;
-
-    // Increment the chain stats for counts of payments.
-    let mut chain_stats = self.chain_stats.load(ctx.deps.storage)?;
-    chain_stats.user_payments_count = chain_stats.next_user_payment();
-    chain_stats.payable_payments_count = chain_stats.next_payable_payment();
-
-    // Increment the chain stats for activities_count.
-    //
-    // Incrementing twice to account for recording two activities: one for the
-    // user and one for the payable.
-    chain_stats.activities_count = chain_stats.next_activity();
-    chain_stats.activities_count = chain_stats.next_activity();
-
-    // Save the updated chain stats.
-    self.chain_stats.save(ctx.deps.storage, &chain_stats)?;
-
-    // Increment global payments_count and the activities_count on the payable.
-    payable.payments_count = payable.next_payment();
-    payable.activities_count = payable.next_activity();
-
-    // Update payable's balances to add this token and its amount.
-    //
-    // This boolean and the following two scopes was used (instead of peekable)
-    // to solve the borrowing twice bug with rust on the payable variable.
-    let mut was_matching_balance_updated = false;
-    {
-      for balance in payable.balances.iter_mut() {
-        if balance.token == token.clone() {
-          balance.amount = balance.amount.checked_add(amount).unwrap();
-          was_matching_balance_updated = true;
-          break;
-        }
-      }
-    }
-    {
-      if !was_matching_balance_updated {
-        payable.balances.push(TokenAndAmount {
-          token: token.clone(),
-          amount,
-        });
-      }
-    }
-
-    // Save the Updated Payable.
-    self.payables.save(ctx.deps.storage, payable_id, &payable)?;
-
-    let config = self.config.load(ctx.deps.storage)?;
-
-    // Increment the local-chain paymentsCount for the payable.
-    let mut local_chain_count = self
-      .per_chain_payable_payments_count
-      .may_load(ctx.deps.storage, (payable_id.to_vec(), config.chain_id))?
-      .unwrap_or_default();
-    local_chain_count = local_chain_count.checked_add(1).unwrap();
-    self.per_chain_payable_payments_count.save(
-      ctx.deps.storage,
-      (payable_id.to_vec(), config.chain_id),
-      &local_chain_count,
-    )?;
-
-    // Increase the supported token's totals from this payment.
-    token_details.add_user_paid(amount);
-    token_details.add_payable_received(amount);
-    self
-      .token_details
-      .save(ctx.deps.storage, token.clone(), &token_details)?;
-
-    /* PAYMENTS DATA STRUCTURES */
-    // Get a new Payment ID for the User
-    let user_payment_id = self.create_id(
-      ctx.deps.storage,
-      &ctx.env,
-      &ctx.info.sender.as_str(),
-      "payment",
-      user.payments_count,
-    )?;
-
-    // Add the Payment ID to the chain_user_payment_ids.
-    let mut chain_user_payment_ids =
-      self.chain_user_payment_ids.load(ctx.deps.storage)?;
-    chain_user_payment_ids.push(user_payment_id);
-    self
-      .chain_user_payment_ids
-      .save(ctx.deps.storage, &chain_user_payment_ids)?;
-
-    // Save the Payment ID to the users_payment_ids.
-    let mut user_payment_ids = self
-      .user_payment_ids
-      .may_load(ctx.deps.storage, &ctx.info.sender)?
-      .unwrap_or_default();
-    user_payment_ids.push(user_payment_id);
-    self.user_payment_ids.save(
-      ctx.deps.storage,
-      &ctx.info.sender,
-      &user_payment_ids,
-    )?;
-
-    let timestamp = ctx.env.block.time.seconds();
-    let details = TokenAndAmount {
-      token: token.clone(),
-      amount,
-    };
-
-    // Create and Save the UserPayment.
-    let user_payment = UserPayment {
-      payable_id,
-      payer: ctx.info.sender.clone(),
-      payable_chain_id: config.chain_id,
-      chain_count: chain_stats.user_payments_count,
-      payer_count: user.payments_count,
-      timestamp,
-      details: details.clone(),
-    };
-    self.user_payments.save(
-      ctx.deps.storage,
-      user_payment_id,
-      &user_payment,
-    )?;
-
-    // Get a new Payment ID for the Payable.
-    let payable_payment_id = self.create_id(
-      ctx.deps.storage,
+    let recorded = self.record_payment(
+      ctx.deps.branch(),
       &ctx.env,
-      &HexBinary::from(&user_payment_id).to_hex(),
-      "payment",
-      payable.payments_count,
-    )?;
-
-    // Add the Payment ID to the chain_payable_payment_ids.
-    let mut chain_payable_payment_ids =
-      self.chain_payable_payment_ids.load(ctx.deps.storage)?;
-    chain_payable_payment_ids.push(payable_payment_id);
-    self
-      .chain_payable_payment_ids
-      .save(ctx.deps.storage, &chain_payable_payment_ids)?;
-
-    // Save the Payment ID to the payables_payment_ids.
-    let mut payable_payment_ids = self
-      .payable_payment_ids
-      .may_load(ctx.deps.storage, payable_id)?
-      .unwrap_or_default();
-    payable_payment_ids.push(payable_payment_id);
-    self.payable_payment_ids.save(
-      ctx.deps.storage,
-      payable_id,
-      &payable_payment_ids,
-    )?;
-
-    // Save the Payment ID to the per_chain_payable_payment_ids.
-    let mut per_chain_payable_payment_ids = self
-      .per_chain_payable_payment_ids
-      .may_load(ctx.deps.storage, (payable_id.to_vec(), config.chain_id))?
-      .unwrap_or_default();
-    per_chain_payable_payment_ids.push(payable_payment_id);
-    self.per_chain_payable_payment_ids.save(
-      ctx.deps.storage,
-      (payable_id.to_vec(), config.chain_id),
-      &per_chain_payable_payment_ids,
-    )?;
-
-    // Create and Save the PayablePayment.
-    let payable_payment = PayablePayment {
-      payable_id,
-      payer: self.address_to_bytes32(&ctx.info.sender, ctx.deps.api),
-      chain_count: chain_stats.payable_payments_count,
-      payer_chain_id: config.chain_id,
-      local_chain_count,
-      payable_count: payable.payments_count,
-      timestamp,
-      details: details.clone(),
-    };
-    self.payable_payments.save(
-      ctx.deps.storage,
-      payable_payment_id,
-      &payable_payment,
-    )?;
-
-    /* ACTIVITIES DATA STRUCTURES */
-    // Create a new ActivityRecord ID from user's perspective.
-    let user_activity_id = self.create_id(
-      ctx.deps.storage,
-      &ctx.env,
-      &ctx.info.sender.clone().as_str(),
-      "activity",
-      user.activities_count,
-    )?;
-
-    // Create a new ActivityRecord ID from payable's perspective.
-    let payable_activity_id = self.create_id(
-      ctx.deps.storage,
-      &ctx.env,
-      &HexBinary::from(&payable_id).to_hex(),
-      "activity",
-      payable.activities_count,
-    )?;
-
-    // Save the User and Payable ActivityRecord IDs to chain_activity_ids.
-    let mut chain_activity_ids =
-      self.chain_activity_ids.load(ctx.deps.storage)?;
-    chain_activity_ids.push(user_activity_id);
-    chain_activity_ids.push(payable_activity_id);
-    self
-      .chain_activity_ids
-      .save(ctx.deps.storage, &chain_activity_ids)?;
-
-    // Save the User ActivityRecord ID to user_activity_ids.
-    let mut user_activity_ids = self
-      .user_activity_ids
-      .may_load(ctx.deps.storage, &ctx.info.sender)?
-      .unwrap_or_default();
-    user_activity_ids.push(user_activity_id);
-    self.user_activity_ids.save(
-      ctx.deps.storage,
       &ctx.info.sender,
-      &user_activity_ids,
-    )?;
-
-    // Save the Payable ActivityRecord ID to payable_activity_ids.
-    let mut payable_activity_ids = self
-      .payable_activity_ids
-      .load(ctx.deps.storage, payable_id)?;
-    payable_activity_ids.push(payable_activity_id);
-    self.payable_activity_ids.save(
-      ctx.deps.storage,
       payable_id,
-      &payable_activity_ids,
-    )?;
-
-    // Create and Save the ActivityRecord for the User.
-    self.activities.save(
-      ctx.deps.storage,
-      user_activity_id,
-      &ActivityRecord {
-        // subtracting 1 because we incremented the activities_count twice.
-        chain_count: chain_stats.activities_count.checked_sub(1).unwrap(),
-        user_count: user.activities_count,
-        payable_count: 0, // Setting 0 because it's not a payable activity.
-        timestamp: ctx.env.block.time.seconds(),
-        entity: HexBinary::from(&user_payment_id).to_hex(),
-        activity_type: ActivityType::UserPaid,
-      },
-    )?;
-
-    // Create and Save the ActivityRecord for the Payable.
-    self.activities.save(
-      ctx.deps.storage,
-      payable_activity_id,
-      &ActivityRecord {
-        chain_count: chain_stats.activities_count,
-        user_count: 0, // Setting 0 because it's not a user activity.
-        payable_count: payable.activities_count,
-        timestamp: ctx.env.block.time.seconds(),
-        entity: HexBinary::from(&payable_payment_id).to_hex(),
-        activity_type: ActivityType::PayableReceived,
-      },
+      TokenAndAmount { token, amount },
+      reference,
     )?;
 
-    /* FINISH */
-    // Return the Response.
+    // Return the Response with the cw20 messages, which must run before the
+    // payable's hook is notified.
     Ok(
       Response::new()
-        .add_messages(cw20_messages) // Add the cw20 messages
-        .add_attributes(user_resp_attrib) // Add the user init attributes
-        .add_attributes([
-          // Shared Details
-          ("payable_id", HexBinary::from(&payable_id).to_hex()),
-          ("payer_wallet", ctx.info.sender.to_string()),
-          // Details relative to the user
-          ("action", "user_paid".to_string()),
-          ("user_payment_id", HexBinary::from(&user_payment_id).to_hex()),
-          ("user_chain_count", chain_stats.user_payments_count.to_string()),
-          ("payable_chain_id", config.chain_id.to_string()),
-          ("payer_count", user.payments_count.to_string()),
-          // Details relative to the payable
-          ("action", "payable_received".to_string()),
-          ("payable_payment_id", HexBinary::from(&payable_payment_id).to_hex()),
-          ("payable_chain_count", chain_stats.payable_payments_count.to_string()),
-          ("payer_chain_id", config.chain_id.to_string()),
-          ("payable_count", payable.payments_count.to_string()),
-        ]),
+        .add_messages(cw20_messages)
+        .add_submessages(recorded.messages)
+        .add_attributes(recorded.attributes),
     )
   }
 }
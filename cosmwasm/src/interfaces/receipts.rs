@@ -0,0 +1,184 @@
+use crate::contract::Chainbills;
+use crate::error::ChainbillsError;
+use crate::messages::{
+  IdMessage, UpdatePayableReceiptsMessage, UpdateReceiptsCollectionMessage,
+};
+use crate::state::{ActivityType, PayableOperator, UserPayment};
+use sylvia::cw_std::{Addr, HexBinary, Response, StdError};
+use sylvia::interface;
+use sylvia::types::{ExecCtx, QueryCtx};
+
+#[interface]
+pub trait Receipts {
+  type Error: From<StdError>;
+
+  #[sv::msg(query)]
+  fn receipts_collection(
+    &self,
+    ctx: QueryCtx,
+  ) -> Result<Option<Addr>, Self::Error>;
+
+  #[sv::msg(exec)]
+  fn update_receipts_collection(
+    &self,
+    ctx: ExecCtx,
+    msg: UpdateReceiptsCollectionMessage,
+  ) -> Result<Response, Self::Error>;
+
+  #[sv::msg(query)]
+  fn payable_receipts(
+    &self,
+    ctx: QueryCtx,
+    msg: IdMessage,
+  ) -> Result<bool, Self::Error>;
+
+  #[sv::msg(exec)]
+  fn update_payable_receipts(
+    &self,
+    ctx: ExecCtx,
+    msg: UpdatePayableReceiptsMessage,
+  ) -> Result<Response, Self::Error>;
+
+  /// Resolves a receipt's token ID to the UserPayment that it proves.
+  #[sv::msg(query)]
+  fn receipt_payment(
+    &self,
+    ctx: QueryCtx,
+    msg: IdMessage,
+  ) -> Result<UserPayment, Self::Error>;
+}
+
+impl Receipts for Chainbills {
+  type Error = ChainbillsError;
+
+  fn receipts_collection(
+    &self,
+    ctx: QueryCtx,
+  ) -> Result<Option<Addr>, Self::Error> {
+    Ok(self.receipts_collection.may_load(ctx.deps.storage)?)
+  }
+
+  fn update_receipts_collection(
+    &self,
+    ctx: ExecCtx,
+    msg: UpdateReceiptsCollectionMessage,
+  ) -> Result<Response, Self::Error> {
+    /* CHECKS */
+    // Ensure the caller is the owner.
+    let config = self.config.load(ctx.deps.storage)?;
+    if ctx.info.sender != config.owner {
+      return Err(ChainbillsError::OwnerUnauthorized {});
+    }
+
+    // Ensure that the collection is a contract.
+    let collection = ctx.deps.api.addr_validate(&msg.collection)?;
+    ctx
+      .deps
+      .querier
+      .query_wasm_contract_info(&collection)
+      .map_err(|_| ChainbillsError::InvalidReceiptsCollection {})?;
+
+    /* STATE CHANGES */
+    self
+      .receipts_collection
+      .save(ctx.deps.storage, &collection)?;
+
+    // Return the Response.
+    Ok(Response::new().add_attributes([
+      ("action", "updated_receipts_collection".to_string()),
+      ("collection", collection.to_string()),
+    ]))
+  }
+
+  fn payable_receipts(
+    &self,
+    ctx: QueryCtx,
+    msg: IdMessage,
+  ) -> Result<bool, Self::Error> {
+    let payable_id =
+      <[u8; 32]>::try_from(HexBinary::from_hex(&msg.id)?.as_slice()).unwrap();
+    if !self.payables.has(ctx.deps.storage, payable_id) {
+      return Err(ChainbillsError::InvalidPayableId { id: msg.id });
+    }
+    Ok(self.payable_receipts.has(ctx.deps.storage, payable_id))
+  }
+
+  fn update_payable_receipts(
+    &self,
+    ctx: ExecCtx,
+    msg: UpdatePayableReceiptsMessage,
+  ) -> Result<Response, Self::Error> {
+    /* CHECKS */
+    // Ensure that the payable_id is valid.
+    let payable_id =
+      <[u8; 32]>::try_from(HexBinary::from_hex(&msg.payable_id)?.as_slice())
+        .unwrap();
+    if !self.payables.has(ctx.deps.storage, payable_id) {
+      return Err(ChainbillsError::InvalidPayableId { id: msg.payable_id });
+    }
+    let mut payable = self.payables.load(ctx.deps.storage, payable_id)?;
+
+    // Ensure that the caller owns the payable or can manage it.
+    self.ensure_host_or_operator(
+      ctx.deps.storage,
+      payable_id,
+      &payable,
+      &ctx.info.sender,
+      PayableOperator::ROLE_MANAGE,
+    )?;
+
+    // Ensure that receipts can be minted.
+    if msg.enabled && !self.receipts_collection.exists(ctx.deps.storage) {
+      return Err(ChainbillsError::ReceiptsCollectionNotSet {});
+    }
+
+    /* STATE CHANGES */
+    // Save the setting. Only payables that mint receipts are stored.
+    if msg.enabled {
+      self
+        .payable_receipts
+        .save(ctx.deps.storage, payable_id, &true)?;
+    } else {
+      self.payable_receipts.remove(ctx.deps.storage, payable_id);
+    }
+
+    // Increment the activity count on the payable and save it.
+    payable.activities_count = payable.next_activity();
+    self.payables.save(ctx.deps.storage, payable_id, &payable)?;
+
+    // Record the activity.
+    self.record_update_payable_activity(
+      ctx.deps.storage,
+      &ctx.env,
+      &payable.host,
+      payable_id,
+      payable.activities_count,
+      ActivityType::UpdatedPayableReceipts,
+    )?;
+
+    // Return the Response.
+    Ok(Response::new().add_attributes([
+      ("action", "updated_payable_receipts".to_string()),
+      ("payable_id", HexBinary::from(&payable_id).to_hex()),
+      ("host_wallet", payable.host.to_string()),
+      ("enabled", msg.enabled.to_string()),
+    ]))
+  }
+
+  fn receipt_payment(
+    &self,
+    ctx: QueryCtx,
+    msg: IdMessage,
+  ) -> Result<UserPayment, Self::Error> {
+    let user_payment_id = HexBinary::from_hex(&msg.id)
+      .ok()
+      .and_then(|id| <[u8; 32]>::try_from(id.as_slice()).ok())
+      .filter(|id| self.receipts.has(ctx.deps.storage, *id))
+      .ok_or(ChainbillsError::InvalidReceiptId { id: msg.id })?;
+    self
+      .may_load_user_payment(ctx.deps.storage, user_payment_id)?
+      .ok_or(ChainbillsError::InvalidPaymentId {
+        id: HexBinary::from(&user_payment_id).to_hex(),
+      })
+  }
+}
@@ -0,0 +1,425 @@
+use crate::contract::Chainbills;
+use crate::error::ChainbillsError;
+use crate::messages::{CreateSubscriptionMessage, SubscriptionMessage};
+use crate::state::{
+  ActivityType, PayableOperator, Subscription, TokenAndAmount,
+};
+use cw20::Cw20ExecuteMsg;
+use sylvia::cw_std::{
+  coins, to_json_binary, BankMsg, HexBinary, Response, StdError, Uint128,
+  WasmMsg,
+};
+use sylvia::interface;
+use sylvia::types::{ExecCtx, QueryCtx};
+
+#[interface]
+pub trait Subscriptions {
+  type Error: From<StdError>;
+
+  #[sv::msg(query)]
+  fn subscription(
+    &self,
+    ctx: QueryCtx,
+    msg: SubscriptionMessage,
+  ) -> Result<Subscription, Self::Error>;
+
+  #[sv::msg(exec)]
+  fn create_subscription(
+    &self,
+    ctx: ExecCtx,
+    msg: CreateSubscriptionMessage,
+  ) -> Result<Response, Self::Error>;
+
+  #[sv::msg(exec)]
+  fn fund_subscription(
+    &self,
+    ctx: ExecCtx,
+    msg: SubscriptionMessage,
+  ) -> Result<Response, Self::Error>;
+
+  #[sv::msg(exec)]
+  fn collect_subscription(
+    &self,
+    ctx: ExecCtx,
+    msg: SubscriptionMessage,
+  ) -> Result<Response, Self::Error>;
+
+  #[sv::msg(exec)]
+  fn cancel_subscription(
+    &self,
+    ctx: ExecCtx,
+    msg: SubscriptionMessage,
+  ) -> Result<Response, Self::Error>;
+}
+
+impl Subscriptions for Chainbills {
+  type Error = ChainbillsError;
+
+  fn subscription(
+    &self,
+    ctx: QueryCtx,
+    msg: SubscriptionMessage,
+  ) -> Result<Subscription, Self::Error> {
+    let payable_id =
+      <[u8; 32]>::try_from(HexBinary::from_hex(&msg.payable_id)?.as_slice())
+        .unwrap();
+    let payer = ctx.deps.api.addr_validate(&msg.payer)?;
+    match self
+      .subscriptions
+      .may_load(ctx.deps.storage, (payable_id.to_vec(), &payer))?
+    {
+      Some(subscription) => Ok(subscription),
+      None => Err(ChainbillsError::SubscriptionNotFound {}),
+    }
+  }
+
+  fn create_subscription(
+    &self,
+    ctx: ExecCtx,
+    msg: CreateSubscriptionMessage,
+  ) -> Result<Response, Self::Error> {
+    /* CHECKS */
+    // Ensure that the payable_id is valid.
+    let payable_id =
+      <[u8; 32]>::try_from(HexBinary::from_hex(&msg.payable_id)?.as_slice())
+        .unwrap();
+    if !self.payables.has(ctx.deps.storage, payable_id) {
+      return Err(ChainbillsError::InvalidPayableId { id: msg.payable_id });
+    }
+    let mut payable = self.payables.load(ctx.deps.storage, payable_id)?;
+
+    // Ensure that the payable is not closed.
+    if payable.is_closed {
+      return Err(ChainbillsError::PayableIsClosed {});
+    }
+
+    // Ensure that the payer is among the payable's allowed payers.
+    self.ensure_allowed_payer(
+      ctx.deps.storage,
+      ctx.deps.api,
+      payable_id,
+      &ctx.info.sender,
+      &msg.payer_proof,
+    )?;
+
+    // Ensure that the payable accepts the token and amount.
+    let CreateSubscriptionMessage {
+      token,
+      amount,
+      period,
+      first_due,
+      ..
+    } = msg;
+    let token_details =
+      self.check_payment(ctx.deps.storage, &payable, &token, amount)?;
+
+    // Ensure that payments are spaced in time.
+    if period == 0 {
+      return Err(ChainbillsError::InvalidSubscriptionPeriod {});
+    }
+
+    // Ensure that the first payment isn't backdated.
+    let now = ctx.env.block.time.seconds();
+    let first_due = first_due.unwrap_or(now);
+    if first_due < now {
+      return Err(ChainbillsError::SubscriptionFirstDueInPast { first_due });
+    }
+
+    // Ensure that the payer doesn't have an active subscription to the
+    // payable.
+    let key = (payable_id.to_vec(), &ctx.info.sender);
+    if let Some(existing) =
+      self.subscriptions.may_load(ctx.deps.storage, key.clone())?
+    {
+      if !existing.is_cancelled {
+        return Err(ChainbillsError::SubscriptionAlreadyExists {});
+      }
+    }
+
+    // Take any native deposit for upcoming payments. Cw20 payments are
+    // pulled from the payer's allowance instead.
+    let deposit = if token_details.is_native_token {
+      cw_utils::may_pay(&ctx.info, &token)?
+    } else {
+      cw_utils::nonpayable(&ctx.info)?;
+      Uint128::zero()
+    };
+
+    /* STATE CHANGES */
+    // Save the subscription.
+    let subscription = Subscription {
+      payer: ctx.info.sender.clone(),
+      payable_id,
+      token,
+      amount,
+      period,
+      next_due: first_due,
+      payments_count: 0,
+      deposit,
+      is_cancelled: false,
+    };
+    self
+      .subscriptions
+      .save(ctx.deps.storage, key, &subscription)?;
+
+    // Increment the activity count on the payable and save it.
+    let user_resp_attrib = self.initialize_user_if_is_new(
+      ctx.deps.storage,
+      &ctx.env,
+      &ctx.info.sender,
+    )?;
+    payable.activities_count = payable.next_activity();
+    self.payables.save(ctx.deps.storage, payable_id, &payable)?;
+
+    // Record the activity.
+    self.record_update_payable_activity(
+      ctx.deps.storage,
+      &ctx.env,
+      &ctx.info.sender,
+      payable_id,
+      payable.activities_count,
+      ActivityType::CreatedSubscription,
+    )?;
+
+    // Return the Response.
+    Ok(
+      Response::new()
+        .add_attributes(user_resp_attrib)
+        .add_attributes([
+          ("action", "created_subscription".to_string()),
+          ("payable_id", HexBinary::from(&payable_id).to_hex()),
+          ("payer_wallet", ctx.info.sender.to_string()),
+          ("token", subscription.token),
+          ("amount", subscription.amount.to_string()),
+          ("period", subscription.period.to_string()),
+          ("next_due", subscription.next_due.to_string()),
+          ("deposit", subscription.deposit.to_string()),
+        ]),
+    )
+  }
+
+  fn fund_subscription(
+    &self,
+    ctx: ExecCtx,
+    msg: SubscriptionMessage,
+  ) -> Result<Response, Self::Error> {
+    /* CHECKS */
+    let payable_id =
+      <[u8; 32]>::try_from(HexBinary::from_hex(&msg.payable_id)?.as_slice())
+        .unwrap();
+    let payer = ctx.deps.api.addr_validate(&msg.payer)?;
+    let key = (payable_id.to_vec(), &payer);
+    let Some(mut subscription) =
+      self.subscriptions.may_load(ctx.deps.storage, key.clone())?
+    else {
+      return Err(ChainbillsError::SubscriptionNotFound {});
+    };
+
+    // Ensure that the subscription is still active.
+    if subscription.is_cancelled {
+      return Err(ChainbillsError::SubscriptionIsCancelled {});
+    }
+
+    // Ensure that the subscription's native token was sent. Deposits for
+    // cw20 subscriptions aren't supported as they use allowances.
+    let is_native_token = self
+      .token_details
+      .load(ctx.deps.storage, subscription.token.clone())?
+      .is_native_token;
+    if !is_native_token {
+      return Err(ChainbillsError::InvalidNativeTokenPayment {});
+    }
+    let amount = cw_utils::must_pay(&ctx.info, &subscription.token)?;
+
+    /* STATE CHANGES */
+    subscription.deposit = subscription.deposit.checked_add(amount).unwrap();
+    self
+      .subscriptions
+      .save(ctx.deps.storage, key, &subscription)?;
+
+    // Return the Response.
+    Ok(Response::new().add_attributes([
+      ("action", "funded_subscription".to_string()),
+      ("payable_id", HexBinary::from(&payable_id).to_hex()),
+      ("payer_wallet", payer.to_string()),
+      ("deposit", subscription.deposit.to_string()),
+    ]))
+  }
+
+  fn collect_subscription(
+    &self,
+    ctx: ExecCtx,
+    msg: SubscriptionMessage,
+  ) -> Result<Response, Self::Error> {
+    /* CHECKS */
+    let payable_id =
+      <[u8; 32]>::try_from(HexBinary::from_hex(&msg.payable_id)?.as_slice())
+        .unwrap();
+    let payer = ctx.deps.api.addr_validate(&msg.payer)?;
+    let key = (payable_id.to_vec(), &payer);
+    let Some(mut subscription) =
+      self.subscriptions.may_load(ctx.deps.storage, key.clone())?
+    else {
+      return Err(ChainbillsError::SubscriptionNotFound {});
+    };
+
+    // Ensure that the subscription is active and due.
+    if subscription.is_cancelled {
+      return Err(ChainbillsError::SubscriptionIsCancelled {});
+    }
+    if ctx.env.block.time.seconds() < subscription.next_due {
+      return Err(ChainbillsError::SubscriptionNotDue {
+        next_due: subscription.next_due,
+      });
+    }
+
+    // Ensure that the payable still accepts the payment.
+    let payable = self.payables.load(ctx.deps.storage, payable_id)?;
+    if payable.is_closed {
+      return Err(ChainbillsError::PayableIsClosed {});
+    }
+    let token_details = self.check_payment(
+      ctx.deps.storage,
+      &payable,
+      &subscription.token,
+      subscription.amount,
+    )?;
+
+    /* FUNDS TRANSFER */
+    let mut cw20_messages = vec![];
+    if token_details.is_native_token {
+      // Pay from the payer's deposit.
+      if subscription.deposit < subscription.amount {
+        return Err(ChainbillsError::InsufficientSubscriptionDeposit {});
+      }
+      subscription.deposit -= subscription.amount;
+    } else {
+      // Pull the payment from the payer's allowance to this contract.
+      cw20_messages.push(WasmMsg::Execute {
+        contract_addr: subscription.token.clone(),
+        funds: vec![],
+        msg: to_json_binary(&Cw20ExecuteMsg::TransferFrom {
+          owner: payer.to_string(),
+          recipient: ctx.env.contract.address.to_string(),
+          amount: subscription.amount,
+        })?,
+      });
+    }
+
+    /* STATE CHANGES */
+    // Move the subscription to its next future period. Missed periods are
+    // skipped, so one payment is collected however late the collection is.
+    subscription.next_due =
+      subscription.next_due_after(ctx.env.block.time.seconds());
+    subscription.payments_count =
+      subscription.payments_count.checked_add(1).unwrap();
+    self
+      .subscriptions
+      .save(ctx.deps.storage, key, &subscription)?;
+
+    // Record the payment as any other.
+    let recorded = self.record_payment(
+      ctx.deps,
+      &ctx.env,
+      &payer,
+      payable_id,
+      TokenAndAmount {
+        token: subscription.token,
+        amount: subscription.amount,
+      },
+      None,
+    )?;
+
+    // Return the Response with the cw20 messages, which must run before the
+    // payable's hook is notified.
+    let response = Response::new()
+      .add_messages(cw20_messages)
+      .add_submessages(recorded.messages)
+      .add_attributes(recorded.attributes);
+    Ok(response.add_attributes([
+      ("action", "collected_subscription".to_string()),
+      ("collector", ctx.info.sender.to_string()),
+      (
+        "subscription_count",
+        subscription.payments_count.to_string(),
+      ),
+      ("next_due", subscription.next_due.to_string()),
+    ]))
+  }
+
+  fn cancel_subscription(
+    &self,
+    ctx: ExecCtx,
+    msg: SubscriptionMessage,
+  ) -> Result<Response, Self::Error> {
+    /* CHECKS */
+    let payable_id =
+      <[u8; 32]>::try_from(HexBinary::from_hex(&msg.payable_id)?.as_slice())
+        .unwrap();
+    let payer = ctx.deps.api.addr_validate(&msg.payer)?;
+    let key = (payable_id.to_vec(), &payer);
+    let Some(mut subscription) =
+      self.subscriptions.may_load(ctx.deps.storage, key.clone())?
+    else {
+      return Err(ChainbillsError::SubscriptionNotFound {});
+    };
+
+    // Ensure that the subscription is still active.
+    if subscription.is_cancelled {
+      return Err(ChainbillsError::SubscriptionIsCancelled {});
+    }
+
+    // Ensure that the caller is the payer, or the payable's host or one of
+    // its managers.
+    let mut payable = self.payables.load(ctx.deps.storage, payable_id)?;
+    if ctx.info.sender != payer {
+      self
+        .ensure_host_or_operator(
+          ctx.deps.storage,
+          payable_id,
+          &payable,
+          &ctx.info.sender,
+          PayableOperator::ROLE_MANAGE,
+        )
+        .map_err(|_| ChainbillsError::NotYourSubscription {})?;
+    }
+
+    /* STATE CHANGES */
+    // Cancel the subscription and refund its deposit.
+    let refund = subscription.deposit;
+    subscription.is_cancelled = true;
+    subscription.deposit = Uint128::zero();
+    self
+      .subscriptions
+      .save(ctx.deps.storage, key, &subscription)?;
+    let mut bank_messages = vec![];
+    if !refund.is_zero() {
+      bank_messages.push(BankMsg::Send {
+        to_address: payer.to_string(),
+        amount: coins(refund.u128(), subscription.token.clone()),
+      });
+    }
+
+    // Increment the activity count on the payable and save it.
+    payable.activities_count = payable.next_activity();
+    self.payables.save(ctx.deps.storage, payable_id, &payable)?;
+
+    // Record the activity for the payer.
+    self.record_update_payable_activity(
+      ctx.deps.storage,
+      &ctx.env,
+      &payer,
+      payable_id,
+      payable.activities_count,
+      ActivityType::CancelledSubscription,
+    )?;
+
+    // Return the Response.
+    Ok(Response::new().add_messages(bank_messages).add_attributes([
+      ("action", "cancelled_subscription".to_string()),
+      ("payable_id", HexBinary::from(&payable_id).to_hex()),
+      ("payer_wallet", payer.to_string()),
+      ("refund", refund.to_string()),
+    ]))
+  }
+}
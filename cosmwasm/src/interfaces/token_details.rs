@@ -1,7 +1,9 @@
 use crate::contract::Chainbills;
 use crate::error::ChainbillsError;
-use crate::messages::{IdMessage, UpdateMaxWithdrawalFeesMessage};
-use crate::state::TokenDetails;
+use crate::messages::{
+  IdMessage, UpdateMaxWithdrawalFeesMessage, UpdateTokenGroupMessage,
+};
+use crate::state::{TokenDetails, TokenGroup};
 use sylvia::cw_std::{Response, StdError};
 use sylvia::interface;
 use sylvia::types::{ExecCtx, QueryCtx};
@@ -23,6 +25,20 @@ pub trait TokenDetailsInterface {
     ctx: ExecCtx,
     msg: UpdateMaxWithdrawalFeesMessage,
   ) -> Result<Response, Self::Error>;
+
+  #[sv::msg(query)]
+  fn token_group(
+    &self,
+    ctx: QueryCtx,
+    msg: IdMessage,
+  ) -> Result<TokenGroup, Self::Error>;
+
+  #[sv::msg(exec)]
+  fn update_token_group(
+    &self,
+    ctx: ExecCtx,
+    msg: UpdateTokenGroupMessage,
+  ) -> Result<Response, Self::Error>;
 }
 
 impl TokenDetailsInterface for Chainbills {
@@ -97,4 +113,73 @@ impl TokenDetailsInterface for Chainbills {
       ("max_withdrawal_fees", max_withdrawal_fees.to_string()),
     ]))
   }
+
+  fn token_group(
+    &self,
+    ctx: QueryCtx,
+    msg: IdMessage,
+  ) -> Result<TokenGroup, Self::Error> {
+    self
+      .token_groups
+      .may_load(ctx.deps.storage, msg.id.clone())?
+      .ok_or(ChainbillsError::InvalidTokenGroup { name: msg.id })
+  }
+
+  fn update_token_group(
+    &self,
+    ctx: ExecCtx,
+    msg: UpdateTokenGroupMessage,
+  ) -> Result<Response, Self::Error> {
+    // Only the owner can manage token groups.
+    let owner = self.config.load(ctx.deps.storage)?.owner;
+    if ctx.info.sender != owner {
+      return Err(ChainbillsError::OwnerUnauthorized {});
+    }
+
+    let UpdateTokenGroupMessage {
+      name,
+      decimals,
+      members,
+    } = msg;
+
+    // Ensure that the name is set and that the members are few enough and
+    // distinct.
+    if name.is_empty()
+      || members.len() > TokenGroup::MAX_MEMBERS
+      || members
+        .iter()
+        .enumerate()
+        .any(|(i, m)| members[..i].iter().any(|o| o.token == m.token))
+    {
+      return Err(ChainbillsError::InvalidTokenGroup { name });
+    }
+
+    // Ensure that all members are known tokens.
+    for member in members.iter() {
+      if !self
+        .token_details
+        .has(ctx.deps.storage, member.token.clone())
+      {
+        return Err(ChainbillsError::InvalidToken {
+          token: member.token.clone(),
+        });
+      }
+    }
+
+    // Save the group.
+    let members_count = members.len();
+    self.token_groups.save(
+      ctx.deps.storage,
+      name.clone(),
+      &TokenGroup { decimals, members },
+    )?;
+
+    // Return the Response.
+    Ok(Response::new().add_attributes([
+      ("action", "updated_token_group".to_string()),
+      ("name", name),
+      ("decimals", decimals.to_string()),
+      ("members_count", members_count.to_string()),
+    ]))
+  }
 }
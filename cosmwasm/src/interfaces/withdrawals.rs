@@ -1,16 +1,18 @@
 use crate::contract::Chainbills;
 use crate::error::ChainbillsError;
 use crate::messages::{
-  CountMessage, FetchIdMessage, IdMessage, TransactionInfoMessage,
+  Asset, AssetInfo, CountMessage, FetchIdMessage, IdMessage,
+  TokenBridgeMessage, TransactionInfoMessage, WithdrawCrossChainMessage,
 };
 use crate::state::{
-  ActivityRecord, ActivityType, TokenAndAmount, TokenDetails, User, Withdrawal,
+  ActivityRecord, ActivityType, PayableOperator, TokenAndAmount, TokenDetails,
+  User, Withdrawal,
 };
 use cw20::Cw20ExecuteMsg;
 use std::cmp::min;
 use sylvia::cw_std::{
-  to_json_binary, BankMsg, Coin, HexBinary, Response, StdError, Uint128,
-  WasmMsg,
+  coins, to_json_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, HexBinary,
+  Response, StdError, Uint128, WasmMsg,
 };
 use sylvia::interface;
 use sylvia::types::{ExecCtx, QueryCtx};
@@ -52,6 +54,13 @@ pub trait Withdrawals {
     ctx: ExecCtx,
     data: TransactionInfoMessage,
   ) -> Result<Response, Self::Error>;
+
+  #[sv::msg(exec)]
+  fn withdraw_cross_chain(
+    &self,
+    ctx: ExecCtx,
+    data: WithdrawCrossChainMessage,
+  ) -> Result<Response, Self::Error>;
 }
 
 impl Withdrawals for Chainbills {
@@ -145,9 +154,267 @@ impl Withdrawals for Chainbills {
 
   fn withdraw(
     &self,
-    ctx: ExecCtx,
+    mut ctx: ExecCtx,
     msg: TransactionInfoMessage,
   ) -> Result<Response, Self::Error> {
+    // The host receives the funds in their wallet on this chain, even when
+    // an operator triggers the withdrawal.
+    let config = self.config.load(ctx.deps.storage)?;
+    let token = msg.token.clone();
+    let RecordedWithdrawal {
+      payable_id,
+      withdrawal_id,
+      host,
+      is_native_token,
+      amount_due,
+      fees,
+      chain_count,
+      host_count,
+      payable_count,
+    } = self.record_withdrawal(&mut ctx, msg, None)?;
+
+    /* FUNDS TRANSFER */
+    // Prepare messages for transfer to add to the response.
+    let mut bank_messages = vec![];
+    let mut cw20_messages = vec![];
+    if is_native_token {
+      // Transfer the amount to the host.
+      bank_messages.push(BankMsg::Send {
+        to_address: host.to_string(),
+        amount: vec![Coin {
+          denom: token.clone(),
+          amount: amount_due,
+        }],
+      });
+      // Transfer the withdrawal fee to the fee collector.
+      bank_messages.push(BankMsg::Send {
+        to_address: config.chainbills_fee_collector.to_string(),
+        amount: vec![Coin {
+          denom: token.clone(),
+          amount: fees,
+        }],
+      });
+    } else {
+      cw20_messages.push(WasmMsg::Execute {
+        contract_addr: token.clone(),
+        funds: vec![],
+        msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+          recipient: host.to_string(),
+          amount: amount_due,
+        })?,
+      });
+      cw20_messages.push(WasmMsg::Execute {
+        contract_addr: token.clone(),
+        funds: vec![],
+        msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+          recipient: config.chainbills_fee_collector.to_string(),
+          amount: fees,
+        })?,
+      });
+    }
+
+    /* FINISH */
+    // Return the Response.
+    Ok(
+      Response::new()
+      .add_messages(bank_messages) // Add the bank messages
+      .add_messages(cw20_messages)// Add the cw20 messages
+      .add_attributes([
+        ("action", "withdrew".to_string()),
+        ("payable_id", HexBinary::from(&payable_id).to_hex()),
+        ("host_wallet", host.to_string()),
+        ("withdrawal_id", HexBinary::from(&withdrawal_id).to_hex()),
+        ("chain_count", chain_count.to_string()),
+        ("host_count", host_count.to_string()),
+        ("payable_count", payable_count.to_string()),
+      ]),
+    )
+  }
+
+  fn withdraw_cross_chain(
+    &self,
+    mut ctx: ExecCtx,
+    msg: WithdrawCrossChainMessage,
+  ) -> Result<Response, Self::Error> {
+    /* CHECKS */
+    // Ensure that cross-chain withdrawals are enabled.
+    let config = self.config.load(ctx.deps.storage)?;
+    let token_bridge = match config.token_bridge {
+      Some(token_bridge) => token_bridge,
+      None => return Err(ChainbillsError::TokenBridgeNotSet {}),
+    };
+
+    // Ensure that the destination is another chain that the Token Bridge
+    // can send to.
+    let dest_chain_id =
+      self.resolve_cb_chain_id(ctx.deps.storage, &msg.dest_chain)?;
+    let recipient_chain = match self
+      .cb_chains
+      .may_load(ctx.deps.storage, dest_chain_id)?
+      .and_then(|chain| chain.wormhole_chain_id)
+    {
+      Some(id) if dest_chain_id != config.cb_chain_id => id,
+      _ => {
+        return Err(ChainbillsError::InvalidDestinationChain {
+          chain: format!("{:?}", msg.dest_chain),
+        })
+      }
+    };
+
+    // Ensure that the destination address is a non-zero 32-byte address.
+    let dest_address = match <[u8; 32]>::try_from(
+      HexBinary::from_hex(&msg.dest_address)?.as_slice(),
+    ) {
+      Ok(address) if address != [0u8; 32] => address,
+      _ => {
+        return Err(ChainbillsError::InvalidDestinationAddress {
+          address: msg.dest_address,
+        })
+      }
+    };
+
+    let WithdrawCrossChainMessage {
+      payable_id,
+      token,
+      amount,
+      ..
+    } = msg;
+    let RecordedWithdrawal {
+      payable_id,
+      withdrawal_id,
+      host,
+      is_native_token,
+      amount_due,
+      fees,
+      chain_count,
+      host_count,
+      payable_count,
+    } = self.record_withdrawal(
+      &mut ctx,
+      TransactionInfoMessage {
+        payable_id,
+        token: token.clone(),
+        amount,
+      },
+      Some((dest_chain_id, dest_address)),
+    )?;
+
+    /* FUNDS TRANSFER */
+    // Transfer the withdrawal fee to the fee collector and hand the amount
+    // due to the Token Bridge. The withdrawal's chain_count is the nonce.
+    let nonce = chain_count as u32;
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let info = if is_native_token {
+      messages.push(
+        BankMsg::Send {
+          to_address: config.chainbills_fee_collector.to_string(),
+          amount: coins(fees.u128(), token.clone()),
+        }
+        .into(),
+      );
+      messages.push(
+        WasmMsg::Execute {
+          contract_addr: token_bridge.to_string(),
+          funds: coins(amount_due.u128(), token.clone()),
+          msg: to_json_binary(&TokenBridgeMessage::DepositTokens {})?,
+        }
+        .into(),
+      );
+      AssetInfo::NativeToken {
+        denom: token.clone(),
+      }
+    } else {
+      messages.push(
+        WasmMsg::Execute {
+          contract_addr: token.clone(),
+          funds: vec![],
+          msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+            recipient: config.chainbills_fee_collector.to_string(),
+            amount: fees,
+          })?,
+        }
+        .into(),
+      );
+      messages.push(
+        WasmMsg::Execute {
+          contract_addr: token.clone(),
+          funds: vec![],
+          msg: to_json_binary(&Cw20ExecuteMsg::IncreaseAllowance {
+            spender: token_bridge.to_string(),
+            amount: amount_due,
+            expires: None,
+          })?,
+        }
+        .into(),
+      );
+      AssetInfo::Token {
+        contract_addr: token.clone(),
+      }
+    };
+    messages.push(
+      WasmMsg::Execute {
+        contract_addr: token_bridge.to_string(),
+        funds: vec![],
+        msg: to_json_binary(&TokenBridgeMessage::InitiateTransfer {
+          asset: Asset {
+            info,
+            amount: amount_due,
+          },
+          recipient_chain,
+          recipient: Binary::from(dest_address),
+          fee: Uint128::zero(),
+          nonce,
+        })?,
+      }
+      .into(),
+    );
+
+    /* FINISH */
+    // Return the Response. The relayer completes the withdrawal by redeeming
+    // the Token Bridge transfer with this nonce on the destination chain.
+    Ok(Response::new().add_messages(messages).add_attributes([
+      ("action", "withdrew_cross_chain".to_string()),
+      ("payable_id", HexBinary::from(&payable_id).to_hex()),
+      ("host_wallet", host.to_string()),
+      ("withdrawal_id", HexBinary::from(&withdrawal_id).to_hex()),
+      ("chain_count", chain_count.to_string()),
+      ("host_count", host_count.to_string()),
+      ("payable_count", payable_count.to_string()),
+      ("dest_chain_id", HexBinary::from(&dest_chain_id).to_hex()),
+      ("dest_address", HexBinary::from(&dest_address).to_hex()),
+      ("recipient_chain", recipient_chain.to_string()),
+      ("amount_due", amount_due.to_string()),
+      ("nonce", nonce.to_string()),
+    ]))
+  }
+}
+
+/// What a recorded withdrawal needs for its funds to be transferred.
+struct RecordedWithdrawal {
+  payable_id: [u8; 32],
+  withdrawal_id: [u8; 32],
+  host: Addr,
+  is_native_token: bool,
+  amount_due: Uint128,
+  fees: Uint128,
+  chain_count: u64,
+  host_count: u64,
+  payable_count: u64,
+}
+
+impl Chainbills {
+  /// Validates a withdrawal, computes its fees, and records it along with
+  /// where its funds are sent. The caller transfers the funds.
+  ///
+  /// `dest` is the cbChainId and address to which the host receives the
+  /// funds on another chain. When it is None, the host receives them in
+  /// their wallet on this chain.
+  fn record_withdrawal(
+    &self,
+    ctx: &mut ExecCtx,
+    msg: TransactionInfoMessage,
+    dest: Option<([u8; 32], [u8; 32])>,
+  ) -> Result<RecordedWithdrawal, ChainbillsError> {
     /* CHECKS */
     // Ensure that the payable_id is valid.
     let payable_id =
@@ -158,10 +425,29 @@ impl Withdrawals for Chainbills {
     }
     let mut payable = self.payables.load(ctx.deps.storage, payable_id)?;
 
-    // Ensure that the caller owns the payable.
-    if payable.host != &ctx.info.sender {
-      return Err(ChainbillsError::NotYourPayable {});
-    }
+    // Ensure that the caller owns the payable. Operators with the withdraw
+    // role can also withdraw, but only to the host's wallet on this chain.
+    let config = self.config.load(ctx.deps.storage)?;
+    let (dest_chain_id, dest_address) = match dest {
+      Some(dest) => {
+        if payable.host != ctx.info.sender {
+          return Err(ChainbillsError::NotYourPayable {});
+        }
+        dest
+      }
+      None => {
+        self.ensure_host_or_operator(
+          ctx.deps.storage,
+          payable_id,
+          &payable,
+          &ctx.info.sender,
+          PayableOperator::ROLE_WITHDRAW,
+        )?;
+        let host_address = self.address_to_bytes32(&payable.host, ctx.deps.api);
+        (config.cb_chain_id, host_address)
+      }
+    };
+    let host = payable.host.clone();
 
     // Extract the token and amount for the withdrawal.
     let TransactionInfoMessage { token, amount, .. } = msg;
@@ -191,9 +477,8 @@ impl Withdrawals for Chainbills {
       }
     }
 
-    /* FUNDS TRANSFER */
+    /* FEES */
     // Prepare withdraw amounts and fees
-    let config = self.config.load(ctx.deps.storage)?;
     let percent = amount
       .checked_mul(config.withdrawal_fee_percentage)
       .unwrap()
@@ -209,45 +494,6 @@ impl Withdrawals for Chainbills {
     let fees = min(percent, max_withdrawal_fees);
     let amount_due = amount.checked_sub(fees).unwrap();
 
-    // Prepare messages for transfer to add to the response.
-    let mut bank_messages = vec![];
-    let mut cw20_messages = vec![];
-    if is_native_token {
-      // Transfer the amount to the host.
-      bank_messages.push(BankMsg::Send {
-        to_address: ctx.info.sender.to_string(),
-        amount: vec![Coin {
-          denom: token.clone(),
-          amount: amount_due,
-        }],
-      });
-      // Transfer the withdrawal fee to the fee collector.
-      bank_messages.push(BankMsg::Send {
-        to_address: config.chainbills_fee_collector.to_string(),
-        amount: vec![Coin {
-          denom: token.clone(),
-          amount: fees,
-        }],
-      });
-    } else {
-      cw20_messages.push(WasmMsg::Execute {
-        contract_addr: token.clone(),
-        funds: vec![],
-        msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
-          recipient: ctx.info.sender.to_string(),
-          amount: amount_due,
-        })?,
-      });
-      cw20_messages.push(WasmMsg::Execute {
-        contract_addr: token.clone(),
-        funds: vec![],
-        msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
-          recipient: config.chainbills_fee_collector.to_string(),
-          amount: fees,
-        })?,
-      });
-    }
-
     /* STATE CHANGES */
     /* COUNTS */
     // Increment the chain stats for counts of withdrawals.
@@ -256,12 +502,12 @@ impl Withdrawals for Chainbills {
     chain_stats.activities_count = chain_stats.next_activity();
     self.chain_stats.save(ctx.deps.storage, &chain_stats)?;
 
-    // Increment withdrawals and activities count in the host(address) that
-    // just withdrew.
-    let mut user = self.users.load(ctx.deps.storage, &ctx.info.sender)?;
+    // Increment withdrawals and activities count in the host(address) of
+    // the payable.
+    let mut user = self.users.load(ctx.deps.storage, &host)?;
     user.withdrawals_count = user.next_withdrawal();
     user.activities_count = user.next_activity();
-    self.users.save(ctx.deps.storage, &ctx.info.sender, &user)?;
+    self.users.save(ctx.deps.storage, &host, &user)?;
 
     // Increment withdrawals_count and activities_count on the payable.
     // Also deduct balances on the involved payable.
@@ -287,7 +533,7 @@ impl Withdrawals for Chainbills {
     let withdrawal_id = self.create_id(
       ctx.deps.storage,
       &ctx.env,
-      &ctx.info.sender.as_str(),
+      &host.as_str(),
       "withdrawal",
       user.withdrawals_count,
     )?;
@@ -303,12 +549,12 @@ impl Withdrawals for Chainbills {
     // Save the Withdrawal ID to the users_withdrawal_ids.
     let mut user_withdrawal_ids = self
       .user_withdrawal_ids
-      .may_load(ctx.deps.storage, &ctx.info.sender)?
+      .may_load(ctx.deps.storage, &host)?
       .unwrap_or_default();
     user_withdrawal_ids.push(withdrawal_id);
     self.user_withdrawal_ids.save(
       ctx.deps.storage,
-      &ctx.info.sender,
+      &host,
       &user_withdrawal_ids,
     )?;
 
@@ -327,7 +573,7 @@ impl Withdrawals for Chainbills {
     // Create and Save the Withdrawal.
     let withdrawal = Withdrawal {
       payable_id,
-      host: ctx.info.sender.clone(),
+      host: host.clone(),
       chain_count: chain_stats.withdrawals_count,
       host_count: user.withdrawals_count,
       payable_count: payable.withdrawals_count,
@@ -336,6 +582,8 @@ impl Withdrawals for Chainbills {
         amount,
         token: token.clone(),
       },
+      dest_chain_id,
+      dest_address,
     };
     self
       .withdrawals
@@ -346,7 +594,7 @@ impl Withdrawals for Chainbills {
     let activity_id = self.create_id(
       ctx.deps.storage,
       &ctx.env,
-      &ctx.info.sender.clone().as_str(),
+      &host.as_str(),
       "activity",
       user.activities_count,
     )?;
@@ -354,7 +602,7 @@ impl Withdrawals for Chainbills {
     // Save the ActivityRecord ID to involved entities.
     self.save_activity_id_for_all(
       ctx.deps.storage,
-      &ctx.info.sender,
+      &host,
       payable_id,
       activity_id,
     )?;
@@ -373,21 +621,16 @@ impl Withdrawals for Chainbills {
       },
     )?;
 
-    /* FINISH */
-    // Return the Response.
-    Ok(
-      Response::new()
-      .add_messages(bank_messages) // Add the bank messages
-      .add_messages(cw20_messages)// Add the cw20 messages
-      .add_attributes([
-        ("action", "withdrew".to_string()),
-        ("payable_id", HexBinary::from(&payable_id).to_hex()),
-        ("host_wallet", ctx.info.sender.to_string()),
-        ("withdrawal_id", HexBinary::from(&withdrawal_id).to_hex()),
-        ("chain_count", chain_stats.withdrawals_count.to_string()),
-        ("host_count", user.withdrawals_count.to_string()),
-        ("payable_count", payable.withdrawals_count.to_string()),
-      ]),
-    )
+    Ok(RecordedWithdrawal {
+      payable_id,
+      withdrawal_id,
+      host,
+      is_native_token,
+      amount_due,
+      fees,
+      chain_count: chain_stats.withdrawals_count,
+      host_count: user.withdrawals_count,
+      payable_count: payable.withdrawals_count,
+    })
   }
 }
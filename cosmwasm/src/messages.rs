@@ -1,13 +1,39 @@
-use crate::state::TokenAndAmount;
+use crate::state::{TokenAndAmount, TokenGroupMember};
 use sylvia::cw_schema::cw_serde;
-use sylvia::cw_std::{Addr, Uint128};
+use sylvia::cw_std::{Addr, Binary, Empty, Uint128};
 
 #[cw_serde(crate = "sylvia::cw_schema")]
 pub struct InstantiateMessage {
+  /// Wormhole Chain ID of this chain.
   pub chain_id: u16,
+  /// CAIP-2 identifier of this chain. Its keccak256 is this chain's
+  /// cbChainId.
+  pub caip2: String,
   pub chainbills_fee_collector: String,
 }
 
+#[cw_serde(crate = "sylvia::cw_schema")]
+pub struct MigrateMessage {
+  /// CAIP-2 identifier of this chain. Its keccak256 is this chain's
+  /// cbChainId.
+  pub caip2: String,
+  /// The other chains whose Wormhole Chain IDs are in stored payments. They
+  /// are registered so that the payments can be keyed by their cbChainIds.
+  pub foreign_chains: Vec<MigrateChainMessage>,
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+pub struct ContinueMigrationMessage {
+  /// The maximum number of stored entries to check.
+  pub limit: u64,
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+pub struct MigrateChainMessage {
+  pub caip2: String,
+  pub wormhole_chain_id: u16,
+}
+
 #[cw_serde(crate = "sylvia::cw_schema")]
 pub struct AddressMessage {
   pub address: Addr,
@@ -20,6 +46,24 @@ pub struct UpdateMaxWithdrawalFeesMessage {
   pub max_withdrawal_fees: Uint128,
 }
 
+#[cw_serde(crate = "sylvia::cw_schema")]
+pub struct UpdateTokenGroupMessage {
+  /// The group's name. Allowed tokens reference it as "group:<name>".
+  pub name: String,
+  /// The decimals of amounts in allowed tokens that reference the group.
+  pub decimals: u8,
+  pub members: Vec<TokenGroupMember>,
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+pub struct TokenIntegrityPageMessage {
+  pub token: String,
+  /// The number of payables created on this chain to skip.
+  pub offset: u64,
+  /// The maximum number of payables to check.
+  pub limit: u64,
+}
+
 #[cw_serde(crate = "sylvia::cw_schema")]
 pub struct CreatePayableMessage {
   pub allowed_tokens_and_amounts: Vec<TokenAndAmount>,
@@ -36,6 +80,46 @@ pub struct UpdatePayableTokensAndAmountsMessage {
   pub allowed_tokens_and_amounts: Vec<TokenAndAmount>,
 }
 
+#[cw_serde(crate = "sylvia::cw_schema")]
+pub struct TransferPayableMessage {
+  pub payable_id: String,
+  /// The wallet that can accept the payable.
+  pub new_host: String,
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+pub struct SetPayableOperatorMessage {
+  pub payable_id: String,
+  pub operator: String,
+  /// Bit flags of the PayableOperator roles to grant.
+  pub roles: u8,
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+pub struct PayableOperatorMessage {
+  pub payable_id: String,
+  pub operator: String,
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+pub struct UpdatePayableAllowedPayersMessage {
+  pub payable_id: String,
+  /// The hex-encoded Wormhole-normalized (32 bytes) addresses of the payers
+  /// that can pay into the payable. Empty with a zero root opens the payable
+  /// to all payers.
+  pub allowed_payers: Vec<String>,
+  /// The hex-encoded Merkle root of a list of payers too long to be passed.
+  /// Only used if allowed_payers is empty.
+  pub allowed_payers_root: Option<String>,
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+pub struct AllowedPayersRootMessage {
+  /// The hex-encoded Merkle root of the payable's allowed payers. Zero if
+  /// anyone can pay.
+  pub allowed_payers_root: String,
+}
+
 #[cw_serde(crate = "sylvia::cw_schema")]
 pub struct FetchIdMessage {
   pub reference: String,
@@ -45,7 +129,7 @@ pub struct FetchIdMessage {
 #[cw_serde(crate = "sylvia::cw_schema")]
 pub struct PerChainPayablePaymentsCountMessage {
   pub payable_id: String,
-  pub chain_id: u16,
+  pub chain: ChainIdentifier,
 }
 
 #[cw_serde(crate = "sylvia::cw_schema")]
@@ -56,7 +140,7 @@ pub struct CountMessage {
 #[cw_serde(crate = "sylvia::cw_schema")]
 pub struct PerChainPayablePaymentIdMessage {
   pub payable_id: String,
-  pub chain_id: u16,
+  pub chain: ChainIdentifier,
   pub count: u64,
 }
 
@@ -66,3 +150,223 @@ pub struct TransactionInfoMessage {
   pub token: String,
   pub amount: Uint128,
 }
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+pub struct PayBatchItemMessage {
+  pub payable_id: String,
+  pub token: String,
+  pub amount: Uint128,
+  /// The hex-encoded sibling hashes proving that the payer is in the
+  /// payable's allowed payers. Empty if the payable doesn't restrict its
+  /// payers.
+  pub payer_proof: Vec<String>,
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+pub struct PayBatchMessage {
+  /// The payments to make. Native payments across them are paid with the
+  /// funds sent, which must match their totals per denomination.
+  pub payments: Vec<PayBatchItemMessage>,
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+pub struct PayWithProofMessage {
+  pub payable_id: String,
+  pub token: String,
+  pub amount: Uint128,
+  /// The hex-encoded sibling hashes proving that the payer is in the
+  /// payable's allowed payers.
+  pub payer_proof: Vec<String>,
+  /// Any reference of the payer for this payment, like an order ID. Passed
+  /// on to the payable's hook contract.
+  pub reference: Option<String>,
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+pub struct PayWithIntentMessage {
+  pub payable_id: String,
+  pub token: String,
+  /// The exact amount that the host signed.
+  pub amount: Uint128,
+  /// The host's reference for the payment, like an invoice number.
+  pub reference: String,
+  /// The timestamp in seconds after which the intent can't be paid.
+  pub expiry: u64,
+  /// The intent's nonce. Each can only be paid once per payable.
+  pub nonce: u64,
+  /// The hex-encoded compressed secp256k1 public key of the payable's host.
+  /// Its address must be the host's.
+  pub public_key: String,
+  /// The hex-encoded 64-byte secp256k1 signature of the SHA-256 hash of the
+  /// intent's signing bytes, by the host's public key.
+  pub signature: String,
+  /// The hex-encoded sibling hashes proving that the payer is in the
+  /// payable's allowed payers.
+  pub payer_proof: Vec<String>,
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+pub struct PaymentIntentMessage {
+  pub payable_id: String,
+  pub nonce: u64,
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+pub struct CreateSubscriptionMessage {
+  pub payable_id: String,
+  pub token: String,
+  pub amount: Uint128,
+  /// The seconds between payments.
+  pub period: u64,
+  /// The timestamp in seconds from which the first payment can be
+  /// collected. Defaults to now.
+  pub first_due: Option<u64>,
+  /// The hex-encoded sibling hashes proving that the payer is in the
+  /// payable's allowed payers.
+  pub payer_proof: Vec<String>,
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+pub struct SubscriptionMessage {
+  pub payable_id: String,
+  pub payer: String,
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+pub struct UpdatePayableEscrowMessage {
+  pub payable_id: String,
+  /// The seconds after each payment from which anyone can release it. Zero
+  /// stops escrowing new payments.
+  pub timeout: u64,
+  /// The wallet that settles disputed payments. None if payments can't be
+  /// disputed.
+  pub arbiter: Option<String>,
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+pub struct UpdatePayableHookMessage {
+  pub payable_id: String,
+  /// The contract to notify of each payment. None stops notifying.
+  pub contract: Option<String>,
+  /// Whether a failing notification reverts the payment.
+  pub revert_on_error: bool,
+  /// The gas that each notification can use. At most
+  /// PayableHook::MAX_GAS_LIMIT. Ignored when removing the hook.
+  pub gas_limit: u64,
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+/// The notification sent to a payable's hook contract after each payment.
+pub struct ChainbillsPaymentNotification {
+  pub payable_id: String,
+  /// The ID of the PayablePayment.
+  pub payment_id: String,
+  pub payer: String,
+  pub token: String,
+  pub amount: Uint128,
+  pub reference: Option<String>,
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+/// The execute message that hook contracts must handle.
+pub enum PaymentHookExecuteMessage {
+  ChainbillsPaymentNotification(ChainbillsPaymentNotification),
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+pub struct UpdateReceiptsCollectionMessage {
+  /// The cw721 contract in which receipts are minted. Chainbills must be
+  /// its minter.
+  pub collection: String,
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+pub struct UpdatePayableReceiptsMessage {
+  pub payable_id: String,
+  /// Whether a receipt NFT is minted to the payer of each payment.
+  pub enabled: bool,
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+/// The execute messages of cw721 collections that Chainbills uses.
+pub enum Cw721ExecuteMessage {
+  /// Mints a new NFT to the owner. Receipts' token IDs are the IDs of the
+  /// UserPayments they prove.
+  Mint {
+    token_id: String,
+    owner: String,
+    token_uri: Option<String>,
+    extension: Option<Empty>,
+  },
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+pub struct WithdrawCrossChainMessage {
+  pub payable_id: String,
+  pub token: String,
+  pub amount: Uint128,
+  /// The chain to which the host receives the withdrawn funds.
+  pub dest_chain: ChainIdentifier,
+  /// The hex-encoded Wormhole-normalized (32 bytes) address of the host's
+  /// wallet on the destination chain.
+  pub dest_address: String,
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+/// The execute messages of the Wormhole Token Bridge that Chainbills uses.
+pub enum TokenBridgeMessage {
+  /// Deposits the attached native funds for a subsequent InitiateTransfer.
+  DepositTokens {},
+  /// Locks (or burns wrapped) tokens and publishes a transfer for a relayer
+  /// to redeem on the recipient chain.
+  InitiateTransfer {
+    asset: Asset,
+    recipient_chain: u16,
+    recipient: Binary,
+    fee: Uint128,
+    nonce: u32,
+  },
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+pub struct Asset {
+  pub info: AssetInfo,
+  pub amount: Uint128,
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+pub enum AssetInfo {
+  Token { contract_addr: String },
+  NativeToken { denom: String },
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+/// Any of the forms by which a blockchain network can be identified. All
+/// resolve to the network's cbChainId.
+pub enum ChainIdentifier {
+  /// The CAIP-2 string (`namespace:reference`).
+  Caip2(String),
+  /// The hex-encoded cbChainId.
+  CbChainId(String),
+  /// The Wormhole Chain ID of a registered network.
+  WormholeChainId(u16),
+  /// The Circle (CCTP) domain of a registered network.
+  CircleDomain(u32),
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+pub struct RegisterCbChainMessage {
+  pub caip2: String,
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+pub struct RegisterChainWormholeIdMessage {
+  pub chain: ChainIdentifier,
+  pub wormhole_chain_id: u16,
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+pub struct RegisterChainCircleDomainMessage {
+  pub chain: ChainIdentifier,
+  pub circle_domain: u32,
+}
@@ -0,0 +1,146 @@
+use crate::contract::sv::mt::{ChainbillsProxy, CodeId};
+use crate::error::ChainbillsError;
+use crate::interfaces::chains::sv::mt::ChainsProxy;
+use crate::messages::{
+  ChainIdentifier, InstantiateMessage, RegisterCbChainMessage,
+  RegisterChainCircleDomainMessage, RegisterChainWormholeIdMessage,
+};
+use sylvia::cw_multi_test::IntoAddr;
+use sylvia::cw_std::HexBinary;
+use sylvia::multitest::App;
+
+// keccak256("eip155:11155111"), as listed in evm/DEPLOYED.md.
+const SEPOLIA_CB_CHAIN_ID: &str =
+  "afa90c317deacd3d68f330a30f96e4fa7736e35e8d1426b2e1b2c04bce1c2fb7";
+
+#[test]
+fn chains_registry() {
+  let app = App::default();
+  let code_id = CodeId::store_code(&app);
+
+  let owner = "owner".into_addr();
+  let fee_collector = "fee_collector".into_addr();
+  let init_msg = InstantiateMessage {
+    chain_id: 4000,
+    caip2: "cosmos:burnt-1".to_string(),
+    chainbills_fee_collector: fee_collector.to_string(),
+  };
+  let contract = code_id.instantiate(init_msg).call(&owner).unwrap();
+
+  // This chain is registered at instantiation.
+  let config = contract.config().unwrap();
+  let this_chain = contract
+    .cb_chain(ChainIdentifier::WormholeChainId(4000))
+    .unwrap();
+  assert_eq!(this_chain.caip2, "cosmos:burnt-1");
+  assert_eq!(this_chain.cb_chain_id, config.cb_chain_id);
+
+  // Only the owner can register chains.
+  let caip2 = "eip155:11155111".to_string();
+  let err = contract
+    .register_cb_chain(RegisterCbChainMessage {
+      caip2: caip2.clone(),
+    })
+    .call(&"stranger".into_addr())
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::OwnerUnauthorized {});
+
+  // CAIP-2 strings are validated.
+  let err = contract
+    .register_cb_chain(RegisterCbChainMessage {
+      caip2: "sepolia".to_string(),
+    })
+    .call(&owner)
+    .unwrap_err();
+  assert_eq!(
+    err,
+    ChainbillsError::InvalidCaip2 {
+      caip2: "sepolia".to_string()
+    }
+  );
+
+  // The cbChainId matches the one on the EVM contracts.
+  contract
+    .register_cb_chain(RegisterCbChainMessage {
+      caip2: caip2.clone(),
+    })
+    .call(&owner)
+    .unwrap();
+  let sepolia = contract
+    .cb_chain(ChainIdentifier::Caip2(caip2.clone()))
+    .unwrap();
+  assert_eq!(
+    HexBinary::from(&sepolia.cb_chain_id).to_hex(),
+    SEPOLIA_CB_CHAIN_ID
+  );
+  assert_eq!(sepolia.wormhole_chain_id, None);
+  assert_eq!(sepolia.circle_domain, None);
+
+  // Register the bridging IDs by either form of the chain.
+  contract
+    .register_chain_wormhole_id(RegisterChainWormholeIdMessage {
+      chain: ChainIdentifier::Caip2(caip2.clone()),
+      wormhole_chain_id: 10002,
+    })
+    .call(&owner)
+    .unwrap();
+  contract
+    .register_chain_circle_domain(RegisterChainCircleDomainMessage {
+      chain: ChainIdentifier::CbChainId(SEPOLIA_CB_CHAIN_ID.to_string()),
+      circle_domain: 0,
+    })
+    .call(&owner)
+    .unwrap();
+
+  // All forms resolve to the same chain.
+  for chain in [
+    ChainIdentifier::Caip2(caip2.clone()),
+    ChainIdentifier::CbChainId(SEPOLIA_CB_CHAIN_ID.to_string()),
+    ChainIdentifier::WormholeChainId(10002),
+    ChainIdentifier::CircleDomain(0),
+  ] {
+    let sepolia = contract.cb_chain(chain).unwrap();
+    assert_eq!(sepolia.caip2, caip2);
+    assert_eq!(sepolia.wormhole_chain_id, Some(10002));
+    assert_eq!(sepolia.circle_domain, Some(0));
+  }
+
+  // Bridging IDs are set only once and can't be shared.
+  let err = contract
+    .register_chain_wormhole_id(RegisterChainWormholeIdMessage {
+      chain: ChainIdentifier::Caip2(caip2.clone()),
+      wormhole_chain_id: 2,
+    })
+    .call(&owner)
+    .unwrap_err();
+  assert_eq!(
+    err,
+    ChainbillsError::ChainIdAlreadyRegistered {
+      chain: "2".to_string()
+    }
+  );
+  contract
+    .register_cb_chain(RegisterCbChainMessage {
+      caip2: "eip155:1".to_string(),
+    })
+    .call(&owner)
+    .unwrap();
+  let err = contract
+    .register_chain_wormhole_id(RegisterChainWormholeIdMessage {
+      chain: ChainIdentifier::Caip2("eip155:1".to_string()),
+      wormhole_chain_id: 10002,
+    })
+    .call(&owner)
+    .unwrap_err();
+  assert_eq!(
+    err,
+    ChainbillsError::ChainIdAlreadyRegistered {
+      chain: "10002".to_string()
+    }
+  );
+
+  // Unregistered bridging IDs don't resolve.
+  assert!(contract
+    .cb_chain(ChainIdentifier::WormholeChainId(5))
+    .is_err());
+}
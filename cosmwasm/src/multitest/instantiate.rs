@@ -12,6 +12,7 @@ fn instantiate() {
   let fee_collector = "fee_collector".into_addr();
   let init_msg = InstantiateMessage {
     chain_id: 1,
+    caip2: "cosmos:cosmoshub-4".to_string(),
     chainbills_fee_collector: fee_collector.to_string(),
   };
   let contract = code_id.instantiate(init_msg).call(&owner).unwrap();
@@ -21,6 +22,10 @@ fn instantiate() {
 
   assert_eq!(chain_stats.users_count, 0);
   assert_eq!(config.chain_id, 1);
+  assert_eq!(
+    config.cb_chain_id,
+    crate::state::CbChain::id_of("cosmos:cosmoshub-4")
+  );
   assert_eq!(config.owner, owner);
   assert_eq!(config.chainbills_fee_collector, fee_collector);
 }
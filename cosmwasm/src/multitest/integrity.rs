@@ -0,0 +1,267 @@
+use crate::contract::sv::mt::{ChainbillsProxy, CodeId};
+use crate::interfaces::escrows::sv::mt::EscrowsProxy;
+use crate::interfaces::integrity::sv::mt::IntegrityProxy;
+use crate::interfaces::payables::sv::mt::PayablesProxy;
+use crate::interfaces::payments::sv::mt::PaymentsProxy;
+use crate::interfaces::subscriptions::sv::mt::SubscriptionsProxy;
+use crate::interfaces::token_details::sv::mt::TokenDetailsInterfaceProxy;
+use crate::interfaces::withdrawals::sv::mt::WithdrawalsProxy;
+use crate::messages::{
+  CreatePayableMessage, CreateSubscriptionMessage, IdMessage,
+  InstantiateMessage, TokenIntegrityPageMessage, TransactionInfoMessage,
+  UpdateMaxWithdrawalFeesMessage, UpdatePayableEscrowMessage,
+};
+use crate::state::{TokenAndAmount, TokenIntegrity};
+use cw20::Cw20Coin;
+use cw20_base::msg::InstantiateMsg;
+use sylvia::cw_multi_test::{Contract, ContractWrapper, Executor, IntoAddr};
+use sylvia::cw_std::{coins, Empty, Uint128};
+use sylvia::multitest::App;
+
+fn contract_cw20() -> Box<dyn Contract<Empty>> {
+  let contract = ContractWrapper::new(
+    cw20_base::contract::execute,
+    cw20_base::contract::instantiate,
+    cw20_base::contract::query,
+  );
+  Box::new(contract)
+}
+
+#[test]
+fn checking_token_integrity() {
+  let owner = "owner".into_addr();
+  let host = "host".into_addr();
+  let payer = "payer".into_addr();
+
+  let mut app = sylvia::cw_multi_test::App::new(|router, _api, storage| {
+    router
+      .bank
+      .init_balance(storage, &payer, coins(1_000, "native"))
+      .unwrap();
+  });
+  let cw20_id = app.store_code(contract_cw20());
+  let usdc_addr = app
+    .instantiate_contract(
+      cw20_id,
+      owner.clone(),
+      &InstantiateMsg {
+        name: "USDC".to_string(),
+        symbol: "USDC".to_string(),
+        decimals: 6,
+        initial_balances: vec![Cw20Coin {
+          address: payer.to_string(),
+          amount: Uint128::new(1_000),
+        }],
+        mint: None,
+        marketing: None,
+      },
+      &[],
+      "USDC",
+      None,
+    )
+    .unwrap();
+
+  let app = App::new(app);
+  let code_id = CodeId::store_code(&app);
+  let init_msg = InstantiateMessage {
+    chain_id: 1,
+    caip2: "cosmos:cosmoshub-4".to_string(),
+    chainbills_fee_collector: "fee_collector".into_addr().to_string(),
+  };
+  let contract = code_id.instantiate(init_msg).call(&owner).unwrap();
+  for (token, is_native_token) in
+    [("native", true), (usdc_addr.as_str(), false)]
+  {
+    contract
+      .update_max_withdrawal_fees(UpdateMaxWithdrawalFeesMessage {
+        token: token.to_string(),
+        max_withdrawal_fees: Uint128::new(100),
+        is_native_token,
+      })
+      .call(&owner)
+      .unwrap();
+  }
+
+  // Create three Payables: an open one, an escrowing one, and one that is
+  // subscribed to.
+  let create_payable = || {
+    contract
+      .create_payable(CreatePayableMessage {
+        allowed_tokens_and_amounts: vec![],
+      })
+      .call(&host)
+      .unwrap()
+      .events
+      .iter()
+      .find(|ev| ev.ty == "wasm")
+      .unwrap()
+      .attributes
+      .iter()
+      .find(|attr| attr.key == "payable_id")
+      .unwrap()
+      .value
+      .clone()
+  };
+  let open_id = create_payable();
+  let escrowing_id = create_payable();
+  let subscribed_id = create_payable();
+  contract
+    .update_payable_escrow(UpdatePayableEscrowMessage {
+      payable_id: escrowing_id.clone(),
+      timeout: 7 * 24 * 60 * 60,
+      arbiter: None,
+    })
+    .call(&host)
+    .unwrap();
+
+  let pay_native = |payable_id: &str, amount: u128| {
+    contract
+      .pay(TransactionInfoMessage {
+        payable_id: payable_id.to_string(),
+        token: "native".to_string(),
+        amount: Uint128::new(amount),
+      })
+      .with_funds(&coins(amount, "native"))
+      .call(&payer)
+      .unwrap();
+  };
+  pay_native(&open_id, 500);
+  pay_native(&escrowing_id, 100);
+  contract
+    .create_subscription(CreateSubscriptionMessage {
+      payable_id: subscribed_id.clone(),
+      token: "native".to_string(),
+      amount: Uint128::new(10),
+      period: 24 * 60 * 60,
+      first_due: Some(app.block_info().time.seconds() + 24 * 60 * 60),
+      payer_proof: vec![],
+    })
+    .with_funds(&coins(30, "native"))
+    .call(&payer)
+    .unwrap();
+  contract
+    .withdraw(TransactionInfoMessage {
+      payable_id: open_id.clone(),
+      token: "native".to_string(),
+      amount: Uint128::new(200),
+    })
+    .call(&host)
+    .unwrap();
+
+  app
+    .app_mut()
+    .execute_contract(
+      payer.clone(),
+      usdc_addr.clone(),
+      &cw20::Cw20ExecuteMsg::IncreaseAllowance {
+        spender: contract.contract_addr.to_string(),
+        amount: Uint128::new(40),
+        expires: None,
+      },
+      &[],
+    )
+    .unwrap();
+  contract
+    .pay(TransactionInfoMessage {
+      payable_id: open_id.clone(),
+      token: usdc_addr.to_string(),
+      amount: Uint128::new(40),
+    })
+    .call(&payer)
+    .unwrap();
+
+  // The contract holds what payables own, what they hold in escrow and what
+  // subscribers deposited.
+  let native_integrity = TokenIntegrity {
+    token: "native".to_string(),
+    holdings: Uint128::new(430),
+    total_payable_received: Uint128::new(600),
+    total_withdrawn: Uint128::new(200),
+    payables_balance: Uint128::new(300),
+    payables_count: 3,
+    escrowed: Uint128::new(100),
+    subscription_deposits: Uint128::new(30),
+    is_solvent: Some(true),
+    is_consistent: Some(true),
+  };
+  assert_eq!(
+    contract
+      .token_integrity(IdMessage {
+        id: "native".to_string(),
+      })
+      .unwrap(),
+    native_integrity
+  );
+  assert_eq!(
+    contract
+      .token_integrity(IdMessage {
+        id: usdc_addr.to_string(),
+      })
+      .unwrap(),
+    TokenIntegrity {
+      token: usdc_addr.to_string(),
+      holdings: Uint128::new(40),
+      total_payable_received: Uint128::new(40),
+      total_withdrawn: Uint128::zero(),
+      payables_balance: Uint128::new(40),
+      payables_count: 3,
+      escrowed: Uint128::zero(),
+      subscription_deposits: Uint128::zero(),
+      is_solvent: Some(true),
+      is_consistent: Some(true),
+    }
+  );
+
+  // Pages sum up to the full check, and only a page of all payables is
+  // flagged.
+  let page = |offset: u64, limit: u64| {
+    contract
+      .token_integrity_page(TokenIntegrityPageMessage {
+        token: "native".to_string(),
+        offset,
+        limit,
+      })
+      .unwrap()
+  };
+  let first_page = page(0, 2);
+  assert_eq!(first_page.payables_count, 2);
+  assert_eq!(first_page.payables_balance, Uint128::new(300));
+  assert_eq!(first_page.escrowed, Uint128::new(100));
+  assert_eq!(first_page.subscription_deposits, Uint128::zero());
+  assert_eq!(first_page.is_solvent, None);
+  assert_eq!(first_page.is_consistent, None);
+  let second_page = page(2, 2);
+  assert_eq!(second_page.payables_count, 1);
+  assert_eq!(second_page.payables_balance, Uint128::zero());
+  assert_eq!(second_page.escrowed, Uint128::zero());
+  assert_eq!(second_page.subscription_deposits, Uint128::new(30));
+  assert_eq!(second_page.holdings, native_integrity.holdings);
+  assert_eq!(second_page.is_solvent, None);
+  assert_eq!(page(3, 2).payables_count, 0);
+  assert_eq!(page(0, 3), native_integrity);
+
+  // Holdings that no longer cover the payables are flagged.
+  contract
+    .owner_withdraw(TokenAndAmount {
+      token: "native".to_string(),
+      amount: Uint128::new(100),
+    })
+    .call(&owner)
+    .unwrap();
+  let integrity = contract
+    .token_integrity(IdMessage {
+      id: "native".to_string(),
+    })
+    .unwrap();
+  assert_eq!(integrity.holdings, Uint128::new(330));
+  assert_eq!(integrity.is_solvent, Some(false));
+  assert_eq!(integrity.is_consistent, Some(true));
+
+  // Only known tokens can be checked.
+  let err = contract
+    .token_integrity(IdMessage {
+      id: "unknown".to_string(),
+    })
+    .unwrap_err();
+  assert!(err.to_string().contains("Invalid Token: unknown"));
+}
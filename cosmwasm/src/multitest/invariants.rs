@@ -0,0 +1,616 @@
+use crate::contract::sv::mt::{ChainbillsProxy, CodeId};
+use crate::contract::Chainbills;
+use crate::interfaces::payables::sv::mt::PayablesProxy;
+use crate::interfaces::payments::sv::mt::PaymentsProxy;
+use crate::interfaces::token_details::sv::mt::TokenDetailsInterfaceProxy;
+use crate::interfaces::withdrawals::sv::mt::WithdrawalsProxy;
+use crate::messages::{
+  CreatePayableMessage, IdMessage, InstantiateMessage, TransactionInfoMessage,
+  UpdateMaxWithdrawalFeesMessage, UpdatePayableTokensAndAmountsMessage,
+};
+use crate::state::{TokenAndAmount, TokenDetails};
+use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg};
+use cw20_base::msg::InstantiateMsg;
+use proptest::prelude::*;
+use sylvia::cw_multi_test::{Contract, ContractWrapper, Executor, IntoAddr};
+use sylvia::cw_std::{coins, Addr, Empty, HexBinary, Uint128};
+use sylvia::multitest::App;
+
+const NATIVE: &str = "native";
+const USERS: usize = 3;
+const INITIAL_BALANCE: u128 = 1_000_000_000;
+
+fn contract_cw20() -> Box<dyn Contract<Empty>> {
+  let contract = ContractWrapper::new(
+    cw20_base::contract::execute,
+    cw20_base::contract::instantiate,
+    cw20_base::contract::query,
+  );
+  Box::new(contract)
+}
+
+/// A step of a generated scenario. Users and payables are indexes into the
+/// scenario's wallets and the payables created so far, and tokens are
+/// indexes into [native, cw20]. Steps on a payable other than payments are
+/// made by its host.
+#[derive(Clone, Debug)]
+enum Op {
+  CreatePayable {
+    host: usize,
+    allowed: Option<(usize, u128)>,
+  },
+  Pay {
+    payer: usize,
+    payable: usize,
+    token: usize,
+    amount: u128,
+  },
+  Withdraw {
+    payable: usize,
+    token: usize,
+    amount: u128,
+  },
+  Close {
+    payable: usize,
+  },
+  Reopen {
+    payable: usize,
+  },
+  UpdateTokensAndAmounts {
+    payable: usize,
+    allowed: Option<(usize, u128)>,
+  },
+  /// Sends tokens to the contract without paying any payable.
+  Donate {
+    from: usize,
+    token: usize,
+    amount: u128,
+  },
+  /// The owner withdraws up to what the contract holds beyond what payables
+  /// own.
+  OwnerWithdraw {
+    token: usize,
+    amount: u128,
+  },
+}
+
+fn op_strategy() -> impl Strategy<Value = Op> {
+  let user = 0..USERS;
+  let token = 0..2usize;
+  let allowed = proptest::option::of((0..2usize, 1..500u128));
+  prop_oneof![
+    2 => (user.clone(), allowed.clone())
+      .prop_map(|(host, allowed)| Op::CreatePayable { host, allowed }),
+    5 => (user.clone(), any::<usize>(), token.clone(), 1..500u128).prop_map(
+      |(payer, payable, token, amount)| Op::Pay {
+        payer,
+        payable,
+        token,
+        amount,
+      }
+    ),
+    // Withdrawals below 50 have no fees, which can't be transferred.
+    3 => (any::<usize>(), token.clone(), 50..500u128).prop_map(
+      |(payable, token, amount)| Op::Withdraw {
+        payable,
+        token,
+        amount,
+      }
+    ),
+    1 => any::<usize>().prop_map(|payable| Op::Close { payable }),
+    1 => any::<usize>().prop_map(|payable| Op::Reopen { payable }),
+    1 => (any::<usize>(), allowed).prop_map(|(payable, allowed)| {
+      Op::UpdateTokensAndAmounts { payable, allowed }
+    }),
+    1 => (user, token.clone(), 1..500u128)
+      .prop_map(|(from, token, amount)| Op::Donate { from, token, amount }),
+    1 => (token, 1..1_000u128)
+      .prop_map(|(token, amount)| Op::OwnerWithdraw { token, amount }),
+  ]
+}
+
+/// A contract under test, its tokens, and what the scenario did to it.
+struct Harness {
+  app: App<sylvia::cw_multi_test::App>,
+  owner: Addr,
+  users: Vec<Addr>,
+  fee_collector: Addr,
+  usdc: Addr,
+  contract: Addr,
+  /// The hex IDs of the payables created so far, with their hosts.
+  payables: Vec<(String, Addr)>,
+  /// Per token, what was sent to the contract beyond payments, minus what
+  /// the owner withdrew.
+  excess: [u128; 2],
+}
+
+impl Harness {
+  fn new() -> Self {
+    let owner = "owner".into_addr();
+    let users: Vec<Addr> =
+      (0..USERS).map(|i| format!("user{i}").into_addr()).collect();
+    let fee_collector = "fee_collector".into_addr();
+
+    let mut app = sylvia::cw_multi_test::App::new(|router, _api, storage| {
+      for user in users.iter() {
+        router
+          .bank
+          .init_balance(storage, user, coins(INITIAL_BALANCE, NATIVE))
+          .unwrap();
+      }
+    });
+    let cw20_id = app.store_code(contract_cw20());
+    let usdc = app
+      .instantiate_contract(
+        cw20_id,
+        owner.clone(),
+        &InstantiateMsg {
+          name: "USDC".to_string(),
+          symbol: "USDC".to_string(),
+          decimals: 6,
+          initial_balances: users
+            .iter()
+            .map(|user| Cw20Coin {
+              address: user.to_string(),
+              amount: Uint128::new(INITIAL_BALANCE),
+            })
+            .collect(),
+          mint: None,
+          marketing: None,
+        },
+        &[],
+        "USDC",
+        None,
+      )
+      .unwrap();
+
+    let app = App::new(app);
+    let code_id = CodeId::store_code(&app);
+    let contract = code_id
+      .instantiate(InstantiateMessage {
+        chain_id: 1,
+        caip2: "cosmos:cosmoshub-4".to_string(),
+        chainbills_fee_collector: fee_collector.to_string(),
+      })
+      .call(&owner)
+      .unwrap();
+    for (token, is_native_token) in [(NATIVE, true), (usdc.as_str(), false)] {
+      contract
+        .update_max_withdrawal_fees(UpdateMaxWithdrawalFeesMessage {
+          token: token.to_string(),
+          max_withdrawal_fees: Uint128::new(5),
+          is_native_token,
+        })
+        .call(&owner)
+        .unwrap();
+    }
+    let contract = contract.contract_addr.clone();
+    for user in users.iter() {
+      app
+        .app_mut()
+        .execute_contract(
+          user.clone(),
+          usdc.clone(),
+          &Cw20ExecuteMsg::IncreaseAllowance {
+            spender: contract.to_string(),
+            amount: Uint128::new(INITIAL_BALANCE),
+            expires: None,
+          },
+          &[],
+        )
+        .unwrap();
+    }
+
+    Harness {
+      app,
+      owner,
+      users,
+      fee_collector,
+      usdc,
+      contract,
+      payables: vec![],
+      excess: [0; 2],
+    }
+  }
+
+  fn token(&self, token: usize) -> String {
+    match token {
+      0 => NATIVE.to_string(),
+      _ => self.usdc.to_string(),
+    }
+  }
+
+  fn payable(&self, payable: usize) -> Option<(String, Addr)> {
+    if self.payables.is_empty() {
+      return None;
+    }
+    Some(self.payables[payable % self.payables.len()].clone())
+  }
+
+  fn allowed(&self, allowed: Option<(usize, u128)>) -> Vec<TokenAndAmount> {
+    allowed
+      .map(|(token, amount)| TokenAndAmount {
+        token: self.token(token),
+        amount: Uint128::new(amount),
+      })
+      .into_iter()
+      .collect()
+  }
+
+  fn balance_of(&self, token: usize, wallet: &Addr) -> u128 {
+    if token == 0 {
+      self
+        .app
+        .querier()
+        .query_balance(wallet, NATIVE)
+        .unwrap()
+        .amount
+        .u128()
+    } else {
+      let response: BalanceResponse = self
+        .app
+        .querier()
+        .query_wasm_smart(
+          &self.usdc,
+          &Cw20QueryMsg::Balance {
+            address: wallet.to_string(),
+          },
+        )
+        .unwrap();
+      response.balance.u128()
+    }
+  }
+
+  /// Runs the step. Steps that the contract rejects are fine, as long as it
+  /// rejects them with its own errors and the invariants still hold.
+  fn apply(&mut self, op: Op) {
+    let proxy = sylvia::multitest::Proxy::<_, Chainbills>::new(
+      self.contract.clone(),
+      &self.app,
+    );
+    match op {
+      Op::CreatePayable { host, allowed } => {
+        if let Ok(resp) = proxy
+          .create_payable(CreatePayableMessage {
+            allowed_tokens_and_amounts: self.allowed(allowed),
+          })
+          .call(&self.users[host])
+        {
+          let payable_id = resp
+            .events
+            .iter()
+            .find(|ev| ev.ty == "wasm")
+            .unwrap()
+            .attributes
+            .iter()
+            .find(|attr| attr.key == "payable_id")
+            .unwrap()
+            .value
+            .clone();
+          self.payables.push((payable_id, self.users[host].clone()));
+        }
+      }
+      Op::Pay {
+        payer,
+        payable,
+        token,
+        amount,
+      } => {
+        let Some((payable_id, _)) = self.payable(payable) else {
+          return;
+        };
+        let funds = if token == 0 { coins(amount, NATIVE) } else { vec![] };
+        let _ = proxy
+          .pay(TransactionInfoMessage {
+            payable_id,
+            token: self.token(token),
+            amount: Uint128::new(amount),
+          })
+          .with_funds(&funds)
+          .call(&self.users[payer]);
+      }
+      Op::Withdraw {
+        payable,
+        token,
+        amount,
+      } => {
+        let Some((payable_id, host)) = self.payable(payable) else {
+          return;
+        };
+        let _ = proxy
+          .withdraw(TransactionInfoMessage {
+            payable_id,
+            token: self.token(token),
+            amount: Uint128::new(amount),
+          })
+          .call(&host);
+      }
+      Op::Close { payable } => {
+        let Some((id, host)) = self.payable(payable) else {
+          return;
+        };
+        let _ = proxy.close_payable(IdMessage { id }).call(&host);
+      }
+      Op::Reopen { payable } => {
+        let Some((id, host)) = self.payable(payable) else {
+          return;
+        };
+        let _ = proxy.reopen_payable(IdMessage { id }).call(&host);
+      }
+      Op::UpdateTokensAndAmounts { payable, allowed } => {
+        let Some((payable_id, host)) = self.payable(payable) else {
+          return;
+        };
+        let _ = proxy
+          .update_payable_tokens_and_amounts(
+            UpdatePayableTokensAndAmountsMessage {
+              payable_id,
+              allowed_tokens_and_amounts: self.allowed(allowed),
+            },
+          )
+          .call(&host);
+      }
+      Op::Donate {
+        from,
+        token,
+        amount,
+      } => {
+        let from = self.users[from].clone();
+        if token == 0 {
+          self
+            .app
+            .app_mut()
+            .send_tokens(from, self.contract.clone(), &coins(amount, NATIVE))
+            .unwrap();
+        } else {
+          self
+            .app
+            .app_mut()
+            .execute_contract(
+              from,
+              self.usdc.clone(),
+              &Cw20ExecuteMsg::Transfer {
+                recipient: self.contract.to_string(),
+                amount: Uint128::new(amount),
+              },
+              &[],
+            )
+            .unwrap();
+        }
+        self.excess[token] += amount;
+      }
+      Op::OwnerWithdraw { token, amount } => {
+        let amount = amount.min(self.excess[token]);
+        if amount == 0 {
+          return;
+        }
+        proxy
+          .owner_withdraw(TokenAndAmount {
+            token: self.token(token),
+            amount: Uint128::new(amount),
+          })
+          .call(&self.owner)
+          .unwrap();
+        self.excess[token] -= amount;
+      }
+    }
+  }
+
+  /// Checks the invariants against the contract's storage.
+  fn check_invariants(&self) {
+    let cb = Chainbills::new();
+    let querier = self.app.querier();
+    let contract = self.contract.clone();
+    let proxy = sylvia::multitest::Proxy::<_, Chainbills>::new(
+      self.contract.clone(),
+      &self.app,
+    );
+
+    // ChainStats counters match the chain's ID lists.
+    let chain_stats = proxy.chain_stats().unwrap();
+    let chain_users = cb
+      .chain_user_addresses
+      .query(&querier, contract.clone())
+      .unwrap();
+    let chain_payable_ids = cb
+      .chain_payable_ids
+      .query(&querier, contract.clone())
+      .unwrap();
+    let chain_user_payment_ids = cb
+      .chain_user_payment_ids
+      .query(&querier, contract.clone())
+      .unwrap();
+    let chain_payable_payment_ids = cb
+      .chain_payable_payment_ids
+      .query(&querier, contract.clone())
+      .unwrap();
+    let chain_withdrawal_ids = cb
+      .chain_withdrawal_ids
+      .query(&querier, contract.clone())
+      .unwrap();
+    let chain_activity_ids = cb
+      .chain_activity_ids
+      .query(&querier, contract.clone())
+      .unwrap();
+    assert_eq!(chain_stats.users_count, chain_users.len() as u64);
+    assert_eq!(chain_stats.payables_count, chain_payable_ids.len() as u64);
+    assert_eq!(chain_payable_ids.len(), self.payables.len());
+    assert_eq!(
+      chain_stats.user_payments_count,
+      chain_user_payment_ids.len() as u64
+    );
+    assert_eq!(
+      chain_stats.payable_payments_count,
+      chain_payable_payment_ids.len() as u64
+    );
+    assert_eq!(
+      chain_stats.withdrawals_count,
+      chain_withdrawal_ids.len() as u64
+    );
+    assert_eq!(
+      chain_stats.activities_count,
+      chain_activity_ids.len() as u64
+    );
+
+    // User counters match their ID lists. Every activity is recorded for one
+    // user, except that payments record another from the payable's
+    // perspective.
+    let mut users_activities_count = 0;
+    for (i, wallet) in chain_users.iter().enumerate() {
+      let user = cb.users.query(&querier, contract.clone(), wallet);
+      let user = user.unwrap().unwrap();
+      assert_eq!(user.chain_count, i as u64 + 1);
+      let ids_len = |map: &cw_storage_plus::Map<&Addr, Vec<[u8; 32]>>| {
+        map
+          .query(&querier, contract.clone(), wallet)
+          .unwrap()
+          .unwrap_or_default()
+          .len() as u64
+      };
+      assert_eq!(user.payables_count, ids_len(&cb.user_payable_ids));
+      assert_eq!(user.payments_count, ids_len(&cb.user_payment_ids));
+      assert_eq!(user.withdrawals_count, ids_len(&cb.user_withdrawal_ids));
+      assert_eq!(user.activities_count, ids_len(&cb.user_activity_ids));
+      users_activities_count += user.activities_count;
+    }
+    assert_eq!(
+      users_activities_count + chain_stats.payable_payments_count,
+      chain_stats.activities_count
+    );
+    for activity_id in chain_activity_ids.iter() {
+      let activity =
+        cb.activities
+          .query(&querier, contract.clone(), *activity_id);
+      assert!(activity.unwrap().is_some());
+    }
+
+    // Payable counters match their ID lists, and their balances are what
+    // was paid into them minus what was withdrawn, which never goes
+    // negative.
+    let mut paid = [0u128; 2];
+    let mut withdrawn = [0u128; 2];
+    let mut owed = [0u128; 2];
+    let mut payable_activities_count = 0;
+    for (i, payable_id) in chain_payable_ids.iter().enumerate() {
+      let payable = proxy
+        .payable(IdMessage {
+          id: HexBinary::from(payable_id).to_hex(),
+        })
+        .unwrap();
+      assert_eq!(payable.chain_count, i as u64 + 1);
+      let ids = |map: &cw_storage_plus::Map<[u8; 32], Vec<[u8; 32]>>| {
+        map
+          .query(&querier, contract.clone(), *payable_id)
+          .unwrap()
+          .unwrap_or_default()
+      };
+      let payment_ids = ids(&cb.payable_payment_ids);
+      let withdrawal_ids = ids(&cb.payable_withdrawal_ids);
+      assert_eq!(payable.payments_count, payment_ids.len() as u64);
+      assert_eq!(payable.withdrawals_count, withdrawal_ids.len() as u64);
+      assert_eq!(
+        payable.activities_count,
+        ids(&cb.payable_activity_ids).len() as u64
+      );
+      payable_activities_count += payable.activities_count;
+
+      for token in 0..2 {
+        let token_name = self.token(token);
+        let payable_paid: u128 = payment_ids
+          .iter()
+          .map(|id| {
+            cb.payable_payments
+              .query(&querier, contract.clone(), *id)
+              .unwrap()
+              .unwrap()
+              .details
+          })
+          .filter(|details| details.token == token_name)
+          .map(|details| details.amount.u128())
+          .sum();
+        let payable_withdrawn: u128 = withdrawal_ids
+          .iter()
+          .map(|id| {
+            cb.withdrawals
+              .query(&querier, contract.clone(), *id)
+              .unwrap()
+              .unwrap()
+              .details
+          })
+          .filter(|details| details.token == token_name)
+          .map(|details| details.amount.u128())
+          .sum();
+        let balance = payable
+          .balances
+          .iter()
+          .find(|balance| balance.token == token_name)
+          .map_or(0, |balance| balance.amount.u128());
+        assert_eq!(
+          payable_paid.checked_sub(payable_withdrawn),
+          Some(balance),
+          "payable {} {token_name} balance",
+          i + 1
+        );
+        paid[token] += payable_paid;
+        withdrawn[token] += payable_withdrawn;
+        owed[token] += balance;
+      }
+    }
+    assert!(payable_activities_count <= chain_stats.activities_count);
+
+    // TokenDetails totals equal the sums of the recorded payments and
+    // withdrawals, the fee collector holds the fees, and the contract holds
+    // what payables own plus the excess.
+    for token in 0..2 {
+      let token_name = self.token(token);
+      let TokenDetails {
+        total_user_paid,
+        total_payable_received,
+        total_withdrawn,
+        total_withdrawal_fees_collected,
+        ..
+      } = proxy
+        .token_details(IdMessage {
+          id: token_name.clone(),
+        })
+        .unwrap();
+      let user_paid: u128 = chain_user_payment_ids
+        .iter()
+        .map(|id| {
+          cb.user_payments
+            .query(&querier, contract.clone(), *id)
+            .unwrap()
+            .unwrap()
+            .details
+        })
+        .filter(|details| details.token == token_name)
+        .map(|details| details.amount.u128())
+        .sum();
+      assert_eq!(total_user_paid.u128(), user_paid);
+      assert_eq!(total_payable_received.u128(), paid[token]);
+      assert_eq!(total_withdrawn.u128(), withdrawn[token]);
+      assert_eq!(
+        total_withdrawal_fees_collected.u128(),
+        self.balance_of(token, &self.fee_collector)
+      );
+      assert_eq!(
+        self.balance_of(token, &self.contract),
+        owed[token] + self.excess[token]
+      );
+    }
+  }
+}
+
+proptest! {
+  #![proptest_config(ProptestConfig::with_cases(48))]
+
+  #[test]
+  fn invariants_hold(ops in proptest::collection::vec(op_strategy(), 1..40)) {
+    let mut harness = Harness::new();
+    harness.check_invariants();
+    for op in ops {
+      harness.apply(op);
+      harness.check_invariants();
+    }
+  }
+}
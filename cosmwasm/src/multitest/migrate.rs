@@ -0,0 +1,372 @@
+use crate::contract::sv::mt::{ChainbillsProxy, CodeId};
+use crate::contract::Chainbills;
+use crate::error::ChainbillsError;
+use crate::interfaces::chains::sv::mt::ChainsProxy;
+use crate::interfaces::migration::sv::mt::MigrationProxy;
+use crate::interfaces::payables::sv::mt::PayablesProxy;
+use crate::interfaces::payments::sv::mt::PaymentsProxy;
+use crate::interfaces::token_details::sv::mt::TokenDetailsInterfaceProxy;
+use crate::messages::{
+  ChainIdentifier, ContinueMigrationMessage, CreatePayableMessage, IdMessage,
+  InstantiateMessage, MigrateChainMessage, MigrateMessage,
+  PerChainPayablePaymentIdMessage, PerChainPayablePaymentsCountMessage,
+  RegisterCbChainMessage, RegisterChainWormholeIdMessage,
+  TransactionInfoMessage, UpdateMaxWithdrawalFeesMessage,
+};
+use crate::state::{
+  CbChain, ConfigV0, MigrationProgress, MigrationStage, PayablePaymentV0,
+  TokenAndAmount, UserPaymentV0,
+};
+use cw_storage_plus::{Item, Map};
+use sylvia::cw_multi_test::IntoAddr;
+use sylvia::cw_std::{coins, HexBinary, Uint128};
+use sylvia::multitest::App;
+
+#[test]
+fn instantiate_rejects_invalid_caip2() {
+  let app = App::default();
+  let code_id = CodeId::store_code(&app);
+
+  let owner = "owner".into_addr();
+  let init_msg = InstantiateMessage {
+    chain_id: 4000,
+    caip2: "burnt-1".to_string(),
+    chainbills_fee_collector: "fee_collector".into_addr().to_string(),
+  };
+  let err = code_id.instantiate(init_msg).call(&owner).unwrap_err();
+  assert_eq!(
+    err,
+    ChainbillsError::InvalidCaip2 {
+      caip2: "burnt-1".to_string()
+    }
+  );
+}
+
+#[test]
+fn migrate_keys_chains_by_cb_chain_id() {
+  let owner = "owner".into_addr();
+  let host = "host".into_addr();
+  let payer = "payer".into_addr();
+  let fee_collector = "fee_collector".into_addr();
+  let app =
+    App::new(sylvia::cw_multi_test::App::new(|router, _api, storage| {
+      router
+        .bank
+        .init_balance(storage, &payer, coins(100, "native"))
+        .unwrap();
+    }));
+  let code_id = CodeId::store_code(&app);
+
+  let init_msg = InstantiateMessage {
+    chain_id: 4000,
+    caip2: "cosmos:burnt-1".to_string(),
+    chainbills_fee_collector: fee_collector.to_string(),
+  };
+  let contract = code_id
+    .instantiate(init_msg)
+    .with_admin(owner.as_str())
+    .call(&owner)
+    .unwrap();
+  let payable_resp = contract
+    .create_payable(CreatePayableMessage {
+      allowed_tokens_and_amounts: vec![],
+    })
+    .call(&host)
+    .unwrap();
+  let local_payable_id = payable_resp
+    .events
+    .iter()
+    .find(|ev| ev.ty == "wasm")
+    .unwrap()
+    .attributes
+    .iter()
+    .find(|attr| attr.key == "payable_id")
+    .unwrap()
+    .value
+    .clone();
+  let local_payable_id = <[u8; 32]>::try_from(
+    HexBinary::from_hex(&local_payable_id).unwrap().as_slice(),
+  )
+  .unwrap();
+
+  // Store the state as it was before chains were keyed by their cbChainIds.
+  // The foreign payment was made from Sepolia (Wormhole Chain ID 10002). The
+  // local payable was paid once on this chain.
+  let payable_id = [1u8; 32];
+  let user_payment_ids = [[2u8; 32], [5u8; 32]];
+  let payable_payment_id = [3u8; 32];
+  let local_payment_id = [6u8; 32];
+  let details = TokenAndAmount {
+    token: "native".to_string(),
+    amount: Uint128::new(100),
+  };
+  {
+    let mut app = app.app_mut();
+    let mut storage = app.contract_storage_mut(&contract.contract_addr);
+    let storage = storage.as_mut();
+    Item::new("config")
+      .save(
+        storage,
+        &ConfigV0 {
+          chain_id: 4000,
+          owner: owner.clone(),
+          chainbills_fee_collector: fee_collector.clone(),
+          withdrawal_fee_percentage: Uint128::new(200),
+        },
+      )
+      .unwrap();
+    for (i, user_payment_id) in user_payment_ids.iter().enumerate() {
+      Map::new("user_payments")
+        .save(
+          storage,
+          *user_payment_id,
+          &UserPaymentV0 {
+            payable_id,
+            payer: payer.clone(),
+            payable_chain_id: 4000,
+            chain_count: i as u64 + 1,
+            payer_count: i as u64 + 1,
+            timestamp: 10,
+            details: details.clone(),
+          },
+        )
+        .unwrap();
+    }
+    Map::new("payable_payments")
+      .save(
+        storage,
+        payable_payment_id,
+        &PayablePaymentV0 {
+          payable_id,
+          payer: [4u8; 32],
+          chain_count: 1,
+          payer_chain_id: 10002,
+          local_chain_count: 1,
+          payable_count: 1,
+          timestamp: 10,
+          details,
+        },
+      )
+      .unwrap();
+    let counts =
+      Map::<(Vec<u8>, u16), u64>::new("per_chain_payable_payments_count");
+    let ids = Map::<(Vec<u8>, u16), Vec<[u8; 32]>>::new(
+      "per_chain_payable_payment_ids",
+    );
+    counts
+      .save(storage, (payable_id.to_vec(), 10002), &1)
+      .unwrap();
+    ids
+      .save(
+        storage,
+        (payable_id.to_vec(), 10002),
+        &vec![payable_payment_id],
+      )
+      .unwrap();
+    counts
+      .save(storage, (local_payable_id.to_vec(), 4000), &1)
+      .unwrap();
+    ids
+      .save(
+        storage,
+        (local_payable_id.to_vec(), 4000),
+        &vec![local_payment_id],
+      )
+      .unwrap();
+  }
+
+  // The chains must have valid CAIP-2s.
+  let msg = MigrateMessage {
+    caip2: "cosmos:burnt-1".to_string(),
+    foreign_chains: vec![MigrateChainMessage {
+      caip2: "sepolia".to_string(),
+      wormhole_chain_id: 10002,
+    }],
+  };
+  let err = contract
+    .migrate(msg)
+    .call(&owner, code_id.code_id())
+    .unwrap_err();
+  assert_eq!(
+    err,
+    ChainbillsError::InvalidCaip2 {
+      caip2: "sepolia".to_string()
+    }
+  );
+
+  // Migrating only upgrades the config. Stored payments are moved later.
+  let msg = MigrateMessage {
+    caip2: "cosmos:burnt-1".to_string(),
+    foreign_chains: vec![],
+  };
+  contract
+    .migrate(msg)
+    .call(&owner, code_id.code_id())
+    .unwrap();
+  let this_chain = CbChain::id_of("cosmos:burnt-1");
+  let sepolia = CbChain::id_of("eip155:11155111");
+  let config = contract.config().unwrap();
+  assert_eq!(config.chain_id, 4000);
+  assert_eq!(config.cb_chain_id, this_chain);
+  assert_eq!(config.owner, owner);
+  assert_eq!(config.token_bridge, None);
+  assert_eq!(
+    contract.migration_progress().unwrap(),
+    Some(MigrationProgress {
+      stage: MigrationStage::UserPayments,
+      cursor: None,
+    })
+  );
+
+  // Stored payments are read in their old format until they are moved.
+  let user_payment = |id: &[u8; 32]| {
+    contract
+      .user_payment(IdMessage {
+        id: HexBinary::from(id).to_hex(),
+      })
+      .unwrap()
+  };
+  assert_eq!(
+    user_payment(&user_payment_ids[0]).payable_chain_id,
+    this_chain
+  );
+
+  // A payment into the local payable keeps counting on from the stored ones.
+  contract
+    .update_max_withdrawal_fees(UpdateMaxWithdrawalFeesMessage {
+      token: "native".to_string(),
+      max_withdrawal_fees: Uint128::new(100),
+      is_native_token: true,
+    })
+    .call(&owner)
+    .unwrap();
+  contract
+    .pay(TransactionInfoMessage {
+      payable_id: HexBinary::from(&local_payable_id).to_hex(),
+      token: "native".to_string(),
+      amount: Uint128::new(10),
+    })
+    .with_funds(&coins(10, "native"))
+    .call(&payer)
+    .unwrap();
+  let local_count = |chain: ChainIdentifier| {
+    contract
+      .per_chain_payable_payment_count(PerChainPayablePaymentsCountMessage {
+        payable_id: HexBinary::from(&local_payable_id).to_hex(),
+        chain,
+      })
+      .unwrap()
+      .count
+  };
+  assert_eq!(local_count(ChainIdentifier::WormholeChainId(4000)), 2);
+  let first_local_payment = contract
+    .per_chain_payable_payment_id(PerChainPayablePaymentIdMessage {
+      payable_id: HexBinary::from(&local_payable_id).to_hex(),
+      chain: ChainIdentifier::Caip2("cosmos:burnt-1".to_string()),
+      count: 1,
+    })
+    .unwrap();
+  assert_eq!(
+    first_local_payment.id,
+    HexBinary::from(&local_payment_id).to_hex()
+  );
+
+  // Anyone moves the stored payments in pages. Payments from chains that
+  // aren't registered stop the migration until the owner registers them.
+  let continue_migration = |limit: u64| {
+    contract
+      .continue_migration(ContinueMigrationMessage { limit })
+      .call(&payer)
+  };
+  let err = continue_migration(0).unwrap_err();
+  assert_eq!(err, ChainbillsError::InvalidMigrationLimit {});
+  // The stored user payments and the one just made take a page each, and
+  // the stage ends with an empty page.
+  for _ in 0..4 {
+    continue_migration(1).unwrap();
+  }
+  assert_eq!(
+    contract.migration_progress().unwrap().unwrap().stage,
+    MigrationStage::PayablePayments
+  );
+  let err = continue_migration(2).unwrap_err();
+  assert_eq!(
+    err,
+    ChainbillsError::UnregisteredChain {
+      chain: format!("{:?}", ChainIdentifier::WormholeChainId(10002))
+    }
+  );
+  contract
+    .register_cb_chain(RegisterCbChainMessage {
+      caip2: "eip155:11155111".to_string(),
+    })
+    .call(&owner)
+    .unwrap();
+  contract
+    .register_chain_wormhole_id(RegisterChainWormholeIdMessage {
+      chain: ChainIdentifier::Caip2("eip155:11155111".to_string()),
+      wormhole_chain_id: 10002,
+    })
+    .call(&owner)
+    .unwrap();
+  let mut pages = 0;
+  while contract.migration_progress().unwrap().is_some() {
+    continue_migration(1).unwrap();
+    pages += 1;
+  }
+  assert!(pages > 1);
+  let err = continue_migration(1).unwrap_err();
+  assert_eq!(err, ChainbillsError::NoMigrationInProgress {});
+
+  for user_payment_id in user_payment_ids.iter() {
+    let user_payment = user_payment(user_payment_id);
+    assert_eq!(user_payment.payer, payer);
+    assert_eq!(user_payment.payable_chain_id, this_chain);
+  }
+  let payable_payment = contract
+    .payable_payment(IdMessage {
+      id: HexBinary::from(&payable_payment_id).to_hex(),
+    })
+    .unwrap();
+  assert_eq!(payable_payment.payer_chain_id, sepolia);
+  assert_eq!(payable_payment.local_chain_count, 1);
+  assert_eq!(local_count(ChainIdentifier::WormholeChainId(4000)), 2);
+
+  {
+    let app = app.app();
+    let storage = app.contract_storage(&contract.contract_addr);
+    let state = Chainbills::new();
+    let key = (payable_id.to_vec(), sepolia);
+    assert_eq!(
+      state
+        .per_chain_payable_payments_count
+        .load(storage.as_ref(), key.clone())
+        .unwrap(),
+      1
+    );
+    assert_eq!(
+      state
+        .per_chain_payable_payment_ids
+        .load(storage.as_ref(), key)
+        .unwrap(),
+      vec![payable_payment_id]
+    );
+    let local_ids = state
+      .per_chain_payable_payment_ids
+      .load(storage.as_ref(), (local_payable_id.to_vec(), this_chain))
+      .unwrap();
+    assert_eq!(local_ids.len(), 2);
+    assert_eq!(local_ids[0], local_payment_id);
+  }
+
+  // Migrating again changes nothing.
+  let msg = MigrateMessage {
+    caip2: "cosmos:burnt-1".to_string(),
+    foreign_chains: vec![],
+  };
+  let err = contract
+    .migrate(msg)
+    .call(&owner, code_id.code_id())
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::AlreadyMigrated {});
+}
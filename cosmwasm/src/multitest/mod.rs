@@ -1,4 +1,9 @@
+mod chains;
 mod instantiate;
+mod integrity;
+mod invariants;
 mod max_withdrawal_fees;
+mod migrate;
 mod owner_can_withdraw;
+mod payloads;
 mod users;
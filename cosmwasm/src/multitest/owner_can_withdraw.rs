@@ -34,6 +34,7 @@ fn owner_can_withdraw() {
   let fee_collector = "fee_collector".into_addr();
   let init_msg = InstantiateMessage {
     chain_id: 1,
+    caip2: "cosmos:cosmoshub-4".to_string(),
     chainbills_fee_collector: fee_collector.to_string(),
   };
   let contract = code_id.instantiate(init_msg).call(&owner).unwrap();
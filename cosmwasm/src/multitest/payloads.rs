@@ -0,0 +1,94 @@
+use crate::contract::sv::mt::CodeId;
+use crate::interfaces::payables::sv::mt::PayablesProxy;
+use crate::interfaces::token_details::sv::mt::TokenDetailsInterfaceProxy;
+use crate::messages::{
+  CreatePayableMessage, IdMessage, InstantiateMessage,
+  UpdateMaxWithdrawalFeesMessage,
+};
+use crate::state::TokenAndAmount;
+use chainbills_payload::PayablePayload;
+use sha2::{Digest, Sha256};
+use sylvia::cw_multi_test::{AppResponse, IntoAddr};
+use sylvia::cw_std::{HexBinary, Uint128};
+use sylvia::multitest::App;
+
+fn attribute(resp: &AppResponse, key: &str) -> String {
+  let wasm = resp.events.iter().find(|ev| ev.ty == "wasm").unwrap();
+  let attr = wasm.attributes.iter().find(|attr| attr.key == key).unwrap();
+  attr.value.clone()
+}
+
+fn payload(resp: &AppResponse) -> PayablePayload {
+  let bytes = HexBinary::from_hex(&attribute(resp, "payload")).unwrap();
+  PayablePayload::decode(&bytes).unwrap()
+}
+
+#[test]
+fn payables_emit_payloads() {
+  let app = App::default();
+  let code_id = CodeId::store_code(&app);
+
+  let owner = "owner".into_addr();
+  let fee_collector = "fee_collector".into_addr();
+  let init_msg = InstantiateMessage {
+    chain_id: 1,
+    caip2: "cosmos:cosmoshub-4".to_string(),
+    chainbills_fee_collector: fee_collector.to_string(),
+  };
+  let contract = code_id.instantiate(init_msg).call(&owner).unwrap();
+  let host = "host".into_addr();
+
+  // Support a native token.
+  contract
+    .update_max_withdrawal_fees(UpdateMaxWithdrawalFeesMessage {
+      token: "uatom".to_string(),
+      max_withdrawal_fees: Uint128::new(100),
+      is_native_token: true,
+    })
+    .call(&owner)
+    .unwrap();
+
+  // Creating a payable broadcasts its allowed tokens and amounts.
+  let resp = contract
+    .create_payable(CreatePayableMessage {
+      allowed_tokens_and_amounts: vec![TokenAndAmount {
+        token: "uatom".to_string(),
+        amount: Uint128::new(5_000_000),
+      }],
+    })
+    .call(&host)
+    .unwrap();
+  let payable_id = attribute(&resp, "payable_id");
+  let created = payload(&resp);
+  assert_eq!(created.action_type, PayablePayload::ACTION_CREATED);
+  assert_eq!(HexBinary::from(&created.payable_id).to_hex(), payable_id);
+  assert_eq!(created.nonce, 1);
+  assert_eq!(created.allowed_tokens_and_amounts.len(), 1);
+  assert_eq!(
+    created.allowed_tokens_and_amounts[0].token,
+    <[u8; 32]>::from(Sha256::digest(b"uatom"))
+  );
+  assert_eq!(created.allowed_tokens_and_amounts[0].amount, 5_000_000);
+
+  // Closing the payable broadcasts its status with the next nonce.
+  let resp = contract
+    .close_payable(IdMessage {
+      id: payable_id.clone(),
+    })
+    .call(&host)
+    .unwrap();
+  let closed = payload(&resp);
+  assert_eq!(closed.action_type, PayablePayload::ACTION_CLOSED);
+  assert_eq!(closed.nonce, 2);
+  assert!(closed.is_closed);
+
+  // Same for reopening it.
+  let resp = contract
+    .reopen_payable(IdMessage { id: payable_id })
+    .call(&host)
+    .unwrap();
+  let reopened = payload(&resp);
+  assert_eq!(reopened.action_type, PayablePayload::ACTION_REOPENED);
+  assert_eq!(reopened.nonce, 3);
+  assert!(!reopened.is_closed);
+}
@@ -0,0 +1,223 @@
+use crate::contract::sv::mt::CodeId;
+use crate::error::ChainbillsError;
+use crate::interfaces::activities::sv::mt::ActivitiesProxy;
+use crate::interfaces::escrows::sv::mt::EscrowsProxy;
+use crate::interfaces::payables::sv::mt::PayablesProxy;
+use crate::interfaces::payments::sv::mt::PaymentsProxy;
+use crate::interfaces::token_details::sv::mt::TokenDetailsInterfaceProxy;
+use crate::messages::{
+  CreatePayableMessage, FetchIdMessage, IdMessage, InstantiateMessage,
+  TransactionInfoMessage, UpdateMaxWithdrawalFeesMessage,
+  UpdatePayableEscrowMessage,
+};
+use crate::state::{ActivityType, EscrowStatus};
+use sylvia::cw_multi_test::IntoAddr;
+use sylvia::cw_std::{coins, Uint128};
+use sylvia::multitest::App;
+
+const DAY: u64 = 24 * 60 * 60;
+
+#[test]
+fn escrowing() {
+  let owner = "owner".into_addr();
+  let host = "host".into_addr();
+  let payer = "payer".into_addr();
+  let arbiter = "arbiter".into_addr();
+  let stranger = "stranger".into_addr();
+
+  let app =
+    App::new(sylvia::cw_multi_test::App::new(|router, _api, storage| {
+      router
+        .bank
+        .init_balance(storage, &payer, coins(100, "native"))
+        .unwrap();
+    }));
+  let code_id = CodeId::store_code(&app);
+
+  let init_msg = InstantiateMessage {
+    chain_id: 1,
+    caip2: "cosmos:cosmoshub-4".to_string(),
+    chainbills_fee_collector: "fee_collector".into_addr().to_string(),
+  };
+  let contract = code_id.instantiate(init_msg).call(&owner).unwrap();
+  contract
+    .update_max_withdrawal_fees(UpdateMaxWithdrawalFeesMessage {
+      token: "native".to_string(),
+      max_withdrawal_fees: Uint128::new(100),
+      is_native_token: true,
+    })
+    .call(&owner)
+    .unwrap();
+
+  // Create a Payable.
+  let payable_resp = contract
+    .create_payable(CreatePayableMessage {
+      allowed_tokens_and_amounts: vec![],
+    })
+    .call(&host)
+    .unwrap();
+  let payable_id = payable_resp
+    .events
+    .iter()
+    .find(|ev| ev.ty == "wasm")
+    .unwrap()
+    .attributes
+    .iter()
+    .find(|attr| attr.key == "payable_id")
+    .unwrap()
+    .value
+    .clone();
+  let id_msg = IdMessage {
+    id: payable_id.clone(),
+  };
+  let escrow_msg = |timeout: u64| UpdatePayableEscrowMessage {
+    payable_id: payable_id.clone(),
+    timeout,
+    arbiter: Some(arbiter.to_string()),
+  };
+
+  // Pays 10 native tokens and returns the escrow's (payment's) ID.
+  let pay = || {
+    let resp = contract
+      .pay(TransactionInfoMessage {
+        payable_id: payable_id.clone(),
+        token: "native".to_string(),
+        amount: Uint128::new(10),
+      })
+      .with_funds(&coins(10, "native"))
+      .call(&payer)
+      .unwrap();
+    let attrs = &resp
+      .events
+      .iter()
+      .find(|ev| ev.ty == "wasm")
+      .unwrap()
+      .attributes;
+    assert!(attrs.iter().any(|attr| attr.key == "escrow_release_after"));
+    IdMessage {
+      id: attrs
+        .iter()
+        .find(|attr| attr.key == "payable_payment_id")
+        .unwrap()
+        .value
+        .clone(),
+    }
+  };
+  let balance = || {
+    let payable = contract.payable(id_msg.clone()).unwrap();
+    payable
+      .balances
+      .first()
+      .map(|b| b.amount)
+      .unwrap_or_default()
+  };
+
+  // Only the host (or a manager) can make a payable escrow its payments.
+  let err = contract
+    .update_payable_escrow(escrow_msg(7 * DAY))
+    .call(&stranger)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::NotYourPayable {});
+  contract
+    .update_payable_escrow(escrow_msg(7 * DAY))
+    .call(&host)
+    .unwrap();
+  let escrow = contract.payable_escrow(id_msg.clone()).unwrap();
+  assert_eq!(escrow.timeout, 7 * DAY);
+  assert_eq!(escrow.arbiter, Some(arbiter.clone()));
+
+  // Payments are locked until the payer releases them.
+  let first = pay();
+  assert_eq!(balance(), Uint128::zero());
+  let escrow_state = contract.escrow_state(first.clone()).unwrap();
+  assert_eq!(escrow_state.status, EscrowStatus::Locked);
+  assert_eq!(escrow_state.payer, payer);
+  let release_after = app.block_info().time.seconds() + 7 * DAY;
+  assert_eq!(escrow_state.release_after, release_after);
+  let err = contract
+    .release_escrow(first.clone())
+    .call(&stranger)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::EscrowNotReleasable { release_after });
+  contract.release_escrow(first.clone()).call(&payer).unwrap();
+  assert_eq!(balance(), Uint128::new(10));
+  let err = contract
+    .release_escrow(first.clone())
+    .call(&payer)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::EscrowIsSettled {});
+  let user_activity_id = contract
+    .user_activity_id(FetchIdMessage {
+      reference: payer.to_string(),
+      count: 3,
+    })
+    .unwrap();
+  let activity = contract.activity(user_activity_id).unwrap();
+  assert_eq!(activity.activity_type, ActivityType::ReleasedEscrow);
+
+  // Disputed payments wait for the arbiter, who can refund them.
+  let second = pay();
+  let err = contract
+    .dispute_escrow(second.clone())
+    .call(&stranger)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::NotYourEscrow {});
+  contract
+    .dispute_escrow(second.clone())
+    .call(&payer)
+    .unwrap();
+  app.update_block(|block| block.time = block.time.plus_seconds(7 * DAY));
+  let err = contract
+    .release_escrow(second.clone())
+    .call(&stranger)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::EscrowIsDisputed {});
+  let err = contract
+    .refund_escrow(second.clone())
+    .call(&stranger)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::NotYourEscrow {});
+  contract
+    .refund_escrow(second.clone())
+    .call(&arbiter)
+    .unwrap();
+  let escrow_state = contract.escrow_state(second).unwrap();
+  assert_eq!(escrow_state.status, EscrowStatus::Refunded);
+  assert_eq!(balance(), Uint128::new(10));
+  assert_eq!(
+    app
+      .querier()
+      .query_balance(&payer, "native")
+      .unwrap()
+      .amount,
+    Uint128::new(90)
+  );
+
+  // Undisputed payments are released by anyone after the timeout.
+  let third = pay();
+  app.update_block(|block| block.time = block.time.plus_seconds(7 * DAY));
+  let err = contract
+    .dispute_escrow(third.clone())
+    .call(&payer)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::EscrowDisputeWindowClosed {});
+  contract.release_escrow(third).call(&stranger).unwrap();
+  assert_eq!(balance(), Uint128::new(20));
+
+  // A zero timeout stops escrowing new payments.
+  contract
+    .update_payable_escrow(escrow_msg(0))
+    .call(&host)
+    .unwrap();
+  assert_eq!(contract.payable_escrow(id_msg.clone()).unwrap().timeout, 0);
+  contract
+    .pay(TransactionInfoMessage {
+      payable_id: payable_id.clone(),
+      token: "native".to_string(),
+      amount: Uint128::new(10),
+    })
+    .with_funds(&coins(10, "native"))
+    .call(&payer)
+    .unwrap();
+  assert_eq!(balance(), Uint128::new(30));
+}
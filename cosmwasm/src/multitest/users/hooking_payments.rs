@@ -0,0 +1,387 @@
+use crate::contract::sv::mt::CodeId;
+use crate::error::ChainbillsError;
+use crate::interfaces::activities::sv::mt::ActivitiesProxy;
+use crate::interfaces::hooks::sv::mt::HooksProxy;
+use crate::interfaces::payables::sv::mt::PayablesProxy;
+use crate::interfaces::payments::sv::mt::PaymentsProxy;
+use crate::interfaces::token_details::sv::mt::TokenDetailsInterfaceProxy;
+use crate::messages::{
+  ChainbillsPaymentNotification, CreatePayableMessage, FetchIdMessage,
+  IdMessage, InstantiateMessage, PayWithProofMessage,
+  PaymentHookExecuteMessage, UpdateMaxWithdrawalFeesMessage,
+  UpdatePayableHookMessage,
+};
+use crate::state::{ActivityType, PayableHook};
+use cosmwasm_schema::cw_serde;
+use cw_storage_plus::Item;
+use sylvia::cw_multi_test::{Contract, ContractWrapper, Executor, IntoAddr};
+use sylvia::cw_std::{
+  coins, to_json_binary, Binary, Deps, DepsMut, Empty, Env, MessageInfo,
+  Response, StdError, StdResult, Uint128,
+};
+use sylvia::multitest::App;
+
+/// The notifications received by the merchant contract.
+const NOTIFICATIONS: Item<Vec<ChainbillsPaymentNotification>> =
+  Item::new("notifications");
+
+/// The amount that the merchant contract refuses.
+const REFUSED_AMOUNT: u128 = 13;
+
+/// The gas given to the hooks.
+const GAS_LIMIT: u64 = 200_000;
+
+/// The storage writes that the burner contract makes before failing.
+const BURNED_WRITES: u32 = 10_000;
+
+fn merchant_instantiate(
+  deps: DepsMut,
+  _env: Env,
+  _info: MessageInfo,
+  _msg: Empty,
+) -> StdResult<Response> {
+  NOTIFICATIONS.save(deps.storage, &vec![])?;
+  Ok(Response::new())
+}
+
+fn merchant_execute(
+  deps: DepsMut,
+  _env: Env,
+  _info: MessageInfo,
+  msg: PaymentHookExecuteMessage,
+) -> StdResult<Response> {
+  let PaymentHookExecuteMessage::ChainbillsPaymentNotification(notification) =
+    msg;
+  if notification.amount == Uint128::new(REFUSED_AMOUNT) {
+    return Err(StdError::generic_err("Refused Amount"));
+  }
+  NOTIFICATIONS.update(deps.storage, |mut notifications| {
+    notifications.push(notification);
+    StdResult::Ok(notifications)
+  })?;
+  Ok(Response::new())
+}
+
+/// Burns gas until it runs out. The multitest app doesn't meter gas, so the
+/// burner stops after BURNED_WRITES writes and fails the way wasmd fails a
+/// submessage that ran out of its gas limit.
+fn burner_execute(
+  deps: DepsMut,
+  _env: Env,
+  _info: MessageInfo,
+  _msg: PaymentHookExecuteMessage,
+) -> StdResult<Response> {
+  for i in 0..BURNED_WRITES {
+    deps.storage.set(&i.to_be_bytes(), &[0u8; 1024]);
+  }
+  Err(StdError::generic_err("out of gas"))
+}
+
+fn merchant_query(deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+  to_json_binary(&NOTIFICATIONS.load(deps.storage)?)
+}
+
+fn contract_merchant() -> Box<dyn Contract<Empty>> {
+  Box::new(ContractWrapper::new(
+    merchant_execute,
+    merchant_instantiate,
+    merchant_query,
+  ))
+}
+
+fn contract_burner() -> Box<dyn Contract<Empty>> {
+  Box::new(ContractWrapper::new(
+    burner_execute,
+    merchant_instantiate,
+    merchant_query,
+  ))
+}
+
+#[cw_serde]
+/// The Chainbills execute message for paying, as sent by wallets. Used for
+/// payments that fail in hooks, whose errors aren't ChainbillsErrors.
+enum PayExecuteMessage {
+  PayWithProof { data: PayWithProofMessage },
+}
+
+#[test]
+fn hooking_payments() {
+  let owner = "owner".into_addr();
+  let host = "host".into_addr();
+  let payer = "payer".into_addr();
+  let stranger = "stranger".into_addr();
+
+  let mut app = sylvia::cw_multi_test::App::new(|router, _api, storage| {
+    router
+      .bank
+      .init_balance(storage, &payer, coins(100, "native"))
+      .unwrap();
+  });
+  let merchant_id = app.store_code(contract_merchant());
+  let merchant = app
+    .instantiate_contract(
+      merchant_id,
+      host.clone(),
+      &Empty {},
+      &[],
+      "Merchant",
+      None,
+    )
+    .unwrap();
+  let burner_id = app.store_code(contract_burner());
+  let burner = app
+    .instantiate_contract(
+      burner_id,
+      host.clone(),
+      &Empty {},
+      &[],
+      "Burner",
+      None,
+    )
+    .unwrap();
+
+  let app = App::new(app);
+  let code_id = CodeId::store_code(&app);
+  let init_msg = InstantiateMessage {
+    chain_id: 1,
+    caip2: "cosmos:cosmoshub-4".to_string(),
+    chainbills_fee_collector: "fee_collector".into_addr().to_string(),
+  };
+  let contract = code_id.instantiate(init_msg).call(&owner).unwrap();
+  contract
+    .update_max_withdrawal_fees(UpdateMaxWithdrawalFeesMessage {
+      token: "native".to_string(),
+      max_withdrawal_fees: Uint128::new(100),
+      is_native_token: true,
+    })
+    .call(&owner)
+    .unwrap();
+
+  // Create a Payable.
+  let payable_resp = contract
+    .create_payable(CreatePayableMessage {
+      allowed_tokens_and_amounts: vec![],
+    })
+    .call(&host)
+    .unwrap();
+  let payable_id = payable_resp
+    .events
+    .iter()
+    .find(|ev| ev.ty == "wasm")
+    .unwrap()
+    .attributes
+    .iter()
+    .find(|attr| attr.key == "payable_id")
+    .unwrap()
+    .value
+    .clone();
+  let id_msg = IdMessage {
+    id: payable_id.clone(),
+  };
+  let hook_msg =
+    |contract: &str, revert_on_error: bool| UpdatePayableHookMessage {
+      payable_id: payable_id.clone(),
+      contract: Some(contract.to_string()),
+      revert_on_error,
+      gas_limit: GAS_LIMIT,
+    };
+  let pay_msg = |amount: u128, reference: &str| PayWithProofMessage {
+    payable_id: payable_id.clone(),
+    token: "native".to_string(),
+    amount: Uint128::new(amount),
+    payer_proof: vec![],
+    reference: Some(reference.to_string()),
+  };
+  let notifications = || -> Vec<ChainbillsPaymentNotification> {
+    app
+      .querier()
+      .query_wasm_smart(&merchant, &Empty {})
+      .unwrap()
+  };
+  let payments_count =
+    || contract.payable(id_msg.clone()).unwrap().payments_count;
+
+  // Only the host (or a manager) can set a payable's hook, and only to a
+  // contract.
+  let err = contract
+    .update_payable_hook(hook_msg(merchant.as_str(), true))
+    .call(&stranger)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::NotYourPayable {});
+  let err = contract
+    .update_payable_hook(hook_msg(stranger.as_str(), true))
+    .call(&host)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::InvalidHookContract {});
+  for gas_limit in [0, PayableHook::MAX_GAS_LIMIT + 1] {
+    let err = contract
+      .update_payable_hook(UpdatePayableHookMessage {
+        gas_limit,
+        ..hook_msg(merchant.as_str(), true)
+      })
+      .call(&host)
+      .unwrap_err();
+    assert_eq!(err, ChainbillsError::InvalidHookGasLimit { gas_limit });
+  }
+  contract
+    .update_payable_hook(hook_msg(merchant.as_str(), true))
+    .call(&host)
+    .unwrap();
+  let hook = contract.payable_hook(id_msg.clone()).unwrap().unwrap();
+  assert_eq!(hook.contract, merchant);
+  assert!(hook.revert_on_error);
+  assert_eq!(hook.gas_limit, GAS_LIMIT);
+  let user_activity_id = contract
+    .user_activity_id(FetchIdMessage {
+      reference: host.to_string(),
+      count: 3,
+    })
+    .unwrap();
+  let activity = contract.activity(user_activity_id).unwrap();
+  assert_eq!(activity.activity_type, ActivityType::UpdatedPayableHook);
+
+  // The hook is notified of each payment with its reference.
+  let resp = contract
+    .pay_with_proof(pay_msg(10, "order-1"))
+    .with_funds(&coins(10, "native"))
+    .call(&payer)
+    .unwrap();
+  let payment_id = resp
+    .events
+    .iter()
+    .find(|ev| ev.ty == "wasm")
+    .unwrap()
+    .attributes
+    .iter()
+    .find(|attr| attr.key == "payable_payment_id")
+    .unwrap()
+    .value
+    .clone();
+  assert_eq!(
+    notifications(),
+    vec![ChainbillsPaymentNotification {
+      payable_id: payable_id.clone(),
+      payment_id,
+      payer: payer.to_string(),
+      token: "native".to_string(),
+      amount: Uint128::new(10),
+      reference: Some("order-1".to_string()),
+    }]
+  );
+
+  // A failing hook reverts the payment if the payable chose so.
+  app
+    .app_mut()
+    .execute_contract(
+      payer.clone(),
+      contract.contract_addr.clone(),
+      &PayExecuteMessage::PayWithProof {
+        data: pay_msg(REFUSED_AMOUNT, "order-2"),
+      },
+      &coins(REFUSED_AMOUNT, "native"),
+    )
+    .unwrap_err();
+  assert_eq!(payments_count(), 1);
+  assert_eq!(
+    app
+      .querier()
+      .query_balance(&payer, "native")
+      .unwrap()
+      .amount,
+    Uint128::new(90)
+  );
+
+  // Otherwise, the failure is recorded and the payment goes through.
+  contract
+    .update_payable_hook(hook_msg(merchant.as_str(), false))
+    .call(&host)
+    .unwrap();
+  let resp = contract
+    .pay_with_proof(pay_msg(REFUSED_AMOUNT, "order-2"))
+    .with_funds(&coins(REFUSED_AMOUNT, "native"))
+    .call(&payer)
+    .unwrap();
+  assert_eq!(payments_count(), 2);
+  assert_eq!(notifications().len(), 1);
+  let failed_event = resp
+    .events
+    .iter()
+    .find(|ev| {
+      ev.attributes
+        .iter()
+        .any(|attr| attr.value == "payment_hook_failed")
+    })
+    .unwrap();
+  let payment_id = failed_event
+    .attributes
+    .iter()
+    .find(|attr| attr.key == "payable_payment_id")
+    .unwrap()
+    .value
+    .clone();
+  let failure = contract
+    .payment_hook_failure(IdMessage { id: payment_id })
+    .unwrap();
+  assert_eq!(failure.hook, merchant);
+  assert!(failure.error.contains("Refused Amount"));
+  assert_eq!(
+    contract
+      .token_details(IdMessage {
+        id: "native".to_string(),
+      })
+      .unwrap()
+      .total_payable_received,
+    Uint128::new(10 + REFUSED_AMOUNT)
+  );
+
+  // A hook that burns all its gas only fails its notification.
+  contract
+    .update_payable_hook(hook_msg(burner.as_str(), false))
+    .call(&host)
+    .unwrap();
+  let resp = contract
+    .pay_with_proof(pay_msg(10, "order-3"))
+    .with_funds(&coins(10, "native"))
+    .call(&payer)
+    .unwrap();
+  assert_eq!(payments_count(), 3);
+  let payment_id = resp
+    .events
+    .iter()
+    .find(|ev| {
+      ev.attributes
+        .iter()
+        .any(|attr| attr.value == "payment_hook_failed")
+    })
+    .unwrap()
+    .attributes
+    .iter()
+    .find(|attr| attr.key == "payable_payment_id")
+    .unwrap()
+    .value
+    .clone();
+  let failure = contract
+    .payment_hook_failure(IdMessage { id: payment_id })
+    .unwrap();
+  assert_eq!(failure.hook, burner);
+  assert!(failure.error.contains("out of gas"));
+
+  // Removing the hook stops the notifications.
+  contract
+    .update_payable_hook(UpdatePayableHookMessage {
+      payable_id: payable_id.clone(),
+      contract: None,
+      revert_on_error: false,
+      gas_limit: 0,
+    })
+    .call(&host)
+    .unwrap();
+  assert_eq!(contract.payable_hook(id_msg.clone()).unwrap(), None);
+  contract
+    .pay_with_proof(pay_msg(10, "order-4"))
+    .with_funds(&coins(10, "native"))
+    .call(&payer)
+    .unwrap();
+  assert_eq!(payments_count(), 4);
+  assert_eq!(notifications().len(), 1);
+}
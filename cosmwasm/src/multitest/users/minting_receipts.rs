@@ -0,0 +1,277 @@
+use crate::contract::sv::mt::CodeId;
+use crate::error::ChainbillsError;
+use crate::interfaces::payables::sv::mt::PayablesProxy;
+use crate::interfaces::payments::sv::mt::PaymentsProxy;
+use crate::interfaces::receipts::sv::mt::ReceiptsProxy;
+use crate::interfaces::token_details::sv::mt::TokenDetailsInterfaceProxy;
+use crate::messages::{
+  CreatePayableMessage, Cw721ExecuteMessage, FetchIdMessage, IdMessage,
+  InstantiateMessage, TransactionInfoMessage, UpdateMaxWithdrawalFeesMessage,
+  UpdatePayableReceiptsMessage, UpdateReceiptsCollectionMessage,
+};
+use cosmwasm_schema::cw_serde;
+use cw_storage_plus::{Item, Map};
+use sylvia::cw_multi_test::{Contract, ContractWrapper, Executor, IntoAddr};
+use sylvia::cw_std::{
+  coins, to_json_binary, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Order,
+  Response, StdError, StdResult, Uint128,
+};
+use sylvia::multitest::App;
+
+/// The only address that can mint in the collection.
+const MINTER: Item<String> = Item::new("minter");
+
+/// The owners of the collection's tokens.
+const OWNERS: Map<String, String> = Map::new("owners");
+
+#[cw_serde]
+/// Instantiates a minimal cw721 collection.
+struct CollectionInstantiateMessage {
+  minter: String,
+}
+
+fn collection_instantiate(
+  deps: DepsMut,
+  _env: Env,
+  _info: MessageInfo,
+  msg: CollectionInstantiateMessage,
+) -> StdResult<Response> {
+  MINTER.save(deps.storage, &msg.minter)?;
+  Ok(Response::new())
+}
+
+fn collection_execute(
+  deps: DepsMut,
+  _env: Env,
+  info: MessageInfo,
+  msg: Cw721ExecuteMessage,
+) -> StdResult<Response> {
+  let Cw721ExecuteMessage::Mint {
+    token_id, owner, ..
+  } = msg;
+  if info.sender.as_str() != MINTER.load(deps.storage)? {
+    return Err(StdError::generic_err("Unauthorized Minter"));
+  }
+  if OWNERS.has(deps.storage, token_id.clone()) {
+    return Err(StdError::generic_err("Token Already Minted"));
+  }
+  OWNERS.save(deps.storage, token_id, &owner)?;
+  Ok(Response::new())
+}
+
+fn collection_query(deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+  let owners: Vec<(String, String)> = OWNERS
+    .range(deps.storage, None, None, Order::Ascending)
+    .collect::<StdResult<_>>()?;
+  to_json_binary(&owners)
+}
+
+fn contract_collection() -> Box<dyn Contract<Empty>> {
+  Box::new(ContractWrapper::new(
+    collection_execute,
+    collection_instantiate,
+    collection_query,
+  ))
+}
+
+#[test]
+fn minting_receipts() {
+  let owner = "owner".into_addr();
+  let host = "host".into_addr();
+  let payer = "payer".into_addr();
+  let stranger = "stranger".into_addr();
+
+  let mut app = sylvia::cw_multi_test::App::new(|router, _api, storage| {
+    router
+      .bank
+      .init_balance(storage, &payer, coins(100, "native"))
+      .unwrap();
+  });
+  let collection_id = app.store_code(contract_collection());
+
+  let app = App::new(app);
+  let code_id = CodeId::store_code(&app);
+  let init_msg = InstantiateMessage {
+    chain_id: 1,
+    caip2: "cosmos:cosmoshub-4".to_string(),
+    chainbills_fee_collector: "fee_collector".into_addr().to_string(),
+  };
+  let contract = code_id.instantiate(init_msg).call(&owner).unwrap();
+  contract
+    .update_max_withdrawal_fees(UpdateMaxWithdrawalFeesMessage {
+      token: "native".to_string(),
+      max_withdrawal_fees: Uint128::new(100),
+      is_native_token: true,
+    })
+    .call(&owner)
+    .unwrap();
+
+  // Instantiate the receipts collection with Chainbills as its minter.
+  let collection = app
+    .app_mut()
+    .instantiate_contract(
+      collection_id,
+      owner.clone(),
+      &CollectionInstantiateMessage {
+        minter: contract.contract_addr.to_string(),
+      },
+      &[],
+      "Receipts",
+      None,
+    )
+    .unwrap();
+
+  // Create a Payable.
+  let payable_resp = contract
+    .create_payable(CreatePayableMessage {
+      allowed_tokens_and_amounts: vec![],
+    })
+    .call(&host)
+    .unwrap();
+  let payable_id = payable_resp
+    .events
+    .iter()
+    .find(|ev| ev.ty == "wasm")
+    .unwrap()
+    .attributes
+    .iter()
+    .find(|attr| attr.key == "payable_id")
+    .unwrap()
+    .value
+    .clone();
+  let receipts_msg = |enabled: bool| UpdatePayableReceiptsMessage {
+    payable_id: payable_id.clone(),
+    enabled,
+  };
+  let pay = || {
+    contract
+      .pay(TransactionInfoMessage {
+        payable_id: payable_id.clone(),
+        token: "native".to_string(),
+        amount: Uint128::new(10),
+      })
+      .with_funds(&coins(10, "native"))
+      .call(&payer)
+      .unwrap()
+  };
+  let minted = || -> Vec<(String, String)> {
+    app
+      .querier()
+      .query_wasm_smart(&collection, &Empty {})
+      .unwrap()
+  };
+
+  // Receipts need a collection set by the owner.
+  let err = contract
+    .update_payable_receipts(receipts_msg(true))
+    .call(&host)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::ReceiptsCollectionNotSet {});
+  let err = contract
+    .update_receipts_collection(UpdateReceiptsCollectionMessage {
+      collection: collection.to_string(),
+    })
+    .call(&stranger)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::OwnerUnauthorized {});
+  let err = contract
+    .update_receipts_collection(UpdateReceiptsCollectionMessage {
+      collection: stranger.to_string(),
+    })
+    .call(&owner)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::InvalidReceiptsCollection {});
+  contract
+    .update_receipts_collection(UpdateReceiptsCollectionMessage {
+      collection: collection.to_string(),
+    })
+    .call(&owner)
+    .unwrap();
+  assert_eq!(
+    contract.receipts_collection().unwrap(),
+    Some(collection.clone())
+  );
+
+  // Payments into payables that don't mint receipts get none.
+  pay();
+  assert!(minted().is_empty());
+
+  // Only the host (or a manager) can enable receipts.
+  let err = contract
+    .update_payable_receipts(receipts_msg(true))
+    .call(&stranger)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::NotYourPayable {});
+  contract
+    .update_payable_receipts(receipts_msg(true))
+    .call(&host)
+    .unwrap();
+  assert!(contract
+    .payable_receipts(IdMessage {
+      id: payable_id.clone(),
+    })
+    .unwrap());
+
+  // Each payment mints a receipt to its payer, which resolves to it.
+  let resp = pay();
+  let user_payment_id = resp
+    .events
+    .iter()
+    .find(|ev| ev.ty == "wasm")
+    .unwrap()
+    .attributes
+    .iter()
+    .find(|attr| attr.key == "user_payment_id")
+    .unwrap()
+    .value
+    .clone();
+  assert_eq!(minted(), vec![(user_payment_id.clone(), payer.to_string())]);
+  let user_payment = contract
+    .receipt_payment(IdMessage {
+      id: user_payment_id.clone(),
+    })
+    .unwrap();
+  assert_eq!(
+    user_payment,
+    contract
+      .user_payment(IdMessage {
+        id: user_payment_id,
+      })
+      .unwrap()
+  );
+  assert_eq!(user_payment.payer, payer);
+  assert_eq!(user_payment.payer_count, 2);
+  assert_eq!(user_payment.details.amount, Uint128::new(10));
+
+  // Payments without receipts don't resolve.
+  let first_payment_id = contract
+    .user_payment_id(FetchIdMessage {
+      reference: payer.to_string(),
+      count: 1,
+    })
+    .unwrap()
+    .id;
+  let err = contract
+    .receipt_payment(IdMessage {
+      id: first_payment_id,
+    })
+    .unwrap_err();
+  assert!(err.to_string().contains("Invalid Receipt ID"));
+
+  // Disabling receipts stops minting them.
+  contract
+    .update_payable_receipts(receipts_msg(false))
+    .call(&host)
+    .unwrap();
+  pay();
+  assert_eq!(minted().len(), 1);
+  assert_eq!(
+    contract
+      .token_details(IdMessage {
+        id: "native".to_string(),
+      })
+      .unwrap()
+      .total_user_paid,
+    Uint128::new(30)
+  );
+}
@@ -1,3 +1,14 @@
 mod creating_payables;
+mod escrowing;
+mod hooking_payments;
 mod making_payments;
 mod making_withdrawals;
+mod minting_receipts;
+mod operating_payables;
+mod paying_in_batches;
+mod paying_with_intents;
+mod paying_with_token_groups;
+mod restricting_payers;
+mod subscribing;
+mod transferring_payables;
+mod withdrawing_cross_chain;
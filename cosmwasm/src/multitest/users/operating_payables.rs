@@ -0,0 +1,216 @@
+use crate::contract::sv::mt::{ChainbillsProxy, CodeId};
+use crate::error::ChainbillsError;
+use crate::interfaces::payables::sv::mt::PayablesProxy;
+use crate::interfaces::payments::sv::mt::PaymentsProxy;
+use crate::interfaces::token_details::sv::mt::TokenDetailsInterfaceProxy;
+use crate::interfaces::withdrawals::sv::mt::WithdrawalsProxy;
+use crate::messages::{
+  CreatePayableMessage, FetchIdMessage, IdMessage, InstantiateMessage,
+  PayableOperatorMessage, SetPayableOperatorMessage, TransactionInfoMessage,
+  TransferPayableMessage, UpdateMaxWithdrawalFeesMessage,
+};
+use crate::state::PayableOperator;
+use sylvia::cw_multi_test::IntoAddr;
+use sylvia::cw_std::{coins, Uint128};
+use sylvia::multitest::App;
+
+#[test]
+fn operating_payables() {
+  let owner = "owner".into_addr();
+  let host = "host".into_addr();
+  let manager = "manager".into_addr();
+  let withdrawer = "withdrawer".into_addr();
+  let new_host = "new_host".into_addr();
+
+  let app =
+    App::new(sylvia::cw_multi_test::App::new(|router, _api, storage| {
+      router
+        .bank
+        .init_balance(storage, &owner, coins(100, "native"))
+        .unwrap();
+    }));
+  let code_id = CodeId::store_code(&app);
+
+  let fee_collector = "fee_collector".into_addr();
+  let init_msg = InstantiateMessage {
+    chain_id: 1,
+    caip2: "cosmos:cosmoshub-4".to_string(),
+    chainbills_fee_collector: fee_collector.to_string(),
+  };
+  let contract = code_id.instantiate(init_msg).call(&owner).unwrap();
+  contract
+    .update_max_withdrawal_fees(UpdateMaxWithdrawalFeesMessage {
+      token: "native".to_string(),
+      max_withdrawal_fees: Uint128::new(100),
+      is_native_token: true,
+    })
+    .call(&owner)
+    .unwrap();
+
+  // Create a Payable and pay into it.
+  let payable_resp = contract
+    .create_payable(CreatePayableMessage {
+      allowed_tokens_and_amounts: vec![],
+    })
+    .call(&host)
+    .unwrap();
+  let payable_id = payable_resp
+    .events
+    .iter()
+    .find(|ev| ev.ty == "wasm")
+    .unwrap()
+    .attributes
+    .iter()
+    .find(|attr| attr.key == "payable_id")
+    .unwrap()
+    .value
+    .clone();
+  let id_msg = IdMessage {
+    id: payable_id.clone(),
+  };
+  let tx_info = TransactionInfoMessage {
+    payable_id: payable_id.clone(),
+    token: "native".to_string(),
+    amount: Uint128::new(50),
+  };
+  contract
+    .pay(TransactionInfoMessage {
+      amount: Uint128::new(100),
+      ..tx_info.clone()
+    })
+    .with_funds(&coins(100, "native"))
+    .call(&owner)
+    .unwrap();
+
+  // Only the host can set operators, and only with valid roles.
+  let set_msg =
+    |operator: &sylvia::cw_std::Addr, roles: u8| SetPayableOperatorMessage {
+      payable_id: payable_id.clone(),
+      operator: operator.to_string(),
+      roles,
+    };
+  let err = contract
+    .set_payable_operator(set_msg(&manager, PayableOperator::ROLE_MANAGE))
+    .call(&manager)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::NotYourPayable {});
+  for roles in [0, 1 << 2] {
+    let err = contract
+      .set_payable_operator(set_msg(&manager, roles))
+      .call(&host)
+      .unwrap_err();
+    assert_eq!(err, ChainbillsError::InvalidOperatorRoles { roles });
+  }
+  contract
+    .set_payable_operator(set_msg(&manager, PayableOperator::ROLE_MANAGE))
+    .call(&host)
+    .unwrap();
+  contract
+    .set_payable_operator(set_msg(&withdrawer, PayableOperator::ROLE_WITHDRAW))
+    .call(&host)
+    .unwrap();
+  let operator = contract
+    .payable_operator(PayableOperatorMessage {
+      payable_id: payable_id.clone(),
+      operator: manager.to_string(),
+    })
+    .unwrap();
+  assert_eq!(operator.host, host);
+  assert_eq!(operator.roles, PayableOperator::ROLE_MANAGE);
+
+  // Managers can close and reopen but not withdraw. The host keeps the
+  // activities.
+  let err = contract
+    .close_payable(id_msg.clone())
+    .call(&withdrawer)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::NotYourPayable {});
+  contract
+    .close_payable(id_msg.clone())
+    .call(&manager)
+    .unwrap();
+  contract
+    .reopen_payable(id_msg.clone())
+    .call(&manager)
+    .unwrap();
+  let host_data = contract
+    .user(IdMessage {
+      id: host.to_string(),
+    })
+    .unwrap();
+  assert_eq!(host_data.activities_count, 4);
+  let err = contract
+    .withdraw(tx_info.clone())
+    .call(&manager)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::NotYourPayable {});
+
+  // Withdrawers withdraw to the host's wallet.
+  contract
+    .withdraw(tx_info.clone())
+    .call(&withdrawer)
+    .unwrap();
+  let host_balance = app.querier().query_balance(&host, "native").unwrap();
+  let fees_balance = app
+    .querier()
+    .query_balance(&fee_collector, "native")
+    .unwrap();
+  assert_eq!(host_balance.amount + fees_balance.amount, Uint128::new(50));
+  assert!(app
+    .querier()
+    .query_balance(&withdrawer, "native")
+    .unwrap()
+    .amount
+    .is_zero());
+  let withdrawal_id = contract
+    .user_withdrawal_id(FetchIdMessage {
+      reference: host.to_string(),
+      count: 1,
+    })
+    .unwrap();
+  let withdrawal = contract.withdrawal(withdrawal_id).unwrap();
+  assert_eq!(withdrawal.host, host);
+
+  // Removed operators lose their roles.
+  let remove_msg = PayableOperatorMessage {
+    payable_id: payable_id.clone(),
+    operator: withdrawer.to_string(),
+  };
+  contract
+    .remove_payable_operator(remove_msg.clone())
+    .call(&host)
+    .unwrap();
+  let err = contract
+    .withdraw(tx_info.clone())
+    .call(&withdrawer)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::NotYourPayable {});
+  let err = contract
+    .remove_payable_operator(remove_msg)
+    .call(&host)
+    .unwrap_err();
+  assert_eq!(
+    err,
+    ChainbillsError::NotPayableOperator {
+      operator: withdrawer.to_string()
+    }
+  );
+
+  // Operators set by a previous host don't act for the new host.
+  contract
+    .transfer_payable(TransferPayableMessage {
+      payable_id: payable_id.clone(),
+      new_host: new_host.to_string(),
+    })
+    .call(&host)
+    .unwrap();
+  contract
+    .accept_payable(id_msg.clone())
+    .call(&new_host)
+    .unwrap();
+  let err = contract
+    .close_payable(id_msg.clone())
+    .call(&manager)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::NotYourPayable {});
+}
@@ -0,0 +1,247 @@
+use crate::contract::sv::mt::{ChainbillsProxy, CodeId};
+use crate::error::ChainbillsError;
+use crate::interfaces::payables::sv::mt::PayablesProxy;
+use crate::interfaces::payments::sv::mt::PaymentsProxy;
+use crate::interfaces::token_details::sv::mt::TokenDetailsInterfaceProxy;
+use crate::messages::{
+  CreatePayableMessage, FetchIdMessage, IdMessage, InstantiateMessage,
+  PayBatchItemMessage, PayBatchMessage, UpdateMaxWithdrawalFeesMessage,
+};
+use crate::state::TokenAndAmount;
+use cw20::{BalanceResponse, Cw20Coin};
+use cw20_base::msg::InstantiateMsg;
+use sylvia::cw_multi_test::{Contract, ContractWrapper, Executor, IntoAddr};
+use sylvia::cw_std::{coins, Empty, StdResult, Uint128};
+use sylvia::multitest::App;
+
+fn contract_cw20() -> Box<dyn Contract<Empty>> {
+  let contract = ContractWrapper::new(
+    cw20_base::contract::execute,
+    cw20_base::contract::instantiate,
+    cw20_base::contract::query,
+  );
+  Box::new(contract)
+}
+
+#[test]
+fn paying_in_batches() {
+  let owner = "owner".into_addr();
+  let host = "host".into_addr();
+  let payer = "payer".into_addr();
+
+  let mut app = sylvia::cw_multi_test::App::new(|router, _api, storage| {
+    router
+      .bank
+      .init_balance(storage, &payer, coins(100, "native"))
+      .unwrap();
+  });
+  let cw20_id = app.store_code(contract_cw20());
+  let usdc_addr = app
+    .instantiate_contract(
+      cw20_id,
+      owner.clone(),
+      &InstantiateMsg {
+        name: "USDC".to_string(),
+        symbol: "USDC".to_string(),
+        decimals: 6,
+        initial_balances: vec![Cw20Coin {
+          address: payer.to_string(),
+          amount: Uint128::new(100),
+        }],
+        mint: None,
+        marketing: None,
+      },
+      &[],
+      "USDC",
+      None,
+    )
+    .unwrap();
+  let usdc = usdc_addr.to_string();
+
+  let app = App::new(app);
+  let code_id = CodeId::store_code(&app);
+  let init_msg = InstantiateMessage {
+    chain_id: 1,
+    caip2: "cosmos:cosmoshub-4".to_string(),
+    chainbills_fee_collector: "fee_collector".into_addr().to_string(),
+  };
+  let contract = code_id.instantiate(init_msg).call(&owner).unwrap();
+  for (token, is_native_token) in [("native", true), (usdc.as_str(), false)] {
+    contract
+      .update_max_withdrawal_fees(UpdateMaxWithdrawalFeesMessage {
+        token: token.to_string(),
+        max_withdrawal_fees: Uint128::new(100),
+        is_native_token,
+      })
+      .call(&owner)
+      .unwrap();
+  }
+
+  // Approve Spend for Cw20 Token
+  app
+    .app_mut()
+    .execute_contract(
+      payer.clone(),
+      usdc_addr.clone(),
+      &cw20::Cw20ExecuteMsg::IncreaseAllowance {
+        spender: contract.contract_addr.to_string(),
+        amount: Uint128::new(100),
+        expires: None,
+      },
+      &[],
+    )
+    .unwrap();
+
+  // Create two Payables.
+  let mut payable_ids = vec![];
+  for _ in 0..2 {
+    let payable_resp = contract
+      .create_payable(CreatePayableMessage {
+        allowed_tokens_and_amounts: vec![],
+      })
+      .call(&host)
+      .unwrap();
+    payable_ids.push(
+      payable_resp
+        .events
+        .iter()
+        .find(|ev| ev.ty == "wasm")
+        .unwrap()
+        .attributes
+        .iter()
+        .find(|attr| attr.key == "payable_id")
+        .unwrap()
+        .value
+        .clone(),
+    );
+  }
+  let payment = |index: usize, token: &str, amount: u128| PayBatchItemMessage {
+    payable_id: payable_ids[index].clone(),
+    token: token.to_string(),
+    amount: Uint128::new(amount),
+    payer_proof: vec![],
+  };
+  let usdc_balance = |address: String| {
+    (app
+      .querier()
+      .query_wasm_smart(&usdc_addr, &cw20::Cw20QueryMsg::Balance { address })
+      as StdResult<BalanceResponse>)
+      .unwrap()
+      .balance
+  };
+
+  // A batch needs payments.
+  let err = contract
+    .pay_batch(PayBatchMessage { payments: vec![] })
+    .call(&payer)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::EmptyPaymentBatch {});
+
+  // The funds sent must match the native payments' totals.
+  let payments = vec![
+    payment(0, "native", 10),
+    payment(1, "native", 20),
+    payment(0, &usdc, 30),
+    payment(1, &usdc, 40),
+  ];
+  let err = contract
+    .pay_batch(PayBatchMessage {
+      payments: payments.clone(),
+    })
+    .with_funds(&coins(10, "native"))
+    .call(&payer)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::InvalidNativeTokenPayment {});
+
+  // An invalid payment fails the whole batch.
+  let mut invalid = payments.clone();
+  invalid.push(payment(1, "unsupported", 1));
+  let err = contract
+    .pay_batch(PayBatchMessage { payments: invalid })
+    .with_funds(&coins(30, "native"))
+    .call(&payer)
+    .unwrap_err();
+  assert_eq!(
+    err,
+    ChainbillsError::UnsupportedToken {
+      token: "unsupported".to_string()
+    }
+  );
+  assert_eq!(contract.chain_stats().unwrap().user_payments_count, 0);
+
+  // Payable IDs must be 32 bytes.
+  let mut invalid = payments.clone();
+  invalid[0].payable_id = "0102".to_string();
+  let err = contract
+    .pay_batch(PayBatchMessage { payments: invalid })
+    .with_funds(&coins(30, "native"))
+    .call(&payer)
+    .unwrap_err();
+  assert_eq!(
+    err,
+    ChainbillsError::InvalidPayableId {
+      id: "0102".to_string()
+    }
+  );
+
+  // A valid batch records each payment.
+  contract
+    .pay_batch(PayBatchMessage { payments })
+    .with_funds(&coins(30, "native"))
+    .call(&payer)
+    .unwrap();
+  let chain_stats = contract.chain_stats().unwrap();
+  assert_eq!(chain_stats.user_payments_count, 4);
+  assert_eq!(chain_stats.payable_payments_count, 4);
+  for (index, native, cw20) in [(0, 10, 30), (1, 20, 40)] {
+    let payable = contract
+      .payable(IdMessage {
+        id: payable_ids[index].clone(),
+      })
+      .unwrap();
+    assert_eq!(payable.payments_count, 2);
+    assert_eq!(
+      payable.balances,
+      vec![
+        TokenAndAmount {
+          token: "native".to_string(),
+          amount: Uint128::new(native),
+        },
+        TokenAndAmount {
+          token: usdc.clone(),
+          amount: Uint128::new(cw20),
+        },
+      ]
+    );
+  }
+  let user_payment_id = contract
+    .user_payment_id(FetchIdMessage {
+      reference: payer.to_string(),
+      count: 4,
+    })
+    .unwrap();
+  let user_payment = contract.user_payment(user_payment_id).unwrap();
+  assert_eq!(user_payment.details.amount, Uint128::new(40));
+  assert_eq!(
+    contract
+      .token_details(IdMessage { id: usdc.clone() })
+      .unwrap()
+      .total_user_paid,
+    Uint128::new(70)
+  );
+
+  // The funds moved once per token.
+  assert_eq!(
+    app
+      .querier()
+      .query_balance(&payer, "native")
+      .unwrap()
+      .amount,
+    Uint128::new(70)
+  );
+  assert_eq!(usdc_balance(payer.to_string()), Uint128::new(30));
+  assert_eq!(
+    usdc_balance(contract.contract_addr.to_string()),
+    Uint128::new(70)
+  );
+}
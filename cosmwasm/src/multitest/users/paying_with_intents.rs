@@ -0,0 +1,288 @@
+use crate::contract::sv::mt::CodeId;
+use crate::contract::Chainbills;
+use crate::error::ChainbillsError;
+use crate::interfaces::intents::sv::mt::IntentsProxy;
+use crate::interfaces::payables::sv::mt::PayablesProxy;
+use crate::interfaces::payments::sv::mt::PaymentsProxy;
+use crate::interfaces::token_details::sv::mt::TokenDetailsInterfaceProxy;
+use crate::messages::{
+  CreatePayableMessage, IdMessage, InstantiateMessage, PayWithIntentMessage,
+  PaymentIntentMessage, TransactionInfoMessage, UpdateMaxWithdrawalFeesMessage,
+};
+use crate::state::TokenAndAmount;
+use chainbills_payload::{PayloadError, PaymentIntent};
+use k256::ecdsa::signature::Signer;
+use k256::ecdsa::{Signature, SigningKey};
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+use sylvia::cw_multi_test::IntoAddr;
+use sylvia::cw_std::{coins, Api, CanonicalAddr, HexBinary, Uint128};
+use sylvia::multitest::App;
+
+const CAIP2: &str = "cosmos:cosmoshub-4";
+
+#[test]
+fn paying_with_intents() {
+  let owner = "owner".into_addr();
+  let payer = "payer".into_addr();
+
+  let app =
+    App::new(sylvia::cw_multi_test::App::new(|router, _api, storage| {
+      router
+        .bank
+        .init_balance(storage, &payer, coins(100, "native"))
+        .unwrap();
+    }));
+  // The host signs intents with the key of their wallet.
+  let public_key = |key: &SigningKey| {
+    HexBinary::from(key.verifying_key().to_encoded_point(true).as_bytes())
+  };
+  let host_key = SigningKey::from_bytes(&[7u8; 32].into()).unwrap();
+  let host_public_key = public_key(&host_key);
+  let host = app
+    .app()
+    .api()
+    .addr_humanize(&CanonicalAddr::from(
+      Ripemd160::digest(Sha256::digest(&host_public_key)).as_slice(),
+    ))
+    .unwrap();
+  let host_public_key = host_public_key.to_hex();
+
+  let code_id = CodeId::store_code(&app);
+  let init_msg = InstantiateMessage {
+    chain_id: 1,
+    caip2: CAIP2.to_string(),
+    chainbills_fee_collector: "fee_collector".into_addr().to_string(),
+  };
+  let contract = code_id.instantiate(init_msg).call(&owner).unwrap();
+  contract
+    .update_max_withdrawal_fees(UpdateMaxWithdrawalFeesMessage {
+      token: "native".to_string(),
+      max_withdrawal_fees: Uint128::new(100),
+      is_native_token: true,
+    })
+    .call(&owner)
+    .unwrap();
+
+  // Create a Payable that only accepts 50 native.
+  let payable_resp = contract
+    .create_payable(CreatePayableMessage {
+      allowed_tokens_and_amounts: vec![TokenAndAmount {
+        token: "native".to_string(),
+        amount: Uint128::new(50),
+      }],
+    })
+    .call(&host)
+    .unwrap();
+  let payable_id = payable_resp
+    .events
+    .iter()
+    .find(|ev| ev.ty == "wasm")
+    .unwrap()
+    .attributes
+    .iter()
+    .find(|attr| attr.key == "payable_id")
+    .unwrap()
+    .value
+    .clone();
+
+  let expiry = app.block_info().time.seconds() + 60;
+  let sign = |key: &SigningKey, amount: u128, nonce: u64, reference: &str| {
+    let intent = PaymentIntent {
+      payable_chain_id: Chainbills::keccak(&[CAIP2.as_bytes()]),
+      payable_id: <[u8; 32]>::try_from(
+        HexBinary::from_hex(&payable_id).unwrap().as_slice(),
+      )
+      .unwrap(),
+      token: Sha256::digest(b"native").into(),
+      amount,
+      expiry,
+      nonce,
+      reference: reference.as_bytes().to_vec(),
+    };
+    let signature: Signature = key.sign(&intent.signing_bytes().unwrap());
+    HexBinary::from(signature.to_bytes().as_slice()).to_hex()
+  };
+  let intent_msg =
+    |amount: u128, nonce: u64, reference: &str, signature: String| {
+      PayWithIntentMessage {
+        payable_id: payable_id.clone(),
+        token: "native".to_string(),
+        amount: Uint128::new(amount),
+        reference: reference.to_string(),
+        expiry,
+        nonce,
+        public_key: host_public_key.clone(),
+        signature,
+        payer_proof: vec![],
+      }
+    };
+  let pay_with_intent = |msg: PayWithIntentMessage| {
+    let funds = coins(msg.amount.u128(), "native");
+    contract
+      .pay_with_intent(msg)
+      .with_funds(&funds)
+      .call(&payer)
+  };
+
+  // Intents must carry a compressed secp256k1 key of the host's address.
+  let uncompressed = HexBinary::from(
+    host_key.verifying_key().to_encoded_point(false).as_bytes(),
+  )
+  .to_hex();
+  for invalid_key in [
+    "zz".to_string(),
+    "02".to_string(),
+    format!("05{}", &host_public_key[2..]),
+    uncompressed,
+  ] {
+    let mut msg = intent_msg(10, 1, "INV-1", sign(&host_key, 10, 1, "INV-1"));
+    msg.public_key = invalid_key;
+    let err = pay_with_intent(msg).unwrap_err();
+    assert_eq!(err, ChainbillsError::InvalidIntentKey {});
+  }
+  let other_key = SigningKey::from_bytes(&[8u8; 32].into()).unwrap();
+  let mut msg = intent_msg(10, 1, "INV-1", sign(&other_key, 10, 1, "INV-1"));
+  msg.public_key = public_key(&other_key).to_hex();
+  let err = pay_with_intent(msg).unwrap_err();
+  assert_eq!(err, ChainbillsError::IntentKeyNotHost {});
+
+  // Payers can't pay amounts outside the payable's allowed ones directly,
+  // but can pay those that the host signed.
+  let err = contract
+    .pay(TransactionInfoMessage {
+      payable_id: payable_id.clone(),
+      token: "native".to_string(),
+      amount: Uint128::new(10),
+    })
+    .with_funds(&coins(10, "native"))
+    .call(&payer)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::MatchingTokenAndAmountNotFound {});
+  let resp = pay_with_intent(intent_msg(
+    10,
+    1,
+    "INV-1",
+    sign(&host_key, 10, 1, "INV-1"),
+  ))
+  .unwrap();
+  let payment_id = resp
+    .events
+    .iter()
+    .find(|ev| ev.ty == "wasm")
+    .unwrap()
+    .attributes
+    .iter()
+    .find(|attr| attr.key == "payable_payment_id")
+    .unwrap()
+    .value
+    .clone();
+  assert_eq!(
+    contract
+      .payment_intent(PaymentIntentMessage {
+        payable_id: payable_id.clone(),
+        nonce: 1,
+      })
+      .unwrap(),
+    Some(IdMessage {
+      id: payment_id.clone(),
+    })
+  );
+  assert_eq!(
+    contract
+      .payment_reference(IdMessage {
+        id: payment_id.clone(),
+      })
+      .unwrap(),
+    Some("INV-1".to_string())
+  );
+  let payment = contract
+    .payable_payment(IdMessage { id: payment_id })
+    .unwrap();
+  assert_eq!(payment.details.amount, Uint128::new(10));
+
+  // Each nonce can only be paid once.
+  let err = pay_with_intent(intent_msg(
+    10,
+    1,
+    "INV-1",
+    sign(&host_key, 10, 1, "INV-1"),
+  ))
+  .unwrap_err();
+  assert_eq!(err, ChainbillsError::PaymentIntentAlreadyUsed { nonce: 1 });
+  assert_eq!(
+    contract
+      .payment_intent(PaymentIntentMessage {
+        payable_id: payable_id.clone(),
+        nonce: 2,
+      })
+      .unwrap(),
+    None
+  );
+
+  // Intents must be paid exactly as the host signed them.
+  let err = pay_with_intent(intent_msg(
+    11,
+    2,
+    "INV-2",
+    sign(&host_key, 10, 2, "INV-2"),
+  ))
+  .unwrap_err();
+  assert_eq!(err, ChainbillsError::InvalidIntentSignature {});
+  let err = pay_with_intent(intent_msg(
+    10,
+    2,
+    "INV-3",
+    sign(&host_key, 10, 2, "INV-2"),
+  ))
+  .unwrap_err();
+  assert_eq!(err, ChainbillsError::InvalidIntentSignature {});
+  let err =
+    pay_with_intent(intent_msg(10, 2, "INV-2", "zz".to_string())).unwrap_err();
+  assert_eq!(err, ChainbillsError::InvalidIntentSignature {});
+
+  // Only the host's key signs their intents.
+  let err = pay_with_intent(intent_msg(
+    10,
+    2,
+    "INV-2",
+    sign(&other_key, 10, 2, "INV-2"),
+  ))
+  .unwrap_err();
+  assert_eq!(err, ChainbillsError::InvalidIntentSignature {});
+
+  // References are limited in length.
+  let long_reference = "x".repeat(PaymentIntent::MAX_REFERENCE_LEN + 1);
+  let err = pay_with_intent(intent_msg(
+    10,
+    2,
+    &long_reference,
+    sign(&host_key, 10, 2, "INV-2"),
+  ))
+  .unwrap_err();
+  assert_eq!(
+    err,
+    ChainbillsError::Payload(PayloadError::ReferenceTooLong)
+  );
+
+  // Intents can't be paid after they expire.
+  app.update_block(|block| block.time = block.time.plus_seconds(61));
+  let err = pay_with_intent(intent_msg(
+    10,
+    2,
+    "INV-2",
+    sign(&host_key, 10, 2, "INV-2"),
+  ))
+  .unwrap_err();
+  assert_eq!(err, ChainbillsError::PaymentIntentExpired { expiry });
+
+  assert_eq!(
+    contract
+      .token_details(IdMessage {
+        id: "native".to_string(),
+      })
+      .unwrap()
+      .total_user_paid,
+    Uint128::new(10)
+  );
+}
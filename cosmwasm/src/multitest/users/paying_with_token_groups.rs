@@ -0,0 +1,220 @@
+use crate::contract::sv::mt::CodeId;
+use crate::error::ChainbillsError;
+use crate::interfaces::payables::sv::mt::PayablesProxy;
+use crate::interfaces::payments::sv::mt::PaymentsProxy;
+use crate::interfaces::token_details::sv::mt::TokenDetailsInterfaceProxy;
+use crate::messages::{
+  CreatePayableMessage, IdMessage, InstantiateMessage, TransactionInfoMessage,
+  UpdateMaxWithdrawalFeesMessage, UpdateTokenGroupMessage,
+};
+use crate::state::{TokenAndAmount, TokenGroup, TokenGroupMember};
+use sylvia::cw_multi_test::IntoAddr;
+use sylvia::cw_std::{coin, coins, Uint128};
+use sylvia::multitest::App;
+
+#[test]
+fn paying_with_token_groups() {
+  let owner = "owner".into_addr();
+  let host = "host".into_addr();
+  let payer = "payer".into_addr();
+
+  let app =
+    App::new(sylvia::cw_multi_test::App::new(|router, _api, storage| {
+      router
+        .bank
+        .init_balance(
+          storage,
+          &payer,
+          vec![
+            coin(10_000_000_000, "usdc"),
+            coin(100_000_000_000, "usdx"),
+            coin(10_000, "wusd"),
+          ],
+        )
+        .unwrap();
+    }));
+  let code_id = CodeId::store_code(&app);
+  let init_msg = InstantiateMessage {
+    chain_id: 1,
+    caip2: "cosmos:cosmoshub-4".to_string(),
+    chainbills_fee_collector: "fee_collector".into_addr().to_string(),
+  };
+  let contract = code_id.instantiate(init_msg).call(&owner).unwrap();
+  for token in ["usdc", "usdx", "wusd"] {
+    contract
+      .update_max_withdrawal_fees(UpdateMaxWithdrawalFeesMessage {
+        token: token.to_string(),
+        max_withdrawal_fees: Uint128::new(100),
+        is_native_token: true,
+      })
+      .call(&owner)
+      .unwrap();
+  }
+
+  // Only the owner can manage token groups, and only with known and distinct
+  // tokens.
+  let member = |token: &str, decimals: u8| TokenGroupMember {
+    token: token.to_string(),
+    decimals,
+  };
+  let group_msg = |members: Vec<TokenGroupMember>| UpdateTokenGroupMessage {
+    name: "usd".to_string(),
+    decimals: 6,
+    members,
+  };
+  let err = contract
+    .update_token_group(group_msg(vec![member("usdc", 6)]))
+    .call(&host)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::OwnerUnauthorized {});
+  let err = contract
+    .update_token_group(group_msg(vec![member("unknown", 6)]))
+    .call(&owner)
+    .unwrap_err();
+  assert_eq!(
+    err,
+    ChainbillsError::InvalidToken {
+      token: "unknown".to_string()
+    }
+  );
+  let err = contract
+    .update_token_group(group_msg(vec![member("usdc", 6), member("usdc", 8)]))
+    .call(&owner)
+    .unwrap_err();
+  assert_eq!(
+    err,
+    ChainbillsError::InvalidTokenGroup {
+      name: "usd".to_string()
+    }
+  );
+
+  // Payables can't reference groups before they exist.
+  let create_payable = |amount: u128| {
+    contract
+      .create_payable(CreatePayableMessage {
+        allowed_tokens_and_amounts: vec![TokenAndAmount {
+          token: format!("{}usd", TokenGroup::REFERENCE_PREFIX),
+          amount: Uint128::new(amount),
+        }],
+      })
+      .call(&host)
+  };
+  let err = create_payable(10_000_000).unwrap_err();
+  assert_eq!(
+    err,
+    ChainbillsError::InvalidTokenGroup {
+      name: "usd".to_string()
+    }
+  );
+
+  let members = vec![member("usdc", 6), member("usdx", 8), member("wusd", 2)];
+  contract
+    .update_token_group(group_msg(members.clone()))
+    .call(&owner)
+    .unwrap();
+  assert_eq!(
+    contract
+      .token_group(IdMessage {
+        id: "usd".to_string(),
+      })
+      .unwrap(),
+    TokenGroup {
+      decimals: 6,
+      members,
+    }
+  );
+
+  // Create a Payable that accepts 10 of the group's tokens.
+  let payable_id = create_payable(10_000_000)
+    .unwrap()
+    .events
+    .iter()
+    .find(|ev| ev.ty == "wasm")
+    .unwrap()
+    .attributes
+    .iter()
+    .find(|attr| attr.key == "payable_id")
+    .unwrap()
+    .value
+    .clone();
+  let pay = |token: &str, amount: u128| {
+    contract
+      .pay(TransactionInfoMessage {
+        payable_id: payable_id.clone(),
+        token: token.to_string(),
+        amount: Uint128::new(amount),
+      })
+      .with_funds(&coins(amount, token))
+      .call(&payer)
+  };
+
+  // Any member pays the amount in its own decimals.
+  pay("usdc", 10_000_000).unwrap();
+  pay("usdx", 1_000_000_000).unwrap();
+  pay("wusd", 1_000).unwrap();
+
+  // Amounts that aren't worth the group's amount don't match.
+  for (token, amount) in [("usdc", 1_000_000_000), ("usdx", 10_000_000)] {
+    let err = pay(token, amount).unwrap_err();
+    assert_eq!(err, ChainbillsError::MatchingTokenAndAmountNotFound {});
+  }
+
+  // Balances are kept per actual token.
+  let payable = contract
+    .payable(IdMessage {
+      id: payable_id.clone(),
+    })
+    .unwrap();
+  assert_eq!(
+    payable.balances,
+    vec![
+      TokenAndAmount {
+        token: "usdc".to_string(),
+        amount: Uint128::new(10_000_000),
+      },
+      TokenAndAmount {
+        token: "usdx".to_string(),
+        amount: Uint128::new(1_000_000_000),
+      },
+      TokenAndAmount {
+        token: "wusd".to_string(),
+        amount: Uint128::new(1_000),
+      },
+    ]
+  );
+
+  // Amounts that members can't express exactly can't be paid in them.
+  let precise_payable_id = create_payable(10_000_001)
+    .unwrap()
+    .events
+    .iter()
+    .find(|ev| ev.ty == "wasm")
+    .unwrap()
+    .attributes
+    .iter()
+    .find(|attr| attr.key == "payable_id")
+    .unwrap()
+    .value
+    .clone();
+  for amount in [1_000, 1_001] {
+    let err = contract
+      .pay(TransactionInfoMessage {
+        payable_id: precise_payable_id.clone(),
+        token: "wusd".to_string(),
+        amount: Uint128::new(amount),
+      })
+      .with_funds(&coins(amount, "wusd"))
+      .call(&payer)
+      .unwrap_err();
+    assert_eq!(err, ChainbillsError::MatchingTokenAndAmountNotFound {});
+  }
+  contract
+    .pay(TransactionInfoMessage {
+      payable_id: precise_payable_id,
+      token: "usdx".to_string(),
+      amount: Uint128::new(1_000_000_100),
+    })
+    .with_funds(&coins(1_000_000_100, "usdx"))
+    .call(&payer)
+    .unwrap();
+}
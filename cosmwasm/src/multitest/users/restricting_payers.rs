@@ -0,0 +1,246 @@
+use crate::contract::sv::mt::CodeId;
+use crate::contract::Chainbills;
+use crate::error::ChainbillsError;
+use crate::interfaces::payables::sv::mt::PayablesProxy;
+use crate::interfaces::payments::sv::mt::PaymentsProxy;
+use crate::interfaces::token_details::sv::mt::TokenDetailsInterfaceProxy;
+use crate::messages::{
+  CreatePayableMessage, IdMessage, InstantiateMessage, PayBatchItemMessage,
+  PayBatchMessage, PayWithProofMessage, TransactionInfoMessage,
+  UpdateMaxWithdrawalFeesMessage, UpdatePayableAllowedPayersMessage,
+};
+use chainbills_payload::{
+  allowed_payer_leaf, allowed_payer_proof, allowed_payers_root,
+};
+use sylvia::cw_multi_test::IntoAddr;
+use sylvia::cw_std::{coins, Addr, Api, HexBinary, Uint128};
+use sylvia::multitest::App;
+
+#[test]
+fn restricting_payers() {
+  let owner = "owner".into_addr();
+  let host = "host".into_addr();
+  let payers: Vec<Addr> = ["alice", "bob", "carol", "dave"]
+    .iter()
+    .map(|name| name.into_addr())
+    .collect();
+
+  let app =
+    App::new(sylvia::cw_multi_test::App::new(|router, _api, storage| {
+      for payer in payers.iter() {
+        router
+          .bank
+          .init_balance(storage, payer, coins(100, "native"))
+          .unwrap();
+      }
+    }));
+  let code_id = CodeId::store_code(&app);
+
+  let init_msg = InstantiateMessage {
+    chain_id: 1,
+    caip2: "cosmos:cosmoshub-4".to_string(),
+    chainbills_fee_collector: "fee_collector".into_addr().to_string(),
+  };
+  let contract = code_id.instantiate(init_msg).call(&owner).unwrap();
+  contract
+    .update_max_withdrawal_fees(UpdateMaxWithdrawalFeesMessage {
+      token: "native".to_string(),
+      max_withdrawal_fees: Uint128::new(100),
+      is_native_token: true,
+    })
+    .call(&owner)
+    .unwrap();
+
+  // Create a Payable.
+  let payable_resp = contract
+    .create_payable(CreatePayableMessage {
+      allowed_tokens_and_amounts: vec![],
+    })
+    .call(&host)
+    .unwrap();
+  let payable_id = payable_resp
+    .events
+    .iter()
+    .find(|ev| ev.ty == "wasm")
+    .unwrap()
+    .attributes
+    .iter()
+    .find(|attr| attr.key == "payable_id")
+    .unwrap()
+    .value
+    .clone();
+  let id_msg = IdMessage {
+    id: payable_id.clone(),
+  };
+  let tx_info = TransactionInfoMessage {
+    payable_id: payable_id.clone(),
+    token: "native".to_string(),
+    amount: Uint128::new(10),
+  };
+
+  // Payers are committed to by their Wormhole-normalized addresses.
+  let payer_bytes: Vec<[u8; 32]> = payers
+    .iter()
+    .map(|payer| {
+      let canonical = app.app().api().addr_canonicalize(payer.as_str());
+      <[u8; 32]>::try_from(canonical.unwrap().as_slice()).unwrap()
+    })
+    .collect();
+  let allowed = &payer_bytes[..3];
+  let to_hex = |bytes: &[u8; 32]| HexBinary::from(bytes).to_hex();
+  let update_msg = |allowed_payers: Vec<String>, root: Option<String>| {
+    UpdatePayableAllowedPayersMessage {
+      payable_id: payable_id.clone(),
+      allowed_payers,
+      allowed_payers_root: root,
+    }
+  };
+
+  // Only the host (or a manager) can restrict payers, with valid inputs.
+  let err = contract
+    .update_payable_allowed_payers(update_msg(vec![], None))
+    .call(&payers[0])
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::NotYourPayable {});
+  let err = contract
+    .update_payable_allowed_payers(update_msg(vec!["01".to_string()], None))
+    .call(&host)
+    .unwrap_err();
+  assert_eq!(
+    err,
+    ChainbillsError::InvalidAllowedPayer {
+      payer: "01".to_string()
+    }
+  );
+  let err = contract
+    .update_payable_allowed_payers(update_msg(
+      vec![to_hex(&allowed[0])],
+      Some(to_hex(&[1u8; 32])),
+    ))
+    .call(&host)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::InvalidAllowedPayersRoot {});
+
+  // Restrict the payable to the first three payers.
+  let resp = contract
+    .update_payable_allowed_payers(update_msg(
+      allowed.iter().map(to_hex).collect(),
+      None,
+    ))
+    .call(&host)
+    .unwrap();
+  let root = allowed_payers_root(allowed, &Chainbills::keccak);
+  let attrs = &resp
+    .events
+    .iter()
+    .find(|ev| ev.ty == "wasm")
+    .unwrap()
+    .attributes;
+  let attr = |key: &str| {
+    attrs
+      .iter()
+      .find(|attr| attr.key == key)
+      .unwrap()
+      .value
+      .clone()
+  };
+  assert_eq!(attr("allowed_payers_root"), to_hex(&root));
+  let payload = HexBinary::from_hex(&attr("payload")).unwrap();
+  assert_eq!(payload[1], 5);
+  assert_eq!(&payload[payload.len() - 32..], root.as_slice());
+  assert_eq!(
+    contract
+      .payable_allowed_payers_root(id_msg.clone())
+      .unwrap()
+      .allowed_payers_root,
+    to_hex(&root)
+  );
+
+  // Allowed payers pay with their proofs. Others can't pay.
+  let pay_with_proof = |payer: usize, proof: Vec<[u8; 32]>| {
+    contract
+      .pay_with_proof(PayWithProofMessage {
+        payable_id: payable_id.clone(),
+        token: "native".to_string(),
+        amount: Uint128::new(10),
+        payer_proof: proof.iter().map(to_hex).collect(),
+        reference: None,
+      })
+      .with_funds(&coins(10, "native"))
+      .call(&payers[payer])
+  };
+  let err = contract
+    .pay(tx_info.clone())
+    .with_funds(&coins(10, "native"))
+    .call(&payers[0])
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::PayerNotAllowed {});
+  for (i, payer) in allowed.iter().enumerate() {
+    let proof =
+      allowed_payer_proof(allowed, payer, &Chainbills::keccak).unwrap();
+    pay_with_proof(i, proof).unwrap();
+  }
+  let proof =
+    allowed_payer_proof(allowed, &payer_bytes[0], &Chainbills::keccak).unwrap();
+  let err = pay_with_proof(3, proof).unwrap_err();
+  assert_eq!(err, ChainbillsError::PayerNotAllowed {});
+
+  // Batch payments carry a proof for each payment.
+  let pay_batch = |payer: usize, proof: Vec<[u8; 32]>| {
+    contract
+      .pay_batch(PayBatchMessage {
+        payments: vec![PayBatchItemMessage {
+          payable_id: payable_id.clone(),
+          token: "native".to_string(),
+          amount: Uint128::new(10),
+          payer_proof: proof.iter().map(to_hex).collect(),
+        }],
+      })
+      .with_funds(&coins(10, "native"))
+      .call(&payers[payer])
+  };
+  let err = pay_batch(1, vec![]).unwrap_err();
+  assert_eq!(err, ChainbillsError::PayerNotAllowed {});
+  let proof =
+    allowed_payer_proof(allowed, &payer_bytes[1], &Chainbills::keccak).unwrap();
+  pay_batch(1, proof.clone()).unwrap();
+  let err = pay_batch(3, proof).unwrap_err();
+  assert_eq!(err, ChainbillsError::PayerNotAllowed {});
+  assert_eq!(contract.payable(id_msg.clone()).unwrap().payments_count, 4);
+
+  // Roots can be given directly. A lone payer pays without a proof.
+  let leaf = allowed_payer_leaf(&payer_bytes[3], &Chainbills::keccak);
+  contract
+    .update_payable_allowed_payers(update_msg(vec![], Some(to_hex(&leaf))))
+    .call(&host)
+    .unwrap();
+  contract
+    .pay(tx_info.clone())
+    .with_funds(&coins(10, "native"))
+    .call(&payers[3])
+    .unwrap();
+  let err = contract
+    .pay(tx_info.clone())
+    .with_funds(&coins(10, "native"))
+    .call(&payers[0])
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::PayerNotAllowed {});
+
+  // Clearing the allowed payers opens the payable to all.
+  contract
+    .update_payable_allowed_payers(update_msg(vec![], None))
+    .call(&host)
+    .unwrap();
+  assert_eq!(
+    contract
+      .payable_allowed_payers_root(id_msg)
+      .unwrap()
+      .allowed_payers_root,
+    to_hex(&[0u8; 32])
+  );
+  contract
+    .pay(tx_info)
+    .with_funds(&coins(10, "native"))
+    .call(&payers[0])
+    .unwrap();
+}
@@ -0,0 +1,264 @@
+use crate::contract::sv::mt::CodeId;
+use crate::error::ChainbillsError;
+use crate::interfaces::activities::sv::mt::ActivitiesProxy;
+use crate::interfaces::payables::sv::mt::PayablesProxy;
+use crate::interfaces::subscriptions::sv::mt::SubscriptionsProxy;
+use crate::interfaces::token_details::sv::mt::TokenDetailsInterfaceProxy;
+use crate::messages::{
+  CreatePayableMessage, CreateSubscriptionMessage, FetchIdMessage, IdMessage,
+  InstantiateMessage, SubscriptionMessage, UpdateMaxWithdrawalFeesMessage,
+};
+use crate::state::ActivityType;
+use cw20::{BalanceResponse, Cw20Coin};
+use cw20_base::msg::InstantiateMsg;
+use sylvia::cw_multi_test::{Contract, ContractWrapper, Executor, IntoAddr};
+use sylvia::cw_std::{coins, Empty, StdResult, Uint128};
+use sylvia::multitest::App;
+
+const DAY: u64 = 24 * 60 * 60;
+
+fn contract_cw20() -> Box<dyn Contract<Empty>> {
+  let contract = ContractWrapper::new(
+    cw20_base::contract::execute,
+    cw20_base::contract::instantiate,
+    cw20_base::contract::query,
+  );
+  Box::new(contract)
+}
+
+#[test]
+fn subscribing() {
+  let owner = "owner".into_addr();
+  let host = "host".into_addr();
+  let payer = "payer".into_addr();
+  let collector = "collector".into_addr();
+
+  let mut app = sylvia::cw_multi_test::App::new(|router, _api, storage| {
+    router
+      .bank
+      .init_balance(storage, &payer, coins(100, "native"))
+      .unwrap();
+  });
+  let cw20_id = app.store_code(contract_cw20());
+  let usdc_addr = app
+    .instantiate_contract(
+      cw20_id,
+      owner.clone(),
+      &InstantiateMsg {
+        name: "USDC".to_string(),
+        symbol: "USDC".to_string(),
+        decimals: 6,
+        initial_balances: vec![Cw20Coin {
+          address: payer.to_string(),
+          amount: Uint128::new(100),
+        }],
+        mint: None,
+        marketing: None,
+      },
+      &[],
+      "USDC",
+      None,
+    )
+    .unwrap();
+
+  let app = App::new(app);
+  let code_id = CodeId::store_code(&app);
+  let init_msg = InstantiateMessage {
+    chain_id: 1,
+    caip2: "cosmos:cosmoshub-4".to_string(),
+    chainbills_fee_collector: "fee_collector".into_addr().to_string(),
+  };
+  let contract = code_id.instantiate(init_msg).call(&owner).unwrap();
+  for (token, is_native_token) in
+    [("native".to_string(), true), (usdc_addr.to_string(), false)]
+  {
+    contract
+      .update_max_withdrawal_fees(UpdateMaxWithdrawalFeesMessage {
+        token,
+        max_withdrawal_fees: Uint128::new(100),
+        is_native_token,
+      })
+      .call(&owner)
+      .unwrap();
+  }
+
+  // Create a Payable.
+  let payable_resp = contract
+    .create_payable(CreatePayableMessage {
+      allowed_tokens_and_amounts: vec![],
+    })
+    .call(&host)
+    .unwrap();
+  let payable_id = payable_resp
+    .events
+    .iter()
+    .find(|ev| ev.ty == "wasm")
+    .unwrap()
+    .attributes
+    .iter()
+    .find(|attr| attr.key == "payable_id")
+    .unwrap()
+    .value
+    .clone();
+  let sub_msg = SubscriptionMessage {
+    payable_id: payable_id.clone(),
+    payer: payer.to_string(),
+  };
+  let create_msg = |token: &str, period: u64| CreateSubscriptionMessage {
+    payable_id: payable_id.clone(),
+    token: token.to_string(),
+    amount: Uint128::new(20),
+    period,
+    first_due: None,
+    payer_proof: vec![],
+  };
+
+  // Subscriptions need a period and a single active one per payable.
+  let err = contract
+    .create_subscription(create_msg("native", 0))
+    .call(&payer)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::InvalidSubscriptionPeriod {});
+  let first_due = app.block_info().time.seconds() - 1;
+  let err = contract
+    .create_subscription(CreateSubscriptionMessage {
+      first_due: Some(first_due),
+      ..create_msg("native", 30 * DAY)
+    })
+    .call(&payer)
+    .unwrap_err();
+  assert_eq!(
+    err,
+    ChainbillsError::SubscriptionFirstDueInPast { first_due }
+  );
+  contract
+    .create_subscription(create_msg("native", 30 * DAY))
+    .with_funds(&coins(30, "native"))
+    .call(&payer)
+    .unwrap();
+  let err = contract
+    .create_subscription(create_msg("native", 30 * DAY))
+    .call(&payer)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::SubscriptionAlreadyExists {});
+  let user_activity_id = contract
+    .user_activity_id(FetchIdMessage {
+      reference: payer.to_string(),
+      count: 2,
+    })
+    .unwrap();
+  let activity = contract.activity(user_activity_id).unwrap();
+  assert_eq!(activity.activity_type, ActivityType::CreatedSubscription);
+
+  // Anyone collects native payments from the deposit when due.
+  contract
+    .collect_subscription(sub_msg.clone())
+    .call(&collector)
+    .unwrap();
+  let err = contract
+    .collect_subscription(sub_msg.clone())
+    .call(&collector)
+    .unwrap_err();
+  let next_due = app.block_info().time.seconds() + 30 * DAY;
+  assert_eq!(err, ChainbillsError::SubscriptionNotDue { next_due });
+  app.update_block(|block| block.time = block.time.plus_seconds(30 * DAY));
+  let err = contract
+    .collect_subscription(sub_msg.clone())
+    .call(&collector)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::InsufficientSubscriptionDeposit {});
+  contract
+    .fund_subscription(sub_msg.clone())
+    .with_funds(&coins(50, "native"))
+    .call(&payer)
+    .unwrap();
+  contract
+    .collect_subscription(sub_msg.clone())
+    .call(&collector)
+    .unwrap();
+  let subscription = contract.subscription(sub_msg.clone()).unwrap();
+  assert_eq!(subscription.payments_count, 2);
+  assert_eq!(subscription.deposit, Uint128::new(40));
+  assert_eq!(subscription.next_due, next_due + 30 * DAY);
+  let payable = contract
+    .payable(IdMessage {
+      id: payable_id.clone(),
+    })
+    .unwrap();
+  assert_eq!(payable.payments_count, 2);
+  assert_eq!(payable.balances[0].amount, Uint128::new(40));
+
+  // Late collections pull one payment and skip the missed periods.
+  app.update_block(|block| block.time = block.time.plus_seconds(75 * DAY));
+  contract
+    .collect_subscription(sub_msg.clone())
+    .call(&collector)
+    .unwrap();
+  let err = contract
+    .collect_subscription(sub_msg.clone())
+    .call(&collector)
+    .unwrap_err();
+  let next_due = next_due + 90 * DAY;
+  assert_eq!(err, ChainbillsError::SubscriptionNotDue { next_due });
+  let subscription = contract.subscription(sub_msg.clone()).unwrap();
+  assert_eq!(subscription.payments_count, 3);
+  assert_eq!(subscription.deposit, Uint128::new(20));
+
+  // Only the payer or the host cancels. The deposit is refunded.
+  let err = contract
+    .cancel_subscription(sub_msg.clone())
+    .call(&collector)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::NotYourSubscription {});
+  contract
+    .cancel_subscription(sub_msg.clone())
+    .call(&host)
+    .unwrap();
+  assert_eq!(
+    app
+      .querier()
+      .query_balance(&payer, "native")
+      .unwrap()
+      .amount,
+    Uint128::new(40)
+  );
+  let err = contract
+    .collect_subscription(sub_msg.clone())
+    .call(&collector)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::SubscriptionIsCancelled {});
+
+  // Cw20 subscriptions pull from the payer's allowance.
+  contract
+    .create_subscription(create_msg(usdc_addr.as_str(), 7 * DAY))
+    .call(&payer)
+    .unwrap();
+  app
+    .app_mut()
+    .execute_contract(
+      payer.clone(),
+      usdc_addr.clone(),
+      &cw20::Cw20ExecuteMsg::IncreaseAllowance {
+        spender: contract.contract_addr.to_string(),
+        amount: Uint128::new(40),
+        expires: None,
+      },
+      &[],
+    )
+    .unwrap();
+  contract
+    .collect_subscription(sub_msg.clone())
+    .call(&collector)
+    .unwrap();
+  let contract_usdc: StdResult<BalanceResponse> =
+    app.querier().query_wasm_smart(
+      &usdc_addr,
+      &cw20::Cw20QueryMsg::Balance {
+        address: contract.contract_addr.to_string(),
+      },
+    );
+  assert_eq!(contract_usdc.unwrap().balance, Uint128::new(20));
+  let subscription = contract.subscription(sub_msg).unwrap();
+  assert_eq!(subscription.payments_count, 1);
+  assert!(!subscription.is_cancelled);
+}
@@ -0,0 +1,142 @@
+use crate::contract::sv::mt::{ChainbillsProxy, CodeId};
+use crate::error::ChainbillsError;
+use crate::interfaces::activities::sv::mt::ActivitiesProxy;
+use crate::interfaces::payables::sv::mt::PayablesProxy;
+use crate::messages::{
+  CreatePayableMessage, FetchIdMessage, IdMessage, InstantiateMessage,
+  TransferPayableMessage,
+};
+use crate::state::ActivityType;
+use sylvia::cw_multi_test::IntoAddr;
+use sylvia::multitest::App;
+
+#[test]
+fn transferring_payables() {
+  let app = App::default();
+  let code_id = CodeId::store_code(&app);
+
+  let owner = "owner".into_addr();
+  let host = "host".into_addr();
+  let new_host = "new_host".into_addr();
+  let init_msg = InstantiateMessage {
+    chain_id: 1,
+    caip2: "cosmos:cosmoshub-4".to_string(),
+    chainbills_fee_collector: "fee_collector".into_addr().to_string(),
+  };
+  let contract = code_id.instantiate(init_msg).call(&owner).unwrap();
+
+  // Create a Payable
+  let payable_resp = contract
+    .create_payable(CreatePayableMessage {
+      allowed_tokens_and_amounts: vec![],
+    })
+    .call(&host)
+    .unwrap();
+  let payable_id = payable_resp
+    .events
+    .iter()
+    .find(|ev| ev.ty == "wasm")
+    .unwrap()
+    .attributes
+    .iter()
+    .find(|attr| attr.key == "payable_id")
+    .unwrap()
+    .value
+    .clone();
+  let transfer_msg = TransferPayableMessage {
+    payable_id: payable_id.clone(),
+    new_host: new_host.to_string(),
+  };
+  let id_msg = IdMessage {
+    id: payable_id.clone(),
+  };
+
+  // Only the host can offer the payable, and not to themselves.
+  let err = contract
+    .transfer_payable(transfer_msg.clone())
+    .call(&new_host)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::NotYourPayable {});
+  let err = contract
+    .transfer_payable(TransferPayableMessage {
+      payable_id: payable_id.clone(),
+      new_host: host.to_string(),
+    })
+    .call(&host)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::CannotTransferPayableToSelf {});
+
+  // Nobody can accept a payable that wasn't offered to them.
+  let err = contract
+    .accept_payable(id_msg.clone())
+    .call(&new_host)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::NotPendingPayableHost {});
+  contract.transfer_payable(transfer_msg).call(&host).unwrap();
+  assert_eq!(
+    contract
+      .pending_payable_host(id_msg.clone())
+      .unwrap()
+      .address,
+    new_host
+  );
+  let err = contract
+    .accept_payable(id_msg.clone())
+    .call(&owner)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::NotPendingPayableHost {});
+
+  // The new host accepts and the payable moves with the same ID.
+  contract
+    .accept_payable(id_msg.clone())
+    .call(&new_host)
+    .unwrap();
+  let payable = contract.payable(id_msg.clone()).unwrap();
+  assert_eq!(payable.host, new_host);
+  assert_eq!(payable.host_count, 1);
+  assert_eq!(payable.activities_count, 3);
+  assert!(contract.pending_payable_host(id_msg.clone()).is_err());
+
+  let new_host_data = contract
+    .user(IdMessage {
+      id: new_host.to_string(),
+    })
+    .unwrap();
+  assert_eq!(new_host_data.payables_count, 1);
+  let indexed = contract
+    .user_payable_id(FetchIdMessage {
+      reference: new_host.to_string(),
+      count: 1,
+    })
+    .unwrap();
+  assert_eq!(indexed.id, payable_id);
+
+  // Both hosts have the activity of the transfer.
+  for (wallet, activity_type) in [
+    (&host, ActivityType::TransferredPayable),
+    (&new_host, ActivityType::AcceptedPayable),
+  ] {
+    let user = contract
+      .user(IdMessage {
+        id: wallet.to_string(),
+      })
+      .unwrap();
+    let activity_id = contract
+      .user_activity_id(FetchIdMessage {
+        reference: wallet.to_string(),
+        count: user.activities_count,
+      })
+      .unwrap();
+    let activity = contract.activity(activity_id).unwrap();
+    assert_eq!(activity.activity_type, activity_type);
+    assert_eq!(activity.entity, payable_id);
+  }
+
+  // The previous host can no longer act on the payable.
+  let err = contract
+    .close_payable(id_msg.clone())
+    .call(&host)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::NotYourPayable {});
+  contract.close_payable(id_msg).call(&new_host).unwrap();
+}
@@ -0,0 +1,322 @@
+use crate::contract::sv::mt::{ChainbillsProxy, CodeId};
+use crate::error::ChainbillsError;
+use crate::interfaces::chains::sv::mt::ChainsProxy;
+use crate::interfaces::payables::sv::mt::PayablesProxy;
+use crate::interfaces::payments::sv::mt::PaymentsProxy;
+use crate::interfaces::token_details::sv::mt::TokenDetailsInterfaceProxy;
+use crate::interfaces::withdrawals::sv::mt::WithdrawalsProxy;
+use crate::messages::{
+  AddressMessage, ChainIdentifier, CreatePayableMessage, FetchIdMessage,
+  IdMessage, InstantiateMessage, RegisterCbChainMessage,
+  RegisterChainWormholeIdMessage, TokenBridgeMessage, TransactionInfoMessage,
+  UpdateMaxWithdrawalFeesMessage, WithdrawCrossChainMessage,
+};
+use crate::state::CbChain;
+use cw20::{AllowanceResponse, Cw20Coin};
+use cw20_base::msg::InstantiateMsg;
+use sylvia::cw_multi_test::{Contract, ContractWrapper, Executor, IntoAddr};
+use sylvia::cw_std::{
+  coins, Binary, Deps, DepsMut, Empty, Env, HexBinary, MessageInfo, Response,
+  StdResult, Uint128,
+};
+use sylvia::multitest::App;
+
+fn contract_cw20() -> Box<dyn Contract<Empty>> {
+  let contract = ContractWrapper::new(
+    cw20_base::contract::execute,
+    cw20_base::contract::instantiate,
+    cw20_base::contract::query,
+  );
+  Box::new(contract)
+}
+
+/// Stands in for the Wormhole Token Bridge. Accepts its messages and keeps
+/// any deposited funds.
+fn contract_token_bridge() -> Box<dyn Contract<Empty>> {
+  let contract = ContractWrapper::new(
+    |_: DepsMut,
+     _: Env,
+     _: MessageInfo,
+     _: TokenBridgeMessage|
+     -> StdResult<Response> { Ok(Response::new()) },
+    |_: DepsMut, _: Env, _: MessageInfo, _: Empty| -> StdResult<Response> {
+      Ok(Response::new())
+    },
+    |_: Deps, _: Env, _: Empty| -> StdResult<Binary> { Ok(Binary::default()) },
+  );
+  Box::new(contract)
+}
+
+#[test]
+fn withdrawing_cross_chain() {
+  let owner = "owner".into_addr();
+  let user = "user".into_addr();
+
+  let mut app = sylvia::cw_multi_test::App::new(|router, _api, storage| {
+    router
+      .bank
+      .init_balance(storage, &owner, coins(10000, "native"))
+      .unwrap();
+  });
+  let cw20_id = app.store_code(contract_cw20());
+  let usdc_addr = app
+    .instantiate_contract(
+      cw20_id,
+      owner.clone(),
+      &InstantiateMsg {
+        name: "USDC".to_string(),
+        symbol: "USDC".to_string(),
+        decimals: 6,
+        initial_balances: vec![Cw20Coin {
+          address: owner.to_string(),
+          amount: Uint128::new(10000),
+        }],
+        mint: None,
+        marketing: None,
+      },
+      &[],
+      "USDC",
+      None,
+    )
+    .unwrap();
+  let bridge_id = app.store_code(contract_token_bridge());
+  let token_bridge = app
+    .instantiate_contract(
+      bridge_id,
+      owner.clone(),
+      &Empty {},
+      &[],
+      "Token Bridge",
+      None,
+    )
+    .unwrap();
+
+  let app = App::new(app);
+  let code_id = CodeId::store_code(&app);
+
+  let fee_collector = "fee_collector".into_addr();
+  let init_msg = InstantiateMessage {
+    chain_id: 1,
+    caip2: "cosmos:cosmoshub-4".to_string(),
+    chainbills_fee_collector: fee_collector.to_string(),
+  };
+  let contract = code_id.instantiate(init_msg).call(&owner).unwrap();
+
+  // Create a Payable and fund it in both tokens.
+  let payable_resp = contract
+    .create_payable(CreatePayableMessage {
+      allowed_tokens_and_amounts: vec![],
+    })
+    .call(&user)
+    .unwrap();
+  let payable_id = payable_resp
+    .events
+    .iter()
+    .find(|ev| ev.ty == "wasm")
+    .unwrap()
+    .attributes
+    .iter()
+    .find(|attr| attr.key == "payable_id")
+    .unwrap()
+    .value
+    .clone();
+  for (token, is_native_token) in
+    [("native".to_string(), true), (usdc_addr.to_string(), false)]
+  {
+    contract
+      .update_max_withdrawal_fees(UpdateMaxWithdrawalFeesMessage {
+        token,
+        max_withdrawal_fees: Uint128::new(100),
+        is_native_token,
+      })
+      .call(&owner)
+      .unwrap();
+  }
+  contract
+    .pay(TransactionInfoMessage {
+      payable_id: payable_id.clone(),
+      token: "native".to_string(),
+      amount: Uint128::new(1000),
+    })
+    .with_funds(&coins(1000, "native"))
+    .call(&owner)
+    .unwrap();
+  app
+    .app_mut()
+    .execute_contract(
+      owner.clone(),
+      usdc_addr.clone(),
+      &cw20::Cw20ExecuteMsg::IncreaseAllowance {
+        spender: contract.contract_addr.to_string(),
+        amount: Uint128::new(1000),
+        expires: None,
+      },
+      &[],
+    )
+    .unwrap();
+  contract
+    .pay(TransactionInfoMessage {
+      payable_id: payable_id.clone(),
+      token: usdc_addr.to_string(),
+      amount: Uint128::new(1000),
+    })
+    .call(&owner)
+    .unwrap();
+
+  let sepolia = ChainIdentifier::Caip2("eip155:11155111".to_string());
+  let dest_address = HexBinary::from(&[7u8; 32]).to_hex();
+  let withdraw_msg =
+    |token: String, dest_chain: ChainIdentifier| WithdrawCrossChainMessage {
+      payable_id: payable_id.clone(),
+      token,
+      amount: Uint128::new(500),
+      dest_chain,
+      dest_address: dest_address.clone(),
+    };
+
+  // Cross-chain withdrawals need the Token Bridge to be set.
+  let err = contract
+    .withdraw_cross_chain(withdraw_msg("native".to_string(), sepolia.clone()))
+    .call(&user)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::TokenBridgeNotSet {});
+
+  // Only the owner can set the Token Bridge.
+  let bridge_msg = AddressMessage {
+    address: token_bridge.clone(),
+  };
+  let err = contract
+    .update_token_bridge(bridge_msg.clone())
+    .call(&user)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::OwnerUnauthorized {});
+  contract
+    .update_token_bridge(bridge_msg)
+    .call(&owner)
+    .unwrap();
+  assert_eq!(
+    contract.config().unwrap().token_bridge,
+    Some(token_bridge.clone())
+  );
+
+  // The destination must be another chain with a Wormhole Chain ID.
+  let err = contract
+    .withdraw_cross_chain(withdraw_msg("native".to_string(), sepolia.clone()))
+    .call(&user)
+    .unwrap_err();
+  assert!(matches!(
+    err,
+    ChainbillsError::InvalidDestinationChain { .. }
+  ));
+  let err = contract
+    .withdraw_cross_chain(withdraw_msg(
+      "native".to_string(),
+      ChainIdentifier::WormholeChainId(1),
+    ))
+    .call(&user)
+    .unwrap_err();
+  assert!(matches!(
+    err,
+    ChainbillsError::InvalidDestinationChain { .. }
+  ));
+  contract
+    .register_cb_chain(RegisterCbChainMessage {
+      caip2: "eip155:11155111".to_string(),
+    })
+    .call(&owner)
+    .unwrap();
+  contract
+    .register_chain_wormhole_id(RegisterChainWormholeIdMessage {
+      chain: sepolia.clone(),
+      wormhole_chain_id: 10002,
+    })
+    .call(&owner)
+    .unwrap();
+
+  // The destination address must be a non-zero 32-byte address.
+  let mut bad_address = withdraw_msg("native".to_string(), sepolia.clone());
+  bad_address.dest_address = HexBinary::from(&[0u8; 32]).to_hex();
+  let err = contract
+    .withdraw_cross_chain(bad_address)
+    .call(&user)
+    .unwrap_err();
+  assert!(matches!(
+    err,
+    ChainbillsError::InvalidDestinationAddress { .. }
+  ));
+
+  // Only the host can withdraw.
+  let err = contract
+    .withdraw_cross_chain(withdraw_msg("native".to_string(), sepolia.clone()))
+    .call(&owner)
+    .unwrap_err();
+  assert_eq!(err, ChainbillsError::NotYourPayable {});
+
+  // Native tokens are deposited to the Token Bridge, minus fees (2%).
+  let resp = contract
+    .withdraw_cross_chain(withdraw_msg("native".to_string(), sepolia.clone()))
+    .call(&user)
+    .unwrap();
+  let nonce = resp
+    .events
+    .iter()
+    .find(|ev| ev.ty == "wasm")
+    .unwrap()
+    .attributes
+    .iter()
+    .find(|attr| attr.key == "nonce")
+    .unwrap()
+    .value
+    .clone();
+  assert_eq!(nonce, "1");
+  assert_eq!(
+    app
+      .querier()
+      .query_balance(&token_bridge, "native")
+      .unwrap()
+      .amount,
+    Uint128::new(490)
+  );
+  assert_eq!(
+    app
+      .querier()
+      .query_balance(&fee_collector, "native")
+      .unwrap()
+      .amount,
+    Uint128::new(10)
+  );
+
+  // Cw20 tokens are approved for the Token Bridge to pull, minus fees.
+  contract
+    .withdraw_cross_chain(withdraw_msg(usdc_addr.to_string(), sepolia.clone()))
+    .call(&user)
+    .unwrap();
+  let allowance: AllowanceResponse = app
+    .querier()
+    .query_wasm_smart(
+      &usdc_addr,
+      &cw20::Cw20QueryMsg::Allowance {
+        owner: contract.contract_addr.to_string(),
+        spender: token_bridge.to_string(),
+      },
+    )
+    .unwrap();
+  assert_eq!(allowance.allowance, Uint128::new(490));
+
+  // The withdrawals record their destination and deduct the payable.
+  let withdrawal_id = contract
+    .user_withdrawal_id(FetchIdMessage {
+      reference: user.to_string(),
+      count: 2,
+    })
+    .unwrap();
+  let withdrawal = contract.withdrawal(withdrawal_id).unwrap();
+  assert_eq!(withdrawal.dest_chain_id, CbChain::id_of("eip155:11155111"));
+  assert_eq!(withdrawal.dest_address, [7u8; 32]);
+  assert_eq!(withdrawal.details.amount, Uint128::new(500));
+  let payable = contract.payable(IdMessage { id: payable_id }).unwrap();
+  assert!(payable
+    .balances
+    .iter()
+    .all(|balance| balance.amount == Uint128::new(500)));
+}
@@ -1,5 +1,6 @@
+use sha3::{Digest, Keccak256};
 use sylvia::cw_schema::cw_serde;
-use sylvia::cw_std::{Addr, Uint128};
+use sylvia::cw_std::{Addr, Binary, Uint128};
 
 #[cw_serde(crate = "sylvia::cw_schema")]
 /// Keeps track of all activities on this chain.
@@ -60,12 +61,64 @@ impl ChainStats {
 pub struct Config {
   /// Wormhole-Chain ID for this chain.
   pub chain_id: u16,
+  /// CAIP-2 cbChainId (keccak256 of the CAIP-2 string) of this chain.
+  pub cb_chain_id: [u8; 32],
   /// Deployer of this contract.
   pub owner: Addr,
   /// Chainbills' FeeCollector address.
   pub chainbills_fee_collector: Addr,
   /// Percentage of withdrawal for fees with 2 decimal places. E.g. 2% is 200.
   pub withdrawal_fee_percentage: Uint128,
+  /// Wormhole Token Bridge through which cross-chain withdrawals are sent.
+  pub token_bridge: Option<Addr>,
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+/// Registry entry of a blockchain network, keyed by its CAIP-2 cbChainId.
+/// Maps the cbChainId to the network's IDs on the bridging protocols.
+pub struct CbChain {
+  /// keccak256 of the CAIP-2 string.
+  pub cb_chain_id: [u8; 32],
+  /// The CAIP-2 string (`namespace:reference`).
+  pub caip2: String,
+  /// The Wormhole Chain ID of the network, if registered.
+  pub wormhole_chain_id: Option<u16>,
+  /// The Circle (CCTP) domain of the network, if registered.
+  pub circle_domain: Option<u32>,
+}
+
+impl CbChain {
+  pub fn initialize(caip2: String) -> Self {
+    CbChain {
+      cb_chain_id: Self::id_of(&caip2),
+      caip2,
+      wormhole_chain_id: None,
+      circle_domain: None,
+    }
+  }
+
+  /// Computes the cbChainId of a CAIP-2 string. That is keccak256 of the
+  /// `namespace:reference` string, as on the EVM contracts.
+  pub fn id_of(caip2: &str) -> [u8; 32] {
+    Keccak256::digest(caip2.as_bytes()).into()
+  }
+
+  /// Whether the string is a CAIP-2 chain identifier. That is a namespace of
+  /// 3 to 8 `[-a-z0-9]` characters, a colon, and a reference of 1 to 32
+  /// `[-_a-zA-Z0-9]` characters.
+  pub fn is_valid_caip2(caip2: &str) -> bool {
+    let Some((namespace, reference)) = caip2.split_once(':') else {
+      return false;
+    };
+    (3..=8).contains(&namespace.len())
+      && namespace
+        .bytes()
+        .all(|b| b == b'-' || b.is_ascii_lowercase() || b.is_ascii_digit())
+      && (1..=32).contains(&reference.len())
+      && reference
+        .bytes()
+        .all(|b| b == b'-' || b == b'_' || b.is_ascii_alphanumeric())
+  }
 }
 
 #[cw_serde(crate = "sylvia::cw_schema")]
@@ -170,6 +223,91 @@ impl TokenDetails {
   }
 }
 
+#[cw_serde(crate = "sylvia::cw_schema")]
+/// Compares what the contract holds of a token against what payables owe in
+/// it, and against the token's totals.
+pub struct TokenIntegrity {
+  /// The token's denom (native) or address (Cw20).
+  pub token: String,
+  /// The contract's bank or Cw20 balance of the token.
+  pub holdings: Uint128,
+  /// The TokenDetails' total_payable_received.
+  pub total_payable_received: Uint128,
+  /// The TokenDetails' total_withdrawn. Includes fees and escrow refunds.
+  pub total_withdrawn: Uint128,
+  /// The sum of the token's balances in the checked payables.
+  pub payables_balance: Uint128,
+  /// The number of payables that were checked.
+  pub payables_count: u64,
+  /// The sum of the checked payables' payments in the token that are still
+  /// held in escrow.
+  pub escrowed: Uint128,
+  /// The sum of the native deposits in the token of subscriptions into the
+  /// checked payables.
+  pub subscription_deposits: Uint128,
+  /// Whether the holdings cover the balances, escrowed payments and
+  /// deposits. None if only some payables were checked, as the sums are then
+  /// partial.
+  pub is_solvent: Option<bool>,
+  /// Whether the token's totals net to the balances and escrowed payments.
+  /// None if only some payables were checked.
+  pub is_consistent: Option<bool>,
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+/// A token of a TokenGroup, with the decimals of its amounts.
+pub struct TokenGroupMember {
+  /// The token's denom (native) or address (Cw20).
+  pub token: String,
+  /// The decimals of the token's amounts.
+  pub decimals: u8,
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+/// Tokens that payables can accept interchangeably, like stablecoins of the
+/// same currency and their bridged variants. Managed by the owner.
+///
+/// A payable's allowed token can reference a group as "group:<name>", with
+/// an amount in the group's decimals. Payments in any member then match it,
+/// for the amount normalized to the member's decimals.
+pub struct TokenGroup {
+  /// The decimals of amounts in allowed tokens and amounts that reference
+  /// this group.
+  pub decimals: u8,
+  /// The tokens of the group.
+  pub members: Vec<TokenGroupMember>,
+}
+
+impl TokenGroup {
+  /// Prefixes a group's name in the token of an allowed token and amount.
+  pub const REFERENCE_PREFIX: &'static str = "group:";
+
+  /// The maximum number of tokens in a group.
+  pub const MAX_MEMBERS: usize = 8;
+
+  /// The name of the group that the token references, if it does.
+  pub fn referenced_name(token: &str) -> Option<&str> {
+    token.strip_prefix(Self::REFERENCE_PREFIX)
+  }
+
+  /// The amount of the member token that is worth the amount in the group's
+  /// decimals. None if the token isn't a member or if the amount can't be
+  /// expressed exactly in the token's decimals.
+  pub fn member_amount(&self, token: &str, amount: Uint128) -> Option<Uint128> {
+    let member = self.members.iter().find(|m| m.token == token)?;
+    if member.decimals >= self.decimals {
+      let factor =
+        10u128.checked_pow((member.decimals - self.decimals) as u32)?;
+      amount.checked_mul(Uint128::new(factor)).ok()
+    } else {
+      let divisor =
+        10u128.checked_pow((self.decimals - member.decimals) as u32)?;
+      let divisor = Uint128::new(divisor);
+      (amount.checked_rem(divisor).ok()?.is_zero()).then(|| amount / divisor)
+    }
+  }
+}
+
 #[cw_serde(crate = "sylvia::cw_schema")]
 /// A combination of a token address and its associated amount.
 ///
@@ -224,6 +362,186 @@ impl Payable {
   pub fn next_activity(&self) -> u64 {
     self.activities_count.checked_add(1).unwrap()
   }
+
+  /// Adds the amount to the payable's balance of the token.
+  pub fn add_balance(&mut self, token: &str, amount: Uint128) {
+    match self
+      .balances
+      .iter_mut()
+      .find(|balance| balance.token == token)
+    {
+      Some(balance) => {
+        balance.amount = balance.amount.checked_add(amount).unwrap()
+      }
+      None => self.balances.push(TokenAndAmount {
+        token: token.to_string(),
+        amount,
+      }),
+    }
+  }
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+/// A wallet to which a payable's host has delegated some roles on the
+/// payable. Operators never receive funds: withdrawals that they trigger
+/// still go to the host.
+pub struct PayableOperator {
+  /// The host that granted the roles. Grants stop working once the payable
+  /// is transferred to another host.
+  pub host: Addr,
+  /// Bit flags of the roles that the operator holds.
+  pub roles: u8,
+}
+
+impl PayableOperator {
+  /// Can close and reopen the payable and update its allowed tokens and
+  /// amounts.
+  pub const ROLE_MANAGE: u8 = 1;
+
+  /// Can withdraw from the payable to the host's wallet.
+  pub const ROLE_WITHDRAW: u8 = 1 << 1;
+
+  /// All the roles that an operator can hold.
+  pub const ALL_ROLES: u8 = Self::ROLE_MANAGE | Self::ROLE_WITHDRAW;
+
+  /// Whether the given roles are a valid non-empty set of roles.
+  pub fn are_valid_roles(roles: u8) -> bool {
+    roles != 0 && roles & !Self::ALL_ROLES == 0
+  }
+
+  /// Whether this operator holds the role granted by the payable's current
+  /// host.
+  pub fn permits(&self, host: &Addr, role: u8) -> bool {
+    self.host == host && self.roles & role == role
+  }
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+/// A payer's recurring payment into a payable. Anyone can collect it once
+/// due, which pays the payable from the payer's cw20 allowance to this
+/// contract, or from the payer's deposit for native tokens.
+pub struct Subscription {
+  /// The wallet that pays.
+  pub payer: Addr,
+  /// The ID of the Payable into which payments are made.
+  pub payable_id: [u8; 32],
+  /// The token of each payment.
+  pub token: String,
+  /// The amount of each payment.
+  pub amount: Uint128,
+  /// The seconds between payments.
+  pub period: u64,
+  /// The timestamp in seconds from which the next payment can be collected.
+  pub next_due: u64,
+  /// The number of payments collected so far.
+  pub payments_count: u64,
+  /// The native tokens deposited for upcoming payments. Refunded to the
+  /// payer on cancellation.
+  pub deposit: Uint128,
+  /// Whether the subscription was cancelled and can no longer be collected.
+  pub is_cancelled: bool,
+}
+
+impl Subscription {
+  /// The first period boundary after `now`. Periods missed before `now` are
+  /// skipped, so only one payment is collected per collection.
+  pub fn next_due_after(&self, now: u64) -> u64 {
+    let missed = now.saturating_sub(self.next_due) / self.period;
+    self
+      .next_due
+      .saturating_add(missed.saturating_add(1).saturating_mul(self.period))
+  }
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+/// A payable's escrow settings. Payments into the payable are locked in
+/// EscrowStates and only count in the payable's balances once released.
+pub struct PayableEscrow {
+  /// The seconds after a payment from which anyone can release it, unless
+  /// the payer disputed it.
+  pub timeout: u64,
+  /// The wallet that settles disputed payments. None if the payments can't
+  /// be disputed.
+  pub arbiter: Option<Addr>,
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+/// Stages of an escrowed payment.
+pub enum EscrowStatus {
+  /// The payment is held until it is released or refunded.
+  Locked,
+  /// The payer disputed the payment. Only the payer or the arbiter can
+  /// settle it now.
+  Disputed,
+  /// The payment was added to the payable's balances.
+  Released,
+  /// The payment was sent back to the payer.
+  Refunded,
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+/// A payment held in escrow for a payable. Shares its ID with the payment's
+/// PayablePayment.
+pub struct EscrowState {
+  /// The ID of the Payable into which the payment was made.
+  pub payable_id: [u8; 32],
+  /// The wallet that made the payment and receives any refund.
+  pub payer: Addr,
+  /// The token and amount held.
+  pub details: TokenAndAmount,
+  /// The timestamp in seconds from which anyone can release the payment.
+  pub release_after: u64,
+  /// The wallet that settles a dispute, as set on the payable at payment.
+  pub arbiter: Option<Addr>,
+  /// The current stage of the payment.
+  pub status: EscrowStatus,
+}
+
+impl EscrowState {
+  /// Whether the payment is still held.
+  pub fn is_held(&self) -> bool {
+    matches!(self.status, EscrowStatus::Locked | EscrowStatus::Disputed)
+  }
+
+  /// Whether the wallet can release the held payment at the given time.
+  /// The payer and the arbiter can do so anytime, and anyone else can once
+  /// the timeout elapsed on an undisputed payment.
+  pub fn can_release(&self, wallet: &Addr, now: u64) -> bool {
+    self.is_held()
+      && (*wallet == self.payer
+        || self.arbiter.as_ref() == Some(wallet)
+        || (self.status == EscrowStatus::Locked && now >= self.release_after))
+  }
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+/// A contract notified of each payment into a payable.
+pub struct PayableHook {
+  /// The contract executed with a ChainbillsPaymentNotification.
+  pub contract: Addr,
+  /// Whether a failing notification reverts the payment. If not, the failure
+  /// is recorded and the payment goes through.
+  pub revert_on_error: bool,
+  /// The gas that the notification can use. A hook that runs out fails like
+  /// any other, so it can't eat the gas of the payment.
+  pub gas_limit: u64,
+}
+
+impl PayableHook {
+  /// The most gas that a hook's notification can be given.
+  pub const MAX_GAS_LIMIT: u64 = 1_000_000;
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+/// A payment whose notification to the payable's hook failed. Shares its ID
+/// with the payment's PayablePayment.
+pub struct PaymentHookFailure {
+  /// The ID of the Payable into which the payment was made.
+  pub payable_id: [u8; 32],
+  /// The contract that failed to process the notification.
+  pub hook: Addr,
+  /// The error returned by the contract.
+  pub error: String,
 }
 
 #[cw_serde(crate = "sylvia::cw_schema")]
@@ -234,8 +552,8 @@ pub struct UserPayment {
   pub payable_id: [u8; 32],
   /// The wallet address that made this Payment.
   pub payer: Addr,
-  /// The Wormhole Chain ID of the chain into which the payment was made.
-  pub payable_chain_id: u16,
+  /// The cbChainId of the chain into which the payment was made.
+  pub payable_chain_id: [u8; 32],
   /// The nth count of payments on this chain at the point this payment
   /// was made.
   pub chain_count: u64,
@@ -261,8 +579,8 @@ pub struct PayablePayment {
   /// The nth count of payable payments on this chain at the point this payment
   /// was received.
   pub chain_count: u64,
-  /// The Wormhole Chain ID of the chain from which the payment was made.
-  pub payer_chain_id: u16,
+  /// The cbChainId of the chain from which the payment was made.
+  pub payer_chain_id: [u8; 32],
   /// The nth count of payments to this payable from the payment source
   /// chain at the point this payment was recorded.
   pub local_chain_count: u64,
@@ -295,6 +613,12 @@ pub struct Withdrawal {
   pub timestamp: u64,
   /// The amount and token that the host withdrew
   pub details: TokenAndAmount,
+  /// The cbChainId of the chain to which the withdrawn funds were sent.
+  /// This chain's cbChainId if the withdrawal wasn't cross-chain.
+  pub dest_chain_id: [u8; 32],
+  /// The Wormhole-normalized address to which the withdrawn funds were sent.
+  /// The host's wallet if the withdrawal wasn't cross-chain.
+  pub dest_address: [u8; 32],
 }
 
 #[cw_serde(crate = "sylvia::cw_schema")]
@@ -316,6 +640,28 @@ pub enum ActivityType {
   ReopenedPayable,
   /// The payable's allowed tokens and amounts were updated.
   UpdatedPayableAllowedTokensAndAmounts,
+  /// The payable was transferred away from its previous host.
+  TransferredPayable,
+  /// The payable was accepted by its new host.
+  AcceptedPayable,
+  /// The payable's allowed payers were updated.
+  UpdatedPayableAllowedPayers,
+  /// A user subscribed to recurring payments into the payable.
+  CreatedSubscription,
+  /// A subscription to the payable was cancelled.
+  CancelledSubscription,
+  /// The payable's escrow settings were updated.
+  UpdatedPayableEscrow,
+  /// An escrowed payment was released to the payable.
+  ReleasedEscrow,
+  /// An escrowed payment was disputed by its payer.
+  DisputedEscrow,
+  /// An escrowed payment was refunded to its payer.
+  RefundedEscrow,
+  /// The payable's hook contract was updated.
+  UpdatedPayableHook,
+  /// The payable's receipt settings were updated.
+  UpdatedPayableReceipts,
 }
 
 #[cw_serde(crate = "sylvia::cw_schema")]
@@ -338,3 +684,101 @@ pub struct ActivityRecord {
   /// The type of activity.
   pub activity_type: ActivityType,
 }
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+/// The kinds of stored entries that the migration to cbChainIds moves, in
+/// the order that it moves them.
+pub enum MigrationStage {
+  UserPayments,
+  PayablePayments,
+  PerChainPayablePayments,
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+/// How far the migration to cbChainIds has gone. Only stored while it is in
+/// progress.
+pub struct MigrationProgress {
+  /// The kind of entries being migrated.
+  pub stage: MigrationStage,
+  /// The storage key, within the stage's namespace, of the last entry
+  /// checked. None at the start of the stage.
+  pub cursor: Option<Binary>,
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+/// Config as stored before chains were keyed by their cbChainId.
+pub struct ConfigV0 {
+  pub chain_id: u16,
+  pub owner: Addr,
+  pub chainbills_fee_collector: Addr,
+  pub withdrawal_fee_percentage: Uint128,
+}
+
+impl ConfigV0 {
+  pub fn upgrade(self, cb_chain_id: [u8; 32]) -> Config {
+    Config {
+      chain_id: self.chain_id,
+      cb_chain_id,
+      owner: self.owner,
+      chainbills_fee_collector: self.chainbills_fee_collector,
+      withdrawal_fee_percentage: self.withdrawal_fee_percentage,
+      token_bridge: None,
+    }
+  }
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+/// UserPayment as stored before chains were keyed by their cbChainId.
+pub struct UserPaymentV0 {
+  pub payable_id: [u8; 32],
+  pub payer: Addr,
+  /// The Wormhole Chain ID of the chain into which the payment was made.
+  pub payable_chain_id: u16,
+  pub chain_count: u64,
+  pub payer_count: u64,
+  pub timestamp: u64,
+  pub details: TokenAndAmount,
+}
+
+impl UserPaymentV0 {
+  pub fn upgrade(self, payable_chain_id: [u8; 32]) -> UserPayment {
+    UserPayment {
+      payable_id: self.payable_id,
+      payer: self.payer,
+      payable_chain_id,
+      chain_count: self.chain_count,
+      payer_count: self.payer_count,
+      timestamp: self.timestamp,
+      details: self.details,
+    }
+  }
+}
+
+#[cw_serde(crate = "sylvia::cw_schema")]
+/// PayablePayment as stored before chains were keyed by their cbChainId.
+pub struct PayablePaymentV0 {
+  pub payable_id: [u8; 32],
+  pub payer: [u8; 32],
+  pub chain_count: u64,
+  /// The Wormhole Chain ID of the chain from which the payment was made.
+  pub payer_chain_id: u16,
+  pub local_chain_count: u64,
+  pub payable_count: u64,
+  pub timestamp: u64,
+  pub details: TokenAndAmount,
+}
+
+impl PayablePaymentV0 {
+  pub fn upgrade(self, payer_chain_id: [u8; 32]) -> PayablePayment {
+    PayablePayment {
+      payable_id: self.payable_id,
+      payer: self.payer,
+      chain_count: self.chain_count,
+      payer_chain_id,
+      local_chain_count: self.local_chain_count,
+      payable_count: self.payable_count,
+      timestamp: self.timestamp,
+      details: self.details,
+    }
+  }
+}
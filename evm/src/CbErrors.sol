@@ -93,6 +93,8 @@ contract CbErrors {
   error PayableIsClosed();
   /// @notice Thrown when attempting to reopen a payable that is not currently closed.
   error PayableIsNotClosed();
+  /// @notice Thrown when the payer isn't in a payable's allowed payers or their proof is invalid.
+  error PayerNotAllowed();
   /// @notice Thrown when a cross-chain payable update has a nonce older or equal to the last recorded nonce.
   error StalePayableUpdateNonce();
   /// @notice Thrown when a native token fee withdrawal transfer fails.
@@ -90,6 +90,13 @@ contract CbEvents is CbErrors {
   /// @param isAutoWithdraw The new auto-withdraw status.
   event UpdatedPayableAutoWithdrawStatus(bytes32 indexed payableId, address indexed hostWallet, bool isAutoWithdraw);
 
+  /// Emitted when a `hostWallet` updates the allowed payers on their
+  /// {Payable} with `payableId`.
+  /// @param payableId The ID of the updated payable.
+  /// @param hostWallet The address of the host who updated it.
+  /// @param allowedPayersRoot The new Merkle root of allowed payers.
+  event UpdatedPayableAllowedPayers(bytes32 indexed payableId, address indexed hostWallet, bytes32 allowedPayersRoot);
+
   /// Emitted when a Wormhole Message is consumed for payable update.
   /// @param payableId The ID of the payable that was updated.
   /// @param cbChainId The CAIP-2 chain ID of the source chain.
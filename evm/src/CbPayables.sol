@@ -105,7 +105,8 @@ contract CbPayables is CbUtils {
         payableId: payableId,
         nonce: 0, // set inside _broadcastPayableUpdate
         isClosed: false,
-        allowedTokensAndAmounts: foreignAtaa
+        allowedTokensAndAmounts: foreignAtaa,
+        allowedPayersRoot: bytes32(0)
       })
     );
   }
@@ -173,7 +174,8 @@ contract CbPayables is CbUtils {
         payableId: payableId,
         nonce: 0,
         isClosed: true,
-        allowedTokensAndAmounts: new TokenAndAmountForeign[](0)
+        allowedTokensAndAmounts: new TokenAndAmountForeign[](0),
+        allowedPayersRoot: bytes32(0)
       })
     );
   }
@@ -213,7 +215,8 @@ contract CbPayables is CbUtils {
         payableId: payableId,
         nonce: 0,
         isClosed: false,
-        allowedTokensAndAmounts: new TokenAndAmountForeign[](0)
+        allowedTokensAndAmounts: new TokenAndAmountForeign[](0),
+        allowedPayersRoot: bytes32(0)
       })
     );
   }
@@ -283,7 +286,56 @@ contract CbPayables is CbUtils {
     // Broadcast ATAA update to all registered foreign chains.
     wormholeMessageSequence = _broadcastPayableUpdate(
       PayablePayload({
-        version: 1, actionType: 4, payableId: payableId, nonce: 0, isClosed: false, allowedTokensAndAmounts: foreignAtaa
+        version: 1,
+        actionType: 4,
+        payableId: payableId,
+        nonce: 0,
+        isClosed: false,
+        allowedTokensAndAmounts: foreignAtaa,
+        allowedPayersRoot: bytes32(0)
+      })
+    );
+  }
+
+  /// Allows a payable's host to restrict who can pay the payable.
+  /// @param payableId The ID of the payable to update.
+  /// @param allowedPayersRoot Merkle root over keccak256 of the allowed payers'
+  /// Wormhole-formatted addresses. Zero allows anyone to pay.
+  /// @return wormholeMessageSequence The sequence of the published Wormhole
+  /// message.
+  function updatePayableAllowedPayers(bytes32 payableId, bytes32 allowedPayersRoot)
+    public
+    payable
+    returns (uint64 wormholeMessageSequence)
+  {
+    /* CHECKS */
+    // Ensure that the caller owns the payable.
+    Payable storage _payable = payables[payableId];
+    if (_payable.host != msg.sender) revert NotYourPayable();
+
+    // Ensure that the required Wormhole Fees were paid.
+    if (hasWormhole()) _ensureWormholeFees();
+
+    /* STATE CHANGES */
+    // Set the allowed payers root.
+    payableAllowedPayersRoots[payableId] = allowedPayersRoot;
+
+    // Record the Activity.
+    _recordUpdatePayableActivity(payableId, ActivityType.UpdatedPayableAllowedPayers);
+
+    // Emit Event.
+    emit UpdatedPayableAllowedPayers(payableId, msg.sender, allowedPayersRoot);
+
+    // Broadcast the allowed payers root to all registered foreign chains.
+    wormholeMessageSequence = _broadcastPayableUpdate(
+      PayablePayload({
+        version: 1,
+        actionType: 5,
+        payableId: payableId,
+        nonce: 0,
+        isClosed: false,
+        allowedTokensAndAmounts: new TokenAndAmountForeign[](0),
+        allowedPayersRoot: allowedPayersRoot
       })
     );
   }
@@ -347,7 +399,8 @@ contract CbPayables is CbUtils {
         payableId: payableId,
         nonce: 0,
         isClosed: _payable.isClosed,
-        allowedTokensAndAmounts: foreignAtaa
+        allowedTokensAndAmounts: foreignAtaa,
+        allowedPayersRoot: bytes32(0)
       })
     );
   }
@@ -444,6 +497,7 @@ contract CbPayables is CbUtils {
   /// @param cbChainId CAIP-2 cbChainId of the source chain.
   /// @param nonce Must be strictly greater than the last recorded nonce.
   /// @param actionType 1=Create/snapshot, 2=Close, 3=Reopen, 4=UpdateATAA.
+  /// Allowed payers roots (5) are synced with adminSyncForeignPayableAllowedPayers.
   /// @param isClosed Current closed status (for actionType 2 or 3).
   /// @param ataa Allowed tokens and amounts (for actionType 1 or 4).
   function adminSyncForeignPayable(
@@ -456,6 +510,7 @@ contract CbPayables is CbUtils {
   ) public {
     if (payableId == bytes32(0)) revert InvalidPayableId();
     if (cbChainId == bytes32(0)) revert InvalidChainId();
+    if (actionType == 5) revert InvalidPayablePayloadActionType();
 
     uint64 lastNonce = payableUpdateNonces[payableId][cbChainId];
     if (nonce <= lastNonce) revert StalePayableUpdateNonce();
@@ -472,7 +527,41 @@ contract CbPayables is CbUtils {
       payableId: payableId,
       nonce: nonce,
       isClosed: isClosed,
-      allowedTokensAndAmounts: ataaMem
+      allowedTokensAndAmounts: ataaMem,
+      allowedPayersRoot: bytes32(0)
+    });
+
+    _applyPayablePayloadUpdate(payload, cbChainId);
+    emit ReceivedPayableUpdateViaAdminSync(payableId, cbChainId, nonce, msg.sender);
+  }
+
+  /// Admin escape hatch for syncing a foreign payable's allowed payers root.
+  /// Same rules as adminSyncForeignPayable (nonce ordering still applies).
+  /// @param payableId The payable ID on the source chain.
+  /// @param cbChainId CAIP-2 cbChainId of the source chain.
+  /// @param nonce Must be strictly greater than the last recorded nonce.
+  /// @param allowedPayersRoot The payable's allowed payers root (zero for anyone).
+  function adminSyncForeignPayableAllowedPayers(
+    bytes32 payableId,
+    bytes32 cbChainId,
+    uint64 nonce,
+    bytes32 allowedPayersRoot
+  ) public {
+    if (payableId == bytes32(0)) revert InvalidPayableId();
+    if (cbChainId == bytes32(0)) revert InvalidChainId();
+
+    uint64 lastNonce = payableUpdateNonces[payableId][cbChainId];
+    if (nonce <= lastNonce) revert StalePayableUpdateNonce();
+    payableUpdateNonces[payableId][cbChainId] = nonce;
+
+    PayablePayload memory payload = PayablePayload({
+      version: 2,
+      actionType: 5,
+      payableId: payableId,
+      nonce: nonce,
+      isClosed: false,
+      allowedTokensAndAmounts: new TokenAndAmountForeign[](0),
+      allowedPayersRoot: allowedPayersRoot
     });
 
     _applyPayablePayloadUpdate(payload, cbChainId);
@@ -480,7 +569,7 @@ contract CbPayables is CbUtils {
   }
 
   /// Applies a decoded PayablePayload to the foreignPayables state.
-  /// Handles all four action types. Extracted to be shared by Wormhole,
+  /// Handles all five action types. Extracted to be shared by Wormhole,
   /// CCTP, and admin-sync receivers.
   /// @param payload Decoded PayablePayload (already nonce-checked by caller).
   /// @param srcCbChainId CAIP-2 cbChainId of the chain that originated this update.
@@ -514,6 +603,9 @@ contract CbPayables is CbUtils {
     } else if (payload.actionType == 2 || payload.actionType == 3) {
       // Close (2) or Reopen (3): update isClosed.
       foreignPayable.isClosed = payload.isClosed;
+    } else if (payload.actionType == 5) {
+      // Update allowed payers (5): replace the stored root.
+      foreignPayableAllowedPayersRoots[payableId] = payload.allowedPayersRoot;
     } else {
       revert InvalidPayablePayloadActionType();
     }
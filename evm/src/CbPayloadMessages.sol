@@ -15,6 +15,7 @@ library CbEncodePayablePayload {
   ///   then action-specific fields:
   ///     actionType 1 or 4: ataaLength(1) | [token(32) | amount(8)] * n
   ///     actionType 2 or 3: isClosed(1)
+  ///     actionType 5: allowedPayersRoot(32)
   ///
   /// @param payload PayablePayload struct
   /// @return encoded bytes
@@ -30,6 +31,8 @@ library CbEncodePayablePayload {
       }
     } else if (payload.actionType == 2 || payload.actionType == 3) {
       encoded = abi.encodePacked(encoded, payload.isClosed);
+    } else if (payload.actionType == 5) {
+      encoded = abi.encodePacked(encoded, payload.allowedPayersRoot);
     } else {
       revert CbErrors.InvalidPayablePayloadActionType();
     }
@@ -86,6 +89,8 @@ library CbDecodePayload {
       }
     } else if (parsed.actionType == 2 || parsed.actionType == 3) {
       (parsed.isClosed, index) = encoded.asBool(index);
+    } else if (parsed.actionType == 5) {
+      (parsed.allowedPayersRoot, index) = encoded.asBytes32(index);
     } else {
       revert CbErrors.InvalidPayablePayloadActionType();
     }
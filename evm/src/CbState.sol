@@ -114,6 +114,12 @@ contract CbState is CbStructs {
   /// Set by registerChainWormholeId. Used in VAA verification to resolve the
   /// emitter chain to its cbChainId for registeredForeignContracts lookup.
   mapping(uint16 => bytes32) public wormholeChainIdToCbChainId;
+  /// Merkle root of the payers allowed to pay each local payable.
+  /// Zero means anyone can pay. Set by updatePayableAllowedPayers.
+  mapping(bytes32 => bytes32) public payableAllowedPayersRoots;
+  /// Merkle root of the payers allowed to pay each foreign payable.
+  /// Mirrored from actionType 5 payable updates. Zero means anyone can pay.
+  mapping(bytes32 => bytes32) public foreignPayableAllowedPayersRoots;
   /// storage gap for additional state variables in future versions
   // forge-lint: disable-next-line(mixed-case-variable)
   uint256[50] __gap;
@@ -225,7 +225,9 @@ contract CbStructs is CbEvents {
     /// The payable's allowed tokens and amounts were updated.
     UpdatedPayableAllowedTokensAndAmounts,
     /// The payable's auto withdraw setting was updated.
-    UpdatedPayableAutoWithdrawStatus
+    UpdatedPayableAutoWithdrawStatus,
+    /// The payable's allowed payers were updated.
+    UpdatedPayableAllowedPayers
   }
 
   /// A record of an activity.
@@ -281,6 +283,7 @@ contract CbStructs is CbEvents {
     /// 2 - ClosedPayable
     /// 3 - ReopenedPayable
     /// 4 - UpdatedPayableAllowedTokensAndAmounts
+    /// 5 - UpdatedPayableAllowedPayers
     uint8 actionType;
     /// The Payable's ID.
     bytes32 payableId;
@@ -292,6 +295,8 @@ contract CbStructs is CbEvents {
     bool isClosed;
     /// The allowed tokens and their amounts.
     TokenAndAmountForeign[] allowedTokensAndAmounts;
+    /// Merkle root of the payers allowed to pay the payable. Zero means anyone.
+    bytes32 allowedPayersRoot;
   }
 
   /// Necessary info to record a payable's payment if the involved blockchain
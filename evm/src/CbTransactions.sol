@@ -3,6 +3,7 @@ pragma solidity ^0.8.30;
 
 import {IERC20} from '@openzeppelin/contracts/token/ERC20/IERC20.sol';
 import {SafeERC20} from '@openzeppelin/contracts/token/ERC20/utils/SafeERC20.sol';
+import {MerkleProof} from '@openzeppelin/contracts/utils/cryptography/MerkleProof.sol';
 import {SafeCast} from '@openzeppelin/contracts/utils/math/SafeCast.sol';
 import {BytesParsing} from 'wormhole/libraries/BytesParsing.sol';
 import {IWormhole} from 'wormhole/interfaces/IWormhole.sol';
@@ -30,6 +31,17 @@ contract CbTransactions is CbUtils {
     if (amount == 0) revert ZeroAmountSpecified();
   }
 
+  /// Ensures that the caller is among a payable's allowed payers.
+  /// @param allowedPayersRoot The payable's allowed payers root. Zero allows
+  /// anyone to pay.
+  /// @param payerProof Merkle proof of keccak256 of the caller's
+  /// Wormhole-formatted address against the root.
+  function _ensureAllowedPayer(bytes32 allowedPayersRoot, bytes32[] memory payerProof) internal view {
+    if (allowedPayersRoot == bytes32(0)) return;
+    bytes32 leaf = keccak256(abi.encodePacked(toWormholeFormat(msg.sender)));
+    if (!MerkleProof.verify(payerProof, allowedPayersRoot, leaf)) revert PayerNotAllowed();
+  }
+
   /// Updates an existing payable balance entry for `token` by adding `amount`.
   /// Returns true if a matching entry was found and updated, false otherwise.
   function _updateBalance(bytes32 payableId, address token, uint256 amount) internal returns (bool) {
@@ -325,6 +337,30 @@ contract CbTransactions is CbUtils {
     public
     payable
     returns (bytes32 userPaymentId, bytes32 payablePaymentId)
+  {
+    return _pay(payableId, token, amount, new bytes32[](0));
+  }
+
+  /// Transfers the amount of tokens from a payer to a payable that restricts
+  /// its payers.
+  /// @param payableId The ID of the payable to pay into.
+  /// @param token The address of the token been paid.
+  /// @param amount The amount of the token.
+  /// @param payerProof Merkle proof that the caller is an allowed payer.
+  /// @return userPaymentId The ID of the recorded payment from the user.
+  /// @return payablePaymentId The ID of the recorded payment from the payable.
+  function payWithProof(bytes32 payableId, address token, uint256 amount, bytes32[] calldata payerProof)
+    public
+    payable
+    returns (bytes32 userPaymentId, bytes32 payablePaymentId)
+  {
+    return _pay(payableId, token, amount, payerProof);
+  }
+
+  /// Shared implementation of pay and payWithProof.
+  function _pay(bytes32 payableId, address token, uint256 amount, bytes32[] memory payerProof)
+    internal
+    returns (bytes32 userPaymentId, bytes32 payablePaymentId)
   {
     /* CHECKS */
     // Basic Payment Checks
@@ -335,6 +371,9 @@ contract CbTransactions is CbUtils {
     if (_payable.host == address(0)) revert InvalidPayableId();
     if (_payable.isClosed) revert PayableIsClosed();
 
+    // Ensure that the caller is allowed to pay this payable.
+    _ensureAllowedPayer(payableAllowedPayersRoots[payableId], payerProof);
+
     // If this payable specified the tokens and amounts it can accept, ensure
     // that the token and amount are matching.
     uint8 aTaaLength = _payable.allowedTokensAndAmountsCount;
@@ -384,6 +423,33 @@ contract CbTransactions is CbUtils {
     public
     payable
     returns (bytes32 userPaymentId, uint64 wormholeMessageSequence)
+  {
+    return _payForeignWithCircle(payableId, token, amount, new bytes32[](0));
+  }
+
+  /// Transfers the amount of tokens from a payer to a foreign payable that
+  /// restricts its payers.
+  /// @param payableId The ID of the payable to pay into.
+  /// @param token The address of the token been paid.
+  /// @param amount The amount of the token.
+  /// @param payerProof Merkle proof that the caller is an allowed payer.
+  /// @return userPaymentId The ID of the recorded payment from the user.
+  /// @return wormholeMessageSequence The sequence number of the Wormhole
+  /// message.
+  function payForeignWithCircleWithProof(
+    bytes32 payableId,
+    address token,
+    uint256 amount,
+    bytes32[] calldata payerProof
+  ) public payable returns (bytes32 userPaymentId, uint64 wormholeMessageSequence) {
+    return _payForeignWithCircle(payableId, token, amount, payerProof);
+  }
+
+  /// Shared implementation of payForeignWithCircle and
+  /// payForeignWithCircleWithProof.
+  function _payForeignWithCircle(bytes32 payableId, address token, uint256 amount, bytes32[] memory payerProof)
+    internal
+    returns (bytes32 userPaymentId, uint64 wormholeMessageSequence)
   {
     /* CHECKS */
     // Basic Payment Checks
@@ -397,6 +463,9 @@ contract CbTransactions is CbUtils {
     if (_payable.chainId == bytes32(0)) revert InvalidPayableId();
     if (_payable.isClosed) revert PayableIsClosed();
 
+    // Ensure that the caller is allowed to pay this foreign payable.
+    _ensureAllowedPayer(foreignPayableAllowedPayersRoots[payableId], payerProof);
+
     // If this payable specified the tokens and amounts it can accept, ensure
     // that the token and amount are matching.
     uint8 aTaaLength = _payable.allowedTokensAndAmountsCount;
@@ -399,6 +399,30 @@ contract Chainbills is
     }
   }
 
+  /// @notice Updates the allowed payers for a payable.
+  /// - Parameter payableId: The ID of the payable.
+  /// - Parameter allowedPayersRoot: Merkle root of the allowed payers (zero for anyone).
+  /// @return wormholeMessageSequence The sequence number of the published Wormhole message.
+  function updatePayableAllowedPayers(
+    bytes32, /* payableId */
+    bytes32 /* allowedPayersRoot */
+  )
+    public
+    payable
+    nonReentrant
+    whenNotPaused
+    returns (uint64 wormholeMessageSequence)
+  {
+    (bool success, bytes memory result) = payablesLogic.delegatecall(msg.data);
+    if (!success) {
+      assembly {
+        revert(add(result, 32), mload(result))
+      }
+    } else {
+      return abi.decode(result, (uint64));
+    }
+  }
+
   /// @notice Updates the auto-withdraw status for a payable.
   /// - Parameter payableId: The ID of the payable.
   /// - Parameter isAutoWithdraw: The new auto-withdraw status.
@@ -531,6 +555,30 @@ contract Chainbills is
     }
   }
 
+  /// @notice Syncs a foreign payable's allowed payers root (admin only).
+  /// - Parameter payableId: The payable ID on the source chain.
+  /// - Parameter cbChainId: CAIP-2 cbChainId of the source chain.
+  /// - Parameter nonce: Must be strictly greater than the last recorded nonce.
+  /// - Parameter allowedPayersRoot: The payable's allowed payers root.
+  function adminSyncForeignPayableAllowedPayers(
+    bytes32, /* payableId */
+    bytes32, /* cbChainId */
+    uint64, /* nonce */
+    bytes32 /* allowedPayersRoot */
+  )
+    public
+    onlyRole(ADMIN_ROLE)
+    nonReentrant
+    whenNotPaused
+  {
+    (bool success, bytes memory result) = payablesLogic.delegatecall(msg.data);
+    if (!success) {
+      assembly {
+        revert(add(result, 32), mload(result))
+      }
+    }
+  }
+
   /// @notice Make a payment to a payable.
   /// - Parameter payableId: The ID of the payable.
   /// - Parameter token: The address of the token being paid.
@@ -560,6 +608,35 @@ contract Chainbills is
     }
   }
 
+  /// @notice Make a payment to a payable that restricts its payers.
+  /// - Parameter payableId: The ID of the payable.
+  /// - Parameter token: The address of the token being paid.
+  /// - Parameter amount: The amount of the token being paid.
+  /// - Parameter payerProof: Merkle proof that the caller is an allowed payer.
+  /// @return userPaymentId The ID of the user payment.
+  /// @return payablePaymentId The ID of the payable payment.
+  function payWithProof(
+    bytes32, /* payableId */
+    address, /* token */
+    uint256, /* amount */
+    bytes32[] calldata /* payerProof */
+  )
+    public
+    payable
+    nonReentrant
+    whenNotPaused
+    returns (bytes32 userPaymentId, bytes32 payablePaymentId)
+  {
+    (bool success, bytes memory result) = transactionsLogic.delegatecall(msg.data);
+    if (!success) {
+      assembly {
+        revert(add(result, 32), mload(result))
+      }
+    } else {
+      return abi.decode(result, (bytes32, bytes32));
+    }
+  }
+
   /// @notice Make a cross-chain payment to a foreign payable using Circle CCTP.
   /// - Parameter payableId: The ID of the foreign payable.
   /// - Parameter token: The address of the local token being paid.
@@ -589,6 +666,36 @@ contract Chainbills is
     }
   }
 
+  /// @notice Make a cross-chain payment to a foreign payable that restricts
+  /// its payers using Circle CCTP.
+  /// - Parameter payableId: The ID of the foreign payable.
+  /// - Parameter token: The address of the local token being paid.
+  /// - Parameter amount: The amount of the token being paid.
+  /// - Parameter payerProof: Merkle proof that the caller is an allowed payer.
+  /// @return userPaymentId The ID of the user payment.
+  /// @return wormholeMessageSequence The sequence number of the published Wormhole message.
+  function payForeignWithCircleWithProof(
+    bytes32, /* payableId */
+    address, /* token */
+    uint256, /* amount */
+    bytes32[] calldata /* payerProof */
+  )
+    public
+    payable
+    nonReentrant
+    whenNotPaused
+    returns (bytes32 userPaymentId, uint64 wormholeMessageSequence)
+  {
+    (bool success, bytes memory result) = transactionsLogic.delegatecall(msg.data);
+    if (!success) {
+      assembly {
+        revert(add(result, 32), mload(result))
+      }
+    } else {
+      return abi.decode(result, (bytes32, uint64));
+    }
+  }
+
   /// @notice Receives a cross-chain payment via Circle CCTP and Wormhole.
   /// - Parameter params: The parameters for redeeming the payment.
   /// @return payablePaymentId The ID of the payable payment.
@@ -260,4 +260,21 @@ contract CbAdminSyncTest is CbStructs, Test {
     chainbills.adminSyncForeignPayable(payableId, foreignCbChainId, 2, 4, false, new TokenAndAmountForeign[](0));
     assertEq(cbGetters.getForeignPayable(payableId).allowedTokensAndAmountsCount, 0);
   }
+
+  function testAdminSyncAllowedPayersSetsForeignRoot() public {
+    bytes32 root = keccak256('allowed-payers');
+
+    vm.prank(nonAdmin);
+    vm.expectPartialRevert(IAccessControl.AccessControlUnauthorizedAccount.selector);
+    chainbills.adminSyncForeignPayableAllowedPayers(payableId, foreignCbChainId, 1, root);
+
+    vm.prank(admin);
+    chainbills.adminSyncForeignPayableAllowedPayers(payableId, foreignCbChainId, 1, root);
+    assertEq(chainbills.foreignPayableAllowedPayersRoots(payableId), root);
+    assertEq(chainbills.payableUpdateNonces(payableId, foreignCbChainId), 1);
+
+    vm.prank(admin);
+    vm.expectRevert(StalePayableUpdateNonce.selector);
+    chainbills.adminSyncForeignPayableAllowedPayers(payableId, foreignCbChainId, 1, bytes32(0));
+  }
 }
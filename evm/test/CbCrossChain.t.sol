@@ -128,7 +128,8 @@ contract CbCrossChainTest is CbStructs, Test {
         payableId: payableId_,
         nonce: nonce_,
         isClosed: isClosed,
-        allowedTokensAndAmounts: emptyAtaa
+        allowedTokensAndAmounts: emptyAtaa,
+        allowedPayersRoot: bytes32(0)
       }).encode();
   }
 
@@ -113,7 +113,8 @@ contract CbFuzzTest is CbStructs, Test {
       payableId: payableId,
       nonce: nonce,
       isClosed: isClosed,
-      allowedTokensAndAmounts: ataa
+      allowedTokensAndAmounts: ataa,
+      allowedPayersRoot: bytes32(0)
     });
 
     PayablePayload memory decoded = original.encode().decodePayablePayload();
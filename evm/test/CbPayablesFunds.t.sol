@@ -416,4 +416,50 @@ contract CbPayablesFundsTest is CbStructs, Test {
     vm.expectRevert(UnsuccessfulFeesWithdrawal.selector);
     chainbills.withdraw(pid, address(chainbills), ethAmt);
   }
+
+  function testPayRestrictedToAllowedPayers() public {
+    vm.prank(host);
+    (bytes32 payableId,) = chainbills.createPayable(new TokenAndAmount[](0), false);
+
+    // Two-leaf tree of the user and the host; each leaf's proof is its sibling.
+    bytes32 userLeaf = keccak256(abi.encodePacked(toWormholeFormat(user)));
+    bytes32 hostLeaf = keccak256(abi.encodePacked(toWormholeFormat(host)));
+    bytes32 root = userLeaf < hostLeaf
+      ? keccak256(abi.encodePacked(userLeaf, hostLeaf))
+      : keccak256(abi.encodePacked(hostLeaf, userLeaf));
+
+    vm.expectRevert(NotYourPayable.selector);
+    chainbills.updatePayableAllowedPayers(payableId, root);
+
+    vm.prank(host);
+    vm.expectEmit(true, true, false, true);
+    emit UpdatedPayableAllowedPayers(payableId, host, root);
+    chainbills.updatePayableAllowedPayers(payableId, root);
+    assertEq(chainbills.payableAllowedPayersRoots(payableId), root);
+
+    bytes32[] memory proof = new bytes32[](1);
+    proof[0] = hostLeaf;
+
+    // Payers outside the tree, or without a proof, are rejected.
+    address stranger = makeAddr('stranger');
+    deal(stranger, ethAmt);
+    vm.prank(stranger);
+    vm.expectRevert(PayerNotAllowed.selector);
+    chainbills.payWithProof{value: ethAmt}(payableId, address(chainbills), ethAmt, proof);
+
+    vm.startPrank(user);
+    vm.expectRevert(PayerNotAllowed.selector);
+    chainbills.pay{value: ethAmt}(payableId, address(chainbills), ethAmt);
+
+    chainbills.payWithProof{value: ethAmt}(payableId, address(chainbills), ethAmt, proof);
+    vm.stopPrank();
+    assertEq(address(chainbills).balance, ethAmt);
+
+    // Clearing the root opens the payable to anyone again.
+    vm.prank(host);
+    chainbills.updatePayableAllowedPayers(payableId, bytes32(0));
+    vm.prank(stranger);
+    chainbills.pay{value: ethAmt}(payableId, address(chainbills), ethAmt);
+    assertEq(address(chainbills).balance, ethAmt * 2);
+  }
 }
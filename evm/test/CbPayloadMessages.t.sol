@@ -28,7 +28,8 @@ contract CbPayloadMessagesTest is CbStructs, Test {
       payableId: bytes32(0),
       nonce: 7,
       isClosed: false,
-      allowedTokensAndAmounts: ataa
+      allowedTokensAndAmounts: ataa,
+      allowedPayersRoot: bytes32(0)
     }).encode();
 
     PayablePayload memory parsed = encoded.decodePayablePayload();
@@ -49,7 +50,8 @@ contract CbPayloadMessagesTest is CbStructs, Test {
       payableId: keccak256('payable'),
       nonce: 42,
       isClosed: false,
-      allowedTokensAndAmounts: new TokenAndAmountForeign[](0)
+      allowedTokensAndAmounts: new TokenAndAmountForeign[](0),
+      allowedPayersRoot: bytes32(0)
     }).encode();
 
     PayablePayload memory parsed = encoded.decodePayablePayload();
@@ -69,7 +71,8 @@ contract CbPayloadMessagesTest is CbStructs, Test {
       payableId: pid,
       nonce: 13,
       isClosed: true,
-      allowedTokensAndAmounts: new TokenAndAmountForeign[](0)
+      allowedTokensAndAmounts: new TokenAndAmountForeign[](0),
+      allowedPayersRoot: bytes32(0)
     }).encode();
 
     PayablePayload memory parsed = encoded.decodePayablePayload();
@@ -92,7 +95,8 @@ contract CbPayloadMessagesTest is CbStructs, Test {
       payableId: pid,
       nonce: 99,
       isClosed: false,
-      allowedTokensAndAmounts: new TokenAndAmountForeign[](0)
+      allowedTokensAndAmounts: new TokenAndAmountForeign[](0),
+      allowedPayersRoot: bytes32(0)
     }).encode();
 
     PayablePayload memory parsed = encoded.decodePayablePayload();
@@ -117,7 +121,8 @@ contract CbPayloadMessagesTest is CbStructs, Test {
       payableId: bytes32(uint256(0xABC)),
       nonce: 5,
       isClosed: false,
-      allowedTokensAndAmounts: ataa
+      allowedTokensAndAmounts: ataa,
+      allowedPayersRoot: bytes32(0)
     }).encode();
 
     PayablePayload memory parsed = encoded.decodePayablePayload();
@@ -139,19 +144,40 @@ contract CbPayloadMessagesTest is CbStructs, Test {
       payableId: bytes32(0),
       nonce: 1,
       isClosed: false,
-      allowedTokensAndAmounts: new TokenAndAmountForeign[](0)
+      allowedTokensAndAmounts: new TokenAndAmountForeign[](0),
+      allowedPayersRoot: bytes32(0)
     }).encode();
   }
 
-  function testEncodePayablePayloadActionType5Reverts() public {
+  function testEncodeDecodePayablePayloadAllowedPayers() public pure {
+    bytes memory encoded = PayablePayload({
+      version: 1,
+      actionType: 5,
+      payableId: keccak256('payable'),
+      nonce: 9,
+      isClosed: false,
+      allowedTokensAndAmounts: new TokenAndAmountForeign[](0),
+      allowedPayersRoot: keccak256('root')
+    }).encode();
+
+    assertEq(encoded.length, 74);
+    PayablePayload memory parsed = encoded.decodePayablePayload();
+    assertEq(parsed.actionType, 5);
+    assertEq(parsed.nonce, 9);
+    assertEq(parsed.allowedPayersRoot, keccak256('root'));
+    assertEq(parsed.allowedTokensAndAmounts.length, 0);
+  }
+
+  function testEncodePayablePayloadActionType6Reverts() public {
     vm.expectRevert(InvalidPayablePayloadActionType.selector);
     PayablePayload({
       version: 1,
-      actionType: 5,
+      actionType: 6,
       payableId: bytes32(0),
       nonce: 1,
       isClosed: false,
-      allowedTokensAndAmounts: new TokenAndAmountForeign[](0)
+      allowedTokensAndAmounts: new TokenAndAmountForeign[](0),
+      allowedPayersRoot: bytes32(0)
     }).encode();
   }
 
@@ -174,7 +200,8 @@ contract CbPayloadMessagesTest is CbStructs, Test {
       payableId: bytes32(0),
       nonce: 1,
       isClosed: true,
-      allowedTokensAndAmounts: new TokenAndAmountForeign[](0)
+      allowedTokensAndAmounts: new TokenAndAmountForeign[](0),
+      allowedPayersRoot: bytes32(0)
     }).encode();
 
     bytes memory withTrailing = abi.encodePacked(valid, uint8(0xFF));
@@ -261,4 +288,122 @@ contract CbPayloadMessagesTest is CbStructs, Test {
     vm.expectRevert(InvalidPayload.selector);
     withTrailing.decodePaymentPayload();
   }
+
+  // ------------------------------------------------------------------------
+  // Golden vectors shared with the Solana and CosmWasm codecs
+  // ------------------------------------------------------------------------
+
+  /// Returns 32 bytes of `b`, as the vectors' values are written in vectors/README.md.
+  function _fill(uint8 b) internal pure returns (bytes32) {
+    return bytes32(type(uint256).max / 0xff * b);
+  }
+
+  function _golden(string memory name) internal view returns (bytes memory) {
+    return vm.parseBytes(string.concat('0x', vm.readFile(string.concat('../payload/vectors/', name, '.hex'))));
+  }
+
+  function testPayablePayloadCreatedMatchesGoldenVector() public view {
+    TokenAndAmountForeign[] memory ataa = new TokenAndAmountForeign[](2);
+    ataa[0] = TokenAndAmountForeign({token: _fill(0xaa), amount: 0x0102030405060708});
+    ataa[1] = TokenAndAmountForeign({token: _fill(0xbb), amount: 1000000});
+
+    bytes memory golden = _golden('payable_created');
+    bytes memory encoded = PayablePayload({
+      version: 1,
+      actionType: 1,
+      payableId: _fill(0x11),
+      nonce: 1,
+      isClosed: false,
+      allowedTokensAndAmounts: ataa,
+      allowedPayersRoot: bytes32(0)
+    }).encode();
+    assertEq(encoded, golden);
+
+    PayablePayload memory parsed = golden.decodePayablePayload();
+    assertEq(parsed.nonce, 1);
+    assertEq(parsed.allowedTokensAndAmounts.length, 2);
+    assertEq(parsed.allowedTokensAndAmounts[0].amount, 0x0102030405060708);
+  }
+
+  function testPayablePayloadClosedAndReopenedMatchGoldenVectors() public view {
+    bytes32 payableId = _fill(0x22);
+    TokenAndAmountForeign[] memory empty = new TokenAndAmountForeign[](0);
+
+    assertEq(
+      PayablePayload({
+        version: 1,
+        actionType: 2,
+        payableId: payableId,
+        nonce: 2,
+        isClosed: true,
+        allowedTokensAndAmounts: empty,
+        allowedPayersRoot: bytes32(0)
+      }).encode(),
+      _golden('payable_closed')
+    );
+    assertEq(
+      PayablePayload({
+        version: 1,
+        actionType: 3,
+        payableId: payableId,
+        nonce: 3,
+        isClosed: false,
+        allowedTokensAndAmounts: empty,
+        allowedPayersRoot: bytes32(0)
+      }).encode(),
+      _golden('payable_reopened')
+    );
+    assertEq(
+      PayablePayload({
+        version: 1,
+        actionType: 4,
+        payableId: payableId,
+        nonce: 4,
+        isClosed: false,
+        allowedTokensAndAmounts: empty,
+        allowedPayersRoot: bytes32(0)
+      }).encode(),
+      _golden('payable_updated_ataa')
+    );
+  }
+
+  function testPayablePayloadUpdatedAllowedPayersMatchesGoldenVector() public view {
+    bytes memory golden = _golden('payable_updated_allowed_payers');
+    assertEq(
+      PayablePayload({
+        version: 1,
+        actionType: 5,
+        payableId: _fill(0x22),
+        nonce: 5,
+        isClosed: false,
+        allowedTokensAndAmounts: new TokenAndAmountForeign[](0),
+        allowedPayersRoot: _fill(0x99)
+      }).encode(),
+      golden
+    );
+
+    PayablePayload memory parsed = golden.decodePayablePayload();
+    assertEq(parsed.actionType, 5);
+    assertEq(parsed.allowedPayersRoot, _fill(0x99));
+  }
+
+  function testPaymentPayloadMatchesGoldenVector() public view {
+    bytes memory golden = _golden('payment');
+    bytes memory encoded = PaymentPayload({
+      version: 1,
+      payableId: _fill(0x33),
+      payableChainToken: _fill(0x44),
+      payableChainId: _fill(0x55),
+      payer: _fill(0x66),
+      payerChainToken: _fill(0x77),
+      payerChainId: _fill(0x88),
+      amount: 5000000,
+      circleNonce: 999
+    }).encode();
+    assertEq(encoded, golden);
+
+    PaymentPayload memory parsed = golden.decodePaymentPayload();
+    assertEq(parsed.amount, 5000000);
+    assertEq(parsed.circleNonce, 999);
+  }
 }
@@ -0,0 +1,125 @@
+use alloc::vec::Vec;
+
+/// The allowed payers root of payables that anyone can pay.
+pub const OPEN_TO_ALL_PAYERS: [u8; 32] = [0u8; 32];
+
+// A payable's allowed payers are committed to by the root of a Merkle tree
+// whose leaves are the keccak256 of each payer's 32-byte (Wormhole-normalized)
+// address. Leaves are sorted and each parent is the keccak256 of its two
+// children in ascending order, as in OpenZeppelin's MerkleProof, so proofs
+// don't need to carry positions. A node without a sibling moves up a level
+// unchanged, so the root of a single payer is their leaf and their proof is
+// empty.
+//
+// This crate doesn't depend on a keccak256 implementation. Each chain passes
+// its own as `keccak`, which hashes the concatenation of the given slices.
+
+/// The Merkle leaf of a payer.
+pub fn allowed_payer_leaf<H>(payer: &[u8; 32], keccak: &H) -> [u8; 32]
+where
+  H: Fn(&[&[u8]]) -> [u8; 32],
+{
+  keccak(&[payer])
+}
+
+fn hash_pair<H>(a: &[u8; 32], b: &[u8; 32], keccak: &H) -> [u8; 32]
+where
+  H: Fn(&[&[u8]]) -> [u8; 32],
+{
+  if a <= b {
+    keccak(&[a, b])
+  } else {
+    keccak(&[b, a])
+  }
+}
+
+/// The sorted and deduplicated leaves of the given payers.
+fn sorted_leaves<H>(payers: &[[u8; 32]], keccak: &H) -> Vec<[u8; 32]>
+where
+  H: Fn(&[&[u8]]) -> [u8; 32],
+{
+  let mut leaves: Vec<[u8; 32]> = payers
+    .iter()
+    .map(|p| allowed_payer_leaf(p, keccak))
+    .collect();
+  leaves.sort_unstable();
+  leaves.dedup();
+  leaves
+}
+
+/// The parent level of a level of the tree.
+fn next_level<H>(level: &[[u8; 32]], keccak: &H) -> Vec<[u8; 32]>
+where
+  H: Fn(&[&[u8]]) -> [u8; 32],
+{
+  level
+    .chunks(2)
+    .map(|pair| match pair {
+      [a, b] => hash_pair(a, b, keccak),
+      [a] => *a,
+      _ => unreachable!(),
+    })
+    .collect()
+}
+
+/// The Merkle root of the given payers. [`OPEN_TO_ALL_PAYERS`] if there are
+/// none.
+pub fn allowed_payers_root<H>(payers: &[[u8; 32]], keccak: &H) -> [u8; 32]
+where
+  H: Fn(&[&[u8]]) -> [u8; 32],
+{
+  let mut level = sorted_leaves(payers, keccak);
+  if level.is_empty() {
+    return OPEN_TO_ALL_PAYERS;
+  }
+  while level.len() > 1 {
+    level = next_level(&level, keccak);
+  }
+  level[0]
+}
+
+/// The proof that the payer is one of the given payers, or None if they
+/// aren't.
+pub fn allowed_payer_proof<H>(
+  payers: &[[u8; 32]],
+  payer: &[u8; 32],
+  keccak: &H,
+) -> Option<Vec<[u8; 32]>>
+where
+  H: Fn(&[&[u8]]) -> [u8; 32],
+{
+  let mut level = sorted_leaves(payers, keccak);
+  let leaf = allowed_payer_leaf(payer, keccak);
+  let mut index = level.binary_search(&leaf).ok()?;
+  let mut proof = Vec::new();
+  while level.len() > 1 {
+    if let Some(sibling) = level.get(index ^ 1) {
+      proof.push(*sibling);
+    }
+    level = next_level(&level, keccak);
+    index /= 2;
+  }
+  Some(proof)
+}
+
+/// Whether the payer can pay a payable with the given allowed payers root.
+/// Anyone can pay if the root is [`OPEN_TO_ALL_PAYERS`].
+pub fn is_allowed_payer<H>(
+  root: &[u8; 32],
+  payer: &[u8; 32],
+  proof: &[[u8; 32]],
+  keccak: &H,
+) -> bool
+where
+  H: Fn(&[&[u8]]) -> [u8; 32],
+{
+  if *root == OPEN_TO_ALL_PAYERS {
+    return true;
+  }
+  let computed = proof
+    .iter()
+    .fold(allowed_payer_leaf(payer, keccak), |node, sibling| {
+      hash_pair(&node, sibling, keccak)
+    });
+  computed == *root
+}
@@ -0,0 +1,41 @@
+use core::fmt;
+
+/// Why a payload couldn't be encoded or decoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PayloadError {
+  /// The input ended before the payload was complete.
+  UnexpectedEnd,
+  /// There were bytes left after the payload was complete.
+  TrailingBytes,
+  /// The payload's version is not one that is supported.
+  UnsupportedVersion(u8),
+  /// The PayablePayload's action type is not one of the known ones.
+  InvalidActionType(u8),
+  /// A boolean field was neither 0 nor 1.
+  InvalidBool(u8),
+  /// More allowed tokens and amounts than fit in the length prefix.
+  TooManyTokensAndAmounts,
+  /// A payment intent's reference is longer than the maximum.
+  ReferenceTooLong,
+  /// The underlying reader failed for a reason other than running out of
+  /// bytes.
+  Io,
+}
+
+impl fmt::Display for PayloadError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::UnexpectedEnd => write!(f, "UnexpectedEnd"),
+      Self::TrailingBytes => write!(f, "TrailingBytes"),
+      Self::UnsupportedVersion(v) => write!(f, "UnsupportedVersion: {v}"),
+      Self::InvalidActionType(t) => write!(f, "InvalidActionType: {t}"),
+      Self::InvalidBool(b) => write!(f, "InvalidBool: {b}"),
+      Self::TooManyTokensAndAmounts => write!(f, "TooManyTokensAndAmounts"),
+      Self::ReferenceTooLong => write!(f, "ReferenceTooLong"),
+      Self::Io => write!(f, "Io"),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PayloadError {}
@@ -0,0 +1,29 @@
+//! Cross-chain payload codec shared by Chainbills' Solana and CosmWasm
+//! programs.
+//!
+//! Payloads are encoded exactly like the EVM contracts' `abi.encodePacked`
+//! layout (see `evm/src/CbPayloadMessages.sol`): fixed-width fields, integers
+//! in big-endian, and no padding. Golden vectors in `vectors/` pin the bytes
+//! that every chain must produce and accept.
+#![no_std]
+
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+mod allowed_payers;
+mod error;
+mod payable_payload;
+mod payment_intent;
+mod payment_payload;
+mod source;
+
+pub use allowed_payers::*;
+pub use error::*;
+pub use payable_payload::*;
+pub use payment_intent::*;
+pub use payment_payload::*;
+pub use source::*;
+
+/// The only payload version that is currently encoded and accepted.
+pub const PAYLOAD_VERSION: u8 = 1;
@@ -0,0 +1,175 @@
+use alloc::vec::Vec;
+
+use crate::{ensure_consumed, read_version, PayloadError, Source};
+
+/// A combination of a token address and its amount from another chain.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TokenAndAmountForeign {
+  /// The Wormhole-normalized address of the associated token.
+  pub token: [u8; 32],
+
+  /// The amount of the token.
+  pub amount: u64,
+}
+
+impl TokenAndAmountForeign {
+  pub const SPACE: usize = 32 + 8;
+}
+
+/// Broadcasted when a payable is created or updated.
+///
+/// Wire format (version 1):
+///   version(1) | action_type(1) | payable_id(32) | nonce(8)
+///   then action-specific fields:
+///     action_type 1 or 4: ataa_len(1) | [token(32) | amount(8)] * n
+///     action_type 2 or 3: is_closed(1)
+///     action_type 5: allowed_payers_root(32)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PayablePayload {
+  /// Version of the payload.
+  pub version: u8,
+
+  /// Type of the payable activity.
+  ///
+  /// 1 - CreatedPayable
+  /// 2 - ClosedPayable
+  /// 3 - ReopenedPayable
+  /// 4 - UpdatedPayableAllowedTokensAndAmounts
+  /// 5 - UpdatedPayableAllowedPayers
+  pub action_type: u8,
+
+  /// The Payable's ID.
+  pub payable_id: [u8; 32],
+
+  /// Monotonically increasing counter for this payable on the source chain.
+  /// Receiving chains reject updates with nonce <= last seen nonce.
+  pub nonce: u64,
+
+  /// Whether the payable is closed or not. Only encoded for action types 2
+  /// and 3.
+  pub is_closed: bool,
+
+  /// The allowed tokens and their amounts. Only encoded for action types 1
+  /// and 4.
+  pub allowed_tokens_and_amounts: Vec<TokenAndAmountForeign>,
+
+  /// The Merkle root of the payers allowed to pay the payable. All zeros
+  /// means that anyone can pay. Only encoded for action type 5. See
+  /// [`crate::allowed_payers_root`].
+  pub allowed_payers_root: [u8; 32],
+}
+
+impl PayablePayload {
+  pub const ACTION_CREATED: u8 = 1;
+  pub const ACTION_CLOSED: u8 = 2;
+  pub const ACTION_REOPENED: u8 = 3;
+  pub const ACTION_UPDATED_ATAA: u8 = 4;
+  pub const ACTION_UPDATED_ALLOWED_PAYERS: u8 = 5;
+
+  /// Length of the fields common to all action types.
+  const HEADER_LEN: usize = 1 + 1 + 32 + 8;
+
+  fn ensure_valid_action_type(action_type: u8) -> Result<(), PayloadError> {
+    match action_type {
+      Self::ACTION_CREATED..=Self::ACTION_UPDATED_ALLOWED_PAYERS => Ok(()),
+      t => Err(PayloadError::InvalidActionType(t)),
+    }
+  }
+
+  /// The exact number of bytes that [`Self::encode`] produces.
+  pub fn encoded_len(&self) -> Result<usize, PayloadError> {
+    Self::ensure_valid_action_type(self.action_type)?;
+    Ok(match self.action_type {
+      Self::ACTION_CREATED | Self::ACTION_UPDATED_ATAA => {
+        Self::HEADER_LEN
+          + 1
+          + self.allowed_tokens_and_amounts.len() * TokenAndAmountForeign::SPACE
+      }
+      Self::ACTION_UPDATED_ALLOWED_PAYERS => Self::HEADER_LEN + 32,
+      _ => Self::HEADER_LEN + 1,
+    })
+  }
+
+  /// Appends the encoded payload to `out`.
+  pub fn encode_into(&self, out: &mut Vec<u8>) -> Result<(), PayloadError> {
+    if self.version != crate::PAYLOAD_VERSION {
+      return Err(PayloadError::UnsupportedVersion(self.version));
+    }
+    Self::ensure_valid_action_type(self.action_type)?;
+
+    out.push(self.version);
+    out.push(self.action_type);
+    out.extend_from_slice(&self.payable_id);
+    out.extend_from_slice(&self.nonce.to_be_bytes());
+    match self.action_type {
+      Self::ACTION_CREATED | Self::ACTION_UPDATED_ATAA => {
+        let ataa_len = u8::try_from(self.allowed_tokens_and_amounts.len())
+          .map_err(|_| PayloadError::TooManyTokensAndAmounts)?;
+        out.push(ataa_len);
+        for ataa in &self.allowed_tokens_and_amounts {
+          out.extend_from_slice(&ataa.token);
+          out.extend_from_slice(&ataa.amount.to_be_bytes());
+        }
+      }
+      Self::ACTION_UPDATED_ALLOWED_PAYERS => {
+        out.extend_from_slice(&self.allowed_payers_root);
+      }
+      _ => out.push(self.is_closed as u8),
+    }
+    Ok(())
+  }
+
+  pub fn encode(&self) -> Result<Vec<u8>, PayloadError> {
+    let mut out = Vec::with_capacity(self.encoded_len()?);
+    self.encode_into(&mut out)?;
+    Ok(out)
+  }
+
+  /// Decodes one payload from `source`, reading no more bytes than it spans.
+  pub fn decode_from<S: Source>(source: &mut S) -> Result<Self, PayloadError> {
+    let version = read_version(source)?;
+    let action_type = source.read_u8()?;
+    let payable_id = source.read_bytes32()?;
+    let nonce = source.read_u64()?;
+
+    Self::ensure_valid_action_type(action_type)?;
+
+    let mut is_closed = false;
+    let mut allowed_tokens_and_amounts = Vec::new();
+    let mut allowed_payers_root = [0u8; 32];
+    match action_type {
+      Self::ACTION_CREATED | Self::ACTION_UPDATED_ATAA => {
+        let ataa_len = source.read_u8()?;
+        allowed_tokens_and_amounts.reserve_exact(ataa_len as usize);
+        for _ in 0..ataa_len {
+          allowed_tokens_and_amounts.push(TokenAndAmountForeign {
+            token: source.read_bytes32()?,
+            amount: source.read_u64()?,
+          });
+        }
+      }
+      Self::ACTION_UPDATED_ALLOWED_PAYERS => {
+        allowed_payers_root = source.read_bytes32()?;
+      }
+      _ => is_closed = source.read_bool()?,
+    }
+
+    Ok(Self {
+      version,
+      action_type,
+      payable_id,
+      nonce,
+      is_closed,
+      allowed_tokens_and_amounts,
+      allowed_payers_root,
+    })
+  }
+
+  /// Decodes a payload that must span the whole of `bytes`.
+  pub fn decode(bytes: &[u8]) -> Result<Self, PayloadError> {
+    let mut rest = bytes;
+    let decoded = Self::decode_from(&mut rest)?;
+    ensure_consumed(rest)?;
+    Ok(decoded)
+  }
+}
@@ -0,0 +1,66 @@
+use alloc::vec::Vec;
+
+use crate::PayloadError;
+
+/// A host's off-chain authorization of one payment into their payable. It
+/// lets payers pay the exact amount in the token, whether or not the
+/// payable's allowed tokens and amounts include them.
+///
+/// Hosts sign [`Self::signing_bytes`]. Chains verify the signature against
+/// the payable's host and only accept each nonce once per payable.
+///
+/// Signed bytes:
+///   "chainbills_payment_intent"(25) | payable_chain_id(32) | payable_id(32)
+///   | token(32) | amount(16) | expiry(8) | nonce(8) | reference(..=64)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PaymentIntent {
+  /// CAIP-2 cbChainId of where the Payable was created.
+  pub payable_chain_id: [u8; 32],
+
+  /// The Payable's ID.
+  pub payable_id: [u8; 32],
+
+  /// The Wormhole-normalized address of the token to pay in.
+  pub token: [u8; 32],
+
+  /// The exact amount to pay.
+  pub amount: u128,
+
+  /// The Unix timestamp (in seconds) after which the intent can't be paid.
+  pub expiry: u64,
+
+  /// Distinguishes the payable's intents. Each can only be paid once.
+  pub nonce: u64,
+
+  /// The host's reference for the payment (an invoice or order number, for
+  /// example). Recorded on the payment.
+  pub reference: Vec<u8>,
+}
+
+impl PaymentIntent {
+  /// Separates intent signatures from any other message that hosts sign.
+  pub const DOMAIN: &'static [u8] = b"chainbills_payment_intent";
+
+  /// The maximum length of a reference in bytes.
+  pub const MAX_REFERENCE_LEN: usize = 64;
+
+  /// The bytes that the host signs. The reference comes last, so it isn't
+  /// length-prefixed.
+  pub fn signing_bytes(&self) -> Result<Vec<u8>, PayloadError> {
+    if self.reference.len() > Self::MAX_REFERENCE_LEN {
+      return Err(PayloadError::ReferenceTooLong);
+    }
+    let mut out = Vec::with_capacity(
+      Self::DOMAIN.len() + 32 * 3 + 16 + 8 + 8 + self.reference.len(),
+    );
+    out.extend_from_slice(Self::DOMAIN);
+    out.extend_from_slice(&self.payable_chain_id);
+    out.extend_from_slice(&self.payable_id);
+    out.extend_from_slice(&self.token);
+    out.extend_from_slice(&self.amount.to_be_bytes());
+    out.extend_from_slice(&self.expiry.to_be_bytes());
+    out.extend_from_slice(&self.nonce.to_be_bytes());
+    out.extend_from_slice(&self.reference);
+    Ok(out)
+  }
+}
@@ -0,0 +1,94 @@
+use alloc::vec::Vec;
+
+use crate::{ensure_consumed, read_version, PayloadError, Source};
+
+/// Necessary info to record a payable's payment if the involved blockchain
+/// networks are different. That is the when a user is on a different chain
+/// from the payable.
+///
+/// Wire format (version 1):
+///   version(1) | payable_id(32) | payable_chain_token(32)
+///   | payable_chain_id(32) | payer(32) | payer_chain_token(32)
+///   | payer_chain_id(32) | amount(8) | circle_nonce(8)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PaymentPayload {
+  /// Version of the payload.
+  pub version: u8,
+
+  /// The Payable's ID.
+  pub payable_id: [u8; 32],
+
+  /// The Wormhole-normalized address of the involved token on the payable
+  /// (destination) chain.
+  pub payable_chain_token: [u8; 32],
+
+  /// CAIP-2 cbChainId of where the Payable was created.
+  pub payable_chain_id: [u8; 32],
+
+  /// Who made the payment.
+  pub payer: [u8; 32],
+
+  /// The Wormhole-normalized address of the involved token on the payer
+  /// (source) chain.
+  pub payer_chain_token: [u8; 32],
+
+  /// CAIP-2 cbChainId of where the User made the payment.
+  pub payer_chain_id: [u8; 32],
+
+  /// The amount paid on for the transaction.
+  pub amount: u64,
+
+  /// Circle Nonce of the payment.
+  pub circle_nonce: u64,
+}
+
+impl PaymentPayload {
+  /// The exact number of bytes that [`Self::encode`] produces.
+  pub const ENCODED_LEN: usize = 1 + 32 * 6 + 8 + 8;
+
+  /// Appends the encoded payload to `out`.
+  pub fn encode_into(&self, out: &mut Vec<u8>) -> Result<(), PayloadError> {
+    if self.version != crate::PAYLOAD_VERSION {
+      return Err(PayloadError::UnsupportedVersion(self.version));
+    }
+    out.push(self.version);
+    out.extend_from_slice(&self.payable_id);
+    out.extend_from_slice(&self.payable_chain_token);
+    out.extend_from_slice(&self.payable_chain_id);
+    out.extend_from_slice(&self.payer);
+    out.extend_from_slice(&self.payer_chain_token);
+    out.extend_from_slice(&self.payer_chain_id);
+    out.extend_from_slice(&self.amount.to_be_bytes());
+    out.extend_from_slice(&self.circle_nonce.to_be_bytes());
+    Ok(())
+  }
+
+  pub fn encode(&self) -> Result<Vec<u8>, PayloadError> {
+    let mut out = Vec::with_capacity(Self::ENCODED_LEN);
+    self.encode_into(&mut out)?;
+    Ok(out)
+  }
+
+  /// Decodes one payload from `source`, reading no more bytes than it spans.
+  pub fn decode_from<S: Source>(source: &mut S) -> Result<Self, PayloadError> {
+    Ok(Self {
+      version: read_version(source)?,
+      payable_id: source.read_bytes32()?,
+      payable_chain_token: source.read_bytes32()?,
+      payable_chain_id: source.read_bytes32()?,
+      payer: source.read_bytes32()?,
+      payer_chain_token: source.read_bytes32()?,
+      payer_chain_id: source.read_bytes32()?,
+      amount: source.read_u64()?,
+      circle_nonce: source.read_u64()?,
+    })
+  }
+
+  /// Decodes a payload that must span the whole of `bytes`.
+  pub fn decode(bytes: &[u8]) -> Result<Self, PayloadError> {
+    let mut rest = bytes;
+    let decoded = Self::decode_from(&mut rest)?;
+    ensure_consumed(rest)?;
+    Ok(decoded)
+  }
+}
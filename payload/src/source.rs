@@ -0,0 +1,84 @@
+use crate::PayloadError;
+
+/// Where payload bytes are read from while decoding.
+///
+/// Decoding pulls exactly the bytes each field needs, so payloads can be
+/// decoded from a stream without knowing their length upfront.
+pub trait Source {
+  /// Fills `buf` completely or fails with [`PayloadError::UnexpectedEnd`].
+  fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), PayloadError>;
+
+  fn read_u8(&mut self) -> Result<u8, PayloadError> {
+    let mut buf = [0u8; 1];
+    self.read_exact(&mut buf)?;
+    Ok(buf[0])
+  }
+
+  /// Reads a byte that must be either 0 or 1.
+  fn read_bool(&mut self) -> Result<bool, PayloadError> {
+    match self.read_u8()? {
+      0 => Ok(false),
+      1 => Ok(true),
+      b => Err(PayloadError::InvalidBool(b)),
+    }
+  }
+
+  /// Reads a big-endian u64.
+  fn read_u64(&mut self) -> Result<u64, PayloadError> {
+    let mut buf = [0u8; 8];
+    self.read_exact(&mut buf)?;
+    Ok(u64::from_be_bytes(buf))
+  }
+
+  fn read_bytes32(&mut self) -> Result<[u8; 32], PayloadError> {
+    let mut buf = [0u8; 32];
+    self.read_exact(&mut buf)?;
+    Ok(buf)
+  }
+}
+
+/// Reading from a slice advances it past the consumed bytes.
+impl Source for &[u8] {
+  fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), PayloadError> {
+    if self.len() < buf.len() {
+      return Err(PayloadError::UnexpectedEnd);
+    }
+    let (head, tail) = self.split_at(buf.len());
+    buf.copy_from_slice(head);
+    *self = tail;
+    Ok(())
+  }
+}
+
+/// Adapts any [`std::io::Read`] into a [`Source`].
+#[cfg(feature = "std")]
+pub struct IoSource<R>(pub R);
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read> Source for IoSource<R> {
+  fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), PayloadError> {
+    self.0.read_exact(buf).map_err(|e| match e.kind() {
+      std::io::ErrorKind::UnexpectedEof => PayloadError::UnexpectedEnd,
+      _ => PayloadError::Io,
+    })
+  }
+}
+
+/// Ensures that nothing is left after a payload was decoded from a slice.
+pub(crate) fn ensure_consumed(rest: &[u8]) -> Result<(), PayloadError> {
+  if !rest.is_empty() {
+    return Err(PayloadError::TrailingBytes);
+  }
+  Ok(())
+}
+
+/// Reads the version byte and ensures that it is supported.
+pub(crate) fn read_version<S: Source>(
+  source: &mut S,
+) -> Result<u8, PayloadError> {
+  let version = source.read_u8()?;
+  if version != crate::PAYLOAD_VERSION {
+    return Err(PayloadError::UnsupportedVersion(version));
+  }
+  Ok(version)
+}
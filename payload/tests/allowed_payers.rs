@@ -0,0 +1,76 @@
+use chainbills_payload::*;
+use sha3::{Digest, Keccak256};
+
+fn keccak(data: &[&[u8]]) -> [u8; 32] {
+  let mut hasher = Keccak256::new();
+  for d in data {
+    hasher.update(d);
+  }
+  hasher.finalize().into()
+}
+
+fn payers(n: u8) -> Vec<[u8; 32]> {
+  (1..=n).map(|i| [i; 32]).collect()
+}
+
+#[test]
+fn test_no_payers_is_open_to_all() {
+  assert_eq!(allowed_payers_root(&[], &keccak), OPEN_TO_ALL_PAYERS);
+  assert!(is_allowed_payer(
+    &OPEN_TO_ALL_PAYERS,
+    &[7; 32],
+    &[],
+    &keccak
+  ));
+}
+
+#[test]
+fn test_single_payer_root_is_their_leaf() {
+  let root = allowed_payers_root(&[[1; 32]], &keccak);
+  assert_eq!(root, keccak(&[&[1; 32]]));
+  assert_eq!(
+    allowed_payer_proof(&[[1; 32]], &[1; 32], &keccak),
+    Some(vec![])
+  );
+  assert!(is_allowed_payer(&root, &[1; 32], &[], &keccak));
+  assert!(!is_allowed_payer(&root, &[2; 32], &[], &keccak));
+}
+
+#[test]
+fn test_every_listed_payer_has_a_valid_proof() {
+  for n in 1..=9 {
+    let payers = payers(n);
+    let root = allowed_payers_root(&payers, &keccak);
+    for payer in payers.iter() {
+      let proof = allowed_payer_proof(&payers, payer, &keccak).unwrap();
+      assert!(is_allowed_payer(&root, payer, &proof, &keccak));
+      assert!(!is_allowed_payer(&root, &[0xff; 32], &proof, &keccak));
+    }
+    assert_eq!(allowed_payer_proof(&payers, &[0xff; 32], &keccak), None);
+  }
+}
+
+#[test]
+fn test_root_ignores_order_and_duplicates() {
+  let mut shuffled = payers(5);
+  shuffled.reverse();
+  shuffled.push([3; 32]);
+  assert_eq!(
+    allowed_payers_root(&shuffled, &keccak),
+    allowed_payers_root(&payers(5), &keccak)
+  );
+}
+
+#[test]
+fn test_root_matches_pinned_value() {
+  // Every chain must compute this root for payers 0x01 * 32, 0x02 * 32, and
+  // 0x03 * 32.
+  let root = allowed_payers_root(&payers(3), &keccak);
+  let expected =
+    "0d5cd68744f13d11a230dbebb20070573ad8c65aaed17dba103c9f17bf0031f6";
+  let expected: Vec<u8> = (0..64)
+    .step_by(2)
+    .map(|i| u8::from_str_radix(&expected[i..i + 2], 16).unwrap())
+    .collect();
+  assert_eq!(root.as_slice(), expected.as_slice());
+}
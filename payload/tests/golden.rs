@@ -0,0 +1,272 @@
+use chainbills_payload::*;
+
+/// Decodes a golden vector file's hex content.
+fn golden(hex: &str) -> Vec<u8> {
+  let hex = hex.trim();
+  (0..hex.len())
+    .step_by(2)
+    .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+    .collect()
+}
+
+fn payable_payload(
+  action_type: u8,
+  payable_id: [u8; 32],
+  nonce: u64,
+  is_closed: bool,
+  allowed_tokens_and_amounts: Vec<TokenAndAmountForeign>,
+) -> PayablePayload {
+  PayablePayload {
+    version: PAYLOAD_VERSION,
+    action_type,
+    payable_id,
+    nonce,
+    is_closed,
+    allowed_tokens_and_amounts,
+    allowed_payers_root: [0; 32],
+  }
+}
+
+fn golden_payable_payloads() -> Vec<(Vec<u8>, PayablePayload)> {
+  vec![
+    (
+      golden(include_str!("../vectors/payable_created.hex")),
+      payable_payload(
+        PayablePayload::ACTION_CREATED,
+        [0x11; 32],
+        1,
+        false,
+        vec![
+          TokenAndAmountForeign {
+            token: [0xaa; 32],
+            amount: 0x0102030405060708,
+          },
+          TokenAndAmountForeign {
+            token: [0xbb; 32],
+            amount: 1_000_000,
+          },
+        ],
+      ),
+    ),
+    (
+      golden(include_str!("../vectors/payable_closed.hex")),
+      payable_payload(
+        PayablePayload::ACTION_CLOSED,
+        [0x22; 32],
+        2,
+        true,
+        vec![],
+      ),
+    ),
+    (
+      golden(include_str!("../vectors/payable_reopened.hex")),
+      payable_payload(
+        PayablePayload::ACTION_REOPENED,
+        [0x22; 32],
+        3,
+        false,
+        vec![],
+      ),
+    ),
+    (
+      golden(include_str!("../vectors/payable_updated_ataa.hex")),
+      payable_payload(
+        PayablePayload::ACTION_UPDATED_ATAA,
+        [0x22; 32],
+        4,
+        false,
+        vec![],
+      ),
+    ),
+    (
+      golden(include_str!(
+        "../vectors/payable_updated_allowed_payers.hex"
+      )),
+      PayablePayload {
+        allowed_payers_root: [0x99; 32],
+        ..payable_payload(
+          PayablePayload::ACTION_UPDATED_ALLOWED_PAYERS,
+          [0x22; 32],
+          5,
+          false,
+          vec![],
+        )
+      },
+    ),
+  ]
+}
+
+fn golden_payment_payload() -> (Vec<u8>, PaymentPayload) {
+  (
+    golden(include_str!("../vectors/payment.hex")),
+    PaymentPayload {
+      version: PAYLOAD_VERSION,
+      payable_id: [0x33; 32],
+      payable_chain_token: [0x44; 32],
+      payable_chain_id: [0x55; 32],
+      payer: [0x66; 32],
+      payer_chain_token: [0x77; 32],
+      payer_chain_id: [0x88; 32],
+      amount: 5_000_000,
+      circle_nonce: 999,
+    },
+  )
+}
+
+#[test]
+fn test_payable_payloads_match_golden_vectors() {
+  for (bytes, payload) in golden_payable_payloads() {
+    assert_eq!(payload.encode().unwrap(), bytes);
+    assert_eq!(payload.encoded_len().unwrap(), bytes.len());
+    assert_eq!(PayablePayload::decode(&bytes).unwrap(), payload);
+  }
+}
+
+#[test]
+fn test_payment_payload_matches_golden_vector() {
+  let (bytes, payload) = golden_payment_payload();
+  assert_eq!(payload.encode().unwrap(), bytes);
+  assert_eq!(bytes.len(), PaymentPayload::ENCODED_LEN);
+  assert_eq!(PaymentPayload::decode(&bytes).unwrap(), payload);
+}
+
+#[test]
+fn test_integers_are_big_endian() {
+  let (bytes, _) = golden_payable_payloads().remove(0);
+  // version(1) | action_type(1) | payable_id(32) | nonce(8) | ataa_len(1)
+  // | token(32) | amount(8) ...
+  assert_eq!(&bytes[34..42], &[0, 0, 0, 0, 0, 0, 0, 1]);
+  assert_eq!(&bytes[75..83], &[1, 2, 3, 4, 5, 6, 7, 8]);
+}
+
+#[test]
+fn test_decode_rejects_trailing_bytes() {
+  for (mut bytes, _) in golden_payable_payloads() {
+    bytes.push(0xff);
+    assert_eq!(
+      PayablePayload::decode(&bytes),
+      Err(PayloadError::TrailingBytes)
+    );
+  }
+
+  let (mut bytes, _) = golden_payment_payload();
+  bytes.push(0xff);
+  assert_eq!(
+    PaymentPayload::decode(&bytes),
+    Err(PayloadError::TrailingBytes)
+  );
+}
+
+#[test]
+fn test_decode_rejects_truncated_input() {
+  for (bytes, _) in golden_payable_payloads() {
+    for len in 0..bytes.len() {
+      assert_eq!(
+        PayablePayload::decode(&bytes[..len]),
+        Err(PayloadError::UnexpectedEnd)
+      );
+    }
+  }
+
+  let (bytes, _) = golden_payment_payload();
+  for len in 0..bytes.len() {
+    assert_eq!(
+      PaymentPayload::decode(&bytes[..len]),
+      Err(PayloadError::UnexpectedEnd)
+    );
+  }
+}
+
+#[test]
+fn test_decode_rejects_unsupported_version() {
+  let (mut bytes, _) = golden_payable_payloads().remove(1);
+  bytes[0] = 2;
+  assert_eq!(
+    PayablePayload::decode(&bytes),
+    Err(PayloadError::UnsupportedVersion(2))
+  );
+
+  let (mut bytes, _) = golden_payment_payload();
+  bytes[0] = 0;
+  assert_eq!(
+    PaymentPayload::decode(&bytes),
+    Err(PayloadError::UnsupportedVersion(0))
+  );
+}
+
+#[test]
+fn test_decode_rejects_invalid_action_type_and_bool() {
+  let (mut bytes, _) = golden_payable_payloads().remove(1);
+  bytes[1] = 6;
+  assert_eq!(
+    PayablePayload::decode(&bytes),
+    Err(PayloadError::InvalidActionType(6))
+  );
+
+  let (mut bytes, _) = golden_payable_payloads().remove(1);
+  *bytes.last_mut().unwrap() = 2;
+  assert_eq!(
+    PayablePayload::decode(&bytes),
+    Err(PayloadError::InvalidBool(2))
+  );
+}
+
+#[test]
+fn test_encode_rejects_invalid_payloads() {
+  let mut payload = payable_payload(0, [0; 32], 1, false, vec![]);
+  assert_eq!(payload.encode(), Err(PayloadError::InvalidActionType(0)));
+
+  payload.action_type = PayablePayload::ACTION_CREATED;
+  payload.allowed_tokens_and_amounts =
+    vec![TokenAndAmountForeign::default(); 256];
+  assert_eq!(payload.encode(), Err(PayloadError::TooManyTokensAndAmounts));
+
+  payload.version = 2;
+  assert_eq!(payload.encode(), Err(PayloadError::UnsupportedVersion(2)));
+}
+
+#[test]
+fn test_decode_from_streams_consecutive_payloads() {
+  let mut stream = Vec::new();
+  for (bytes, _) in golden_payable_payloads() {
+    stream.extend_from_slice(&bytes);
+  }
+
+  let mut source = &stream[..];
+  for (_, payload) in golden_payable_payloads() {
+    assert_eq!(PayablePayload::decode_from(&mut source).unwrap(), payload);
+  }
+  assert!(source.is_empty());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_decode_from_io_reader() {
+  let (bytes, payload) = golden_payment_payload();
+  let mut source = IoSource(std::io::Cursor::new(&bytes));
+  assert_eq!(PaymentPayload::decode_from(&mut source).unwrap(), payload);
+  assert_eq!(
+    PaymentPayload::decode_from(&mut source),
+    Err(PayloadError::UnexpectedEnd)
+  );
+}
+
+#[test]
+fn test_payment_intent_matches_golden_vector() {
+  let mut intent = PaymentIntent {
+    payable_chain_id: [0x55; 32],
+    payable_id: [0x33; 32],
+    token: [0x44; 32],
+    amount: 5_000_000,
+    expiry: 1_700_000_000,
+    nonce: 7,
+    reference: b"INV-0001".to_vec(),
+  };
+  assert_eq!(
+    intent.signing_bytes().unwrap(),
+    golden(include_str!("../vectors/payment_intent.hex"))
+  );
+
+  intent.reference = vec![b'x'; PaymentIntent::MAX_REFERENCE_LEN + 1];
+  assert_eq!(intent.signing_bytes(), Err(PayloadError::ReferenceTooLong));
+}
@@ -0,0 +1,165 @@
+use crate::{
+  error::{ClientError, Result},
+  pda,
+  source::AccountSource,
+};
+use anchor_lang::{prelude::*, AccountDeserialize, Discriminator};
+use chainbills::state::*;
+use solana_sdk::account::Account;
+
+/// Reads the program's accounts from a source and builds its instructions
+/// from them.
+pub struct Client<S> {
+  source: S,
+  paged: bool,
+}
+
+impl<S: AccountSource> Client<S> {
+  pub fn new(source: S) -> Self {
+    Self {
+      source,
+      paged: false,
+    }
+  }
+
+  /// Has the payments that this client builds record their index entries
+  /// in shared ledger pages, rather than in accounts of their own, so that
+  /// payers pay less rent.
+  pub fn with_ledger_pages(mut self, paged: bool) -> Self {
+    self.paged = paged;
+    self
+  }
+
+  pub fn source(&self) -> &S {
+    &self.source
+  }
+
+  /// Whether the payments that this client builds are paged.
+  pub fn paged(&self) -> bool {
+    self.paged
+  }
+
+  /// The raw account at the address, if any.
+  pub fn get_account(&self, address: Pubkey) -> Result<Option<Account>> {
+    let mut accounts = self.source.get_accounts(&[address])?;
+    Ok(accounts.pop().flatten())
+  }
+
+  pub fn exists(&self, address: Pubkey) -> Result<bool> {
+    Ok(self.get_account(address)?.is_some())
+  }
+
+  /// Deserializes the account at the address, which must exist.
+  pub fn account<T: AccountDeserialize>(&self, address: Pubkey) -> Result<T> {
+    self
+      .maybe_account(address)?
+      .ok_or(ClientError::AccountNotFound(address))
+  }
+
+  /// Deserializes the account at the address, if it exists.
+  pub fn maybe_account<T: AccountDeserialize>(
+    &self,
+    address: Pubkey,
+  ) -> Result<Option<T>> {
+    let mut accounts = self.accounts(&[address])?;
+    Ok(accounts.pop().flatten())
+  }
+
+  /// Deserializes the accounts at the addresses in one read, with `None`
+  /// for those that don't exist, such as archived ones.
+  pub fn accounts<T: AccountDeserialize>(
+    &self,
+    addresses: &[Pubkey],
+  ) -> Result<Vec<Option<T>>> {
+    let accounts = self.source.get_accounts(addresses)?;
+    addresses
+      .iter()
+      .zip(accounts)
+      .map(|(address, account)| {
+        account.map(|a| decode(*address, &a)).transpose()
+      })
+      .collect()
+  }
+
+  /// Deserializes all the program's accounts of the type, in no particular
+  /// order. Reads the whole program, so it is for what isn't indexed.
+  pub fn program_accounts<T: AccountDeserialize + Discriminator>(
+    &self,
+  ) -> Result<Vec<(Pubkey, T)>> {
+    self
+      .source
+      .get_program_accounts(&chainbills::ID, &T::DISCRIMINATOR)?
+      .into_iter()
+      .map(|(address, account)| Ok((address, decode(address, &account)?)))
+      .collect()
+  }
+
+  /// The Config, which is zero-copy and so isn't read like other accounts.
+  pub fn config(&self) -> Result<Config> {
+    let address = pda::config();
+    let account = self
+      .get_account(address)?
+      .ok_or(ClientError::AccountNotFound(address))?;
+    let data = account
+      .data
+      .strip_prefix(&Config::DISCRIMINATOR)
+      .and_then(|data| data.get(..std::mem::size_of::<Config>()))
+      .ok_or(ClientError::InvalidAccount(address))?;
+    Ok(bytemuck::pod_read_unaligned(data))
+  }
+
+  pub fn chain_stats(&self) -> Result<ChainStats> {
+    self.account(pda::chain_stats())
+  }
+
+  pub fn chain_shard(&self, index: u8) -> Result<ChainStatsShard> {
+    self.account(pda::chain_shard(index))
+  }
+
+  pub fn user(&self, wallet: &Pubkey) -> Result<User> {
+    self.account(pda::user(wallet))
+  }
+
+  pub fn payable(&self, payable: &Pubkey) -> Result<Payable> {
+    self.account(*payable)
+  }
+
+  /// The TokenDetails of a mint, or of native SOL if the token is this
+  /// program's ID.
+  pub fn token_details(&self, token: &Pubkey) -> Result<TokenDetails> {
+    self.account(pda::token_details(token))
+  }
+
+  /// The payable's escrow settings, if it escrows its payments.
+  pub fn active_escrow(
+    &self,
+    payable: &Pubkey,
+  ) -> Result<Option<PayableEscrow>> {
+    let escrow: Option<PayableEscrow> =
+      self.maybe_account(pda::payable_escrow(payable))?;
+    Ok(escrow.filter(|escrow| escrow.timeout > 0))
+  }
+
+  /// Whether payments into the payable mint receipts to their payers.
+  pub fn receipts_enabled(&self, payable: &Pubkey) -> Result<bool> {
+    let receipts: Option<PayableReceipts> =
+      self.maybe_account(pda::payable_receipts(&payable.to_bytes()))?;
+    Ok(receipts.is_some_and(|receipts| receipts.enabled))
+  }
+
+  /// The token program that owns the mint, SPL Token or Token-2022.
+  pub fn token_program(&self, mint: &Pubkey) -> Result<Pubkey> {
+    let account = self
+      .get_account(*mint)?
+      .ok_or(ClientError::AccountNotFound(*mint))?;
+    Ok(account.owner)
+  }
+}
+
+fn decode<T: AccountDeserialize>(
+  address: Pubkey,
+  account: &Account,
+) -> Result<T> {
+  T::try_deserialize(&mut account.data.as_slice())
+    .map_err(|_| ClientError::InvalidAccount(address))
+}
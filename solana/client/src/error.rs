@@ -0,0 +1,45 @@
+use anchor_lang::prelude::Pubkey;
+use chainbills_payload::PayloadError;
+use std::fmt;
+
+/// Why the client couldn't fetch an account or build an instruction.
+#[derive(Debug)]
+pub enum ClientError {
+  /// There is no account at the address.
+  AccountNotFound(Pubkey),
+  /// The account at the address isn't of the expected type.
+  InvalidAccount(Pubkey),
+  /// The RPC node failed to answer.
+  Rpc(Box<solana_client::client_error::ClientError>),
+  /// A message for the program couldn't be encoded.
+  Payload(PayloadError),
+}
+
+impl fmt::Display for ClientError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::AccountNotFound(address) => {
+        write!(f, "AccountNotFound: {address}")
+      }
+      Self::InvalidAccount(address) => write!(f, "InvalidAccount: {address}"),
+      Self::Rpc(err) => write!(f, "Rpc: {err}"),
+      Self::Payload(err) => write!(f, "Payload: {err}"),
+    }
+  }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<solana_client::client_error::ClientError> for ClientError {
+  fn from(err: solana_client::client_error::ClientError) -> Self {
+    Self::Rpc(Box::new(err))
+  }
+}
+
+impl From<PayloadError> for ClientError {
+  fn from(err: PayloadError) -> Self {
+    Self::Payload(err)
+  }
+}
+
+pub type Result<T> = std::result::Result<T, ClientError>;
@@ -0,0 +1,671 @@
+//! Paginated histories of the chain, of users, and of payables.
+//!
+//! Pages hold the items with counts from `page * count + 1` up to
+//! `(page + 1) * count`, in ascending order, like the pages of the frontend.
+//! Archived items are skipped, so a page may hold fewer items than its size.
+//!
+//! The chain's histories list the items indexed in ChainStats before
+//! sharding, then those of each ChainStatsShard in turn, so the counts of
+//! their pages are positions in that order rather than chain counts.
+//!
+//! Payments don't index their payments and activities in the chain's
+//! histories, so those histories are read from all the program's accounts
+//! of their type, by the chain counts that the shards still enumerate.
+//! Payments derive their activities from their payers' and payables'
+//! counts. The index entries of other activities are found in ledger pages
+//! if they were recorded there, and otherwise in their own accounts.
+
+use crate::{client::Client, error::Result, pda, source::AccountSource};
+use anchor_lang::{prelude::*, AccountDeserialize, Discriminator};
+use chainbills::state::*;
+use std::{collections::HashMap, ops::RangeInclusive};
+
+/// A page of a history: the items that still exist, with their addresses,
+/// and the history's total number of items, including archived ones.
+#[derive(Debug)]
+pub struct Page<T> {
+  pub total: u64,
+  pub items: Vec<(Pubkey, T)>,
+}
+
+/// The 1-based counts on the page of the history.
+fn counts(total: u64, page: u64, count: u64) -> RangeInclusive<u64> {
+  let start = page.saturating_mul(count).saturating_add(1);
+  let end = page.saturating_add(1).saturating_mul(count).min(total);
+  start..=end
+}
+
+/// The chain counts of an entity, in the order that the chain's histories
+/// list them.
+struct ChainCounts(Vec<RangeInclusive<u64>>);
+
+impl ChainCounts {
+  fn total(&self) -> u64 {
+    self.0.iter().map(|r| r.end() + 1 - r.start()).sum()
+  }
+
+  /// The chain count at the 1-based position.
+  fn nth(&self, position: u64) -> u64 {
+    let mut position = position;
+    for range in &self.0 {
+      let len = range.end() + 1 - range.start();
+      if position <= len {
+        return range.start() + position - 1;
+      }
+      position -= len;
+    }
+    unreachable!("position beyond total")
+  }
+}
+
+impl<S: AccountSource> Client<S> {
+  /// The chain counts of the entity whose count in ChainStats before
+  /// sharding is legacy, and whose count in each shard is given. Shards
+  /// that aren't initialized are skipped.
+  fn chain_counts(
+    &self,
+    legacy: u64,
+    count: impl Fn(&ChainStatsShard) -> u64,
+  ) -> Result<ChainCounts> {
+    let addresses: Vec<Pubkey> =
+      (0..ChainStatsShard::COUNT).map(pda::chain_shard).collect();
+    let shards: Vec<Option<ChainStatsShard>> = self.accounts(&addresses)?;
+    let mut ranges = vec![1..=legacy];
+    for shard in shards.into_iter().flatten() {
+      ranges.push(shard.base() + 1..=count(&shard));
+    }
+    Ok(ChainCounts(ranges))
+  }
+
+  /// The accounts at the addresses that still exist.
+  fn existing<T: AccountDeserialize>(
+    &self,
+    addresses: Vec<Pubkey>,
+  ) -> Result<Vec<(Pubkey, T)>> {
+    let accounts = self.accounts(&addresses)?;
+    Ok(
+      addresses
+        .into_iter()
+        .zip(accounts)
+        .filter_map(|(address, account)| Some((address, account?)))
+        .collect(),
+    )
+  }
+
+  /// The page of the history whose items are at the addresses of their
+  /// counts.
+  fn page<T: AccountDeserialize>(
+    &self,
+    total: u64,
+    page: u64,
+    count: u64,
+    address: impl Fn(u64) -> Pubkey,
+  ) -> Result<Page<T>> {
+    let addresses = counts(total, page, count).map(address).collect();
+    Ok(Page {
+      total,
+      items: self.existing(addresses)?,
+    })
+  }
+
+  /// The page of the history whose items are referenced by the index
+  /// accounts at the addresses of their counts.
+  fn indexed_page<I: AccountDeserialize, T: AccountDeserialize>(
+    &self,
+    total: u64,
+    page: u64,
+    count: u64,
+    address: impl Fn(u64) -> Pubkey,
+    reference: impl Fn(&I) -> Pubkey,
+  ) -> Result<Page<T>> {
+    let index: Page<I> = self.page(total, page, count, address)?;
+    let addresses = index.items.iter().map(|(_, i)| reference(i)).collect();
+    Ok(Page {
+      total,
+      items: self.existing(addresses)?,
+    })
+  }
+
+  /// The page of the history whose items are referenced by index entries
+  /// of their counts: in the ledger pages at the page addresses if the
+  /// entries are there, or else in the index accounts at the addresses.
+  /// The pages and the index accounts are each read at once.
+  fn ledgered_page<I, L, T>(
+    &self,
+    total: u64,
+    page: u64,
+    count: u64,
+    (address, reference): (impl Fn(u64) -> Pubkey, impl Fn(&I) -> Pubkey),
+    (page_address, entry): (
+      impl Fn(u64) -> Pubkey,
+      impl Fn(&L, u64) -> Option<Pubkey>,
+    ),
+  ) -> Result<Page<T>>
+  where
+    I: AccountDeserialize,
+    L: AccountDeserialize,
+    T: AccountDeserialize,
+  {
+    let counts: Vec<u64> = counts(total, page, count).collect();
+    let page_addresses: Vec<Pubkey> =
+      counts.iter().map(|n| page_address(*n)).collect();
+    let mut unique = page_addresses.clone();
+    unique.dedup();
+    let ledgers: Vec<Option<L>> = self.accounts(&unique)?;
+    let ledgers: HashMap<Pubkey, L> = unique
+      .into_iter()
+      .zip(ledgers)
+      .filter_map(|(address, ledger)| Some((address, ledger?)))
+      .collect();
+    let own: Vec<Pubkey> = counts.iter().map(|n| address(*n)).collect();
+    let own: Vec<Option<I>> = self.accounts(&own)?;
+    let addresses = counts
+      .iter()
+      .zip(page_addresses)
+      .zip(own)
+      .filter_map(|((n, page_address), own)| {
+        let paged = ledgers.get(&page_address).and_then(|l| entry(l, *n));
+        paged.or_else(|| own.map(|i| reference(&i)))
+      })
+      .collect();
+    Ok(Page {
+      total,
+      items: self.existing(addresses)?,
+    })
+  }
+
+  /// The page of the chain's history at the chain counts whose items are
+  /// found among all the program's accounts of their type.
+  fn scanned_page<T: AccountDeserialize + Discriminator>(
+    &self,
+    counts: &ChainCounts,
+    page: u64,
+    count: u64,
+    chain_count: impl Fn(&T) -> u64,
+  ) -> Result<Page<T>> {
+    let total = counts.total();
+    let mut all: HashMap<u64, (Pubkey, T)> = self
+      .program_accounts::<T>()?
+      .into_iter()
+      .map(|(address, item)| (chain_count(&item), (address, item)))
+      .collect();
+    Ok(Page {
+      total,
+      items: self::counts(total, page, count)
+        .filter_map(|n| all.remove(&counts.nth(n)))
+        .collect(),
+    })
+  }
+
+  /// The page of the activities of the owner (a wallet or a payable):
+  /// those that payments derived from its counts, and the others as their
+  /// index entries reference them, in the order of the owner's counts.
+  fn owner_activities<I: AccountDeserialize>(
+    &self,
+    owner: &Pubkey,
+    (total, page, count): (u64, u64, u64),
+    reference: impl Fn(&I) -> u64,
+    owner_count: impl Fn(&ActivityRecord) -> u64,
+  ) -> Result<Page<ActivityRecord>> {
+    let mut activities: Page<ActivityRecord> = self.ledgered_page(
+      total,
+      page,
+      count,
+      (
+        |n| pda::activity_info(owner, n),
+        |i: &I| pda::activity(reference(i)),
+      ),
+      (
+        |n| pda::activities_page(owner, n),
+        |l: &CountLedgerPage, n| l.get(n).map(pda::activity),
+      ),
+    )?;
+    let payments = counts(total, page, count)
+      .map(|n| pda::payment_activity(owner, n))
+      .collect();
+    activities.items.extend(self.existing(payments)?);
+    activities.items.sort_by_key(|(_, a)| owner_count(a));
+    Ok(activities)
+  }
+
+  /// The users of this chain, keyed by their wallets.
+  pub fn chain_users(&self, page: u64, count: u64) -> Result<Page<User>> {
+    let counts =
+      self.chain_counts(self.chain_stats()?.users_count, |s| s.users_count)?;
+    let total = counts.total();
+    let index: Page<ChainUserAddress> = self.page(total, page, count, |n| {
+      pda::chain_user_address(counts.nth(n))
+    })?;
+    let wallets: Vec<Pubkey> =
+      index.items.iter().map(|(_, i)| i.user_address).collect();
+    let addresses: Vec<Pubkey> = wallets.iter().map(pda::user).collect();
+    let users = self.accounts(&addresses)?;
+    Ok(Page {
+      total,
+      items: wallets
+        .into_iter()
+        .zip(users)
+        .filter_map(|(wallet, user)| Some((wallet, user?)))
+        .collect(),
+    })
+  }
+
+  pub fn chain_payables(&self, page: u64, count: u64) -> Result<Page<Payable>> {
+    let counts = self
+      .chain_counts(self.chain_stats()?.payables_count, |s| s.payables_count)?;
+    self.indexed_page(
+      counts.total(),
+      page,
+      count,
+      |n| pda::chain_payable_id(counts.nth(n)),
+      |i: &ChainPayableId| i.payable_id,
+    )
+  }
+
+  /// The payables of other chains that this chain has recorded.
+  pub fn chain_foreign_payables(
+    &self,
+    page: u64,
+    count: u64,
+  ) -> Result<Page<PayableForeign>> {
+    let total = self.chain_stats()?.foreign_payables_count;
+    self.indexed_page(
+      total,
+      page,
+      count,
+      pda::chain_foreign_payable_id,
+      |i: &ChainForeignPayableId| pda::foreign_payable(&i.payable_id),
+    )
+  }
+
+  pub fn chain_user_payments(
+    &self,
+    page: u64,
+    count: u64,
+  ) -> Result<Page<UserPayment>> {
+    let counts = self
+      .chain_counts(self.chain_stats()?.user_payments_count, |s| {
+        s.user_payments_count
+      })?;
+    self.scanned_page(&counts, page, count, |p: &UserPayment| p.chain_count)
+  }
+
+  pub fn chain_payable_payments(
+    &self,
+    page: u64,
+    count: u64,
+  ) -> Result<Page<PayablePayment>> {
+    let counts = self
+      .chain_counts(self.chain_stats()?.payable_payments_count, |s| {
+        s.payable_payments_count
+      })?;
+    self.scanned_page(&counts, page, count, |p: &PayablePayment| p.chain_count)
+  }
+
+  pub fn chain_withdrawals(
+    &self,
+    page: u64,
+    count: u64,
+  ) -> Result<Page<Withdrawal>> {
+    let counts = self
+      .chain_counts(self.chain_stats()?.withdrawals_count, |s| {
+        s.withdrawals_count
+      })?;
+    self.indexed_page(
+      counts.total(),
+      page,
+      count,
+      |n| pda::chain_withdrawal_id(counts.nth(n)),
+      |i: &ChainWithdrawalId| i.withdrawal_id,
+    )
+  }
+
+  pub fn chain_activities(
+    &self,
+    page: u64,
+    count: u64,
+  ) -> Result<Page<ActivityRecord>> {
+    let counts = self
+      .chain_counts(self.chain_stats()?.activities_count, |s| {
+        s.activities_count
+      })?;
+    self.scanned_page(&counts, page, count, |a: &ActivityRecord| a.chain_count)
+  }
+
+  /// The payables that the wallet created or accepted. Payables it has
+  /// since transferred remain in its history.
+  pub fn user_payables(
+    &self,
+    wallet: &Pubkey,
+    page: u64,
+    count: u64,
+  ) -> Result<Page<Payable>> {
+    let total = self.user(wallet)?.payables_count;
+    let counts: Vec<u64> = counts(total, page, count).collect();
+    let created: Vec<Pubkey> =
+      counts.iter().map(|n| pda::payable(wallet, *n)).collect();
+    let exists = self.source().get_accounts(&created)?;
+    let mut addresses: Vec<Option<Pubkey>> = created
+      .into_iter()
+      .zip(exists)
+      .map(|(address, account)| account.map(|_| address))
+      .collect();
+
+    // Accepted payables weren't derived from the wallet's count, so their
+    // UserPayableInfo takes their place.
+    let missing: Vec<usize> = (0..addresses.len())
+      .filter(|i| addresses[*i].is_none())
+      .collect();
+    let infos: Vec<Pubkey> = missing
+      .iter()
+      .map(|i| pda::user_payable_info(wallet, counts[*i]))
+      .collect();
+    let infos: Vec<Option<UserPayableInfo>> = self.accounts(&infos)?;
+    for (i, info) in missing.into_iter().zip(infos) {
+      addresses[i] = info.map(|info| info.payable_id);
+    }
+    Ok(Page {
+      total,
+      items: self.existing(addresses.into_iter().flatten().collect())?,
+    })
+  }
+
+  pub fn user_payments(
+    &self,
+    wallet: &Pubkey,
+    page: u64,
+    count: u64,
+  ) -> Result<Page<UserPayment>> {
+    let total = self.user(wallet)?.payments_count;
+    self.page(total, page, count, |n| pda::user_payment(wallet, n))
+  }
+
+  pub fn user_withdrawals(
+    &self,
+    wallet: &Pubkey,
+    page: u64,
+    count: u64,
+  ) -> Result<Page<Withdrawal>> {
+    let total = self.user(wallet)?.withdrawals_count;
+    self.page(total, page, count, |n| pda::withdrawal(wallet, n))
+  }
+
+  pub fn user_activities(
+    &self,
+    wallet: &Pubkey,
+    page: u64,
+    count: u64,
+  ) -> Result<Page<ActivityRecord>> {
+    let total = self.user(wallet)?.activities_count;
+    self.owner_activities(
+      wallet,
+      (total, page, count),
+      |i: &UserActivityInfo| i.chain_count,
+      |a| a.user_count,
+    )
+  }
+
+  pub fn payable_payments(
+    &self,
+    payable: &Pubkey,
+    page: u64,
+    count: u64,
+  ) -> Result<Page<PayablePayment>> {
+    let total = self.payable(payable)?.payments_count;
+    self.page(total, page, count, |n| pda::payable_payment(payable, n))
+  }
+
+  /// The withdrawals from the payable. Withdrawals are derived from their
+  /// hosts' counts, so only those of the payable's current host are found:
+  /// withdrawals made before the payable was transferred are skipped.
+  pub fn payable_withdrawals(
+    &self,
+    payable: &Pubkey,
+    page: u64,
+    count: u64,
+  ) -> Result<Page<Withdrawal>> {
+    let payable_data = self.payable(payable)?;
+    let host = payable_data.host;
+    let mut withdrawals: Page<Withdrawal> = self.indexed_page(
+      payable_data.withdrawals_count,
+      page,
+      count,
+      |n| pda::payable_withdrawal_info(payable, n),
+      |i: &PayableWithdrawalInfo| pda::withdrawal(&host, i.host_count),
+    )?;
+    withdrawals.items.retain(|(_, w)| w.payable_id == *payable);
+    Ok(withdrawals)
+  }
+
+  pub fn payable_activities(
+    &self,
+    payable: &Pubkey,
+    page: u64,
+    count: u64,
+  ) -> Result<Page<ActivityRecord>> {
+    let total = self.payable(payable)?.activities_count;
+    self.owner_activities(
+      payable,
+      (total, page, count),
+      |i: &PayableActivityInfo| i.chain_count,
+      |a| a.payable_count,
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use anchor_lang::AccountSerialize;
+  use solana_sdk::account::Account;
+  use std::collections::HashMap;
+
+  fn insert<T: AccountSerialize>(
+    accounts: &mut HashMap<Pubkey, Account>,
+    address: Pubkey,
+    data: &T,
+  ) {
+    let mut bytes = vec![];
+    data.try_serialize(&mut bytes).unwrap();
+    accounts.insert(
+      address,
+      Account {
+        lamports: 1,
+        data: bytes,
+        owner: chainbills::ID,
+        executable: false,
+        rent_epoch: 0,
+      },
+    );
+  }
+
+  fn payable(host: Pubkey, host_count: u64) -> Payable {
+    Payable {
+      version: 1,
+      chain_count: host_count,
+      host,
+      host_count,
+      created_at: 0,
+      payments_count: 0,
+      withdrawals_count: 0,
+      activities_count: 0,
+      is_closed: false,
+      allowed_tokens_and_amounts: vec![],
+      balances: vec![],
+    }
+  }
+
+  fn user(payables_count: u64) -> User {
+    User {
+      version: 1,
+      chain_count: 1,
+      payables_count,
+      payments_count: 0,
+      withdrawals_count: 0,
+      activities_count: 0,
+    }
+  }
+
+  #[test]
+  fn test_counts() {
+    assert_eq!(counts(25, 0, 10), 1..=10);
+    assert_eq!(counts(25, 2, 10), 21..=25);
+    assert!(counts(25, 3, 10).is_empty());
+    assert!(counts(25, 0, 0).is_empty());
+    assert!(counts(0, 0, 10).is_empty());
+  }
+
+  #[test]
+  fn test_chain_counts() {
+    let base = ChainStatsShard::base_of(3);
+    let counts = ChainCounts(vec![
+      1..=2,
+      ChainStatsShard::base_of(0) + 1..=ChainStatsShard::base_of(0),
+      base + 1..=base + 3,
+    ]);
+    assert_eq!(counts.total(), 5);
+    let all: Vec<u64> = (1..=5).map(|n| counts.nth(n)).collect();
+    assert_eq!(all, vec![1, 2, base + 1, base + 2, base + 3]);
+  }
+
+  #[test]
+  fn test_chain_activities_spans_shards() {
+    let mut accounts = HashMap::new();
+    let stats = ChainStats {
+      version: 1,
+      users_count: 0,
+      payables_count: 0,
+      foreign_payables_count: 0,
+      user_payments_count: 0,
+      payable_payments_count: 0,
+      withdrawals_count: 0,
+      activities_count: 1,
+      published_wormhole_messages_count: 0,
+      consumed_wormhole_messages_count: 0,
+    };
+    insert(&mut accounts, pda::chain_stats(), &stats);
+    // Only the 3rd shard is initialized, with two activities.
+    let base = ChainStatsShard::base_of(2);
+    let shard = ChainStatsShard {
+      index: 2,
+      users_count: base,
+      payables_count: base,
+      user_payments_count: base,
+      payable_payments_count: base,
+      withdrawals_count: base,
+      activities_count: base + 2,
+    };
+    insert(&mut accounts, pda::chain_shard(2), &shard);
+    let activity = |chain_count| ActivityRecord {
+      chain_count,
+      user_count: 1,
+      payable_count: 0,
+      timestamp: 0,
+      entity: Pubkey::default(),
+      activity_type: ActivityType::InitializedUser,
+      rent_payer: Pubkey::default(),
+    };
+    for n in [1, base + 1, base + 2] {
+      insert(&mut accounts, pda::activity(n), &activity(n));
+    }
+
+    let client = Client::new(accounts);
+    let page = client.chain_activities(0, 2).unwrap();
+    assert_eq!(page.total, 3);
+    let counts: Vec<u64> =
+      page.items.iter().map(|(_, a)| a.chain_count).collect();
+    assert_eq!(counts, vec![1, base + 1]);
+    let page = client.chain_activities(1, 2).unwrap();
+    assert_eq!(page.items[0].1.chain_count, base + 2);
+  }
+
+  #[test]
+  fn test_user_activities_reads_ledger_pages() {
+    let wallet = Pubkey::new_unique();
+    let mut accounts = HashMap::new();
+    let mut data = user(0);
+    data.activities_count = 5;
+    insert(&mut accounts, pda::user(&wallet), &data);
+    // The 1st activity has its own info, the 2nd and 4th are paged, the 3rd
+    // was archived from its page, and the 5th is a payment's.
+    insert(
+      &mut accounts,
+      pda::activity_info(&wallet, 1),
+      &UserActivityInfo { chain_count: 1 },
+    );
+    insert(
+      &mut accounts,
+      pda::activities_page(&wallet, 2),
+      &CountLedgerPage {
+        entries: vec![0, 5, 0, 9],
+      },
+    );
+    for (user_count, n) in [(1, 1), (2, 5), (3, 7), (4, 9)] {
+      let activity = ActivityRecord {
+        chain_count: n,
+        user_count,
+        payable_count: 0,
+        timestamp: 0,
+        entity: Pubkey::default(),
+        activity_type: ActivityType::UserPaid,
+        rent_payer: Pubkey::default(),
+      };
+      insert(&mut accounts, pda::activity(n), &activity);
+    }
+    let payment = ActivityRecord {
+      chain_count: 11,
+      user_count: 5,
+      payable_count: 0,
+      timestamp: 0,
+      entity: Pubkey::default(),
+      activity_type: ActivityType::UserPaid,
+      rent_payer: Pubkey::default(),
+    };
+    insert(&mut accounts, pda::payment_activity(&wallet, 5), &payment);
+
+    let client = Client::new(accounts);
+    let page = client.user_activities(&wallet, 0, 10).unwrap();
+    assert_eq!(page.total, 5);
+    let counts: Vec<u64> =
+      page.items.iter().map(|(_, a)| a.chain_count).collect();
+    assert_eq!(counts, vec![1, 5, 9, 11]);
+  }
+
+  #[test]
+  fn test_user_payables_skips_archived_and_finds_accepted() {
+    let wallet = Pubkey::new_unique();
+    let other = Pubkey::new_unique();
+    let accepted = pda::payable(&other, 1);
+    let mut accounts = HashMap::new();
+    insert(&mut accounts, pda::user(&wallet), &user(4));
+    insert(&mut accounts, pda::payable(&wallet, 1), &payable(wallet, 1));
+    // The 2nd payable was archived and the 4th was accepted from another.
+    insert(&mut accounts, pda::payable(&wallet, 3), &payable(wallet, 3));
+    insert(
+      &mut accounts,
+      pda::user_payable_info(&wallet, 4),
+      &UserPayableInfo {
+        payable_id: accepted,
+      },
+    );
+    insert(&mut accounts, accepted, &payable(wallet, 1));
+
+    let client = Client::new(accounts);
+    let page = client.user_payables(&wallet, 0, 10).unwrap();
+    assert_eq!(page.total, 4);
+    let addresses: Vec<Pubkey> = page.items.iter().map(|(a, _)| *a).collect();
+    assert_eq!(
+      addresses,
+      vec![pda::payable(&wallet, 1), pda::payable(&wallet, 3), accepted]
+    );
+
+    let page = client.user_payables(&wallet, 1, 2).unwrap();
+    assert_eq!(page.items.len(), 2);
+    assert_eq!(page.items[0].0, pda::payable(&wallet, 3));
+    assert!(client
+      .user_payables(&wallet, 2, 2)
+      .unwrap()
+      .items
+      .is_empty());
+  }
+}
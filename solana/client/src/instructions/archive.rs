@@ -0,0 +1,248 @@
+use super::ix;
+use crate::{
+  client::Client,
+  error::{ClientError, Result},
+  pda,
+  source::AccountSource,
+};
+use anchor_lang::{
+  prelude::*,
+  solana_program::instruction::{AccountMeta, Instruction},
+};
+use chainbills::state::*;
+
+impl<S: AccountSource> Client<S> {
+  /// Archives the closed payable. Signed by its host. Its escrow, allowed
+  /// payers, receipts and pending host accounts are closed too, and so are
+  /// its PayableOperators of the given operator wallets. The rent goes back
+  /// to the host.
+  pub fn archive_payable(
+    &self,
+    host: &Pubkey,
+    payable: &Pubkey,
+    operators: &[Pubkey],
+  ) -> Result<Instruction> {
+    let chain_id = self.config()?.chain_id;
+    let mut instruction = ix(
+      chainbills::accounts::ArchivePayable {
+        payable: *payable,
+        payable_per_chain_payments_counter:
+          pda::payable_per_chain_payments_counter(payable, chain_id),
+        payable_escrow: pda::payable_escrow(payable),
+        allowed_payers: pda::allowed_payers(&payable.to_bytes()),
+        payable_receipts: pda::payable_receipts(&payable.to_bytes()),
+        pending_payable_host: pda::pending_payable_host(payable),
+        config: pda::config(),
+        signer: *host,
+        event_authority: pda::event_authority(),
+        program: chainbills::ID,
+      },
+      chainbills::instruction::ArchivePayable {},
+    );
+    instruction
+      .accounts
+      .extend(operators.iter().map(|operator| {
+        AccountMeta::new(pda::payable_operator(payable, operator), false)
+      }));
+    Ok(instruction)
+  }
+
+  /// Archives the payment with the PayablePayment address. Signed by the
+  /// payable's host. The chain's (if any) and the payable's references to it
+  /// are closed if they have their own accounts, and are otherwise cleared
+  /// from their ledger pages. Its escrow state is closed too if it was escrowed.
+  /// The rent goes back to whoever paid it.
+  pub fn archive_payable_payment(
+    &self,
+    host: &Pubkey,
+    payable_payment: &Pubkey,
+  ) -> Result<Instruction> {
+    let payment: PayablePayment = self.account(*payable_payment)?;
+    let chain_id = self.config()?.chain_id;
+    let (chain_payable_payment_id, chain_payable_payments_page) = self
+      .chain_payment_reference(
+        pda::chain_payable_payment_id(payment.chain_count),
+        pda::chain_payable_payments_page(payment.chain_count),
+        payment.chain_count,
+        payable_payment,
+      )?;
+    let info = pda::payable_per_chain_payment_info(
+      &payment.payable_id,
+      chain_id,
+      payment.local_chain_count,
+    );
+    let info_paged = !self.exists(info)?;
+    let escrow_state = pda::escrow_state(payable_payment);
+    Ok(ix(
+      chainbills::accounts::ArchivePayablePayment {
+        payable: payment.payable_id,
+        payable_payment: *payable_payment,
+        chain_payable_payment_id,
+        chain_payable_payments_page,
+        payable_per_chain_payment_info: (!info_paged).then_some(info),
+        payable_per_chain_payments_page: info_paged.then(|| {
+          pda::payable_per_chain_payments_page(
+            &payment.payable_id,
+            chain_id,
+            payment.local_chain_count,
+          )
+        }),
+        escrow_state: self.exists(escrow_state)?.then_some(escrow_state),
+        config: pda::config(),
+        rent_payer: payment.rent_payer,
+        signer: *host,
+        event_authority: pda::event_authority(),
+        program: chainbills::ID,
+      },
+      chainbills::instruction::ArchivePayablePayment {},
+    ))
+  }
+
+  /// Archives the payment with the UserPayment address. Signed by its payer.
+  /// Its chain-level reference, if any, is closed if it has its own account,
+  /// and is otherwise cleared from its ledger page. The rent goes back to whoever
+  /// paid it.
+  pub fn archive_user_payment(
+    &self,
+    payer: &Pubkey,
+    user_payment: &Pubkey,
+  ) -> Result<Instruction> {
+    let payment: UserPayment = self.account(*user_payment)?;
+    let (chain_user_payment_id, chain_user_payments_page) = self
+      .chain_payment_reference(
+        pda::chain_user_payment_id(payment.chain_count),
+        pda::chain_user_payments_page(payment.chain_count),
+        payment.chain_count,
+        user_payment,
+      )?;
+    Ok(ix(
+      chainbills::accounts::ArchiveUserPayment {
+        user_payment: *user_payment,
+        chain_user_payment_id,
+        chain_user_payments_page,
+        rent_payer: payment.rent_payer,
+        signer: *payer,
+        event_authority: pda::event_authority(),
+        program: chainbills::ID,
+      },
+      chainbills::instruction::ArchiveUserPayment {},
+    ))
+  }
+
+  /// The chain-level reference account or ledger page of the payment with
+  /// the chain count, as (id, page). Neither if the payment has none, as
+  /// current payments don't.
+  fn chain_payment_reference(
+    &self,
+    id: Pubkey,
+    page: Pubkey,
+    chain_count: u64,
+    payment: &Pubkey,
+  ) -> Result<(Option<Pubkey>, Option<Pubkey>)> {
+    if self.exists(id)? {
+      return Ok((Some(id), None));
+    }
+    let paged = self
+      .maybe_account::<AddressLedgerPage>(page)?
+      .is_some_and(|page| page.get(chain_count) == Some(*payment));
+    Ok((None, paged.then_some(page)))
+  }
+
+  /// Archives the withdrawal with the Withdrawal address. Signed by its
+  /// host. The rent goes back to whoever paid it.
+  pub fn archive_withdrawal(
+    &self,
+    host: &Pubkey,
+    withdrawal: &Pubkey,
+  ) -> Result<Instruction> {
+    let data: Withdrawal = self.account(*withdrawal)?;
+    Ok(ix(
+      chainbills::accounts::ArchiveWithdrawal {
+        withdrawal: *withdrawal,
+        chain_withdrawal_id: pda::chain_withdrawal_id(data.chain_count),
+        payable_withdrawal_info: pda::payable_withdrawal_info(
+          &data.payable_id,
+          data.payable_count,
+        ),
+        rent_payer: data.rent_payer,
+        signer: *host,
+        event_authority: pda::event_authority(),
+        program: chainbills::ID,
+      },
+      chainbills::instruction::ArchiveWithdrawal {},
+    ))
+  }
+
+  /// The info account or ledger page that records the wallet's activity
+  /// with the user count, and the activity's address. Neither is returned
+  /// for a payment's activity, which is derived from the wallet and count.
+  pub(super) fn user_activity_accounts(
+    &self,
+    wallet: &Pubkey,
+    user_count: u64,
+  ) -> Result<(Option<Pubkey>, Option<Pubkey>, Pubkey)> {
+    let payment_activity = pda::payment_activity(wallet, user_count);
+    if self.exists(payment_activity)? {
+      return Ok((None, None, payment_activity));
+    }
+    let user_activity_info = pda::activity_info(wallet, user_count);
+    let user_activities_page = pda::activities_page(wallet, user_count);
+    let info: Option<UserActivityInfo> =
+      self.maybe_account(user_activity_info)?;
+    let chain_count = match &info {
+      Some(info) => info.chain_count,
+      None => self
+        .account::<CountLedgerPage>(user_activities_page)?
+        .get(user_count)
+        .ok_or(ClientError::AccountNotFound(user_activity_info))?,
+    };
+    let paged = info.is_none();
+    Ok((
+      (!paged).then_some(user_activity_info),
+      paged.then_some(user_activities_page),
+      pda::activity(chain_count),
+    ))
+  }
+
+  /// Archives the wallet's activity with the user count. Signed by the
+  /// wallet. Its info is closed if it has its own account, and is otherwise
+  /// cleared from its ledger page. The payable's info of the activity is
+  /// closed too, so a withdrawal's activity has to be archived before the
+  /// withdrawal. The rent goes back to whoever paid it.
+  pub fn archive_user_activity(
+    &self,
+    wallet: &Pubkey,
+    user_count: u64,
+  ) -> Result<Instruction> {
+    let (user_activity_info, user_activities_page, activity_address) =
+      self.user_activity_accounts(wallet, user_count)?;
+    let activity: ActivityRecord = self.account(activity_address)?;
+    let payable_activity_info = if activity.payable_count == 0 {
+      None
+    } else {
+      let payable = match activity.activity_type {
+        ActivityType::Withdrew => {
+          self.account::<Withdrawal>(activity.entity)?.payable_id
+        }
+        ActivityType::PayableReceived => {
+          self.account::<PayablePayment>(activity.entity)?.payable_id
+        }
+        _ => activity.entity,
+      };
+      Some(pda::activity_info(&payable, activity.payable_count))
+    };
+    Ok(ix(
+      chainbills::accounts::ArchiveUserActivity {
+        user_activity_info,
+        user_activities_page,
+        activity: activity_address,
+        payable_activity_info,
+        rent_payer: activity.rent_payer,
+        signer: *wallet,
+        event_authority: pda::event_authority(),
+        program: chainbills::ID,
+      },
+      chainbills::instruction::ArchiveUserActivity { user_count },
+    ))
+  }
+}
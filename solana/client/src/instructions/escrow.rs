@@ -0,0 +1,147 @@
+use super::{ata, ix};
+use crate::{client::Client, error::Result, pda, source::AccountSource};
+use anchor_lang::{
+  prelude::*, solana_program::instruction::Instruction, system_program,
+};
+use chainbills::state::*;
+
+impl<S: AccountSource> Client<S> {
+  /// Sets how long the payable's payments are escrowed, and who may settle
+  /// disputes. A timeout of zero stops escrowing new payments.
+  pub fn update_payable_escrow(
+    &self,
+    signer: &Pubkey,
+    payable: &Pubkey,
+    timeout: u64,
+    arbiter: Option<Pubkey>,
+  ) -> Result<Instruction> {
+    let host = self.payable(payable)?.host;
+    let activities = self.activity_accounts(&host, payable)?;
+    Ok(ix(
+      chainbills::accounts::UpdatePayableEscrow {
+        payable: *payable,
+        activity: activities.activity,
+        user_activity_info: activities.user_activity_info,
+        payable_activity_info: activities.payable_activity_info,
+        payable_escrow: pda::payable_escrow(payable),
+        host: activities.user,
+        operator: self.operator(signer, payable, &host)?,
+        chain_shard: activities.chain_shard,
+        signer: *signer,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::UpdatePayableEscrow { timeout, arbiter },
+    ))
+  }
+
+  /// Releases the escrowed payment to the payable's balances.
+  pub fn release_escrow(
+    &self,
+    signer: &Pubkey,
+    escrow_state: &Pubkey,
+  ) -> Result<Instruction> {
+    let escrow: EscrowState = self.account(*escrow_state)?;
+    let a = self.activity_accounts(&escrow.payer, &escrow.payable_id)?;
+    Ok(ix(
+      chainbills::accounts::ReleaseEscrow {
+        escrow_state: *escrow_state,
+        payable: escrow.payable_id,
+        payable_escrow: pda::payable_escrow(&escrow.payable_id),
+        activity: a.activity,
+        user_activity_info: a.user_activity_info,
+        payable_activity_info: a.payable_activity_info,
+        payer: a.user,
+        chain_shard: a.chain_shard,
+        signer: *signer,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::ReleaseEscrow {},
+    ))
+  }
+
+  /// Holds the escrowed payment until the arbiter settles it.
+  pub fn dispute_escrow(
+    &self,
+    signer: &Pubkey,
+    escrow_state: &Pubkey,
+  ) -> Result<Instruction> {
+    let escrow: EscrowState = self.account(*escrow_state)?;
+    let a = self.activity_accounts(&escrow.payer, &escrow.payable_id)?;
+    Ok(ix(
+      chainbills::accounts::DisputeEscrow {
+        escrow_state: *escrow_state,
+        payable: escrow.payable_id,
+        activity: a.activity,
+        user_activity_info: a.user_activity_info,
+        payable_activity_info: a.payable_activity_info,
+        payer: a.user,
+        chain_shard: a.chain_shard,
+        signer: *signer,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::DisputeEscrow {},
+    ))
+  }
+
+  /// Refunds the escrowed payment of an SPL token to the payer.
+  pub fn refund_escrow(
+    &self,
+    signer: &Pubkey,
+    escrow_state: &Pubkey,
+  ) -> Result<Instruction> {
+    let escrow: EscrowState = self.account(*escrow_state)?;
+    let a = self.activity_accounts(&escrow.payer, &escrow.payable_id)?;
+    let mint = escrow.details.token;
+    let token_program = self.token_program(&mint)?;
+    let chain_stats = pda::chain_stats();
+    Ok(ix(
+      chainbills::accounts::RefundEscrow {
+        escrow_state: *escrow_state,
+        payable: escrow.payable_id,
+        payable_escrow: pda::payable_escrow(&escrow.payable_id),
+        activity: a.activity,
+        user_activity_info: a.user_activity_info,
+        payable_activity_info: a.payable_activity_info,
+        payer: a.user,
+        chain_stats,
+        chain_shard: a.chain_shard,
+        mint,
+        token_details: pda::token_details(&mint),
+        payer_token_account: ata(&escrow.payer, &mint, &token_program),
+        chain_token_account: ata(&chain_stats, &mint, &token_program),
+        signer: *signer,
+        token_program,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::RefundEscrow {},
+    ))
+  }
+
+  /// Refunds the escrowed payment of native SOL to the payer.
+  pub fn refund_escrow_native(
+    &self,
+    signer: &Pubkey,
+    escrow_state: &Pubkey,
+  ) -> Result<Instruction> {
+    let escrow: EscrowState = self.account(*escrow_state)?;
+    let a = self.activity_accounts(&escrow.payer, &escrow.payable_id)?;
+    Ok(ix(
+      chainbills::accounts::RefundEscrowNative {
+        escrow_state: *escrow_state,
+        payable: escrow.payable_id,
+        payable_escrow: pda::payable_escrow(&escrow.payable_id),
+        activity: a.activity,
+        user_activity_info: a.user_activity_info,
+        payable_activity_info: a.payable_activity_info,
+        payer: a.user,
+        payer_wallet: escrow.payer,
+        chain_shard: a.chain_shard,
+        token_details: pda::token_details(&chainbills::ID),
+        native_vault: pda::native_vault(),
+        signer: *signer,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::RefundEscrowNative {},
+    ))
+  }
+}
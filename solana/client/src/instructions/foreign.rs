@@ -0,0 +1,56 @@
+use super::ix;
+use crate::{client::Client, error::Result, pda, source::AccountSource};
+use anchor_lang::{
+  prelude::*, solana_program::instruction::Instruction, system_program,
+};
+use chainbills::payload::PayablePayload;
+use wormhole_anchor_sdk::wormhole;
+
+impl<S: AccountSource> Client<S> {
+  /// Records the payable update in the VAA that was posted with the hash.
+  /// Anyone can sign as the relayer.
+  pub fn record_foreign_payable_update(
+    &self,
+    signer: &Pubkey,
+    vaa_hash: [u8; 32],
+  ) -> Result<Instruction> {
+    let posted_vaa = pda::posted_vaa(&vaa_hash);
+    let vaa: wormhole::PostedVaa<PayablePayload> = self.account(posted_vaa)?;
+    let payload = vaa.data();
+    let emitter_chain = vaa.emitter_chain();
+    let chain_stats = self.chain_stats()?;
+    Ok(ix(
+      chainbills::accounts::RecordForeignPayableUpdate {
+        foreign_payable: pda::foreign_payable(&payload.payable_id),
+        chain_foreign_payable_id: pda::chain_foreign_payable_id(
+          chain_stats.next_foreign_payable(),
+        ),
+        registered_foreign_contract: pda::registered_foreign_contract(
+          emitter_chain,
+        ),
+        emitter_cb_chain_id: pda::wormhole_chain_cb_chain_id(emitter_chain),
+        chain_stats: pda::chain_stats(),
+        config: pda::config(),
+        signer: *signer,
+        posted_vaa,
+        consumed_wormhole_message: pda::consumed_wormhole_message(
+          emitter_chain,
+          vaa.sequence(),
+        ),
+        chain_consumed_wormhole_message_id:
+          pda::chain_consumed_wormhole_message_id(
+            chain_stats.next_consumed_wormhole_message(),
+          ),
+        per_chain_consumed_wormhole_messages_counter:
+          pda::per_chain_consumed_wormhole_messages_counter(emitter_chain),
+        wormhole_program: wormhole::program::ID,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::RecordForeignPayableUpdate {
+        payable_id: payload.payable_id,
+        ataa_len: payload.allowed_tokens_and_amounts.len() as u8,
+        vaa_hash,
+      },
+    ))
+  }
+}
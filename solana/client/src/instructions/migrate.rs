@@ -0,0 +1,154 @@
+use super::ix;
+use crate::{client::Client, error::Result, pda, source::AccountSource};
+use anchor_lang::{
+  prelude::*, solana_program::instruction::Instruction, system_program,
+};
+
+/// Builders of the migrations of accounts in legacy layouts. Each migration
+/// is signed by the account's owner: the wallet for users and activity
+/// records, the host for payables and withdrawals, the payer for user
+/// payments, and the program's owner for the singletons and token details.
+/// Anyone can sign for payable payments.
+impl<S: AccountSource> Client<S> {
+  pub fn migrate_user(&self, wallet: &Pubkey) -> Instruction {
+    ix(
+      chainbills::accounts::MigrateUser {
+        user: pda::user(wallet),
+        signer: *wallet,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::MigrateUser {},
+    )
+  }
+
+  pub fn migrate_payable(
+    &self,
+    host: &Pubkey,
+    payable: &Pubkey,
+  ) -> Instruction {
+    ix(
+      chainbills::accounts::MigratePayable {
+        payable: *payable,
+        signer: *host,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::MigratePayable {},
+    )
+  }
+
+  pub fn migrate_user_payment(
+    &self,
+    signer: &Pubkey,
+    user_payment: &Pubkey,
+  ) -> Instruction {
+    ix(
+      chainbills::accounts::MigrateUserPayment {
+        user_payment: *user_payment,
+        signer: *signer,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::MigrateUserPayment {},
+    )
+  }
+
+  pub fn migrate_payable_payment(
+    &self,
+    signer: &Pubkey,
+    payable_payment: &Pubkey,
+  ) -> Instruction {
+    ix(
+      chainbills::accounts::MigratePayablePayment {
+        payable_payment: *payable_payment,
+        signer: *signer,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::MigratePayablePayment {},
+    )
+  }
+
+  pub fn migrate_withdrawal(
+    &self,
+    host: &Pubkey,
+    withdrawal: &Pubkey,
+  ) -> Instruction {
+    ix(
+      chainbills::accounts::MigrateWithdrawal {
+        withdrawal: *withdrawal,
+        signer: *host,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::MigrateWithdrawal {},
+    )
+  }
+
+  /// Fails if the wallet has no activity with the user count.
+  pub fn migrate_activity_record(
+    &self,
+    wallet: &Pubkey,
+    user_count: u64,
+  ) -> Result<Instruction> {
+    let (user_activity_info, user_activities_page, activity) =
+      self.user_activity_accounts(wallet, user_count)?;
+    Ok(ix(
+      chainbills::accounts::MigrateActivityRecord {
+        user_activity_info,
+        user_activities_page,
+        activity,
+        signer: *wallet,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::MigrateActivityRecord { user_count },
+    ))
+  }
+
+  pub fn migrate_config(&self, owner: &Pubkey) -> Instruction {
+    ix(
+      chainbills::accounts::MigrateConfig {
+        config: pda::config(),
+        owner: *owner,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::MigrateConfig {},
+    )
+  }
+
+  pub fn migrate_chain_stats(&self, owner: &Pubkey) -> Instruction {
+    ix(
+      chainbills::accounts::MigrateChainStats {
+        chain_stats: pda::chain_stats(),
+        config: pda::config(),
+        owner: *owner,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::MigrateChainStats {},
+    )
+  }
+
+  pub fn migrate_token_details(
+    &self,
+    owner: &Pubkey,
+    token: Pubkey,
+  ) -> Instruction {
+    ix(
+      chainbills::accounts::MigrateTokenDetails {
+        token_details: pda::token_details(&token),
+        config: pda::config(),
+        owner: *owner,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::MigrateTokenDetails { token },
+    )
+  }
+
+  pub fn migrate_native_vault(&self, owner: &Pubkey) -> Instruction {
+    ix(
+      chainbills::accounts::MigrateNativeVault {
+        chain_stats: pda::chain_stats(),
+        native_vault: pda::native_vault(),
+        config: pda::config(),
+        owner: *owner,
+      },
+      chainbills::instruction::MigrateNativeVault {},
+    )
+  }
+}
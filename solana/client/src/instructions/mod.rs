@@ -0,0 +1,236 @@
+//! Builders of the program's instructions, with complete account lists.
+//!
+//! The accounts that an instruction initializes are derived from counts
+//! that the instruction itself increments, so a built instruction is only
+//! valid until another instruction advances those counts. Build right
+//! before sending, and rebuild if the transaction fails on an account
+//! that is already in use.
+
+mod archive;
+mod escrow;
+mod foreign;
+mod migrate;
+mod owner;
+mod payables;
+mod payments;
+mod receipts;
+mod subscriptions;
+mod users;
+mod withdrawals;
+
+pub use payments::{intent_signature_ix, intent_signing_bytes};
+
+use crate::{client::Client, error::Result, pda, source::AccountSource};
+use anchor_lang::{
+  prelude::*, solana_program::instruction::Instruction, InstructionData,
+};
+use anchor_spl::{
+  associated_token::{self, get_associated_token_address_with_program_id},
+  token_2022,
+};
+use chainbills::state::*;
+use wormhole_anchor_sdk::wormhole;
+
+/// Builds an instruction of the program.
+pub(crate) fn ix(
+  accounts: impl ToAccountMetas,
+  data: impl InstructionData,
+) -> Instruction {
+  Instruction {
+    program_id: chainbills::ID,
+    accounts: accounts.to_account_metas(None),
+    data: data.data(),
+  }
+}
+
+/// The associated token account of the wallet for the mint.
+pub(crate) fn ata(
+  wallet: &Pubkey,
+  mint: &Pubkey,
+  token_program: &Pubkey,
+) -> Pubkey {
+  get_associated_token_address_with_program_id(wallet, mint, token_program)
+}
+
+/// The program's Wormhole accounts for publishing its next message.
+pub struct WormholeAccounts {
+  pub wormhole_program: Pubkey,
+  pub wormhole_bridge: Pubkey,
+  pub wormhole_fee_collector: Pubkey,
+  pub wormhole_emitter: Pubkey,
+  pub wormhole_sequence: Pubkey,
+  pub wormhole_message: Pubkey,
+}
+
+/// The accounts that record an activity of a user on a payable.
+pub struct ActivityAccounts {
+  pub activity: Pubkey,
+  pub user_activity_info: Pubkey,
+  pub payable_activity_info: Pubkey,
+  /// The user's User account.
+  pub user: Pubkey,
+  /// The shard that the activity is indexed in, the payable's.
+  pub chain_shard: Pubkey,
+}
+
+/// The accounts that every kind of payment into a payable initializes or
+/// updates. The payable's index entry has either its own account or, if the
+/// client is paged, its ledger page.
+pub struct PaymentAccounts {
+  pub user_payment: Pubkey,
+  pub payable_payment: Pubkey,
+  pub payable_per_chain_payment_info: Option<Pubkey>,
+  pub payable_per_chain_payments_page: Option<Pubkey>,
+  pub payable_per_chain_payments_counter: Pubkey,
+  pub user_activity: Pubkey,
+  pub payable_activity: Pubkey,
+  pub allowed_payers: Pubkey,
+  pub payable_escrow: Pubkey,
+  /// Set only if the payable escrows its payments.
+  pub escrow_state: Option<Pubkey>,
+  pub payable_receipts: Pubkey,
+  /// Set only if the payable mints receipts, as are the programs that mint
+  /// them.
+  pub receipt_mint: Option<Pubkey>,
+  pub payer_receipt_token_account: Option<Pubkey>,
+  pub receipt_token_program: Option<Pubkey>,
+  pub associated_token_program: Option<Pubkey>,
+  /// The payer's User account.
+  pub payer: Pubkey,
+  /// The shard that the payment is indexed in, the payable's.
+  pub chain_shard: Pubkey,
+  /// The payment's token's shard of totals that goes with chain_shard.
+  pub token_details_shard: Pubkey,
+}
+
+impl<S: AccountSource> Client<S> {
+  pub fn wormhole_accounts(&self) -> Result<WormholeAccounts> {
+    let config = self.config()?;
+    let sequence: wormhole::SequenceTracker =
+      self.account(config.wormhole_sequence)?;
+    Ok(WormholeAccounts {
+      wormhole_program: wormhole::program::ID,
+      wormhole_bridge: config.wormhole_bridge,
+      wormhole_fee_collector: config.wormhole_fee_collector,
+      wormhole_emitter: config.wormhole_emitter,
+      wormhole_sequence: config.wormhole_sequence,
+      wormhole_message: pda::wormhole_message(sequence.next_value()),
+    })
+  }
+
+  /// The accounts that record the next activity of the wallet on the
+  /// payable.
+  pub fn activity_accounts(
+    &self,
+    wallet: &Pubkey,
+    payable: &Pubkey,
+  ) -> Result<ActivityAccounts> {
+    let shard = pda::shard_of(payable);
+    let chain_shard = self.chain_shard(shard)?;
+    let user = self.user(wallet)?;
+    let payable_data = self.payable(payable)?;
+    Ok(ActivityAccounts {
+      activity: pda::activity(chain_shard.next_activity()),
+      user_activity_info: pda::activity_info(wallet, user.next_activity()),
+      payable_activity_info: pda::activity_info(
+        payable,
+        payable_data.next_activity(),
+      ),
+      user: pda::user(wallet),
+      chain_shard: pda::chain_shard(shard),
+    })
+  }
+
+  /// The accounts of the payer's next payment in the token into the
+  /// payable. The token is this program's ID for native SOL.
+  pub fn payment_accounts(
+    &self,
+    payer: &Pubkey,
+    payable: &Pubkey,
+    token: &Pubkey,
+  ) -> Result<PaymentAccounts> {
+    let chain_id = self.config()?.chain_id;
+    let shard = pda::shard_of(payable);
+    let user = self.user(payer)?;
+    let payable_data = self.payable(payable)?;
+    let counter_address =
+      pda::payable_per_chain_payments_counter(payable, chain_id);
+    let counter: PayablePerChainPaymentsCounter =
+      self.account(counter_address)?;
+    let payable_payment =
+      pda::payable_payment(payable, payable_data.next_payment());
+    let escrows = self.active_escrow(payable)?.is_some();
+    let receipts = self.receipts_enabled(payable)?;
+    let user_payment = pda::user_payment(payer, user.next_payment());
+    let receipt_mint = pda::receipt_mint(&user_payment);
+    let (own, paged) = (!self.paged(), self.paged());
+    let per_chain_count = counter.next_payment();
+    Ok(PaymentAccounts {
+      user_payment,
+      payable_payment,
+      payable_per_chain_payment_info: own.then(|| {
+        pda::payable_per_chain_payment_info(payable, chain_id, per_chain_count)
+      }),
+      payable_per_chain_payments_page: paged.then(|| {
+        pda::payable_per_chain_payments_page(payable, chain_id, per_chain_count)
+      }),
+      payable_per_chain_payments_counter: counter_address,
+      user_activity: pda::payment_activity(payer, user.next_activity()),
+      payable_activity: pda::payment_activity(
+        payable,
+        payable_data.next_activity(),
+      ),
+      allowed_payers: pda::allowed_payers(&payable.to_bytes()),
+      payable_escrow: pda::payable_escrow(payable),
+      escrow_state: escrows.then(|| pda::escrow_state(&payable_payment)),
+      payable_receipts: pda::payable_receipts(&payable.to_bytes()),
+      receipt_mint: receipts.then_some(receipt_mint),
+      payer_receipt_token_account: receipts
+        .then(|| ata(payer, &receipt_mint, &token_2022::ID)),
+      receipt_token_program: receipts.then_some(token_2022::ID),
+      associated_token_program: receipts.then_some(associated_token::ID),
+      payer: pda::user(payer),
+      chain_shard: pda::chain_shard(shard),
+      token_details_shard: pda::token_details_shard(token, shard),
+    })
+  }
+
+  /// The account that an allowed token references: the token itself if it
+  /// is a TokenGroup, or else the token's TokenDetails.
+  pub fn allowed_token_account(&self, token: &Pubkey) -> Result<Pubkey> {
+    match self.get_account(*token)? {
+      Some(account) if account.owner == chainbills::ID => Ok(*token),
+      _ => Ok(pda::token_details(token)),
+    }
+  }
+
+  /// The signer's operator account on the payable, if the signer isn't the
+  /// host and the account exists.
+  fn operator(
+    &self,
+    signer: &Pubkey,
+    payable: &Pubkey,
+    host: &Pubkey,
+  ) -> Result<Option<Pubkey>> {
+    if signer == host {
+      return Ok(None);
+    }
+    let operator = pda::payable_operator(payable, signer);
+    Ok(self.exists(operator)?.then_some(operator))
+  }
+
+  /// The referenced accounts of the allowed tokens, which go after an
+  /// instruction's named accounts.
+  fn allowed_token_metas(
+    &self,
+    allowed_tokens_and_amounts: &[TokenAndAmount],
+  ) -> Result<Vec<AccountMeta>> {
+    allowed_tokens_and_amounts
+      .iter()
+      .map(|taa| {
+        let account = self.allowed_token_account(&taa.token)?;
+        Ok(AccountMeta::new_readonly(account, false))
+      })
+      .collect()
+  }
+}
@@ -0,0 +1,260 @@
+use super::{ata, ix};
+use crate::{client::Client, error::Result, pda, source::AccountSource};
+use anchor_lang::{
+  prelude::*,
+  solana_program::{instruction::Instruction, sysvar},
+  system_program,
+};
+use anchor_spl::associated_token;
+use chainbills::state::*;
+use wormhole_anchor_sdk::wormhole;
+
+impl<S: AccountSource> Client<S> {
+  /// Initializes the program with the signing owner and the fee collector.
+  pub fn initialize(
+    &self,
+    owner: &Pubkey,
+    fee_collector: Pubkey,
+  ) -> Instruction {
+    ix(
+      chainbills::accounts::Initialize {
+        owner: *owner,
+        chain_stats: pda::chain_stats(),
+        config: pda::config(),
+        chainbills_fee_collector: fee_collector,
+        wormhole_program: wormhole::program::ID,
+        wormhole_bridge: pda::wormhole_bridge(),
+        wormhole_emitter: pda::wormhole_emitter(),
+        wormhole_fee_collector: pda::wormhole_fee_collector(),
+        wormhole_sequence: pda::wormhole_sequence(),
+        wormhole_message: pda::wormhole_message(wormhole::INITIAL_SEQUENCE),
+        clock: sysvar::clock::ID,
+        rent: sysvar::rent::ID,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::Initialize {},
+    )
+  }
+
+  /// Supports the SPL token with the fees cap, or updates its cap.
+  pub fn update_max_withdrawal_fees(
+    &self,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    max_withdrawal_fees: u64,
+  ) -> Result<Instruction> {
+    let fee_collector = self.config()?.chainbills_fee_collector;
+    let token_program = self.token_program(mint)?;
+    let chain_stats = pda::chain_stats();
+    Ok(ix(
+      chainbills::accounts::UpdateMaxWithdrawalFees {
+        token_details: pda::token_details(mint),
+        chain_token_account: ata(&chain_stats, mint, &token_program),
+        fee_collector,
+        fees_token_account: ata(&fee_collector, mint, &token_program),
+        config: pda::config(),
+        chain_stats,
+        mint: *mint,
+        owner: *owner,
+        associated_token_program: associated_token::ID,
+        token_program,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::UpdateMaxWithdrawalFees {
+        token: *mint,
+        max_withdrawal_fees,
+      },
+    ))
+  }
+
+  /// Supports native SOL with the fees cap, or updates its cap.
+  pub fn update_max_withdrawal_fees_native(
+    &self,
+    owner: &Pubkey,
+    max_withdrawal_fees: u64,
+  ) -> Instruction {
+    ix(
+      chainbills::accounts::UpdateMaxWithdrawalFeesNative {
+        token_details: pda::token_details(&chainbills::ID),
+        native_vault: pda::native_vault(),
+        config: pda::config(),
+        owner: *owner,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::UpdateMaxWithdrawalFeesNative {
+        max_withdrawal_fees,
+      },
+    )
+  }
+
+  pub fn update_token_group(
+    &self,
+    owner: &Pubkey,
+    args: chainbills::instruction::UpdateTokenGroup,
+  ) -> Instruction {
+    ix(
+      chainbills::accounts::UpdateTokenGroup {
+        token_group: pda::token_group(&args.name),
+        config: pda::config(),
+        owner: *owner,
+        system_program: system_program::ID,
+      },
+      args,
+    )
+  }
+
+  pub fn update_token_foreign_chain(
+    &self,
+    owner: &Pubkey,
+    args: chainbills::instruction::UpdateTokenForeignChain,
+  ) -> Instruction {
+    ix(
+      chainbills::accounts::UpdateTokenForeignChain {
+        token_foreign_chain: pda::token_foreign_chain(args.chain),
+        config: pda::config(),
+        owner: *owner,
+        system_program: system_program::ID,
+      },
+      args,
+    )
+  }
+
+  /// Withdraws the amount of the mint that no payable holds to the owner.
+  pub fn owner_withdraw(
+    &self,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+  ) -> Result<Instruction> {
+    let token_program = self.token_program(mint)?;
+    let chain_stats = pda::chain_stats();
+    Ok(ix(
+      chainbills::accounts::OwnerWithdraw {
+        mint: *mint,
+        chain_stats,
+        chain_token_account: ata(&chain_stats, mint, &token_program),
+        owner_token_account: ata(owner, mint, &token_program),
+        config: pda::config(),
+        owner: *owner,
+        token_program,
+      },
+      chainbills::instruction::OwnerWithdraw { amount },
+    ))
+  }
+
+  pub fn register_cb_chain(
+    &self,
+    owner: &Pubkey,
+    caip2: String,
+  ) -> Instruction {
+    ix(
+      chainbills::accounts::RegisterCbChain {
+        cb_chain: pda::cb_chain(&cb_chain_id(&caip2)),
+        config: pda::config(),
+        owner: *owner,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::RegisterCbChain { caip2 },
+    )
+  }
+
+  pub fn register_chain_wormhole_id(
+    &self,
+    owner: &Pubkey,
+    cb_chain_id: [u8; 32],
+    wormhole_chain_id: u16,
+  ) -> Instruction {
+    ix(
+      chainbills::accounts::RegisterChainWormholeId {
+        cb_chain: pda::cb_chain(&cb_chain_id),
+        wormhole_chain_cb_chain_id: pda::wormhole_chain_cb_chain_id(
+          wormhole_chain_id,
+        ),
+        config: pda::config(),
+        owner: *owner,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::RegisterChainWormholeId {
+        cb_chain_id,
+        wormhole_chain_id,
+      },
+    )
+  }
+
+  pub fn register_chain_circle_domain(
+    &self,
+    owner: &Pubkey,
+    cb_chain_id: [u8; 32],
+    circle_domain: u32,
+  ) -> Instruction {
+    ix(
+      chainbills::accounts::RegisterChainCircleDomain {
+        cb_chain: pda::cb_chain(&cb_chain_id),
+        circle_domain_cb_chain_id: pda::circle_domain_cb_chain_id(
+          circle_domain,
+        ),
+        config: pda::config(),
+        owner: *owner,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::RegisterChainCircleDomain {
+        cb_chain_id,
+        circle_domain,
+      },
+    )
+  }
+
+  pub fn register_foreign_contract(
+    &self,
+    owner: &Pubkey,
+    chain_id: u16,
+    emitter_address: [u8; 32],
+  ) -> Instruction {
+    ix(
+      chainbills::accounts::RegisterForeignContract {
+        registered_foreign_contract: pda::registered_foreign_contract(chain_id),
+        chain_stats: pda::chain_stats(),
+        config: pda::config(),
+        owner: *owner,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::RegisterForeignContract {
+        chain_id,
+        emitter_address,
+      },
+    )
+  }
+
+  /// Compares the program's holdings of the token, or of native SOL if the
+  /// token is this program's ID, with what the payables and escrow states
+  /// owe. Simulate it to read the returned TokenIntegrity.
+  pub fn check_token_integrity(
+    &self,
+    token: &Pubkey,
+    payables_and_escrow_states: &[Pubkey],
+  ) -> Result<Instruction> {
+    let token_program = if *token == chainbills::ID {
+      None
+    } else {
+      Some(self.token_program(token)?)
+    };
+    let chain_stats = pda::chain_stats();
+    let mut instruction = ix(
+      chainbills::accounts::CheckTokenIntegrity {
+        token_details: pda::token_details(token),
+        chain_stats,
+        native_vault: pda::native_vault(),
+        chain_token_account: token_program
+          .map(|program| ata(&chain_stats, token, &program)),
+        token_program,
+      },
+      chainbills::instruction::CheckTokenIntegrity { token: *token },
+    );
+    instruction.accounts.extend(
+      payables_and_escrow_states
+        .iter()
+        .map(|account| AccountMeta::new_readonly(*account, false)),
+    );
+    Ok(instruction)
+  }
+}
@@ -0,0 +1,321 @@
+use super::{ix, ActivityAccounts, WormholeAccounts};
+use crate::{client::Client, error::Result, pda, source::AccountSource};
+use anchor_lang::{
+  prelude::*,
+  solana_program::{instruction::Instruction, sysvar},
+  system_program, InstructionData,
+};
+use chainbills::state::*;
+
+/// The accounts of an update of a payable that is published to other chains.
+struct PublishedUpdate {
+  activities: ActivityAccounts,
+  operator: Option<Pubkey>,
+  wormhole: WormholeAccounts,
+}
+
+impl<S: AccountSource> Client<S> {
+  /// Creates the host's next payable, returning its address alongside.
+  pub fn create_payable(
+    &self,
+    host: &Pubkey,
+    allowed_tokens_and_amounts: Vec<TokenAndAmount>,
+  ) -> Result<(Instruction, Pubkey)> {
+    let chain_id = self.config()?.chain_id;
+    let user = self.user(host)?;
+    let payable = pda::payable(host, user.next_payable());
+    let shard = pda::shard_of(&payable);
+    let chain_shard = self.chain_shard(shard)?;
+    let wormhole = self.wormhole_accounts()?;
+    let remaining = self.allowed_token_metas(&allowed_tokens_and_amounts)?;
+    let mut instruction = ix(
+      chainbills::accounts::CreatePayable {
+        payable,
+        chain_payable_id: pda::chain_payable_id(chain_shard.next_payable()),
+        payable_per_chain_payments_counter:
+          pda::payable_per_chain_payments_counter(&payable, chain_id),
+        activity: pda::activity(chain_shard.next_activity()),
+        user_activity_info: pda::activity_info(host, user.next_activity()),
+        payable_activity_info: pda::activity_info(&payable, 1),
+        host: pda::user(host),
+        chain_stats: pda::chain_stats(),
+        chain_shard: pda::chain_shard(shard),
+        config: pda::config(),
+        wormhole_program: wormhole.wormhole_program,
+        wormhole_bridge: wormhole.wormhole_bridge,
+        wormhole_fee_collector: wormhole.wormhole_fee_collector,
+        wormhole_emitter: wormhole.wormhole_emitter,
+        wormhole_sequence: wormhole.wormhole_sequence,
+        wormhole_message: wormhole.wormhole_message,
+        signer: *host,
+        clock: sysvar::clock::ID,
+        rent: sysvar::rent::ID,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::CreatePayable {
+        allowed_tokens_and_amounts,
+      },
+    );
+    instruction.accounts.extend(remaining);
+    Ok((instruction, payable))
+  }
+
+  fn published_update(
+    &self,
+    signer: &Pubkey,
+    payable: &Pubkey,
+  ) -> Result<PublishedUpdate> {
+    let host = self.payable(payable)?.host;
+    Ok(PublishedUpdate {
+      activities: self.activity_accounts(&host, payable)?,
+      operator: self.operator(signer, payable, &host)?,
+      wormhole: self.wormhole_accounts()?,
+    })
+  }
+
+  fn update_payable(
+    &self,
+    signer: &Pubkey,
+    payable: &Pubkey,
+    data: impl InstructionData,
+  ) -> Result<Instruction> {
+    let PublishedUpdate {
+      activities,
+      operator,
+      wormhole,
+    } = self.published_update(signer, payable)?;
+    Ok(ix(
+      chainbills::accounts::UpdatePayable {
+        payable: *payable,
+        activity: activities.activity,
+        user_activity_info: activities.user_activity_info,
+        payable_activity_info: activities.payable_activity_info,
+        host: activities.user,
+        operator,
+        chain_stats: pda::chain_stats(),
+        chain_shard: activities.chain_shard,
+        config: pda::config(),
+        wormhole_program: wormhole.wormhole_program,
+        wormhole_bridge: wormhole.wormhole_bridge,
+        wormhole_fee_collector: wormhole.wormhole_fee_collector,
+        wormhole_emitter: wormhole.wormhole_emitter,
+        wormhole_sequence: wormhole.wormhole_sequence,
+        wormhole_message: wormhole.wormhole_message,
+        signer: *signer,
+        clock: sysvar::clock::ID,
+        rent: sysvar::rent::ID,
+        system_program: system_program::ID,
+      },
+      data,
+    ))
+  }
+
+  /// Stops the payable from accepting payments. Signed by the host or an
+  /// operator with the manage role.
+  pub fn close_payable(
+    &self,
+    signer: &Pubkey,
+    payable: &Pubkey,
+  ) -> Result<Instruction> {
+    let data = chainbills::instruction::ClosePayable {};
+    self.update_payable(signer, payable, data)
+  }
+
+  /// Lets the payable accept payments again. Signed by the host or an
+  /// operator with the manage role.
+  pub fn reopen_payable(
+    &self,
+    signer: &Pubkey,
+    payable: &Pubkey,
+  ) -> Result<Instruction> {
+    let data = chainbills::instruction::ReopenPayable {};
+    self.update_payable(signer, payable, data)
+  }
+
+  pub fn update_payable_allowed_tokens_and_amounts(
+    &self,
+    signer: &Pubkey,
+    payable: &Pubkey,
+    allowed_tokens_and_amounts: Vec<TokenAndAmount>,
+  ) -> Result<Instruction> {
+    let PublishedUpdate {
+      activities,
+      operator,
+      wormhole,
+    } = self.published_update(signer, payable)?;
+    let remaining = self.allowed_token_metas(&allowed_tokens_and_amounts)?;
+    let mut instruction = ix(
+      chainbills::accounts::UpdatePayableAllowedTokensAndAmounts {
+        payable: *payable,
+        activity: activities.activity,
+        user_activity_info: activities.user_activity_info,
+        payable_activity_info: activities.payable_activity_info,
+        host: activities.user,
+        operator,
+        chain_stats: pda::chain_stats(),
+        chain_shard: activities.chain_shard,
+        config: pda::config(),
+        wormhole_program: wormhole.wormhole_program,
+        wormhole_bridge: wormhole.wormhole_bridge,
+        wormhole_fee_collector: wormhole.wormhole_fee_collector,
+        wormhole_emitter: wormhole.wormhole_emitter,
+        wormhole_sequence: wormhole.wormhole_sequence,
+        wormhole_message: wormhole.wormhole_message,
+        signer: *signer,
+        clock: sysvar::clock::ID,
+        rent: sysvar::rent::ID,
+        system_program: system_program::ID,
+        event_authority: pda::event_authority(),
+        program: chainbills::ID,
+      },
+      chainbills::instruction::UpdatePayableAllowedTokensAndAmounts {
+        allowed_tokens_and_amounts,
+      },
+    );
+    instruction.accounts.extend(remaining);
+    Ok(instruction)
+  }
+
+  pub fn update_payable_allowed_payers(
+    &self,
+    signer: &Pubkey,
+    payable: &Pubkey,
+    allowed_payers: Vec<[u8; 32]>,
+    allowed_payers_root: [u8; 32],
+  ) -> Result<Instruction> {
+    let PublishedUpdate {
+      activities,
+      operator,
+      wormhole,
+    } = self.published_update(signer, payable)?;
+    Ok(ix(
+      chainbills::accounts::UpdatePayableAllowedPayers {
+        payable: *payable,
+        activity: activities.activity,
+        user_activity_info: activities.user_activity_info,
+        payable_activity_info: activities.payable_activity_info,
+        allowed_payers: pda::allowed_payers(&payable.to_bytes()),
+        host: activities.user,
+        operator,
+        chain_stats: pda::chain_stats(),
+        chain_shard: activities.chain_shard,
+        config: pda::config(),
+        wormhole_program: wormhole.wormhole_program,
+        wormhole_bridge: wormhole.wormhole_bridge,
+        wormhole_fee_collector: wormhole.wormhole_fee_collector,
+        wormhole_emitter: wormhole.wormhole_emitter,
+        wormhole_sequence: wormhole.wormhole_sequence,
+        wormhole_message: wormhole.wormhole_message,
+        signer: *signer,
+        clock: sysvar::clock::ID,
+        rent: sysvar::rent::ID,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::UpdatePayableAllowedPayers {
+        allowed_payers,
+        allowed_payers_root,
+      },
+    ))
+  }
+
+  /// Offers the payable to the new host, who must accept it.
+  pub fn transfer_payable(
+    &self,
+    host: &Pubkey,
+    payable: &Pubkey,
+    new_host: Pubkey,
+  ) -> Instruction {
+    ix(
+      chainbills::accounts::TransferPayable {
+        payable: *payable,
+        pending_payable_host: pda::pending_payable_host(payable),
+        signer: *host,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::TransferPayable { new_host },
+    )
+  }
+
+  /// Takes over the payable that was offered to the new host, which signs.
+  pub fn accept_payable(
+    &self,
+    new_host: &Pubkey,
+    payable: &Pubkey,
+  ) -> Result<Instruction> {
+    let shard = pda::shard_of(payable);
+    let chain_shard = self.chain_shard(shard)?;
+    let payable_data = self.payable(payable)?;
+    let previous_host = self.user(&payable_data.host)?;
+    let new_host_data = self.user(new_host)?;
+    Ok(ix(
+      chainbills::accounts::AcceptPayable {
+        payable: *payable,
+        pending_payable_host: pda::pending_payable_host(payable),
+        user_payable_info: pda::user_payable_info(
+          new_host,
+          new_host_data.next_payable(),
+        ),
+        previous_host_activity: pda::activity(chain_shard.next_activity()),
+        previous_host_activity_info: pda::activity_info(
+          &payable_data.host,
+          previous_host.next_activity(),
+        ),
+        previous_host_payable_activity_info: pda::activity_info(
+          payable,
+          payable_data.next_activity(),
+        ),
+        new_host_activity: pda::activity(chain_shard.next_activity() + 1),
+        new_host_activity_info: pda::activity_info(
+          new_host,
+          new_host_data.next_activity(),
+        ),
+        new_host_payable_activity_info: pda::activity_info(
+          payable,
+          payable_data.next_activity() + 1,
+        ),
+        previous_host: pda::user(&payable_data.host),
+        previous_host_wallet: payable_data.host,
+        new_host: pda::user(new_host),
+        chain_shard: pda::chain_shard(shard),
+        signer: *new_host,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::AcceptPayable {},
+    ))
+  }
+
+  /// Grants the roles of PayableOperator to the operator on the payable.
+  pub fn set_payable_operator(
+    &self,
+    host: &Pubkey,
+    payable: &Pubkey,
+    operator: Pubkey,
+    roles: u8,
+  ) -> Instruction {
+    ix(
+      chainbills::accounts::SetPayableOperator {
+        payable: *payable,
+        payable_operator: pda::payable_operator(payable, &operator),
+        signer: *host,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::SetPayableOperator { operator, roles },
+    )
+  }
+
+  pub fn remove_payable_operator(
+    &self,
+    host: &Pubkey,
+    payable: &Pubkey,
+    operator: &Pubkey,
+  ) -> Instruction {
+    ix(
+      chainbills::accounts::RemovePayableOperator {
+        payable: *payable,
+        payable_operator: pda::payable_operator(payable, operator),
+        signer: *host,
+      },
+      chainbills::instruction::RemovePayableOperator {},
+    )
+  }
+}
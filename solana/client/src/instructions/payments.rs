@@ -0,0 +1,358 @@
+use super::{ata, ix};
+use crate::{client::Client, error::Result, pda, source::AccountSource};
+use anchor_lang::{
+  prelude::*,
+  solana_program::{ed25519_program, instruction::Instruction, sysvar},
+  system_program,
+};
+use anchor_spl::{associated_token, token_2022};
+use chainbills::{context::BatchPayment, state::*};
+use chainbills_payload::PaymentIntent;
+use solana_sdk::signature::Signature;
+
+/// The bytes that the payable's host signs to authorize a payment of the
+/// intent's exact amount of the mint.
+pub fn intent_signing_bytes(
+  payable: &Pubkey,
+  mint: &Pubkey,
+  intent: &chainbills::instruction::PayWithIntent,
+) -> Result<Vec<u8>> {
+  let bytes = PaymentIntent {
+    payable_chain_id: solana_cb_chain_id(),
+    payable_id: payable.to_bytes(),
+    token: mint.to_bytes(),
+    amount: intent.amount.into(),
+    expiry: intent.expiry,
+    nonce: intent.nonce,
+    reference: intent.reference.as_bytes().to_vec(),
+  }
+  .signing_bytes()?;
+  Ok(bytes)
+}
+
+/// The Ed25519 program instruction that verifies the host's signature of an
+/// intent. It must come right before the pay_with_intent instruction.
+pub fn intent_signature_ix(
+  host: &Pubkey,
+  signature: &Signature,
+  message: &[u8],
+) -> Instruction {
+  const PUBKEY_OFFSET: u16 = 16;
+  const SIGNATURE_OFFSET: u16 = PUBKEY_OFFSET + 32;
+  const MESSAGE_OFFSET: u16 = SIGNATURE_OFFSET + 64;
+  // One signature, with all its inputs in this instruction's data.
+  let mut data = vec![1, 0];
+  for offset in [
+    SIGNATURE_OFFSET,
+    u16::MAX,
+    PUBKEY_OFFSET,
+    u16::MAX,
+    MESSAGE_OFFSET,
+    message.len() as u16,
+    u16::MAX,
+  ] {
+    data.extend_from_slice(&offset.to_le_bytes());
+  }
+  data.extend_from_slice(host.as_ref());
+  data.extend_from_slice(signature.as_ref());
+  data.extend_from_slice(message);
+  Instruction {
+    program_id: ed25519_program::ID,
+    accounts: vec![],
+    data,
+  }
+}
+
+impl<S: AccountSource> Client<S> {
+  /// The TokenGroup that the payable accepts the token through, if the
+  /// payable doesn't list the token itself.
+  pub fn token_group_for(
+    &self,
+    payable: &Pubkey,
+    token: &Pubkey,
+  ) -> Result<Option<Pubkey>> {
+    let allowed: Vec<Pubkey> = self
+      .payable(payable)?
+      .allowed_tokens_and_amounts
+      .iter()
+      .map(|taa| taa.token)
+      .collect();
+    if allowed.contains(token) {
+      return Ok(None);
+    }
+    let accounts = self.source().get_accounts(&allowed)?;
+    for (address, account) in allowed.iter().zip(accounts) {
+      let Some(account) = account.filter(|a| a.owner == chainbills::ID) else {
+        continue;
+      };
+      let group = TokenGroup::try_deserialize(&mut account.data.as_slice());
+      if group.is_ok_and(|g| g.members.iter().any(|m| m.token == *token)) {
+        return Ok(Some(*address));
+      }
+    }
+    Ok(None)
+  }
+
+  /// Pays the amount of the SPL token into the payable.
+  pub fn pay(
+    &self,
+    payer: &Pubkey,
+    payable: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+    payer_proof: Vec<[u8; 32]>,
+  ) -> Result<Instruction> {
+    let p = self.payment_accounts(payer, payable, mint)?;
+    let token_program = self.token_program(mint)?;
+    let chain_stats = pda::chain_stats();
+    Ok(ix(
+      chainbills::accounts::Pay {
+        user_payment: p.user_payment,
+        payable_payment: p.payable_payment,
+        payable_per_chain_payment_info: p.payable_per_chain_payment_info,
+        payable_per_chain_payments_page: p.payable_per_chain_payments_page,
+        payable_per_chain_payments_counter: p
+          .payable_per_chain_payments_counter,
+        user_activity: p.user_activity,
+        payable_activity: p.payable_activity,
+        payable: *payable,
+        allowed_payers: p.allowed_payers,
+        payable_escrow: p.payable_escrow,
+        escrow_state: p.escrow_state,
+        payable_receipts: p.payable_receipts,
+        receipt_mint: p.receipt_mint,
+        payer_receipt_token_account: p.payer_receipt_token_account,
+        receipt_token_program: p.receipt_token_program,
+        associated_token_program: p.associated_token_program,
+        token_group: self.token_group_for(payable, mint)?,
+        payer: p.payer,
+        chain_stats,
+        chain_shard: p.chain_shard,
+        config: pda::config(),
+        mint: *mint,
+        token_details: pda::token_details(mint),
+        token_details_shard: p.token_details_shard,
+        payer_token_account: ata(payer, mint, &token_program),
+        chain_token_account: ata(&chain_stats, mint, &token_program),
+        signer: *payer,
+        token_program,
+        system_program: system_program::ID,
+        event_authority: pda::event_authority(),
+        program: chainbills::ID,
+      },
+      chainbills::instruction::Pay {
+        amount,
+        payer_proof,
+      },
+    ))
+  }
+
+  /// Pays the amount of native SOL into the payable.
+  pub fn pay_native(
+    &self,
+    payer: &Pubkey,
+    payable: &Pubkey,
+    amount: u64,
+    payer_proof: Vec<[u8; 32]>,
+  ) -> Result<Instruction> {
+    let p = self.payment_accounts(payer, payable, &chainbills::ID)?;
+    Ok(ix(
+      chainbills::accounts::PayNative {
+        user_payment: p.user_payment,
+        payable_payment: p.payable_payment,
+        payable_per_chain_payment_info: p.payable_per_chain_payment_info,
+        payable_per_chain_payments_page: p.payable_per_chain_payments_page,
+        payable_per_chain_payments_counter: p
+          .payable_per_chain_payments_counter,
+        user_activity: p.user_activity,
+        payable_activity: p.payable_activity,
+        payable: *payable,
+        allowed_payers: p.allowed_payers,
+        payable_escrow: p.payable_escrow,
+        escrow_state: p.escrow_state,
+        payable_receipts: p.payable_receipts,
+        receipt_mint: p.receipt_mint,
+        payer_receipt_token_account: p.payer_receipt_token_account,
+        receipt_token_program: p.receipt_token_program,
+        associated_token_program: p.associated_token_program,
+        token_group: self.token_group_for(payable, &chainbills::ID)?,
+        payer: p.payer,
+        chain_stats: p.receipt_mint.map(|_| pda::chain_stats()),
+        chain_shard: p.chain_shard,
+        config: pda::config(),
+        token_details: pda::token_details(&chainbills::ID),
+        token_details_shard: p.token_details_shard,
+        native_vault: pda::native_vault(),
+        signer: *payer,
+        system_program: system_program::ID,
+        event_authority: pda::event_authority(),
+        program: chainbills::ID,
+      },
+      chainbills::instruction::PayNative {
+        amount,
+        payer_proof,
+      },
+    ))
+  }
+
+  /// Pays an intent that the payable's host signed. Send it right after
+  /// [`intent_signature_ix`] of the [`intent_signing_bytes`] of the same
+  /// args.
+  pub fn pay_with_intent(
+    &self,
+    payer: &Pubkey,
+    payable: &Pubkey,
+    mint: &Pubkey,
+    args: chainbills::instruction::PayWithIntent,
+  ) -> Result<Instruction> {
+    let p = self.payment_accounts(payer, payable, mint)?;
+    let token_program = self.token_program(mint)?;
+    let chain_stats = pda::chain_stats();
+    Ok(ix(
+      chainbills::accounts::PayWithIntent {
+        consumed_payment_intent: pda::consumed_payment_intent(
+          payable, args.nonce,
+        ),
+        user_payment: p.user_payment,
+        payable_payment: p.payable_payment,
+        payable_per_chain_payment_info: p.payable_per_chain_payment_info,
+        payable_per_chain_payments_page: p.payable_per_chain_payments_page,
+        payable_per_chain_payments_counter: p
+          .payable_per_chain_payments_counter,
+        user_activity: p.user_activity,
+        payable_activity: p.payable_activity,
+        payable: *payable,
+        allowed_payers: p.allowed_payers,
+        payable_escrow: p.payable_escrow,
+        escrow_state: p.escrow_state,
+        payable_receipts: p.payable_receipts,
+        receipt_mint: p.receipt_mint,
+        payer_receipt_token_account: p.payer_receipt_token_account,
+        receipt_token_program: p.receipt_token_program,
+        associated_token_program: p.associated_token_program,
+        payer: p.payer,
+        chain_stats,
+        chain_shard: p.chain_shard,
+        config: pda::config(),
+        mint: *mint,
+        token_details: pda::token_details(mint),
+        token_details_shard: p.token_details_shard,
+        payer_token_account: ata(payer, mint, &token_program),
+        chain_token_account: ata(&chain_stats, mint, &token_program),
+        signer: *payer,
+        token_program,
+        instructions: sysvar::instructions::ID,
+        system_program: system_program::ID,
+        event_authority: pda::event_authority(),
+        program: chainbills::ID,
+      },
+      args,
+    ))
+  }
+
+  /// Pays many payables in one SPL token at once. The payables must be
+  /// distinct, as each payment's accounts are derived from the counts of
+  /// its payable before the batch. The payments are indexed in the payer's
+  /// shard. Paged payments pass the ledger page of their payable's index
+  /// entry in place of the entry's own account. Payments into payables that mint
+  /// receipts also pass their receipt accounts.
+  pub fn pay_batch(
+    &self,
+    payer: &Pubkey,
+    mint: &Pubkey,
+    payments: Vec<(Pubkey, BatchPayment)>,
+  ) -> Result<Instruction> {
+    let chain_id = self.config()?.chain_id;
+    let shard = pda::shard_of(payer);
+    let user = self.user(payer)?;
+    let token_program = self.token_program(mint)?;
+
+    let mut remaining = vec![];
+    let mut mints_receipts = false;
+    for (i, (payable, payment)) in payments.iter().enumerate() {
+      let i = i as u64;
+      let payable_data = self.payable(payable)?;
+      let counter_address =
+        pda::payable_per_chain_payments_counter(payable, chain_id);
+      let counter: PayablePerChainPaymentsCounter =
+        self.account(counter_address)?;
+      let payable_payment =
+        pda::payable_payment(payable, payable_data.next_payment());
+      // The payable's index entry goes in its own account, or else in its
+      // page.
+      let per_chain_count = counter.next_payment();
+      let created = [
+        pda::user_payment(payer, user.next_payment() + i),
+        payable_payment,
+        if payment.paged {
+          pda::payable_per_chain_payments_page(
+            payable,
+            chain_id,
+            per_chain_count,
+          )
+        } else {
+          pda::payable_per_chain_payment_info(
+            payable,
+            chain_id,
+            per_chain_count,
+          )
+        },
+        pda::payment_activity(payer, user.next_activity() + i),
+        pda::payment_activity(payable, payable_data.next_activity()),
+      ];
+      remaining.push(AccountMeta::new(*payable, false));
+      remaining.push(AccountMeta::new(counter_address, false));
+      remaining.push(AccountMeta::new_readonly(
+        pda::allowed_payers(&payable.to_bytes()),
+        false,
+      ));
+      remaining.push(AccountMeta::new(pda::payable_escrow(payable), false));
+      remaining.push(AccountMeta::new_readonly(
+        pda::payable_receipts(&payable.to_bytes()),
+        false,
+      ));
+      remaining.extend(created.iter().map(|a| AccountMeta::new(*a, false)));
+      if self.active_escrow(payable)?.is_some() {
+        let escrow_state = pda::escrow_state(&payable_payment);
+        remaining.push(AccountMeta::new(escrow_state, false));
+      }
+      if self.receipts_enabled(payable)? {
+        let receipt_mint = pda::receipt_mint(&created[0]);
+        remaining.push(AccountMeta::new(receipt_mint, false));
+        remaining.push(AccountMeta::new(
+          ata(payer, &receipt_mint, &token_2022::ID),
+          false,
+        ));
+        mints_receipts = true;
+      }
+    }
+
+    let chain_stats = pda::chain_stats();
+    let mut instruction = ix(
+      chainbills::accounts::PayBatch {
+        payer: pda::user(payer),
+        chain_stats,
+        chain_shard: pda::chain_shard(shard),
+        config: pda::config(),
+        mint: *mint,
+        token_details: pda::token_details(mint),
+        token_details_shard: pda::token_details_shard(mint, shard),
+        payer_token_account: ata(payer, mint, &token_program),
+        chain_token_account: ata(&chain_stats, mint, &token_program),
+        signer: *payer,
+        token_program,
+        receipt_token_program: mints_receipts.then_some(token_2022::ID),
+        associated_token_program: mints_receipts
+          .then_some(associated_token::ID),
+        system_program: system_program::ID,
+        event_authority: pda::event_authority(),
+        program: chainbills::ID,
+      },
+      chainbills::instruction::PayBatch {
+        payments: payments.into_iter().map(|(_, payment)| payment).collect(),
+      },
+    );
+    instruction.accounts.extend(remaining);
+    Ok(instruction)
+  }
+}
@@ -0,0 +1,34 @@
+use super::ix;
+use crate::{client::Client, error::Result, pda, source::AccountSource};
+use anchor_lang::{
+  prelude::*, solana_program::instruction::Instruction, system_program,
+};
+
+impl<S: AccountSource> Client<S> {
+  /// Starts or stops minting receipts to the payers of payments into the
+  /// payable.
+  pub fn update_payable_receipts(
+    &self,
+    signer: &Pubkey,
+    payable: &Pubkey,
+    enabled: bool,
+  ) -> Result<Instruction> {
+    let host = self.payable(payable)?.host;
+    let activities = self.activity_accounts(&host, payable)?;
+    Ok(ix(
+      chainbills::accounts::UpdatePayableReceipts {
+        payable: *payable,
+        activity: activities.activity,
+        user_activity_info: activities.user_activity_info,
+        payable_activity_info: activities.payable_activity_info,
+        payable_receipts: pda::payable_receipts(&payable.to_bytes()),
+        host: activities.user,
+        operator: self.operator(signer, payable, &host)?,
+        chain_shard: activities.chain_shard,
+        signer: *signer,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::UpdatePayableReceipts { enabled },
+    ))
+  }
+}
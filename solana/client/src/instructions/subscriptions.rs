@@ -0,0 +1,115 @@
+use super::{ata, ix};
+use crate::{client::Client, error::Result, pda, source::AccountSource};
+use anchor_lang::{
+  prelude::*, solana_program::instruction::Instruction, system_program,
+};
+use chainbills::state::*;
+
+impl<S: AccountSource> Client<S> {
+  /// Subscribes the payer to the payable in the mint. The payer must also
+  /// approve the ChainStats account as delegate on their token account.
+  pub fn create_subscription(
+    &self,
+    payer: &Pubkey,
+    payable: &Pubkey,
+    mint: &Pubkey,
+    args: chainbills::instruction::CreateSubscription,
+  ) -> Result<Instruction> {
+    let activities = self.activity_accounts(payer, payable)?;
+    Ok(ix(
+      chainbills::accounts::CreateSubscription {
+        subscription: pda::subscription(payable, payer),
+        payable: *payable,
+        allowed_payers: pda::allowed_payers(&payable.to_bytes()),
+        activity: activities.activity,
+        user_activity_info: activities.user_activity_info,
+        payable_activity_info: activities.payable_activity_info,
+        payer: activities.user,
+        chain_shard: activities.chain_shard,
+        mint: *mint,
+        token_details: pda::token_details(mint),
+        signer: *payer,
+        system_program: system_program::ID,
+      },
+      args,
+    ))
+  }
+
+  /// Collects the due payment of the payer's subscription to the payable.
+  /// Anyone can sign as the collector.
+  pub fn collect_subscription(
+    &self,
+    collector: &Pubkey,
+    payable: &Pubkey,
+    payer: &Pubkey,
+  ) -> Result<Instruction> {
+    let subscription = pda::subscription(payable, payer);
+    let mint = self.account::<Subscription>(subscription)?.token;
+    let p = self.payment_accounts(payer, payable, &mint)?;
+    let token_program = self.token_program(&mint)?;
+    let chain_stats = pda::chain_stats();
+    Ok(ix(
+      chainbills::accounts::CollectSubscription {
+        subscription,
+        user_payment: p.user_payment,
+        payable_payment: p.payable_payment,
+        payable_per_chain_payment_info: p.payable_per_chain_payment_info,
+        payable_per_chain_payments_page: p.payable_per_chain_payments_page,
+        payable_per_chain_payments_counter: p
+          .payable_per_chain_payments_counter,
+        user_activity: p.user_activity,
+        payable_activity: p.payable_activity,
+        payable: *payable,
+        payable_escrow: p.payable_escrow,
+        escrow_state: p.escrow_state,
+        payer: p.payer,
+        chain_stats,
+        chain_shard: p.chain_shard,
+        config: pda::config(),
+        mint,
+        token_details: pda::token_details(&mint),
+        token_details_shard: p.token_details_shard,
+        payer_token_account: ata(payer, &mint, &token_program),
+        chain_token_account: ata(&chain_stats, &mint, &token_program),
+        signer: *collector,
+        token_program,
+        system_program: system_program::ID,
+        event_authority: pda::event_authority(),
+        program: chainbills::ID,
+      },
+      chainbills::instruction::CollectSubscription {},
+    ))
+  }
+
+  /// Cancels the payer's subscription to the payable. Signed by the payer,
+  /// the host, or an operator with the manage role.
+  pub fn cancel_subscription(
+    &self,
+    signer: &Pubkey,
+    payable: &Pubkey,
+    payer: &Pubkey,
+  ) -> Result<Instruction> {
+    let host = self.payable(payable)?.host;
+    let operator = if signer == payer {
+      None
+    } else {
+      self.operator(signer, payable, &host)?
+    };
+    let activities = self.activity_accounts(payer, payable)?;
+    Ok(ix(
+      chainbills::accounts::CancelSubscription {
+        subscription: pda::subscription(payable, payer),
+        payable: *payable,
+        operator,
+        activity: activities.activity,
+        user_activity_info: activities.user_activity_info,
+        payable_activity_info: activities.payable_activity_info,
+        payer: activities.user,
+        chain_shard: activities.chain_shard,
+        signer: *signer,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::CancelSubscription {},
+    ))
+  }
+}
@@ -0,0 +1,25 @@
+use super::ix;
+use crate::{client::Client, error::Result, pda, source::AccountSource};
+use anchor_lang::{
+  prelude::*, solana_program::instruction::Instruction, system_program,
+};
+
+impl<S: AccountSource> Client<S> {
+  /// Initializes the User of the wallet, which signs.
+  pub fn initialize_user(&self, wallet: &Pubkey) -> Result<Instruction> {
+    let shard = pda::shard_of(wallet);
+    let chain_shard = self.chain_shard(shard)?;
+    Ok(ix(
+      chainbills::accounts::InitializeUser {
+        user: pda::user(wallet),
+        chain_user_address: pda::chain_user_address(chain_shard.next_user()),
+        activity: pda::activity(chain_shard.next_activity()),
+        user_activity_info: pda::activity_info(wallet, 1),
+        chain_shard: pda::chain_shard(shard),
+        signer: *wallet,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::InitializeUser {},
+    ))
+  }
+}
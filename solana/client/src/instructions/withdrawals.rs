@@ -0,0 +1,187 @@
+use super::{ata, ix, ActivityAccounts};
+use crate::{
+  client::Client,
+  error::{ClientError, Result},
+  pda,
+  source::AccountSource,
+};
+use anchor_lang::{
+  prelude::*, solana_program::instruction::Instruction, system_program,
+};
+use chainbills::state::*;
+use wormhole_cctp_solana::cctp::{
+  message_transmitter_program, token_messenger_minter_program,
+};
+
+/// The accounts that the next withdrawal from a payable initializes.
+struct WithdrawalAccounts {
+  withdrawal: Pubkey,
+  chain_withdrawal_id: Pubkey,
+  payable_withdrawal_info: Pubkey,
+  activities: ActivityAccounts,
+  host_wallet: Pubkey,
+  fee_collector: Pubkey,
+}
+
+impl<S: AccountSource> Client<S> {
+  fn withdrawal_accounts(
+    &self,
+    payable: &Pubkey,
+  ) -> Result<WithdrawalAccounts> {
+    let fee_collector = self.config()?.chainbills_fee_collector;
+    let chain_shard = self.chain_shard(pda::shard_of(payable))?;
+    let payable_data = self.payable(payable)?;
+    let host = self.user(&payable_data.host)?;
+    Ok(WithdrawalAccounts {
+      withdrawal: pda::withdrawal(&payable_data.host, host.next_withdrawal()),
+      chain_withdrawal_id: pda::chain_withdrawal_id(
+        chain_shard.next_withdrawal(),
+      ),
+      payable_withdrawal_info: pda::payable_withdrawal_info(
+        payable,
+        payable_data.next_withdrawal(),
+      ),
+      activities: self.activity_accounts(&payable_data.host, payable)?,
+      host_wallet: payable_data.host,
+      fee_collector,
+    })
+  }
+
+  /// Withdraws the amount of the SPL token from the payable to its host.
+  /// Signed by the host or an operator with the withdraw role.
+  pub fn withdraw(
+    &self,
+    signer: &Pubkey,
+    payable: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+  ) -> Result<Instruction> {
+    let w = self.withdrawal_accounts(payable)?;
+    let token_program = self.token_program(mint)?;
+    let chain_stats = pda::chain_stats();
+    Ok(ix(
+      chainbills::accounts::Withdraw {
+        withdrawal: w.withdrawal,
+        chain_withdrawal_id: w.chain_withdrawal_id,
+        payable_withdrawal_info: w.payable_withdrawal_info,
+        activity: w.activities.activity,
+        user_activity_info: w.activities.user_activity_info,
+        payable_activity_info: w.activities.payable_activity_info,
+        payable: *payable,
+        host: w.activities.user,
+        host_wallet: w.host_wallet,
+        operator: self.operator(signer, payable, &w.host_wallet)?,
+        chain_stats,
+        chain_shard: w.activities.chain_shard,
+        config: pda::config(),
+        mint: *mint,
+        token_details: pda::token_details(mint),
+        host_token_account: ata(&w.host_wallet, mint, &token_program),
+        chain_token_account: ata(&chain_stats, mint, &token_program),
+        fees_token_account: ata(&w.fee_collector, mint, &token_program),
+        fee_collector: w.fee_collector,
+        signer: *signer,
+        token_program,
+        system_program: system_program::ID,
+        event_authority: pda::event_authority(),
+        program: chainbills::ID,
+      },
+      chainbills::instruction::Withdraw { amount },
+    ))
+  }
+
+  /// Withdraws the amount of native SOL from the payable to its host.
+  /// Signed by the host or an operator with the withdraw role.
+  pub fn withdraw_native(
+    &self,
+    signer: &Pubkey,
+    payable: &Pubkey,
+    amount: u64,
+  ) -> Result<Instruction> {
+    let w = self.withdrawal_accounts(payable)?;
+    Ok(ix(
+      chainbills::accounts::WithdrawNative {
+        withdrawal: w.withdrawal,
+        chain_withdrawal_id: w.chain_withdrawal_id,
+        payable_withdrawal_info: w.payable_withdrawal_info,
+        activity: w.activities.activity,
+        user_activity_info: w.activities.user_activity_info,
+        payable_activity_info: w.activities.payable_activity_info,
+        payable: *payable,
+        host: w.activities.user,
+        host_wallet: w.host_wallet,
+        operator: self.operator(signer, payable, &w.host_wallet)?,
+        chain_shard: w.activities.chain_shard,
+        config: pda::config(),
+        fee_collector: w.fee_collector,
+        token_details: pda::token_details(&chainbills::ID),
+        native_vault: pda::native_vault(),
+        signer: *signer,
+        system_program: system_program::ID,
+        event_authority: pda::event_authority(),
+        program: chainbills::ID,
+      },
+      chainbills::instruction::WithdrawNative { amount },
+    ))
+  }
+
+  /// Withdraws the amount of the USDC mint from the payable to the address
+  /// on the destination chain through CCTP. Signed by the host and by the
+  /// fresh cctp_message keypair.
+  pub fn withdraw_cross_chain(
+    &self,
+    host: &Pubkey,
+    payable: &Pubkey,
+    mint: &Pubkey,
+    cctp_message: &Pubkey,
+    args: chainbills::instruction::WithdrawCrossChain,
+  ) -> Result<Instruction> {
+    let w = self.withdrawal_accounts(payable)?;
+    let dest_cb_chain = pda::cb_chain(&args.dest_chain_id);
+    let dest: CbChain = self.account(dest_cb_chain)?;
+    if !dest.has_circle_domain {
+      return Err(ClientError::InvalidAccount(dest_cb_chain));
+    }
+    let token_program = self.token_program(mint)?;
+    let chain_stats = pda::chain_stats();
+    Ok(ix(
+      chainbills::accounts::WithdrawCrossChain {
+        withdrawal: w.withdrawal,
+        chain_withdrawal_id: w.chain_withdrawal_id,
+        payable_withdrawal_info: w.payable_withdrawal_info,
+        activity: w.activities.activity,
+        user_activity_info: w.activities.user_activity_info,
+        payable_activity_info: w.activities.payable_activity_info,
+        payable: *payable,
+        host: w.activities.user,
+        chain_stats,
+        chain_shard: w.activities.chain_shard,
+        config: pda::config(),
+        dest_cb_chain,
+        mint: *mint,
+        token_details: pda::token_details(mint),
+        chain_token_account: ata(&chain_stats, mint, &token_program),
+        fees_token_account: ata(&w.fee_collector, mint, &token_program),
+        fee_collector: w.fee_collector,
+        signer: *host,
+        cctp_message: *cctp_message,
+        token_messenger_minter_sender_authority: pda::cctp_sender_authority(),
+        message_transmitter_config: pda::cctp_message_transmitter_config(),
+        token_messenger: pda::cctp_token_messenger(),
+        remote_token_messenger: pda::cctp_remote_token_messenger(
+          dest.circle_domain,
+        ),
+        token_minter: pda::cctp_token_minter(),
+        local_token: pda::cctp_local_token(mint),
+        token_messenger_minter_event_authority: pda::cctp_event_authority(),
+        message_transmitter_program: message_transmitter_program::ID,
+        token_messenger_minter_program: token_messenger_minter_program::ID,
+        token_program,
+        system_program: system_program::ID,
+        event_authority: pda::event_authority(),
+        program: chainbills::ID,
+      },
+      args,
+    ))
+  }
+}
@@ -0,0 +1,32 @@
+//! Rust client for the Chainbills Solana program.
+//!
+//! Built on the program's own `state` types, it derives the program's
+//! addresses ([`pda`]), reads and decodes its accounts ([`Client`]), builds
+//! its instructions with complete account lists ([`instructions`]), and
+//! pages through the histories of the chain, users, and payables
+//! ([`history`]).
+//!
+//! ```no_run
+//! use chainbills_client::{Client, pda};
+//! use solana_client::rpc_client::RpcClient;
+//! # use anchor_lang::prelude::Pubkey;
+//! # let (payer, host, mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+//!
+//! let client = Client::new(RpcClient::new("https://api.devnet.solana.com"));
+//! let payable = pda::payable(&host, 1);
+//! let pay = client.pay(&payer, &payable, &mint, 1_000_000, vec![])?;
+//! let payments = client.payable_payments(&payable, 0, 20)?;
+//! # Ok::<(), chainbills_client::ClientError>(())
+//! ```
+
+pub mod client;
+pub mod error;
+pub mod history;
+pub mod instructions;
+pub mod pda;
+pub mod source;
+
+pub use client::Client;
+pub use error::{ClientError, Result};
+pub use history::Page;
+pub use source::AccountSource;
@@ -0,0 +1,433 @@
+//! Addresses of the program's accounts, derived from the same seeds that the
+//! program's contexts check.
+//!
+//! Counts are 1-based, like the program's `next_*` helpers. The address of
+//! the nth payable of a host for example is `payable(host, n)`.
+
+use anchor_lang::prelude::*;
+use chainbills::state::*;
+use wormhole_anchor_sdk::wormhole;
+use wormhole_cctp_solana::cctp::{
+  message_transmitter_program, token_messenger_minter_program,
+};
+
+/// Derives an address of this program.
+pub fn pda(seeds: &[&[u8]]) -> Pubkey {
+  Pubkey::find_program_address(seeds, &chainbills::ID).0
+}
+
+/// Derives an address of the Wormhole Core Bridge program.
+pub fn wormhole_pda(seeds: &[&[u8]]) -> Pubkey {
+  Pubkey::find_program_address(seeds, &wormhole::program::ID).0
+}
+
+pub fn chain_stats() -> Pubkey {
+  pda(&[ChainStats::SEED_PREFIX])
+}
+
+/// One of the shards that chain-level entities are indexed in.
+pub fn chain_shard(index: u8) -> Pubkey {
+  pda(&[ChainStatsShard::SEED_PREFIX, &[index]])
+}
+
+/// The shard that the instruction builders index entities in when the
+/// instruction is about the key (a payable, or else the signer). Spreading
+/// keys over the shards lets transactions about different payables run in
+/// parallel.
+pub fn shard_of(key: &Pubkey) -> u8 {
+  key.as_ref()[0] % ChainStatsShard::COUNT
+}
+
+/// The token's shard of payment totals that goes with the ChainStatsShard
+/// of the index.
+pub fn token_details_shard(token: &Pubkey, index: u8) -> Pubkey {
+  pda(&[TokenDetailsShard::SEED_PREFIX, token.as_ref(), &[index]])
+}
+
+pub fn config() -> Pubkey {
+  pda(&[Config::SEED_PREFIX])
+}
+
+/// Holds the native SOL paid into payables.
+pub fn native_vault() -> Pubkey {
+  pda(&[SEED_PREFIX_NATIVE_VAULT])
+}
+
+/// Signs the program's `emit_cpi!` self-invocations.
+pub fn event_authority() -> Pubkey {
+  pda(&[b"__event_authority"])
+}
+
+/// The TokenDetails of a mint, or of native SOL if the token is this
+/// program's ID.
+pub fn token_details(token: &Pubkey) -> Pubkey {
+  pda(&[TokenDetails::SEED_PREFIX, token.as_ref()])
+}
+
+pub fn token_group(name: &str) -> Pubkey {
+  pda(&[TokenGroup::SEED_PREFIX, name.as_bytes()])
+}
+
+/// The token's address on the chain with the Wormhole Chain ID.
+pub fn token_foreign_chain(chain: u16) -> Pubkey {
+  pda(&[TokenForeignChain::SEED_PREFIX, &chain.to_le_bytes()])
+}
+
+pub fn user(wallet: &Pubkey) -> Pubkey {
+  pda(&[wallet.as_ref()])
+}
+
+/// The wallet of the nth user on this chain.
+pub fn chain_user_address(count: u64) -> Pubkey {
+  pda(&[ChainUserAddress::SEED_PREFIX, &count.to_le_bytes()])
+}
+
+/// The nth payable that the host created.
+pub fn payable(host: &Pubkey, count: u64) -> Pubkey {
+  pda(&[host.as_ref(), Payable::SEED_PREFIX, &count.to_le_bytes()])
+}
+
+/// The ID of the nth payable created on this chain.
+pub fn chain_payable_id(count: u64) -> Pubkey {
+  pda(&[ChainPayableId::SEED_PREFIX, &count.to_le_bytes()])
+}
+
+/// The ID of the payable that the wallet received as its nth payable
+/// through a transfer.
+pub fn user_payable_info(wallet: &Pubkey, count: u64) -> Pubkey {
+  pda(&[
+    wallet.as_ref(),
+    UserPayableInfo::SEED_PREFIX,
+    &count.to_le_bytes(),
+  ])
+}
+
+pub fn pending_payable_host(payable: &Pubkey) -> Pubkey {
+  pda(&[payable.as_ref(), PendingPayableHost::SEED_PREFIX])
+}
+
+pub fn payable_operator(payable: &Pubkey, operator: &Pubkey) -> Pubkey {
+  pda(&[
+    payable.as_ref(),
+    PayableOperator::SEED_PREFIX,
+    operator.as_ref(),
+  ])
+}
+
+/// The allowed payers of a payable, local or foreign.
+pub fn allowed_payers(payable_id: &[u8; 32]) -> Pubkey {
+  pda(&[payable_id.as_ref(), PayableAllowedPayers::SEED_PREFIX])
+}
+
+pub fn payable_escrow(payable: &Pubkey) -> Pubkey {
+  pda(&[payable.as_ref(), PayableEscrow::SEED_PREFIX])
+}
+
+/// The escrow of the payment with the PayablePayment address.
+pub fn escrow_state(payable_payment: &Pubkey) -> Pubkey {
+  pda(&[payable_payment.as_ref(), EscrowState::SEED_PREFIX])
+}
+
+pub fn payable_receipts(payable_id: &[u8; 32]) -> Pubkey {
+  pda(&[payable_id.as_ref(), PayableReceipts::SEED_PREFIX])
+}
+
+/// The mint of the receipt of the payment with the UserPayment address.
+pub fn receipt_mint(user_payment: &Pubkey) -> Pubkey {
+  pda(&[user_payment.as_ref(), Receipt::MINT_SEED_PREFIX])
+}
+
+pub fn subscription(payable: &Pubkey, payer: &Pubkey) -> Pubkey {
+  pda(&[payable.as_ref(), Subscription::SEED_PREFIX, payer.as_ref()])
+}
+
+pub fn consumed_payment_intent(payable: &Pubkey, nonce: u64) -> Pubkey {
+  pda(&[
+    payable.as_ref(),
+    ConsumedPaymentIntent::SEED_PREFIX,
+    &nonce.to_le_bytes(),
+  ])
+}
+
+/// The payable's counter of payments from the chain with the Wormhole Chain
+/// ID.
+pub fn payable_per_chain_payments_counter(
+  payable: &Pubkey,
+  chain_id: u16,
+) -> Pubkey {
+  pda(&[payable.as_ref(), &chain_id.to_le_bytes()])
+}
+
+/// The payable count of the nth payment into the payable from the chain with
+/// the Wormhole Chain ID.
+pub fn payable_per_chain_payment_info(
+  payable: &Pubkey,
+  chain_id: u16,
+  count: u64,
+) -> Pubkey {
+  pda(&[
+    payable.as_ref(),
+    &chain_id.to_le_bytes(),
+    &count.to_le_bytes(),
+  ])
+}
+
+/// The nth payment that the payer made.
+pub fn user_payment(payer: &Pubkey, count: u64) -> Pubkey {
+  pda(&[
+    payer.as_ref(),
+    UserPayment::SEED_PREFIX,
+    &count.to_le_bytes(),
+  ])
+}
+
+/// The nth payment into the payable.
+pub fn payable_payment(payable: &Pubkey, count: u64) -> Pubkey {
+  pda(&[
+    payable.as_ref(),
+    PayablePayment::SEED_PREFIX,
+    &count.to_le_bytes(),
+  ])
+}
+
+/// The ID of the nth UserPayment made on this chain.
+pub fn chain_user_payment_id(count: u64) -> Pubkey {
+  pda(&[ChainUserPaymentId::SEED_PREFIX, &count.to_le_bytes()])
+}
+
+/// The ID of the nth PayablePayment made on this chain.
+pub fn chain_payable_payment_id(count: u64) -> Pubkey {
+  pda(&[ChainPayablePaymentId::SEED_PREFIX, &count.to_le_bytes()])
+}
+
+/// The ledger page that holds the entry of the count, in place of the
+/// entry's own account whose seeds start with the prefix.
+fn ledger(prefix: &[&[u8]], count: u64) -> Pubkey {
+  let page = ledger_page(count).to_le_bytes();
+  pda(&[prefix, &[SEED_PREFIX_LEDGER_PAGE, &page]].concat())
+}
+
+/// The ledger page that holds the ID of the nth UserPayment made on this
+/// chain, if it was paged.
+pub fn chain_user_payments_page(count: u64) -> Pubkey {
+  ledger(&[ChainUserPaymentId::SEED_PREFIX], count)
+}
+
+/// The ledger page that holds the ID of the nth PayablePayment made on this
+/// chain, if it was paged.
+pub fn chain_payable_payments_page(count: u64) -> Pubkey {
+  ledger(&[ChainPayablePaymentId::SEED_PREFIX], count)
+}
+
+/// The ledger page that holds the payable count of the nth payment into the
+/// payable from the chain with the Wormhole Chain ID, if it was paged.
+pub fn payable_per_chain_payments_page(
+  payable: &Pubkey,
+  chain_id: u16,
+  count: u64,
+) -> Pubkey {
+  ledger(&[payable.as_ref(), &chain_id.to_le_bytes()], count)
+}
+
+/// The nth withdrawal that the host made.
+pub fn withdrawal(host: &Pubkey, count: u64) -> Pubkey {
+  pda(&[host.as_ref(), Withdrawal::SEED_PREFIX, &count.to_le_bytes()])
+}
+
+/// The ID of the nth withdrawal made on this chain.
+pub fn chain_withdrawal_id(count: u64) -> Pubkey {
+  pda(&[ChainWithdrawalId::SEED_PREFIX, &count.to_le_bytes()])
+}
+
+/// The host count of the nth withdrawal from the payable.
+pub fn payable_withdrawal_info(payable: &Pubkey, count: u64) -> Pubkey {
+  pda(&[
+    payable.as_ref(),
+    PayableWithdrawalInfo::SEED_PREFIX,
+    &count.to_le_bytes(),
+  ])
+}
+
+/// The ledger page that holds the chain count of the nth activity of a User
+/// (by wallet) or Payable, if it was paged.
+pub fn activities_page(owner: &Pubkey, count: u64) -> Pubkey {
+  ledger(&[owner.as_ref(), ActivityRecord::SEED_PREFIX], count)
+}
+
+/// The nth activity recorded on this chain.
+pub fn activity(count: u64) -> Pubkey {
+  pda(&[ActivityRecord::SEED_PREFIX, &count.to_le_bytes()])
+}
+
+/// The nth activity of a User (by wallet) or Payable, if a payment recorded
+/// it. Payments derive their activities from their owners' counts.
+pub fn payment_activity(owner: &Pubkey, count: u64) -> Pubkey {
+  pda(&[
+    ActivityRecord::SEED_PREFIX,
+    owner.as_ref(),
+    &count.to_le_bytes(),
+  ])
+}
+
+/// The chain count of the nth activity of a User (by wallet) or Payable.
+pub fn activity_info(owner: &Pubkey, count: u64) -> Pubkey {
+  pda(&[
+    owner.as_ref(),
+    ActivityRecord::SEED_PREFIX,
+    &count.to_le_bytes(),
+  ])
+}
+
+pub fn cb_chain(cb_chain_id: &[u8; 32]) -> Pubkey {
+  pda(&[CbChain::SEED_PREFIX, cb_chain_id])
+}
+
+pub fn wormhole_chain_cb_chain_id(wormhole_chain_id: u16) -> Pubkey {
+  pda(&[
+    WormholeChainCbChainId::SEED_PREFIX,
+    &wormhole_chain_id.to_le_bytes(),
+  ])
+}
+
+pub fn circle_domain_cb_chain_id(circle_domain: u32) -> Pubkey {
+  pda(&[
+    CircleDomainCbChainId::SEED_PREFIX,
+    &circle_domain.to_le_bytes(),
+  ])
+}
+
+/// The trusted Chainbills contract of the chain with the Wormhole Chain ID.
+pub fn registered_foreign_contract(chain_id: u16) -> Pubkey {
+  pda(&[
+    RegisteredForeignContract::SEED_PREFIX,
+    &chain_id.to_le_bytes(),
+  ])
+}
+
+/// The PayableForeign of the payable with the ID on another chain.
+pub fn foreign_payable(payable_id: &[u8; 32]) -> Pubkey {
+  pda(&[payable_id.as_ref()])
+}
+
+/// The ID of the nth foreign payable recorded on this chain.
+pub fn chain_foreign_payable_id(count: u64) -> Pubkey {
+  pda(&[ChainForeignPayableId::SEED_PREFIX, &count.to_le_bytes()])
+}
+
+/// Marks the Wormhole message of the emitter chain with the sequence as
+/// consumed.
+pub fn consumed_wormhole_message(chain_id: u16, sequence: u64) -> Pubkey {
+  pda(&[
+    ConsumedWormholeMessage::SEED_PREFIX,
+    &chain_id.to_le_bytes(),
+    &sequence.to_le_bytes(),
+  ])
+}
+
+/// The emitter chain and sequence of the nth Wormhole message consumed on
+/// this chain.
+pub fn chain_consumed_wormhole_message_id(count: u64) -> Pubkey {
+  pda(&[
+    ChainConsumedWormholeMessageId::SEED_PREFIX,
+    &count.to_le_bytes(),
+  ])
+}
+
+pub fn per_chain_consumed_wormhole_messages_counter(chain_id: u16) -> Pubkey {
+  pda(&[
+    PerChainConsumedWormholeMessagesCounter::SEED_PREFIX,
+    &chain_id.to_le_bytes(),
+  ])
+}
+
+/// The program's Wormhole emitter.
+pub fn wormhole_emitter() -> Pubkey {
+  pda(&[wormhole::SEED_PREFIX_EMITTER])
+}
+
+/// The account of the Wormhole message that the program publishes with the
+/// sequence.
+pub fn wormhole_message(sequence: u64) -> Pubkey {
+  pda(&[SEED_PREFIX_SENT, &sequence.to_le_bytes()])
+}
+
+pub fn wormhole_bridge() -> Pubkey {
+  wormhole_pda(&[wormhole::BridgeData::SEED_PREFIX])
+}
+
+pub fn wormhole_fee_collector() -> Pubkey {
+  wormhole_pda(&[wormhole::FeeCollector::SEED_PREFIX])
+}
+
+/// The Wormhole sequence tracker of the program's emitter.
+pub fn wormhole_sequence() -> Pubkey {
+  wormhole_pda(&[
+    wormhole::SequenceTracker::SEED_PREFIX,
+    wormhole_emitter().as_ref(),
+  ])
+}
+
+/// The VAA with the hash that the Wormhole program verified and posted.
+pub fn posted_vaa(vaa_hash: &[u8; 32]) -> Pubkey {
+  wormhole_pda(&[wormhole::SEED_PREFIX_POSTED_VAA, vaa_hash])
+}
+
+/// Derives an address of the CCTP Token Messenger Minter program.
+fn token_messenger_minter_pda(seeds: &[&[u8]]) -> Pubkey {
+  Pubkey::find_program_address(seeds, &token_messenger_minter_program::ID).0
+}
+
+pub fn cctp_sender_authority() -> Pubkey {
+  token_messenger_minter_pda(&[b"sender_authority"])
+}
+
+pub fn cctp_message_transmitter_config() -> Pubkey {
+  Pubkey::find_program_address(
+    &[b"message_transmitter"],
+    &message_transmitter_program::ID,
+  )
+  .0
+}
+
+pub fn cctp_token_messenger() -> Pubkey {
+  token_messenger_minter_pda(&[b"token_messenger"])
+}
+
+/// The Token Messenger of the chain with the Circle domain.
+pub fn cctp_remote_token_messenger(circle_domain: u32) -> Pubkey {
+  token_messenger_minter_pda(&[
+    b"remote_token_messenger",
+    circle_domain.to_string().as_bytes(),
+  ])
+}
+
+pub fn cctp_token_minter() -> Pubkey {
+  token_messenger_minter_pda(&[b"token_minter"])
+}
+
+pub fn cctp_local_token(mint: &Pubkey) -> Pubkey {
+  token_messenger_minter_pda(&[b"local_token", mint.as_ref()])
+}
+
+pub fn cctp_event_authority() -> Pubkey {
+  token_messenger_minter_pda(&[b"__event_authority"])
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_counts_are_little_endian() {
+    let host = Pubkey::new_unique();
+    assert_eq!(
+      payable(&host, 1),
+      pda(&[host.as_ref(), b"payable", &[1, 0, 0, 0, 0, 0, 0, 0]])
+    );
+    assert_eq!(
+      payable_per_chain_payments_counter(&host, 1),
+      pda(&[host.as_ref(), &[1, 0]])
+    );
+    assert_ne!(payable(&host, 1), payable(&host, 2));
+  }
+}
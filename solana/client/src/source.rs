@@ -0,0 +1,76 @@
+use crate::error::Result;
+use anchor_lang::prelude::Pubkey;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+  rpc_client::RpcClient,
+  rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+  rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::account::Account;
+use std::collections::HashMap;
+
+/// Where the client reads accounts from.
+pub trait AccountSource {
+  /// The accounts at the addresses, in order. `None` where there is no
+  /// account.
+  fn get_accounts(&self, addresses: &[Pubkey]) -> Result<Vec<Option<Account>>>;
+
+  /// The accounts that the program owns whose data starts with the prefix,
+  /// like an account type's discriminator, in no particular order.
+  fn get_program_accounts(
+    &self,
+    program: &Pubkey,
+    prefix: &[u8],
+  ) -> Result<Vec<(Pubkey, Account)>>;
+}
+
+impl AccountSource for RpcClient {
+  fn get_accounts(&self, addresses: &[Pubkey]) -> Result<Vec<Option<Account>>> {
+    // RPC nodes cap the addresses of getMultipleAccounts at 100.
+    let mut accounts = Vec::with_capacity(addresses.len());
+    for chunk in addresses.chunks(100) {
+      accounts.extend(self.get_multiple_accounts(chunk)?);
+    }
+    Ok(accounts)
+  }
+
+  fn get_program_accounts(
+    &self,
+    program: &Pubkey,
+    prefix: &[u8],
+  ) -> Result<Vec<(Pubkey, Account)>> {
+    let config = RpcProgramAccountsConfig {
+      filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+        0,
+        prefix.to_vec(),
+      ))]),
+      account_config: RpcAccountInfoConfig {
+        encoding: Some(UiAccountEncoding::Base64),
+        ..Default::default()
+      },
+      ..Default::default()
+    };
+    Ok(self.get_program_accounts_with_config(program, config)?)
+  }
+}
+
+/// Accounts held in memory, such as snapshots or test fixtures.
+impl AccountSource for HashMap<Pubkey, Account> {
+  fn get_accounts(&self, addresses: &[Pubkey]) -> Result<Vec<Option<Account>>> {
+    Ok(addresses.iter().map(|a| self.get(a).cloned()).collect())
+  }
+
+  fn get_program_accounts(
+    &self,
+    program: &Pubkey,
+    prefix: &[u8],
+  ) -> Result<Vec<(Pubkey, Account)>> {
+    Ok(
+      self
+        .iter()
+        .filter(|(_, a)| a.owner == *program && a.data.starts_with(prefix))
+        .map(|(address, a)| (*address, a.clone()))
+        .collect(),
+    )
+  }
+}
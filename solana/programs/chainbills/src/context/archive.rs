@@ -0,0 +1,313 @@
+use crate::{error::ChainbillsError, state::*};
+use anchor_lang::prelude::*;
+
+#[event_cpi]
+#[derive(Accounts)]
+/// Context used to close the account of a closed and emptied payable.
+pub struct ArchivePayable<'info> {
+  #[account(
+    mut,
+    constraint = payable.host == *signer.key @ ChainbillsError::NotYourPayable,
+    close = signer
+  )]
+  /// The payable to close. Its rent goes back to the host.
+  pub payable: Box<Account<'info, Payable>>,
+
+  #[account(
+        mut,
+        seeds = [
+            payable.key().as_ref(),
+            &config.load()?.chain_id.to_le_bytes()[..],
+        ],
+        bump,
+        close = signer
+    )]
+  /// The payable's counter of payments from this chain. Created alongside
+  /// the payable, so it goes with it.
+  pub payable_per_chain_payments_counter:
+    Box<Account<'info, PayablePerChainPaymentsCounter>>,
+
+  #[account(mut, seeds = [payable.key().as_ref(), PayableEscrow::SEED_PREFIX], bump)]
+  /// CHECK: The payable's escrow settings, if it ever had any. Holds the
+  /// count of its escrowed payments that are still held. Closed if it
+  /// exists.
+  pub payable_escrow: UncheckedAccount<'info>,
+
+  #[account(mut, seeds = [payable.key().as_ref(), PayableAllowedPayers::SEED_PREFIX], bump)]
+  /// CHECK: The payable's allowed payers, if its host ever restricted its
+  /// payers. Closed if it exists.
+  pub allowed_payers: UncheckedAccount<'info>,
+
+  #[account(mut, seeds = [payable.key().as_ref(), PayableReceipts::SEED_PREFIX], bump)]
+  /// CHECK: The payable's receipts settings, if it ever had any. Closed if
+  /// it exists.
+  pub payable_receipts: UncheckedAccount<'info>,
+
+  #[account(mut, seeds = [payable.key().as_ref(), PendingPayableHost::SEED_PREFIX], bump)]
+  /// CHECK: The payable's pending transfer to another host, if any. Closed
+  /// if it exists.
+  pub pending_payable_host: UncheckedAccount<'info>,
+
+  #[account(seeds = [Config::SEED_PREFIX], bump)]
+  pub config: AccountLoader<'info, Config>,
+
+  #[account(mut)]
+  pub signer: Signer<'info>,
+  // Remaining accounts are the payable's PayableOperators to close.
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+/// Context used to close a payable's receipt of a payment.
+pub struct ArchivePayablePayment<'info> {
+  #[account(constraint = payable.host == *signer.key @ ChainbillsError::NotYourPayable)]
+  pub payable: Box<Account<'info, Payable>>,
+
+  #[account(
+    mut,
+    constraint = payable_payment.payable_id == payable.key() @ ChainbillsError::NotYourPayment,
+    close = rent_payer
+  )]
+  /// The payment receipt to close. Its rent goes back to whoever paid it.
+  pub payable_payment: Box<Account<'info, PayablePayment>>,
+
+  #[account(
+    mut,
+    constraint = chain_payable_payment_id.payable_payment_id == payable_payment.key() @ ChainbillsError::NotYourPayment,
+    close = rent_payer
+  )]
+  /// The chain-level reference to the payment receipt. Not passed when the
+  /// reference was recorded in a ledger page instead, or when the payment
+  /// has none, as current payments don't.
+  pub chain_payable_payment_id:
+    Option<Box<Account<'info, ChainPayablePaymentId>>>,
+
+  #[account(
+    mut,
+    seeds = [ChainPayablePaymentId::SEED_PREFIX, SEED_PREFIX_LEDGER_PAGE, &ledger_page(payable_payment.chain_count).to_le_bytes()[..]],
+    bump
+  )]
+  /// The page of the chain's ledger of payable payments that records the
+  /// reference, in place of chain_payable_payment_id. Its entry is cleared.
+  pub chain_payable_payments_page:
+    Option<Box<Account<'info, AddressLedgerPage>>>,
+
+  #[account(
+    mut,
+    seeds = [payable.key().as_ref(), &config.load()?.chain_id.to_le_bytes()[..], &payable_payment.local_chain_count.to_le_bytes()[..]],
+    bump,
+    close = rent_payer
+  )]
+  /// The payable's reference to the payment among those from its chain. Not
+  /// passed when the reference was recorded in a ledger page instead.
+  pub payable_per_chain_payment_info:
+    Option<Box<Account<'info, PayablePerChainPaymentInfo>>>,
+
+  #[account(
+    mut,
+    seeds = [payable.key().as_ref(), &config.load()?.chain_id.to_le_bytes()[..], SEED_PREFIX_LEDGER_PAGE, &ledger_page(payable_payment.local_chain_count).to_le_bytes()[..]],
+    bump
+  )]
+  /// The page of the payable's ledger of payments from its chain that
+  /// records the reference, in place of payable_per_chain_payment_info. Its
+  /// entry is cleared.
+  pub payable_per_chain_payments_page:
+    Option<Box<Account<'info, CountLedgerPage>>>,
+
+  #[account(
+    mut,
+    seeds = [payable_payment.key().as_ref(), EscrowState::SEED_PREFIX],
+    bump,
+    constraint = !escrow_state.is_held() @ ChainbillsError::PaymentIsHeldInEscrow,
+    close = rent_payer
+  )]
+  /// The escrow state of the payment, if it was escrowed. Only settled
+  /// payments can be archived.
+  pub escrow_state: Option<Box<Account<'info, EscrowState>>>,
+
+  #[account(seeds = [Config::SEED_PREFIX], bump)]
+  pub config: AccountLoader<'info, Config>,
+
+  #[account(mut, address = payable_payment.rent_payer)]
+  /// The wallet that paid the receipt's rent, like whoever collected a
+  /// subscription payment. Receives the rent back.
+  pub rent_payer: SystemAccount<'info>,
+
+  pub signer: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+/// Context used to close a payer's receipt of a payment.
+pub struct ArchiveUserPayment<'info> {
+  #[account(
+    mut,
+    constraint = user_payment.payer == *signer.key @ ChainbillsError::NotYourPayment,
+    close = rent_payer
+  )]
+  /// The payment receipt to close. Its rent goes back to whoever paid it.
+  pub user_payment: Box<Account<'info, UserPayment>>,
+
+  #[account(
+    mut,
+    constraint = chain_user_payment_id.user_payment_id == user_payment.key() @ ChainbillsError::NotYourPayment,
+    close = rent_payer
+  )]
+  /// The chain-level reference to the payment receipt. Not passed when the
+  /// reference was recorded in a ledger page instead, or when the payment
+  /// has none, as current payments don't.
+  pub chain_user_payment_id: Option<Box<Account<'info, ChainUserPaymentId>>>,
+
+  #[account(
+    mut,
+    seeds = [ChainUserPaymentId::SEED_PREFIX, SEED_PREFIX_LEDGER_PAGE, &ledger_page(user_payment.chain_count).to_le_bytes()[..]],
+    bump
+  )]
+  /// The page of the chain's ledger of user payments that records the
+  /// reference, in place of chain_user_payment_id. Its entry is cleared.
+  pub chain_user_payments_page: Option<Box<Account<'info, AddressLedgerPage>>>,
+
+  #[account(mut, address = user_payment.rent_payer)]
+  /// The wallet that paid the receipt's rent, like whoever collected a
+  /// subscription payment. Receives the rent back.
+  pub rent_payer: SystemAccount<'info>,
+
+  pub signer: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+/// Context used to close a host's receipt of a withdrawal.
+pub struct ArchiveWithdrawal<'info> {
+  #[account(
+    mut,
+    constraint = withdrawal.host == *signer.key @ ChainbillsError::NotYourWithdrawal,
+    close = rent_payer
+  )]
+  /// The withdrawal receipt to close. Its rent goes back to whoever paid it.
+  pub withdrawal: Box<Account<'info, Withdrawal>>,
+
+  #[account(
+    mut,
+    constraint = chain_withdrawal_id.withdrawal_id == withdrawal.key() @ ChainbillsError::NotYourWithdrawal,
+    close = rent_payer
+  )]
+  /// The chain-level reference to the withdrawal receipt.
+  pub chain_withdrawal_id: Box<Account<'info, ChainWithdrawalId>>,
+
+  #[account(
+    mut,
+    seeds = [withdrawal.payable_id.as_ref(), PayableWithdrawalInfo::SEED_PREFIX, &withdrawal.payable_count.to_le_bytes()[..]],
+    bump,
+    close = rent_payer
+  )]
+  /// The payable's reference to the withdrawal receipt.
+  pub payable_withdrawal_info: Box<Account<'info, PayableWithdrawalInfo>>,
+
+  #[account(mut, address = withdrawal.rent_payer)]
+  /// The wallet that paid the receipt's rent, like an operator that withdrew
+  /// for the host. Receives the rent back.
+  pub rent_payer: SystemAccount<'info>,
+
+  pub signer: Signer<'info>,
+}
+
+/// The chain count of the user's activity, from its info account or else its
+/// ledger page. Zero if neither records it.
+pub(crate) fn activity_chain_count(
+  user_activity_info: &Option<Box<Account<UserActivityInfo>>>,
+  user_activities_page: &Option<Box<Account<CountLedgerPage>>>,
+  user_count: u64,
+) -> u64 {
+  match (user_activity_info, user_activities_page) {
+    (Some(info), _) => info.chain_count,
+    (None, Some(page)) => page.get(user_count).unwrap_or_default(),
+    (None, None) => 0,
+  }
+}
+
+/// The address of the user's activity record: the one of its chain count if
+/// its info account or its ledger page is passed, or else the one that
+/// payments derive from the user's wallet and count.
+pub(crate) fn user_activity_address(
+  signer: &Pubkey,
+  user_activity_info: &Option<Box<Account<UserActivityInfo>>>,
+  user_activities_page: &Option<Box<Account<CountLedgerPage>>>,
+  user_count: u64,
+) -> Pubkey {
+  let address = if user_activity_info.is_none() && user_activities_page.is_none()
+  {
+    Pubkey::find_program_address(
+      &[
+        ActivityRecord::SEED_PREFIX,
+        signer.as_ref(),
+        &user_count.to_le_bytes(),
+      ],
+      &crate::ID,
+    )
+  } else {
+    let chain_count = activity_chain_count(
+      user_activity_info,
+      user_activities_page,
+      user_count,
+    );
+    Pubkey::find_program_address(
+      &[ActivityRecord::SEED_PREFIX, &chain_count.to_le_bytes()],
+      &crate::ID,
+    )
+  };
+  address.0
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(user_count: u64)]
+/// Context used to close one of a user's activity records.
+pub struct ArchiveUserActivity<'info> {
+  #[account(
+    mut,
+    seeds = [signer.key().as_ref(), ActivityRecord::SEED_PREFIX, &user_count.to_le_bytes()[..]],
+    bump,
+    close = rent_payer
+  )]
+  /// Houses Chain Count of the activity. Its seeds prove that the activity
+  /// belongs to the signer. Not passed when the activity was recorded in a
+  /// ledger page instead, or when it is a payment's.
+  pub user_activity_info: Option<Box<Account<'info, UserActivityInfo>>>,
+
+  #[account(
+    mut,
+    seeds = [signer.key().as_ref(), ActivityRecord::SEED_PREFIX, SEED_PREFIX_LEDGER_PAGE, &ledger_page(user_count).to_le_bytes()[..]],
+    bump
+  )]
+  /// The page of the user's ledger of activities that records the activity,
+  /// in place of user_activity_info. Its entry is cleared.
+  pub user_activities_page: Option<Box<Account<'info, CountLedgerPage>>>,
+
+  #[account(
+    mut,
+    address = user_activity_address(&signer.key(), &user_activity_info, &user_activities_page, user_count),
+    close = rent_payer
+  )]
+  /// The activity record to close. Its rent goes back to whoever paid it. A
+  /// payment's is derived from the signer and the user count, so that it
+  /// belongs to the signer without an info account.
+  pub activity: Box<Account<'info, ActivityRecord>>,
+
+  #[account(
+    mut,
+    constraint = payable_activity_info.chain_count == activity.chain_count @ ChainbillsError::InvalidPayableActivityInfo,
+    close = rent_payer
+  )]
+  /// The payable's reference to the activity. Required when the activity is
+  /// also one of a payable's (when its payable_count isn't zero). No other
+  /// reference holds the activity's chain count.
+  pub payable_activity_info: Option<Box<Account<'info, PayableActivityInfo>>>,
+
+  #[account(mut, address = activity.rent_payer)]
+  /// The wallet that paid the activity's rent, like an operator that acted
+  /// for the user. Receives the rent back.
+  pub rent_payer: SystemAccount<'info>,
+
+  pub signer: Signer<'info>,
+}
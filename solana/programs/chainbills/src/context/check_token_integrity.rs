@@ -0,0 +1,30 @@
+use crate::state::*;
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+#[instruction(token: Pubkey)]
+/// Context used to compare what the program holds of a token against what
+/// payables owe in it. The payables and escrow states are passed as
+/// remaining accounts.
+pub struct CheckTokenIntegrity<'info> {
+  #[account(seeds = [TokenDetails::SEED_PREFIX, token.as_ref()], bump)]
+  pub token_details: Box<Account<'info, TokenDetails>>,
+
+  #[account(seeds = [ChainStats::SEED_PREFIX], bump)]
+  pub chain_stats: Box<Account<'info, ChainStats>>,
+
+  #[account(seeds = [SEED_PREFIX_NATIVE_VAULT], bump)]
+  /// Holds the native SOL paid into payables on this chain.
+  pub native_vault: SystemAccount<'info>,
+
+  #[account(
+        associated_token::mint = token,
+        associated_token::authority = chain_stats,
+        associated_token::token_program = token_program,
+    )]
+  /// Holds the token for payables. Required only for SPL tokens.
+  pub chain_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+  pub token_program: Option<Interface<'info, TokenInterface>>,
+}
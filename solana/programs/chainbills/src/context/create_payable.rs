@@ -22,7 +22,7 @@ pub struct CreatePayable<'info> {
 
   #[account(
     init,
-    seeds = [ChainPayableId::SEED_PREFIX, &chain_stats.next_payable().to_le_bytes()[..]],
+    seeds = [ChainPayableId::SEED_PREFIX, &chain_shard.next_payable().to_le_bytes()[..]],
     bump,
     payer = signer,
     space = ChainPayableId::SPACE
@@ -48,7 +48,7 @@ pub struct CreatePayable<'info> {
 
   #[account(
     init,
-    seeds = [ActivityRecord::SEED_PREFIX, &chain_stats.next_activity().to_le_bytes()[..]],
+    seeds = [ActivityRecord::SEED_PREFIX, &chain_shard.next_activity().to_le_bytes()[..]],
     bump,
     payer = signer,
     space = ActivityRecord::SPACE
@@ -81,10 +81,16 @@ pub struct CreatePayable<'info> {
   pub host: Box<Account<'info, User>>,
 
   #[account(mut, seeds = [ChainStats::SEED_PREFIX], bump)]
-  /// Keeps track of entities on this chain. Its payable_count will be
-  /// incremented in this instruction.
+  /// Keeps track of entities on this chain. Its
+  /// published_wormhole_messages_count will be incremented in this
+  /// instruction.
   pub chain_stats: Box<Account<'info, ChainStats>>,
 
+  #[account(mut, seeds = [ChainStatsShard::SEED_PREFIX, &[chain_shard.index]], bump)]
+  /// The shard of the chain's counts that this instruction's entities are
+  /// indexed in. Any initialized shard can be used.
+  pub chain_shard: Box<Account<'info, ChainStatsShard>>,
+
   #[account(seeds = [Config::SEED_PREFIX], bump)]
   pub config: AccountLoader<'info, Config>,
 
@@ -0,0 +1,356 @@
+use crate::{error::ChainbillsError, state::*};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+/// Context used by the host (or a manager) to update a payable's escrow
+/// settings.
+pub struct UpdatePayableEscrow<'info> {
+  #[account(mut, constraint = payable.host == *signer.key || PayableOperator::permits(&operator, &payable.host, PayableOperator::ROLE_MANAGE) @ ChainbillsError::NotYourPayable)]
+  pub payable: Box<Account<'info, Payable>>,
+
+  #[account(
+    init,
+    seeds = [ActivityRecord::SEED_PREFIX, &chain_shard.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = ActivityRecord::SPACE
+  )]
+  /// Houses Details of this activity as UpdatedPayableEscrow.
+  pub activity: Box<Account<'info, ActivityRecord>>,
+
+  #[account(
+    init,
+    seeds = [payable.host.as_ref(), ActivityRecord::SEED_PREFIX, &host.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = UserActivityInfo::SPACE
+  )]
+  /// Houses Chain Count of activities for this activity.
+  pub user_activity_info: Box<Account<'info, UserActivityInfo>>,
+
+  #[account(
+    init,
+    seeds = [payable.key().as_ref(), ActivityRecord::SEED_PREFIX, &payable.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = PayableActivityInfo::SPACE
+  )]
+  /// Houses Chain Count of activities for this activity.
+  pub payable_activity_info: Box<Account<'info, PayableActivityInfo>>,
+
+  #[account(
+    init_if_needed,
+    seeds = [payable.key().as_ref(), PayableEscrow::SEED_PREFIX],
+    bump,
+    payer = signer,
+    space = PayableEscrow::SPACE
+  )]
+  pub payable_escrow: Box<Account<'info, PayableEscrow>>,
+
+  #[account(mut, seeds = [payable.host.as_ref()], bump)]
+  pub host: Box<Account<'info, User>>,
+
+  #[account(seeds = [payable.key().as_ref(), PayableOperator::SEED_PREFIX, signer.key().as_ref()], bump)]
+  /// The signer's operator account on the payable. Required only when the
+  /// signer isn't the host.
+  pub operator: Option<Box<Account<'info, PayableOperator>>>,
+
+  #[account(mut, seeds = [ChainStatsShard::SEED_PREFIX, &[chain_shard.index]], bump)]
+  /// The shard of the chain's counts that this instruction's entities are
+  /// indexed in. Any initialized shard can be used.
+  pub chain_shard: Box<Account<'info, ChainStatsShard>>,
+
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+/// Context used to release an escrowed payment into its payable's balances.
+/// The activity is recorded for the payer.
+pub struct ReleaseEscrow<'info> {
+  #[account(mut, constraint = escrow_state.payable_id == payable.key() @ ChainbillsError::InvalidEscrowAccounts)]
+  pub escrow_state: Box<Account<'info, EscrowState>>,
+
+  #[account(
+    mut,
+    realloc = payable.space_update_balance(escrow_state.details.token),
+    realloc::payer = signer,
+    realloc::zero = false
+  )]
+  pub payable: Box<Account<'info, Payable>>,
+
+  #[account(
+    init,
+    seeds = [ActivityRecord::SEED_PREFIX, &chain_shard.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = ActivityRecord::SPACE
+  )]
+  /// Houses Details of this activity as ReleasedEscrow.
+  pub activity: Box<Account<'info, ActivityRecord>>,
+
+  #[account(
+    init,
+    seeds = [escrow_state.payer.as_ref(), ActivityRecord::SEED_PREFIX, &payer.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = UserActivityInfo::SPACE
+  )]
+  /// Houses Chain Count of activities for this activity.
+  pub user_activity_info: Box<Account<'info, UserActivityInfo>>,
+
+  #[account(
+    init,
+    seeds = [payable.key().as_ref(), ActivityRecord::SEED_PREFIX, &payable.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = PayableActivityInfo::SPACE
+  )]
+  /// Houses Chain Count of activities for this activity.
+  pub payable_activity_info: Box<Account<'info, PayableActivityInfo>>,
+
+  #[account(mut, seeds = [payable.key().as_ref(), PayableEscrow::SEED_PREFIX], bump)]
+  /// The payable's escrow settings. Counts the payments it still holds.
+  pub payable_escrow: Box<Account<'info, PayableEscrow>>,
+
+  #[account(mut, seeds = [escrow_state.payer.as_ref()], bump)]
+  pub payer: Box<Account<'info, User>>,
+
+  #[account(mut, seeds = [ChainStatsShard::SEED_PREFIX, &[chain_shard.index]], bump)]
+  /// The shard of the chain's counts that this instruction's entities are
+  /// indexed in. Any initialized shard can be used.
+  pub chain_shard: Box<Account<'info, ChainStatsShard>>,
+
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+/// Context used by a payer to dispute an escrowed payment.
+pub struct DisputeEscrow<'info> {
+  #[account(
+    mut,
+    constraint = escrow_state.payable_id == payable.key() @ ChainbillsError::InvalidEscrowAccounts,
+    constraint = escrow_state.payer == *signer.key @ ChainbillsError::NotYourEscrow
+  )]
+  pub escrow_state: Box<Account<'info, EscrowState>>,
+
+  #[account(mut)]
+  pub payable: Box<Account<'info, Payable>>,
+
+  #[account(
+    init,
+    seeds = [ActivityRecord::SEED_PREFIX, &chain_shard.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = ActivityRecord::SPACE
+  )]
+  /// Houses Details of this activity as DisputedEscrow.
+  pub activity: Box<Account<'info, ActivityRecord>>,
+
+  #[account(
+    init,
+    seeds = [escrow_state.payer.as_ref(), ActivityRecord::SEED_PREFIX, &payer.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = UserActivityInfo::SPACE
+  )]
+  /// Houses Chain Count of activities for this activity.
+  pub user_activity_info: Box<Account<'info, UserActivityInfo>>,
+
+  #[account(
+    init,
+    seeds = [payable.key().as_ref(), ActivityRecord::SEED_PREFIX, &payable.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = PayableActivityInfo::SPACE
+  )]
+  /// Houses Chain Count of activities for this activity.
+  pub payable_activity_info: Box<Account<'info, PayableActivityInfo>>,
+
+  #[account(mut, seeds = [escrow_state.payer.as_ref()], bump)]
+  pub payer: Box<Account<'info, User>>,
+
+  #[account(mut, seeds = [ChainStatsShard::SEED_PREFIX, &[chain_shard.index]], bump)]
+  /// The shard of the chain's counts that this instruction's entities are
+  /// indexed in. Any initialized shard can be used.
+  pub chain_shard: Box<Account<'info, ChainStatsShard>>,
+
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+/// Context used by the arbiter or the payable's host to refund an escrowed
+/// payment in tokens to its payer.
+pub struct RefundEscrow<'info> {
+  #[account(
+    mut,
+    constraint = escrow_state.payable_id == payable.key() @ ChainbillsError::InvalidEscrowAccounts,
+    constraint = (escrow_state.has_arbiter() && escrow_state.arbiter == *signer.key)
+      || payable.host == *signer.key
+      @ ChainbillsError::NotYourEscrow
+  )]
+  pub escrow_state: Box<Account<'info, EscrowState>>,
+
+  #[account(mut)]
+  pub payable: Box<Account<'info, Payable>>,
+
+  #[account(
+    init,
+    seeds = [ActivityRecord::SEED_PREFIX, &chain_shard.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = ActivityRecord::SPACE
+  )]
+  /// Houses Details of this activity as RefundedEscrow.
+  pub activity: Box<Account<'info, ActivityRecord>>,
+
+  #[account(
+    init,
+    seeds = [escrow_state.payer.as_ref(), ActivityRecord::SEED_PREFIX, &payer.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = UserActivityInfo::SPACE
+  )]
+  /// Houses Chain Count of activities for this activity.
+  pub user_activity_info: Box<Account<'info, UserActivityInfo>>,
+
+  #[account(
+    init,
+    seeds = [payable.key().as_ref(), ActivityRecord::SEED_PREFIX, &payable.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = PayableActivityInfo::SPACE
+  )]
+  /// Houses Chain Count of activities for this activity.
+  pub payable_activity_info: Box<Account<'info, PayableActivityInfo>>,
+
+  #[account(mut, seeds = [payable.key().as_ref(), PayableEscrow::SEED_PREFIX], bump)]
+  /// The payable's escrow settings. Counts the payments it still holds.
+  pub payable_escrow: Box<Account<'info, PayableEscrow>>,
+
+  #[account(mut, seeds = [escrow_state.payer.as_ref()], bump)]
+  pub payer: Box<Account<'info, User>>,
+
+  #[account(seeds = [ChainStats::SEED_PREFIX], bump)]
+  pub chain_stats: Box<Account<'info, ChainStats>>,
+
+  #[account(mut, seeds = [ChainStatsShard::SEED_PREFIX, &[chain_shard.index]], bump)]
+  /// The shard of the chain's counts that this instruction's entities are
+  /// indexed in. Any initialized shard can be used.
+  pub chain_shard: Box<Account<'info, ChainStatsShard>>,
+
+  #[account(constraint = mint.key() == escrow_state.details.token @ ChainbillsError::InvalidEscrowAccounts)]
+  pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(mut, seeds = [TokenDetails::SEED_PREFIX, mint.key().as_ref()], bump)]
+  pub token_details: Box<Account<'info, TokenDetails>>,
+
+  #[account(
+    mut,
+    token::mint = mint,
+    token::token_program = token_program,
+    constraint = payer_token_account.owner == escrow_state.payer @ ChainbillsError::InvalidEscrowAccounts
+  )]
+  /// Any token account of the payer. Receives the refund.
+  pub payer_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = chain_stats,
+        associated_token::token_program = token_program,
+    )]
+  pub chain_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+
+  pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+/// Context used by the arbiter or the payable's host to refund an escrowed
+/// payment in native SOL to its payer.
+pub struct RefundEscrowNative<'info> {
+  #[account(
+    mut,
+    constraint = escrow_state.payable_id == payable.key() @ ChainbillsError::InvalidEscrowAccounts,
+    constraint = escrow_state.details.token == crate::ID @ ChainbillsError::InvalidEscrowAccounts,
+    constraint = (escrow_state.has_arbiter() && escrow_state.arbiter == *signer.key)
+      || payable.host == *signer.key
+      @ ChainbillsError::NotYourEscrow
+  )]
+  pub escrow_state: Box<Account<'info, EscrowState>>,
+
+  #[account(mut)]
+  pub payable: Box<Account<'info, Payable>>,
+
+  #[account(
+    init,
+    seeds = [ActivityRecord::SEED_PREFIX, &chain_shard.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = ActivityRecord::SPACE
+  )]
+  /// Houses Details of this activity as RefundedEscrow.
+  pub activity: Box<Account<'info, ActivityRecord>>,
+
+  #[account(
+    init,
+    seeds = [escrow_state.payer.as_ref(), ActivityRecord::SEED_PREFIX, &payer.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = UserActivityInfo::SPACE
+  )]
+  /// Houses Chain Count of activities for this activity.
+  pub user_activity_info: Box<Account<'info, UserActivityInfo>>,
+
+  #[account(
+    init,
+    seeds = [payable.key().as_ref(), ActivityRecord::SEED_PREFIX, &payable.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = PayableActivityInfo::SPACE
+  )]
+  /// Houses Chain Count of activities for this activity.
+  pub payable_activity_info: Box<Account<'info, PayableActivityInfo>>,
+
+  #[account(mut, seeds = [payable.key().as_ref(), PayableEscrow::SEED_PREFIX], bump)]
+  /// The payable's escrow settings. Counts the payments it still holds.
+  pub payable_escrow: Box<Account<'info, PayableEscrow>>,
+
+  #[account(mut, seeds = [escrow_state.payer.as_ref()], bump)]
+  pub payer: Box<Account<'info, User>>,
+
+  #[account(mut, address = escrow_state.payer @ ChainbillsError::InvalidEscrowAccounts)]
+  /// The payer's wallet. Receives the refund.
+  pub payer_wallet: SystemAccount<'info>,
+
+  #[account(mut, seeds = [ChainStatsShard::SEED_PREFIX, &[chain_shard.index]], bump)]
+  /// The shard of the chain's counts that this instruction's entities are
+  /// indexed in. Any initialized shard can be used.
+  pub chain_shard: Box<Account<'info, ChainStatsShard>>,
+
+  #[account(mut, seeds = [TokenDetails::SEED_PREFIX, crate::ID.as_ref()], bump)]
+  pub token_details: Box<Account<'info, TokenDetails>>,
+
+  #[account(mut, seeds = [SEED_PREFIX_NATIVE_VAULT], bump)]
+  /// Holds the native SOL paid into payables on this chain.
+  pub native_vault: SystemAccount<'info>,
+
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,26 @@
+use crate::{error::*, state::*};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+#[instruction(index: u8)]
+/// Context used to initialize one of the shards of the chain's counts.
+pub struct InitializeChainShard<'info> {
+  #[account(
+    init,
+    payer = owner,
+    seeds = [ChainStatsShard::SEED_PREFIX, &[index]],
+    bump,
+    space = ChainStatsShard::SPACE
+  )]
+  /// The shard to initialize. Can be initialized only once.
+  pub chain_shard: Account<'info, ChainStatsShard>,
+
+  #[account(seeds = [Config::SEED_PREFIX], bump)]
+  pub config: AccountLoader<'info, Config>,
+
+  #[account(mut, address = config.load()?.owner @ ChainbillsError::OwnerUnauthorized)]
+  pub owner: Signer<'info>,
+
+  /// System program.
+  pub system_program: Program<'info, System>,
+}
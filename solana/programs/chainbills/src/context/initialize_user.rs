@@ -17,7 +17,7 @@ pub struct InitializeUser<'info> {
 
   #[account(
     init,
-    seeds = [ChainUserAddress::SEED_PREFIX, &chain_stats.next_user().to_le_bytes()[..]],
+    seeds = [ChainUserAddress::SEED_PREFIX, &chain_shard.next_user().to_le_bytes()[..]],
     bump,
     payer = signer,
     space = ChainUserAddress::SPACE
@@ -27,7 +27,7 @@ pub struct InitializeUser<'info> {
 
   #[account(
     init,
-    seeds = [ActivityRecord::SEED_PREFIX, &chain_stats.next_activity().to_le_bytes()[..]],
+    seeds = [ActivityRecord::SEED_PREFIX, &chain_shard.next_activity().to_le_bytes()[..]],
     bump,
     payer = signer,
     space = ActivityRecord::SPACE
@@ -45,10 +45,10 @@ pub struct InitializeUser<'info> {
   /// Houses Chain Count of activities for this activity.
   pub user_activity_info: Box<Account<'info, UserActivityInfo>>,
 
-  #[account(mut, seeds = [ChainStats::SEED_PREFIX], bump)]
-  /// Keeps track of entities on this chain. Its user_count will be
-  /// incremented in this instruction.
-  pub chain_stats: Box<Account<'info, ChainStats>>,
+  #[account(mut, seeds = [ChainStatsShard::SEED_PREFIX, &[chain_shard.index]], bump)]
+  /// The shard of the chain's counts that this instruction's entities are
+  /// indexed in. Any initialized shard can be used.
+  pub chain_shard: Box<Account<'info, ChainStatsShard>>,
 
   #[account(mut)]
   /// The signer of the transaction.
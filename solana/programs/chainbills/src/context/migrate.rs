@@ -0,0 +1,182 @@
+use super::archive::activity_chain_count;
+use crate::{error::ChainbillsError, state::*};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+/// Context used to upgrade a Payable to its current layout.
+pub struct MigratePayable<'info> {
+  #[account(mut, owner = crate::ID)]
+  /// CHECK: Can't be deserialized as a current Payable. Its discriminator and
+  /// host are checked in the instruction.
+  pub payable: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// The payable's host. Pays for any extra space.
+  pub signer: Signer<'info>,
+
+  pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+/// Context used to upgrade a User to its current layout.
+pub struct MigrateUser<'info> {
+  #[account(mut, seeds = [signer.key().as_ref()], bump, owner = crate::ID)]
+  /// CHECK: Can't be deserialized as a current User. Its seeds tie it to the
+  /// signer and its discriminator is checked in the instruction.
+  pub user: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// The user's wallet. Pays for any extra space.
+  pub signer: Signer<'info>,
+
+  pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+/// Context used to upgrade a UserPayment to its current layout.
+pub struct MigrateUserPayment<'info> {
+  #[account(mut, owner = crate::ID)]
+  /// CHECK: Can't be deserialized as a current UserPayment. Its discriminator
+  /// and payer are checked in the instruction.
+  pub user_payment: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// The payment's payer. Pays for the extra space.
+  pub signer: Signer<'info>,
+
+  pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+/// Context used to upgrade a PayablePayment to its current layout.
+pub struct MigratePayablePayment<'info> {
+  #[account(mut, owner = crate::ID)]
+  /// CHECK: Can't be deserialized as a current PayablePayment. Its
+  /// discriminator is checked in the instruction.
+  pub payable_payment: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// Anyone can upgrade a PayablePayment. Pays for the extra space.
+  pub signer: Signer<'info>,
+
+  pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+/// Context used to upgrade a Withdrawal to its current layout.
+pub struct MigrateWithdrawal<'info> {
+  #[account(mut, owner = crate::ID)]
+  /// CHECK: Can't be deserialized as a current Withdrawal. Its discriminator
+  /// and host are checked in the instruction.
+  pub withdrawal: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// The withdrawal's host. Pays for the extra space.
+  pub signer: Signer<'info>,
+
+  pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(user_count: u64)]
+/// Context used to upgrade one of a user's ActivityRecords to its current
+/// layout.
+pub struct MigrateActivityRecord<'info> {
+  #[account(seeds = [signer.key().as_ref(), ActivityRecord::SEED_PREFIX, &user_count.to_le_bytes()[..]], bump)]
+  /// Houses Chain Count of the activity. Its seeds prove that the activity
+  /// belongs to the signer. Not passed when the activity was recorded in a
+  /// ledger page instead.
+  pub user_activity_info: Option<Box<Account<'info, UserActivityInfo>>>,
+
+  #[account(seeds = [signer.key().as_ref(), ActivityRecord::SEED_PREFIX, SEED_PREFIX_LEDGER_PAGE, &ledger_page(user_count).to_le_bytes()[..]], bump)]
+  /// The page of the user's ledger of activities that records the activity,
+  /// in place of user_activity_info.
+  pub user_activities_page: Option<Box<Account<'info, CountLedgerPage>>>,
+
+  #[account(
+    mut,
+    seeds = [ActivityRecord::SEED_PREFIX, &activity_chain_count(&user_activity_info, &user_activities_page, user_count).to_le_bytes()[..]],
+    bump,
+    owner = crate::ID
+  )]
+  /// CHECK: Can't be deserialized as a current ActivityRecord. Its seeds tie
+  /// it to the signer and its discriminator is checked in the instruction.
+  pub activity: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// The user's wallet. Pays for the extra space.
+  pub signer: Signer<'info>,
+
+  pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+/// Context used to upgrade the Config to its current layout. Has to be run
+/// before the other owner migrations, as they read the Config.
+pub struct MigrateConfig<'info> {
+  #[account(mut, seeds = [Config::SEED_PREFIX], bump, owner = crate::ID)]
+  /// CHECK: Can't be loaded as a current Config. Its discriminator and owner
+  /// are checked in the instruction.
+  pub config: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// Should be the owner recorded in the Config. Pays for any extra space.
+  pub owner: Signer<'info>,
+
+  pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+/// Context used to upgrade Solana's ChainStats to its current layout.
+pub struct MigrateChainStats<'info> {
+  #[account(mut, seeds = [ChainStats::SEED_PREFIX], bump, owner = crate::ID)]
+  /// CHECK: Can't be deserialized as a current ChainStats. Its discriminator
+  /// is checked in the instruction.
+  pub chain_stats: UncheckedAccount<'info>,
+
+  #[account(seeds = [Config::SEED_PREFIX], bump)]
+  pub config: AccountLoader<'info, Config>,
+
+  #[account(mut, address = config.load()?.owner @ ChainbillsError::OwnerUnauthorized)]
+  /// Pays for any extra space.
+  pub owner: Signer<'info>,
+
+  pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(token: Pubkey)]
+/// Context used to upgrade a TokenDetails to its current layout.
+pub struct MigrateTokenDetails<'info> {
+  #[account(mut, seeds = [TokenDetails::SEED_PREFIX, token.as_ref()], bump, owner = crate::ID)]
+  /// CHECK: Can't be deserialized as a current TokenDetails. Its discriminator
+  /// is checked in the instruction.
+  pub token_details: UncheckedAccount<'info>,
+
+  #[account(seeds = [Config::SEED_PREFIX], bump)]
+  pub config: AccountLoader<'info, Config>,
+
+  #[account(mut, address = config.load()?.owner @ ChainbillsError::OwnerUnauthorized)]
+  /// Pays for any extra space.
+  pub owner: Signer<'info>,
+
+  pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+/// Context used to move the native SOL that was paid into ChainStats before
+/// the native vault existed.
+pub struct MigrateNativeVault<'info> {
+  #[account(mut, seeds = [ChainStats::SEED_PREFIX], bump)]
+  pub chain_stats: Box<Account<'info, ChainStats>>,
+
+  #[account(mut, seeds = [SEED_PREFIX_NATIVE_VAULT], bump)]
+  /// Holds the native SOL paid into payables on this chain.
+  pub native_vault: SystemAccount<'info>,
+
+  #[account(seeds = [Config::SEED_PREFIX], bump)]
+  pub config: AccountLoader<'info, Config>,
+
+  #[account(address = config.load()?.owner @ ChainbillsError::OwnerUnauthorized)]
+  pub owner: Signer<'info>,
+}
@@ -1,29 +1,57 @@
+pub mod archive;
+pub mod check_token_integrity;
 pub mod initialize;
+pub mod initialize_chain_shard;
 pub mod create_payable;
+pub mod escrow;
 pub mod initialize_user;
+pub mod migrate;
 pub mod owner_withdraw;
 pub mod pay;
+pub mod pay_batch;
+pub mod payable_operator;
 pub mod pay_native;
+pub mod pay_with_intent;
+pub mod receipts;
+pub mod register_cb_chain;
 pub mod register_foreign_contract;
 pub mod record_foreign_payable_update;
+pub mod subscription;
+pub mod token_group;
+pub mod transfer_payable;
 pub mod update_max_withdrawal_fees;
 pub mod update_max_withdrawal_fees_native;
 pub mod update_payable;
 pub mod update_token_foreign_chain;
 pub mod withdraw;
+pub mod withdraw_cross_chain;
 pub mod withdraw_native;
 
+pub use archive::*;
+pub use check_token_integrity::*;
 pub use initialize::*;
+pub use initialize_chain_shard::*;
 pub use create_payable::*;
+pub use escrow::*;
 pub use initialize_user::*;
+pub use migrate::*;
 pub use owner_withdraw::*;
 pub use pay::*;
+pub use pay_batch::*;
+pub use payable_operator::*;
 pub use pay_native::*;
+pub use pay_with_intent::*;
+pub use receipts::*;
+pub use register_cb_chain::*;
 pub use register_foreign_contract::*;
 pub use record_foreign_payable_update::*;
+pub use subscription::*;
+pub use token_group::*;
+pub use transfer_payable::*;
 pub use update_max_withdrawal_fees::*;
 pub use update_max_withdrawal_fees_native::*;
 pub use update_payable::*;
 pub use update_token_foreign_chain::*;
 pub use withdraw::*;
+pub use withdraw_cross_chain::*;
 pub use withdraw_native::*;
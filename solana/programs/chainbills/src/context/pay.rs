@@ -1,7 +1,10 @@
 use crate::state::*;
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Mint, Token, TokenAccount};
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct Pay<'info> {
   #[account(
@@ -26,32 +29,10 @@ pub struct Pay<'info> {
         ],
         bump,
         payer = signer,
-        space = UserPayment::SPACE
+        space = PayablePayment::SPACE
     )]
   pub payable_payment: Box<Account<'info, PayablePayment>>,
 
-  #[account(
-    init,
-    seeds = [ChainUserPaymentId::SEED_PREFIX, &chain_stats.next_user_payment().to_le_bytes()[..]],
-    bump,
-    payer = signer,
-    space = ChainUserPaymentId::SPACE
-  )]
-  /// Keeps the user_payment_id at chain level. Useful for getting all 
-  /// user payments on this chain.
-  pub chain_user_payment_id: Box<Account<'info, ChainUserPaymentId>>,
-
-  #[account(
-    init,
-    seeds = [ChainPayablePaymentId::SEED_PREFIX, &chain_stats.next_payable_payment().to_le_bytes()[..]],
-    bump,
-    payer = signer,
-    space = ChainPayablePaymentId::SPACE
-  )]
-  /// Keeps the payable_payment_id at chain level. Useful for getting all 
-  /// payable payments on this chain.
-  pub chain_payable_payment_id: Box<Account<'info, ChainPayablePaymentId>>,
-
   #[account(
         init,
         seeds = [
@@ -63,8 +44,21 @@ pub struct Pay<'info> {
         payer = signer,
         space = PayablePerChainPaymentInfo::SPACE
     )]
+  /// Not passed when the ledger page below records the entry instead.
   pub payable_per_chain_payment_info:
-    Box<Account<'info, PayablePerChainPaymentInfo>>,
+    Option<Box<Account<'info, PayablePerChainPaymentInfo>>>,
+
+  #[account(
+    init_if_needed,
+    seeds = [payable.key().as_ref(), &config.load()?.chain_id.to_le_bytes()[..], SEED_PREFIX_LEDGER_PAGE, &ledger_page(payable_per_chain_payments_counter.next_payment()).to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = CountLedgerPage::SPACE
+  )]
+  /// The page of the payable's ledger of payments from this chain that
+  /// records this payment, in place of payable_per_chain_payment_info.
+  pub payable_per_chain_payments_page:
+    Option<Box<Account<'info, CountLedgerPage>>>,
 
   #[account(
         mut,
@@ -79,80 +73,127 @@ pub struct Pay<'info> {
 
   #[account(
     init,
-    seeds = [ActivityRecord::SEED_PREFIX, &chain_stats.next_activity().to_le_bytes()[..]],
+    seeds = [ActivityRecord::SEED_PREFIX, signer.key().as_ref(), &payer.next_activity().to_le_bytes()[..]],
     bump,
     payer = signer,
     space = ActivityRecord::SPACE
   )]
-  /// Houses Details of this activity as one of UserPaid.
+  /// Houses Details of this activity as one of UserPaid. Derived from the
+  /// payer's count of activities, so that concurrent payers don't collide.
   pub user_activity: Box<Account<'info, ActivityRecord>>,
 
   #[account(
     init,
-    seeds = [signer.key().as_ref(), ActivityRecord::SEED_PREFIX, &payer.next_activity().to_le_bytes()[..]],
-    bump,
-    payer = signer,
-    space = UserActivityInfo::SPACE
-  )]
-  /// Houses Chain Count of activities for this activity.
-  pub user_activity_info: Box<Account<'info, UserActivityInfo>>,
-
-  #[account(
-    init,
-    // added 1 to chain_stats.next_activity() because the previous addition in this same transaction is for the user activity
-    seeds = [ActivityRecord::SEED_PREFIX, &(chain_stats.next_activity().checked_add(1).unwrap()).to_le_bytes()[..]], 
+    seeds = [ActivityRecord::SEED_PREFIX, payable.key().as_ref(), &payable.next_activity().to_le_bytes()[..]],
     bump,
     payer = signer,
     space = ActivityRecord::SPACE
   )]
-  /// Houses Details of this activity as one of PayableReceived.
+  /// Houses Details of this activity as one of PayableReceived. Derived from
+  /// the payable's count of activities.
   pub payable_activity: Box<Account<'info, ActivityRecord>>,
 
+  #[account(mut, realloc = payable.space_update_balance(mint.key()), realloc::payer = signer, realloc::zero = false)]
+  pub payable: Box<Account<'info, Payable>>,
+
+  #[account(seeds = [payable.key().as_ref(), PayableAllowedPayers::SEED_PREFIX], bump)]
+  /// CHECK: The payable's allowed payers. Anyone can pay if the payable's
+  /// host never restricted its payers, in which case this isn't initialized.
+  pub allowed_payers: UncheckedAccount<'info>,
+
+  #[account(mut, seeds = [payable.key().as_ref(), PayableEscrow::SEED_PREFIX], bump)]
+  /// CHECK: The payable's escrow settings. Payments aren't escrowed if the
+  /// payable's host never set them, in which case this isn't initialized.
+  pub payable_escrow: UncheckedAccount<'info>,
+
   #[account(
     init,
-    seeds = [payable.key().as_ref(), ActivityRecord::SEED_PREFIX, &payable.next_activity().to_le_bytes()[..]],
+    seeds = [payable_payment.key().as_ref(), EscrowState::SEED_PREFIX],
     bump,
     payer = signer,
-    space = PayableActivityInfo::SPACE
+    space = EscrowState::SPACE
   )]
-  /// Houses Chain Count of activities for this activity.
-  pub payable_activity_info: Box<Account<'info, PayableActivityInfo>>,
+  /// Holds the payment until it is released. Required only when the payable
+  /// escrows its payments.
+  pub escrow_state: Option<Box<Account<'info, EscrowState>>>,
+
+  #[account(seeds = [TokenGroup::SEED_PREFIX, token_group.name.as_bytes()], bump)]
+  /// The token group that an allowed token of the payable references.
+  /// Required only to pay a member of the group.
+  pub token_group: Option<Box<Account<'info, TokenGroup>>>,
+
+  #[account(seeds = [payable.key().as_ref(), PayableReceipts::SEED_PREFIX], bump)]
+  /// CHECK: The payable's receipt settings. The signer gets the receipt of
+  /// this payment if they are enabled. Payables whose host never enabled
+  /// receipts don't have them initialized.
+  pub payable_receipts: UncheckedAccount<'info>,
+
+  #[account(mut, seeds = [user_payment.key().as_ref(), Receipt::MINT_SEED_PREFIX], bump)]
+  /// CHECK: The mint of this payment's receipt, created with its metadata.
+  /// Required only when the payable mints receipts.
+  pub receipt_mint: Option<UncheckedAccount<'info>>,
 
-  #[account(mut, realloc = payable.space_update_balance(mint.key()), realloc::payer = signer, realloc::zero = false)]
-  pub payable: Box<Account<'info, Payable>>,
+  #[account(mut)]
+  /// CHECK: The signer's associated token account of the receipt, created by
+  /// the associated token program. Required only when the payable mints
+  /// receipts.
+  pub payer_receipt_token_account: Option<UncheckedAccount<'info>>,
+
+  /// Required only when the payable mints receipts.
+  pub receipt_token_program: Option<Program<'info, Token2022>>,
+
+  /// Required only when the payable mints receipts.
+  pub associated_token_program: Option<Program<'info, AssociatedToken>>,
 
   #[account(mut, seeds = [signer.key().as_ref()], bump)]
   pub payer: Box<Account<'info, User>>,
 
-  #[account(mut, seeds = [ChainStats::SEED_PREFIX], bump)]
+  #[account(seeds = [ChainStats::SEED_PREFIX], bump)]
   pub chain_stats: Box<Account<'info, ChainStats>>,
 
+  #[account(mut, seeds = [ChainStatsShard::SEED_PREFIX, &[chain_shard.index]], bump)]
+  /// The shard of the chain's counts that this instruction's entities are
+  /// indexed in. Any initialized shard can be used.
+  pub chain_shard: Box<Account<'info, ChainStatsShard>>,
+
   #[account(seeds = [Config::SEED_PREFIX], bump)]
   pub config: AccountLoader<'info, Config>,
 
-  pub mint: Box<Account<'info, Mint>>,
+  pub mint: Box<InterfaceAccount<'info, Mint>>,
 
   #[account(seeds = [TokenDetails::SEED_PREFIX, mint.key().as_ref()], bump)]
   pub token_details: Box<Account<'info, TokenDetails>>,
 
+  #[account(
+    init_if_needed,
+    seeds = [TokenDetailsShard::SEED_PREFIX, mint.key().as_ref(), &[chain_shard.index]],
+    bump,
+    payer = signer,
+    space = TokenDetailsShard::SPACE
+  )]
+  /// The token's shard of payment totals that goes with chain_shard.
+  pub token_details_shard: Box<Account<'info, TokenDetailsShard>>,
+
   #[account(
         mut,
         associated_token::mint = mint,
         associated_token::authority = signer,
+        associated_token::token_program = token_program,
     )]
-  pub payer_token_account: Box<Account<'info, TokenAccount>>,
+  pub payer_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
   #[account(
         mut,
         associated_token::mint = mint,
         associated_token::authority = chain_stats,
+        associated_token::token_program = token_program,
     )]
-  pub chain_token_account: Box<Account<'info, TokenAccount>>,
+  pub chain_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
   #[account(mut)]
   pub signer: Signer<'info>,
 
-  pub token_program: Program<'info, Token>,
+  pub token_program: Interface<'info, TokenInterface>,
 
   pub system_program: Program<'info, System>,
 }
@@ -0,0 +1,94 @@
+use crate::state::*;
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+/// One payment of a batch.
+pub struct BatchPayment {
+  /// The Wormhole-normalized amount to be paid.
+  pub amount: u64,
+
+  /// The Merkle proof that the signer is an allowed payer of the payable.
+  /// Empty if the payable doesn't restrict its payers.
+  pub payer_proof: Vec<[u8; 32]>,
+
+  /// Whether the payment records its payable_per_chain_payment_info in a
+  /// ledger page, as when Pay is given the page instead of the entry's own
+  /// account.
+  pub paged: bool,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+/// Context used to pay many payables in one token at once. For each payment,
+/// the remaining accounts hold, in order: the payable, its payments counter
+/// for Solana, its allowed payers, its (writable) escrow settings, its
+/// receipt settings, and the accounts to be created for the payment
+/// (user_payment, payable_payment, payable_per_chain_payment_info,
+/// user_activity, and payable_activity), followed by an escrow_state if the
+/// payable escrows its payments, and by a receipt_mint and a
+/// payer_receipt_token_account if the payable mints receipts. These are
+/// derived as in Pay, with the counts of the payer and the payable advancing
+/// after each payment. Paged payments pass the ledger page of their
+/// payable_per_chain_payment_info in place of its own account.
+pub struct PayBatch<'info> {
+  #[account(mut, seeds = [signer.key().as_ref()], bump)]
+  pub payer: Box<Account<'info, User>>,
+
+  #[account(seeds = [ChainStats::SEED_PREFIX], bump)]
+  pub chain_stats: Box<Account<'info, ChainStats>>,
+
+  #[account(mut, seeds = [ChainStatsShard::SEED_PREFIX, &[chain_shard.index]], bump)]
+  /// The shard of the chain's counts that all payments of the batch are
+  /// indexed in. Any initialized shard can be used.
+  pub chain_shard: Box<Account<'info, ChainStatsShard>>,
+
+  #[account(seeds = [Config::SEED_PREFIX], bump)]
+  pub config: AccountLoader<'info, Config>,
+
+  pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(seeds = [TokenDetails::SEED_PREFIX, mint.key().as_ref()], bump)]
+  pub token_details: Box<Account<'info, TokenDetails>>,
+
+  #[account(
+    init_if_needed,
+    seeds = [TokenDetailsShard::SEED_PREFIX, mint.key().as_ref(), &[chain_shard.index]],
+    bump,
+    payer = signer,
+    space = TokenDetailsShard::SPACE
+  )]
+  /// The token's shard of payment totals that goes with chain_shard.
+  pub token_details_shard: Box<Account<'info, TokenDetailsShard>>,
+
+  #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = signer,
+        associated_token::token_program = token_program,
+    )]
+  pub payer_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = chain_stats,
+        associated_token::token_program = token_program,
+    )]
+  pub chain_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+
+  /// Required only when a payable of the batch mints receipts.
+  pub receipt_token_program: Option<Program<'info, Token2022>>,
+
+  /// Required only when a payable of the batch mints receipts.
+  pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+
+  pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,212 @@
+use crate::state::*;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(amount: u64, payer_proof: Vec<[u8; 32]>, reference: String, expiry: u64, nonce: u64)]
+pub struct PayWithIntent<'info> {
+  #[account(
+    init,
+    seeds = [payable.key().as_ref(), ConsumedPaymentIntent::SEED_PREFIX, &nonce.to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = ConsumedPaymentIntent::SPACE
+  )]
+  /// Marks the intent as paid. Its creation fails if the intent's nonce was
+  /// already used on the payable.
+  pub consumed_payment_intent: Box<Account<'info, ConsumedPaymentIntent>>,
+
+  #[account(
+        init,
+        seeds = [
+            signer.key().as_ref(),
+            UserPayment::SEED_PREFIX,
+            &payer.next_payment().to_le_bytes()[..]
+        ],
+        bump,
+        payer = signer,
+        space = UserPayment::SPACE
+    )]
+  pub user_payment: Box<Account<'info, UserPayment>>,
+
+  #[account(
+        init,
+        seeds = [
+            payable.key().as_ref(),
+            PayablePayment::SEED_PREFIX,
+            &payable.next_payment().to_le_bytes()[..]
+        ],
+        bump,
+        payer = signer,
+        space = PayablePayment::SPACE
+    )]
+  pub payable_payment: Box<Account<'info, PayablePayment>>,
+
+  #[account(
+        init,
+        seeds = [
+            payable.key().as_ref(),
+            &config.load()?.chain_id.to_le_bytes()[..],
+            &payable_per_chain_payments_counter.next_payment().to_le_bytes()[..]
+        ],
+        bump,
+        payer = signer,
+        space = PayablePerChainPaymentInfo::SPACE
+    )]
+  /// Not passed when the ledger page below records the entry instead.
+  pub payable_per_chain_payment_info:
+    Option<Box<Account<'info, PayablePerChainPaymentInfo>>>,
+
+  #[account(
+    init_if_needed,
+    seeds = [payable.key().as_ref(), &config.load()?.chain_id.to_le_bytes()[..], SEED_PREFIX_LEDGER_PAGE, &ledger_page(payable_per_chain_payments_counter.next_payment()).to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = CountLedgerPage::SPACE
+  )]
+  /// The page of the payable's ledger of payments from this chain that
+  /// records this payment, in place of payable_per_chain_payment_info.
+  pub payable_per_chain_payments_page:
+    Option<Box<Account<'info, CountLedgerPage>>>,
+
+  #[account(
+        mut,
+        seeds = [
+            payable.key().as_ref(),
+            &config.load()?.chain_id.to_le_bytes()[..],
+        ],
+        bump
+    )]
+  pub payable_per_chain_payments_counter:
+    Box<Account<'info, PayablePerChainPaymentsCounter>>,
+
+  #[account(
+    init,
+    seeds = [ActivityRecord::SEED_PREFIX, signer.key().as_ref(), &payer.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = ActivityRecord::SPACE
+  )]
+  /// Houses Details of this activity as one of UserPaid. Derived from the
+  /// payer's count of activities, so that concurrent payers don't collide.
+  pub user_activity: Box<Account<'info, ActivityRecord>>,
+
+  #[account(
+    init,
+    seeds = [ActivityRecord::SEED_PREFIX, payable.key().as_ref(), &payable.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = ActivityRecord::SPACE
+  )]
+  /// Houses Details of this activity as one of PayableReceived. Derived from
+  /// the payable's count of activities.
+  pub payable_activity: Box<Account<'info, ActivityRecord>>,
+
+  #[account(mut, realloc = payable.space_update_balance(mint.key()), realloc::payer = signer, realloc::zero = false)]
+  pub payable: Box<Account<'info, Payable>>,
+
+  #[account(seeds = [payable.key().as_ref(), PayableAllowedPayers::SEED_PREFIX], bump)]
+  /// CHECK: The payable's allowed payers. Anyone can pay if the payable's
+  /// host never restricted its payers, in which case this isn't initialized.
+  pub allowed_payers: UncheckedAccount<'info>,
+
+  #[account(mut, seeds = [payable.key().as_ref(), PayableEscrow::SEED_PREFIX], bump)]
+  /// CHECK: The payable's escrow settings. Payments aren't escrowed if the
+  /// payable's host never set them, in which case this isn't initialized.
+  pub payable_escrow: UncheckedAccount<'info>,
+
+  #[account(
+    init,
+    seeds = [payable_payment.key().as_ref(), EscrowState::SEED_PREFIX],
+    bump,
+    payer = signer,
+    space = EscrowState::SPACE
+  )]
+  /// Holds the payment until it is released. Required only when the payable
+  /// escrows its payments.
+  pub escrow_state: Option<Box<Account<'info, EscrowState>>>,
+
+  #[account(seeds = [payable.key().as_ref(), PayableReceipts::SEED_PREFIX], bump)]
+  /// CHECK: The payable's receipt settings. The signer gets the receipt of
+  /// this payment if they are enabled. Payables whose host never enabled
+  /// receipts don't have them initialized.
+  pub payable_receipts: UncheckedAccount<'info>,
+
+  #[account(mut, seeds = [user_payment.key().as_ref(), Receipt::MINT_SEED_PREFIX], bump)]
+  /// CHECK: The mint of this payment's receipt, created with its metadata.
+  /// Required only when the payable mints receipts.
+  pub receipt_mint: Option<UncheckedAccount<'info>>,
+
+  #[account(mut)]
+  /// CHECK: The signer's associated token account of the receipt, created by
+  /// the associated token program. Required only when the payable mints
+  /// receipts.
+  pub payer_receipt_token_account: Option<UncheckedAccount<'info>>,
+
+  /// Required only when the payable mints receipts.
+  pub receipt_token_program: Option<Program<'info, Token2022>>,
+
+  /// Required only when the payable mints receipts.
+  pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+
+  #[account(mut, seeds = [signer.key().as_ref()], bump)]
+  pub payer: Box<Account<'info, User>>,
+
+  #[account(seeds = [ChainStats::SEED_PREFIX], bump)]
+  pub chain_stats: Box<Account<'info, ChainStats>>,
+
+  #[account(mut, seeds = [ChainStatsShard::SEED_PREFIX, &[chain_shard.index]], bump)]
+  /// The shard of the chain's counts that this instruction's entities are
+  /// indexed in. Any initialized shard can be used.
+  pub chain_shard: Box<Account<'info, ChainStatsShard>>,
+
+  #[account(seeds = [Config::SEED_PREFIX], bump)]
+  pub config: AccountLoader<'info, Config>,
+
+  pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(seeds = [TokenDetails::SEED_PREFIX, mint.key().as_ref()], bump)]
+  pub token_details: Box<Account<'info, TokenDetails>>,
+
+  #[account(
+    init_if_needed,
+    seeds = [TokenDetailsShard::SEED_PREFIX, mint.key().as_ref(), &[chain_shard.index]],
+    bump,
+    payer = signer,
+    space = TokenDetailsShard::SPACE
+  )]
+  /// The token's shard of payment totals that goes with chain_shard.
+  pub token_details_shard: Box<Account<'info, TokenDetailsShard>>,
+
+  #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = signer,
+        associated_token::token_program = token_program,
+    )]
+  pub payer_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = chain_stats,
+        associated_token::token_program = token_program,
+    )]
+  pub chain_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+
+  #[account(address = sysvar::instructions::ID)]
+  /// CHECK: The instructions sysvar, in which the host's Ed25519 signature of
+  /// the intent is looked up.
+  pub instructions: UncheckedAccount<'info>,
+
+  pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,43 @@
+use crate::{error::ChainbillsError, state::*};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+#[instruction(operator: Pubkey)]
+/// Context used by a host to add an operator to their payable or to change
+/// the operator's roles.
+pub struct SetPayableOperator<'info> {
+  #[account(constraint = payable.host == *signer.key @ ChainbillsError::NotYourPayable)]
+  pub payable: Box<Account<'info, Payable>>,
+
+  #[account(
+    init_if_needed,
+    seeds = [payable.key().as_ref(), PayableOperator::SEED_PREFIX, operator.as_ref()],
+    bump,
+    payer = signer,
+    space = PayableOperator::SPACE
+  )]
+  pub payable_operator: Box<Account<'info, PayableOperator>>,
+
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+/// Context used by a host to remove an operator from their payable.
+pub struct RemovePayableOperator<'info> {
+  #[account(constraint = payable.host == *signer.key @ ChainbillsError::NotYourPayable)]
+  pub payable: Box<Account<'info, Payable>>,
+
+  #[account(
+    mut,
+    seeds = [payable.key().as_ref(), PayableOperator::SEED_PREFIX, payable_operator.operator.as_ref()],
+    bump,
+    close = signer
+  )]
+  pub payable_operator: Box<Account<'info, PayableOperator>>,
+
+  #[account(mut)]
+  pub signer: Signer<'info>,
+}
@@ -0,0 +1,67 @@
+use crate::{error::ChainbillsError, state::*};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+/// Context used by the host (or a manager) to update a payable's receipt
+/// settings.
+pub struct UpdatePayableReceipts<'info> {
+  #[account(mut, constraint = payable.host == *signer.key || PayableOperator::permits(&operator, &payable.host, PayableOperator::ROLE_MANAGE) @ ChainbillsError::NotYourPayable)]
+  pub payable: Box<Account<'info, Payable>>,
+
+  #[account(
+    init,
+    seeds = [ActivityRecord::SEED_PREFIX, &chain_shard.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = ActivityRecord::SPACE
+  )]
+  /// Houses Details of this activity as UpdatedPayableReceipts.
+  pub activity: Box<Account<'info, ActivityRecord>>,
+
+  #[account(
+    init,
+    seeds = [payable.host.as_ref(), ActivityRecord::SEED_PREFIX, &host.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = UserActivityInfo::SPACE
+  )]
+  /// Houses Chain Count of activities for this activity.
+  pub user_activity_info: Box<Account<'info, UserActivityInfo>>,
+
+  #[account(
+    init,
+    seeds = [payable.key().as_ref(), ActivityRecord::SEED_PREFIX, &payable.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = PayableActivityInfo::SPACE
+  )]
+  /// Houses Chain Count of activities for this activity.
+  pub payable_activity_info: Box<Account<'info, PayableActivityInfo>>,
+
+  #[account(
+    init_if_needed,
+    seeds = [payable.key().as_ref(), PayableReceipts::SEED_PREFIX],
+    bump,
+    payer = signer,
+    space = PayableReceipts::SPACE
+  )]
+  pub payable_receipts: Box<Account<'info, PayableReceipts>>,
+
+  #[account(mut, seeds = [payable.host.as_ref()], bump)]
+  pub host: Box<Account<'info, User>>,
+
+  #[account(seeds = [payable.key().as_ref(), PayableOperator::SEED_PREFIX, signer.key().as_ref()], bump)]
+  /// The signer's operator account on the payable. Required only when the
+  /// signer isn't the host.
+  pub operator: Option<Box<Account<'info, PayableOperator>>>,
+
+  #[account(mut, seeds = [ChainStatsShard::SEED_PREFIX, &[chain_shard.index]], bump)]
+  /// The shard of the chain's counts that this instruction's entities are
+  /// indexed in. Any initialized shard can be used.
+  pub chain_shard: Box<Account<'info, ChainStatsShard>>,
+
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  pub system_program: Program<'info, System>,
+}
@@ -37,6 +37,16 @@ pub struct RecordForeignPayableUpdate<'info> {
   /// Foreign Contract account. It's address should be the emitter of the VAA
   pub registered_foreign_contract: Account<'info, RegisteredForeignContract>,
 
+  #[account(
+    seeds = [
+      WormholeChainCbChainId::SEED_PREFIX,
+      &posted_vaa.emitter_chain().to_le_bytes()[..]
+    ],
+    bump
+  )]
+  /// The cbChainId of the VAA's emitter chain. Recorded on the foreign payable.
+  pub emitter_cb_chain_id: Account<'info, WormholeChainCbChainId>,
+
   #[account(mut, seeds = [ChainStats::SEED_PREFIX], bump)]
   /// Keeps track of entities on this chain. Its payable_count will be
   /// incremented in this instruction.
@@ -92,7 +102,7 @@ pub struct RecordForeignPayableUpdate<'info> {
     Box<Account<'info, ChainConsumedWormholeMessageId>>,
 
   #[account(
-    init,
+    init_if_needed,
     seeds = [
       PerChainConsumedWormholeMessagesCounter::SEED_PREFIX,
       &posted_vaa.emitter_chain().to_le_bytes()[..]
@@ -0,0 +1,90 @@
+use crate::{error::*, state::*};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+#[instruction(caip2: String)]
+/// Context used to register a blockchain network by its CAIP-2 string.
+pub struct RegisterCbChain<'info> {
+  #[account(
+    init,
+    payer = owner,
+    seeds = [CbChain::SEED_PREFIX, &cb_chain_id(&caip2)[..]],
+    bump,
+    space = CbChain::SPACE
+  )]
+  /// The registry entry of the network. Can be registered only once.
+  pub cb_chain: Account<'info, CbChain>,
+
+  #[account(seeds = [Config::SEED_PREFIX], bump)]
+  pub config: AccountLoader<'info, Config>,
+
+  #[account(mut, address = config.load()?.owner @ ChainbillsError::OwnerUnauthorized)]
+  /// Signer for this instruction. Should be the account that holds
+  /// the upgrade authority of this program.
+  pub owner: Signer<'info>,
+
+  /// System program.
+  pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(cb_chain_id: [u8; 32], wormhole_chain_id: u16)]
+/// Context used to register the Wormhole Chain ID of a registered network.
+pub struct RegisterChainWormholeId<'info> {
+  #[account(mut, seeds = [CbChain::SEED_PREFIX, cb_chain_id.as_ref()], bump)]
+  pub cb_chain: Account<'info, CbChain>,
+
+  #[account(
+    init,
+    payer = owner,
+    seeds = [
+      WormholeChainCbChainId::SEED_PREFIX,
+      &wormhole_chain_id.to_le_bytes()[..]
+    ],
+    bump,
+    space = WormholeChainCbChainId::SPACE
+  )]
+  /// Reverse lookup of the Wormhole Chain ID. Its initialization ensures that
+  /// the Wormhole Chain ID isn't registered for another network.
+  pub wormhole_chain_cb_chain_id: Account<'info, WormholeChainCbChainId>,
+
+  #[account(seeds = [Config::SEED_PREFIX], bump)]
+  pub config: AccountLoader<'info, Config>,
+
+  #[account(mut, address = config.load()?.owner @ ChainbillsError::OwnerUnauthorized)]
+  pub owner: Signer<'info>,
+
+  /// System program.
+  pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(cb_chain_id: [u8; 32], circle_domain: u32)]
+/// Context used to register the Circle (CCTP) domain of a registered network.
+pub struct RegisterChainCircleDomain<'info> {
+  #[account(mut, seeds = [CbChain::SEED_PREFIX, cb_chain_id.as_ref()], bump)]
+  pub cb_chain: Account<'info, CbChain>,
+
+  #[account(
+    init,
+    payer = owner,
+    seeds = [
+      CircleDomainCbChainId::SEED_PREFIX,
+      &circle_domain.to_le_bytes()[..]
+    ],
+    bump,
+    space = CircleDomainCbChainId::SPACE
+  )]
+  /// Reverse lookup of the Circle domain. Its initialization ensures that
+  /// the domain isn't registered for another network.
+  pub circle_domain_cb_chain_id: Account<'info, CircleDomainCbChainId>,
+
+  #[account(seeds = [Config::SEED_PREFIX], bump)]
+  pub config: AccountLoader<'info, Config>,
+
+  #[account(mut, address = config.load()?.owner @ ChainbillsError::OwnerUnauthorized)]
+  pub owner: Signer<'info>,
+
+  /// System program.
+  pub system_program: Program<'info, System>,
+}
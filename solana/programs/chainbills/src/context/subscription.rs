@@ -0,0 +1,315 @@
+use crate::{error::ChainbillsError, state::*};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+/// Context used by a payer to subscribe to recurring payments into a
+/// payable.
+pub struct CreateSubscription<'info> {
+  #[account(
+    init_if_needed,
+    seeds = [payable.key().as_ref(), Subscription::SEED_PREFIX, signer.key().as_ref()],
+    bump,
+    payer = signer,
+    space = Subscription::SPACE
+  )]
+  /// Holds the subscription. Reused if a previous subscription of the payer
+  /// to the payable was cancelled.
+  pub subscription: Box<Account<'info, Subscription>>,
+
+  #[account(mut)]
+  pub payable: Box<Account<'info, Payable>>,
+
+  #[account(seeds = [payable.key().as_ref(), PayableAllowedPayers::SEED_PREFIX], bump)]
+  /// CHECK: The payable's allowed payers. Anyone can pay if the payable's
+  /// host never restricted its payers, in which case this isn't initialized.
+  pub allowed_payers: UncheckedAccount<'info>,
+
+  #[account(
+    init,
+    seeds = [ActivityRecord::SEED_PREFIX, &chain_shard.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = ActivityRecord::SPACE
+  )]
+  /// Houses Details of this activity as CreatedSubscription.
+  pub activity: Box<Account<'info, ActivityRecord>>,
+
+  #[account(
+    init,
+    seeds = [signer.key().as_ref(), ActivityRecord::SEED_PREFIX, &payer.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = UserActivityInfo::SPACE
+  )]
+  /// Houses Chain Count of activities for this activity.
+  pub user_activity_info: Box<Account<'info, UserActivityInfo>>,
+
+  #[account(
+    init,
+    seeds = [payable.key().as_ref(), ActivityRecord::SEED_PREFIX, &payable.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = PayableActivityInfo::SPACE
+  )]
+  /// Houses Chain Count of activities for this activity.
+  pub payable_activity_info: Box<Account<'info, PayableActivityInfo>>,
+
+  #[account(mut, seeds = [signer.key().as_ref()], bump)]
+  pub payer: Box<Account<'info, User>>,
+
+  #[account(mut, seeds = [ChainStatsShard::SEED_PREFIX, &[chain_shard.index]], bump)]
+  /// The shard of the chain's counts that this instruction's entities are
+  /// indexed in. Any initialized shard can be used.
+  pub chain_shard: Box<Account<'info, ChainStatsShard>>,
+
+  pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(seeds = [TokenDetails::SEED_PREFIX, mint.key().as_ref()], bump)]
+  pub token_details: Box<Account<'info, TokenDetails>>,
+
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+/// Context used by the payer, or by the payable's host or managers, to
+/// cancel a subscription.
+pub struct CancelSubscription<'info> {
+  #[account(
+    mut,
+    seeds = [payable.key().as_ref(), Subscription::SEED_PREFIX, subscription.payer.as_ref()],
+    bump,
+    constraint = subscription.payer == *signer.key
+      || payable.host == *signer.key
+      || PayableOperator::permits(&operator, &payable.host, PayableOperator::ROLE_MANAGE)
+      @ ChainbillsError::NotYourSubscription
+  )]
+  pub subscription: Box<Account<'info, Subscription>>,
+
+  #[account(mut)]
+  pub payable: Box<Account<'info, Payable>>,
+
+  #[account(seeds = [payable.key().as_ref(), PayableOperator::SEED_PREFIX, signer.key().as_ref()], bump)]
+  /// The signer's operator account on the payable. Required only when the
+  /// signer is neither the payer nor the host.
+  pub operator: Option<Box<Account<'info, PayableOperator>>>,
+
+  #[account(
+    init,
+    seeds = [ActivityRecord::SEED_PREFIX, &chain_shard.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = ActivityRecord::SPACE
+  )]
+  /// Houses Details of this activity as CancelledSubscription.
+  pub activity: Box<Account<'info, ActivityRecord>>,
+
+  #[account(
+    init,
+    seeds = [subscription.payer.as_ref(), ActivityRecord::SEED_PREFIX, &payer.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = UserActivityInfo::SPACE
+  )]
+  /// Houses Chain Count of activities for this activity.
+  pub user_activity_info: Box<Account<'info, UserActivityInfo>>,
+
+  #[account(
+    init,
+    seeds = [payable.key().as_ref(), ActivityRecord::SEED_PREFIX, &payable.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = PayableActivityInfo::SPACE
+  )]
+  /// Houses Chain Count of activities for this activity.
+  pub payable_activity_info: Box<Account<'info, PayableActivityInfo>>,
+
+  #[account(mut, seeds = [subscription.payer.as_ref()], bump)]
+  pub payer: Box<Account<'info, User>>,
+
+  #[account(mut, seeds = [ChainStatsShard::SEED_PREFIX, &[chain_shard.index]], bump)]
+  /// The shard of the chain's counts that this instruction's entities are
+  /// indexed in. Any initialized shard can be used.
+  pub chain_shard: Box<Account<'info, ChainStatsShard>>,
+
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+/// Context used by anyone to collect a due subscription payment. The signer
+/// pays for the payment records.
+pub struct CollectSubscription<'info> {
+  #[account(
+    mut,
+    seeds = [payable.key().as_ref(), Subscription::SEED_PREFIX, subscription.payer.as_ref()],
+    bump,
+    constraint = subscription.token == mint.key() @ ChainbillsError::InvalidSubscriptionMint
+  )]
+  pub subscription: Box<Account<'info, Subscription>>,
+
+  #[account(
+        init,
+        seeds = [
+            subscription.payer.as_ref(),
+            UserPayment::SEED_PREFIX,
+            &payer.next_payment().to_le_bytes()[..]
+        ],
+        bump,
+        payer = signer,
+        space = UserPayment::SPACE
+    )]
+  pub user_payment: Box<Account<'info, UserPayment>>,
+
+  #[account(
+        init,
+        seeds = [
+            payable.key().as_ref(),
+            PayablePayment::SEED_PREFIX,
+            &payable.next_payment().to_le_bytes()[..]
+        ],
+        bump,
+        payer = signer,
+        space = PayablePayment::SPACE
+    )]
+  pub payable_payment: Box<Account<'info, PayablePayment>>,
+
+  #[account(
+        init,
+        seeds = [
+            payable.key().as_ref(),
+            &config.load()?.chain_id.to_le_bytes()[..],
+            &payable_per_chain_payments_counter.next_payment().to_le_bytes()[..]
+        ],
+        bump,
+        payer = signer,
+        space = PayablePerChainPaymentInfo::SPACE
+    )]
+  /// Not passed when the ledger page below records the entry instead.
+  pub payable_per_chain_payment_info:
+    Option<Box<Account<'info, PayablePerChainPaymentInfo>>>,
+
+  #[account(
+    init_if_needed,
+    seeds = [payable.key().as_ref(), &config.load()?.chain_id.to_le_bytes()[..], SEED_PREFIX_LEDGER_PAGE, &ledger_page(payable_per_chain_payments_counter.next_payment()).to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = CountLedgerPage::SPACE
+  )]
+  /// The page of the payable's ledger of payments from this chain that
+  /// records this payment, in place of payable_per_chain_payment_info.
+  pub payable_per_chain_payments_page:
+    Option<Box<Account<'info, CountLedgerPage>>>,
+
+  #[account(
+        mut,
+        seeds = [
+            payable.key().as_ref(),
+            &config.load()?.chain_id.to_le_bytes()[..],
+        ],
+        bump
+    )]
+  pub payable_per_chain_payments_counter:
+    Box<Account<'info, PayablePerChainPaymentsCounter>>,
+
+  #[account(
+    init,
+    seeds = [ActivityRecord::SEED_PREFIX, subscription.payer.as_ref(), &payer.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = ActivityRecord::SPACE
+  )]
+  /// Houses Details of this activity as one of UserPaid. Derived from the
+  /// payer's count of activities, as in Pay.
+  pub user_activity: Box<Account<'info, ActivityRecord>>,
+
+  #[account(
+    init,
+    seeds = [ActivityRecord::SEED_PREFIX, payable.key().as_ref(), &payable.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = ActivityRecord::SPACE
+  )]
+  /// Houses Details of this activity as one of PayableReceived. Derived from
+  /// the payable's count of activities.
+  pub payable_activity: Box<Account<'info, ActivityRecord>>,
+
+  #[account(mut, realloc = payable.space_update_balance(mint.key()), realloc::payer = signer, realloc::zero = false)]
+  pub payable: Box<Account<'info, Payable>>,
+
+  #[account(mut, seeds = [payable.key().as_ref(), PayableEscrow::SEED_PREFIX], bump)]
+  /// CHECK: The payable's escrow settings. Payments aren't escrowed if the
+  /// payable's host never set them, in which case this isn't initialized.
+  pub payable_escrow: UncheckedAccount<'info>,
+
+  #[account(
+    init,
+    seeds = [payable_payment.key().as_ref(), EscrowState::SEED_PREFIX],
+    bump,
+    payer = signer,
+    space = EscrowState::SPACE
+  )]
+  /// Holds the payment until it is released. Required only when the payable
+  /// escrows its payments.
+  pub escrow_state: Option<Box<Account<'info, EscrowState>>>,
+
+  #[account(mut, seeds = [subscription.payer.as_ref()], bump)]
+  pub payer: Box<Account<'info, User>>,
+
+  #[account(seeds = [ChainStats::SEED_PREFIX], bump)]
+  /// Also the delegate that the payer approved on their token account.
+  pub chain_stats: Box<Account<'info, ChainStats>>,
+
+  #[account(mut, seeds = [ChainStatsShard::SEED_PREFIX, &[chain_shard.index]], bump)]
+  /// The shard of the chain's counts that this instruction's entities are
+  /// indexed in. Any initialized shard can be used.
+  pub chain_shard: Box<Account<'info, ChainStatsShard>>,
+
+  #[account(seeds = [Config::SEED_PREFIX], bump)]
+  pub config: AccountLoader<'info, Config>,
+
+  pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(seeds = [TokenDetails::SEED_PREFIX, mint.key().as_ref()], bump)]
+  pub token_details: Box<Account<'info, TokenDetails>>,
+
+  #[account(
+    init_if_needed,
+    seeds = [TokenDetailsShard::SEED_PREFIX, mint.key().as_ref(), &[chain_shard.index]],
+    bump,
+    payer = signer,
+    space = TokenDetailsShard::SPACE
+  )]
+  /// The token's shard of payment totals that goes with chain_shard.
+  pub token_details_shard: Box<Account<'info, TokenDetailsShard>>,
+
+  #[account(
+    mut,
+    token::mint = mint,
+    token::token_program = token_program,
+    constraint = payer_token_account.owner == subscription.payer @ ChainbillsError::NotYourSubscription
+  )]
+  /// Any token account of the payer that delegates to the ChainStats account.
+  pub payer_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = chain_stats,
+        associated_token::token_program = token_program,
+    )]
+  pub chain_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+
+  pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,28 @@
+use crate::{error::*, state::*};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+/// Context used to create or update a token group.
+pub struct UpdateTokenGroup<'info> {
+  #[account(
+    init_if_needed,
+    payer = owner,
+    seeds = [TokenGroup::SEED_PREFIX, name.as_bytes()],
+    bump,
+    space = TokenGroup::SPACE
+  )]
+  /// The tokens that payables can accept interchangeably.
+  pub token_group: Box<Account<'info, TokenGroup>>,
+
+  #[account(seeds = [Config::SEED_PREFIX], bump)]
+  pub config: AccountLoader<'info, Config>,
+
+  #[account(mut, address = config.load()?.owner @ ChainbillsError::OwnerUnauthorized)]
+  /// Signer for this instruction. Should be the account that holds
+  /// the upgrade authority of this program.
+  pub owner: Signer<'info>,
+
+  /// System program.
+  pub system_program: Program<'info, System>,
+}
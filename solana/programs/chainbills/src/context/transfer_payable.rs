@@ -0,0 +1,136 @@
+use crate::{error::ChainbillsError, state::*};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+/// Context used by a host to offer their payable to another wallet.
+pub struct TransferPayable<'info> {
+  #[account(constraint = payable.host == *signer.key @ ChainbillsError::NotYourPayable)]
+  pub payable: Box<Account<'info, Payable>>,
+
+  #[account(
+    init_if_needed,
+    seeds = [payable.key().as_ref(), PendingPayableHost::SEED_PREFIX],
+    bump,
+    payer = signer,
+    space = PendingPayableHost::SPACE
+  )]
+  /// Holds the wallet that can accept the payable. Offering the payable
+  /// again replaces the wallet.
+  pub pending_payable_host: Box<Account<'info, PendingPayableHost>>,
+
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+/// Context used by the offered wallet to accept a payable.
+pub struct AcceptPayable<'info> {
+  #[account(mut)]
+  pub payable: Box<Account<'info, Payable>>,
+
+  #[account(
+    mut,
+    seeds = [payable.key().as_ref(), PendingPayableHost::SEED_PREFIX],
+    bump,
+    constraint = pending_payable_host.new_host == *signer.key @ ChainbillsError::NotPendingPayableHost,
+    close = previous_host_wallet
+  )]
+  /// Closed as the transfer completes. Its rent returns to the previous host.
+  pub pending_payable_host: Box<Account<'info, PendingPayableHost>>,
+
+  #[account(
+    init,
+    seeds = [signer.key().as_ref(), UserPayableInfo::SEED_PREFIX, &new_host.next_payable().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = UserPayableInfo::SPACE
+  )]
+  /// Holds the payable's ID at the new host's next payables count.
+  pub user_payable_info: Box<Account<'info, UserPayableInfo>>,
+
+  #[account(
+    init,
+    seeds = [ActivityRecord::SEED_PREFIX, &chain_shard.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = ActivityRecord::SPACE
+  )]
+  /// Houses Details of this activity as TransferredPayable.
+  pub previous_host_activity: Box<Account<'info, ActivityRecord>>,
+
+  #[account(
+    init,
+    seeds = [payable.host.as_ref(), ActivityRecord::SEED_PREFIX, &previous_host.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = UserActivityInfo::SPACE
+  )]
+  /// Houses Chain Count of activities for the previous host's activity.
+  pub previous_host_activity_info: Box<Account<'info, UserActivityInfo>>,
+
+  #[account(
+    init,
+    seeds = [payable.key().as_ref(), ActivityRecord::SEED_PREFIX, &payable.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = PayableActivityInfo::SPACE
+  )]
+  /// Houses Chain Count of activities for the previous host's activity.
+  pub previous_host_payable_activity_info:
+    Box<Account<'info, PayableActivityInfo>>,
+
+  #[account(
+    init,
+    seeds = [ActivityRecord::SEED_PREFIX, &(chain_shard.next_activity() + 1).to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = ActivityRecord::SPACE
+  )]
+  /// Houses Details of this activity as AcceptedPayable.
+  pub new_host_activity: Box<Account<'info, ActivityRecord>>,
+
+  #[account(
+    init,
+    seeds = [signer.key().as_ref(), ActivityRecord::SEED_PREFIX, &new_host.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = UserActivityInfo::SPACE
+  )]
+  /// Houses Chain Count of activities for the new host's activity.
+  pub new_host_activity_info: Box<Account<'info, UserActivityInfo>>,
+
+  #[account(
+    init,
+    seeds = [payable.key().as_ref(), ActivityRecord::SEED_PREFIX, &(payable.next_activity() + 1).to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = PayableActivityInfo::SPACE
+  )]
+  /// Houses Chain Count of activities for the new host's activity.
+  pub new_host_payable_activity_info: Box<Account<'info, PayableActivityInfo>>,
+
+  #[account(mut, seeds = [payable.host.as_ref()], bump)]
+  /// The user account of the payable's current host.
+  pub previous_host: Box<Account<'info, User>>,
+
+  #[account(mut, address = payable.host)]
+  /// The payable's current host's wallet. Receives the rent of the
+  /// pending_payable_host.
+  pub previous_host_wallet: SystemAccount<'info>,
+
+  #[account(mut, seeds = [signer.key().as_ref()], bump)]
+  /// The user account of the signer that is accepting the payable.
+  pub new_host: Box<Account<'info, User>>,
+
+  #[account(mut, seeds = [ChainStatsShard::SEED_PREFIX, &[chain_shard.index]], bump)]
+  /// The shard of the chain's counts that this instruction's entities are
+  /// indexed in. Any initialized shard can be used.
+  pub chain_shard: Box<Account<'info, ChainStatsShard>>,
+
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  pub system_program: Program<'info, System>,
+}
@@ -2,7 +2,7 @@ use crate::state::*;
 use anchor_lang::prelude::*;
 use anchor_spl::{
   associated_token::AssociatedToken,
-  token::{Mint, Token, TokenAccount},
+  token_interface::{Mint, TokenAccount, TokenInterface},
 };
 
 #[derive(Accounts)]
@@ -18,11 +18,12 @@ pub struct UpdateMaxWithdrawalFees<'info> {
         init_if_needed,
         associated_token::mint = mint,
         associated_token::authority = chain_stats,
+        associated_token::token_program = token_program,
         payer = owner,
     )]
   /// Initialize the chain token account for storing payments of the token mint
   /// if it doesn't exist.
-  pub chain_token_account: Box<Account<'info, TokenAccount>>,
+  pub chain_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
   /// Chainbills' fee collector account. Not verifying it is correct
   /// in the constraints inorder to bypass the stack offset error. However, the
@@ -33,11 +34,12 @@ pub struct UpdateMaxWithdrawalFees<'info> {
         init_if_needed,
         associated_token::mint = mint,
         associated_token::authority = fee_collector,
+        associated_token::token_program = token_program,
         payer = owner,
     )]
   /// Initialize the fees token account for storing payments of the token mint
   /// if it doesn't exist.
-  pub fees_token_account: Box<Account<'info, TokenAccount>>,
+  pub fees_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
   #[account(seeds = [Config::SEED_PREFIX], bump)]
   /// Config Account that stores important constant addresses that are used
@@ -52,7 +54,7 @@ pub struct UpdateMaxWithdrawalFees<'info> {
 
   #[account(constraint = mint.key() == token)]
   /// The token mint whose max withdrawal fee is being set/updated.
-  pub mint: Box<Account<'info, Mint>>,
+  pub mint: Box<InterfaceAccount<'info, Mint>>,
 
   #[account(mut)]
   /// Signer for this instruction. Should be the account that holds
@@ -64,8 +66,8 @@ pub struct UpdateMaxWithdrawalFees<'info> {
   /// Associated Token Program.
   pub associated_token_program: Program<'info, AssociatedToken>,
 
-  /// Token Program.
-  pub token_program: Program<'info, Token>,
+  /// Token Program. Either the SPL Token or the Token-2022 Program.
+  pub token_program: Interface<'info, TokenInterface>,
 
   /// System Program.
   pub system_program: Program<'info, System>,
@@ -7,6 +7,11 @@ pub struct UpdateMaxWithdrawalFeesNative<'info> {
   /// Account that stores the details of the token to updates its max withdrawal fees.
   pub token_details: Box<Account<'info, TokenDetails>>,
 
+  #[account(mut, seeds = [SEED_PREFIX_NATIVE_VAULT], bump)]
+  /// Holds the native SOL paid into payables on this chain. Funded here up to
+  /// rent-exemption so that payments of any size can land in it.
+  pub native_vault: SystemAccount<'info>,
+
   #[account(seeds = [Config::SEED_PREFIX], bump)]
   pub config: AccountLoader<'info, Config>,
 
@@ -4,12 +4,12 @@ use wormhole_anchor_sdk::wormhole::{self, program::Wormhole};
 
 #[derive(Accounts)]
 pub struct UpdatePayable<'info> {
-  #[account(mut, constraint = payable.host == *signer.key @ ChainbillsError::NotYourPayable)]
+  #[account(mut, constraint = payable.host == *signer.key || PayableOperator::permits(&operator, &payable.host, PayableOperator::ROLE_MANAGE) @ ChainbillsError::NotYourPayable)]
   pub payable: Box<Account<'info, Payable>>,
 
   #[account(
     init,
-    seeds = [ActivityRecord::SEED_PREFIX, &chain_stats.next_activity().to_le_bytes()[..]],
+    seeds = [ActivityRecord::SEED_PREFIX, &chain_shard.next_activity().to_le_bytes()[..]],
     bump,
     payer = signer,
     space = ActivityRecord::SPACE
@@ -19,7 +19,7 @@ pub struct UpdatePayable<'info> {
 
   #[account(
     init,
-    seeds = [signer.key().as_ref(), ActivityRecord::SEED_PREFIX, &host.next_activity().to_le_bytes()[..]],
+    seeds = [payable.host.as_ref(), ActivityRecord::SEED_PREFIX, &host.next_activity().to_le_bytes()[..]],
     bump,
     payer = signer,
     space = UserActivityInfo::SPACE
@@ -37,12 +37,22 @@ pub struct UpdatePayable<'info> {
   /// Houses Chain Count of activities for this activity.
   pub payable_activity_info: Box<Account<'info, PayableActivityInfo>>,
 
-  #[account(seeds = [signer.key().as_ref()], bump)]
+  #[account(mut, seeds = [payable.host.as_ref()], bump)]
   pub host: Box<Account<'info, User>>,
 
+  #[account(seeds = [payable.key().as_ref(), PayableOperator::SEED_PREFIX, signer.key().as_ref()], bump)]
+  /// The signer's operator account on the payable. Required only when the
+  /// signer isn't the host.
+  pub operator: Option<Box<Account<'info, PayableOperator>>>,
+
   #[account(mut, seeds = [ChainStats::SEED_PREFIX], bump)]
   pub chain_stats: Box<Account<'info, ChainStats>>,
 
+  #[account(mut, seeds = [ChainStatsShard::SEED_PREFIX, &[chain_shard.index]], bump)]
+  /// The shard of the chain's counts that this instruction's entities are
+  /// indexed in. Any initialized shard can be used.
+  pub chain_shard: Box<Account<'info, ChainStatsShard>>,
+
   #[account(seeds = [Config::SEED_PREFIX], bump)]
   pub config: AccountLoader<'info, Config>,
 
@@ -104,18 +114,19 @@ pub struct UpdatePayable<'info> {
   pub system_program: Program<'info, System>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 #[instruction(allowed_tokens_and_amounts: Vec<TokenAndAmount>)]
 pub struct UpdatePayableAllowedTokensAndAmounts<'info> {
   // Allowing realloc::zero to be true if in case the allowed tokens and
   // amounts vec's len is lower than the previous one. This will allow the
   // program to refresh zeroing out discarded space as needed.
-  #[account(mut, constraint = payable.host == *signer.key @ ChainbillsError::NotYourPayable, realloc = payable.space_update_ataa(allowed_tokens_and_amounts.len()), realloc::payer = signer, realloc::zero = true)]
+  #[account(mut, constraint = payable.host == *signer.key || PayableOperator::permits(&operator, &payable.host, PayableOperator::ROLE_MANAGE) @ ChainbillsError::NotYourPayable, realloc = payable.space_update_ataa(allowed_tokens_and_amounts.len()), realloc::payer = signer, realloc::zero = true)]
   pub payable: Box<Account<'info, Payable>>,
 
   #[account(
     init,
-    seeds = [ActivityRecord::SEED_PREFIX, &chain_stats.next_activity().to_le_bytes()[..]],
+    seeds = [ActivityRecord::SEED_PREFIX, &chain_shard.next_activity().to_le_bytes()[..]],
     bump,
     payer = signer,
     space = ActivityRecord::SPACE
@@ -125,7 +136,7 @@ pub struct UpdatePayableAllowedTokensAndAmounts<'info> {
 
   #[account(
     init,
-    seeds = [signer.key().as_ref(), ActivityRecord::SEED_PREFIX, &host.next_activity().to_le_bytes()[..]],
+    seeds = [payable.host.as_ref(), ActivityRecord::SEED_PREFIX, &host.next_activity().to_le_bytes()[..]],
     bump,
     payer = signer,
     space = UserActivityInfo::SPACE
@@ -143,12 +154,144 @@ pub struct UpdatePayableAllowedTokensAndAmounts<'info> {
   /// Houses Chain Count of activities for this activity.
   pub payable_activity_info: Box<Account<'info, PayableActivityInfo>>,
 
-  #[account(seeds = [signer.key().as_ref()], bump)]
+  #[account(mut, seeds = [payable.host.as_ref()], bump)]
   pub host: Box<Account<'info, User>>,
 
+  #[account(seeds = [payable.key().as_ref(), PayableOperator::SEED_PREFIX, signer.key().as_ref()], bump)]
+  /// The signer's operator account on the payable. Required only when the
+  /// signer isn't the host.
+  pub operator: Option<Box<Account<'info, PayableOperator>>>,
+
   #[account(mut, seeds = [ChainStats::SEED_PREFIX], bump)]
   pub chain_stats: Box<Account<'info, ChainStats>>,
 
+  #[account(mut, seeds = [ChainStatsShard::SEED_PREFIX, &[chain_shard.index]], bump)]
+  /// The shard of the chain's counts that this instruction's entities are
+  /// indexed in. Any initialized shard can be used.
+  pub chain_shard: Box<Account<'info, ChainStatsShard>>,
+
+  #[account(seeds = [Config::SEED_PREFIX], bump)]
+  pub config: AccountLoader<'info, Config>,
+
+  /// Wormhole program.
+  pub wormhole_program: Program<'info, Wormhole>,
+
+  #[account(
+        mut,
+        address = config.load()?.wormhole_bridge @ ChainbillsError::InvalidWormholeConfig
+    )]
+  /// Wormhole bridge data. [`wormhole::post_message`] requires this account
+  /// be mutable.
+  pub wormhole_bridge: Account<'info, wormhole::BridgeData>,
+
+  #[account(
+        mut,
+        address = config.load()?.wormhole_fee_collector @ ChainbillsError::InvalidWormholeFeeCollector
+    )]
+  /// Wormhole fee collector. [`wormhole::post_message`] requires this
+  /// account be mutable.
+  pub wormhole_fee_collector: Account<'info, wormhole::FeeCollector>,
+
+  #[account(
+        seeds = [wormhole::SEED_PREFIX_EMITTER],
+        bump,
+    )]
+  /// Program's emitter account. Read-only.
+  pub wormhole_emitter: Account<'info, Empty>,
+
+  #[account(
+        mut,
+        address = config.load()?.wormhole_sequence @ ChainbillsError::InvalidWormholeSequence
+    )]
+  /// Emitter's sequence account. [`wormhole::post_message`] requires this
+  /// account be mutable.
+  pub wormhole_sequence: Account<'info, wormhole::SequenceTracker>,
+
+  #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_SENT,
+            &wormhole_sequence.next_value().to_le_bytes()[..]
+        ],
+        bump,
+    )]
+  /// CHECK: Wormhole Message. [`wormhole::post_message`] requires this
+  /// account be mutable.
+  pub wormhole_message: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  /// Clock sysvar.
+  pub clock: Sysvar<'info, Clock>,
+
+  /// Rent sysvar.
+  pub rent: Sysvar<'info, Rent>,
+
+  pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+/// Context used to update the payers allowed to pay a payable.
+pub struct UpdatePayableAllowedPayers<'info> {
+  #[account(mut, constraint = payable.host == *signer.key || PayableOperator::permits(&operator, &payable.host, PayableOperator::ROLE_MANAGE) @ ChainbillsError::NotYourPayable)]
+  pub payable: Box<Account<'info, Payable>>,
+
+  #[account(
+    init,
+    seeds = [ActivityRecord::SEED_PREFIX, &chain_shard.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = ActivityRecord::SPACE
+  )]
+  /// Houses Details of this activity as UpdatedPayableAllowedPayers.
+  pub activity: Box<Account<'info, ActivityRecord>>,
+
+  #[account(
+    init,
+    seeds = [payable.host.as_ref(), ActivityRecord::SEED_PREFIX, &host.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = UserActivityInfo::SPACE
+  )]
+  /// Houses Chain Count of activities for this activity.
+  pub user_activity_info: Box<Account<'info, UserActivityInfo>>,
+
+  #[account(
+    init,
+    seeds = [payable.key().as_ref(), ActivityRecord::SEED_PREFIX, &payable.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = PayableActivityInfo::SPACE
+  )]
+  /// Houses Chain Count of activities for this activity.
+  pub payable_activity_info: Box<Account<'info, PayableActivityInfo>>,
+
+  #[account(
+    init_if_needed,
+    seeds = [payable.key().as_ref(), PayableAllowedPayers::SEED_PREFIX],
+    bump,
+    payer = signer,
+    space = PayableAllowedPayers::SPACE
+  )]
+  pub allowed_payers: Box<Account<'info, PayableAllowedPayers>>,
+
+  #[account(mut, seeds = [payable.host.as_ref()], bump)]
+  pub host: Box<Account<'info, User>>,
+
+  #[account(seeds = [payable.key().as_ref(), PayableOperator::SEED_PREFIX, signer.key().as_ref()], bump)]
+  /// The signer's operator account on the payable. Required only when the
+  /// signer isn't the host.
+  pub operator: Option<Box<Account<'info, PayableOperator>>>,
+
+  #[account(mut, seeds = [ChainStats::SEED_PREFIX], bump)]
+  pub chain_stats: Box<Account<'info, ChainStats>>,
+
+  #[account(mut, seeds = [ChainStatsShard::SEED_PREFIX, &[chain_shard.index]], bump)]
+  /// The shard of the chain's counts that this instruction's entities are
+  /// indexed in. Any initialized shard can be used.
+  pub chain_shard: Box<Account<'info, ChainStatsShard>>,
+
   #[account(seeds = [Config::SEED_PREFIX], bump)]
   pub config: AccountLoader<'info, Config>,
 
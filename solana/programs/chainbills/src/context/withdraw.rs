@@ -1,12 +1,13 @@
 use crate::{error::ChainbillsError, state::*};
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Mint, Token, TokenAccount};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct Withdraw<'info> {
   #[account(
         init,
-        seeds = [signer.key().as_ref(),
+        seeds = [payable.host.as_ref(),
             Withdrawal::SEED_PREFIX,
             &host.next_withdrawal().to_le_bytes()[..]],
         bump,
@@ -17,7 +18,7 @@ pub struct Withdraw<'info> {
 
   #[account(
     init,
-    seeds = [ChainWithdrawalId::SEED_PREFIX, &chain_stats.next_withdrawal().to_le_bytes()[..]],
+    seeds = [ChainWithdrawalId::SEED_PREFIX, &chain_shard.next_withdrawal().to_le_bytes()[..]],
     bump,
     payer = signer,
     space = ChainWithdrawalId::SPACE
@@ -39,7 +40,7 @@ pub struct Withdraw<'info> {
 
   #[account(
     init,
-    seeds = [ActivityRecord::SEED_PREFIX, &chain_stats.next_activity().to_le_bytes()[..]],
+    seeds = [ActivityRecord::SEED_PREFIX, &chain_shard.next_activity().to_le_bytes()[..]],
     bump,
     payer = signer,
     space = ActivityRecord::SPACE
@@ -49,7 +50,7 @@ pub struct Withdraw<'info> {
 
   #[account(
     init,
-    seeds = [signer.key().as_ref(), ActivityRecord::SEED_PREFIX, &host.next_activity().to_le_bytes()[..]],
+    seeds = [payable.host.as_ref(), ActivityRecord::SEED_PREFIX, &host.next_activity().to_le_bytes()[..]],
     bump,
     payer = signer,
     space = UserActivityInfo::SPACE
@@ -67,43 +68,61 @@ pub struct Withdraw<'info> {
   /// Houses Chain Count of activities for this activity.
   pub payable_activity_info: Box<Account<'info, PayableActivityInfo>>,
 
-  #[account(mut, constraint = payable.host == *signer.key @ ChainbillsError::NotYourPayable)]
+  #[account(mut, constraint = payable.host == *signer.key || PayableOperator::permits(&operator, &payable.host, PayableOperator::ROLE_WITHDRAW) @ ChainbillsError::NotYourPayable)]
   pub payable: Box<Account<'info, Payable>>,
 
-  #[account(mut, seeds = [signer.key().as_ref()], bump)]
+  #[account(mut, seeds = [payable.host.as_ref()], bump)]
   pub host: Box<Account<'info, User>>,
 
-  #[account(mut, seeds = [ChainStats::SEED_PREFIX], bump)]
+  #[account(mut, address = payable.host)]
+  /// The payable's host's wallet. Receives the withdrawn funds and the rent
+  /// freed from the payable.
+  pub host_wallet: SystemAccount<'info>,
+
+  #[account(seeds = [payable.key().as_ref(), PayableOperator::SEED_PREFIX, signer.key().as_ref()], bump)]
+  /// The signer's operator account on the payable. Required only when the
+  /// signer isn't the host.
+  pub operator: Option<Box<Account<'info, PayableOperator>>>,
+
+  #[account(seeds = [ChainStats::SEED_PREFIX], bump)]
   pub chain_stats: Box<Account<'info, ChainStats>>,
 
+  #[account(mut, seeds = [ChainStatsShard::SEED_PREFIX, &[chain_shard.index]], bump)]
+  /// The shard of the chain's counts that this instruction's entities are
+  /// indexed in. Any initialized shard can be used.
+  pub chain_shard: Box<Account<'info, ChainStatsShard>>,
+
   #[account(seeds = [Config::SEED_PREFIX], bump)]
   pub config: AccountLoader<'info, Config>,
 
-  pub mint: Box<Account<'info, Mint>>,
+  pub mint: Box<InterfaceAccount<'info, Mint>>,
 
-  #[account(seeds = [TokenDetails::SEED_PREFIX, mint.key().as_ref()], bump)]
+  #[account(mut, seeds = [TokenDetails::SEED_PREFIX, mint.key().as_ref()], bump)]
   pub token_details: Box<Account<'info, TokenDetails>>,
 
   #[account(
         mut,
         associated_token::mint = mint,
-        associated_token::authority = signer,
+        associated_token::authority = host_wallet,
+        associated_token::token_program = token_program,
     )]
-  pub host_token_account: Box<Account<'info, TokenAccount>>,
+  pub host_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
   #[account(
         mut,
         associated_token::mint = mint,
         associated_token::authority = chain_stats,
+        associated_token::token_program = token_program,
     )]
-  pub chain_token_account: Box<Account<'info, TokenAccount>>,
+  pub chain_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
   #[account(
         mut,
         associated_token::mint = mint,
         associated_token::authority = fee_collector,
+        associated_token::token_program = token_program,
     )]
-  pub fees_token_account: Box<Account<'info, TokenAccount>>,
+  pub fees_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
   #[account(address = config.load()?.chainbills_fee_collector)]
   pub fee_collector: SystemAccount<'info>,
@@ -111,7 +130,7 @@ pub struct Withdraw<'info> {
   #[account(mut)]
   pub signer: Signer<'info>,
 
-  pub token_program: Program<'info, Token>,
+  pub token_program: Interface<'info, TokenInterface>,
 
   pub system_program: Program<'info, System>,
 }
@@ -0,0 +1,175 @@
+use crate::{error::ChainbillsError, state::*};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use wormhole_cctp_solana::cctp::{
+  message_transmitter_program::{self, MessageTransmitterConfig},
+  token_messenger_minter_program,
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(amount: u64, dest_chain_id: [u8; 32])]
+/// Context used to withdraw USDC from a payable to the host's wallet on
+/// another chain through Circle's CCTP.
+pub struct WithdrawCrossChain<'info> {
+  #[account(
+        init,
+        seeds = [signer.key().as_ref(),
+            Withdrawal::SEED_PREFIX,
+            &host.next_withdrawal().to_le_bytes()[..]],
+        bump,
+        payer = signer,
+        space = Withdrawal::SPACE
+    )]
+  pub withdrawal: Box<Account<'info, Withdrawal>>,
+
+  #[account(
+    init,
+    seeds = [ChainWithdrawalId::SEED_PREFIX, &chain_shard.next_withdrawal().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = ChainWithdrawalId::SPACE
+  )]
+  /// Keeps the withdrawal_id at chain level. Useful for getting all  on
+  /// on this chain.
+  pub chain_withdrawal_id: Box<Account<'info, ChainWithdrawalId>>,
+
+  #[account(
+        init,
+        seeds = [payable.key().as_ref(),
+            PayableWithdrawalInfo::SEED_PREFIX,
+            &payable.next_withdrawal().to_le_bytes()[..]],
+        bump,
+        payer = signer,
+        space = PayableWithdrawalInfo::SPACE
+    )]
+  pub payable_withdrawal_info: Box<Account<'info, PayableWithdrawalInfo>>,
+
+  #[account(
+    init,
+    seeds = [ActivityRecord::SEED_PREFIX, &chain_shard.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = ActivityRecord::SPACE
+  )]
+  /// Houses Details of this activity as Withdrew.
+  pub activity: Box<Account<'info, ActivityRecord>>,
+
+  #[account(
+    init,
+    seeds = [signer.key().as_ref(), ActivityRecord::SEED_PREFIX, &host.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = UserActivityInfo::SPACE
+  )]
+  /// Houses Chain Count of activities for this activity.
+  pub user_activity_info: Box<Account<'info, UserActivityInfo>>,
+
+  #[account(
+    init,
+    seeds = [payable.key().as_ref(), ActivityRecord::SEED_PREFIX, &payable.next_activity().to_le_bytes()[..]],
+    bump,
+    payer = signer,
+    space = PayableActivityInfo::SPACE
+  )]
+  /// Houses Chain Count of activities for this activity.
+  pub payable_activity_info: Box<Account<'info, PayableActivityInfo>>,
+
+  #[account(mut, constraint = payable.host == *signer.key @ ChainbillsError::NotYourPayable)]
+  pub payable: Box<Account<'info, Payable>>,
+
+  #[account(mut, seeds = [signer.key().as_ref()], bump)]
+  pub host: Box<Account<'info, User>>,
+
+  #[account(seeds = [ChainStats::SEED_PREFIX], bump)]
+  pub chain_stats: Box<Account<'info, ChainStats>>,
+
+  #[account(mut, seeds = [ChainStatsShard::SEED_PREFIX, &[chain_shard.index]], bump)]
+  /// The shard of the chain's counts that this instruction's entities are
+  /// indexed in. Any initialized shard can be used.
+  pub chain_shard: Box<Account<'info, ChainStatsShard>>,
+
+  #[account(seeds = [Config::SEED_PREFIX], bump)]
+  pub config: AccountLoader<'info, Config>,
+
+  #[account(seeds = [CbChain::SEED_PREFIX, dest_chain_id.as_ref()], bump)]
+  /// The registry entry of the destination chain. Holds its Circle domain.
+  pub dest_cb_chain: Box<Account<'info, CbChain>>,
+
+  #[account(mut)]
+  /// USDC. Mutable as CCTP burns from its supply.
+  pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(mut, seeds = [TokenDetails::SEED_PREFIX, mint.key().as_ref()], bump)]
+  pub token_details: Box<Account<'info, TokenDetails>>,
+
+  #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = chain_stats,
+        associated_token::token_program = token_program,
+    )]
+  pub chain_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = fee_collector,
+        associated_token::token_program = token_program,
+    )]
+  pub fees_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(address = config.load()?.chainbills_fee_collector)]
+  pub fee_collector: SystemAccount<'info>,
+
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  #[account(mut)]
+  /// A fresh keypair that the CCTP Message Transmitter stores the sent
+  /// message in.
+  pub cctp_message: Signer<'info>,
+
+  /// CHECK: Seeds must be ["sender_authority"] (CCTP Token Messenger Minter
+  /// program). Checked by the CCTP Token Messenger Minter program.
+  pub token_messenger_minter_sender_authority: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// Seeds must be ["message_transmitter"] (CCTP Message Transmitter
+  /// program). Read to get the nonce of the CCTP message.
+  pub message_transmitter_config: Box<Account<'info, MessageTransmitterConfig>>,
+
+  /// CHECK: Seeds must be ["token_messenger"] (CCTP Token Messenger Minter
+  /// program). Checked by the CCTP Token Messenger Minter program.
+  pub token_messenger: UncheckedAccount<'info>,
+
+  /// CHECK: Seeds must be ["remote_token_messenger", remote_domain.to_string()]
+  /// (CCTP Token Messenger Minter program). Checked by the CCTP Token
+  /// Messenger Minter program.
+  pub remote_token_messenger: UncheckedAccount<'info>,
+
+  /// CHECK: Seeds must be ["token_minter"] (CCTP Token Messenger Minter
+  /// program). Checked by the CCTP Token Messenger Minter program.
+  pub token_minter: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// CHECK: Seeds must be ["local_token", mint] (CCTP Token Messenger Minter
+  /// program). Checked by the CCTP Token Messenger Minter program.
+  pub local_token: UncheckedAccount<'info>,
+
+  /// CHECK: Seeds must be ["__event_authority"] (CCTP Token Messenger Minter
+  /// program). Checked by the CCTP Token Messenger Minter program.
+  pub token_messenger_minter_event_authority: UncheckedAccount<'info>,
+
+  /// CHECK: CCTP Message Transmitter program.
+  #[account(address = message_transmitter_program::ID)]
+  pub message_transmitter_program: UncheckedAccount<'info>,
+
+  /// CHECK: CCTP Token Messenger Minter program.
+  #[account(address = token_messenger_minter_program::ID)]
+  pub token_messenger_minter_program: UncheckedAccount<'info>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+
+  pub system_program: Program<'info, System>,
+}
@@ -1,11 +1,12 @@
 use crate::{error::ChainbillsError, state::*};
 use anchor_lang::prelude::*;
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct WithdrawNative<'info> {
   #[account(
         init,
-        seeds = [signer.key().as_ref(),
+        seeds = [payable.host.as_ref(),
             Withdrawal::SEED_PREFIX,
             &host.next_withdrawal().to_le_bytes()[..]],
         bump,
@@ -16,7 +17,7 @@ pub struct WithdrawNative<'info> {
 
   #[account(
     init,
-    seeds = [ChainWithdrawalId::SEED_PREFIX, &chain_stats.next_withdrawal().to_le_bytes()[..]],
+    seeds = [ChainWithdrawalId::SEED_PREFIX, &chain_shard.next_withdrawal().to_le_bytes()[..]],
     bump,
     payer = signer,
     space = ChainWithdrawalId::SPACE
@@ -38,7 +39,7 @@ pub struct WithdrawNative<'info> {
 
   #[account(
     init,
-    seeds = [ActivityRecord::SEED_PREFIX, &chain_stats.next_activity().to_le_bytes()[..]],
+    seeds = [ActivityRecord::SEED_PREFIX, &chain_shard.next_activity().to_le_bytes()[..]],
     bump,
     payer = signer,
     space = ActivityRecord::SPACE
@@ -48,7 +49,7 @@ pub struct WithdrawNative<'info> {
 
   #[account(
     init,
-    seeds = [signer.key().as_ref(), ActivityRecord::SEED_PREFIX, &host.next_activity().to_le_bytes()[..]],
+    seeds = [payable.host.as_ref(), ActivityRecord::SEED_PREFIX, &host.next_activity().to_le_bytes()[..]],
     bump,
     payer = signer,
     space = UserActivityInfo::SPACE
@@ -66,24 +67,40 @@ pub struct WithdrawNative<'info> {
   /// Houses Chain Count of activities for this activity.
   pub payable_activity_info: Box<Account<'info, PayableActivityInfo>>,
 
-  #[account(mut, constraint = payable.host == *signer.key @ ChainbillsError::NotYourPayable)]
+  #[account(mut, constraint = payable.host == *signer.key || PayableOperator::permits(&operator, &payable.host, PayableOperator::ROLE_WITHDRAW) @ ChainbillsError::NotYourPayable)]
   pub payable: Box<Account<'info, Payable>>,
 
-  #[account(mut, seeds = [signer.key().as_ref()], bump)]
+  #[account(mut, seeds = [payable.host.as_ref()], bump)]
   pub host: Box<Account<'info, User>>,
 
-  #[account(mut, seeds = [ChainStats::SEED_PREFIX], bump)]
-  pub chain_stats: Box<Account<'info, ChainStats>>,
+  #[account(mut, address = payable.host)]
+  /// The payable's host's wallet. Receives the withdrawn funds and the rent
+  /// freed from the payable.
+  pub host_wallet: SystemAccount<'info>,
+
+  #[account(seeds = [payable.key().as_ref(), PayableOperator::SEED_PREFIX, signer.key().as_ref()], bump)]
+  /// The signer's operator account on the payable. Required only when the
+  /// signer isn't the host.
+  pub operator: Option<Box<Account<'info, PayableOperator>>>,
+
+  #[account(mut, seeds = [ChainStatsShard::SEED_PREFIX, &[chain_shard.index]], bump)]
+  /// The shard of the chain's counts that this instruction's entities are
+  /// indexed in. Any initialized shard can be used.
+  pub chain_shard: Box<Account<'info, ChainStatsShard>>,
 
   #[account(seeds = [Config::SEED_PREFIX], bump)]
   pub config: AccountLoader<'info, Config>,
 
-  #[account(address = config.load()?.chainbills_fee_collector)]
+  #[account(mut, address = config.load()?.chainbills_fee_collector)]
   pub fee_collector: SystemAccount<'info>,
 
-  #[account(seeds = [TokenDetails::SEED_PREFIX, crate::ID.as_ref()], bump)]
+  #[account(mut, seeds = [TokenDetails::SEED_PREFIX, crate::ID.as_ref()], bump)]
   pub token_details: Box<Account<'info, TokenDetails>>,
 
+  #[account(mut, seeds = [SEED_PREFIX_NATIVE_VAULT], bump)]
+  /// Holds the native SOL paid into payables on this chain.
+  pub native_vault: SystemAccount<'info>,
+
   #[account(mut)]
   pub signer: Signer<'info>,
 
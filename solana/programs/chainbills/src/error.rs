@@ -94,4 +94,221 @@ pub enum ChainbillsError {
   #[msg("InvalidPayloadActionType")]
   /// Specified action type in the payload is invalid.
   InvalidPayloadActionType,
+
+  #[msg("UnsupportedMintExtension")]
+  /// The token mint has a Token-2022 extension that can't be safely supported.
+  UnsupportedMintExtension,
+
+  #[msg("NothingReceivedFromTransfer")]
+  /// The transfer fees on the token consumed the entire payment.
+  NothingReceivedFromTransfer,
+
+  #[msg("PayableHasBalances")]
+  /// The payable still has balances that should be withdrawn first.
+  PayableHasBalances,
+
+  #[msg("NotYourPayment")]
+  /// The payment was not made by the caller.
+  NotYourPayment,
+
+  #[msg("NotYourWithdrawal")]
+  /// The withdrawal was not made by the caller.
+  NotYourWithdrawal,
+
+  #[msg("TooManyAllowedTokensAndAmounts")]
+  /// More allowed tokens and amounts than a payable can hold were specified.
+  TooManyAllowedTokensAndAmounts,
+
+  #[msg("TooManyBalances")]
+  /// The payable already holds the maximum number of token balances.
+  TooManyBalances,
+
+  #[msg("AlreadyMigrated")]
+  /// The account already has the current layout.
+  AlreadyMigrated,
+
+  #[msg("InsufficientNativeVaultBalance")]
+  /// The withdrawal would leave the native vault below rent-exemption.
+  InsufficientNativeVaultBalance,
+
+  #[msg("NonPayableAccountProvided")]
+  /// A non-payable account was provided in the remaining accounts.
+  NonPayableAccountProvided,
+
+  #[msg("InvalidPayloadLength")]
+  /// The posted message has bytes beyond its decoded payload.
+  InvalidPayloadLength,
+
+  #[msg("InvalidCaip2")]
+  /// The provided string is not a CAIP-2 chain identifier.
+  InvalidCaip2,
+
+  #[msg("InvalidWormholeChainId")]
+  /// Zero is not a valid Wormhole Chain ID.
+  InvalidWormholeChainId,
+
+  #[msg("ChainIdAlreadyRegistered")]
+  /// The cbChain already has an ID registered for the bridging protocol.
+  ChainIdAlreadyRegistered,
+
+  #[msg("InvalidPayableForeignChainId")]
+  /// The provided Wormhole Chain ID isn't that of the foreign payable.
+  InvalidPayableForeignChainId,
+
+  #[msg("InvalidDestinationChain")]
+  /// Cross-chain withdrawals must be to another chain with a Circle domain.
+  InvalidDestinationChain,
+
+  #[msg("InvalidDestinationAddress")]
+  /// Withdrawn funds can't be sent to the zero address.
+  InvalidDestinationAddress,
+
+  #[msg("CannotTransferPayableToSelf")]
+  /// A payable can't be transferred to its current host.
+  CannotTransferPayableToSelf,
+
+  #[msg("NotPendingPayableHost")]
+  /// Only the wallet that a payable was offered to can accept it.
+  NotPendingPayableHost,
+
+  #[msg("InvalidOperatorRoles")]
+  /// Operator roles must be a non-empty set of known roles.
+  InvalidOperatorRoles,
+
+  #[msg("InvalidAllowedPayers")]
+  /// Either a list of allowed payers or their Merkle root can be given, not
+  /// both.
+  InvalidAllowedPayers,
+
+  #[msg("PayerNotAllowed")]
+  /// The payer isn't allowed to pay the payable or their proof is invalid.
+  PayerNotAllowed,
+
+  #[msg("InvalidSubscriptionPeriod")]
+  /// Subscriptions must have a non-zero period between payments.
+  InvalidSubscriptionPeriod,
+
+  #[msg("SubscriptionAlreadyExists")]
+  /// A payer can only have one active subscription to a payable.
+  SubscriptionAlreadyExists,
+
+  #[msg("SubscriptionIsCancelled")]
+  /// Cancelled subscriptions can't be collected or cancelled again.
+  SubscriptionIsCancelled,
+
+  #[msg("SubscriptionNotDue")]
+  /// The subscription's next payment isn't due yet.
+  SubscriptionNotDue,
+
+  #[msg("NotYourSubscription")]
+  /// Only the payer or the payable's host or managers can cancel a
+  /// subscription.
+  NotYourSubscription,
+
+  #[msg("InvalidSubscriptionMint")]
+  /// Subscriptions are collected in the token they were created with.
+  InvalidSubscriptionMint,
+
+  #[msg("EscrowStateRequired")]
+  /// Payments into payables that escrow them need an EscrowState account.
+  EscrowStateRequired,
+
+  #[msg("InvalidEscrowAccounts")]
+  /// The provided accounts don't match the escrowed payment.
+  InvalidEscrowAccounts,
+
+  #[msg("EscrowIsSettled")]
+  /// The escrowed payment was already released or refunded.
+  EscrowIsSettled,
+
+  #[msg("EscrowIsDisputed")]
+  /// Disputed payments are settled only by their payer or arbiter.
+  EscrowIsDisputed,
+
+  #[msg("EscrowNotReleasable")]
+  /// Only the payer or the arbiter can release a payment before its timeout.
+  EscrowNotReleasable,
+
+  #[msg("EscrowNotDisputable")]
+  /// Only undisputed payments with an arbiter can be disputed, before their
+  /// timeout.
+  EscrowNotDisputable,
+
+  #[msg("NotYourEscrow")]
+  /// The signer can't settle this escrowed payment.
+  NotYourEscrow,
+
+  #[msg("EmptyPaymentBatch")]
+  /// A batch needs at least one payment.
+  EmptyPaymentBatch,
+
+  #[msg("InvalidBatchAccounts")]
+  /// The remaining accounts don't match the payments of the batch.
+  InvalidBatchAccounts,
+
+  #[msg("ReceiptsNotEnabled")]
+  /// The payable doesn't mint receipts for its payments.
+  ReceiptsNotEnabled,
+
+  #[msg("InvalidReceiptAccounts")]
+  /// The provided accounts don't match the payment of the receipt.
+  InvalidReceiptAccounts,
+
+  #[msg("PaymentIntentExpired")]
+  /// The payment intent can't be paid after its expiry.
+  PaymentIntentExpired,
+
+  #[msg("InvalidIntentSignature")]
+  /// The payment intent wasn't signed by the payable's host in the Ed25519
+  /// instruction right before this one.
+  InvalidIntentSignature,
+
+  #[msg("IntentReferenceTooLong")]
+  /// The payment intent's reference is longer than the maximum.
+  IntentReferenceTooLong,
+
+  #[msg("InvalidTokenGroup")]
+  /// The token group is unnamed, has too many or duplicate members, or
+  /// doesn't match an allowed token.
+  InvalidTokenGroup,
+
+  #[msg("ChainTokenAccountRequired")]
+  /// Checking the integrity of an SPL token needs the program's token
+  /// account for it.
+  ChainTokenAccountRequired,
+
+  #[msg("NonPayableOrEscrowStateAccountProvided")]
+  /// A remaining account is neither a payable, an escrow state, nor a token
+  /// details shard.
+  NonPayableOrEscrowStateAccountProvided,
+
+  #[msg("InvalidChainShard")]
+  /// The index of the chain stats shard isn't below the number of shards.
+  InvalidChainShard,
+
+  #[msg("InvalidLedgerAccounts")]
+  /// An index entry was given both or neither of its own account and its
+  /// ledger page.
+  InvalidLedgerAccounts,
+
+  #[msg("InvalidPayableActivityInfo")]
+  /// The payable's reference to the activity is missing or doesn't point to
+  /// it.
+  InvalidPayableActivityInfo,
+
+  #[msg("PayableHasHeldEscrows")]
+  /// The payable still holds escrowed payments that are locked or disputed.
+  PayableHasHeldEscrows,
+
+  #[msg("SubscriptionFirstDueInPast")]
+  /// The first payment of a subscription is due before it was created.
+  SubscriptionFirstDueInPast,
+
+  #[msg("NonPayableOperatorAccountProvided")]
+  /// A remaining account isn't one of the payable's operators.
+  NonPayableOperatorAccountProvided,
+
+  #[msg("PaymentIsHeldInEscrow")]
+  /// The payment is still held in escrow (locked or disputed).
+  PaymentIsHeldInEscrow,
 }
@@ -1,8 +1,14 @@
+use crate::state::{ActivityType, TokenAndAmount, TokenGroupMember};
 use anchor_lang::prelude::*;
 
 #[event]
 pub struct Initialized {}
 
+#[event]
+pub struct InitializedChainShard {
+  pub index: u8,
+}
+
 #[event]
 pub struct InitializedUser {
   pub wallet: Pubkey,
@@ -24,9 +30,14 @@ pub struct UserPaid {
   pub payable_id: [u8; 32],
   pub payer_wallet: Pubkey,
   pub payment_id: Pubkey,
-  pub payable_chain_id: u16,
+  pub payable_chain_id: [u8; 32],
   pub chain_count: u64,
   pub payer_count: u64,
+  /// The token mint (or the program ID for native SOL) that was paid.
+  pub token: Pubkey,
+  /// The amount (with decimals) that was paid.
+  pub amount: u64,
+  pub timestamp: u64,
 }
 
 #[event]
@@ -36,9 +47,14 @@ pub struct PayableReceived {
   pub payable_id: Pubkey,
   pub payer_wallet: [u8; 32],
   pub payment_id: Pubkey,
-  pub payer_chain_id: u16,
+  pub payer_chain_id: [u8; 32],
   pub chain_count: u64,
   pub payable_count: u64,
+  /// The token mint (or the program ID for native SOL) that was received.
+  pub token: Pubkey,
+  /// The amount (with decimals) that was credited to the payable's balances.
+  pub amount: u64,
+  pub timestamp: u64,
 }
 
 #[event]
@@ -49,6 +65,43 @@ pub struct Withdrew {
   pub chain_count: u64,
   pub payable_count: u64,
   pub host_count: u64,
+  /// The token mint (or the program ID for native SOL) that was withdrawn.
+  pub token: Pubkey,
+  /// The gross amount deducted from the payable's balances.
+  pub amount: u64,
+  /// The fees that were sent to the fee collector.
+  pub fees: u64,
+  /// The net amount sent to the host (amount - fees).
+  pub amount_due: u64,
+  pub timestamp: u64,
+}
+
+#[event]
+/// Emitted alongside Withdrew when the withdrawn funds were burnt through
+/// CCTP for the host to receive on another chain. A relayer completes the
+/// withdrawal by fetching the attestation of the CCTP message (from this
+/// chain's domain and with cctp_nonce) and receiving it on the destination.
+pub struct WithdrewCrossChain {
+  pub payable_id: Pubkey,
+  pub withdrawal_id: Pubkey,
+  pub dest_chain_id: [u8; 32],
+  pub dest_address: [u8; 32],
+  pub dest_circle_domain: u32,
+  pub cctp_nonce: u64,
+  /// The net amount that was burnt for the host (amount - fees).
+  pub amount_due: u64,
+  pub timestamp: u64,
+}
+
+#[event]
+/// Emitted alongside Withdrew when fees are sent to Chainbills' fee collector.
+pub struct CollectedWithdrawalFees {
+  pub payable_id: Pubkey,
+  pub withdrawal_id: Pubkey,
+  pub fee_collector: Pubkey,
+  pub token: Pubkey,
+  pub fees: u64,
+  pub timestamp: u64,
 }
 
 #[event]
@@ -57,6 +110,150 @@ pub struct ClosedPayable {
   pub host_wallet: Pubkey,
 }
 
+#[event]
+/// Emitted when a host offers to transfer their payable to another wallet.
+pub struct InitiatedPayableTransfer {
+  pub payable_id: Pubkey,
+  pub host_wallet: Pubkey,
+  pub new_host: Pubkey,
+}
+
+#[event]
+/// Emitted when the offered wallet accepts a payable and becomes its host.
+pub struct TransferredPayable {
+  pub payable_id: Pubkey,
+  pub previous_host: Pubkey,
+  pub new_host: Pubkey,
+  /// The nth count of payables of the new host that this payable now is.
+  pub new_host_count: u64,
+}
+
+#[event]
+/// Emitted when a host sets the roles of an operator on their payable. The
+/// roles are zero when the operator was removed.
+pub struct UpdatedPayableOperator {
+  pub payable_id: Pubkey,
+  pub operator: Pubkey,
+  pub roles: u8,
+}
+
+#[event]
+/// Emitted when the payers allowed to pay a payable are updated. The list is
+/// empty when only the root was given, in which case the host shares the
+/// list and proofs with the payers.
+pub struct UpdatedPayableAllowedPayers {
+  pub payable_id: Pubkey,
+  pub host_wallet: Pubkey,
+  pub allowed_payers_root: [u8; 32],
+  pub allowed_payers: Vec<[u8; 32]>,
+}
+
+#[event]
+/// Emitted when a payer subscribes to recurring payments into a payable.
+pub struct CreatedSubscription {
+  pub payable_id: Pubkey,
+  pub payer_wallet: Pubkey,
+  pub token: Pubkey,
+  pub amount: u64,
+  pub period: u64,
+  pub next_due: u64,
+}
+
+#[event]
+/// Emitted when a subscription's payment is collected. The payment itself
+/// is in the accompanying UserPaid and PayableReceived events.
+pub struct CollectedSubscription {
+  pub payable_id: Pubkey,
+  pub payer_wallet: Pubkey,
+  pub collector: Pubkey,
+  /// The nth payment of the subscription that this was.
+  pub subscription_count: u64,
+  pub next_due: u64,
+}
+
+#[event]
+/// Emitted when the payer or the payable's host cancels a subscription.
+pub struct CancelledSubscription {
+  pub payable_id: Pubkey,
+  pub payer_wallet: Pubkey,
+  pub cancelled_by: Pubkey,
+}
+
+#[event]
+/// Emitted when a host (or manager) updates a payable's escrow settings.
+pub struct UpdatedPayableEscrow {
+  pub payable_id: Pubkey,
+  pub host_wallet: Pubkey,
+  pub timeout: u64,
+  pub arbiter: Pubkey,
+}
+
+#[event]
+/// Emitted when an escrowed payment is added to the payable's balances.
+pub struct ReleasedEscrow {
+  pub payable_id: Pubkey,
+  pub escrow_id: Pubkey,
+  pub released_by: Pubkey,
+  pub token: Pubkey,
+  pub amount: u64,
+}
+
+#[event]
+/// Emitted when a payer disputes an escrowed payment.
+pub struct DisputedEscrow {
+  pub payable_id: Pubkey,
+  pub escrow_id: Pubkey,
+  pub payer_wallet: Pubkey,
+}
+
+#[event]
+/// Emitted when an escrowed payment is sent back to its payer.
+pub struct RefundedEscrow {
+  pub payable_id: Pubkey,
+  pub escrow_id: Pubkey,
+  pub payer_wallet: Pubkey,
+  pub refunded_by: Pubkey,
+  pub token: Pubkey,
+  pub amount: u64,
+}
+
+#[event]
+/// Emitted when a host (or manager) updates a payable's receipt settings.
+pub struct UpdatedPayableReceipts {
+  pub payable_id: Pubkey,
+  pub host_wallet: Pubkey,
+  pub enabled: bool,
+}
+
+#[event]
+/// Emitted when a payment's receipt NFT is minted to its payer.
+pub struct MintedReceipt {
+  pub payable_id: [u8; 32],
+  pub user_payment_id: Pubkey,
+  pub payer_wallet: Pubkey,
+  pub receipt_mint: Pubkey,
+}
+
+#[event]
+/// Emitted when a payment is made with an intent signed by the payable's
+/// host.
+pub struct PaidWithIntent {
+  pub payable_id: Pubkey,
+  pub payable_payment_id: Pubkey,
+  pub payer_wallet: Pubkey,
+  pub nonce: u64,
+  pub reference: String,
+}
+
+#[event]
+/// Emitted when the owner creates or updates a token group.
+pub struct UpdatedTokenGroup {
+  pub token_group: Pubkey,
+  pub name: String,
+  pub decimals: u8,
+  pub members: Vec<TokenGroupMember>,
+}
+
 #[event]
 pub struct ReopenedPayable {
   pub payable_id: Pubkey,
@@ -67,6 +264,95 @@ pub struct ReopenedPayable {
 pub struct UpdatedPayableAllowedTokensAndAmounts {
   pub payable_id: Pubkey,
   pub host_wallet: Pubkey,
+  /// The new set of tokens and amounts that the payable accepts.
+  pub allowed_tokens_and_amounts: Vec<TokenAndAmount>,
+  pub timestamp: u64,
+}
+
+#[event]
+/// Emitted when a host closes the account of a closed and emptied payable.
+/// Holds everything that was in the account so that it can be archived.
+pub struct ArchivedPayable {
+  pub payable_id: Pubkey,
+  pub host_wallet: Pubkey,
+  pub chain_count: u64,
+  pub host_count: u64,
+  pub created_at: u64,
+  pub payments_count: u64,
+  pub withdrawals_count: u64,
+  pub activities_count: u64,
+  pub allowed_tokens_and_amounts: Vec<TokenAndAmount>,
+  pub timestamp: u64,
+}
+
+#[event]
+/// Emitted when a payer closes one of their payment receipts. Holds everything
+/// that was in the account so that it can be archived.
+pub struct ArchivedUserPayment {
+  pub payment_id: Pubkey,
+  pub payable_id: [u8; 32],
+  pub payer_wallet: Pubkey,
+  pub payable_chain_id: [u8; 32],
+  pub chain_count: u64,
+  pub payer_count: u64,
+  pub paid_at: u64,
+  pub details: TokenAndAmount,
+  pub timestamp: u64,
+}
+
+#[event]
+/// Emitted when a host closes one of their payable's payment receipts. Holds
+/// everything that was in the account so that it can be archived.
+pub struct ArchivedPayablePayment {
+  pub payment_id: Pubkey,
+  pub payable_id: Pubkey,
+  pub payer_wallet: [u8; 32],
+  pub payer_chain_id: [u8; 32],
+  pub chain_count: u64,
+  pub local_chain_count: u64,
+  pub payable_count: u64,
+  pub paid_at: u64,
+  pub details: TokenAndAmount,
+  pub timestamp: u64,
+}
+
+#[event]
+/// Emitted when a host closes one of their withdrawal receipts. Holds
+/// everything that was in the account so that it can be archived.
+pub struct ArchivedWithdrawal {
+  pub withdrawal_id: Pubkey,
+  pub payable_id: Pubkey,
+  pub host_wallet: Pubkey,
+  pub chain_count: u64,
+  pub host_count: u64,
+  pub payable_count: u64,
+  pub withdrawn_at: u64,
+  pub details: TokenAndAmount,
+  pub dest_chain_id: [u8; 32],
+  pub dest_address: [u8; 32],
+  pub timestamp: u64,
+}
+
+#[event]
+/// Emitted when a user closes one of their activity records. Holds everything
+/// that was in the account so that it can be archived.
+pub struct ArchivedUserActivity {
+  pub activity_id: Pubkey,
+  pub wallet: Pubkey,
+  pub chain_count: u64,
+  pub user_count: u64,
+  pub payable_count: u64,
+  pub recorded_at: u64,
+  pub entity: Pubkey,
+  pub activity_type: ActivityType,
+  pub timestamp: u64,
+}
+
+#[event]
+/// Emitted when an account is upgraded to its current layout.
+pub struct MigratedAccount {
+  pub account: Pubkey,
+  pub version: u8,
 }
 
 #[event]
@@ -101,3 +387,21 @@ pub struct RegisteredMatchingTokenForForeignChain {
   pub foreign_token: [u8; 32],
   pub token: Pubkey,
 }
+
+#[event]
+pub struct RegisteredCbChain {
+  pub cb_chain_id: [u8; 32],
+  pub caip2: String,
+}
+
+#[event]
+pub struct RegisteredChainWormholeId {
+  pub cb_chain_id: [u8; 32],
+  pub wormhole_chain_id: u16,
+}
+
+#[event]
+pub struct RegisteredChainCircleDomain {
+  pub cb_chain_id: [u8; 32],
+  pub circle_domain: u32,
+}
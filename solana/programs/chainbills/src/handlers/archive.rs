@@ -0,0 +1,238 @@
+use super::pay::LedgerEntry;
+use crate::{context::*, error::ChainbillsError, events::*, state::*};
+use anchor_lang::{prelude::*, solana_program::clock};
+
+/// Closes one of a payable's accounts that may not exist, so that Anchor
+/// can't close it on exit. The rent goes to the destination.
+fn close_if_initialized<'info>(
+  account: &AccountInfo<'info>,
+  destination: &AccountInfo<'info>,
+) -> Result<()> {
+  if account.data_is_empty() {
+    return Ok(());
+  }
+
+  // The account is owned by this program, so its lamports can be debited
+  // directly.
+  let rent = account.lamports();
+  let mut destination_lamports = destination.try_borrow_mut_lamports()?;
+  **destination_lamports = destination_lamports.checked_add(rent).unwrap();
+  **account.try_borrow_mut_lamports()? = 0;
+  account.assign(&System::id());
+  account.realloc(0, false)?;
+  Ok(())
+}
+
+/// Closes the account of a closed payable that has no balances left nor
+/// held escrowed payments, along with its escrow, allowed payers, receipts
+/// and pending host accounts if any. The payable's operators to close are
+/// passed as remaining accounts. The rent goes back to the host and the
+/// payable's data is emitted as an event.
+#[inline(never)]
+pub fn archive_payable<'info>(
+  ctx: Context<'_, '_, 'info, 'info, ArchivePayable<'info>>,
+) -> Result<()> {
+  /* CHECKS */
+  let payable = &ctx.accounts.payable;
+  require!(payable.is_closed, ChainbillsError::PayableIsNotClosed);
+  require!(
+    payable.balances.iter().all(|b| b.amount == 0),
+    ChainbillsError::PayableHasBalances
+  );
+  require!(
+    PayableEscrow::held(&ctx.accounts.payable_escrow)? == 0,
+    ChainbillsError::PayableHasHeldEscrows
+  );
+  for account in ctx.remaining_accounts.iter() {
+    let operator = Account::<'info, PayableOperator>::try_from(account)
+      .map_err(|_| ChainbillsError::NonPayableOperatorAccountProvided)?;
+    require!(
+      operator.payable_id == payable.key(),
+      ChainbillsError::NonPayableOperatorAccountProvided
+    );
+  }
+
+  /* STATE CHANGES */
+  // Close the payable's other accounts. The payable itself and its payments
+  // counter are closed by Anchor's close constraints on exit.
+  let signer = ctx.accounts.signer.to_account_info();
+  for account in [
+    &ctx.accounts.payable_escrow,
+    &ctx.accounts.allowed_payers,
+    &ctx.accounts.payable_receipts,
+    &ctx.accounts.pending_payable_host,
+  ] {
+    close_if_initialized(account, &signer)?;
+  }
+  for account in ctx.remaining_accounts.iter() {
+    close_if_initialized(account, &signer)?;
+  }
+
+  /* EVENTS */
+  msg!("Archived Payable.");
+  emit_cpi!(ArchivedPayable {
+    payable_id: payable.key(),
+    host_wallet: payable.host,
+    chain_count: payable.chain_count,
+    host_count: payable.host_count,
+    created_at: payable.created_at,
+    payments_count: payable.payments_count,
+    withdrawals_count: payable.withdrawals_count,
+    activities_count: payable.activities_count,
+    allowed_tokens_and_amounts: payable.allowed_tokens_and_amounts.clone(),
+    timestamp: clock::Clock::get()?.unix_timestamp as u64,
+  });
+  Ok(())
+}
+
+/// Closes a payer's payment receipt and its chain-level reference. The rent
+/// goes back to whoever paid it and the receipt's data is emitted as an event.
+#[inline(never)]
+pub fn archive_user_payment(ctx: Context<ArchiveUserPayment>) -> Result<()> {
+  let user_payment = &ctx.accounts.user_payment;
+
+  // Clear the chain-level reference if it is in a ledger page. Otherwise, its
+  // account (if any) is closed by Anchor's close constraint on exit.
+  let entry = LedgerEntry::maybe_of(
+    ctx.accounts.chain_user_payment_id.as_deref_mut(),
+    ctx.accounts.chain_user_payments_page.as_deref_mut(),
+  )?;
+  if let Some(LedgerEntry::Page(page)) = entry {
+    require!(
+      page.get(user_payment.chain_count) == Some(user_payment.key()),
+      ChainbillsError::NotYourPayment
+    );
+    page.set(user_payment.chain_count, Pubkey::default());
+  }
+
+  msg!("Archived User Payment.");
+  emit_cpi!(ArchivedUserPayment {
+    payment_id: user_payment.key(),
+    payable_id: user_payment.payable_id,
+    payer_wallet: user_payment.payer,
+    payable_chain_id: user_payment.payable_chain_id,
+    chain_count: user_payment.chain_count,
+    payer_count: user_payment.payer_count,
+    paid_at: user_payment.timestamp,
+    details: user_payment.details,
+    timestamp: clock::Clock::get()?.unix_timestamp as u64,
+  });
+  Ok(())
+}
+
+/// Closes a payable's payment receipt and the chain's and the payable's
+/// references to it, along with its escrow state if it was escrowed. The rent
+/// goes back to whoever paid it and the receipt's data is emitted as an
+/// event.
+#[inline(never)]
+pub fn archive_payable_payment(
+  ctx: Context<ArchivePayablePayment>,
+) -> Result<()> {
+  let payable_payment = &ctx.accounts.payable_payment;
+
+  // Clear the references if they are in ledger pages. Otherwise, their
+  // accounts (if any) are closed by Anchor's close constraints on exit.
+  let entry = LedgerEntry::maybe_of(
+    ctx.accounts.chain_payable_payment_id.as_deref_mut(),
+    ctx.accounts.chain_payable_payments_page.as_deref_mut(),
+  )?;
+  if let Some(LedgerEntry::Page(page)) = entry {
+    require!(
+      page.get(payable_payment.chain_count) == Some(payable_payment.key()),
+      ChainbillsError::NotYourPayment
+    );
+    page.set(payable_payment.chain_count, Pubkey::default());
+  }
+  let entry = LedgerEntry::of(
+    ctx.accounts.payable_per_chain_payment_info.as_deref_mut(),
+    ctx.accounts.payable_per_chain_payments_page.as_deref_mut(),
+  )?;
+  if let LedgerEntry::Page(page) = entry {
+    page.set(payable_payment.local_chain_count, 0);
+  }
+
+  msg!("Archived Payable Payment.");
+  emit_cpi!(ArchivedPayablePayment {
+    payment_id: payable_payment.key(),
+    payable_id: payable_payment.payable_id,
+    payer_wallet: payable_payment.payer,
+    payer_chain_id: payable_payment.payer_chain_id,
+    chain_count: payable_payment.chain_count,
+    local_chain_count: payable_payment.local_chain_count,
+    payable_count: payable_payment.payable_count,
+    paid_at: payable_payment.timestamp,
+    details: payable_payment.details,
+    timestamp: clock::Clock::get()?.unix_timestamp as u64,
+  });
+  Ok(())
+}
+
+/// Closes a host's withdrawal receipt, its chain-level reference, and the
+/// payable's reference to it. The rent goes back to whoever paid it and the
+/// receipt's data is emitted as an event.
+#[inline(never)]
+pub fn archive_withdrawal(ctx: Context<ArchiveWithdrawal>) -> Result<()> {
+  let withdrawal = &ctx.accounts.withdrawal;
+
+  msg!("Archived Withdrawal.");
+  emit_cpi!(ArchivedWithdrawal {
+    withdrawal_id: withdrawal.key(),
+    payable_id: withdrawal.payable_id,
+    host_wallet: withdrawal.host,
+    chain_count: withdrawal.chain_count,
+    host_count: withdrawal.host_count,
+    payable_count: withdrawal.payable_count,
+    withdrawn_at: withdrawal.timestamp,
+    details: withdrawal.details,
+    dest_chain_id: withdrawal.dest_chain_id,
+    dest_address: withdrawal.dest_address,
+    timestamp: clock::Clock::get()?.unix_timestamp as u64,
+  });
+  Ok(())
+}
+
+/// Closes one of a user's activity records, its info account if any, and the
+/// payable's info account of it if any. The rent goes back to whoever paid it
+/// and the record's data is emitted as an event.
+///
+/// ### args
+/// * user_count<u64>: The nth count of the user's activities to archive.
+#[inline(never)]
+pub fn archive_user_activity(
+  ctx: Context<ArchiveUserActivity>,
+  user_count: u64,
+) -> Result<()> {
+  let activity = &ctx.accounts.activity;
+
+  // A payable's activity must leave its payable's activities too. Its info
+  // account is closed by Anchor's close constraint on exit.
+  require!(
+    activity.payable_count == 0 || ctx.accounts.payable_activity_info.is_some(),
+    ChainbillsError::InvalidPayableActivityInfo
+  );
+
+  // Clear the activity's entry if it is in a ledger page. Otherwise, its
+  // info account (if any, as payments' activities have none) is closed by
+  // Anchor's close constraint on exit.
+  let entry = LedgerEntry::maybe_of(
+    ctx.accounts.user_activity_info.as_deref_mut(),
+    ctx.accounts.user_activities_page.as_deref_mut(),
+  )?;
+  if let Some(LedgerEntry::Page(page)) = entry {
+    page.set(user_count, 0);
+  }
+
+  msg!("Archived User Activity.");
+  emit_cpi!(ArchivedUserActivity {
+    activity_id: activity.key(),
+    wallet: ctx.accounts.signer.key(),
+    chain_count: activity.chain_count,
+    user_count,
+    payable_count: activity.payable_count,
+    recorded_at: activity.timestamp,
+    entity: activity.entity,
+    activity_type: activity.activity_type,
+    timestamp: clock::Clock::get()?.unix_timestamp as u64,
+  });
+  Ok(())
+}
@@ -0,0 +1,89 @@
+use crate::{context::*, error::ChainbillsError, state::*};
+use anchor_lang::prelude::*;
+
+/// Compares what the program holds of the token against the token's balances
+/// in the payables and its held payments in the escrow states passed as
+/// remaining accounts, in any order. Accounts can be passed in pages and the
+/// sums added by the caller. Accounts passed more than once are counted once,
+/// but each account must be in only one page. The token's TokenDetailsShards
+/// can be passed among them too, and their totals are added to those of its
+/// TokenDetails, so pass them all in one page.
+#[inline(never)]
+pub fn check_token_integrity<'info>(
+  ctx: Context<'_, '_, 'info, 'info, CheckTokenIntegrity<'info>>,
+  token: Pubkey,
+) -> Result<TokenIntegrity> {
+  let mut payables_balance: u64 = 0;
+  let mut payables_count: u64 = 0;
+  let mut escrowed: u64 = 0;
+  let mut escrow_states_count: u64 = 0;
+  let mut shards_received: u64 = 0;
+  let accounts = ctx.remaining_accounts;
+  for (i, account) in accounts.iter().enumerate() {
+    if accounts[..i].iter().any(|a| a.key == account.key) {
+      continue;
+    }
+    if let Ok(payable) = Account::<'info, Payable>::try_from(account) {
+      if let Some(balance) = payable.balances.iter().find(|b| b.token == token)
+      {
+        payables_balance =
+          payables_balance.checked_add(balance.amount).unwrap();
+      }
+      payables_count = payables_count.checked_add(1).unwrap();
+    } else if let Ok(escrow_state) =
+      Account::<'info, EscrowState>::try_from(account)
+    {
+      if escrow_state.is_held() && escrow_state.details.token == token {
+        escrowed = escrowed.checked_add(escrow_state.details.amount).unwrap();
+      }
+      escrow_states_count = escrow_states_count.checked_add(1).unwrap();
+    } else if let Ok(shard) =
+      Account::<'info, TokenDetailsShard>::try_from(account)
+    {
+      if shard.mint == token {
+        shards_received = shards_received
+          .checked_add(shard.total_payable_received)
+          .unwrap();
+      }
+    } else {
+      return err!(ChainbillsError::NonPayableOrEscrowStateAccountProvided);
+    }
+  }
+
+  let holdings = if token == crate::ID {
+    let vault_lamports = ctx.accounts.native_vault.lamports();
+    vault_lamports.saturating_sub(Rent::get()?.minimum_balance(0))
+  } else {
+    ctx
+      .accounts
+      .chain_token_account
+      .as_ref()
+      .ok_or(ChainbillsError::ChainTokenAccountRequired)?
+      .amount
+  };
+
+  let token_details = &ctx.accounts.token_details;
+  let integrity = TokenIntegrity {
+    token,
+    holdings,
+    total_payable_received: token_details
+      .total_payable_received
+      .checked_add(shards_received)
+      .unwrap(),
+    total_withdrawn: token_details.total_withdrawn,
+    payables_balance,
+    payables_count,
+    escrowed,
+    escrow_states_count,
+  };
+  msg!(
+    "Holding {} of {} against {} in {} payables and {} in {} escrow states.",
+    integrity.holdings,
+    token,
+    integrity.payables_balance,
+    integrity.payables_count,
+    integrity.escrowed,
+    integrity.escrow_states_count
+  );
+  Ok(integrity)
+}
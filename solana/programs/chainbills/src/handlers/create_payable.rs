@@ -2,6 +2,37 @@ use crate::{context::*, error::*, events::*, payload::*, state::*};
 use anchor_lang::{prelude::*, solana_program::clock};
 use wormhole_anchor_sdk::wormhole;
 
+/// Ensures that an allowed token and amount's remaining account backs it,
+/// either as the TokenDetails of a supported token or as a TokenGroup with
+/// members, and that its amount is greater than zero.
+pub(crate) fn check_allowed_token_and_amount<'info>(
+  taa: &TokenAndAmount,
+  account: &'info AccountInfo<'info>,
+) -> Result<()> {
+  if let Ok(token_details) = Account::<'info, TokenDetails>::try_from(account) {
+    require!(
+      taa.token == token_details.mint,
+      ChainbillsError::InvalidTokenDetailsAccount
+    );
+    require!(
+      token_details.is_supported,
+      ChainbillsError::UnsupportedToken
+    );
+  } else if let Ok(token_group) =
+    Account::<'info, TokenGroup>::try_from(account)
+  {
+    require!(
+      taa.token == token_group.key() && !token_group.members.is_empty(),
+      ChainbillsError::InvalidTokenGroup
+    );
+  } else {
+    return err!(ChainbillsError::NonTokenDetailsAccountProvided);
+  }
+
+  require!(taa.amount > 0, ChainbillsError::ZeroAmountSpecified);
+  Ok(())
+}
+
 /// Create a Payable
 ///
 /// ### args
@@ -22,25 +53,17 @@ pub fn create_payable_handler<'info>(
     ChainbillsError::InvalidRemainingAccountsLength
   );
 
+  // Ensure that the payable won't grow beyond what can be processed.
+  require!(
+    allowed_tokens_and_amounts.len() <= Payable::MAX_ALLOWED_TOKENS_AND_AMOUNTS,
+    ChainbillsError::TooManyAllowedTokensAndAmounts
+  );
+
   let mut ataa_foreign: Vec<TokenAndAmountForeign> = vec![];
   for (i, taa) in allowed_tokens_and_amounts.iter().enumerate() {
-    // Get the token details for the specified token.
-    let token_details =
-      Account::<'info, TokenDetails>::try_from(&ctx.remaining_accounts[i])
-        .map_err(|_| ChainbillsError::NonTokenDetailsAccountProvided)?;
-
-    // Ensure that the token is supported.
-    require!(
-      taa.token == token_details.mint,
-      ChainbillsError::InvalidTokenDetailsAccount
-    );
-    require!(
-      token_details.is_supported,
-      ChainbillsError::UnsupportedToken
-    );
-
-    // Ensure that all specified acceptable amounts are greater than zero.
-    require!(taa.amount > 0, ChainbillsError::ZeroAmountSpecified);
+    // Ensure that the token is supported (or is a token group) and that the
+    // amount is greater than zero.
+    check_allowed_token_and_amount(taa, &ctx.remaining_accounts[i])?;
 
     // Set the foreign ATAA in the same loop
     ataa_foreign.push(TokenAndAmountForeign {
@@ -50,14 +73,16 @@ pub fn create_payable_handler<'info>(
   }
 
   /* STATE CHANGES */
-  // Increment the chain stats for payables_count, activities_count, and
-  // published_wormhole_messages_count.
+  // Increment the chain stats' published_wormhole_messages_count.
   let chain_stats = ctx.accounts.chain_stats.as_mut();
-  chain_stats.payables_count = chain_stats.next_payable();
-  chain_stats.activities_count = chain_stats.next_activity();
   chain_stats.published_wormhole_messages_count =
     chain_stats.next_published_wormhole_message();
 
+  // Increment the chain shard's payables_count and activities_count.
+  let chain_shard = ctx.accounts.chain_shard.as_mut();
+  chain_shard.payables_count = chain_shard.next_payable();
+  chain_shard.activities_count = chain_shard.next_activity();
+
   // Increment payables_count and activities_count on the host initializing
   // this payable.
   let host = ctx.accounts.host.as_mut();
@@ -68,7 +93,8 @@ pub fn create_payable_handler<'info>(
 
   // Initialize the payable.
   let payable = ctx.accounts.payable.as_mut();
-  payable.chain_count = chain_stats.payables_count;
+  payable.version = Payable::VERSION;
+  payable.chain_count = chain_shard.payables_count;
   payable.host = ctx.accounts.signer.key();
   payable.host_count = host.payables_count;
   payable.allowed_tokens_and_amounts = allowed_tokens_and_amounts;
@@ -89,20 +115,21 @@ pub fn create_payable_handler<'info>(
 
   // Initialize the activity.
   let activity = ctx.accounts.activity.as_mut();
-  activity.chain_count = chain_stats.activities_count;
+  activity.chain_count = chain_shard.activities_count;
   activity.user_count = host.activities_count;
   activity.payable_count = payable.activities_count;
   activity.timestamp = timestamp;
   activity.entity = payable.key();
   activity.activity_type = ActivityType::CreatedPayable;
+  activity.rent_payer = ctx.accounts.signer.key();
 
   // Initialize the user activity info.
   let user_activity_info = ctx.accounts.user_activity_info.as_mut();
-  user_activity_info.chain_count = chain_stats.activities_count;
+  user_activity_info.chain_count = chain_shard.activities_count;
 
   // Initialize the payable activity info.
   let payable_activity_info = ctx.accounts.payable_activity_info.as_mut();
-  payable_activity_info.chain_count = chain_stats.activities_count;
+  payable_activity_info.chain_count = chain_shard.activities_count;
 
   // Emit log and event.
   msg!(
@@ -159,8 +186,10 @@ pub fn create_payable_handler<'info>(
       version: 1,
       action_type: 1, // Create Payable
       payable_id: ctx.accounts.payable.key().to_bytes(),
+      nonce: ctx.accounts.payable.activities_count,
       is_closed: false,
       allowed_tokens_and_amounts: ataa_foreign,
+      allowed_payers_root: [0; 32],
     }
     .try_to_vec()?,
     wormhole::Finality::Confirmed,
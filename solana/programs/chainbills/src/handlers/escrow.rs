@@ -0,0 +1,299 @@
+use crate::{context::*, error::ChainbillsError, events::*, state::*};
+use anchor_lang::{
+  prelude::*,
+  solana_program::clock,
+  system_program::{self, Transfer},
+};
+use anchor_spl::token_interface::{self, TransferChecked};
+
+fn record_escrow_activity(
+  chain_shard: &mut Account<ChainStatsShard>,
+  user: &mut Account<User>,
+  payable: &mut Account<Payable>,
+  activity: &mut Account<ActivityRecord>,
+  user_activity_info: &mut Account<UserActivityInfo>,
+  payable_activity_info: &mut Account<PayableActivityInfo>,
+  activity_type: ActivityType,
+) -> Result<()> {
+  // Increment the activities counts.
+  chain_shard.activities_count = chain_shard.next_activity();
+  user.activities_count = user.next_activity();
+  payable.activities_count = payable.next_activity();
+
+  // Initialize the activity.
+  activity.chain_count = chain_shard.activities_count;
+  activity.user_count = user.activities_count;
+  activity.payable_count = payable.activities_count;
+  activity.timestamp = clock::Clock::get()?.unix_timestamp as u64;
+  activity.entity = payable.key();
+  activity.activity_type = activity_type;
+
+  // Initialize the user and payable activity infos.
+  user_activity_info.chain_count = chain_shard.activities_count;
+  payable_activity_info.chain_count = chain_shard.activities_count;
+
+  Ok(())
+}
+
+/// Stops counting a settled payment among the payable's held escrows.
+fn settle_held_escrow(payable_escrow: &mut Account<PayableEscrow>) {
+  payable_escrow.held_count = payable_escrow.held_count.checked_sub(1).unwrap();
+}
+
+/// Ensures that an escrowed payment is still held, so that it can be refunded.
+fn check_refund(escrow_state: &EscrowState) -> Result<()> {
+  require!(escrow_state.is_held(), ChainbillsError::EscrowIsSettled);
+  Ok(())
+}
+
+/// Allows a payable's host (or its operators with the manage role) to hold
+/// new payments in escrow.
+///
+/// ### args
+/// * timeout<u64>: The seconds after a payment from which anyone can release
+///   it. Zero stops escrowing new payments.
+/// * arbiter<Option<Pubkey>>: The wallet that settles disputes. Payments
+///   can't be disputed without one.
+#[inline(never)]
+pub fn update_payable_escrow(
+  ctx: Context<UpdatePayableEscrow>,
+  timeout: u64,
+  arbiter: Option<Pubkey>,
+) -> Result<()> {
+  /* STATE CHANGES */
+  // Update the payable's escrow settings.
+  let arbiter = arbiter.unwrap_or_default();
+  let payable_escrow = ctx.accounts.payable_escrow.as_mut();
+  payable_escrow.timeout = timeout;
+  payable_escrow.arbiter = arbiter;
+
+  // Record the activity.
+  let payable = ctx.accounts.payable.as_mut();
+  record_escrow_activity(
+    ctx.accounts.chain_shard.as_mut(),
+    ctx.accounts.host.as_mut(),
+    payable,
+    ctx.accounts.activity.as_mut(),
+    ctx.accounts.user_activity_info.as_mut(),
+    ctx.accounts.payable_activity_info.as_mut(),
+    ActivityType::UpdatedPayableEscrow,
+  )?;
+  ctx.accounts.activity.rent_payer = ctx.accounts.signer.key();
+
+  /* EVENTS */
+  msg!("Updated Payable's escrow.");
+  emit!(UpdatedPayableEscrow {
+    payable_id: payable.key(),
+    host_wallet: payable.host,
+    timeout,
+    arbiter,
+  });
+  Ok(())
+}
+
+/// Adds an escrowed payment to its payable's balances. Can be called by the
+/// payer or the arbiter anytime, and by anyone once the timeout elapsed on
+/// an undisputed payment.
+#[inline(never)]
+pub fn release_escrow(ctx: Context<ReleaseEscrow>) -> Result<()> {
+  /* CHECKS */
+  let escrow_state = ctx.accounts.escrow_state.as_mut();
+  let signer = ctx.accounts.signer.key();
+  let now = clock::Clock::get()?.unix_timestamp as u64;
+  require!(escrow_state.is_held(), ChainbillsError::EscrowIsSettled);
+  if !escrow_state.can_release(&signer, now) {
+    if escrow_state.status == EscrowStatus::Disputed {
+      return err!(ChainbillsError::EscrowIsDisputed);
+    }
+    return err!(ChainbillsError::EscrowNotReleasable);
+  }
+
+  /* STATE CHANGES */
+  let TokenAndAmount { token, amount } = escrow_state.details;
+  let payable = ctx.accounts.payable.as_mut();
+  payable.add_balance(token, amount);
+  escrow_state.status = EscrowStatus::Released;
+  settle_held_escrow(&mut ctx.accounts.payable_escrow);
+
+  // Record the activity for the payer.
+  record_escrow_activity(
+    ctx.accounts.chain_shard.as_mut(),
+    ctx.accounts.payer.as_mut(),
+    payable,
+    ctx.accounts.activity.as_mut(),
+    ctx.accounts.user_activity_info.as_mut(),
+    ctx.accounts.payable_activity_info.as_mut(),
+    ActivityType::ReleasedEscrow,
+  )?;
+  ctx.accounts.activity.rent_payer = ctx.accounts.signer.key();
+
+  /* EVENTS */
+  msg!("Released Escrow.");
+  emit!(ReleasedEscrow {
+    payable_id: payable.key(),
+    escrow_id: escrow_state.key(),
+    released_by: signer,
+    token,
+    amount,
+  });
+  Ok(())
+}
+
+/// Allows the payer to dispute an escrowed payment before its timeout, so
+/// that only the payer or the arbiter can settle it.
+#[inline(never)]
+pub fn dispute_escrow(ctx: Context<DisputeEscrow>) -> Result<()> {
+  /* CHECKS */
+  let escrow_state = ctx.accounts.escrow_state.as_mut();
+  let now = clock::Clock::get()?.unix_timestamp as u64;
+  require!(escrow_state.is_held(), ChainbillsError::EscrowIsSettled);
+  require!(
+    escrow_state.can_dispute(now),
+    ChainbillsError::EscrowNotDisputable
+  );
+
+  /* STATE CHANGES */
+  escrow_state.status = EscrowStatus::Disputed;
+
+  // Record the activity for the payer.
+  let payable = ctx.accounts.payable.as_mut();
+  record_escrow_activity(
+    ctx.accounts.chain_shard.as_mut(),
+    ctx.accounts.payer.as_mut(),
+    payable,
+    ctx.accounts.activity.as_mut(),
+    ctx.accounts.user_activity_info.as_mut(),
+    ctx.accounts.payable_activity_info.as_mut(),
+    ActivityType::DisputedEscrow,
+  )?;
+  ctx.accounts.activity.rent_payer = ctx.accounts.signer.key();
+
+  /* EVENTS */
+  msg!("Disputed Escrow.");
+  emit!(DisputedEscrow {
+    payable_id: payable.key(),
+    escrow_id: escrow_state.key(),
+    payer_wallet: escrow_state.payer,
+  });
+  Ok(())
+}
+
+/// Sends an escrowed payment in tokens back to its payer. Can be called by
+/// the arbiter or the payable's host.
+#[inline(never)]
+pub fn refund_escrow(ctx: Context<RefundEscrow>) -> Result<()> {
+  /* CHECKS */
+  check_refund(&ctx.accounts.escrow_state)?;
+
+  /* TRANSFER */
+  let amount = ctx.accounts.escrow_state.details.amount;
+  let mint = &ctx.accounts.mint;
+  token_interface::transfer_checked(
+    CpiContext::new_with_signer(
+      ctx.accounts.token_program.to_account_info(),
+      TransferChecked {
+        from: ctx.accounts.chain_token_account.to_account_info(),
+        mint: mint.to_account_info(),
+        to: ctx.accounts.payer_token_account.to_account_info(),
+        authority: ctx.accounts.chain_stats.to_account_info(),
+      },
+      &[&[ChainStats::SEED_PREFIX, &[ctx.bumps.chain_stats]]],
+    ),
+    amount,
+    mint.decimals,
+  )?;
+
+  /* STATE CHANGES */
+  // Refunds leave the chain like withdrawals do.
+  ctx.accounts.token_details.add_withdrawn(amount);
+  let escrow_state = ctx.accounts.escrow_state.as_mut();
+  escrow_state.status = EscrowStatus::Refunded;
+  settle_held_escrow(&mut ctx.accounts.payable_escrow);
+
+  // Record the activity for the payer.
+  let payable = ctx.accounts.payable.as_mut();
+  record_escrow_activity(
+    ctx.accounts.chain_shard.as_mut(),
+    ctx.accounts.payer.as_mut(),
+    payable,
+    ctx.accounts.activity.as_mut(),
+    ctx.accounts.user_activity_info.as_mut(),
+    ctx.accounts.payable_activity_info.as_mut(),
+    ActivityType::RefundedEscrow,
+  )?;
+  ctx.accounts.activity.rent_payer = ctx.accounts.signer.key();
+
+  /* EVENTS */
+  msg!("Refunded Escrow.");
+  emit!(RefundedEscrow {
+    payable_id: payable.key(),
+    escrow_id: escrow_state.key(),
+    payer_wallet: escrow_state.payer,
+    refunded_by: ctx.accounts.signer.key(),
+    token: mint.key(),
+    amount,
+  });
+  Ok(())
+}
+
+/// Sends an escrowed payment in native tokens (Solana) back to its payer.
+/// Can be called by the arbiter or the payable's host.
+#[inline(never)]
+pub fn refund_escrow_native(ctx: Context<RefundEscrowNative>) -> Result<()> {
+  /* CHECKS */
+  check_refund(&ctx.accounts.escrow_state)?;
+
+  // Ensure that the vault stays rent-exempt after this refund.
+  let amount = ctx.accounts.escrow_state.details.amount;
+  let vault = &ctx.accounts.native_vault;
+  let rent_exempt_reserve = Rent::get()?.minimum_balance(0);
+  require!(
+    vault.lamports().saturating_sub(amount) >= rent_exempt_reserve,
+    ChainbillsError::InsufficientNativeVaultBalance
+  );
+
+  /* TRANSFER */
+  system_program::transfer(
+    CpiContext::new_with_signer(
+      ctx.accounts.system_program.to_account_info(),
+      Transfer {
+        from: vault.to_account_info(),
+        to: ctx.accounts.payer_wallet.to_account_info(),
+      },
+      &[&[SEED_PREFIX_NATIVE_VAULT, &[ctx.bumps.native_vault]]],
+    ),
+    amount,
+  )?;
+
+  /* STATE CHANGES */
+  // Refunds leave the chain like withdrawals do.
+  ctx.accounts.token_details.add_withdrawn(amount);
+  let escrow_state = ctx.accounts.escrow_state.as_mut();
+  escrow_state.status = EscrowStatus::Refunded;
+  settle_held_escrow(&mut ctx.accounts.payable_escrow);
+
+  // Record the activity for the payer.
+  let payable = ctx.accounts.payable.as_mut();
+  record_escrow_activity(
+    ctx.accounts.chain_shard.as_mut(),
+    ctx.accounts.payer.as_mut(),
+    payable,
+    ctx.accounts.activity.as_mut(),
+    ctx.accounts.user_activity_info.as_mut(),
+    ctx.accounts.payable_activity_info.as_mut(),
+    ActivityType::RefundedEscrow,
+  )?;
+  ctx.accounts.activity.rent_payer = ctx.accounts.signer.key();
+
+  /* EVENTS */
+  msg!("Refunded Escrow.");
+  emit!(RefundedEscrow {
+    payable_id: payable.key(),
+    escrow_id: escrow_state.key(),
+    payer_wallet: escrow_state.payer,
+    refunded_by: ctx.accounts.signer.key(),
+    token: crate::ID,
+    amount,
+  });
+  Ok(())
+}
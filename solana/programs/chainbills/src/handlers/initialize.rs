@@ -1,4 +1,8 @@
-use crate::{context::Initialize, events::*, state::SEED_PREFIX_SENT};
+use crate::{
+  context::Initialize,
+  events::*,
+  state::{Config, SEED_PREFIX_SENT},
+};
 use anchor_lang::prelude::*;
 use wormhole_anchor_sdk::wormhole;
 
@@ -10,6 +14,7 @@ use wormhole_anchor_sdk::wormhole;
 pub fn initialize_handler(ctx: Context<Initialize>) -> Result<()> {
   // Initialize config account.
   let config = &mut ctx.accounts.config.load_init()?;
+  config.version = Config::VERSION;
   config.chain_id = wormhole::CHAIN_ID_SOLANA;
   config.withdrawal_fee_percentage = 200u16; // 2.00%
   config.owner = *ctx.accounts.owner.to_account_info().key;
@@ -0,0 +1,22 @@
+use crate::{context::*, error::*, events::*, state::*};
+use anchor_lang::prelude::*;
+
+/// Initializes one of the shards of the chain's counts. Owner-only.
+///
+/// ### args
+/// * index<u8>: The index of the shard, below [`ChainStatsShard::COUNT`].
+#[inline(never)]
+pub fn initialize_chain_shard(
+  ctx: Context<InitializeChainShard>,
+  index: u8,
+) -> Result<()> {
+  require!(
+    index < ChainStatsShard::COUNT,
+    ChainbillsError::InvalidChainShard
+  );
+  ctx.accounts.chain_shard.initialize(index);
+
+  msg!("Initialized ChainStatsShard: {}.", index);
+  emit!(InitializedChainShard { index });
+  Ok(())
+}
@@ -1,4 +1,4 @@
-use crate::{context::InitializeUser, events::*, state::ActivityType};
+use crate::{context::InitializeUser, events::*, state::{ActivityType, User}};
 use anchor_lang::prelude::*;
 use solana_program::clock;
 
@@ -8,13 +8,14 @@ use solana_program::clock;
 /// interact with this program.
 pub fn initialize_user_handler(ctx: Context<InitializeUser>) -> Result<()> {
   // Increment chain count for users and activities.
-  let chain_stats = ctx.accounts.chain_stats.as_mut();
-  chain_stats.users_count = chain_stats.next_user();
-  chain_stats.activities_count = chain_stats.next_activity();
+  let chain_shard = ctx.accounts.chain_shard.as_mut();
+  chain_shard.users_count = chain_shard.next_user();
+  chain_shard.activities_count = chain_shard.next_activity();
 
   // Initialize the user.
   let user = ctx.accounts.user.as_mut();
-  user.chain_count = chain_stats.users_count;
+  user.version = User::VERSION;
+  user.chain_count = chain_shard.users_count;
   user.payables_count = 0;
   user.payments_count = 0;
   user.withdrawals_count = 0;
@@ -26,16 +27,17 @@ pub fn initialize_user_handler(ctx: Context<InitializeUser>) -> Result<()> {
 
   // Initialize the activity.
   let activity = ctx.accounts.activity.as_mut();
-  activity.chain_count = chain_stats.activities_count;
+  activity.chain_count = chain_shard.activities_count;
   activity.user_count = user.activities_count;
   activity.payable_count = 0; // Setting 0 because it's not a payable activity.
   activity.timestamp = clock::Clock::get()?.unix_timestamp as u64;
   activity.entity = ctx.accounts.signer.key();
   activity.activity_type = ActivityType::InitializedUser;
+  activity.rent_payer = ctx.accounts.signer.key();
 
   // Initialize the user activity info.
   let user_activity_info = ctx.accounts.user_activity_info.as_mut();
-  user_activity_info.chain_count = chain_stats.activities_count;
+  user_activity_info.chain_count = chain_shard.activities_count;
 
   // Emit log and event.
   msg!("Initialized User with chain_count: {}.", user.chain_count);
@@ -0,0 +1,302 @@
+use crate::{context::*, error::ChainbillsError, events::*, state::*};
+use anchor_lang::{
+  prelude::*,
+  system_program::{self, Transfer},
+  Discriminator,
+};
+
+/// Resizes the account to fit the upgraded data (with its discriminator),
+/// tops up its rent from the payer if needed, and writes the data.
+fn write_upgraded<'info>(
+  account: &AccountInfo<'info>,
+  payer: &Signer<'info>,
+  system_program: &Program<'info, System>,
+  data: &[u8],
+) -> Result<()> {
+  let rent_exempt = Rent::get()?.minimum_balance(data.len());
+  let lamports = account.lamports();
+  if rent_exempt > lamports {
+    system_program::transfer(
+      CpiContext::new(
+        system_program.to_account_info(),
+        Transfer {
+          from: payer.to_account_info(),
+          to: account.clone(),
+        },
+      ),
+      rent_exempt.checked_sub(lamports).unwrap(),
+    )?;
+  }
+
+  account.realloc(data.len(), false)?;
+  account.try_borrow_mut_data()?.copy_from_slice(data);
+
+  msg!("Migrated account to version {}.", data[8]);
+  emit!(MigratedAccount {
+    account: account.key(),
+    version: data[8],
+  });
+  Ok(())
+}
+
+/// Serializes a borsh account with its discriminator.
+fn serialize_upgraded(account: &impl AccountSerialize) -> Result<Vec<u8>> {
+  let mut data = Vec::new();
+  account.try_serialize(&mut data)?;
+  Ok(data)
+}
+
+/// Upgrades a Payable to its current layout. Can be called only by the host.
+#[inline(never)]
+pub fn migrate_payable(ctx: Context<MigratePayable>) -> Result<()> {
+  let payable = {
+    let data = ctx.accounts.payable.try_borrow_data()?;
+    require!(
+      PayableV0::is_legacy(&data),
+      ChainbillsError::AlreadyMigrated
+    );
+    deserialize_legacy::<Payable, PayableV0>(&data)?.upgrade()
+  };
+  require!(
+    payable.host == ctx.accounts.signer.key(),
+    ChainbillsError::NotYourPayable
+  );
+
+  write_upgraded(
+    &ctx.accounts.payable,
+    &ctx.accounts.signer,
+    &ctx.accounts.system_program,
+    &serialize_upgraded(&payable)?,
+  )
+}
+
+/// Upgrades the signer's User to its current layout.
+#[inline(never)]
+pub fn migrate_user(ctx: Context<MigrateUser>) -> Result<()> {
+  let user = {
+    let data = ctx.accounts.user.try_borrow_data()?;
+    require!(
+      data.len() == UserV0::SPACE,
+      ChainbillsError::AlreadyMigrated
+    );
+    deserialize_legacy::<User, UserV0>(&data)?.upgrade()
+  };
+
+  write_upgraded(
+    &ctx.accounts.user,
+    &ctx.accounts.signer,
+    &ctx.accounts.system_program,
+    &serialize_upgraded(&user)?,
+  )
+}
+
+/// Upgrades a UserPayment to its current layout. Can be called only by the
+/// payer.
+#[inline(never)]
+pub fn migrate_user_payment(ctx: Context<MigrateUserPayment>) -> Result<()> {
+  let user_payment = {
+    let data = ctx.accounts.user_payment.try_borrow_data()?;
+    match data.len() {
+      UserPaymentV0::SPACE => {
+        deserialize_legacy::<UserPayment, UserPaymentV0>(&data)?.upgrade()
+      }
+      UserPaymentV1::SPACE => {
+        deserialize_legacy::<UserPayment, UserPaymentV1>(&data)?.upgrade()
+      }
+      _ => return err!(ChainbillsError::AlreadyMigrated),
+    }
+  };
+  require!(
+    user_payment.payer == ctx.accounts.signer.key(),
+    ChainbillsError::NotYourPayment
+  );
+
+  write_upgraded(
+    &ctx.accounts.user_payment,
+    &ctx.accounts.signer,
+    &ctx.accounts.system_program,
+    &serialize_upgraded(&user_payment)?,
+  )
+}
+
+/// Upgrades a PayablePayment to its current layout. Can be called by anyone,
+/// as the upgrade doesn't depend on the caller.
+#[inline(never)]
+pub fn migrate_payable_payment(
+  ctx: Context<MigratePayablePayment>,
+) -> Result<()> {
+  let payable_payment = {
+    let data = ctx.accounts.payable_payment.try_borrow_data()?;
+    match data.len() {
+      PayablePaymentV0::SPACE => {
+        deserialize_legacy::<PayablePayment, PayablePaymentV0>(&data)?.upgrade()
+      }
+      PayablePaymentV1::SPACE => {
+        deserialize_legacy::<PayablePayment, PayablePaymentV1>(&data)?.upgrade()
+      }
+      _ => return err!(ChainbillsError::AlreadyMigrated),
+    }
+  };
+
+  write_upgraded(
+    &ctx.accounts.payable_payment,
+    &ctx.accounts.signer,
+    &ctx.accounts.system_program,
+    &serialize_upgraded(&payable_payment)?,
+  )
+}
+
+/// Upgrades a Withdrawal to its current layout. Can be called only by the
+/// host.
+#[inline(never)]
+pub fn migrate_withdrawal(ctx: Context<MigrateWithdrawal>) -> Result<()> {
+  let withdrawal = {
+    let data = ctx.accounts.withdrawal.try_borrow_data()?;
+    match data.len() {
+      WithdrawalV0::SPACE => {
+        deserialize_legacy::<Withdrawal, WithdrawalV0>(&data)?.upgrade()
+      }
+      WithdrawalV1::SPACE => {
+        deserialize_legacy::<Withdrawal, WithdrawalV1>(&data)?.upgrade()
+      }
+      _ => return err!(ChainbillsError::AlreadyMigrated),
+    }
+  };
+  require!(
+    withdrawal.host == ctx.accounts.signer.key(),
+    ChainbillsError::NotYourWithdrawal
+  );
+
+  write_upgraded(
+    &ctx.accounts.withdrawal,
+    &ctx.accounts.signer,
+    &ctx.accounts.system_program,
+    &serialize_upgraded(&withdrawal)?,
+  )
+}
+
+/// Upgrades one of the signer's ActivityRecords to its current layout. The
+/// signer is recorded as its rent payer.
+///
+/// ### args
+/// * user_count<u64>: The nth count of the user's activities to upgrade.
+#[inline(never)]
+pub fn migrate_activity_record(
+  ctx: Context<MigrateActivityRecord>,
+  _user_count: u64,
+) -> Result<()> {
+  let activity = {
+    let data = ctx.accounts.activity.try_borrow_data()?;
+    require!(
+      data.len() == ActivityRecordV0::SPACE,
+      ChainbillsError::AlreadyMigrated
+    );
+    deserialize_legacy::<ActivityRecord, ActivityRecordV0>(&data)?
+      .upgrade(ctx.accounts.signer.key())
+  };
+
+  write_upgraded(
+    &ctx.accounts.activity,
+    &ctx.accounts.signer,
+    &ctx.accounts.system_program,
+    &serialize_upgraded(&activity)?,
+  )
+}
+
+/// Upgrades the Config to its current layout. Can be called only by the
+/// owner recorded in the Config.
+#[inline(never)]
+pub fn migrate_config(ctx: Context<MigrateConfig>) -> Result<()> {
+  let config = {
+    let data = ctx.accounts.config.try_borrow_data()?;
+    require!(
+      data.len() == ConfigV0::SPACE,
+      ChainbillsError::AlreadyMigrated
+    );
+    deserialize_legacy::<Config, ConfigV0>(&data)?.upgrade()
+  };
+  require!(
+    config.owner == ctx.accounts.owner.key(),
+    ChainbillsError::OwnerUnauthorized
+  );
+
+  let mut data = Config::DISCRIMINATOR.to_vec();
+  data.extend_from_slice(bytemuck::bytes_of(&config));
+  write_upgraded(
+    &ctx.accounts.config,
+    &ctx.accounts.owner,
+    &ctx.accounts.system_program,
+    &data,
+  )
+}
+
+/// Upgrades Solana's ChainStats to its current layout. Can be called only by
+/// the owner.
+#[inline(never)]
+pub fn migrate_chain_stats(ctx: Context<MigrateChainStats>) -> Result<()> {
+  let chain_stats = {
+    let data = ctx.accounts.chain_stats.try_borrow_data()?;
+    require!(
+      data.len() == ChainStatsV0::SPACE,
+      ChainbillsError::AlreadyMigrated
+    );
+    deserialize_legacy::<ChainStats, ChainStatsV0>(&data)?.upgrade()
+  };
+
+  write_upgraded(
+    &ctx.accounts.chain_stats,
+    &ctx.accounts.owner,
+    &ctx.accounts.system_program,
+    &serialize_upgraded(&chain_stats)?,
+  )
+}
+
+/// Upgrades the TokenDetails of the given token to its current layout. Can be
+/// called only by the owner.
+///
+/// ### args
+/// * token<Pubkey>: The token mint (or this program's ID for native SOL).
+#[inline(never)]
+pub fn migrate_token_details(
+  ctx: Context<MigrateTokenDetails>,
+  _token: Pubkey,
+) -> Result<()> {
+  let token_details = {
+    let data = ctx.accounts.token_details.try_borrow_data()?;
+    require!(
+      data.len() == TokenDetailsV0::SPACE,
+      ChainbillsError::AlreadyMigrated
+    );
+    deserialize_legacy::<TokenDetails, TokenDetailsV0>(&data)?.upgrade()
+  };
+
+  write_upgraded(
+    &ctx.accounts.token_details,
+    &ctx.accounts.owner,
+    &ctx.accounts.system_program,
+    &serialize_upgraded(&token_details)?,
+  )
+}
+
+/// Moves the native SOL that was paid into ChainStats (before the native
+/// vault existed) into the native vault. Leaves ChainStats rent-exempt.
+/// Should be called only by the owner.
+#[inline(never)]
+pub fn migrate_native_vault(ctx: Context<MigrateNativeVault>) -> Result<()> {
+  let chain_stats = ctx.accounts.chain_stats.to_account_info();
+  let rent_exempt_reserve =
+    Rent::get()?.minimum_balance(chain_stats.data_len());
+  let excess = chain_stats.lamports().saturating_sub(rent_exempt_reserve);
+  require!(excess > 0, ChainbillsError::AlreadyMigrated);
+
+  // ChainStats is owned by this program, so its lamports can be debited
+  // directly.
+  let vault = ctx.accounts.native_vault.to_account_info();
+  let mut chain_stats_lamports = chain_stats.try_borrow_mut_lamports()?;
+  **chain_stats_lamports = chain_stats_lamports.checked_sub(excess).unwrap();
+  let mut vault_lamports = vault.try_borrow_mut_lamports()?;
+  **vault_lamports = vault_lamports.checked_add(excess).unwrap();
+
+  msg!("Moved {} lamports into the native vault.", excess);
+  Ok(())
+}
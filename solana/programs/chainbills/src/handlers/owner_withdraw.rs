@@ -2,7 +2,7 @@ use crate::{
   context::OwnerWithdraw, error::ChainbillsError, events::*, state::ChainStats,
 };
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Transfer as SplTransfer};
+use anchor_spl::token_interface::{self, TransferChecked};
 
 /// Withdraws fees from this program.
 /// Should be called only by upgrade authority holder of this program.
@@ -20,19 +20,22 @@ pub fn owner_withdraw_handler(
   let source = &ctx.accounts.chain_token_account;
   let token_program = &ctx.accounts.token_program;
   let authority = &ctx.accounts.chain_stats;
-  let cpi_accounts = SplTransfer {
+  let mint = &ctx.accounts.mint;
+  let cpi_accounts = TransferChecked {
     from: source.to_account_info().clone(),
+    mint: mint.to_account_info().clone(),
     to: destination.to_account_info().clone(),
     authority: authority.to_account_info().clone(),
   };
   let cpi_program = token_program.to_account_info();
-  token::transfer(
+  token_interface::transfer_checked(
     CpiContext::new_with_signer(
       cpi_program,
       cpi_accounts,
       &[&[ChainStats::SEED_PREFIX, &[ctx.bumps.chain_stats]]],
     ),
     amount,
+    mint.decimals,
   )?;
 
   msg!("Owner made a withdrawal.");
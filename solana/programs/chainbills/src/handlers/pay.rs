@@ -1,12 +1,24 @@
+use super::receipts::{mint_receipt, ReceiptAccounts};
 use crate::{context::*, error::ChainbillsError, events::*, state::*};
 use anchor_lang::{
   prelude::*,
-  solana_program::clock,
-  system_program::{self, Transfer},
+  solana_program::{
+    clock, ed25519_program,
+    sysvar::instructions::{
+      load_current_index_checked, load_instruction_at_checked,
+    },
+  },
+  system_program::{self, CreateAccount, Transfer},
 };
-use anchor_spl::token::{self, Transfer as SplTransfer};
+use anchor_spl::{
+  token::spl_token::native_mint,
+  token_interface::{self, TransferChecked},
+};
+use chainbills_payload::PaymentIntent;
 
-fn check_pay_inputs(
+/// Ensures that the payable can receive a payment of the amount in the token,
+/// whether or not it is among the payable's allowed tokens and amounts.
+fn check_payable_can_receive(
   amount: u64,
   mint: Pubkey,
   payable: &Account<Payable>,
@@ -24,6 +36,32 @@ fn check_pay_inputs(
   // Ensure that the payable is not closed
   require!(!payable.is_closed, ChainbillsError::PayableIsClosed);
 
+  // Ensure that a new balance entry won't grow the payable beyond what can
+  // be processed.
+  if !payable.balances.iter().any(|b| b.token == mint) {
+    require!(
+      payable.balances.len() < Payable::MAX_BALANCES,
+      ChainbillsError::TooManyBalances
+    );
+  }
+
+  Ok(())
+}
+
+/// Ensures that the payable accepts a payment of the amount in the token.
+/// An allowed token that is the given token group's address accepts the
+/// group's members, for the amount normalized to their decimals, which must
+/// be the token's decimals.
+pub(crate) fn check_payment(
+  amount: u64,
+  mint: Pubkey,
+  decimals: u8,
+  payable: &Account<Payable>,
+  token_details: &Account<TokenDetails>,
+  token_group: Option<&Account<TokenGroup>>,
+) -> Result<()> {
+  check_payable_can_receive(amount, mint, payable, token_details)?;
+
   // If this payable specified the tokens and amounts it can accept, ensure
   // that the token and amount are matching.
   if !payable.allowed_tokens_and_amounts.is_empty() {
@@ -32,6 +70,14 @@ fn check_pay_inputs(
       if taa.token == mint && taa.amount == amount {
         break;
       }
+      if let Some(token_group) = token_group {
+        if taa.token == token_group.key()
+          && token_group.member_amount(mint, decimals, taa.amount)
+            == Some(amount)
+        {
+          break;
+        }
+      }
       if ataa_it.peek().is_none() {
         return err!(ChainbillsError::MatchingTokenAndAmountNotFound);
       }
@@ -41,30 +87,66 @@ fn check_pay_inputs(
   Ok(())
 }
 
-fn update_state_for_user_payment(
+/// Where a payment records one of its index entries: in the entry's own
+/// account, or in a slot of one of the ledger pages that replace such
+/// accounts.
+pub(crate) enum LedgerEntry<'a, A, P> {
+  Account(&'a mut A),
+  Page(&'a mut P),
+}
+
+impl<'a, A, P> LedgerEntry<'a, A, P> {
+  /// Ensures that exactly one of the entry's own account and its ledger page
+  /// was passed.
+  pub(crate) fn of(
+    account: Option<&'a mut A>,
+    page: Option<&'a mut P>,
+  ) -> Result<Self> {
+    match (account, page) {
+      (Some(account), None) => Ok(Self::Account(account)),
+      (None, Some(page)) => Ok(Self::Page(page)),
+      _ => err!(ChainbillsError::InvalidLedgerAccounts),
+    }
+  }
+
+  /// Like [`LedgerEntry::of`], but for entries that may not exist, in which
+  /// case neither is passed.
+  pub(crate) fn maybe_of(
+    account: Option<&'a mut A>,
+    page: Option<&'a mut P>,
+  ) -> Result<Option<Self>> {
+    match (account, page) {
+      (None, None) => Ok(None),
+      (account, page) => Self::of(account, page).map(Some),
+    }
+  }
+}
+
+pub(crate) fn update_state_for_user_payment(
   amount: u64,
   mint: Pubkey,
   signer: Pubkey,
+  rent_payer: Pubkey,
   payer: &mut Account<User>,
-  chain_stats: &mut Account<ChainStats>,
+  chain_shard: &mut Account<ChainStatsShard>,
   payable_id: [u8; 32],
-  payable_chain_id: u16,
-  token_details: &mut Account<TokenDetails>,
+  payable_chain_id: [u8; 32],
+  token_details_shard: &mut Account<TokenDetailsShard>,
   user_payment: &mut Account<UserPayment>,
-  chain_user_payment_id: &mut Account<ChainUserPaymentId>,
   user_activity: &mut Account<ActivityRecord>,
-  user_activity_info: &mut Account<UserActivityInfo>,
-) -> Result<()> {
-  // Increment user_payments_count and activities_count in chain_stats.
-  chain_stats.user_payments_count = chain_stats.next_user_payment();
-  chain_stats.activities_count = chain_stats.next_activity();
+) -> Result<UserPaid> {
+  // Increment user_payments_count and activities_count in the chain shard.
+  chain_shard.user_payments_count = chain_shard.next_user_payment();
+  chain_shard.activities_count = chain_shard.next_activity();
 
   // Increment payments_count and activities_count in the payer that just paid.
   payer.payments_count = payer.next_payment();
   payer.activities_count = payer.next_activity();
 
-  // Increase the supported token's totals from this payment.
-  token_details.add_user_paid(amount);
+  // Increase the supported token's totals in the shard from this payment.
+  token_details_shard.mint = mint;
+  token_details_shard.index = chain_shard.index;
+  token_details_shard.add_user_paid(amount);
 
   let timestamp = clock::Clock::get()?.unix_timestamp as u64;
   let payment_details = TokenAndAmount {
@@ -73,100 +155,116 @@ fn update_state_for_user_payment(
   };
 
   // Initialize the User Payment.
-  user_payment.chain_count = chain_stats.payables_count;
+  user_payment.chain_count = chain_shard.user_payments_count;
   user_payment.payable_id = payable_id;
   user_payment.payable_chain_id = payable_chain_id;
   user_payment.payer = signer;
   user_payment.payer_count = payer.payments_count;
   user_payment.timestamp = timestamp;
   user_payment.details = payment_details;
-
-  // Initialize the Chain User Payment ID.
-  chain_user_payment_id.user_payment_id = user_payment.key();
+  user_payment.rent_payer = rent_payer;
 
   // Initialize the User Activity.
-  user_activity.chain_count = chain_stats.activities_count;
+  user_activity.chain_count = chain_shard.activities_count;
   user_activity.user_count = payer.activities_count;
   // Setting 0 because it's not a payable activity.
   user_activity.payable_count = 0;
   user_activity.timestamp = timestamp;
   user_activity.entity = user_payment.key();
   user_activity.activity_type = ActivityType::UserPaid;
+  user_activity.rent_payer = rent_payer;
 
-  // Initialize the User Activity Info.
-  user_activity_info.chain_count = chain_stats.activities_count;
-
-  // Emit logs and events.
+  // Log and return the event for the caller to emit through CPI.
   msg!(
     "User Payment was made with chain_count: {} and payer_count: {}.",
     user_payment.chain_count,
     user_payment.payer_count
   );
-  emit!(UserPaid {
+  Ok(UserPaid {
     payable_id,
     payer_wallet: signer,
     payment_id: user_payment.key(),
     payable_chain_id: user_payment.payable_chain_id,
     chain_count: user_payment.chain_count,
     payer_count: user_payment.payer_count,
-  });
+    token: mint,
+    amount,
+    timestamp,
+  })
+}
+
+/// Adds the payment to the payable's balances, or locks it in the escrow
+/// state if the payable escrows its payments.
+pub(crate) fn credit_or_escrow_payment(
+  payable: &mut Account<Payable>,
+  payable_escrow: &AccountInfo,
+  escrow_state: Option<&mut Account<EscrowState>>,
+  payer: Pubkey,
+  details: TokenAndAmount,
+) -> Result<()> {
+  let Some(mut escrow) = PayableEscrow::active(payable_escrow)? else {
+    require!(
+      escrow_state.is_none(),
+      ChainbillsError::InvalidEscrowAccounts
+    );
+    payable.add_balance(details.token, details.amount);
+    return Ok(());
+  };
+  let Some(escrow_state) = escrow_state else {
+    return err!(ChainbillsError::EscrowStateRequired);
+  };
+
+  let now = clock::Clock::get()?.unix_timestamp as u64;
+  escrow_state.payable_id = payable.key();
+  escrow_state.payer = payer;
+  escrow_state.details = details;
+  escrow_state.release_after = now.checked_add(escrow.timeout).unwrap();
+  escrow_state.arbiter = escrow.arbiter;
+  escrow_state.status = EscrowStatus::Locked;
+  escrow.held_count = escrow.held_count.checked_add(1).unwrap();
+  escrow.save(payable_escrow)?;
+  msg!(
+    "Payment is held in escrow until: {}.",
+    escrow_state.release_after
+  );
   Ok(())
 }
 
-fn update_state_for_payable_payment(
+pub(crate) fn update_state_for_payable_payment(
   amount: u64,
   mint: Pubkey,
+  rent_payer: Pubkey,
   payable: &mut Account<Payable>,
   payable_per_chain_payments_counter: &mut Account<
     PayablePerChainPaymentsCounter,
   >,
-  chain_stats: &mut Account<ChainStats>,
+  chain_shard: &mut Account<ChainStatsShard>,
   payer_wallet: [u8; 32],
-  payer_chain_id: u16,
-  token_details: &mut Account<TokenDetails>,
+  payer_chain_id: [u8; 32],
+  token_details_shard: &mut Account<TokenDetailsShard>,
   payable_payment: &mut Account<PayablePayment>,
-  chain_payable_payment_id: &mut Account<ChainPayablePaymentId>,
-  payable_per_chain_payment_info: &mut Account<PayablePerChainPaymentInfo>,
+  payable_per_chain_payment_info: LedgerEntry<
+    Account<PayablePerChainPaymentInfo>,
+    Account<CountLedgerPage>,
+  >,
   payable_activity: &mut Account<ActivityRecord>,
-  payable_activity_info: &mut Account<PayableActivityInfo>,
-) -> Result<()> {
-  // Increment payable_payments_count and activities_count in chain_stats.
-  chain_stats.payable_payments_count = chain_stats.next_payable_payment();
-  chain_stats.activities_count = chain_stats.next_activity();
+) -> Result<PayableReceived> {
+  // Increment payable_payments_count and activities_count in the chain shard.
+  chain_shard.payable_payments_count = chain_shard.next_payable_payment();
+  chain_shard.activities_count = chain_shard.next_activity();
 
   // Increment payments_count and activities_count on involved payable.
   payable.payments_count = payable.next_payment();
   payable.activities_count = payable.next_activity();
 
-  // Update payable's balances to add this token and its amount.
-  //
-  // This boolean and the following two scopes was used (instead of peekable)
-  // to solve the borrowing twice bug with rust on the payable variable.
-  let mut was_matching_balance_updated = false;
-  {
-    for balance in payable.balances.iter_mut() {
-      if balance.token == mint {
-        balance.amount = balance.amount.checked_add(amount).unwrap();
-        was_matching_balance_updated = true;
-        break;
-      }
-    }
-  }
-  {
-    if !was_matching_balance_updated {
-      payable.balances.push(TokenAndAmount {
-        token: mint,
-        amount,
-      });
-    }
-  }
-
   // Increment payments_count on the payable_chain_counter for Solana.
   payable_per_chain_payments_counter.payments_count =
     payable_per_chain_payments_counter.next_payment();
 
-  // Increase the supported token's totals from this payment.
-  token_details.add_payable_received(amount);
+  // Increase the supported token's totals in the shard from this payment.
+  token_details_shard.mint = mint;
+  token_details_shard.index = chain_shard.index;
+  token_details_shard.add_payable_received(amount);
 
   let timestamp = clock::Clock::get()?.unix_timestamp as u64;
   let payment_details = TokenAndAmount {
@@ -177,124 +275,492 @@ fn update_state_for_payable_payment(
   // Initialize the Payable Payment.
   payable_payment.payable_id = payable.key();
   payable_payment.payer = payer_wallet;
-  payable_payment.chain_count = chain_stats.payable_payments_count;
+  payable_payment.chain_count = chain_shard.payable_payments_count;
   payable_payment.payer_chain_id = payer_chain_id;
   payable_payment.local_chain_count =
     payable_per_chain_payments_counter.payments_count;
   payable_payment.payable_count = payable.payments_count;
   payable_payment.timestamp = timestamp;
   payable_payment.details = payment_details;
+  payable_payment.rent_payer = rent_payer;
 
-  // Initialize the Chain Payable Payment ID.
-  chain_payable_payment_id.payable_payment_id = payable_payment.key();
-
-  // Initialize the Payable Per Chain Payment. This is used for retrieving
-  // payments per chain. The stored payable_count can then be used to get the
-  // main payable_payment.
-  payable_per_chain_payment_info.payable_count = payable.payments_count;
+  // Initialize the Payable Per Chain Payment, or record it in its ledger
+  // page. This is used for retrieving payments per chain. The stored
+  // payable_count can then be used to get the main payable_payment.
+  match payable_per_chain_payment_info {
+    LedgerEntry::Account(info) => info.payable_count = payable.payments_count,
+    LedgerEntry::Page(page) => page.set(
+      payable_per_chain_payments_counter.payments_count,
+      payable.payments_count,
+    ),
+  }
 
   // Initialize the Payable Activity.
-  payable_activity.chain_count = chain_stats.activities_count;
+  payable_activity.chain_count = chain_shard.activities_count;
   // Setting 0 because it's not a user activity.
   payable_activity.user_count = 0;
   payable_activity.payable_count = payable.activities_count;
   payable_activity.timestamp = timestamp;
   payable_activity.entity = payable_payment.key();
   payable_activity.activity_type = ActivityType::PayableReceived;
+  payable_activity.rent_payer = rent_payer;
 
-  // Initialize the Payable Activity Info.
-  payable_activity_info.chain_count = chain_stats.activities_count;
-
-  // Emit logs and events.
+  // Log and return the event for the caller to emit through CPI.
   msg!(
     "Payable Payment was received with chain_count: {}, and payable_count: {}.",
     payable_payment.chain_count,
     payable_payment.payable_count
   );
-  emit!(PayableReceived {
+  Ok(PayableReceived {
     payable_id: payable.key(),
     payer_wallet,
     payment_id: payable_payment.key(),
     payer_chain_id: payable_payment.payer_chain_id,
     chain_count: payable_payment.chain_count,
     payable_count: payable_payment.payable_count,
-  });
-  Ok(())
+    token: mint,
+    amount,
+    timestamp,
+  })
 }
 
 /// Transfers the amount of tokens to a payable
 ///
 /// ### args
 /// * amount<u64>: The Wormhole-normalized amount to be paid
+/// * payer_proof<Vec<[u8; 32]>>: The Merkle proof that the signer is an
+///   allowed payer. Empty if the payable doesn't restrict its payers.
 #[inline(never)]
-pub fn pay(ctx: Context<Pay>, amount: u64) -> Result<()> {
+pub fn pay(
+  ctx: Context<Pay>,
+  amount: u64,
+  payer_proof: Vec<[u8; 32]>,
+) -> Result<()> {
   /* CHECKS */
   let mint = &ctx.accounts.mint;
   let payable = ctx.accounts.payable.as_mut();
-  let token_details = ctx.accounts.token_details.as_mut();
-  check_pay_inputs(amount, mint.key(), payable, token_details)?;
+  let token_details = &ctx.accounts.token_details;
+  // Ensure that the payer is allowed to pay if the payable restricted its
+  // payers.
+  require!(
+    PayableAllowedPayers::permits(
+      &ctx.accounts.allowed_payers,
+      ctx.accounts.signer.key,
+      &payer_proof
+    )?,
+    ChainbillsError::PayerNotAllowed
+  );
+  check_payment(
+    amount,
+    mint.key(),
+    mint.decimals,
+    payable,
+    token_details,
+    ctx.accounts.token_group.as_deref(),
+  )?;
 
   /* TRANSFER */
-  token::transfer(
+  let balance_before = ctx.accounts.chain_token_account.amount;
+  token_interface::transfer_checked(
     CpiContext::new(
       ctx.accounts.token_program.to_account_info(),
-      SplTransfer {
+      TransferChecked {
         from: ctx.accounts.payer_token_account.to_account_info(),
+        mint: mint.to_account_info(),
         to: ctx.accounts.chain_token_account.to_account_info(),
         authority: ctx.accounts.signer.to_account_info(),
       },
     ),
     amount,
+    mint.decimals,
   )?;
 
+  // Token-2022 mints with the transfer-fee extension withhold part of the
+  // amount at the destination. Credit the payable with what actually arrived.
+  ctx.accounts.chain_token_account.reload()?;
+  let received = ctx
+    .accounts
+    .chain_token_account
+    .amount
+    .checked_sub(balance_before)
+    .unwrap();
+  require!(received > 0, ChainbillsError::NothingReceivedFromTransfer);
+
   /* STATE CHANGES */
-  let chain_id = ctx.accounts.config.load()?.chain_id;
-  let chain_stats = ctx.accounts.chain_stats.as_mut();
+  let cb_chain_id = solana_cb_chain_id();
+  let chain_shard = ctx.accounts.chain_shard.as_mut();
 
   // Update State for User
-  update_state_for_user_payment(
+  let user_paid = update_state_for_user_payment(
     amount,
     mint.key(),
     ctx.accounts.signer.key(),
+    ctx.accounts.signer.key(),
     ctx.accounts.payer.as_mut(),
-    chain_stats,
+    chain_shard,
     payable.key().to_bytes(),
-    chain_id,
-    token_details,
+    cb_chain_id,
+    ctx.accounts.token_details_shard.as_mut(),
     ctx.accounts.user_payment.as_mut(),
-    ctx.accounts.chain_user_payment_id.as_mut(),
     ctx.accounts.user_activity.as_mut(),
-    ctx.accounts.user_activity_info.as_mut(),
+  )?;
+
+  // Credit the payable, or hold the payment in escrow.
+  credit_or_escrow_payment(
+    payable,
+    &ctx.accounts.payable_escrow,
+    ctx.accounts.escrow_state.as_deref_mut(),
+    ctx.accounts.signer.key(),
+    TokenAndAmount {
+      token: mint.key(),
+      amount: received,
+    },
   )?;
 
   // Update State for Payable
-  update_state_for_payable_payment(
+  let payable_received = update_state_for_payable_payment(
+    received,
+    mint.key(),
+    ctx.accounts.signer.key(),
+    payable,
+    ctx.accounts.payable_per_chain_payments_counter.as_mut(),
+    chain_shard,
+    ctx.accounts.signer.key().to_bytes(),
+    cb_chain_id,
+    ctx.accounts.token_details_shard.as_mut(),
+    ctx.accounts.payable_payment.as_mut(),
+    LedgerEntry::of(
+      ctx.accounts.payable_per_chain_payment_info.as_deref_mut(),
+      ctx.accounts.payable_per_chain_payments_page.as_deref_mut(),
+    )?,
+    ctx.accounts.payable_activity.as_mut(),
+  )?;
+
+  // Mint the payment's receipt to the payer if the payable mints receipts.
+  let receipt_accounts = ReceiptAccounts::of(
+    &ctx.accounts.payable_receipts,
+    ctx
+      .accounts
+      .receipt_mint
+      .as_ref()
+      .zip(ctx.bumps.receipt_mint)
+      .map(|(mint, bump)| (mint.to_account_info(), bump)),
+    Some((
+      ctx.accounts.chain_stats.to_account_info(),
+      ctx.bumps.chain_stats,
+    )),
+    ctx
+      .accounts
+      .payer_receipt_token_account
+      .as_ref()
+      .map(|account| account.to_account_info()),
+    ctx
+      .accounts
+      .receipt_token_program
+      .as_ref()
+      .map(|program| program.to_account_info()),
+    ctx
+      .accounts
+      .associated_token_program
+      .as_ref()
+      .map(|program| program.to_account_info()),
+  )?;
+  let minted_receipt = match receipt_accounts {
+    Some(receipt_accounts) => Some(mint_receipt(
+      receipt_accounts,
+      &ctx.accounts.user_payment,
+      ctx.accounts.signer.to_account_info(),
+      ctx.accounts.system_program.to_account_info(),
+    )?),
+    None => None,
+  };
+
+  /* EVENTS */
+  emit_cpi!(user_paid);
+  emit_cpi!(payable_received);
+  if let Some(minted_receipt) = minted_receipt {
+    emit_cpi!(minted_receipt);
+  }
+  Ok(())
+}
+
+/// Ensures that the instruction right before the current one is the Ed25519
+/// program's verification of the host's signature of the message, with the
+/// signature, public key, and message all in its own data.
+fn verify_intent_signature(
+  instructions: &AccountInfo,
+  host: &Pubkey,
+  message: &[u8],
+) -> Result<()> {
+  let current_index = load_current_index_checked(instructions)?;
+  require!(current_index > 0, ChainbillsError::InvalidIntentSignature);
+  let ix =
+    load_instruction_at_checked((current_index - 1) as usize, instructions)?;
+  require!(
+    ix.program_id == ed25519_program::ID,
+    ChainbillsError::InvalidIntentSignature
+  );
+
+  // num_signatures(1) | padding(1) | signature_offset(2)
+  // | signature_instruction_index(2) | public_key_offset(2)
+  // | public_key_instruction_index(2) | message_data_offset(2)
+  // | message_data_size(2) | message_instruction_index(2) | ...
+  let data = &ix.data;
+  require!(
+    data.len() >= 16 && data[0] == 1,
+    ChainbillsError::InvalidIntentSignature
+  );
+  let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+  let read_slice = |offset: u16, len: usize| {
+    data.get(offset as usize..(offset as usize).checked_add(len)?)
+  };
+  require!(
+    read_u16(4) == u16::MAX
+      && read_u16(8) == u16::MAX
+      && read_u16(14) == u16::MAX,
+    ChainbillsError::InvalidIntentSignature
+  );
+  require!(
+    read_slice(read_u16(6), 32) == Some(host.as_ref()),
+    ChainbillsError::InvalidIntentSignature
+  );
+  require!(
+    read_slice(read_u16(10), read_u16(12) as usize) == Some(message),
+    ChainbillsError::InvalidIntentSignature
+  );
+  Ok(())
+}
+
+/// Transfers the exact amount of tokens of an intent that the payable's host
+/// signed off-chain. The payable's allowed tokens and amounts don't apply.
+/// The transaction must verify the host's signature of the intent's signing
+/// bytes with an Ed25519 program instruction right before this one.
+///
+/// ### args
+/// * amount<u64>: The amount that the host signed.
+/// * payer_proof<Vec<[u8; 32]>>: The Merkle proof that the signer is an
+///   allowed payer. Empty if the payable doesn't restrict its payers.
+/// * reference<String>: The host's reference for the payment.
+/// * expiry<u64>: The timestamp after which the intent can't be paid.
+/// * nonce<u64>: The intent's nonce, which can only be paid once.
+#[inline(never)]
+pub fn pay_with_intent(
+  ctx: Context<PayWithIntent>,
+  amount: u64,
+  payer_proof: Vec<[u8; 32]>,
+  reference: String,
+  expiry: u64,
+  nonce: u64,
+) -> Result<()> {
+  /* CHECKS */
+  let mint = &ctx.accounts.mint;
+  let payable = ctx.accounts.payable.as_mut();
+  let token_details = &ctx.accounts.token_details;
+
+  // Ensure that the payer is allowed to pay if the payable restricted its
+  // payers.
+  require!(
+    PayableAllowedPayers::permits(
+      &ctx.accounts.allowed_payers,
+      ctx.accounts.signer.key,
+      &payer_proof
+    )?,
+    ChainbillsError::PayerNotAllowed
+  );
+  check_payable_can_receive(amount, mint.key(), payable, token_details)?;
+
+  // Ensure that the intent hasn't expired.
+  let now = clock::Clock::get()?.unix_timestamp as u64;
+  require!(now <= expiry, ChainbillsError::PaymentIntentExpired);
+
+  // Ensure that the payable's host signed the intent.
+  let signing_bytes = PaymentIntent {
+    payable_chain_id: solana_cb_chain_id(),
+    payable_id: payable.key().to_bytes(),
+    token: mint.key().to_bytes(),
+    amount: amount.into(),
+    expiry,
+    nonce,
+    reference: reference.as_bytes().to_vec(),
+  }
+  .signing_bytes()
+  .map_err(|_| ChainbillsError::IntentReferenceTooLong)?;
+  verify_intent_signature(
+    &ctx.accounts.instructions,
+    &payable.host,
+    &signing_bytes,
+  )?;
+
+  /* TRANSFER */
+  let balance_before = ctx.accounts.chain_token_account.amount;
+  token_interface::transfer_checked(
+    CpiContext::new(
+      ctx.accounts.token_program.to_account_info(),
+      TransferChecked {
+        from: ctx.accounts.payer_token_account.to_account_info(),
+        mint: mint.to_account_info(),
+        to: ctx.accounts.chain_token_account.to_account_info(),
+        authority: ctx.accounts.signer.to_account_info(),
+      },
+    ),
+    amount,
+    mint.decimals,
+  )?;
+
+  // Credit the payable with what actually arrived.
+  ctx.accounts.chain_token_account.reload()?;
+  let received = ctx
+    .accounts
+    .chain_token_account
+    .amount
+    .checked_sub(balance_before)
+    .unwrap();
+  require!(received > 0, ChainbillsError::NothingReceivedFromTransfer);
+
+  /* STATE CHANGES */
+  let cb_chain_id = solana_cb_chain_id();
+  let chain_shard = ctx.accounts.chain_shard.as_mut();
+
+  // Update State for User
+  let user_paid = update_state_for_user_payment(
     amount,
     mint.key(),
+    ctx.accounts.signer.key(),
+    ctx.accounts.signer.key(),
+    ctx.accounts.payer.as_mut(),
+    chain_shard,
+    payable.key().to_bytes(),
+    cb_chain_id,
+    ctx.accounts.token_details_shard.as_mut(),
+    ctx.accounts.user_payment.as_mut(),
+    ctx.accounts.user_activity.as_mut(),
+  )?;
+
+  // Credit the payable, or hold the payment in escrow.
+  credit_or_escrow_payment(
+    payable,
+    &ctx.accounts.payable_escrow,
+    ctx.accounts.escrow_state.as_deref_mut(),
+    ctx.accounts.signer.key(),
+    TokenAndAmount {
+      token: mint.key(),
+      amount: received,
+    },
+  )?;
+
+  // Update State for Payable
+  let payable_received = update_state_for_payable_payment(
+    received,
+    mint.key(),
+    ctx.accounts.signer.key(),
     payable,
     ctx.accounts.payable_per_chain_payments_counter.as_mut(),
-    chain_stats,
+    chain_shard,
     ctx.accounts.signer.key().to_bytes(),
-    chain_id,
-    token_details,
+    cb_chain_id,
+    ctx.accounts.token_details_shard.as_mut(),
     ctx.accounts.payable_payment.as_mut(),
-    ctx.accounts.chain_payable_payment_id.as_mut(),
-    ctx.accounts.payable_per_chain_payment_info.as_mut(),
+    LedgerEntry::of(
+      ctx.accounts.payable_per_chain_payment_info.as_deref_mut(),
+      ctx.accounts.payable_per_chain_payments_page.as_deref_mut(),
+    )?,
     ctx.accounts.payable_activity.as_mut(),
-    ctx.accounts.payable_activity_info.as_mut(),
-  )
+  )?;
+
+  // Mark the intent as paid, with its reference.
+  let consumed_payment_intent = ctx.accounts.consumed_payment_intent.as_mut();
+  consumed_payment_intent.payable_payment = ctx.accounts.payable_payment.key();
+  consumed_payment_intent.reference = reference.clone();
+
+  // Mint the payment's receipt to the payer if the payable mints receipts.
+  let receipt_accounts = ReceiptAccounts::of(
+    &ctx.accounts.payable_receipts,
+    ctx
+      .accounts
+      .receipt_mint
+      .as_ref()
+      .zip(ctx.bumps.receipt_mint)
+      .map(|(mint, bump)| (mint.to_account_info(), bump)),
+    Some((
+      ctx.accounts.chain_stats.to_account_info(),
+      ctx.bumps.chain_stats,
+    )),
+    ctx
+      .accounts
+      .payer_receipt_token_account
+      .as_ref()
+      .map(|account| account.to_account_info()),
+    ctx
+      .accounts
+      .receipt_token_program
+      .as_ref()
+      .map(|program| program.to_account_info()),
+    ctx
+      .accounts
+      .associated_token_program
+      .as_ref()
+      .map(|program| program.to_account_info()),
+  )?;
+  let minted_receipt = match receipt_accounts {
+    Some(receipt_accounts) => Some(mint_receipt(
+      receipt_accounts,
+      &ctx.accounts.user_payment,
+      ctx.accounts.signer.to_account_info(),
+      ctx.accounts.system_program.to_account_info(),
+    )?),
+    None => None,
+  };
+
+  /* EVENTS */
+  msg!("Paid intent with nonce: {}.", nonce);
+  emit_cpi!(user_paid);
+  emit_cpi!(payable_received);
+  emit_cpi!(PaidWithIntent {
+    payable_id: payable.key(),
+    payable_payment_id: ctx.accounts.payable_payment.key(),
+    payer_wallet: ctx.accounts.signer.key(),
+    nonce,
+    reference,
+  });
+  if let Some(minted_receipt) = minted_receipt {
+    emit_cpi!(minted_receipt);
+  }
+  Ok(())
 }
 
 /// Transfers the amount of native tokens (Solana) to a payable
 ///
 /// ### args
 /// * amount<u64>: The Wormhole-normalized amount to be paid
+/// * payer_proof<Vec<[u8; 32]>>: The Merkle proof that the signer is an
+///   allowed payer. Empty if the payable doesn't restrict its payers.
 #[inline(never)]
-pub fn pay_native(ctx: Context<PayNative>, amount: u64) -> Result<()> {
+pub fn pay_native(
+  ctx: Context<PayNative>,
+  amount: u64,
+  payer_proof: Vec<[u8; 32]>,
+) -> Result<()> {
   /* CHECKS */
   let payable = ctx.accounts.payable.as_mut();
-  let token_details = ctx.accounts.token_details.as_mut();
-  check_pay_inputs(amount, crate::ID, payable, token_details)?;
+  let token_details = &ctx.accounts.token_details;
+  // Ensure that the payer is allowed to pay if the payable restricted its
+  // payers.
+  require!(
+    PayableAllowedPayers::permits(
+      &ctx.accounts.allowed_payers,
+      ctx.accounts.signer.key,
+      &payer_proof
+    )?,
+    ChainbillsError::PayerNotAllowed
+  );
+  check_payment(
+    amount,
+    crate::ID,
+    native_mint::DECIMALS,
+    payable,
+    token_details,
+    ctx.accounts.token_group.as_deref(),
+  )?;
 
   /* TRANSFER */
   system_program::transfer(
@@ -302,46 +768,565 @@ pub fn pay_native(ctx: Context<PayNative>, amount: u64) -> Result<()> {
       ctx.accounts.system_program.to_account_info(),
       Transfer {
         from: ctx.accounts.signer.to_account_info(),
-        to: ctx.accounts.chain_stats.to_account_info(),
+        to: ctx.accounts.native_vault.to_account_info(),
       },
     ),
     amount,
   )?;
 
   /* STATE CHANGES */
-  let chain_id = ctx.accounts.config.load()?.chain_id;
-  let chain_stats = ctx.accounts.chain_stats.as_mut();
+  let cb_chain_id = solana_cb_chain_id();
+  let chain_shard = ctx.accounts.chain_shard.as_mut();
 
   // Update State for User
-  update_state_for_user_payment(
+  let user_paid = update_state_for_user_payment(
     amount,
     crate::ID,
     ctx.accounts.signer.key(),
+    ctx.accounts.signer.key(),
     ctx.accounts.payer.as_mut(),
-    chain_stats,
+    chain_shard,
     payable.key().to_bytes(),
-    chain_id,
-    token_details,
+    cb_chain_id,
+    ctx.accounts.token_details_shard.as_mut(),
     ctx.accounts.user_payment.as_mut(),
-    ctx.accounts.chain_user_payment_id.as_mut(),
     ctx.accounts.user_activity.as_mut(),
-    ctx.accounts.user_activity_info.as_mut(),
+  )?;
+
+  // Credit the payable, or hold the payment in escrow.
+  credit_or_escrow_payment(
+    payable,
+    &ctx.accounts.payable_escrow,
+    ctx.accounts.escrow_state.as_deref_mut(),
+    ctx.accounts.signer.key(),
+    TokenAndAmount {
+      token: crate::ID,
+      amount,
+    },
   )?;
 
   // Update State for Payable
-  update_state_for_payable_payment(
+  let payable_received = update_state_for_payable_payment(
     amount,
     crate::ID,
+    ctx.accounts.signer.key(),
     payable,
     ctx.accounts.payable_per_chain_payments_counter.as_mut(),
-    chain_stats,
+    chain_shard,
     ctx.accounts.signer.key().to_bytes(),
-    chain_id,
-    token_details,
+    cb_chain_id,
+    ctx.accounts.token_details_shard.as_mut(),
     ctx.accounts.payable_payment.as_mut(),
-    ctx.accounts.chain_payable_payment_id.as_mut(),
-    ctx.accounts.payable_per_chain_payment_info.as_mut(),
+    LedgerEntry::of(
+      ctx.accounts.payable_per_chain_payment_info.as_deref_mut(),
+      ctx.accounts.payable_per_chain_payments_page.as_deref_mut(),
+    )?,
     ctx.accounts.payable_activity.as_mut(),
-    ctx.accounts.payable_activity_info.as_mut(),
-  )
+  )?;
+
+  // Mint the payment's receipt to the payer if the payable mints receipts.
+  let receipt_accounts = ReceiptAccounts::of(
+    &ctx.accounts.payable_receipts,
+    ctx
+      .accounts
+      .receipt_mint
+      .as_ref()
+      .zip(ctx.bumps.receipt_mint)
+      .map(|(mint, bump)| (mint.to_account_info(), bump)),
+    ctx
+      .accounts
+      .chain_stats
+      .as_ref()
+      .zip(ctx.bumps.chain_stats)
+      .map(|(chain_stats, bump)| (chain_stats.to_account_info(), bump)),
+    ctx
+      .accounts
+      .payer_receipt_token_account
+      .as_ref()
+      .map(|account| account.to_account_info()),
+    ctx
+      .accounts
+      .receipt_token_program
+      .as_ref()
+      .map(|program| program.to_account_info()),
+    ctx
+      .accounts
+      .associated_token_program
+      .as_ref()
+      .map(|program| program.to_account_info()),
+  )?;
+  let minted_receipt = match receipt_accounts {
+    Some(receipt_accounts) => Some(mint_receipt(
+      receipt_accounts,
+      &ctx.accounts.user_payment,
+      ctx.accounts.signer.to_account_info(),
+      ctx.accounts.system_program.to_account_info(),
+    )?),
+    None => None,
+  };
+
+  /* EVENTS */
+  emit_cpi!(user_paid);
+  emit_cpi!(payable_received);
+  if let Some(minted_receipt) = minted_receipt {
+    emit_cpi!(minted_receipt);
+  }
+  Ok(())
+}
+
+/// Returns the next of the remaining accounts of a batch.
+fn next_batch_account<'info>(
+  accounts: &mut std::slice::Iter<'info, AccountInfo<'info>>,
+) -> Result<&'info AccountInfo<'info>> {
+  accounts
+    .next()
+    .ok_or(error!(ChainbillsError::InvalidBatchAccounts))
+}
+
+/// Ensures that the account of a batch is at the address derived from the
+/// seeds, and returns the address' bump.
+fn check_batch_address(account: &AccountInfo, seeds: &[&[u8]]) -> Result<u8> {
+  let (address, bump) = Pubkey::find_program_address(seeds, &crate::ID);
+  require_keys_eq!(
+    account.key(),
+    address,
+    ChainbillsError::InvalidBatchAccounts
+  );
+  Ok(bump)
+}
+
+/// Creates a program account of a batch at the address derived from the
+/// seeds, as the `init` constraint does. The signer pays for its rent.
+fn init_batch_account<'info, T>(
+  account: &'info AccountInfo<'info>,
+  seeds: &[&[u8]],
+  space: usize,
+  signer: &AccountInfo<'info>,
+  system_program: &AccountInfo<'info>,
+) -> Result<Box<Account<'info, T>>>
+where
+  T: AccountSerialize
+    + AccountDeserialize
+    + anchor_lang::Discriminator
+    + Owner
+    + Clone,
+{
+  let bump = [check_batch_address(account, seeds)?];
+  let signer_seeds = [seeds, &[&bump[..]]].concat();
+  system_program::create_account(
+    CpiContext::new_with_signer(
+      system_program.clone(),
+      CreateAccount {
+        from: signer.clone(),
+        to: account.clone(),
+      },
+      &[&signer_seeds],
+    ),
+    Rent::get()?.minimum_balance(space),
+    space as u64,
+    &crate::ID,
+  )?;
+  account.try_borrow_mut_data()?[..8].copy_from_slice(&T::DISCRIMINATOR);
+  Ok(Box::new(Account::try_from(account)?))
+}
+
+/// An index entry of a batch payment: its own account, or its ledger page.
+struct BatchEntry<'info, A, P>
+where
+  A: AccountSerialize + AccountDeserialize + Clone,
+  P: AccountSerialize + AccountDeserialize + Clone,
+{
+  account: Option<Box<Account<'info, A>>>,
+  page: Option<Box<Account<'info, P>>>,
+}
+
+impl<'info, A, P> BatchEntry<'info, A, P>
+where
+  A: AccountSerialize + AccountDeserialize + Owner + Clone,
+  P: AccountSerialize + AccountDeserialize + Owner + Clone,
+{
+  fn ledger(
+    &mut self,
+  ) -> Result<LedgerEntry<'_, Account<'info, A>, Account<'info, P>>> {
+    LedgerEntry::of(self.account.as_deref_mut(), self.page.as_deref_mut())
+  }
+
+  fn exit(&self) -> Result<()> {
+    if let Some(account) = &self.account {
+      account.exit(&crate::ID)?;
+    }
+    if let Some(page) = &self.page {
+      page.exit(&crate::ID)?;
+    }
+    Ok(())
+  }
+}
+
+/// Creates the account of an index entry of a batch or, if the payment is
+/// paged, loads the entry's ledger page, creating it first if it doesn't
+/// exist yet as the `init_if_needed` constraint does. The entry and its page
+/// each come with their seeds and space.
+fn init_batch_entry<'info, A, P>(
+  account: &'info AccountInfo<'info>,
+  paged: bool,
+  entry: (&[&[u8]], usize),
+  page: (&[&[u8]], usize),
+  signer: &AccountInfo<'info>,
+  system_program: &AccountInfo<'info>,
+) -> Result<BatchEntry<'info, A, P>>
+where
+  A: AccountSerialize
+    + AccountDeserialize
+    + anchor_lang::Discriminator
+    + Owner
+    + Clone,
+  P: AccountSerialize
+    + AccountDeserialize
+    + anchor_lang::Discriminator
+    + Owner
+    + Clone,
+{
+  if !paged {
+    let (seeds, space) = entry;
+    return Ok(BatchEntry {
+      account: Some(init_batch_account(
+        account,
+        seeds,
+        space,
+        signer,
+        system_program,
+      )?),
+      page: None,
+    });
+  }
+
+  let (seeds, space) = page;
+  let page = if account.owner == &crate::ID {
+    check_batch_address(account, seeds)?;
+    Box::new(Account::try_from(account)?)
+  } else {
+    init_batch_account(account, seeds, space, signer, system_program)?
+  };
+  Ok(BatchEntry {
+    account: None,
+    page: Some(page),
+  })
+}
+
+/// Grows a payable of a batch to fit a new balance of the token, as the
+/// `realloc` constraint does. The signer pays for the extra rent.
+fn realloc_batch_payable<'info>(
+  payable: &Account<'info, Payable>,
+  mint: Pubkey,
+  signer: &AccountInfo<'info>,
+  system_program: &AccountInfo<'info>,
+) -> Result<()> {
+  let info = payable.to_account_info();
+  let space = payable.space_update_balance(mint);
+  if space > info.data_len() {
+    let rent = Rent::get()?
+      .minimum_balance(space)
+      .saturating_sub(info.lamports());
+    if rent > 0 {
+      system_program::transfer(
+        CpiContext::new(
+          system_program.clone(),
+          Transfer {
+            from: signer.clone(),
+            to: info.clone(),
+          },
+        ),
+        rent,
+      )?;
+    }
+    info.realloc(space, false)?;
+  }
+  Ok(())
+}
+
+/// Transfers tokens of one mint to many payables at once. The whole batch
+/// fails if any of its payments is invalid.
+///
+/// ### args
+/// * payments<Vec<BatchPayment>>: The amounts and payer proofs of the
+///   payments, in the order of their accounts in the remaining accounts.
+#[inline(never)]
+pub fn pay_batch<'info>(
+  ctx: Context<'_, '_, 'info, 'info, PayBatch<'info>>,
+  payments: Vec<BatchPayment>,
+) -> Result<()> {
+  require!(!payments.is_empty(), ChainbillsError::EmptyPaymentBatch);
+  let mint = ctx.accounts.mint.key();
+  let signer = ctx.accounts.signer.to_account_info();
+  let system_program = ctx.accounts.system_program.to_account_info();
+  let chain_id = ctx.accounts.config.load()?.chain_id.to_le_bytes();
+  let cb_chain_id = solana_cb_chain_id();
+  let mut accounts = ctx.remaining_accounts.iter();
+
+  for payment in payments.iter() {
+    /* CHECKS */
+    // Load the payable and the accounts it is checked against.
+    let payable_info = next_batch_account(&mut accounts)?;
+    let mut payable = Box::new(
+      Account::<'info, Payable>::try_from(payable_info)
+        .map_err(|_| ChainbillsError::NonPayableAccountProvided)?,
+    );
+    let payable_id = payable.key();
+    let counter_info = next_batch_account(&mut accounts)?;
+    check_batch_address(counter_info, &[payable_id.as_ref(), &chain_id])?;
+    let mut counter = Box::new(
+      Account::<'info, PayablePerChainPaymentsCounter>::try_from(counter_info)?,
+    );
+    let allowed_payers = next_batch_account(&mut accounts)?;
+    check_batch_address(
+      allowed_payers,
+      &[payable_id.as_ref(), PayableAllowedPayers::SEED_PREFIX],
+    )?;
+    let payable_escrow = next_batch_account(&mut accounts)?;
+    check_batch_address(
+      payable_escrow,
+      &[payable_id.as_ref(), PayableEscrow::SEED_PREFIX],
+    )?;
+    let payable_receipts = next_batch_account(&mut accounts)?;
+    check_batch_address(
+      payable_receipts,
+      &[payable_id.as_ref(), PayableReceipts::SEED_PREFIX],
+    )?;
+
+    require!(
+      PayableAllowedPayers::permits(
+        allowed_payers,
+        signer.key,
+        &payment.payer_proof
+      )?,
+      ChainbillsError::PayerNotAllowed
+    );
+    check_payment(
+      payment.amount,
+      mint,
+      ctx.accounts.mint.decimals,
+      &payable,
+      &ctx.accounts.token_details,
+      None,
+    )?;
+
+    /* TRANSFER */
+    let balance_before = ctx.accounts.chain_token_account.amount;
+    token_interface::transfer_checked(
+      CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        TransferChecked {
+          from: ctx.accounts.payer_token_account.to_account_info(),
+          mint: ctx.accounts.mint.to_account_info(),
+          to: ctx.accounts.chain_token_account.to_account_info(),
+          authority: signer.clone(),
+        },
+      ),
+      payment.amount,
+      ctx.accounts.mint.decimals,
+    )?;
+
+    // Credit the payable with what actually arrived, as in pay.
+    ctx.accounts.chain_token_account.reload()?;
+    let received = ctx
+      .accounts
+      .chain_token_account
+      .amount
+      .checked_sub(balance_before)
+      .unwrap();
+    require!(received > 0, ChainbillsError::NothingReceivedFromTransfer);
+
+    /* ACCOUNTS CREATION */
+    realloc_batch_payable(&payable, mint, &signer, &system_program)?;
+    let payer = &ctx.accounts.payer;
+    let mut user_payment = init_batch_account::<UserPayment>(
+      next_batch_account(&mut accounts)?,
+      &[
+        signer.key.as_ref(),
+        UserPayment::SEED_PREFIX,
+        &payer.next_payment().to_le_bytes(),
+      ],
+      UserPayment::SPACE,
+      &signer,
+      &system_program,
+    )?;
+    let mut payable_payment = init_batch_account::<PayablePayment>(
+      next_batch_account(&mut accounts)?,
+      &[
+        payable_id.as_ref(),
+        PayablePayment::SEED_PREFIX,
+        &payable.next_payment().to_le_bytes(),
+      ],
+      PayablePayment::SPACE,
+      &signer,
+      &system_program,
+    )?;
+    let next = counter.next_payment();
+    let mut payable_per_chain_payment_info =
+      init_batch_entry::<PayablePerChainPaymentInfo, CountLedgerPage>(
+        next_batch_account(&mut accounts)?,
+        payment.paged,
+        (
+          &[payable_id.as_ref(), &chain_id, &next.to_le_bytes()],
+          PayablePerChainPaymentInfo::SPACE,
+        ),
+        (
+          &[
+            payable_id.as_ref(),
+            &chain_id,
+            SEED_PREFIX_LEDGER_PAGE,
+            &ledger_page(next).to_le_bytes(),
+          ],
+          CountLedgerPage::SPACE,
+        ),
+        &signer,
+        &system_program,
+      )?;
+    let mut user_activity = init_batch_account::<ActivityRecord>(
+      next_batch_account(&mut accounts)?,
+      &[
+        ActivityRecord::SEED_PREFIX,
+        signer.key.as_ref(),
+        &payer.next_activity().to_le_bytes(),
+      ],
+      ActivityRecord::SPACE,
+      &signer,
+      &system_program,
+    )?;
+    let mut payable_activity = init_batch_account::<ActivityRecord>(
+      next_batch_account(&mut accounts)?,
+      &[
+        ActivityRecord::SEED_PREFIX,
+        payable_id.as_ref(),
+        &payable.next_activity().to_le_bytes(),
+      ],
+      ActivityRecord::SPACE,
+      &signer,
+      &system_program,
+    )?;
+    let mut escrow_state = if PayableEscrow::active(payable_escrow)?.is_some() {
+      Some(init_batch_account::<EscrowState>(
+        next_batch_account(&mut accounts)?,
+        &[payable_payment.key().as_ref(), EscrowState::SEED_PREFIX],
+        EscrowState::SPACE,
+        &signer,
+        &system_program,
+      )?)
+    } else {
+      None
+    };
+    let receipt_accounts = if PayableReceipts::is_enabled(payable_receipts)? {
+      let receipt_mint = next_batch_account(&mut accounts)?;
+      let mint_bump = check_batch_address(
+        receipt_mint,
+        &[user_payment.key().as_ref(), Receipt::MINT_SEED_PREFIX],
+      )?;
+      ReceiptAccounts::of(
+        payable_receipts,
+        Some((receipt_mint.clone(), mint_bump)),
+        Some((
+          ctx.accounts.chain_stats.to_account_info(),
+          ctx.bumps.chain_stats,
+        )),
+        Some(next_batch_account(&mut accounts)?.clone()),
+        ctx
+          .accounts
+          .receipt_token_program
+          .as_ref()
+          .map(|program| program.to_account_info()),
+        ctx
+          .accounts
+          .associated_token_program
+          .as_ref()
+          .map(|program| program.to_account_info()),
+      )?
+    } else {
+      None
+    };
+
+    /* STATE CHANGES */
+    let chain_shard = ctx.accounts.chain_shard.as_mut();
+    let token_details_shard = ctx.accounts.token_details_shard.as_mut();
+
+    // Update State for User
+    let user_paid = update_state_for_user_payment(
+      payment.amount,
+      mint,
+      signer.key(),
+      signer.key(),
+      ctx.accounts.payer.as_mut(),
+      chain_shard,
+      payable_id.to_bytes(),
+      cb_chain_id,
+      token_details_shard,
+      user_payment.as_mut(),
+      user_activity.as_mut(),
+    )?;
+
+    // Credit the payable, or hold the payment in escrow.
+    credit_or_escrow_payment(
+      payable.as_mut(),
+      payable_escrow,
+      escrow_state.as_deref_mut(),
+      signer.key(),
+      TokenAndAmount {
+        token: mint,
+        amount: received,
+      },
+    )?;
+
+    // Update State for Payable
+    let payable_received = update_state_for_payable_payment(
+      received,
+      mint,
+      signer.key(),
+      payable.as_mut(),
+      counter.as_mut(),
+      chain_shard,
+      signer.key().to_bytes(),
+      cb_chain_id,
+      token_details_shard,
+      payable_payment.as_mut(),
+      payable_per_chain_payment_info.ledger()?,
+      payable_activity.as_mut(),
+    )?;
+
+    // Persist this payment's accounts, so that later payments into the same
+    // payable build on them.
+    payable.exit(&crate::ID)?;
+    counter.exit(&crate::ID)?;
+    user_payment.exit(&crate::ID)?;
+    payable_payment.exit(&crate::ID)?;
+    payable_per_chain_payment_info.exit()?;
+    user_activity.exit(&crate::ID)?;
+    payable_activity.exit(&crate::ID)?;
+    if let Some(escrow_state) = escrow_state {
+      escrow_state.exit(&crate::ID)?;
+    }
+
+    // Mint the payment's receipt to the payer if the payable mints receipts.
+    let minted_receipt = match receipt_accounts {
+      Some(receipt_accounts) => Some(mint_receipt(
+        receipt_accounts,
+        &user_payment,
+        signer.clone(),
+        system_program.clone(),
+      )?),
+      None => None,
+    };
+
+    /* EVENTS */
+    emit_cpi!(user_paid);
+    emit_cpi!(payable_received);
+    if let Some(minted_receipt) = minted_receipt {
+      emit_cpi!(minted_receipt);
+    }
+  }
+
+  // Ensure that every remaining account was used.
+  require!(
+    accounts.next().is_none(),
+    ChainbillsError::InvalidBatchAccounts
+  );
+  Ok(())
 }
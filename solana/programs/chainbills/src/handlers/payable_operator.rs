@@ -0,0 +1,52 @@
+use crate::{context::*, error::ChainbillsError, events::*, state::*};
+use anchor_lang::prelude::*;
+
+/// Adds an operator to a payable or changes the operator's roles. Can be
+/// called only by the host of the payable.
+///
+/// ### args
+/// * operator<Pubkey>: The operator's wallet.
+/// * roles<u8>: Bit flags of [PayableOperator] roles to grant.
+#[inline(never)]
+pub fn set_payable_operator(
+  ctx: Context<SetPayableOperator>,
+  operator: Pubkey,
+  roles: u8,
+) -> Result<()> {
+  /* CHECKS */
+  require!(
+    PayableOperator::are_valid_roles(roles),
+    ChainbillsError::InvalidOperatorRoles
+  );
+
+  /* STATE CHANGES */
+  let payable_operator = ctx.accounts.payable_operator.as_mut();
+  payable_operator.payable_id = ctx.accounts.payable.key();
+  payable_operator.operator = operator;
+  payable_operator.host = ctx.accounts.signer.key();
+  payable_operator.roles = roles;
+
+  /* EVENTS */
+  msg!("Set Payable Operator.");
+  emit!(UpdatedPayableOperator {
+    payable_id: ctx.accounts.payable.key(),
+    operator,
+    roles,
+  });
+  Ok(())
+}
+
+/// Removes an operator from a payable. Can be called only by the host of the
+/// payable.
+#[inline(never)]
+pub fn remove_payable_operator(
+  ctx: Context<RemovePayableOperator>,
+) -> Result<()> {
+  msg!("Removed Payable Operator.");
+  emit!(UpdatedPayableOperator {
+    payable_id: ctx.accounts.payable.key(),
+    operator: ctx.accounts.payable_operator.operator,
+    roles: 0,
+  });
+  Ok(())
+}
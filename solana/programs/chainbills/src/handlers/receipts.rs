@@ -0,0 +1,304 @@
+use crate::{context::*, error::ChainbillsError, events::*, state::*};
+use anchor_lang::{
+  prelude::*,
+  solana_program::clock,
+  system_program::{self, CreateAccount},
+};
+use anchor_spl::{
+  associated_token::{self, Create},
+  token_2022,
+  token_interface::{
+    self,
+    spl_pod::optional_keys::OptionalNonZeroPubkey,
+    spl_token_2022::{
+      extension::ExtensionType, instruction::AuthorityType,
+      state::Mint as MintState,
+    },
+    spl_token_metadata_interface::state::{Field, TokenMetadata},
+    InitializeMint2, MetadataPointerInitialize, MintTo, SetAuthority,
+    TokenMetadataInitialize, TokenMetadataUpdateAuthority,
+    TokenMetadataUpdateField,
+  },
+};
+
+/// Allows a payable's host (or its operators with the manage role) to have
+/// receipt NFTs minted to the payers of payments into the payable.
+///
+/// ### args
+/// * enabled<bool>: Whether payments mint receipts.
+#[inline(never)]
+pub fn update_payable_receipts(
+  ctx: Context<UpdatePayableReceipts>,
+  enabled: bool,
+) -> Result<()> {
+  /* STATE CHANGES */
+  // Update the payable's receipt settings.
+  ctx.accounts.payable_receipts.enabled = enabled;
+
+  // Increment the activities counts.
+  let chain_shard = ctx.accounts.chain_shard.as_mut();
+  let host = ctx.accounts.host.as_mut();
+  let payable = ctx.accounts.payable.as_mut();
+  chain_shard.activities_count = chain_shard.next_activity();
+  host.activities_count = host.next_activity();
+  payable.activities_count = payable.next_activity();
+
+  // Initialize the activity.
+  let activity = ctx.accounts.activity.as_mut();
+  activity.chain_count = chain_shard.activities_count;
+  activity.user_count = host.activities_count;
+  activity.payable_count = payable.activities_count;
+  activity.timestamp = clock::Clock::get()?.unix_timestamp as u64;
+  activity.entity = payable.key();
+  activity.activity_type = ActivityType::UpdatedPayableReceipts;
+  activity.rent_payer = ctx.accounts.signer.key();
+
+  // Initialize the user and payable activity infos.
+  ctx.accounts.user_activity_info.chain_count = chain_shard.activities_count;
+  ctx.accounts.payable_activity_info.chain_count = chain_shard.activities_count;
+
+  /* EVENTS */
+  msg!("Updated Payable's receipts.");
+  emit!(UpdatedPayableReceipts {
+    payable_id: payable.key(),
+    host_wallet: payable.host,
+    enabled,
+  });
+  Ok(())
+}
+
+/// The accounts with which a payment mints its receipt to the signer, who
+/// made the payment.
+pub(crate) struct ReceiptAccounts<'info> {
+  pub receipt_mint: AccountInfo<'info>,
+  pub mint_bump: u8,
+  pub chain_stats: AccountInfo<'info>,
+  pub chain_stats_bump: u8,
+  pub payer_receipt_token_account: AccountInfo<'info>,
+  pub token_program: AccountInfo<'info>,
+  pub associated_token_program: AccountInfo<'info>,
+}
+
+impl<'info> ReceiptAccounts<'info> {
+  /// The accounts that mint the receipt of a payment into the payable whose
+  /// (possibly uninitialized) receipts account is given. None if the payable
+  /// doesn't mint receipts, and all of them are required if it does.
+  pub(crate) fn of(
+    payable_receipts: &AccountInfo,
+    receipt_mint: Option<(AccountInfo<'info>, u8)>,
+    chain_stats: Option<(AccountInfo<'info>, u8)>,
+    payer_receipt_token_account: Option<AccountInfo<'info>>,
+    token_program: Option<AccountInfo<'info>>,
+    associated_token_program: Option<AccountInfo<'info>>,
+  ) -> Result<Option<Self>> {
+    if !PayableReceipts::is_enabled(payable_receipts)? {
+      return Ok(None);
+    }
+    match (
+      receipt_mint,
+      chain_stats,
+      payer_receipt_token_account,
+      token_program,
+      associated_token_program,
+    ) {
+      (
+        Some((receipt_mint, mint_bump)),
+        Some((chain_stats, chain_stats_bump)),
+        Some(payer_receipt_token_account),
+        Some(token_program),
+        Some(associated_token_program),
+      ) => Ok(Some(Self {
+        receipt_mint,
+        mint_bump,
+        chain_stats,
+        chain_stats_bump,
+        payer_receipt_token_account,
+        token_program,
+        associated_token_program,
+      })),
+      _ => err!(ChainbillsError::InvalidReceiptAccounts),
+    }
+  }
+}
+
+/// Mints the receipt NFT of a payment to its payer, who is the signer. The
+/// receipt is a Token-2022 mint with no decimals whose metadata pointer
+/// points at itself, where the payment's metadata is stored. The mint's and
+/// the metadata's authorities are removed afterwards, so that its supply stays
+/// at one and its metadata never changes.
+pub(crate) fn mint_receipt<'info>(
+  accounts: ReceiptAccounts<'info>,
+  user_payment: &Account<'info, UserPayment>,
+  signer: AccountInfo<'info>,
+  system_program: AccountInfo<'info>,
+) -> Result<MintedReceipt> {
+  /* CHECKS */
+  require_keys_eq!(
+    accounts.token_program.key(),
+    token_2022::ID,
+    ChainbillsError::InvalidReceiptAccounts
+  );
+  require_keys_eq!(
+    accounts.associated_token_program.key(),
+    associated_token::ID,
+    ChainbillsError::InvalidReceiptAccounts
+  );
+
+  /* ACCOUNTS CREATION */
+  let user_payment_id = user_payment.key();
+  let receipt_mint = accounts.receipt_mint;
+  let chain_stats = accounts.chain_stats;
+  let token_program = accounts.token_program;
+  let signer_seeds: &[&[&[u8]]] = &[
+    &[
+      user_payment_id.as_ref(),
+      Receipt::MINT_SEED_PREFIX,
+      &[accounts.mint_bump],
+    ],
+    &[ChainStats::SEED_PREFIX, &[accounts.chain_stats_bump]],
+  ];
+
+  // Create the mint with its metadata pointer, funded for the metadata that
+  // Token-2022 appends to it.
+  let metadata = TokenMetadata {
+    update_authority: OptionalNonZeroPubkey(chain_stats.key()),
+    mint: receipt_mint.key(),
+    name: Receipt::NAME.to_string(),
+    symbol: Receipt::SYMBOL.to_string(),
+    uri: String::new(),
+    additional_metadata: Receipt::fields(user_payment_id, user_payment),
+  };
+  let space = ExtensionType::try_calculate_account_len::<MintState>(&[
+    ExtensionType::MetadataPointer,
+  ])?;
+  system_program::create_account(
+    CpiContext::new_with_signer(
+      system_program.clone(),
+      CreateAccount {
+        from: signer.clone(),
+        to: receipt_mint.clone(),
+      },
+      signer_seeds,
+    ),
+    Rent::get()?.minimum_balance(space + metadata.tlv_size_of()?),
+    space as u64,
+    &token_2022::ID,
+  )?;
+  token_interface::metadata_pointer_initialize(
+    CpiContext::new(
+      token_program.clone(),
+      MetadataPointerInitialize {
+        token_program_id: token_program.clone(),
+        mint: receipt_mint.clone(),
+      },
+    ),
+    None,
+    Some(receipt_mint.key()),
+  )?;
+  token_interface::initialize_mint2(
+    CpiContext::new(
+      token_program.clone(),
+      InitializeMint2 {
+        mint: receipt_mint.clone(),
+      },
+    ),
+    0,
+    &chain_stats.key(),
+    None,
+  )?;
+
+  // Store the payment's metadata on the mint and fix it.
+  token_interface::token_metadata_initialize(
+    CpiContext::new_with_signer(
+      token_program.clone(),
+      TokenMetadataInitialize {
+        token_program_id: token_program.clone(),
+        metadata: receipt_mint.clone(),
+        update_authority: chain_stats.clone(),
+        mint_authority: chain_stats.clone(),
+        mint: receipt_mint.clone(),
+      },
+      signer_seeds,
+    ),
+    metadata.name,
+    metadata.symbol,
+    metadata.uri,
+  )?;
+  for (key, value) in metadata.additional_metadata {
+    token_interface::token_metadata_update_field(
+      CpiContext::new_with_signer(
+        token_program.clone(),
+        TokenMetadataUpdateField {
+          token_program_id: token_program.clone(),
+          metadata: receipt_mint.clone(),
+          update_authority: chain_stats.clone(),
+        },
+        signer_seeds,
+      ),
+      Field::Key(key),
+      value,
+    )?;
+  }
+  token_interface::token_metadata_update_authority(
+    CpiContext::new_with_signer(
+      token_program.clone(),
+      TokenMetadataUpdateAuthority {
+        token_program_id: token_program.clone(),
+        metadata: receipt_mint.clone(),
+        current_authority: chain_stats.clone(),
+        new_authority: chain_stats.clone(),
+      },
+      signer_seeds,
+    ),
+    OptionalNonZeroPubkey::default(),
+  )?;
+
+  // Create the payer's token account of the receipt.
+  associated_token::create(CpiContext::new(
+    accounts.associated_token_program,
+    Create {
+      payer: signer.clone(),
+      associated_token: accounts.payer_receipt_token_account.clone(),
+      authority: signer.clone(),
+      mint: receipt_mint.clone(),
+      system_program,
+      token_program: token_program.clone(),
+    },
+  ))?;
+
+  /* TRANSFERS */
+  // Mint the receipt to the payer and fix its supply.
+  token_interface::mint_to(
+    CpiContext::new_with_signer(
+      token_program.clone(),
+      MintTo {
+        mint: receipt_mint.clone(),
+        to: accounts.payer_receipt_token_account,
+        authority: chain_stats.clone(),
+      },
+      signer_seeds,
+    ),
+    1,
+  )?;
+  token_interface::set_authority(
+    CpiContext::new_with_signer(
+      token_program,
+      SetAuthority {
+        current_authority: chain_stats,
+        account_or_mint: receipt_mint.clone(),
+      },
+      signer_seeds,
+    ),
+    AuthorityType::MintTokens,
+    None,
+  )?;
+
+  // Log and return the event for the caller to emit through CPI.
+  msg!("Minted Receipt.");
+  Ok(MintedReceipt {
+    payable_id: user_payment.payable_id,
+    user_payment_id,
+    payer_wallet: user_payment.payer,
+    receipt_mint: receipt_mint.key(),
+  })
+}
@@ -1,7 +1,7 @@
 use crate::{context::*, error::*, events::*};
 use anchor_lang::prelude::*;
 
-/// This instruction records a foreign payable update in the chain. 
+/// This instruction records a foreign payable update in the chain.
 #[inline(never)]
 pub fn record_foreign_payable_update_handler(
   ctx: Context<RecordForeignPayableUpdate>,
@@ -13,6 +13,13 @@ pub fn record_foreign_payable_update_handler(
   let posted_vaa = &ctx.accounts.posted_vaa;
   let payload = &posted_vaa.payload.1;
 
+  // Ensure the payload spans the whole message. It is decoded as a stream
+  // while deserializing the PostedVaa, so trailing bytes aren't caught there.
+  require!(
+    payload.encoded_len()? == posted_vaa.payload_size() as usize,
+    ChainbillsError::InvalidPayloadLength
+  );
+
   // Ensure the Payable ID is what is in the Payload.
   require!(
     payable_id == payload.payable_id,
@@ -29,12 +36,15 @@ pub fn record_foreign_payable_update_handler(
   /* STATE CHANGES */
   // Record the foreign payable update.
   let foreign_payable = &mut ctx.accounts.foreign_payable;
-  foreign_payable.chain_id = posted_vaa.emitter_chain();
+  foreign_payable.chain_id = ctx.accounts.emitter_cb_chain_id.cb_chain_id;
   if payload.action_type == 1 || payload.action_type == 4 {
     foreign_payable.allowed_tokens_and_amounts =
       payload.allowed_tokens_and_amounts.clone();
   } else if payload.action_type == 2 || payload.action_type == 3 {
     foreign_payable.is_closed = payload.is_closed;
+  } else if payload.action_type == 5 {
+    // Allowed payers are enforced on the chain the payer pays from. Solana
+    // doesn't pay foreign payables, so the root is consumed but not stored.
   } else {
     return Err(ChainbillsError::InvalidPayloadActionType.into());
   }
@@ -0,0 +1,92 @@
+use crate::{context::*, error::*, events::*, state::*};
+use anchor_lang::prelude::*;
+
+/// Registers a blockchain network by its CAIP-2 string. The network's
+/// cbChainId is keccak256 of the string. Owner-only.
+///
+/// ### args
+/// * caip2<String>: The CAIP-2 chain identifier (`namespace:reference`).
+#[inline(never)]
+pub fn register_cb_chain(
+  ctx: Context<RegisterCbChain>,
+  caip2: String,
+) -> Result<()> {
+  require!(
+    CbChain::is_valid_caip2(&caip2),
+    ChainbillsError::InvalidCaip2
+  );
+
+  let cb_chain = &mut ctx.accounts.cb_chain;
+  cb_chain.cb_chain_id = cb_chain_id(&caip2);
+  cb_chain.caip2 = caip2.clone();
+
+  msg!("Registered CbChain: {}.", caip2);
+  emit!(RegisteredCbChain {
+    cb_chain_id: cb_chain.cb_chain_id,
+    caip2
+  });
+  Ok(())
+}
+
+/// Registers the Wormhole Chain ID of a registered network. Can be set only
+/// once per network. Owner-only.
+///
+/// ### args
+/// * cb_chain_id<[u8; 32]>: The network's cbChainId.
+/// * wormhole_chain_id<u16>: The network's Wormhole Chain ID.
+#[inline(never)]
+pub fn register_chain_wormhole_id(
+  ctx: Context<RegisterChainWormholeId>,
+  cb_chain_id: [u8; 32],
+  wormhole_chain_id: u16,
+) -> Result<()> {
+  require!(
+    wormhole_chain_id > 0,
+    ChainbillsError::InvalidWormholeChainId
+  );
+  let cb_chain = &mut ctx.accounts.cb_chain;
+  require!(
+    cb_chain.wormhole_chain_id == 0,
+    ChainbillsError::ChainIdAlreadyRegistered
+  );
+
+  cb_chain.wormhole_chain_id = wormhole_chain_id;
+  ctx.accounts.wormhole_chain_cb_chain_id.cb_chain_id = cb_chain_id;
+
+  msg!("Registered Wormhole Chain ID: {}.", wormhole_chain_id);
+  emit!(RegisteredChainWormholeId {
+    cb_chain_id,
+    wormhole_chain_id
+  });
+  Ok(())
+}
+
+/// Registers the Circle (CCTP) domain of a registered network. Can be set
+/// only once per network. Owner-only.
+///
+/// ### args
+/// * cb_chain_id<[u8; 32]>: The network's cbChainId.
+/// * circle_domain<u32>: The network's Circle domain.
+#[inline(never)]
+pub fn register_chain_circle_domain(
+  ctx: Context<RegisterChainCircleDomain>,
+  cb_chain_id: [u8; 32],
+  circle_domain: u32,
+) -> Result<()> {
+  let cb_chain = &mut ctx.accounts.cb_chain;
+  require!(
+    !cb_chain.has_circle_domain,
+    ChainbillsError::ChainIdAlreadyRegistered
+  );
+
+  cb_chain.circle_domain = circle_domain;
+  cb_chain.has_circle_domain = true;
+  ctx.accounts.circle_domain_cb_chain_id.cb_chain_id = cb_chain_id;
+
+  msg!("Registered Circle Domain: {}.", circle_domain);
+  emit!(RegisteredChainCircleDomain {
+    cb_chain_id,
+    circle_domain
+  });
+  Ok(())
+}
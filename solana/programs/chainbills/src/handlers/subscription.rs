@@ -0,0 +1,290 @@
+use super::pay::{
+  check_payment, credit_or_escrow_payment, update_state_for_payable_payment,
+  update_state_for_user_payment, LedgerEntry,
+};
+use crate::{context::*, error::ChainbillsError, events::*, state::*};
+use anchor_lang::{prelude::*, solana_program::clock};
+use anchor_spl::token_interface::{self, TransferChecked};
+
+fn record_subscription_activity(
+  chain_shard: &mut Account<ChainStatsShard>,
+  payer: &mut Account<User>,
+  payable: &mut Account<Payable>,
+  activity: &mut Account<ActivityRecord>,
+  user_activity_info: &mut Account<UserActivityInfo>,
+  payable_activity_info: &mut Account<PayableActivityInfo>,
+  activity_type: ActivityType,
+) -> Result<()> {
+  // Increment the activities counts.
+  chain_shard.activities_count = chain_shard.next_activity();
+  payer.activities_count = payer.next_activity();
+  payable.activities_count = payable.next_activity();
+
+  // Initialize the activity.
+  activity.chain_count = chain_shard.activities_count;
+  activity.user_count = payer.activities_count;
+  activity.payable_count = payable.activities_count;
+  activity.timestamp = clock::Clock::get()?.unix_timestamp as u64;
+  activity.entity = payable.key();
+  activity.activity_type = activity_type;
+
+  // Initialize the user and payable activity infos.
+  user_activity_info.chain_count = chain_shard.activities_count;
+  payable_activity_info.chain_count = chain_shard.activities_count;
+
+  Ok(())
+}
+
+/// Subscribes the signer to recurring payments into a payable. The payments
+/// are pulled from the signer's token account, so the signer must approve
+/// the ChainStats account as its delegate for at least the amount of the
+/// payments to be collected.
+///
+/// ### args
+/// * amount<u64>: The amount of each payment.
+/// * period<u64>: The seconds between payments.
+/// * first_due<Option<u64>>: The timestamp from which the first payment can
+///   be collected. Defaults to now and can't be in the past.
+/// * payer_proof<Vec<[u8; 32]>>: The Merkle proof that the signer is an
+///   allowed payer. Empty if the payable doesn't restrict its payers.
+#[inline(never)]
+pub fn create_subscription(
+  ctx: Context<CreateSubscription>,
+  amount: u64,
+  period: u64,
+  first_due: Option<u64>,
+  payer_proof: Vec<[u8; 32]>,
+) -> Result<()> {
+  /* CHECKS */
+  let mint = ctx.accounts.mint.key();
+  let payable = ctx.accounts.payable.as_mut();
+  check_payment(
+    amount,
+    mint,
+    ctx.accounts.mint.decimals,
+    payable,
+    &ctx.accounts.token_details,
+    None,
+  )?;
+  require!(
+    PayableAllowedPayers::permits(
+      &ctx.accounts.allowed_payers,
+      ctx.accounts.signer.key,
+      &payer_proof
+    )?,
+    ChainbillsError::PayerNotAllowed
+  );
+  require!(period > 0, ChainbillsError::InvalidSubscriptionPeriod);
+  let now = clock::Clock::get()?.unix_timestamp as u64;
+  let first_due = first_due.unwrap_or(now);
+  require!(
+    first_due >= now,
+    ChainbillsError::SubscriptionFirstDueInPast
+  );
+
+  // Ensure that the payer doesn't have an active subscription to the payable.
+  let subscription = ctx.accounts.subscription.as_mut();
+  require!(
+    subscription.is_available(),
+    ChainbillsError::SubscriptionAlreadyExists
+  );
+
+  /* STATE CHANGES */
+  subscription.payer = ctx.accounts.signer.key();
+  subscription.payable_id = payable.key();
+  subscription.token = mint;
+  subscription.amount = amount;
+  subscription.period = period;
+  subscription.next_due = first_due;
+  subscription.payments_count = 0;
+  subscription.is_cancelled = false;
+
+  // Record the activity.
+  record_subscription_activity(
+    ctx.accounts.chain_shard.as_mut(),
+    ctx.accounts.payer.as_mut(),
+    payable,
+    ctx.accounts.activity.as_mut(),
+    ctx.accounts.user_activity_info.as_mut(),
+    ctx.accounts.payable_activity_info.as_mut(),
+    ActivityType::CreatedSubscription,
+  )?;
+  ctx.accounts.activity.rent_payer = ctx.accounts.signer.key();
+
+  /* EVENTS */
+  msg!("Created Subscription.");
+  emit!(CreatedSubscription {
+    payable_id: payable.key(),
+    payer_wallet: subscription.payer,
+    token: mint,
+    amount,
+    period,
+    next_due: subscription.next_due,
+  });
+  Ok(())
+}
+
+/// Collects a due payment of a subscription. Can be called by anyone, who
+/// pays for the payment records. Only one payment is collected however many
+/// periods were missed, and the next one is due at the first period boundary
+/// after now.
+#[inline(never)]
+pub fn collect_subscription(ctx: Context<CollectSubscription>) -> Result<()> {
+  /* CHECKS */
+  let subscription = ctx.accounts.subscription.as_mut();
+  require!(
+    !subscription.is_cancelled,
+    ChainbillsError::SubscriptionIsCancelled
+  );
+  let now = clock::Clock::get()?.unix_timestamp as u64;
+  require!(
+    now >= subscription.next_due,
+    ChainbillsError::SubscriptionNotDue
+  );
+
+  let amount = subscription.amount;
+  let mint = &ctx.accounts.mint;
+  let payable = ctx.accounts.payable.as_mut();
+  let token_details = &ctx.accounts.token_details;
+  check_payment(
+    amount,
+    mint.key(),
+    mint.decimals,
+    payable,
+    token_details,
+    None,
+  )?;
+
+  /* TRANSFER */
+  // Pull the payment through the payer's delegation to the ChainStats.
+  let balance_before = ctx.accounts.chain_token_account.amount;
+  token_interface::transfer_checked(
+    CpiContext::new_with_signer(
+      ctx.accounts.token_program.to_account_info(),
+      TransferChecked {
+        from: ctx.accounts.payer_token_account.to_account_info(),
+        mint: mint.to_account_info(),
+        to: ctx.accounts.chain_token_account.to_account_info(),
+        authority: ctx.accounts.chain_stats.to_account_info(),
+      },
+      &[&[ChainStats::SEED_PREFIX, &[ctx.bumps.chain_stats]]],
+    ),
+    amount,
+    mint.decimals,
+  )?;
+
+  // Credit the payable with what actually arrived, as in pay.
+  ctx.accounts.chain_token_account.reload()?;
+  let received = ctx
+    .accounts
+    .chain_token_account
+    .amount
+    .checked_sub(balance_before)
+    .unwrap();
+  require!(received > 0, ChainbillsError::NothingReceivedFromTransfer);
+
+  /* STATE CHANGES */
+  // Move the subscription to its next future period, skipping missed ones.
+  subscription.next_due = subscription.next_due_after(now);
+  subscription.payments_count =
+    subscription.payments_count.checked_add(1).unwrap();
+
+  let cb_chain_id = solana_cb_chain_id();
+  let chain_shard = ctx.accounts.chain_shard.as_mut();
+
+  // Update State for User
+  let user_paid = update_state_for_user_payment(
+    amount,
+    mint.key(),
+    subscription.payer,
+    ctx.accounts.signer.key(),
+    ctx.accounts.payer.as_mut(),
+    chain_shard,
+    payable.key().to_bytes(),
+    cb_chain_id,
+    ctx.accounts.token_details_shard.as_mut(),
+    ctx.accounts.user_payment.as_mut(),
+    ctx.accounts.user_activity.as_mut(),
+  )?;
+
+  // Credit the payable, or hold the payment in escrow.
+  credit_or_escrow_payment(
+    payable,
+    &ctx.accounts.payable_escrow,
+    ctx.accounts.escrow_state.as_deref_mut(),
+    subscription.payer,
+    TokenAndAmount {
+      token: mint.key(),
+      amount: received,
+    },
+  )?;
+
+  // Update State for Payable
+  let payable_received = update_state_for_payable_payment(
+    received,
+    mint.key(),
+    ctx.accounts.signer.key(),
+    payable,
+    ctx.accounts.payable_per_chain_payments_counter.as_mut(),
+    chain_shard,
+    subscription.payer.to_bytes(),
+    cb_chain_id,
+    ctx.accounts.token_details_shard.as_mut(),
+    ctx.accounts.payable_payment.as_mut(),
+    LedgerEntry::of(
+      ctx.accounts.payable_per_chain_payment_info.as_deref_mut(),
+      ctx.accounts.payable_per_chain_payments_page.as_deref_mut(),
+    )?,
+    ctx.accounts.payable_activity.as_mut(),
+  )?;
+
+  /* EVENTS */
+  emit_cpi!(user_paid);
+  emit_cpi!(payable_received);
+  emit_cpi!(CollectedSubscription {
+    payable_id: payable.key(),
+    payer_wallet: subscription.payer,
+    collector: ctx.accounts.signer.key(),
+    subscription_count: subscription.payments_count,
+    next_due: subscription.next_due,
+  });
+  Ok(())
+}
+
+/// Cancels a subscription so that it can no longer be collected. Can be
+/// called by the payer, or by the payable's host or its operators with the
+/// manage role.
+#[inline(never)]
+pub fn cancel_subscription(ctx: Context<CancelSubscription>) -> Result<()> {
+  /* CHECKS */
+  let subscription = ctx.accounts.subscription.as_mut();
+  require!(
+    !subscription.is_cancelled,
+    ChainbillsError::SubscriptionIsCancelled
+  );
+
+  /* STATE CHANGES */
+  subscription.is_cancelled = true;
+
+  // Record the activity for the payer.
+  let payable = ctx.accounts.payable.as_mut();
+  record_subscription_activity(
+    ctx.accounts.chain_shard.as_mut(),
+    ctx.accounts.payer.as_mut(),
+    payable,
+    ctx.accounts.activity.as_mut(),
+    ctx.accounts.user_activity_info.as_mut(),
+    ctx.accounts.payable_activity_info.as_mut(),
+    ActivityType::CancelledSubscription,
+  )?;
+  ctx.accounts.activity.rent_payer = ctx.accounts.signer.key();
+
+  /* EVENTS */
+  msg!("Cancelled Subscription.");
+  emit!(CancelledSubscription {
+    payable_id: payable.key(),
+    payer_wallet: subscription.payer,
+    cancelled_by: ctx.accounts.signer.key(),
+  });
+  Ok(())
+}
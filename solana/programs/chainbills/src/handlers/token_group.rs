@@ -0,0 +1,49 @@
+use crate::{context::*, error::*, events::*, state::*};
+use anchor_lang::prelude::*;
+
+/// Creates or updates a token group, whose members payables can accept
+/// interchangeably. Owner-only.
+///
+/// ### args
+/// * name<String>: The group's name, which seeds its address.
+/// * decimals<u8>: The decimals of amounts that reference the group.
+/// * members<Vec<TokenGroupMember>>: The group's tokens with their decimals.
+///   Empty stops payments matching the group.
+#[inline(never)]
+pub fn update_token_group(
+  ctx: Context<UpdateTokenGroup>,
+  name: String,
+  decimals: u8,
+  members: Vec<TokenGroupMember>,
+) -> Result<()> {
+  /* CHECKS */
+  require!(
+    !name.is_empty() && name.len() <= TokenGroup::MAX_NAME_LEN,
+    ChainbillsError::InvalidTokenGroup
+  );
+  require!(
+    members.len() <= TokenGroup::MAX_MEMBERS,
+    ChainbillsError::InvalidTokenGroup
+  );
+  for (i, member) in members.iter().enumerate() {
+    require!(
+      !members[..i].iter().any(|m| m.token == member.token),
+      ChainbillsError::InvalidTokenGroup
+    );
+  }
+
+  /* STATE CHANGES */
+  let token_group = ctx.accounts.token_group.as_mut();
+  token_group.name = name.clone();
+  token_group.decimals = decimals;
+  token_group.members = members.clone();
+
+  msg!("Updated TokenGroup: {}.", name);
+  emit!(UpdatedTokenGroup {
+    token_group: token_group.key(),
+    name,
+    decimals,
+    members,
+  });
+  Ok(())
+}
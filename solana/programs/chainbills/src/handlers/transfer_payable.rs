@@ -0,0 +1,111 @@
+use crate::{context::*, error::ChainbillsError, events::*, state::*};
+use anchor_lang::prelude::*;
+
+fn record_transfer_activity(
+  chain_shard: &mut Account<ChainStatsShard>,
+  user: &mut Account<User>,
+  payable: &mut Account<Payable>,
+  activity: &mut Account<ActivityRecord>,
+  user_activity_info: &mut Account<UserActivityInfo>,
+  payable_activity_info: &mut Account<PayableActivityInfo>,
+  activity_type: ActivityType,
+) -> Result<()> {
+  // Increment the activities counts.
+  chain_shard.activities_count = chain_shard.next_activity();
+  user.activities_count = user.next_activity();
+  payable.activities_count = payable.next_activity();
+
+  // Initialize the activity.
+  activity.chain_count = chain_shard.activities_count;
+  activity.user_count = user.activities_count;
+  activity.payable_count = payable.activities_count;
+  activity.timestamp = Clock::get()?.unix_timestamp as u64;
+  activity.entity = payable.key();
+  activity.activity_type = activity_type;
+
+  // Initialize the user and payable activity infos.
+  user_activity_info.chain_count = chain_shard.activities_count;
+  payable_activity_info.chain_count = chain_shard.activities_count;
+
+  Ok(())
+}
+
+/// Offers a payable to another wallet. The payable only changes hosts when
+/// that wallet accepts it. Can be called only by the host of the payable.
+///
+/// ### args
+/// * new_host<Pubkey>: The wallet that can accept the payable.
+#[inline(never)]
+pub fn transfer_payable(
+  ctx: Context<TransferPayable>,
+  new_host: Pubkey,
+) -> Result<()> {
+  /* CHECKS */
+  require!(
+    new_host != ctx.accounts.signer.key(),
+    ChainbillsError::CannotTransferPayableToSelf
+  );
+
+  /* STATE CHANGES */
+  ctx.accounts.pending_payable_host.new_host = new_host;
+
+  /* EVENTS */
+  msg!("Initiated Payable Transfer.");
+  emit!(InitiatedPayableTransfer {
+    payable_id: ctx.accounts.payable.key(),
+    host_wallet: ctx.accounts.signer.key(),
+    new_host,
+  });
+  Ok(())
+}
+
+/// Makes the signer the host of a payable that was offered to them. The
+/// payable keeps its ID and is indexed at the new host's next payables count.
+#[inline(never)]
+pub fn accept_payable(ctx: Context<AcceptPayable>) -> Result<()> {
+  /* STATE CHANGES */
+  let previous_host = ctx.accounts.payable.host;
+  let new_host = ctx.accounts.signer.key();
+
+  // Index the payable for the new host.
+  let host = ctx.accounts.new_host.as_mut();
+  host.payables_count = host.next_payable();
+  ctx.accounts.user_payable_info.payable_id = ctx.accounts.payable.key();
+
+  // Move the payable to the new host.
+  let payable = ctx.accounts.payable.as_mut();
+  payable.host = new_host;
+  payable.host_count = host.payables_count;
+
+  // Record the activities of both hosts.
+  record_transfer_activity(
+    ctx.accounts.chain_shard.as_mut(),
+    ctx.accounts.previous_host.as_mut(),
+    payable,
+    ctx.accounts.previous_host_activity.as_mut(),
+    ctx.accounts.previous_host_activity_info.as_mut(),
+    ctx.accounts.previous_host_payable_activity_info.as_mut(),
+    ActivityType::TransferredPayable,
+  )?;
+  ctx.accounts.previous_host_activity.rent_payer = ctx.accounts.signer.key();
+  record_transfer_activity(
+    ctx.accounts.chain_shard.as_mut(),
+    host,
+    payable,
+    ctx.accounts.new_host_activity.as_mut(),
+    ctx.accounts.new_host_activity_info.as_mut(),
+    ctx.accounts.new_host_payable_activity_info.as_mut(),
+    ActivityType::AcceptedPayable,
+  )?;
+  ctx.accounts.new_host_activity.rent_payer = ctx.accounts.signer.key();
+
+  /* EVENTS */
+  msg!("Transferred Payable.");
+  emit!(TransferredPayable {
+    payable_id: payable.key(),
+    previous_host,
+    new_host,
+    new_host_count: payable.host_count,
+  });
+  Ok(())
+}
@@ -1,5 +1,85 @@
-use crate::{context::*, error::ChainbillsError, events::*};
-use anchor_lang::prelude::*;
+use crate::{
+  context::*, error::ChainbillsError, events::*, state::TokenDetails,
+};
+use anchor_lang::{
+  prelude::*,
+  system_program::{self, Transfer},
+};
+use anchor_spl::token_interface::spl_token_2022::{
+  self,
+  extension::{
+    default_account_state::DefaultAccountState,
+    permanent_delegate::PermanentDelegate, transfer_fee::TransferFeeConfig,
+    transfer_hook::TransferHook, BaseStateWithExtensions, ExtensionType,
+    StateWithExtensions,
+  },
+  state::AccountState,
+};
+
+/// Ensures that the mint doesn't use Token-2022 extensions that would break
+/// payments or let someone else move the funds held by this program.
+fn check_mint_extensions(mint: &AccountInfo) -> Result<()> {
+  // Legacy SPL Token mints have no extensions.
+  if *mint.owner != spl_token_2022::ID {
+    return Ok(());
+  }
+
+  let data = mint.try_borrow_data()?;
+  let state =
+    StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+  // Extensions newer than our spl-token-2022 (like Pausable, which lets an
+  // authority halt every transfer) can't be parsed, so they are refused too.
+  let extensions = state
+    .get_extension_types()
+    .map_err(|_| ChainbillsError::UnsupportedMintExtension)?;
+  for extension in extensions {
+    match extension {
+      // Tokens that can't be transferred can't be paid or withdrawn.
+      ExtensionType::NonTransferable => {
+        return err!(ChainbillsError::UnsupportedMintExtension);
+      }
+      // A permanent delegate can move tokens out of the chain token account
+      // without updating any payable's balances.
+      ExtensionType::PermanentDelegate => {
+        let delegate = state.get_extension::<PermanentDelegate>()?.delegate;
+        if Option::<Pubkey>::from(delegate).is_some() {
+          return err!(ChainbillsError::UnsupportedMintExtension);
+        }
+      }
+      // Transfer hooks need extra accounts that we don't pass along. An
+      // authority could also set a hook program later on.
+      ExtensionType::TransferHook => {
+        let hook = state.get_extension::<TransferHook>()?;
+        if Option::<Pubkey>::from(hook.program_id).is_some()
+          || Option::<Pubkey>::from(hook.authority).is_some()
+        {
+          return err!(ChainbillsError::UnsupportedMintExtension);
+        }
+      }
+      // New token accounts (like hosts' and the fee collector's) would be
+      // frozen and unable to receive withdrawals.
+      ExtensionType::DefaultAccountState => {
+        let state = state.get_extension::<DefaultAccountState>()?.state;
+        if state == AccountState::Frozen as u8 {
+          return err!(ChainbillsError::UnsupportedMintExtension);
+        }
+      }
+      // Fixed transfer fees are accounted for when paying, but an authority
+      // could raise them at any time.
+      ExtensionType::TransferFeeConfig => {
+        let config = state.get_extension::<TransferFeeConfig>()?;
+        if Option::<Pubkey>::from(config.transfer_fee_config_authority)
+          .is_some()
+        {
+          return err!(ChainbillsError::UnsupportedMintExtension);
+        }
+      }
+      _ => {}
+    }
+  }
+
+  Ok(())
+}
 
 /// Updates the maximum withdrawal fees of the given token.
 ///
@@ -24,8 +104,10 @@ pub fn update_max_withdrawal_fees(
   {
     return Err(ChainbillsError::WrongFeeCollectorAddress.into());
   }
+  check_mint_extensions(&ctx.accounts.mint.to_account_info())?;
 
   let token_details = ctx.accounts.token_details.as_mut();
+  token_details.version = TokenDetails::VERSION;
   token_details.mint = token;
   token_details.is_supported = true;
   token_details.max_withdrawal_fees = max_withdrawal_fees;
@@ -46,7 +128,25 @@ pub fn update_max_withdrawal_fees_native(
   ctx: Context<UpdateMaxWithdrawalFeesNative>,
   max_withdrawal_fees: u64,
 ) -> Result<()> {
+  // Fund the native vault up to rent-exemption if it isn't yet. Payments
+  // smaller than the rent-exempt minimum could not land in it otherwise.
+  let rent_exempt_reserve = Rent::get()?.minimum_balance(0);
+  let vault_lamports = ctx.accounts.native_vault.lamports();
+  if vault_lamports < rent_exempt_reserve {
+    system_program::transfer(
+      CpiContext::new(
+        ctx.accounts.system_program.to_account_info(),
+        Transfer {
+          from: ctx.accounts.owner.to_account_info(),
+          to: ctx.accounts.native_vault.to_account_info(),
+        },
+      ),
+      rent_exempt_reserve.checked_sub(vault_lamports).unwrap(),
+    )?;
+  }
+
   let token_details = ctx.accounts.token_details.as_mut();
+  token_details.version = TokenDetails::VERSION;
   token_details.mint = crate::ID;
   token_details.is_supported = true;
   token_details.max_withdrawal_fees = max_withdrawal_fees;
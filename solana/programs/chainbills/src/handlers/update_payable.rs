@@ -1,9 +1,10 @@
+use super::create_payable::check_allowed_token_and_amount;
 use crate::{context::*, error::*, events::*, payload::*, state::*};
 use anchor_lang::prelude::*;
 use wormhole_anchor_sdk::wormhole;
 
 fn record_update_payable_activity(
-  chain_stats: &mut Account<ChainStats>,
+  chain_shard: &mut Account<ChainStatsShard>,
   host: &mut Account<User>,
   payable: &mut Account<Payable>,
   activity: &mut Account<ActivityRecord>,
@@ -11,11 +12,8 @@ fn record_update_payable_activity(
   payable_activity_info: &mut Account<PayableActivityInfo>,
   activity_type: ActivityType,
 ) -> Result<()> {
-  // Increment the chain stats for activities count and 
-  // published_wormhole_messages_count.
-  chain_stats.activities_count = chain_stats.next_activity();
-  chain_stats.published_wormhole_messages_count =
-    chain_stats.next_published_wormhole_message();
+  // Increment the chain shard's activities count.
+  chain_shard.activities_count = chain_shard.next_activity();
 
   // Increment the host's activities count.
   host.activities_count = host.next_activity();
@@ -24,7 +22,7 @@ fn record_update_payable_activity(
   payable.activities_count = payable.next_activity();
 
   // Initialize the activity.
-  activity.chain_count = chain_stats.activities_count;
+  activity.chain_count = chain_shard.activities_count;
   activity.user_count = host.activities_count;
   activity.payable_count = payable.activities_count;
   activity.timestamp = Clock::get()?.unix_timestamp as u64;
@@ -32,16 +30,17 @@ fn record_update_payable_activity(
   activity.activity_type = activity_type;
 
   // Initialize the user activity info.
-  user_activity_info.chain_count = chain_stats.activities_count;
+  user_activity_info.chain_count = chain_shard.activities_count;
 
   // Initialize the payable activity info.
-  payable_activity_info.chain_count = chain_stats.activities_count;
+  payable_activity_info.chain_count = chain_shard.activities_count;
 
   Ok(())
 }
 
 /// Stop a payable from accepting payments. Can be called only
-/// by the host (user) that owns the payable.
+/// by the host (user) that owns the payable or by its operators with the
+/// manage role.
 #[inline(never)]
 pub fn close_payable(ctx: Context<UpdatePayable>) -> Result<()> {
   // Ensure that the payable is not already closed.
@@ -53,7 +52,7 @@ pub fn close_payable(ctx: Context<UpdatePayable>) -> Result<()> {
 
   // Record the activity.
   record_update_payable_activity(
-    ctx.accounts.chain_stats.as_mut(),
+    ctx.accounts.chain_shard.as_mut(),
     ctx.accounts.host.as_mut(),
     payable,
     ctx.accounts.activity.as_mut(),
@@ -61,14 +60,20 @@ pub fn close_payable(ctx: Context<UpdatePayable>) -> Result<()> {
     ctx.accounts.payable_activity_info.as_mut(),
     ActivityType::ClosedPayable,
   )?;
+  ctx.accounts.activity.rent_payer = ctx.accounts.signer.key();
 
   // Emit log and event.
   msg!("Closed Payable.");
   emit!(ClosedPayable {
     payable_id: payable.key(),
-    host_wallet: ctx.accounts.signer.key()
+    host_wallet: payable.host
   });
 
+  // Increment the chain stats' published_wormhole_messages_count.
+  let chain_stats = ctx.accounts.chain_stats.as_mut();
+  chain_stats.published_wormhole_messages_count =
+    chain_stats.next_published_wormhole_message();
+
   // If there is a fee for message sending, transfer it.
   let fee = ctx.accounts.wormhole_bridge.fee();
   if fee > 0 {
@@ -111,8 +116,10 @@ pub fn close_payable(ctx: Context<UpdatePayable>) -> Result<()> {
       version: 1,
       action_type: 2, // Close Payable
       payable_id: ctx.accounts.payable.key().to_bytes(),
+      nonce: ctx.accounts.payable.activities_count,
       is_closed: true,
       allowed_tokens_and_amounts: vec![],
+      allowed_payers_root: [0; 32],
     }
     .try_to_vec()?,
     wormhole::Finality::Confirmed,
@@ -122,7 +129,8 @@ pub fn close_payable(ctx: Context<UpdatePayable>) -> Result<()> {
 }
 
 /// Allow a closed payable to continue accepting payments.
-/// Can be called only by the host (user) that owns the payable.
+/// Can be called only by the host (user) that owns the payable or by its
+/// operators with the manage role.
 #[inline(never)]
 pub fn reopen_payable(ctx: Context<UpdatePayable>) -> Result<()> {
   // Ensure that the payable is not closed.
@@ -134,7 +142,7 @@ pub fn reopen_payable(ctx: Context<UpdatePayable>) -> Result<()> {
 
   // Record the activity.
   record_update_payable_activity(
-    ctx.accounts.chain_stats.as_mut(),
+    ctx.accounts.chain_shard.as_mut(),
     ctx.accounts.host.as_mut(),
     payable,
     ctx.accounts.activity.as_mut(),
@@ -142,14 +150,20 @@ pub fn reopen_payable(ctx: Context<UpdatePayable>) -> Result<()> {
     ctx.accounts.payable_activity_info.as_mut(),
     ActivityType::ReopenedPayable,
   )?;
+  ctx.accounts.activity.rent_payer = ctx.accounts.signer.key();
 
   // Emit log and event.
   msg!("Reopened Payable.");
   emit!(ReopenedPayable {
     payable_id: payable.key(),
-    host_wallet: ctx.accounts.signer.key()
+    host_wallet: payable.host
   });
 
+  // Increment the chain stats' published_wormhole_messages_count.
+  let chain_stats = ctx.accounts.chain_stats.as_mut();
+  chain_stats.published_wormhole_messages_count =
+    chain_stats.next_published_wormhole_message();
+
   // If there is a fee for message sending, transfer it.
   let fee = ctx.accounts.wormhole_bridge.fee();
   if fee > 0 {
@@ -192,8 +206,10 @@ pub fn reopen_payable(ctx: Context<UpdatePayable>) -> Result<()> {
       version: 1,
       action_type: 3, // Reopen Payable
       payable_id: ctx.accounts.payable.key().to_bytes(),
+      nonce: ctx.accounts.payable.activities_count,
       is_closed: false,
       allowed_tokens_and_amounts: vec![],
+      allowed_payers_root: [0; 32],
     }
     .try_to_vec()?,
     wormhole::Finality::Confirmed,
@@ -221,25 +237,17 @@ pub fn update_payable_allowed_tokens_and_amounts<'info>(
     ChainbillsError::InvalidRemainingAccountsLength
   );
 
+  // Ensure that the payable won't grow beyond what can be processed.
+  require!(
+    allowed_tokens_and_amounts.len() <= Payable::MAX_ALLOWED_TOKENS_AND_AMOUNTS,
+    ChainbillsError::TooManyAllowedTokensAndAmounts
+  );
+
   let mut ataa_foreign: Vec<TokenAndAmountForeign> = vec![];
   for (i, taa) in allowed_tokens_and_amounts.iter().enumerate() {
-    // Get the token details for the specified token.
-    let token_details =
-      Account::<'info, TokenDetails>::try_from(&ctx.remaining_accounts[i])
-        .map_err(|_| ChainbillsError::NonTokenDetailsAccountProvided)?;
-
-    // Ensure that the token is supported.
-    require!(
-      taa.token == token_details.mint,
-      ChainbillsError::InvalidTokenDetailsAccount
-    );
-    require!(
-      token_details.is_supported,
-      ChainbillsError::UnsupportedToken
-    );
-
-    // Ensure that all specified acceptable amounts are greater than zero.
-    require!(taa.amount > 0, ChainbillsError::ZeroAmountSpecified);
+    // Ensure that the token is supported (or is a token group) and that the
+    // amount is greater than zero.
+    check_allowed_token_and_amount(taa, &ctx.remaining_accounts[i])?;
 
     // Set the foreign ATAA in the same loop
     ataa_foreign.push(TokenAndAmountForeign {
@@ -255,7 +263,7 @@ pub fn update_payable_allowed_tokens_and_amounts<'info>(
 
   // Record the activity.
   record_update_payable_activity(
-    ctx.accounts.chain_stats.as_mut(),
+    ctx.accounts.chain_shard.as_mut(),
     ctx.accounts.host.as_mut(),
     payable,
     ctx.accounts.activity.as_mut(),
@@ -263,14 +271,22 @@ pub fn update_payable_allowed_tokens_and_amounts<'info>(
     ctx.accounts.payable_activity_info.as_mut(),
     ActivityType::UpdatedPayableAllowedTokensAndAmounts,
   )?;
+  ctx.accounts.activity.rent_payer = ctx.accounts.signer.key();
 
   // Emit log and event.
   msg!("Updated Payable's allowedTokensAndAmounts.");
-  emit!(UpdatedPayableAllowedTokensAndAmounts {
+  emit_cpi!(UpdatedPayableAllowedTokensAndAmounts {
     payable_id: payable.key(),
-    host_wallet: ctx.accounts.signer.key()
+    host_wallet: payable.host,
+    allowed_tokens_and_amounts: payable.allowed_tokens_and_amounts.clone(),
+    timestamp: ctx.accounts.activity.timestamp,
   });
 
+  // Increment the chain stats' published_wormhole_messages_count.
+  let chain_stats = ctx.accounts.chain_stats.as_mut();
+  chain_stats.published_wormhole_messages_count =
+    chain_stats.next_published_wormhole_message();
+
   // If there is a fee for message sending, transfer it.
   let fee = ctx.accounts.wormhole_bridge.fee();
   if fee > 0 {
@@ -313,8 +329,125 @@ pub fn update_payable_allowed_tokens_and_amounts<'info>(
       version: 1,
       action_type: 4, // Update Payable Allowed Tokens And Amounts
       payable_id: ctx.accounts.payable.key().to_bytes(),
+      nonce: ctx.accounts.payable.activities_count,
       is_closed: false,
       allowed_tokens_and_amounts: ataa_foreign,
+      allowed_payers_root: [0; 32],
+    }
+    .try_to_vec()?,
+    wormhole::Finality::Confirmed,
+  )?;
+
+  Ok(())
+}
+
+/// Allows a payable's host (or its operators with the manage role) to
+/// restrict who can pay the payable.
+///
+/// ### args
+/// * allowed_payers: the addresses of the payers allowed to pay. Their Merkle
+///   root is computed and stored.
+/// * allowed_payers_root: the Merkle root of the allowed payers, for lists
+///   too long to be given. Must be zero if allowed_payers isn't empty. Both
+///   being empty opens the payable to anyone.
+#[inline(never)]
+pub fn update_payable_allowed_payers(
+  ctx: Context<UpdatePayableAllowedPayers>,
+  allowed_payers: Vec<[u8; 32]>,
+  allowed_payers_root: [u8; 32],
+) -> Result<()> {
+  /* CHECKS */
+  // Ensure that only one of the list or the root was given.
+  let root = if allowed_payers.is_empty() {
+    allowed_payers_root
+  } else {
+    require!(
+      allowed_payers_root == chainbills_payload::OPEN_TO_ALL_PAYERS,
+      ChainbillsError::InvalidAllowedPayers
+    );
+    chainbills_payload::allowed_payers_root(
+      &allowed_payers,
+      &PayableAllowedPayers::keccak,
+    )
+  };
+
+  /* STATE CHANGES */
+  // Update the payable's allowed payers.
+  ctx.accounts.allowed_payers.root = root;
+
+  // Record the activity.
+  let payable = ctx.accounts.payable.as_mut();
+  record_update_payable_activity(
+    ctx.accounts.chain_shard.as_mut(),
+    ctx.accounts.host.as_mut(),
+    payable,
+    ctx.accounts.activity.as_mut(),
+    ctx.accounts.user_activity_info.as_mut(),
+    ctx.accounts.payable_activity_info.as_mut(),
+    ActivityType::UpdatedPayableAllowedPayers,
+  )?;
+  ctx.accounts.activity.rent_payer = ctx.accounts.signer.key();
+
+  // Emit log and event.
+  msg!("Updated Payable's allowed payers.");
+  emit!(UpdatedPayableAllowedPayers {
+    payable_id: payable.key(),
+    host_wallet: payable.host,
+    allowed_payers_root: root,
+    allowed_payers,
+  });
+
+  // Increment the chain stats' published_wormhole_messages_count.
+  let chain_stats = ctx.accounts.chain_stats.as_mut();
+  chain_stats.published_wormhole_messages_count =
+    chain_stats.next_published_wormhole_message();
+
+  // If there is a fee for message sending, transfer it.
+  let fee = ctx.accounts.wormhole_bridge.fee();
+  if fee > 0 {
+    solana_program::program::invoke(
+      &solana_program::system_instruction::transfer(
+        &ctx.accounts.signer.key(),
+        &ctx.accounts.wormhole_fee_collector.key(),
+        fee,
+      ),
+      &ctx.accounts.to_account_infos(),
+    )?;
+  }
+
+  // Publish Message through Wormhole.
+  wormhole::post_message(
+    CpiContext::new_with_signer(
+      ctx.accounts.wormhole_program.to_account_info(),
+      wormhole::PostMessage {
+        config: ctx.accounts.wormhole_bridge.to_account_info(),
+        message: ctx.accounts.wormhole_message.to_account_info(),
+        emitter: ctx.accounts.wormhole_emitter.to_account_info(),
+        sequence: ctx.accounts.wormhole_sequence.to_account_info(),
+        payer: ctx.accounts.signer.to_account_info(),
+        fee_collector: ctx.accounts.wormhole_fee_collector.to_account_info(),
+        clock: ctx.accounts.clock.to_account_info(),
+        rent: ctx.accounts.rent.to_account_info(),
+        system_program: ctx.accounts.system_program.to_account_info(),
+      },
+      &[
+        &[
+          SEED_PREFIX_SENT,
+          &ctx.accounts.wormhole_sequence.next_value().to_le_bytes()[..],
+          &[ctx.bumps.wormhole_message],
+        ],
+        &[wormhole::SEED_PREFIX_EMITTER, &[ctx.bumps.wormhole_emitter]],
+      ],
+    ),
+    0, // Zero means no batching.
+    PayablePayload {
+      version: 1,
+      action_type: 5, // Update Payable Allowed Payers
+      payable_id: ctx.accounts.payable.key().to_bytes(),
+      nonce: ctx.accounts.payable.activities_count,
+      is_closed: false,
+      allowed_tokens_and_amounts: vec![],
+      allowed_payers_root: root,
     }
     .try_to_vec()?,
     wormhole::Finality::Confirmed,
@@ -1,7 +1,25 @@
 use crate::{context::*, error::ChainbillsError, events::*, state::*};
-use anchor_lang::{prelude::*, solana_program::clock};
-use anchor_spl::token::{self, Transfer as SplTransfer};
+use anchor_lang::{
+  prelude::*,
+  solana_program::{clock, instruction::Instruction, program::invoke_signed},
+  system_program::{self, Transfer},
+};
+use anchor_spl::token_interface::{self, TransferChecked};
 use std::cmp::min;
+use wormhole_cctp_solana::cctp::{
+  token_messenger_minter_program::cpi::DepositForBurnWithCaller,
+  TOKEN_MESSENGER_MINTER_PROGRAM_ID,
+};
+
+/// Anchor selector of the CCTP Token Messenger Minter's deposit_for_burn.
+const DEPOSIT_FOR_BURN_SELECTOR: [u8; 8] = [215, 60, 61, 46, 114, 55, 128, 176];
+
+#[derive(AnchorSerialize)]
+struct DepositForBurnParams {
+  amount: u64,
+  destination_domain: u32,
+  mint_recipient: [u8; 32],
+}
 
 fn check_withdraw_inputs(
   amount: u64,
@@ -34,6 +52,31 @@ fn check_withdraw_inputs(
   Ok(())
 }
 
+/// Shrinks the payable's account down to the space its data now needs and
+/// refunds the freed rent to the host.
+fn shrink_payable(
+  payable: &Account<Payable>,
+  host: &AccountInfo,
+) -> Result<()> {
+  let payable_info = payable.to_account_info();
+  let new_space = payable.space_current();
+  if new_space >= payable_info.data_len() {
+    return Ok(());
+  }
+  payable_info.realloc(new_space, false)?;
+
+  let rent_exempt = Rent::get()?.minimum_balance(new_space);
+  let excess = payable_info.lamports().saturating_sub(rent_exempt);
+  if excess > 0 {
+    let mut payable_lamports = payable_info.try_borrow_mut_lamports()?;
+    **payable_lamports = payable_lamports.checked_sub(excess).unwrap();
+    let mut host_lamports = host.try_borrow_mut_lamports()?;
+    **host_lamports = host_lamports.checked_add(excess).unwrap();
+  }
+
+  Ok(())
+}
+
 struct WithdrawalAmounts {
   amount_due: u64,
   fees: u64,
@@ -58,8 +101,11 @@ fn update_state_for_withdrawal(
   amount: u64,
   fees: u64,
   mint: Pubkey,
-  signer: Pubkey,
-  chain_stats: &mut Account<ChainStats>,
+  host_wallet: Pubkey,
+  rent_payer: Pubkey,
+  dest_chain_id: [u8; 32],
+  dest_address: [u8; 32],
+  chain_shard: &mut Account<ChainStatsShard>,
   payable: &mut Account<Payable>,
   host: &mut Account<User>,
   token_details: &mut Account<TokenDetails>,
@@ -69,10 +115,10 @@ fn update_state_for_withdrawal(
   activity: &mut Account<ActivityRecord>,
   user_activity_info: &mut Account<UserActivityInfo>,
   payable_activity_info: &mut Account<PayableActivityInfo>,
-) -> Result<()> {
-  // Increment the chain stats for payables_count and activities_count.
-  chain_stats.withdrawals_count = chain_stats.next_withdrawal();
-  chain_stats.activities_count = chain_stats.next_activity();
+) -> Result<Withdrew> {
+  // Increment the chain shard's withdrawals_count and activities_count.
+  chain_shard.withdrawals_count = chain_shard.next_withdrawal();
+  chain_shard.activities_count = chain_shard.next_activity();
 
   // Increment withdrawals_count and activities_count in the host that just
   // withdrew.
@@ -91,6 +137,10 @@ fn update_state_for_withdrawal(
     }
   }
 
+  // Drop emptied balances so that they don't bloat the payable. The account
+  // is shrunk accordingly after this.
+  payable.balances.retain(|balance| balance.amount > 0);
+
   // Increase the supported token's totals from this withdrawal.
   token_details.add_withdrawn(amount);
   token_details.add_withdrawal_fees_collected(fees);
@@ -98,16 +148,19 @@ fn update_state_for_withdrawal(
   let timestamp = clock::Clock::get()?.unix_timestamp as u64;
 
   // Initialize the withdrawal.
-  withdrawal.chain_count = chain_stats.withdrawals_count;
+  withdrawal.chain_count = chain_shard.withdrawals_count;
   withdrawal.payable_id = payable.key();
   withdrawal.payable_count = payable.withdrawals_count;
-  withdrawal.host = signer;
+  withdrawal.host = host_wallet;
   withdrawal.host_count = host.withdrawals_count;
   withdrawal.timestamp = timestamp;
   withdrawal.details = TokenAndAmount {
     token: mint,
     amount,
   };
+  withdrawal.dest_chain_id = dest_chain_id;
+  withdrawal.dest_address = dest_address;
+  withdrawal.rent_payer = rent_payer;
 
   // Initialize the chain_withdrawal_id.
   chain_withdrawal_id.withdrawal_id = withdrawal.key();
@@ -118,38 +171,44 @@ fn update_state_for_withdrawal(
   payable_withdrawal_info.host_count = host.withdrawals_count;
 
   // Initialize the activity.
-  activity.chain_count = chain_stats.activities_count;
+  activity.chain_count = chain_shard.activities_count;
   activity.user_count = host.activities_count;
   activity.payable_count = payable.activities_count;
   activity.timestamp = timestamp;
   activity.entity = withdrawal.key();
   activity.activity_type = ActivityType::Withdrew;
+  activity.rent_payer = rent_payer;
 
   // Initialize the user activity info.
-  user_activity_info.chain_count = chain_stats.activities_count;
+  user_activity_info.chain_count = chain_shard.activities_count;
 
   // Initialize the payable activity info.
-  payable_activity_info.chain_count = chain_stats.activities_count;
+  payable_activity_info.chain_count = chain_shard.activities_count;
 
-  // Emit log and event.
+  // Log and return the event for the caller to emit through CPI.
   msg!(
     "Withdrawal was made with chain_count: {}, host_count: {}, and payable_count: {}.",
     withdrawal.chain_count,
     withdrawal.host_count,
     withdrawal.payable_count
   );
-  emit!(Withdrew {
+  Ok(Withdrew {
     payable_id: payable.key(),
-    host_wallet: signer,
+    host_wallet,
     withdrawal_id: withdrawal.key(),
     chain_count: withdrawal.chain_count,
     host_count: withdrawal.host_count,
     payable_count: withdrawal.payable_count,
-  });
-  Ok(())
+    token: mint,
+    amount,
+    fees,
+    amount_due: amount.checked_sub(fees).unwrap(),
+    timestamp,
+  })
 }
 
-/// Transfers the amount of tokens from a payable to a host
+/// Transfers the amount of tokens from a payable to a host. Can be called by
+/// the host or by an operator with the withdraw role.
 ///
 /// ### args
 /// * amount<u64>: The amount to be withdrawn
@@ -175,44 +234,51 @@ pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
   let authority = &ctx.accounts.chain_stats;
 
   // Prepare accounts for withdrawing and for fees
-  let cpi_accounts_host = SplTransfer {
+  let cpi_accounts_host = TransferChecked {
     from: source.to_account_info().clone(),
+    mint: mint.to_account_info().clone(),
     to: host_ta.to_account_info().clone(),
     authority: authority.to_account_info().clone(),
   };
-  let cpi_accounts_fees = SplTransfer {
+  let cpi_accounts_fees = TransferChecked {
     from: source.to_account_info().clone(),
+    mint: mint.to_account_info().clone(),
     to: fees_ta.to_account_info().clone(),
     authority: authority.to_account_info().clone(),
   };
 
   // Transfer the amount minus fees to the host.
-  token::transfer(
+  token_interface::transfer_checked(
     CpiContext::new_with_signer(
       token_program.to_account_info(),
       cpi_accounts_host,
       &[&[ChainStats::SEED_PREFIX, &[ctx.bumps.chain_stats]]],
     ),
     amount_due,
+    mint.decimals,
   )?;
 
   // Transfer the fees to the fees collector.
-  token::transfer(
+  token_interface::transfer_checked(
     CpiContext::new_with_signer(
       token_program.to_account_info(),
       cpi_accounts_fees,
       &[&[ChainStats::SEED_PREFIX, &[ctx.bumps.chain_stats]]],
     ),
     fees,
+    mint.decimals,
   )?;
 
   /* STATE CHANGES */
-  update_state_for_withdrawal(
+  let withdrew = update_state_for_withdrawal(
     amount,
     fees,
     mint.key(),
+    ctx.accounts.host_wallet.key(),
     ctx.accounts.signer.key(),
-    ctx.accounts.chain_stats.as_mut(),
+    solana_cb_chain_id(),
+    ctx.accounts.host_wallet.key().to_bytes(),
+    ctx.accounts.chain_shard.as_mut(),
     payable,
     ctx.accounts.host.as_mut(),
     token_details,
@@ -222,10 +288,29 @@ pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
     ctx.accounts.activity.as_mut(),
     ctx.accounts.user_activity_info.as_mut(),
     ctx.accounts.payable_activity_info.as_mut(),
-  )
+  )?;
+  shrink_payable(
+    &ctx.accounts.payable,
+    &ctx.accounts.host_wallet.to_account_info(),
+  )?;
+
+  /* EVENTS */
+  if withdrew.fees > 0 {
+    emit_cpi!(CollectedWithdrawalFees {
+      payable_id: withdrew.payable_id,
+      withdrawal_id: withdrew.withdrawal_id,
+      fee_collector: ctx.accounts.fee_collector.key(),
+      token: withdrew.token,
+      fees: withdrew.fees,
+      timestamp: withdrew.timestamp,
+    });
+  }
+  emit_cpi!(withdrew);
+  Ok(())
 }
 
-/// Transfers the amount of native tokens (Solana) from a payable to a host
+/// Transfers the amount of native tokens (Solana) from a payable to a host.
+/// Can be called by the host or by an operator with the withdraw role.
 ///
 /// ### args
 /// * amount<u64>: The amount to be withdrawn
@@ -245,38 +330,54 @@ pub fn withdraw_native(
   let WithdrawalAmounts { amount_due, fees } =
     compute_amounts(amount, token_details, &config);
 
-  // Extract Accounts needed for transferring
-  let chain_stats = ctx.accounts.chain_stats.to_account_info();
-  let signer = ctx.accounts.signer.to_account_info();
-  let fees_collector = ctx.accounts.fee_collector.to_account_info();
+  // Ensure that the vault stays rent-exempt after this withdrawal.
+  let vault = &ctx.accounts.native_vault;
+  let rent_exempt_reserve = Rent::get()?.minimum_balance(0);
+  require!(
+    vault.lamports().saturating_sub(amount) >= rent_exempt_reserve,
+    ChainbillsError::InsufficientNativeVaultBalance
+  );
+  let signer_seeds: &[&[&[u8]]] =
+    &[&[SEED_PREFIX_NATIVE_VAULT, &[ctx.bumps.native_vault]]];
 
   // Transfer the amount minus fees to the host.
-  chain_stats
-    .try_borrow_mut_lamports()?
-    .checked_sub(amount_due)
-    .unwrap();
-  signer
-    .try_borrow_mut_lamports()?
-    .checked_add(amount_due)
-    .unwrap();
+  system_program::transfer(
+    CpiContext::new_with_signer(
+      ctx.accounts.system_program.to_account_info(),
+      Transfer {
+        from: vault.to_account_info(),
+        to: ctx.accounts.host_wallet.to_account_info(),
+      },
+      signer_seeds,
+    ),
+    amount_due,
+  )?;
 
   // Transfer the fees to the fees collector.
-  chain_stats
-    .try_borrow_mut_lamports()?
-    .checked_sub(fees)
-    .unwrap();
-  fees_collector
-    .try_borrow_mut_lamports()?
-    .checked_add(fees)
-    .unwrap();
+  if fees > 0 {
+    system_program::transfer(
+      CpiContext::new_with_signer(
+        ctx.accounts.system_program.to_account_info(),
+        Transfer {
+          from: vault.to_account_info(),
+          to: ctx.accounts.fee_collector.to_account_info(),
+        },
+        signer_seeds,
+      ),
+      fees,
+    )?;
+  }
 
   /* STATE CHANGES */
-  update_state_for_withdrawal(
+  let withdrew = update_state_for_withdrawal(
     amount,
     fees,
     crate::ID,
+    ctx.accounts.host_wallet.key(),
     ctx.accounts.signer.key(),
-    ctx.accounts.chain_stats.as_mut(),
+    solana_cb_chain_id(),
+    ctx.accounts.host_wallet.key().to_bytes(),
+    ctx.accounts.chain_shard.as_mut(),
     payable,
     ctx.accounts.host.as_mut(),
     token_details,
@@ -286,5 +387,192 @@ pub fn withdraw_native(
     ctx.accounts.activity.as_mut(),
     ctx.accounts.user_activity_info.as_mut(),
     ctx.accounts.payable_activity_info.as_mut(),
-  )
+  )?;
+  shrink_payable(
+    &ctx.accounts.payable,
+    &ctx.accounts.host_wallet.to_account_info(),
+  )?;
+
+  /* EVENTS */
+  if withdrew.fees > 0 {
+    emit_cpi!(CollectedWithdrawalFees {
+      payable_id: withdrew.payable_id,
+      withdrawal_id: withdrew.withdrawal_id,
+      fee_collector: ctx.accounts.fee_collector.key(),
+      token: withdrew.token,
+      fees: withdrew.fees,
+      timestamp: withdrew.timestamp,
+    });
+  }
+  emit_cpi!(withdrew);
+  Ok(())
+}
+
+/// Burns the amount of USDC (minus fees) from a payable through Circle's CCTP
+/// for the host to receive in a wallet on another chain. A relayer completes
+/// the withdrawal with the attestation of the emitted CCTP message.
+///
+/// ### args
+/// * amount<u64>: The amount to be withdrawn
+/// * dest_chain_id<[u8; 32]>: The cbChainId of the destination chain.
+/// * dest_address<[u8; 32]>: The host's wallet on the destination chain.
+#[inline(never)]
+pub fn withdraw_cross_chain(
+  ctx: Context<WithdrawCrossChain>,
+  amount: u64,
+  dest_chain_id: [u8; 32],
+  dest_address: [u8; 32],
+) -> Result<()> {
+  /* CHECKS */
+  let payable = ctx.accounts.payable.as_mut();
+  let mint = &ctx.accounts.mint;
+  check_withdraw_inputs(amount, mint.key(), payable)?;
+
+  // Ensure that the destination is another chain that CCTP can mint on.
+  let dest_cb_chain = &ctx.accounts.dest_cb_chain;
+  require!(
+    dest_chain_id != solana_cb_chain_id() && dest_cb_chain.has_circle_domain,
+    ChainbillsError::InvalidDestinationChain
+  );
+  require!(
+    dest_address != [0u8; 32],
+    ChainbillsError::InvalidDestinationAddress
+  );
+
+  /* TRANSFERS */
+  // Prepare withdraw amounts and fees
+  let config = ctx.accounts.config.load()?;
+  let token_details = ctx.accounts.token_details.as_mut();
+  let WithdrawalAmounts { amount_due, fees } =
+    compute_amounts(amount, token_details, &config);
+
+  let source = &ctx.accounts.chain_token_account;
+  let token_program = &ctx.accounts.token_program;
+  let authority = &ctx.accounts.chain_stats;
+  let signer_seeds: &[&[&[u8]]] =
+    &[&[ChainStats::SEED_PREFIX, &[ctx.bumps.chain_stats]]];
+
+  // Transfer the fees to the fees collector.
+  if fees > 0 {
+    token_interface::transfer_checked(
+      CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        TransferChecked {
+          from: source.to_account_info(),
+          mint: mint.to_account_info(),
+          to: ctx.accounts.fees_token_account.to_account_info(),
+          authority: authority.to_account_info(),
+        },
+        signer_seeds,
+      ),
+      fees,
+      mint.decimals,
+    )?;
+  }
+
+  // The CCTP message will take the nonce that is next as at now.
+  let cctp_nonce = ctx.accounts.message_transmitter_config.next_available_nonce;
+
+  // Burn the amount minus fees for the host's wallet on the destination. Any
+  // caller can receive the message on the destination, so that the relayer
+  // isn't needed for the host to get their funds.
+  let cctp_accounts = DepositForBurnWithCaller {
+    burn_token_owner: authority.to_account_info(),
+    payer: ctx.accounts.signer.to_account_info(),
+    token_messenger_minter_sender_authority: ctx
+      .accounts
+      .token_messenger_minter_sender_authority
+      .to_account_info(),
+    burn_token: source.to_account_info(),
+    message_transmitter_config: ctx
+      .accounts
+      .message_transmitter_config
+      .to_account_info(),
+    token_messenger: ctx.accounts.token_messenger.to_account_info(),
+    remote_token_messenger: ctx
+      .accounts
+      .remote_token_messenger
+      .to_account_info(),
+    token_minter: ctx.accounts.token_minter.to_account_info(),
+    local_token: ctx.accounts.local_token.to_account_info(),
+    mint: mint.to_account_info(),
+    cctp_message: ctx.accounts.cctp_message.to_account_info(),
+    message_transmitter_program: ctx
+      .accounts
+      .message_transmitter_program
+      .to_account_info(),
+    token_messenger_minter_program: ctx
+      .accounts
+      .token_messenger_minter_program
+      .to_account_info(),
+    token_program: token_program.to_account_info(),
+    system_program: ctx.accounts.system_program.to_account_info(),
+    event_authority: ctx
+      .accounts
+      .token_messenger_minter_event_authority
+      .to_account_info(),
+  };
+  let params = DepositForBurnParams {
+    amount: amount_due,
+    destination_domain: dest_cb_chain.circle_domain,
+    mint_recipient: dest_address,
+  };
+  invoke_signed(
+    &Instruction {
+      program_id: TOKEN_MESSENGER_MINTER_PROGRAM_ID,
+      accounts: cctp_accounts.to_account_metas(None),
+      data: (DEPOSIT_FOR_BURN_SELECTOR, params).try_to_vec()?,
+    },
+    &cctp_accounts.to_account_infos(),
+    signer_seeds,
+  )?;
+
+  /* STATE CHANGES */
+  let withdrew = update_state_for_withdrawal(
+    amount,
+    fees,
+    mint.key(),
+    ctx.accounts.signer.key(),
+    ctx.accounts.signer.key(),
+    dest_chain_id,
+    dest_address,
+    ctx.accounts.chain_shard.as_mut(),
+    payable,
+    ctx.accounts.host.as_mut(),
+    token_details,
+    ctx.accounts.withdrawal.as_mut(),
+    ctx.accounts.chain_withdrawal_id.as_mut(),
+    ctx.accounts.payable_withdrawal_info.as_mut(),
+    ctx.accounts.activity.as_mut(),
+    ctx.accounts.user_activity_info.as_mut(),
+    ctx.accounts.payable_activity_info.as_mut(),
+  )?;
+  shrink_payable(
+    &ctx.accounts.payable,
+    &ctx.accounts.signer.to_account_info(),
+  )?;
+
+  /* EVENTS */
+  if withdrew.fees > 0 {
+    emit_cpi!(CollectedWithdrawalFees {
+      payable_id: withdrew.payable_id,
+      withdrawal_id: withdrew.withdrawal_id,
+      fee_collector: ctx.accounts.fee_collector.key(),
+      token: withdrew.token,
+      fees: withdrew.fees,
+      timestamp: withdrew.timestamp,
+    });
+  }
+  emit_cpi!(WithdrewCrossChain {
+    payable_id: withdrew.payable_id,
+    withdrawal_id: withdrew.withdrawal_id,
+    dest_chain_id,
+    dest_address,
+    dest_circle_domain: ctx.accounts.dest_cb_chain.circle_domain,
+    cctp_nonce,
+    amount_due: withdrew.amount_due,
+    timestamp: withdrew.timestamp,
+  });
+  emit_cpi!(withdrew);
+  Ok(())
 }
@@ -5,7 +5,10 @@ pub mod handlers;
 pub mod payload;
 pub mod state;
 
-use crate::{context::*, state::TokenAndAmount};
+use crate::{
+  context::*,
+  state::{TokenAndAmount, TokenGroupMember, TokenIntegrity},
+};
 use anchor_lang::prelude::*;
 
 declare_id!("GazbpBKrionSvJbeqqqbfqCvK8m7prd8eq5P1SK5EZUD");
@@ -24,7 +27,8 @@ pub mod chainbills {
   /// ChainStats keeps track of the count of all entities in this program,
   /// that were created on this chain (and any other chain). Entities include
   /// Users, Payables, Payments, and Withdrawals. Initializing any other entity
-  /// must increment the appropriate count in the appropriate ChainStats.
+  /// must increment the appropriate count in the appropriate ChainStats (or,
+  /// on Solana, in one of its shards. See initialize_chain_shard).
   ///
   /// ChainStats has to be initialized for each BlockChain Network
   /// involved in Chainbills. Solana's ChainStats also gets initialized here.
@@ -35,6 +39,23 @@ pub mod chainbills {
     handlers::initialize_handler(ctx)
   }
 
+  /// Initialize one of the shards of Solana's ChainStats. Should be called
+  /// only by the owner, once for each index below ChainStatsShard::COUNT.
+  ///
+  /// Users, payables, payments, withdrawals, and activities are indexed in
+  /// the shard that their transaction passes instead of in ChainStats, so
+  /// that transactions on different payables can run in parallel.
+  ///
+  /// ### args
+  /// * index<u8>: The index of the shard.
+  #[inline(never)]
+  pub fn initialize_chain_shard(
+    ctx: Context<InitializeChainShard>,
+    index: u8,
+  ) -> Result<()> {
+    handlers::initialize_chain_shard(ctx, index)
+  }
+
   /// Initialize a User.
   ///
   /// A User Account keeps track of the count of all entities associated with
@@ -61,23 +82,186 @@ pub mod chainbills {
 
   /// Transfers the amount of tokens from a payer to a payable
   ///
+  /// The payment's accounts are derived from the counts of the payer and of
+  /// the payable, so that concurrent payments into different payables don't
+  /// collide. The chain_shard only counts the payment at chain level and the
+  /// token_details_shard adds it to the token's totals. The payable's index
+  /// entry (payable_per_chain_payment_info) is recorded either in its own
+  /// account or, if that account isn't passed, in its ledger page, which
+  /// holds the entries of many payments at the cost of one account. The same
+  /// goes for the other payment instructions.
+  ///
+  /// If the payable's receipts are enabled, the payment also mints its
+  /// receipt NFT to the payer, with the receipt accounts that are otherwise
+  /// left out. So do the other payment instructions on this chain.
+  ///
   /// ### args
   /// * amount<u64>: The amount to be paid
+  /// * payer_proof<Vec<[u8; 32]>>: The Merkle proof that the signer is an
+  ///   allowed payer. Empty if the payable doesn't restrict its payers.
   #[inline(never)]
-  pub fn pay(ctx: Context<Pay>, amount: u64) -> Result<()> {
-    handlers::pay(ctx, amount)
+  pub fn pay(
+    ctx: Context<Pay>,
+    amount: u64,
+    payer_proof: Vec<[u8; 32]>,
+  ) -> Result<()> {
+    handlers::pay(ctx, amount, payer_proof)
   }
 
   /// Transfers the amount of native tokens (Solana) to a payable
   ///
   /// ### args
   /// * amount<u64>: The Wormhole-normalized amount to be paid
+  /// * payer_proof<Vec<[u8; 32]>>: The Merkle proof that the signer is an
+  ///   allowed payer. Empty if the payable doesn't restrict its payers.
+  #[inline(never)]
+  pub fn pay_native(
+    ctx: Context<PayNative>,
+    amount: u64,
+    payer_proof: Vec<[u8; 32]>,
+  ) -> Result<()> {
+    handlers::pay_native(ctx, amount, payer_proof)
+  }
+
+  /// Transfers the exact amount of tokens of an intent that the payable's
+  /// host signed off-chain, regardless of the payable's allowed tokens and
+  /// amounts. The host's signature of the intent must be verified by an
+  /// Ed25519 program instruction right before this one.
+  ///
+  /// ### args
+  /// * amount<u64>: The amount that the host signed.
+  /// * payer_proof<Vec<[u8; 32]>>: The Merkle proof that the signer is an
+  ///   allowed payer. Empty if the payable doesn't restrict its payers.
+  /// * reference<String>: The host's reference for the payment.
+  /// * expiry<u64>: The timestamp after which the intent can't be paid.
+  /// * nonce<u64>: The intent's nonce, which can only be paid once.
+  #[inline(never)]
+  pub fn pay_with_intent(
+    ctx: Context<PayWithIntent>,
+    amount: u64,
+    payer_proof: Vec<[u8; 32]>,
+    reference: String,
+    expiry: u64,
+    nonce: u64,
+  ) -> Result<()> {
+    handlers::pay_with_intent(
+      ctx,
+      amount,
+      payer_proof,
+      reference,
+      expiry,
+      nonce,
+    )
+  }
+
+  /// Transfers tokens of one mint to many payables at once. Each payment's
+  /// accounts are passed as remaining accounts, in the order given in
+  /// PayBatch. The whole batch fails if any of its payments is invalid.
+  ///
+  /// ### args
+  /// * payments<Vec<BatchPayment>>: The amounts and payer proofs of the
+  ///   payments.
+  #[inline(never)]
+  pub fn pay_batch<'info>(
+    ctx: Context<'_, '_, 'info, 'info, PayBatch<'info>>,
+    payments: Vec<BatchPayment>,
+  ) -> Result<()> {
+    handlers::pay_batch(ctx, payments)
+  }
+
+  /// Subscribes the signer to recurring payments into a payable. The signer
+  /// must approve the ChainStats account as delegate on their token account.
+  ///
+  /// ### args
+  /// * amount<u64>: The amount of each payment.
+  /// * period<u64>: The seconds between payments.
+  /// * first_due<Option<u64>>: The timestamp from which the first payment can
+  ///   be collected. Defaults to now and can't be in the past.
+  /// * payer_proof<Vec<[u8; 32]>>: The Merkle proof that the signer is an
+  ///   allowed payer. Empty if the payable doesn't restrict its payers.
+  #[inline(never)]
+  pub fn create_subscription(
+    ctx: Context<CreateSubscription>,
+    amount: u64,
+    period: u64,
+    first_due: Option<u64>,
+    payer_proof: Vec<[u8; 32]>,
+  ) -> Result<()> {
+    handlers::create_subscription(ctx, amount, period, first_due, payer_proof)
+  }
+
+  /// Collects a due subscription payment from the payer into the payable.
+  /// Can be called by anyone.
+  #[inline(never)]
+  pub fn collect_subscription(ctx: Context<CollectSubscription>) -> Result<()> {
+    handlers::collect_subscription(ctx)
+  }
+
+  /// Cancels a subscription. Can be called by the payer, or by the payable's
+  /// host or its operators with the manage role.
   #[inline(never)]
-  pub fn pay_native(ctx: Context<PayNative>, amount: u64) -> Result<()> {
-    handlers::pay_native(ctx, amount)
+  pub fn cancel_subscription(ctx: Context<CancelSubscription>) -> Result<()> {
+    handlers::cancel_subscription(ctx)
   }
 
-  /// Transfers the amount of tokens from a payable to a host
+  /// Allows a payable's host (or its operators with the manage role) to hold
+  /// new payments in escrow until they are released.
+  ///
+  /// ### args
+  /// * timeout<u64>: The seconds after a payment from which anyone can
+  ///   release it. Zero stops escrowing new payments.
+  /// * arbiter<Option<Pubkey>>: The wallet that settles disputes.
+  #[inline(never)]
+  pub fn update_payable_escrow(
+    ctx: Context<UpdatePayableEscrow>,
+    timeout: u64,
+    arbiter: Option<Pubkey>,
+  ) -> Result<()> {
+    handlers::update_payable_escrow(ctx, timeout, arbiter)
+  }
+
+  /// Adds an escrowed payment to its payable's balances. Can be called by
+  /// the payer or the arbiter, or by anyone after the timeout.
+  #[inline(never)]
+  pub fn release_escrow(ctx: Context<ReleaseEscrow>) -> Result<()> {
+    handlers::release_escrow(ctx)
+  }
+
+  /// Allows a payer to dispute an escrowed payment before its timeout.
+  #[inline(never)]
+  pub fn dispute_escrow(ctx: Context<DisputeEscrow>) -> Result<()> {
+    handlers::dispute_escrow(ctx)
+  }
+
+  /// Sends an escrowed payment in tokens back to its payer. Can be called by
+  /// the arbiter or the payable's host.
+  #[inline(never)]
+  pub fn refund_escrow(ctx: Context<RefundEscrow>) -> Result<()> {
+    handlers::refund_escrow(ctx)
+  }
+
+  /// Sends an escrowed payment in native tokens (Solana) back to its payer.
+  /// Can be called by the arbiter or the payable's host.
+  #[inline(never)]
+  pub fn refund_escrow_native(ctx: Context<RefundEscrowNative>) -> Result<()> {
+    handlers::refund_escrow_native(ctx)
+  }
+
+  /// Allows a payable's host (or its operators with the manage role) to have
+  /// receipt NFTs minted to the payers of payments into the payable.
+  ///
+  /// ### args
+  /// * enabled<bool>: Whether payments mint receipts.
+  #[inline(never)]
+  pub fn update_payable_receipts(
+    ctx: Context<UpdatePayableReceipts>,
+    enabled: bool,
+  ) -> Result<()> {
+    handlers::update_payable_receipts(ctx, enabled)
+  }
+
+  /// Transfers the amount of tokens from a payable to a host. Can be called
+  /// by the host or by its operators with the withdraw role.
   ///
   /// ### args
   /// * amount<u64>: The amount to be withdrawn
@@ -86,7 +270,8 @@ pub mod chainbills {
     handlers::withdraw(ctx, amount)
   }
 
-  /// Transfers the amount of native tokens (Solana) from a payable to a host
+  /// Transfers the amount of native tokens (Solana) from a payable to a host. Can be called
+  /// by the host or by its operators with the withdraw role.
   ///
   /// ### args
   /// * amount<u64>: The amount to be withdrawn
@@ -98,21 +283,140 @@ pub mod chainbills {
     handlers::withdraw_native(ctx, amount)
   }
 
+  /// Burns the amount of USDC (minus fees) from a payable through CCTP for
+  /// the host to receive on another chain.
+  ///
+  /// ### args
+  /// * amount<u64>: The amount to be withdrawn
+  /// * dest_chain_id<[u8; 32]>: The cbChainId of the destination chain.
+  /// * dest_address<[u8; 32]>: The host's wallet on the destination chain.
+  #[inline(never)]
+  pub fn withdraw_cross_chain(
+    ctx: Context<WithdrawCrossChain>,
+    amount: u64,
+    dest_chain_id: [u8; 32],
+    dest_address: [u8; 32],
+  ) -> Result<()> {
+    handlers::withdraw_cross_chain(ctx, amount, dest_chain_id, dest_address)
+  }
+
   /// Stop a payable from accepting payments. Can be called only
-  /// by the host (user) that owns the payable.
+  /// by the host (user) that owns the payable or by its operators with the
+  /// manage role.
   #[inline(never)]
   pub fn close_payable(ctx: Context<UpdatePayable>) -> Result<()> {
     handlers::close_payable(ctx)
   }
 
   /// Allow a closed payable to continue accepting payments.
-  /// Can be called only by the host (user) that owns the payable.
+  /// Can be called only by the host (user) that owns the payable or by its
+  /// operators with the manage role.
   #[inline(never)]
   pub fn reopen_payable(ctx: Context<UpdatePayable>) -> Result<()> {
     handlers::reopen_payable(ctx)
   }
 
-  /// Allows a payable's host to update the payable's allowed_tokens_and_amounts.
+  /// Offer a payable to another wallet. Can be called only by the host
+  /// (user) that owns the payable. Calling again replaces the offered wallet.
+  ///
+  /// ### args
+  /// * new_host<Pubkey>: The wallet that can accept the payable.
+  #[inline(never)]
+  pub fn transfer_payable(
+    ctx: Context<TransferPayable>,
+    new_host: Pubkey,
+  ) -> Result<()> {
+    handlers::transfer_payable(ctx, new_host)
+  }
+
+  /// Add an operator to a payable or change the operator's roles. Can be
+  /// called only by the host (user) that owns the payable.
+  ///
+  /// ### args
+  /// * operator<Pubkey>: The operator's wallet.
+  /// * roles<u8>: Bit flags of the roles to grant. 1 is to manage the
+  ///   payable's settings and 2 is to withdraw to the host's wallet.
+  #[inline(never)]
+  pub fn set_payable_operator(
+    ctx: Context<SetPayableOperator>,
+    operator: Pubkey,
+    roles: u8,
+  ) -> Result<()> {
+    handlers::set_payable_operator(ctx, operator, roles)
+  }
+
+  /// Remove an operator from a payable. Can be called only by the host
+  /// (user) that owns the payable.
+  #[inline(never)]
+  pub fn remove_payable_operator(
+    ctx: Context<RemovePayableOperator>,
+  ) -> Result<()> {
+    handlers::remove_payable_operator(ctx)
+  }
+
+  /// Become the host of a payable that was offered to the signer. The
+  /// payable keeps its ID.
+  #[inline(never)]
+  pub fn accept_payable(ctx: Context<AcceptPayable>) -> Result<()> {
+    handlers::accept_payable(ctx)
+  }
+
+  /// Close the account of a closed payable that has no balances left, along
+  /// with its escrow, allowed payers, receipts and pending host accounts.
+  /// Can be called only by the host (user) that owns the payable. The
+  /// payable's operators to close are passed as remaining accounts. The rent
+  /// goes back to the host and the payable's data is emitted as an event.
+  #[inline(never)]
+  pub fn archive_payable<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ArchivePayable<'info>>,
+  ) -> Result<()> {
+    handlers::archive_payable(ctx)
+  }
+
+  /// Close a payable's payment receipt and the chain's (if any) and the
+  /// payable's references to it (or clear them from their ledger pages). Can be called
+  /// only by the host. The rent goes back to whoever paid it and the
+  /// receipt's data is emitted as an event.
+  #[inline(never)]
+  pub fn archive_payable_payment(
+    ctx: Context<ArchivePayablePayment>,
+  ) -> Result<()> {
+    handlers::archive_payable_payment(ctx)
+  }
+
+  /// Close a payment receipt and its chain-level reference if any (or clear
+  /// the reference from its ledger page). Can be called only by the payer. The
+  /// rent goes back to the payer and the receipt's data is emitted as an
+  /// event.
+  #[inline(never)]
+  pub fn archive_user_payment(ctx: Context<ArchiveUserPayment>) -> Result<()> {
+    handlers::archive_user_payment(ctx)
+  }
+
+  /// Close a withdrawal receipt and its chain-level reference. Can be called
+  /// only by the host. The rent goes back to the host and the receipt's data
+  /// is emitted as an event.
+  #[inline(never)]
+  pub fn archive_withdrawal(ctx: Context<ArchiveWithdrawal>) -> Result<()> {
+    handlers::archive_withdrawal(ctx)
+  }
+
+  /// Close one of the signer's activity records and its info account if any
+  /// (or clear its entry from its ledger page). The rent goes back to the signer
+  /// and the record's data is emitted as an event.
+  ///
+  /// ### args
+  /// * user_count<u64>: The nth count of the user's activities to archive.
+  #[inline(never)]
+  pub fn archive_user_activity(
+    ctx: Context<ArchiveUserActivity>,
+    user_count: u64,
+  ) -> Result<()> {
+    handlers::archive_user_activity(ctx, user_count)
+  }
+
+  /// Allows a payable's host (or its operators with the manage role) to
+  /// update the payable's allowed_tokens_and_amounts.
   ///
   /// ### args
   /// * allowed_tokens_and_amounts: the new set of tokens and amounts that the payable
@@ -128,6 +432,26 @@ pub mod chainbills {
     )
   }
 
+  /// Allows a payable's host (or its operators with the manage role) to
+  /// restrict who can pay the payable.
+  ///
+  /// ### args
+  /// * allowed_payers<Vec<[u8; 32]>>: The addresses of the allowed payers.
+  /// * allowed_payers_root<[u8; 32]>: The Merkle root of the allowed payers
+  ///   if the list is too long to be given. Zero if allowed_payers is given.
+  #[inline(never)]
+  pub fn update_payable_allowed_payers(
+    ctx: Context<UpdatePayableAllowedPayers>,
+    allowed_payers: Vec<[u8; 32]>,
+    allowed_payers_root: [u8; 32],
+  ) -> Result<()> {
+    handlers::update_payable_allowed_payers(
+      ctx,
+      allowed_payers,
+      allowed_payers_root,
+    )
+  }
+
   /// Record a foreign payable update.
   ///
   /// ### args
@@ -195,6 +519,25 @@ pub mod chainbills {
     handlers::update_max_withdrawal_fees_native(ctx, max_withdrawal_fees)
   }
 
+  /// Creates or updates a token group, whose members payables can accept
+  /// interchangeably when an allowed token is the group's address. Should be
+  /// called only by the owner.
+  ///
+  /// ### Args
+  /// * name<String>: The group's name, which seeds its address.
+  /// * decimals<u8>: The decimals of amounts that reference the group.
+  /// * members<Vec<TokenGroupMember>>: The group's tokens with their
+  ///   decimals.
+  #[inline(never)]
+  pub fn update_token_group(
+    ctx: Context<UpdateTokenGroup>,
+    name: String,
+    decimals: u8,
+    members: Vec<TokenGroupMember>,
+  ) -> Result<()> {
+    handlers::update_token_group(ctx, name, decimals, members)
+  }
+
   /// Withdraws fees from this program.
   /// Should be called only by upgrade authority holder of this program.
   ///
@@ -208,6 +551,152 @@ pub mod chainbills {
     handlers::owner_withdraw_handler(ctx, amount)
   }
 
+  /// Upgrade a Payable that predates versioned layouts in place.
+  /// Can be called only by the host (user) that owns the payable.
+  #[inline(never)]
+  pub fn migrate_payable(ctx: Context<MigratePayable>) -> Result<()> {
+    handlers::migrate_payable(ctx)
+  }
+
+  /// Upgrade the signer's User that predates versioned layouts in place.
+  #[inline(never)]
+  pub fn migrate_user(ctx: Context<MigrateUser>) -> Result<()> {
+    handlers::migrate_user(ctx)
+  }
+
+  /// Upgrade one of the signer's UserPayments that predates cbChainIds or
+  /// recorded rent payers in place.
+  #[inline(never)]
+  pub fn migrate_user_payment(ctx: Context<MigrateUserPayment>) -> Result<()> {
+    handlers::migrate_user_payment(ctx)
+  }
+
+  /// Upgrade a PayablePayment that predates cbChainIds in place. Can be
+  /// called by anyone.
+  #[inline(never)]
+  pub fn migrate_payable_payment(
+    ctx: Context<MigratePayablePayment>,
+  ) -> Result<()> {
+    handlers::migrate_payable_payment(ctx)
+  }
+
+  /// Upgrade one of the signer's Withdrawals that predates recorded
+  /// destinations or rent payers in place.
+  #[inline(never)]
+  pub fn migrate_withdrawal(ctx: Context<MigrateWithdrawal>) -> Result<()> {
+    handlers::migrate_withdrawal(ctx)
+  }
+
+  /// Upgrade one of the signer's ActivityRecords that predates recorded rent
+  /// payers in place.
+  ///
+  /// ### args
+  /// * user_count<u64>: The nth count of the user's activities to upgrade.
+  #[inline(never)]
+  pub fn migrate_activity_record(
+    ctx: Context<MigrateActivityRecord>,
+    user_count: u64,
+  ) -> Result<()> {
+    handlers::migrate_activity_record(ctx, user_count)
+  }
+
+  /// Upgrade the Config that predates versioned layouts in place.
+  /// Should be called only by the owner, and before the other owner
+  /// migrations.
+  #[inline(never)]
+  pub fn migrate_config(ctx: Context<MigrateConfig>) -> Result<()> {
+    handlers::migrate_config(ctx)
+  }
+
+  /// Upgrade Solana's ChainStats that predates versioned layouts in place.
+  /// Should be called only by the owner.
+  #[inline(never)]
+  pub fn migrate_chain_stats(ctx: Context<MigrateChainStats>) -> Result<()> {
+    handlers::migrate_chain_stats(ctx)
+  }
+
+  /// Upgrade a TokenDetails that predates versioned layouts in place.
+  /// Should be called only by the owner.
+  ///
+  /// ### args
+  /// * token<Pubkey>: The token mint (or this program's ID for native SOL).
+  #[inline(never)]
+  pub fn migrate_token_details(
+    ctx: Context<MigrateTokenDetails>,
+    token: Pubkey,
+  ) -> Result<()> {
+    handlers::migrate_token_details(ctx, token)
+  }
+
+  /// Move the native SOL that was paid into ChainStats before the native
+  /// vault existed into the native vault. Should be called only by the owner.
+  #[inline(never)]
+  pub fn migrate_native_vault(ctx: Context<MigrateNativeVault>) -> Result<()> {
+    handlers::migrate_native_vault(ctx)
+  }
+
+  /// View that compares what this program holds of the token against the
+  /// token's balances in the payables and its held payments in the escrow
+  /// states passed as remaining accounts, and against the token's totals,
+  /// including those of the token's TokenDetailsShards passed among them.
+  /// Accounts can be passed in pages and the sums added by the caller.
+  ///
+  /// ### Args
+  /// * token<Pubkey>: The token's mint, or this program's ID for native SOL.
+  #[inline(never)]
+  pub fn check_token_integrity<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CheckTokenIntegrity<'info>>,
+    token: Pubkey,
+  ) -> Result<TokenIntegrity> {
+    handlers::check_token_integrity(ctx, token)
+  }
+
+  /// Register a blockchain network by its CAIP-2 string. The network's
+  /// cbChainId (keccak256 of the string) keys its CbChain account.
+  /// Should be called only by the owner.
+  ///
+  /// ### args
+  /// * caip2<String>: The CAIP-2 chain identifier (`namespace:reference`).
+  #[inline(never)]
+  pub fn register_cb_chain(
+    ctx: Context<RegisterCbChain>,
+    caip2: String,
+  ) -> Result<()> {
+    handlers::register_cb_chain(ctx, caip2)
+  }
+
+  /// Register the Wormhole Chain ID of a registered network. Also initializes
+  /// the reverse lookup from the Wormhole Chain ID to the cbChainId.
+  /// Should be called only by the owner.
+  ///
+  /// ### args
+  /// * cb_chain_id<[u8; 32]>: The network's cbChainId.
+  /// * wormhole_chain_id<u16>: The network's Wormhole Chain ID.
+  #[inline(never)]
+  pub fn register_chain_wormhole_id(
+    ctx: Context<RegisterChainWormholeId>,
+    cb_chain_id: [u8; 32],
+    wormhole_chain_id: u16,
+  ) -> Result<()> {
+    handlers::register_chain_wormhole_id(ctx, cb_chain_id, wormhole_chain_id)
+  }
+
+  /// Register the Circle (CCTP) domain of a registered network. Also
+  /// initializes the reverse lookup from the domain to the cbChainId.
+  /// Should be called only by the owner.
+  ///
+  /// ### args
+  /// * cb_chain_id<[u8; 32]>: The network's cbChainId.
+  /// * circle_domain<u32>: The network's Circle domain.
+  #[inline(never)]
+  pub fn register_chain_circle_domain(
+    ctx: Context<RegisterChainCircleDomain>,
+    cb_chain_id: [u8; 32],
+    circle_domain: u32,
+  ) -> Result<()> {
+    handlers::register_chain_circle_domain(ctx, cb_chain_id, circle_domain)
+  }
+
   /// Register (or update) a trusted contract or Wormhole emitter from another
   /// chain. Also initialize that chain's ChainStats if need be.
   ///
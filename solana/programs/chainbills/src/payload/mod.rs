@@ -3,3 +3,15 @@ pub mod payment_payload;
 
 pub use payable_payload::*;
 pub use payment_payload::*;
+
+use chainbills_payload::PayloadError;
+use std::io;
+
+/// Maps errors from the shared payload codec into borsh's io errors.
+fn to_io_error(error: PayloadError) -> io::Error {
+  let kind = match error {
+    PayloadError::UnexpectedEnd => io::ErrorKind::UnexpectedEof,
+    _ => io::ErrorKind::InvalidInput,
+  };
+  io::Error::new(kind, error)
+}
@@ -1,9 +1,14 @@
+use super::to_io_error;
 use crate::state::TokenAndAmountForeign;
 use anchor_lang::prelude::*;
+use chainbills_payload::IoSource;
 use std::io;
 
 #[derive(Clone, Debug)]
 /// Emitted when a payable is created.
+///
+/// Encoded with the shared `chainbills-payload` codec, so that its bytes
+/// match those of the other chains.
 pub struct PayablePayload {
   /// Version of the payload.
   pub version: u8,
@@ -14,88 +19,104 @@ pub struct PayablePayload {
   /// 2 - ClosedPayable
   /// 3 - ReopenedPayable
   /// 4 - UpdatedPayableAllowedTokensAndAmounts
+  /// 5 - UpdatedPayableAllowedPayers
   pub action_type: u8,
 
   /// The Payable's ID.
   pub payable_id: [u8; 32],
 
+  /// Monotonically increasing counter for this payable on the source chain.
+  /// This chain uses the payable's activities_count.
+  pub nonce: u64,
+
   /// Whether the payable is closed or not.
   pub is_closed: bool,
-  
+
   /// The allowed tokens and their amounts.
   pub allowed_tokens_and_amounts: Vec<TokenAndAmountForeign>,
+
+  /// The Merkle root of the payers allowed to pay the payable. All zeros
+  /// means that anyone can pay.
+  pub allowed_payers_root: [u8; 32],
 }
 
-impl AnchorSerialize for PayablePayload {
-  fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
-    self.version.serialize(writer)?;
-    self.action_type.serialize(writer)?;
-    self.payable_id.serialize(writer)?;
-    if self.action_type == 1 || self.action_type == 4 {
-      (self.allowed_tokens_and_amounts.len() as u8).serialize(writer)?;
-      for ataa in &self.allowed_tokens_and_amounts {
-        ataa.serialize(writer)?;
-      }
-    } else if self.action_type == 2 || self.action_type == 3 {
-      self.is_closed.serialize(writer)?;
-    } else {
-      return Err(io::Error::new(
-        io::ErrorKind::InvalidInput,
-        "InvalidPayload",
-      ));
-    }
-    Ok(())
+impl PayablePayload {
+  /// The exact number of bytes of this payload when encoded.
+  pub fn encoded_len(&self) -> io::Result<usize> {
+    chainbills_payload::PayablePayload::from(self)
+      .encoded_len()
+      .map_err(to_io_error)
   }
 }
 
-impl AnchorDeserialize for PayablePayload {
-  fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
-    let mut index = 0usize;
-    let version = u8::deserialize(&mut &buf[index..(index + 1)])?;
-    index += 1;
-
-    let action_type = u8::deserialize(&mut &buf[index..(index + 1)])?;
-    index += 1;
-
-    let payable_id = <[u8; 32]>::deserialize(&mut &buf[index..(index + 32)])?;
-    index += 32;
-
-    let mut is_closed = false;
-    let mut allowed_tokens_and_amounts = vec![];
-
-    if action_type == 1 || action_type == 4 {
-      let ataa_len = u8::deserialize(&mut &buf[index..(index + 1)])?;
-      index += 1;
-      for _ in 0..ataa_len {
-        let ataa = TokenAndAmountForeign::deserialize(&mut &buf[index..])?;
-        allowed_tokens_and_amounts.push(ataa);
-        index += 40; // 32 bytes for token + 8 bytes for amount
-      }
-    } else if action_type == 2 || action_type == 3 {
-      is_closed = bool::deserialize(&mut &buf[index..(index + 1)])?;
-      index += 1;
+impl From<&PayablePayload> for chainbills_payload::PayablePayload {
+  fn from(payload: &PayablePayload) -> Self {
+    Self {
+      version: payload.version,
+      action_type: payload.action_type,
+      payable_id: payload.payable_id,
+      nonce: payload.nonce,
+      is_closed: payload.is_closed,
+      allowed_tokens_and_amounts: payload
+        .allowed_tokens_and_amounts
+        .iter()
+        .map(|ataa| chainbills_payload::TokenAndAmountForeign {
+          token: ataa.token,
+          amount: ataa.amount,
+        })
+        .collect(),
+      allowed_payers_root: payload.allowed_payers_root,
     }
+  }
+}
 
-    if index != buf.len() {
-      return Err(io::Error::new(
-        io::ErrorKind::InvalidInput,
-        "InvalidPayload",
-      ));
+impl From<chainbills_payload::PayablePayload> for PayablePayload {
+  fn from(payload: chainbills_payload::PayablePayload) -> Self {
+    Self {
+      version: payload.version,
+      action_type: payload.action_type,
+      payable_id: payload.payable_id,
+      nonce: payload.nonce,
+      is_closed: payload.is_closed,
+      allowed_tokens_and_amounts: payload
+        .allowed_tokens_and_amounts
+        .into_iter()
+        .map(|ataa| TokenAndAmountForeign {
+          token: ataa.token,
+          amount: ataa.amount,
+        })
+        .collect(),
+      allowed_payers_root: payload.allowed_payers_root,
     }
+  }
+}
+
+impl AnchorSerialize for PayablePayload {
+  fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+    let encoded = chainbills_payload::PayablePayload::from(self)
+      .encode()
+      .map_err(to_io_error)?;
+    writer.write_all(&encoded)
+  }
+}
 
-    Ok(PayablePayload {
-      version,
-      action_type,
-      payable_id,
-      is_closed,
-      allowed_tokens_and_amounts,
-    })
+impl AnchorDeserialize for PayablePayload {
+  /// Decodes a payload that must span the whole of `buf`.
+  fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+    let payload =
+      chainbills_payload::PayablePayload::decode(buf).map_err(to_io_error)?;
+    *buf = &buf[buf.len()..];
+    Ok(payload.into())
   }
 
+  /// Decodes one payload from the reader, reading no more bytes than it
+  /// spans. Used when the payload is nested, as in a PostedVaa.
   fn deserialize_reader<R: io::prelude::Read>(
-    _reader: &mut R,
+    reader: &mut R,
   ) -> io::Result<Self> {
-    todo!()
+    chainbills_payload::PayablePayload::decode_from(&mut IoSource(reader))
+      .map(Into::into)
+      .map_err(to_io_error)
   }
 }
 
@@ -114,8 +135,10 @@ mod tests {
       version: 1,
       action_type: 1,
       payable_id: [2; 32],
+      nonce: 1,
       is_closed: false,
       allowed_tokens_and_amounts: vec![token_and_amount],
+      allowed_payers_root: [0; 32],
     };
 
     let mut buf = Vec::new();
@@ -126,6 +149,7 @@ mod tests {
     assert_eq!(payload.version, deserialized_payload.version);
     assert_eq!(payload.action_type, deserialized_payload.action_type);
     assert_eq!(payload.payable_id, deserialized_payload.payable_id);
+    assert_eq!(payload.nonce, deserialized_payload.nonce);
     assert_eq!(payload.is_closed, deserialized_payload.is_closed);
     assert_eq!(
       payload.allowed_tokens_and_amounts.len(),
@@ -149,4 +173,32 @@ mod tests {
     assert!(result.is_err());
     assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidInput);
   }
+
+  #[test]
+  fn test_matches_golden_vector() {
+    let golden =
+      include_str!("../../../../../payload/vectors/payable_closed.hex");
+    let bytes: Vec<u8> = (0..golden.trim().len())
+      .step_by(2)
+      .map(|i| u8::from_str_radix(&golden[i..i + 2], 16).unwrap())
+      .collect();
+
+    let payload = PayablePayload {
+      version: 1,
+      action_type: 2,
+      payable_id: [0x22; 32],
+      nonce: 2,
+      is_closed: true,
+      allowed_tokens_and_amounts: vec![],
+      allowed_payers_root: [0; 32],
+    };
+    assert_eq!(payload.try_to_vec().unwrap(), bytes);
+
+    // The same bytes are decoded when nested in a reader, as in a PostedVaa.
+    let mut reader = io::Cursor::new(&bytes);
+    let decoded = PayablePayload::deserialize_reader(&mut reader).unwrap();
+    assert_eq!(decoded.nonce, 2);
+    assert!(decoded.is_closed);
+    assert_eq!(reader.position() as usize, bytes.len());
+  }
 }
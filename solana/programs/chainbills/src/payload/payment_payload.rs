@@ -1,10 +1,15 @@
+use super::to_io_error;
 use anchor_lang::prelude::*;
+use chainbills_payload::IoSource;
 use std::io;
 
 #[derive(Clone, Copy, Debug)]
 /// Necessary info to record a payable's payment if the involved blockchain
 /// networks are different. That is the when a user is on a different chain
 /// from the payable.
+///
+/// Encoded with the shared `chainbills-payload` codec, so that its bytes
+/// match those of the other chains.
 pub struct PaymentPayload {
   /// Version of the payload.
   pub version: u8,
@@ -16,8 +21,8 @@ pub struct PaymentPayload {
   /// (destination) chain.
   pub payable_chain_token: [u8; 32],
 
-  /// Wormhole Chain ID of where the Payable was created.
-  pub payable_chain_id: u16,
+  /// CAIP-2 cbChainId of where the Payable was created.
+  pub payable_chain_id: [u8; 32],
 
   /// Who made the payment.
   pub payer: [u8; 32],
@@ -26,103 +31,74 @@ pub struct PaymentPayload {
   /// (source) chain.
   pub payer_chain_token: [u8; 32],
 
-  /// Wormhole Chain ID of where the User made the payment.
-  pub payer_chain_id: u16,
+  /// CAIP-2 cbChainId of where the User made the payment.
+  pub payer_chain_id: [u8; 32],
 
   /// The amount paid on for the transaction.
   pub amount: u64,
-  
+
   /// Circle Nonce of the payment.
   pub circle_nonce: u64,
 }
 
+impl From<&PaymentPayload> for chainbills_payload::PaymentPayload {
+  fn from(payload: &PaymentPayload) -> Self {
+    Self {
+      version: payload.version,
+      payable_id: payload.payable_id,
+      payable_chain_token: payload.payable_chain_token,
+      payable_chain_id: payload.payable_chain_id,
+      payer: payload.payer,
+      payer_chain_token: payload.payer_chain_token,
+      payer_chain_id: payload.payer_chain_id,
+      amount: payload.amount,
+      circle_nonce: payload.circle_nonce,
+    }
+  }
+}
+
+impl From<chainbills_payload::PaymentPayload> for PaymentPayload {
+  fn from(payload: chainbills_payload::PaymentPayload) -> Self {
+    Self {
+      version: payload.version,
+      payable_id: payload.payable_id,
+      payable_chain_token: payload.payable_chain_token,
+      payable_chain_id: payload.payable_chain_id,
+      payer: payload.payer,
+      payer_chain_token: payload.payer_chain_token,
+      payer_chain_id: payload.payer_chain_id,
+      amount: payload.amount,
+      circle_nonce: payload.circle_nonce,
+    }
+  }
+}
+
 impl AnchorSerialize for PaymentPayload {
   fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
-    self.version.serialize(writer)?;
-    self.payable_id.serialize(writer)?;
-    self.payable_chain_token.serialize(writer)?;
-    self.payable_chain_id.to_le_bytes().serialize(writer)?;
-    self.payer.serialize(writer)?;
-    self.payer_chain_token.serialize(writer)?;
-    self.payer_chain_id.to_le_bytes().serialize(writer)?;
-    self.amount.to_le_bytes().serialize(writer)?;
-    self.circle_nonce.to_le_bytes().serialize(writer)?;
-    Ok(())
+    let encoded = chainbills_payload::PaymentPayload::from(self)
+      .encode()
+      .map_err(to_io_error)?;
+    writer.write_all(&encoded)
   }
 }
 
 impl AnchorDeserialize for PaymentPayload {
+  /// Decodes a payload that must span the whole of `buf`.
   fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
-    let mut index = 0usize;
-    let version = u8::deserialize(&mut &buf[index..(index + 1)])?;
-    index += 1;
-
-    let payable_id = <[u8; 32]>::deserialize(&mut &buf[index..(index + 32)])?;
-    index += 32;
-
-    let payable_chain_token =
-      <[u8; 32]>::deserialize(&mut &buf[index..(index + 32)])?;
-    index += 32;
-
-    let payable_chain_id = {
-      let mut out = [0u8; 2];
-      out.copy_from_slice(&buf[index..(index + 2)]);
-      u16::from_le_bytes(out) as u16
-    };
-    index += 2;
-
-    let payer = <[u8; 32]>::deserialize(&mut &buf[index..(index + 32)])?;
-    index += 32;
-
-    let payer_chain_token =
-      <[u8; 32]>::deserialize(&mut &buf[index..(index + 32)])?;
-    index += 32;
-
-    let payer_chain_id = {
-      let mut out = [0u8; 2];
-      out.copy_from_slice(&buf[index..(index + 2)]);
-      u16::from_le_bytes(out) as u16
-    };
-    index += 2;
-
-    let amount = {
-      let mut out = [0u8; 8];
-      out.copy_from_slice(&buf[index..(index + 8)]);
-      u64::from_le_bytes(out) as u64
-    };
-    index += 8;
-
-    let circle_nonce = {
-      let mut out = [0u8; 8];
-      out.copy_from_slice(&buf[index..(index + 8)]);
-      u64::from_le_bytes(out) as u64
-    };
-    index += 8;
-
-    if index != buf.len() {
-      return Err(io::Error::new(
-        io::ErrorKind::InvalidInput,
-        "InvalidPayload",
-      ));
-    }
-
-    Ok(PaymentPayload {
-      version,
-      payable_id,
-      payable_chain_token,
-      payable_chain_id,
-      payer,
-      payer_chain_token,
-      payer_chain_id,
-      amount,
-      circle_nonce,
-    })
+    let payload =
+      chainbills_payload::PaymentPayload::decode(buf).map_err(to_io_error)?;
+    *buf = &buf[buf.len()..];
+    Ok(payload.into())
   }
 
+  /// Decodes one payload from the reader, reading no more bytes than it
+  /// spans. Used when the payload is nested, as in a PostedVaa.
   fn deserialize_reader<R: io::prelude::Read>(
-    _reader: &mut R,
+    reader: &mut R,
   ) -> io::Result<Self> {
-    todo!()
+    chainbills_payload::PaymentPayload::decode_from(&mut IoSource(reader))
+      .map(Into::into)
+      .map_err(to_io_error)
   }
 }
 
@@ -136,10 +112,10 @@ mod tests {
       version: 1,
       payable_id: [1; 32],
       payable_chain_token: [2; 32],
-      payable_chain_id: 100,
+      payable_chain_id: [5; 32],
       payer: [3; 32],
       payer_chain_token: [4; 32],
-      payer_chain_id: 200,
+      payer_chain_id: [6; 32],
       amount: 1000,
       circle_nonce: 123456789,
     };
@@ -176,4 +152,20 @@ mod tests {
     assert!(result.is_err());
     assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidInput);
   }
+
+  #[test]
+  fn test_matches_golden_vector() {
+    let golden = include_str!("../../../../../payload/vectors/payment.hex");
+    let bytes: Vec<u8> = (0..golden.trim().len())
+      .step_by(2)
+      .map(|i| u8::from_str_radix(&golden[i..i + 2], 16).unwrap())
+      .collect();
+
+    let payload = PaymentPayload::deserialize(&mut &bytes[..]).unwrap();
+    assert_eq!(payload.payable_chain_id, [0x55; 32]);
+    assert_eq!(payload.payer_chain_id, [0x88; 32]);
+    assert_eq!(payload.amount, 5_000_000);
+    assert_eq!(payload.circle_nonce, 999);
+    assert_eq!(payload.try_to_vec().unwrap(), bytes);
+  }
 }
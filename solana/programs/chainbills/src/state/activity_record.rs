@@ -26,10 +26,46 @@ pub enum ActivityType {
 
   /// The payable's allowed tokens and amounts were updated.
   UpdatedPayableAllowedTokensAndAmounts,
+
+  /// The payable was transferred away from its previous host.
+  TransferredPayable,
+
+  /// The payable was accepted by its new host.
+  AcceptedPayable,
+
+  /// The payers allowed to pay the payable were updated.
+  UpdatedPayableAllowedPayers,
+
+  /// A user subscribed to recurring payments into the payable.
+  CreatedSubscription,
+
+  /// A subscription to the payable was cancelled.
+  CancelledSubscription,
+
+  /// The payable's escrow settings were updated.
+  UpdatedPayableEscrow,
+
+  /// An escrowed payment was released to the payable.
+  ReleasedEscrow,
+
+  /// An escrowed payment was disputed by its payer.
+  DisputedEscrow,
+
+  /// An escrowed payment was refunded to its payer.
+  RefundedEscrow,
+
+  /// The payable's receipt settings were updated.
+  UpdatedPayableReceipts,
 }
 
 #[account]
 /// A record of an activity.
+///
+/// The activities of payments are at the seeds of their owners: the seed
+/// prefix, the payer's wallet or the payable, then the activity's count in
+/// it. This way concurrent payments don't derive their records from the same
+/// chain count. The other activities are at the seed prefix followed by their
+/// chain count.
 pub struct ActivityRecord {
   /// The nth count of activities on this chain at the point this activity
   /// was recorded.
@@ -52,11 +88,15 @@ pub struct ActivityRecord {
 
   /// The type of activity.
   pub activity_type: ActivityType, // 1 byte
+
+  /// The wallet that paid the rent of this record and of its infos. Gets it
+  /// back when they are archived.
+  pub rent_payer: Pubkey, // 32 bytes
 }
 
 impl ActivityRecord {
   // discriminator (8) included
-  pub const SPACE: usize = 1 + (5 * 8) + 32;
+  pub const SPACE: usize = 1 + (5 * 8) + (2 * 32);
 
   /// AKA `b"activity"`.
   pub const SEED_PREFIX: &'static [u8] = b"activity";
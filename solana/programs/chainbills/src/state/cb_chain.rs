@@ -0,0 +1,129 @@
+use anchor_lang::{prelude::*, solana_program::keccak};
+
+/// CAIP-2 identifier of the Solana cluster this program is built for.
+#[cfg(feature = "mainnet")]
+pub const CAIP2_SOLANA: &str = "solana:5eykt4UsFv8P8NJdTREpY1vzqKqZKvdp";
+
+/// CAIP-2 identifier of the Solana cluster this program is built for.
+#[cfg(not(feature = "mainnet"))]
+pub const CAIP2_SOLANA: &str = "solana:EtWTRABZaYq6iMfeYKouRu166VU2xqa1";
+
+/// Computes the cbChainId of a CAIP-2 chain identifier. That is
+/// keccak256 of the `namespace:reference` string, as on the EVM contracts.
+pub fn cb_chain_id(caip2: &str) -> [u8; 32] {
+  keccak::hash(caip2.as_bytes()).to_bytes()
+}
+
+/// The cbChainId of the Solana cluster this program is built for.
+pub fn solana_cb_chain_id() -> [u8; 32] {
+  cb_chain_id(CAIP2_SOLANA)
+}
+
+#[account]
+/// Registry entry of a blockchain network, keyed by its CAIP-2 cbChainId.
+/// Maps the cbChainId to the network's IDs on the bridging protocols.
+pub struct CbChain {
+  /// keccak256 of the CAIP-2 string.
+  pub cb_chain_id: [u8; 32], // 32 bytes
+
+  /// The CAIP-2 string (`namespace:reference`).
+  pub caip2: String, // 4 + MAX_CAIP2_LEN bytes
+
+  /// The Wormhole Chain ID of the network. Zero if not registered.
+  pub wormhole_chain_id: u16, // 2 bytes
+
+  /// The Circle (CCTP) domain of the network. Only valid if
+  /// has_circle_domain is true, as zero is a valid domain.
+  pub circle_domain: u32, // 4 bytes
+
+  /// Whether circle_domain has been registered.
+  pub has_circle_domain: bool, // 1 byte
+}
+
+impl CbChain {
+  /// The longest valid CAIP-2 string: namespace (8) + ":" + reference (32).
+  pub const MAX_CAIP2_LEN: usize = 8 + 1 + 32;
+
+  // discriminator (8) included
+  pub const SPACE: usize = 8 + 32 + (4 + Self::MAX_CAIP2_LEN) + 2 + 4 + 1;
+
+  /// AKA `b"cb_chain"`.
+  pub const SEED_PREFIX: &'static [u8] = b"cb_chain";
+
+  /// Whether the string is a CAIP-2 chain identifier. That is a namespace of
+  /// 3 to 8 `[-a-z0-9]` characters, a colon, and a reference of 1 to 32
+  /// `[-_a-zA-Z0-9]` characters.
+  pub fn is_valid_caip2(caip2: &str) -> bool {
+    let Some((namespace, reference)) = caip2.split_once(':') else {
+      return false;
+    };
+    (3..=8).contains(&namespace.len())
+      && namespace
+        .bytes()
+        .all(|b| b == b'-' || b.is_ascii_lowercase() || b.is_ascii_digit())
+      && (1..=32).contains(&reference.len())
+      && reference
+        .bytes()
+        .all(|b| b == b'-' || b == b'_' || b.is_ascii_alphanumeric())
+  }
+}
+
+#[account]
+/// Reverse lookup from a Wormhole Chain ID to its cbChainId.
+pub struct WormholeChainCbChainId {
+  pub cb_chain_id: [u8; 32], // 32 bytes
+}
+
+impl WormholeChainCbChainId {
+  // discriminator (8) included
+  pub const SPACE: usize = 8 + 32;
+
+  /// AKA `b"wormhole_chain_cb_chain_id"`.
+  pub const SEED_PREFIX: &'static [u8] = b"wormhole_chain_cb_chain_id";
+}
+
+#[account]
+/// Reverse lookup from a Circle (CCTP) domain to its cbChainId.
+pub struct CircleDomainCbChainId {
+  pub cb_chain_id: [u8; 32], // 32 bytes
+}
+
+impl CircleDomainCbChainId {
+  // discriminator (8) included
+  pub const SPACE: usize = 8 + 32;
+
+  /// AKA `b"circle_domain_cb_chain_id"`.
+  pub const SEED_PREFIX: &'static [u8] = b"circle_domain_cb_chain_id";
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_cb_chain_id_matches_evm() {
+    // Ethereum Sepolia's cbChainId, as listed in evm/DEPLOYED.md.
+    assert_eq!(
+      cb_chain_id("eip155:11155111"),
+      [
+        0xaf, 0xa9, 0x0c, 0x31, 0x7d, 0xea, 0xcd, 0x3d, 0x68, 0xf3, 0x30, 0xa3,
+        0x0f, 0x96, 0xe4, 0xfa, 0x77, 0x36, 0xe3, 0x5e, 0x8d, 0x14, 0x26, 0xb2,
+        0xe1, 0xb2, 0xc0, 0x4b, 0xce, 0x1c, 0x2f, 0xb7,
+      ]
+    );
+  }
+
+  #[test]
+  fn test_is_valid_caip2() {
+    assert!(CbChain::is_valid_caip2("eip155:11155111"));
+    assert!(CbChain::is_valid_caip2(CAIP2_SOLANA));
+    assert!(CbChain::is_valid_caip2("cosmos:osmosis-1"));
+    assert!(!CbChain::is_valid_caip2("eip155"));
+    assert!(!CbChain::is_valid_caip2("ep:1"));
+    assert!(!CbChain::is_valid_caip2("EIP155:1"));
+    assert!(!CbChain::is_valid_caip2("eip155:"));
+    assert!(!CbChain::is_valid_caip2(
+      "solana:5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpX"
+    ));
+  }
+}
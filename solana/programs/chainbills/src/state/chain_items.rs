@@ -110,6 +110,6 @@ impl ChainConsumedWormholeMessageId {
   // discriminator (8) included
   pub const SPACE: usize = 8 + 2 + 8;
 
-  /// AKA `b"chain_consumed_wormhole_message_id"`.
-  pub const SEED_PREFIX: &'static [u8] = b"chain_consumed_wormhole_message_id";
+  /// AKA `b"chain_consumed_wormhole_msg_id"`. Seeds are capped at 32 bytes.
+  pub const SEED_PREFIX: &'static [u8] = b"chain_consumed_wormhole_msg_id";
 }
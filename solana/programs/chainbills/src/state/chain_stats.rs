@@ -2,26 +2,34 @@ use anchor_lang::prelude::*;
 
 #[account]
 /// Keeps track of all activities on this chain.
+///
+/// Users, payables, payments, withdrawals, and activities are now indexed in
+/// [`ChainStatsShard`](crate::state::ChainStatsShard)s, so their counts here
+/// only cover those indexed before sharding.
 pub struct ChainStats {
-  /// Total number of users that have ever been initialized on this chain.
+  /// The layout version of this account.
+  pub version: u8, // 1 byte
+
+  /// Total number of users initialized on this chain before sharding.
   pub users_count: u64, // 8 bytes
 
-  /// Total number of payables that have ever been created on this chain.
+  /// Total number of payables created on this chain before sharding.
   pub payables_count: u64, // 8 bytes
 
   /// Total number of foreign payables recorded on this chain.
   pub foreign_payables_count: u64, // 8 bytes
 
-  /// Total number of payments that users have ever been made on this chain.
+  /// Total number of payments that users made on this chain before sharding.
   pub user_payments_count: u64, // 8 bytes
 
-  /// Total number of payments that payables have ever received on this chain.
+  /// Total number of payments that payables received on this chain before
+  /// sharding.
   pub payable_payments_count: u64, // 8 bytes
 
-  /// Total number of withdrawals that have ever been made on this chain.
+  /// Total number of withdrawals made on this chain before sharding.
   pub withdrawals_count: u64, // 8 bytes
 
-  /// Total number of activities that have ever been made on this chain.
+  /// Total number of activities on this chain before sharding.
   pub activities_count: u64, // 8 bytes
 
   /// Total number of published Wormhole messages on this chain.
@@ -33,12 +41,16 @@ pub struct ChainStats {
 
 impl ChainStats {
   // discriminator included
-  pub const SPACE: usize = 10 * 8;
+  pub const SPACE: usize = 1 + 10 * 8;
+
+  /// The current layout version.
+  pub const VERSION: u8 = 1;
 
   /// AKA `b"chain"`.
   pub const SEED_PREFIX: &'static [u8] = b"chain";
 
   pub fn initialize(&mut self) {
+    self.version = Self::VERSION;
     self.users_count = 0;
     self.payables_count = 0;
     self.user_payments_count = 0;
@@ -0,0 +1,94 @@
+use anchor_lang::prelude::*;
+
+#[account]
+/// One of the shards of this chain's counts of users, payables, payments,
+/// withdrawals, and activities.
+///
+/// Instructions index what they create in whichever shard the transaction
+/// passes, so that transactions on unrelated payables don't all write to (and
+/// get serialized on) [`ChainStats`](crate::state::ChainStats).
+///
+/// Each count starts at the shard's [`base`](ChainStatsShard::base) and holds
+/// the chain count of the last entity indexed in the shard. Chain counts of a
+/// shard are therefore distinct from those of other shards and from those
+/// indexed in ChainStats before sharding (which are all below 2^56), and the
+/// chain-level PDAs keep being derived from them as before. Readers enumerate
+/// an entity's chain counts as `1..=chain_stats.x_count` followed, for each
+/// shard, by `shard.base() + 1..=shard.x_count`.
+pub struct ChainStatsShard {
+  /// The index of this shard, below [`ChainStatsShard::COUNT`].
+  pub index: u8, // 1 byte
+
+  /// Chain count of the last user indexed in this shard.
+  pub users_count: u64, // 8 bytes
+
+  /// Chain count of the last payable indexed in this shard.
+  pub payables_count: u64, // 8 bytes
+
+  /// Chain count of the last user payment indexed in this shard.
+  pub user_payments_count: u64, // 8 bytes
+
+  /// Chain count of the last payable payment indexed in this shard.
+  pub payable_payments_count: u64, // 8 bytes
+
+  /// Chain count of the last withdrawal indexed in this shard.
+  pub withdrawals_count: u64, // 8 bytes
+
+  /// Chain count of the last activity indexed in this shard.
+  pub activities_count: u64, // 8 bytes
+}
+
+impl ChainStatsShard {
+  // discriminator (8) included
+  pub const SPACE: usize = 8 + 1 + 6 * 8;
+
+  /// The number of shards.
+  pub const COUNT: u8 = 16;
+
+  /// AKA `b"chain_shard"`.
+  pub const SEED_PREFIX: &'static [u8] = b"chain_shard";
+
+  /// The chain count before the first of each entity in the shard.
+  pub fn base_of(index: u8) -> u64 {
+    (index as u64 + 1) << 56
+  }
+
+  pub fn base(&self) -> u64 {
+    Self::base_of(self.index)
+  }
+
+  pub fn initialize(&mut self, index: u8) {
+    let base = Self::base_of(index);
+    self.index = index;
+    self.users_count = base;
+    self.payables_count = base;
+    self.user_payments_count = base;
+    self.payable_payments_count = base;
+    self.withdrawals_count = base;
+    self.activities_count = base;
+  }
+
+  pub fn next_user(&self) -> u64 {
+    self.users_count.checked_add(1).unwrap()
+  }
+
+  pub fn next_payable(&self) -> u64 {
+    self.payables_count.checked_add(1).unwrap()
+  }
+
+  pub fn next_user_payment(&self) -> u64 {
+    self.user_payments_count.checked_add(1).unwrap()
+  }
+
+  pub fn next_payable_payment(&self) -> u64 {
+    self.payable_payments_count.checked_add(1).unwrap()
+  }
+
+  pub fn next_withdrawal(&self) -> u64 {
+    self.withdrawals_count.checked_add(1).unwrap()
+  }
+
+  pub fn next_activity(&self) -> u64 {
+    self.activities_count.checked_add(1).unwrap()
+  }
+}
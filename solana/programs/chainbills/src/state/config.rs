@@ -3,6 +3,12 @@ use anchor_lang::prelude::*;
 #[account(zero_copy)]
 /// Config account data. Mainly Wormhole-related addresses and infos.
 pub struct Config {
+  /// The layout version of this account.
+  pub version: u8, // 1 byte
+
+  /// Unused. Keeps the zero-copy layout free of implicit padding.
+  pub reserved: u8, // 1 byte
+
   /// Wormhole-Chain ID for this chain.
   pub chain_id: u16, // 2 bytes
 
@@ -35,7 +41,10 @@ pub struct Config {
 
 impl Config {
   // discriminator (8) included
-  pub const SPACE: usize = 2 + 2 + 8 + (6 * 32);
+  pub const SPACE: usize = 1 + 1 + 2 + 2 + 8 + (6 * 32);
+
+  /// The current layout version.
+  pub const VERSION: u8 = 1;
 
   /// AKA `b"config"`.
   pub const SEED_PREFIX: &'static [u8] = b"config";
@@ -0,0 +1,21 @@
+use anchor_lang::prelude::*;
+
+#[account]
+/// Holds data for every paid payment intent. Its seeds include the intent's
+/// nonce, so each intent can only be paid once.
+pub struct ConsumedPaymentIntent {
+  /// The PayablePayment that paid the intent.
+  pub payable_payment: Pubkey, // 32 bytes
+
+  /// The host's reference for the payment, as signed in the intent.
+  pub reference: String, // 4 + 64 bytes
+}
+
+impl ConsumedPaymentIntent {
+  // discriminator (8) included
+  pub const SPACE: usize =
+    8 + 32 + 4 + chainbills_payload::PaymentIntent::MAX_REFERENCE_LEN;
+
+  /// AKA `b"consumed_payment_intent"`.
+  pub const SEED_PREFIX: &'static [u8] = b"consumed_payment_intent";
+}
@@ -27,9 +27,8 @@ impl PerChainConsumedWormholeMessagesCounter {
   // discriminator first
   pub const SPACE: usize = 8 + 8;
 
-  /// AKA `b"per_chain_consumed_wormhole_messages_counter"`.
-  pub const SEED_PREFIX: &'static [u8] =
-    b"per_chain_consumed_wormhole_messages_counter";
+  /// AKA `b"per_chain_consumed_wormhole_msgs"`. Seeds are capped at 32 bytes.
+  pub const SEED_PREFIX: &'static [u8] = b"per_chain_consumed_wormhole_msgs";
 
   pub fn next_consumed_messages_count(&self) -> u64 {
     self.consumed_messages_count.checked_add(1).unwrap()
@@ -0,0 +1,137 @@
+use crate::state::TokenAndAmount;
+use anchor_lang::prelude::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+/// Stages of an escrowed payment.
+pub enum EscrowStatus {
+  /// The payment is held until it is released or refunded.
+  Locked,
+
+  /// The payer disputed the payment. Only the payer or the arbiter can
+  /// settle it now.
+  Disputed,
+
+  /// The payment was added to the payable's balances.
+  Released,
+
+  /// The payment was sent back to the payer.
+  Refunded,
+}
+
+#[account]
+/// A payment held in escrow for a payable. Its address is derived from the
+/// payment's PayablePayment account.
+pub struct EscrowState {
+  /// The payable into which the payment was made.
+  pub payable_id: Pubkey, // 32 bytes
+
+  /// The wallet that made the payment and receives any refund.
+  pub payer: Pubkey, // 32 bytes
+
+  /// The token and amount held.
+  pub details: TokenAndAmount, // 40 bytes
+
+  /// The timestamp from which anyone can release the payment.
+  pub release_after: u64, // 8 bytes
+
+  /// The wallet that settles a dispute, as set on the payable at payment.
+  /// The default public key if the payment can't be disputed.
+  pub arbiter: Pubkey, // 32 bytes
+
+  /// The current stage of the payment.
+  pub status: EscrowStatus, // 1 byte
+}
+
+impl EscrowState {
+  // discriminator (8) included
+  pub const SPACE: usize = 8 + (3 * 32) + TokenAndAmount::SPACE + 8 + 1;
+
+  /// AKA `b"escrow_state"`.
+  pub const SEED_PREFIX: &'static [u8] = b"escrow_state";
+
+  /// Whether the payment is still held.
+  pub fn is_held(&self) -> bool {
+    matches!(self.status, EscrowStatus::Locked | EscrowStatus::Disputed)
+  }
+
+  /// Whether the payment has an arbiter to settle disputes.
+  pub fn has_arbiter(&self) -> bool {
+    self.arbiter != Pubkey::default()
+  }
+
+  /// Whether the wallet can release the held payment at the given time.
+  /// The payer and the arbiter can do so anytime, and anyone else can once
+  /// the timeout elapsed on an undisputed payment.
+  pub fn can_release(&self, wallet: &Pubkey, now: u64) -> bool {
+    self.is_held()
+      && (*wallet == self.payer
+        || (self.has_arbiter() && *wallet == self.arbiter)
+        || (self.status == EscrowStatus::Locked && now >= self.release_after))
+  }
+
+  /// Whether the payer can dispute the payment at the given time. Only
+  /// undisputed payments with an arbiter can be disputed, before anyone can
+  /// release them.
+  pub fn can_dispute(&self, now: u64) -> bool {
+    self.status == EscrowStatus::Locked
+      && self.has_arbiter()
+      && now < self.release_after
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn escrow_state(status: EscrowStatus, arbiter: Pubkey) -> EscrowState {
+    EscrowState {
+      payable_id: Pubkey::new_unique(),
+      payer: Pubkey::new_unique(),
+      details: TokenAndAmount {
+        token: Pubkey::new_unique(),
+        amount: 10,
+      },
+      release_after: 100,
+      arbiter,
+      status,
+    }
+  }
+
+  #[test]
+  fn test_can_release() {
+    let arbiter = Pubkey::new_unique();
+    let anyone = Pubkey::new_unique();
+    let locked = escrow_state(EscrowStatus::Locked, arbiter);
+    assert!(locked.can_release(&locked.payer, 0));
+    assert!(locked.can_release(&arbiter, 0));
+    assert!(!locked.can_release(&anyone, 99));
+    assert!(locked.can_release(&anyone, 100));
+
+    let disputed = escrow_state(EscrowStatus::Disputed, arbiter);
+    assert!(disputed.can_release(&disputed.payer, 100));
+    assert!(disputed.can_release(&arbiter, 100));
+    assert!(!disputed.can_release(&anyone, 100));
+
+    for status in [EscrowStatus::Released, EscrowStatus::Refunded] {
+      let settled = escrow_state(status, arbiter);
+      assert!(!settled.is_held());
+      assert!(!settled.can_release(&settled.payer, 100));
+    }
+
+    // Without an arbiter, the default key can't release.
+    let no_arbiter = escrow_state(EscrowStatus::Locked, Pubkey::default());
+    assert!(!no_arbiter.can_release(&Pubkey::default(), 0));
+  }
+
+  #[test]
+  fn test_can_dispute() {
+    let locked = escrow_state(EscrowStatus::Locked, Pubkey::new_unique());
+    assert!(locked.can_dispute(99));
+    assert!(!locked.can_dispute(100));
+    assert!(
+      !escrow_state(EscrowStatus::Locked, Pubkey::default()).can_dispute(0)
+    );
+    assert!(!escrow_state(EscrowStatus::Disputed, Pubkey::new_unique())
+      .can_dispute(0));
+  }
+}
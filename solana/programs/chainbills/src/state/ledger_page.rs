@@ -0,0 +1,125 @@
+use anchor_lang::prelude::*;
+
+/// The number of index entries that each ledger page holds.
+pub const LEDGER_PAGE_ENTRIES: u64 = 32;
+
+/// AKA `b"ledger_page"`. Follows the seeds that the entries' own accounts
+/// share in the seeds of their ledger pages, before the page's number.
+pub const SEED_PREFIX_LEDGER_PAGE: &[u8; 11] = b"ledger_page";
+
+/// The number of the ledger page that holds the entry of the 1-based count.
+pub fn ledger_page(count: u64) -> u64 {
+  count.checked_sub(1).unwrap() / LEDGER_PAGE_ENTRIES
+}
+
+/// The slot of the entry of the 1-based count in its ledger page.
+pub fn ledger_slot(count: u64) -> usize {
+  (count.checked_sub(1).unwrap() % LEDGER_PAGE_ENTRIES) as usize
+}
+
+#[account]
+/// A page of a ledger of addresses, in place of the ChainUserPaymentId or
+/// ChainPayablePaymentId accounts of [`LEDGER_PAGE_ENTRIES`] counts.
+///
+/// Payments can append their index entries into ledger pages instead of
+/// creating an account for each, so that payers only pay rent for the
+/// accounts of the payment itself (and, once per page, for a new page). The
+/// entry of a count is at its [`ledger_slot`] in its [`ledger_page`]. Empty
+/// slots hold the default Pubkey: their entries are in their own accounts,
+/// were archived, or weren't recorded yet.
+pub struct AddressLedgerPage {
+  /// The entries of the page, up to the last slot that was written.
+  pub entries: Vec<Pubkey>, // 4 + 32 * LEDGER_PAGE_ENTRIES bytes
+}
+
+impl AddressLedgerPage {
+  // discriminator (8) included
+  pub const SPACE: usize = 8 + 4 + 32 * LEDGER_PAGE_ENTRIES as usize;
+
+  /// The entry of the count, if it is in this page.
+  pub fn get(&self, count: u64) -> Option<Pubkey> {
+    let entry = *self.entries.get(ledger_slot(count))?;
+    (entry != Pubkey::default()).then_some(entry)
+  }
+
+  pub fn set(&mut self, count: u64, entry: Pubkey) {
+    let slot = ledger_slot(count);
+    if self.entries.len() <= slot {
+      self.entries.resize(slot + 1, Pubkey::default());
+    }
+    self.entries[slot] = entry;
+  }
+}
+
+#[account]
+/// A page of a ledger of counts, in place of the UserActivityInfo,
+/// PayableActivityInfo, or PayablePerChainPaymentInfo accounts of
+/// [`LEDGER_PAGE_ENTRIES`] counts. See [`AddressLedgerPage`]. Empty slots
+/// hold zero, which no 1-based count is.
+pub struct CountLedgerPage {
+  /// The entries of the page, up to the last slot that was written.
+  pub entries: Vec<u64>, // 4 + 8 * LEDGER_PAGE_ENTRIES bytes
+}
+
+impl CountLedgerPage {
+  // discriminator (8) included
+  pub const SPACE: usize = 8 + 4 + 8 * LEDGER_PAGE_ENTRIES as usize;
+
+  /// The entry of the count, if it is in this page.
+  pub fn get(&self, count: u64) -> Option<u64> {
+    let entry = *self.entries.get(ledger_slot(count))?;
+    (entry != 0).then_some(entry)
+  }
+
+  pub fn set(&mut self, count: u64, entry: u64) {
+    let slot = ledger_slot(count);
+    if self.entries.len() <= slot {
+      self.entries.resize(slot + 1, 0);
+    }
+    self.entries[slot] = entry;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::state::ChainStatsShard;
+
+  #[test]
+  fn test_ledger_page_and_slot() {
+    assert_eq!((ledger_page(1), ledger_slot(1)), (0, 0));
+    assert_eq!((ledger_page(32), ledger_slot(32)), (0, 31));
+    assert_eq!((ledger_page(33), ledger_slot(33)), (1, 0));
+    let base = ChainStatsShard::base_of(2);
+    assert_eq!(ledger_page(base + 1), base / LEDGER_PAGE_ENTRIES);
+    assert_eq!(ledger_slot(base + 1), 0);
+  }
+
+  #[test]
+  fn test_set_and_get() {
+    let mut page = CountLedgerPage { entries: vec![] };
+    page.set(35, 7);
+    assert_eq!(page.entries, vec![0, 0, 7]);
+    assert_eq!(page.get(35), Some(7));
+    assert_eq!(page.get(33), None);
+    assert_eq!(page.get(64), None);
+    page.set(35, 0);
+    assert_eq!(page.get(35), None);
+
+    let entry = Pubkey::new_unique();
+    let mut page = AddressLedgerPage { entries: vec![] };
+    page.set(1, entry);
+    assert_eq!(page.get(1), Some(entry));
+    assert_eq!(page.get(2), None);
+  }
+
+  #[test]
+  fn test_space_fits_full_page() {
+    let page = AddressLedgerPage {
+      entries: vec![Pubkey::new_unique(); LEDGER_PAGE_ENTRIES as usize],
+    };
+    let mut data = vec![];
+    page.try_serialize(&mut data).unwrap();
+    assert_eq!(data.len(), AddressLedgerPage::SPACE);
+  }
+}
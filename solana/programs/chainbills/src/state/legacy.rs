@@ -0,0 +1,800 @@
+use crate::state::{
+  solana_cb_chain_id, ActivityRecord, ActivityType, ChainStats, Config,
+  Payable, PayablePayment, TokenAndAmount, TokenDetails, User, UserPayment,
+  Withdrawal,
+};
+use anchor_lang::{prelude::*, Discriminator};
+
+// Layouts of accounts from before they carried a version byte. They are only
+// used to read old accounts in the migrate_* instructions. Old accounts are
+// told apart from current ones by their data length, as every account is
+// reallocated to its current space when migrated.
+
+/// Reads the legacy layout of an account after ensuring that it has the
+/// discriminator of the current layout.
+pub fn deserialize_legacy<T: Discriminator, L: AnchorDeserialize>(
+  data: &[u8],
+) -> Result<L> {
+  require!(
+    data.len() >= 8 && data[..8] == T::DISCRIMINATOR,
+    ErrorCode::AccountDiscriminatorMismatch
+  );
+  L::deserialize(&mut &data[8..])
+    .map_err(|_| error!(ErrorCode::AccountDidNotDeserialize))
+}
+
+#[derive(AnchorDeserialize, AnchorSerialize)]
+/// [User] before it had a version.
+pub struct UserV0 {
+  pub chain_count: u64,
+  pub payables_count: u64,
+  pub payments_count: u64,
+  pub withdrawals_count: u64,
+  pub activities_count: u64,
+}
+
+impl UserV0 {
+  // discriminator (8) included
+  pub const SPACE: usize = 6 * 8;
+
+  pub fn upgrade(self) -> User {
+    User {
+      version: User::VERSION,
+      chain_count: self.chain_count,
+      payables_count: self.payables_count,
+      payments_count: self.payments_count,
+      withdrawals_count: self.withdrawals_count,
+      activities_count: self.activities_count,
+    }
+  }
+}
+
+#[derive(AnchorDeserialize, AnchorSerialize)]
+/// [ChainStats] before it had a version.
+pub struct ChainStatsV0 {
+  pub users_count: u64,
+  pub payables_count: u64,
+  pub foreign_payables_count: u64,
+  pub user_payments_count: u64,
+  pub payable_payments_count: u64,
+  pub withdrawals_count: u64,
+  pub activities_count: u64,
+  pub published_wormhole_messages_count: u64,
+  pub consumed_wormhole_messages_count: u64,
+}
+
+impl ChainStatsV0 {
+  // discriminator (8) included
+  pub const SPACE: usize = 10 * 8;
+
+  pub fn upgrade(self) -> ChainStats {
+    ChainStats {
+      version: ChainStats::VERSION,
+      users_count: self.users_count,
+      payables_count: self.payables_count,
+      foreign_payables_count: self.foreign_payables_count,
+      user_payments_count: self.user_payments_count,
+      payable_payments_count: self.payable_payments_count,
+      withdrawals_count: self.withdrawals_count,
+      activities_count: self.activities_count,
+      published_wormhole_messages_count: self.published_wormhole_messages_count,
+      consumed_wormhole_messages_count: self.consumed_wormhole_messages_count,
+    }
+  }
+}
+
+#[derive(AnchorDeserialize, AnchorSerialize)]
+/// [TokenDetails] before it had a version.
+pub struct TokenDetailsV0 {
+  pub mint: Pubkey,
+  pub is_supported: bool,
+  pub max_withdrawal_fees: u64,
+  pub total_user_paid: u64,
+  pub total_payable_received: u64,
+  pub total_withdrawn: u64,
+  pub total_withdrawal_fees_collected: u64,
+}
+
+impl TokenDetailsV0 {
+  // discriminator (8) included
+  pub const SPACE: usize = 1 + 6 * 8 + 32;
+
+  pub fn upgrade(self) -> TokenDetails {
+    TokenDetails {
+      version: TokenDetails::VERSION,
+      mint: self.mint,
+      is_supported: self.is_supported,
+      max_withdrawal_fees: self.max_withdrawal_fees,
+      total_user_paid: self.total_user_paid,
+      total_payable_received: self.total_payable_received,
+      total_withdrawn: self.total_withdrawn,
+      total_withdrawal_fees_collected: self.total_withdrawal_fees_collected,
+    }
+  }
+}
+
+#[derive(AnchorDeserialize, AnchorSerialize)]
+/// [Config] before it had a version. Its zero-copy layout had no padding, so
+/// it matches the borsh layout of these fields.
+pub struct ConfigV0 {
+  pub chain_id: u16,
+  pub withdrawal_fee_percentage: u16,
+  pub owner: Pubkey,
+  pub chainbills_fee_collector: Pubkey,
+  pub wormhole_bridge: Pubkey,
+  pub wormhole_emitter: Pubkey,
+  pub wormhole_fee_collector: Pubkey,
+  pub wormhole_sequence: Pubkey,
+}
+
+impl ConfigV0 {
+  // discriminator (8) included
+  pub const SPACE: usize = 2 + 2 + 8 + (6 * 32);
+
+  pub fn upgrade(self) -> Config {
+    Config {
+      version: Config::VERSION,
+      reserved: 0,
+      chain_id: self.chain_id,
+      withdrawal_fee_percentage: self.withdrawal_fee_percentage,
+      owner: self.owner,
+      chainbills_fee_collector: self.chainbills_fee_collector,
+      wormhole_bridge: self.wormhole_bridge,
+      wormhole_emitter: self.wormhole_emitter,
+      wormhole_fee_collector: self.wormhole_fee_collector,
+      wormhole_sequence: self.wormhole_sequence,
+    }
+  }
+}
+
+#[derive(AnchorDeserialize, AnchorSerialize)]
+/// [Payable] before it had a version.
+pub struct PayableV0 {
+  pub chain_count: u64,
+  pub host: Pubkey,
+  pub host_count: u64,
+  pub created_at: u64,
+  pub payments_count: u64,
+  pub withdrawals_count: u64,
+  pub activities_count: u64,
+  pub is_closed: bool,
+  pub allowed_tokens_and_amounts: Vec<TokenAndAmount>,
+  pub balances: Vec<TokenAndAmount>,
+}
+
+impl PayableV0 {
+  /// Whether the data isn't that of a current payable. Current payables
+  /// always occupy exactly [Payable::space_current], which no legacy payable
+  /// does.
+  pub fn is_legacy(data: &[u8]) -> bool {
+    match Payable::try_deserialize(&mut &data[..]) {
+      Ok(payable) => {
+        payable.version != Payable::VERSION
+          || data.len() != payable.space_current()
+      }
+      Err(_) => true,
+    }
+  }
+
+  pub fn upgrade(self) -> Payable {
+    Payable {
+      version: Payable::VERSION,
+      chain_count: self.chain_count,
+      host: self.host,
+      host_count: self.host_count,
+      created_at: self.created_at,
+      payments_count: self.payments_count,
+      withdrawals_count: self.withdrawals_count,
+      activities_count: self.activities_count,
+      is_closed: self.is_closed,
+      allowed_tokens_and_amounts: self.allowed_tokens_and_amounts,
+      balances: self.balances,
+    }
+  }
+}
+
+#[derive(AnchorDeserialize, AnchorSerialize)]
+/// [UserPayment] before it held a cbChainId.
+pub struct UserPaymentV0 {
+  pub payable_id: [u8; 32],
+  pub payer: Pubkey,
+  pub payable_chain_id: u16,
+  pub chain_count: u64,
+  pub payer_count: u64,
+  pub timestamp: u64,
+  pub details: TokenAndAmount,
+}
+
+impl UserPaymentV0 {
+  // discriminator (8) included
+  pub const SPACE: usize = 2 + (4 * 8) + (2 * 32) + TokenAndAmount::SPACE;
+
+  /// Only payments to Solana payables were recorded, so the legacy Wormhole
+  /// Chain ID was always Solana's.
+  pub fn upgrade(self) -> UserPayment {
+    UserPaymentV1 {
+      payable_id: self.payable_id,
+      payer: self.payer,
+      payable_chain_id: solana_cb_chain_id(),
+      chain_count: self.chain_count,
+      payer_count: self.payer_count,
+      timestamp: self.timestamp,
+      details: self.details,
+    }
+    .upgrade()
+  }
+}
+
+#[derive(AnchorDeserialize, AnchorSerialize)]
+/// [UserPayment] before it held its rent payer.
+pub struct UserPaymentV1 {
+  pub payable_id: [u8; 32],
+  pub payer: Pubkey,
+  pub payable_chain_id: [u8; 32],
+  pub chain_count: u64,
+  pub payer_count: u64,
+  pub timestamp: u64,
+  pub details: TokenAndAmount,
+}
+
+impl UserPaymentV1 {
+  // discriminator (8) included
+  pub const SPACE: usize = (4 * 8) + (3 * 32) + TokenAndAmount::SPACE;
+
+  /// Payments weren't made on behalf of payers yet, so payers paid the rent.
+  pub fn upgrade(self) -> UserPayment {
+    UserPayment {
+      payable_id: self.payable_id,
+      payer: self.payer,
+      payable_chain_id: self.payable_chain_id,
+      chain_count: self.chain_count,
+      payer_count: self.payer_count,
+      timestamp: self.timestamp,
+      details: self.details,
+      rent_payer: self.payer,
+    }
+  }
+}
+
+#[derive(AnchorDeserialize, AnchorSerialize)]
+/// [PayablePayment] before it held a cbChainId.
+pub struct PayablePaymentV0 {
+  pub payable_id: Pubkey,
+  pub payer: [u8; 32],
+  pub chain_count: u64,
+  pub payer_chain_id: u16,
+  pub local_chain_count: u64,
+  pub payable_count: u64,
+  pub timestamp: u64,
+  pub details: TokenAndAmount,
+}
+
+impl PayablePaymentV0 {
+  // discriminator (8) included
+  pub const SPACE: usize = 2 + (5 * 8) + (2 * 32) + TokenAndAmount::SPACE;
+
+  /// Only payments from Solana were recorded, so the legacy Wormhole Chain
+  /// ID was always Solana's.
+  pub fn upgrade(self) -> PayablePayment {
+    PayablePaymentV1 {
+      payable_id: self.payable_id,
+      payer: self.payer,
+      chain_count: self.chain_count,
+      payer_chain_id: solana_cb_chain_id(),
+      local_chain_count: self.local_chain_count,
+      payable_count: self.payable_count,
+      timestamp: self.timestamp,
+      details: self.details,
+    }
+    .upgrade()
+  }
+}
+
+#[derive(AnchorDeserialize, AnchorSerialize)]
+/// [PayablePayment] before it held its rent payer.
+pub struct PayablePaymentV1 {
+  pub payable_id: Pubkey,
+  pub payer: [u8; 32],
+  pub chain_count: u64,
+  pub payer_chain_id: [u8; 32],
+  pub local_chain_count: u64,
+  pub payable_count: u64,
+  pub timestamp: u64,
+  pub details: TokenAndAmount,
+}
+
+impl PayablePaymentV1 {
+  // discriminator (8) included
+  pub const SPACE: usize = (5 * 8) + (3 * 32) + TokenAndAmount::SPACE;
+
+  /// Only payments from Solana were recorded, and they weren't made on
+  /// behalf of payers yet, so payers paid the rent.
+  pub fn upgrade(self) -> PayablePayment {
+    PayablePayment {
+      payable_id: self.payable_id,
+      payer: self.payer,
+      chain_count: self.chain_count,
+      payer_chain_id: self.payer_chain_id,
+      local_chain_count: self.local_chain_count,
+      payable_count: self.payable_count,
+      timestamp: self.timestamp,
+      details: self.details,
+      rent_payer: Pubkey::new_from_array(self.payer),
+    }
+  }
+}
+
+#[derive(AnchorDeserialize, AnchorSerialize)]
+/// [Withdrawal] before it held its destination.
+pub struct WithdrawalV0 {
+  pub payable_id: Pubkey,
+  pub host: Pubkey,
+  pub chain_count: u64,
+  pub host_count: u64,
+  pub payable_count: u64,
+  pub timestamp: u64,
+  pub details: TokenAndAmount,
+}
+
+impl WithdrawalV0 {
+  // discriminator (8) included
+  pub const SPACE: usize = (5 * 8) + (2 * 32) + TokenAndAmount::SPACE;
+
+  /// Only local withdrawals were made, so the funds went to the host on
+  /// Solana.
+  pub fn upgrade(self) -> Withdrawal {
+    WithdrawalV1 {
+      payable_id: self.payable_id,
+      host: self.host,
+      chain_count: self.chain_count,
+      host_count: self.host_count,
+      payable_count: self.payable_count,
+      timestamp: self.timestamp,
+      details: self.details,
+      dest_chain_id: solana_cb_chain_id(),
+      dest_address: self.host.to_bytes(),
+    }
+    .upgrade()
+  }
+}
+
+#[derive(AnchorDeserialize, AnchorSerialize)]
+/// [Withdrawal] before it held its rent payer.
+pub struct WithdrawalV1 {
+  pub payable_id: Pubkey,
+  pub host: Pubkey,
+  pub chain_count: u64,
+  pub host_count: u64,
+  pub payable_count: u64,
+  pub timestamp: u64,
+  pub details: TokenAndAmount,
+  pub dest_chain_id: [u8; 32],
+  pub dest_address: [u8; 32],
+}
+
+impl WithdrawalV1 {
+  // discriminator (8) included
+  pub const SPACE: usize = (5 * 8) + (4 * 32) + TokenAndAmount::SPACE;
+
+  /// The rent payer is unknown, so the host (who archives the withdrawal) is
+  /// taken to have paid it, as before.
+  pub fn upgrade(self) -> Withdrawal {
+    Withdrawal {
+      payable_id: self.payable_id,
+      host: self.host,
+      chain_count: self.chain_count,
+      host_count: self.host_count,
+      payable_count: self.payable_count,
+      timestamp: self.timestamp,
+      details: self.details,
+      dest_chain_id: self.dest_chain_id,
+      dest_address: self.dest_address,
+      rent_payer: self.host,
+    }
+  }
+}
+
+#[derive(AnchorDeserialize, AnchorSerialize)]
+/// [ActivityRecord] before it held its rent payer.
+pub struct ActivityRecordV0 {
+  pub chain_count: u64,
+  pub user_count: u64,
+  pub payable_count: u64,
+  pub timestamp: u64,
+  pub entity: Pubkey,
+  pub activity_type: ActivityType,
+}
+
+impl ActivityRecordV0 {
+  // discriminator (8) included
+  pub const SPACE: usize = 1 + (5 * 8) + 32;
+
+  /// The record doesn't say who paid its rent, so it is given to the user
+  /// (who archives the activity) as before.
+  pub fn upgrade(self, user: Pubkey) -> ActivityRecord {
+    ActivityRecord {
+      chain_count: self.chain_count,
+      user_count: self.user_count,
+      payable_count: self.payable_count,
+      timestamp: self.timestamp,
+      entity: self.entity,
+      activity_type: self.activity_type,
+      rent_payer: user,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn legacy_bytes<T: Discriminator>(legacy: &impl AnchorSerialize) -> Vec<u8> {
+    let mut buf = T::DISCRIMINATOR.to_vec();
+    legacy.serialize(&mut buf).unwrap();
+    buf
+  }
+
+  fn current_bytes(account: &impl AccountSerialize, space: usize) -> Vec<u8> {
+    let mut buf = vec![0u8; space];
+    account.try_serialize(&mut &mut buf[..]).unwrap();
+    buf
+  }
+
+  #[test]
+  fn test_upgrade_user() {
+    let legacy = UserV0 {
+      chain_count: 1,
+      payables_count: 2,
+      payments_count: 3,
+      withdrawals_count: 4,
+      activities_count: 5,
+    };
+    let data = legacy_bytes::<User>(&legacy);
+    assert_eq!(data.len(), UserV0::SPACE);
+    assert!(User::try_deserialize(&mut &data[..]).is_err());
+
+    let user = deserialize_legacy::<User, UserV0>(&data).unwrap().upgrade();
+    let data = current_bytes(&user, User::SPACE);
+    let user = User::try_deserialize(&mut &data[..]).unwrap();
+    assert_eq!(user.version, User::VERSION);
+    assert_eq!(user.chain_count, 1);
+    assert_eq!(user.payables_count, 2);
+    assert_eq!(user.payments_count, 3);
+    assert_eq!(user.withdrawals_count, 4);
+    assert_eq!(user.activities_count, 5);
+  }
+
+  #[test]
+  fn test_upgrade_chain_stats() {
+    let legacy = ChainStatsV0 {
+      users_count: 1,
+      payables_count: 2,
+      foreign_payables_count: 3,
+      user_payments_count: 4,
+      payable_payments_count: 5,
+      withdrawals_count: 6,
+      activities_count: 7,
+      published_wormhole_messages_count: 8,
+      consumed_wormhole_messages_count: 9,
+    };
+    let data = legacy_bytes::<ChainStats>(&legacy);
+    assert_eq!(data.len(), ChainStatsV0::SPACE);
+
+    let chain_stats = deserialize_legacy::<ChainStats, ChainStatsV0>(&data)
+      .unwrap()
+      .upgrade();
+    let data = current_bytes(&chain_stats, ChainStats::SPACE);
+    let chain_stats = ChainStats::try_deserialize(&mut &data[..]).unwrap();
+    assert_eq!(chain_stats.version, ChainStats::VERSION);
+    assert_eq!(chain_stats.users_count, 1);
+    assert_eq!(chain_stats.activities_count, 7);
+    assert_eq!(chain_stats.consumed_wormhole_messages_count, 9);
+  }
+
+  #[test]
+  fn test_upgrade_token_details() {
+    let legacy = TokenDetailsV0 {
+      mint: Pubkey::new_from_array([1; 32]),
+      is_supported: true,
+      max_withdrawal_fees: 2,
+      total_user_paid: 3,
+      total_payable_received: 4,
+      total_withdrawn: 5,
+      total_withdrawal_fees_collected: 6,
+    };
+    let data = legacy_bytes::<TokenDetails>(&legacy);
+    assert_eq!(data.len(), TokenDetailsV0::SPACE);
+
+    let token_details =
+      deserialize_legacy::<TokenDetails, TokenDetailsV0>(&data)
+        .unwrap()
+        .upgrade();
+    let data = current_bytes(&token_details, TokenDetails::SPACE);
+    let token_details = TokenDetails::try_deserialize(&mut &data[..]).unwrap();
+    assert_eq!(token_details.version, TokenDetails::VERSION);
+    assert_eq!(token_details.mint, Pubkey::new_from_array([1; 32]));
+    assert!(token_details.is_supported);
+    assert_eq!(token_details.max_withdrawal_fees, 2);
+    assert_eq!(token_details.total_withdrawal_fees_collected, 6);
+  }
+
+  #[test]
+  fn test_upgrade_config() {
+    let legacy = ConfigV0 {
+      chain_id: 1,
+      withdrawal_fee_percentage: 200,
+      owner: Pubkey::new_from_array([2; 32]),
+      chainbills_fee_collector: Pubkey::new_from_array([3; 32]),
+      wormhole_bridge: Pubkey::new_from_array([4; 32]),
+      wormhole_emitter: Pubkey::new_from_array([5; 32]),
+      wormhole_fee_collector: Pubkey::new_from_array([6; 32]),
+      wormhole_sequence: Pubkey::new_from_array([7; 32]),
+    };
+    let data = legacy_bytes::<Config>(&legacy);
+    assert_eq!(data.len(), ConfigV0::SPACE);
+
+    let config = deserialize_legacy::<Config, ConfigV0>(&data)
+      .unwrap()
+      .upgrade();
+    assert_eq!(8 + bytemuck::bytes_of(&config).len(), Config::SPACE);
+    assert_eq!(config.version, Config::VERSION);
+    assert_eq!(config.chain_id, 1);
+    assert_eq!(config.withdrawal_fee_percentage, 200);
+    assert_eq!(config.owner, Pubkey::new_from_array([2; 32]));
+    assert_eq!(config.wormhole_sequence, Pubkey::new_from_array([7; 32]));
+  }
+
+  #[test]
+  fn test_upgrade_payable() {
+    let taa = TokenAndAmount {
+      token: Pubkey::new_from_array([1; 32]),
+      amount: 100,
+    };
+    let legacy = PayableV0 {
+      chain_count: 1,
+      host: Pubkey::new_from_array([2; 32]),
+      host_count: 3,
+      created_at: 4,
+      payments_count: 5,
+      withdrawals_count: 6,
+      activities_count: 7,
+      is_closed: false,
+      allowed_tokens_and_amounts: vec![taa],
+      balances: vec![taa, taa],
+    };
+    let data = legacy_bytes::<Payable>(&legacy);
+    assert!(PayableV0::is_legacy(&data));
+
+    let payable = deserialize_legacy::<Payable, PayableV0>(&data)
+      .unwrap()
+      .upgrade();
+    let space = payable.space_current();
+    let data = current_bytes(&payable, space);
+    assert!(!PayableV0::is_legacy(&data));
+
+    let payable = Payable::try_deserialize(&mut &data[..]).unwrap();
+    assert_eq!(payable.version, Payable::VERSION);
+    assert_eq!(payable.host, Pubkey::new_from_array([2; 32]));
+    assert_eq!(payable.activities_count, 7);
+    assert_eq!(payable.allowed_tokens_and_amounts.len(), 1);
+    assert_eq!(payable.balances.len(), 2);
+    assert_eq!(payable.balances[1].amount, 100);
+  }
+
+  #[test]
+  fn test_upgrade_user_payment() {
+    let details = TokenAndAmount {
+      token: Pubkey::new_from_array([3; 32]),
+      amount: 100,
+    };
+    let legacy = UserPaymentV0 {
+      payable_id: [1; 32],
+      payer: Pubkey::new_from_array([2; 32]),
+      payable_chain_id: 1,
+      chain_count: 4,
+      payer_count: 5,
+      timestamp: 6,
+      details,
+    };
+    let data = legacy_bytes::<UserPayment>(&legacy);
+    assert_eq!(data.len(), UserPaymentV0::SPACE);
+    assert!(UserPayment::try_deserialize(&mut &data[..]).is_err());
+
+    let user_payment = deserialize_legacy::<UserPayment, UserPaymentV0>(&data)
+      .unwrap()
+      .upgrade();
+    let data = current_bytes(&user_payment, UserPayment::SPACE);
+    let user_payment = UserPayment::try_deserialize(&mut &data[..]).unwrap();
+    assert_eq!(user_payment.payable_id, [1; 32]);
+    assert_eq!(user_payment.payable_chain_id, solana_cb_chain_id());
+    assert_eq!(user_payment.payer_count, 5);
+    assert_eq!(user_payment.details.amount, 100);
+    assert_eq!(user_payment.rent_payer, Pubkey::new_from_array([2; 32]));
+  }
+
+  #[test]
+  fn test_upgrade_user_payment_v1() {
+    let details = TokenAndAmount {
+      token: Pubkey::new_from_array([3; 32]),
+      amount: 100,
+    };
+    let legacy = UserPaymentV1 {
+      payable_id: [1; 32],
+      payer: Pubkey::new_from_array([2; 32]),
+      payable_chain_id: [7; 32],
+      chain_count: 4,
+      payer_count: 5,
+      timestamp: 6,
+      details,
+    };
+    let data = legacy_bytes::<UserPayment>(&legacy);
+    assert_eq!(data.len(), UserPaymentV1::SPACE);
+    assert!(UserPayment::try_deserialize(&mut &data[..]).is_err());
+
+    let user_payment = deserialize_legacy::<UserPayment, UserPaymentV1>(&data)
+      .unwrap()
+      .upgrade();
+    let data = current_bytes(&user_payment, UserPayment::SPACE);
+    let user_payment = UserPayment::try_deserialize(&mut &data[..]).unwrap();
+    assert_eq!(user_payment.payable_chain_id, [7; 32]);
+    assert_eq!(user_payment.timestamp, 6);
+    assert_eq!(user_payment.rent_payer, Pubkey::new_from_array([2; 32]));
+  }
+
+  #[test]
+  fn test_upgrade_payable_payment() {
+    let details = TokenAndAmount {
+      token: Pubkey::new_from_array([3; 32]),
+      amount: 100,
+    };
+    let legacy = PayablePaymentV0 {
+      payable_id: Pubkey::new_from_array([1; 32]),
+      payer: [2; 32],
+      chain_count: 4,
+      payer_chain_id: 1,
+      local_chain_count: 5,
+      payable_count: 6,
+      timestamp: 7,
+      details,
+    };
+    let data = legacy_bytes::<PayablePayment>(&legacy);
+    assert_eq!(data.len(), PayablePaymentV0::SPACE);
+    assert!(PayablePayment::try_deserialize(&mut &data[..]).is_err());
+
+    let payable_payment =
+      deserialize_legacy::<PayablePayment, PayablePaymentV0>(&data)
+        .unwrap()
+        .upgrade();
+    let data = current_bytes(&payable_payment, PayablePayment::SPACE);
+    let payable_payment =
+      PayablePayment::try_deserialize(&mut &data[..]).unwrap();
+    assert_eq!(payable_payment.payer, [2; 32]);
+    assert_eq!(payable_payment.payer_chain_id, solana_cb_chain_id());
+    assert_eq!(payable_payment.local_chain_count, 5);
+    assert_eq!(payable_payment.details.amount, 100);
+    assert_eq!(payable_payment.rent_payer, Pubkey::new_from_array([2; 32]));
+  }
+
+  #[test]
+  fn test_upgrade_payable_payment_v1() {
+    let details = TokenAndAmount {
+      token: Pubkey::new_from_array([3; 32]),
+      amount: 100,
+    };
+    let legacy = PayablePaymentV1 {
+      payable_id: Pubkey::new_from_array([1; 32]),
+      payer: [2; 32],
+      chain_count: 4,
+      payer_chain_id: [7; 32],
+      local_chain_count: 5,
+      payable_count: 6,
+      timestamp: 7,
+      details,
+    };
+    let data = legacy_bytes::<PayablePayment>(&legacy);
+    assert_eq!(data.len(), PayablePaymentV1::SPACE);
+    assert!(PayablePayment::try_deserialize(&mut &data[..]).is_err());
+
+    let payable_payment =
+      deserialize_legacy::<PayablePayment, PayablePaymentV1>(&data)
+        .unwrap()
+        .upgrade();
+    let data = current_bytes(&payable_payment, PayablePayment::SPACE);
+    let payable_payment =
+      PayablePayment::try_deserialize(&mut &data[..]).unwrap();
+    assert_eq!(payable_payment.payer_chain_id, [7; 32]);
+    assert_eq!(payable_payment.timestamp, 7);
+    assert_eq!(payable_payment.rent_payer, Pubkey::new_from_array([2; 32]));
+  }
+
+  #[test]
+  fn test_upgrade_withdrawal() {
+    let details = TokenAndAmount {
+      token: Pubkey::new_from_array([3; 32]),
+      amount: 100,
+    };
+    let legacy = WithdrawalV0 {
+      payable_id: Pubkey::new_from_array([1; 32]),
+      host: Pubkey::new_from_array([2; 32]),
+      chain_count: 4,
+      host_count: 5,
+      payable_count: 6,
+      timestamp: 7,
+      details,
+    };
+    let data = legacy_bytes::<Withdrawal>(&legacy);
+    assert_eq!(data.len(), WithdrawalV0::SPACE);
+    assert!(Withdrawal::try_deserialize(&mut &data[..]).is_err());
+
+    let withdrawal = deserialize_legacy::<Withdrawal, WithdrawalV0>(&data)
+      .unwrap()
+      .upgrade();
+    let data = current_bytes(&withdrawal, Withdrawal::SPACE);
+    let withdrawal = Withdrawal::try_deserialize(&mut &data[..]).unwrap();
+    assert_eq!(withdrawal.host_count, 5);
+    assert_eq!(withdrawal.details.amount, 100);
+    assert_eq!(withdrawal.dest_chain_id, solana_cb_chain_id());
+    assert_eq!(withdrawal.dest_address, [2; 32]);
+    assert_eq!(withdrawal.rent_payer, Pubkey::new_from_array([2; 32]));
+  }
+
+  #[test]
+  fn test_upgrade_withdrawal_v1() {
+    let details = TokenAndAmount {
+      token: Pubkey::new_from_array([3; 32]),
+      amount: 100,
+    };
+    let legacy = WithdrawalV1 {
+      payable_id: Pubkey::new_from_array([1; 32]),
+      host: Pubkey::new_from_array([2; 32]),
+      chain_count: 4,
+      host_count: 5,
+      payable_count: 6,
+      timestamp: 7,
+      details,
+      dest_chain_id: [8; 32],
+      dest_address: [9; 32],
+    };
+    let data = legacy_bytes::<Withdrawal>(&legacy);
+    assert_eq!(data.len(), WithdrawalV1::SPACE);
+    assert!(Withdrawal::try_deserialize(&mut &data[..]).is_err());
+
+    let withdrawal = deserialize_legacy::<Withdrawal, WithdrawalV1>(&data)
+      .unwrap()
+      .upgrade();
+    let data = current_bytes(&withdrawal, Withdrawal::SPACE);
+    let withdrawal = Withdrawal::try_deserialize(&mut &data[..]).unwrap();
+    assert_eq!(withdrawal.payable_count, 6);
+    assert_eq!(withdrawal.dest_chain_id, [8; 32]);
+    assert_eq!(withdrawal.dest_address, [9; 32]);
+    assert_eq!(withdrawal.rent_payer, Pubkey::new_from_array([2; 32]));
+  }
+
+  #[test]
+  fn test_upgrade_activity_record() {
+    let legacy = ActivityRecordV0 {
+      chain_count: 1,
+      user_count: 2,
+      payable_count: 3,
+      timestamp: 4,
+      entity: Pubkey::new_from_array([5; 32]),
+      activity_type: ActivityType::Withdrew,
+    };
+    let data = legacy_bytes::<ActivityRecord>(&legacy);
+    assert_eq!(data.len(), ActivityRecordV0::SPACE);
+    assert!(ActivityRecord::try_deserialize(&mut &data[..]).is_err());
+
+    let user = Pubkey::new_from_array([6; 32]);
+    let activity =
+      deserialize_legacy::<ActivityRecord, ActivityRecordV0>(&data)
+        .unwrap()
+        .upgrade(user);
+    let data = current_bytes(&activity, ActivityRecord::SPACE);
+    let activity = ActivityRecord::try_deserialize(&mut &data[..]).unwrap();
+    assert_eq!(activity.chain_count, 1);
+    assert_eq!(activity.payable_count, 3);
+    assert_eq!(activity.entity, Pubkey::new_from_array([5; 32]));
+    assert!(matches!(activity.activity_type, ActivityType::Withdrew));
+    assert_eq!(activity.rent_payer, user);
+  }
+}
@@ -1,40 +1,74 @@
 pub mod activity_record;
+pub mod cb_chain;
 pub mod chain_items;
 pub mod chain_stats;
+pub mod chain_stats_shard;
 pub mod config;
+pub mod escrow_state;
+pub mod ledger_page;
+pub mod legacy;
+pub mod native_vault;
 pub mod registered_foreign_contract;
 pub mod payable;
+pub mod payable_allowed_payers;
+pub mod payable_escrow;
 pub mod payable_foreign;
 pub mod payable_items;
+pub mod payable_operator;
 pub mod payable_payment;
+pub mod payable_receipts;
+pub mod receipt;
+pub mod subscription;
 pub mod token_and_amount;
 pub mod token_and_amount_foreign;
 pub mod token_details;
+pub mod token_details_shard;
 pub mod token_foreign_chain;
+pub mod token_group;
+pub mod token_integrity;
 pub mod user;
 pub mod user_activity_info;
+pub mod user_payable_info;
 pub mod user_payment;
 pub mod withdrawal;
 pub mod consumed_wormhole_message;
+pub mod consumed_payment_intent;
 
 pub use activity_record::*;
+pub use cb_chain::*;
 pub use chain_items::*;
 pub use chain_stats::*;
+pub use chain_stats_shard::*;
 pub use config::*;
+pub use escrow_state::*;
+pub use ledger_page::*;
+pub use legacy::*;
+pub use native_vault::*;
 pub use registered_foreign_contract::*;
 pub use payable::*;
+pub use payable_allowed_payers::*;
+pub use payable_escrow::*;
 pub use payable_foreign::*;
 pub use payable_items::*;
+pub use payable_operator::*;
 pub use payable_payment::*;
+pub use payable_receipts::*;
+pub use receipt::*;
+pub use subscription::*;
 pub use token_and_amount::*;
 pub use token_and_amount_foreign::*;
 pub use token_foreign_chain::*;
+pub use token_group::*;
+pub use token_integrity::*;
 pub use token_details::*;
+pub use token_details_shard::*;
 pub use user::*;
 pub use user_activity_info::*;
+pub use user_payable_info::*;
 pub use user_payment::*;
 pub use withdrawal::*;
 pub use consumed_wormhole_message::*;
+pub use consumed_payment_intent::*;
 
 /// AKA `b"sent"`.
 pub const SEED_PREFIX_SENT: &[u8; 4] = b"sent";
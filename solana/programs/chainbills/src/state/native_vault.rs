@@ -0,0 +1,3 @@
+/// AKA `b"native_vault"`. Seed of the data-less PDA that holds the native SOL
+/// paid into payables on this chain.
+pub const SEED_PREFIX_NATIVE_VAULT: &[u8; 12] = b"native_vault";
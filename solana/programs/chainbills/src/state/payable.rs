@@ -4,6 +4,9 @@ use anchor_lang::prelude::*;
 #[account]
 /// A payable is like a public invoice through which anybody can pay to.
 pub struct Payable {
+  /// The layout version of this account.
+  pub version: u8, // 1 byte
+
   /// The nth count of payables on this chain at the point this payable
   /// was created.
   pub chain_count: u64, // 8 bytes
@@ -43,6 +46,16 @@ impl Payable {
   /// AKA `b"payable"`.
   pub const SEED_PREFIX: &'static [u8] = b"payable";
 
+  /// The current layout version.
+  pub const VERSION: u8 = 1;
+
+  /// The maximum number of allowed tokens and amounts a payable can have.
+  /// Keeps the account size and the compute used in loops over it bounded.
+  pub const MAX_ALLOWED_TOKENS_AND_AMOUNTS: usize = 20;
+
+  /// The maximum number of token balances a payable can hold at once.
+  pub const MAX_BALANCES: usize = 20;
+
   pub fn next_payment(&self) -> u64 {
     self.payments_count.checked_add(1).unwrap()
   }
@@ -55,16 +68,33 @@ impl Payable {
     self.activities_count.checked_add(1).unwrap()
   }
 
-  pub fn space_new(ataa_len: usize) -> usize {
+  /// Adds the amount to the payable's balance of the token. The payable
+  /// must have been reallocated with space_update_balance.
+  pub fn add_balance(&mut self, token: Pubkey, amount: u64) {
+    match self.balances.iter_mut().find(|balance| balance.token == token) {
+      Some(balance) => {
+        balance.amount = balance.amount.checked_add(amount).unwrap()
+      }
+      None => self.balances.push(TokenAndAmount { token, amount }),
+    }
+  }
+
+  /// The space needed by a payable with the given number of allowed tokens
+  /// and amounts and of balances.
+  pub fn space(ataa_len: usize, balances_len: usize) -> usize {
     // discriminator (8) included
-    1 + (7 * 8) + 32 + (ataa_len * TokenAndAmount::SPACE)
+    8 + 1 + (6 * 8) + 32 + 1 // fixed fields
+      + (2 * 4) // length prefixes of both vectors
+      + (ataa_len * TokenAndAmount::SPACE)
+      + (balances_len * TokenAndAmount::SPACE)
+  }
+
+  pub fn space_new(ataa_len: usize) -> usize {
+    Self::space(ataa_len, 0)
   }
 
   pub fn space_update_ataa(&self, ataa_len: usize) -> usize {
-    1 + (6 * 8) // discriminator (8) included
-      + 32
-      + (ataa_len * TokenAndAmount::SPACE)
-      + (self.balances.len() * TokenAndAmount::SPACE)
+    Self::space(ataa_len, self.balances.len())
   }
 
   pub fn space_update_balance(&self, token: Pubkey) -> usize {
@@ -73,9 +103,11 @@ impl Payable {
     let new_bals_len =
       self.balances.len() + if will_add_new_balance { 1 } else { 0 };
 
-    1 + (6 * 8) // discriminator (8) included
-      + 32
-      + (self.allowed_tokens_and_amounts.len() * TokenAndAmount::SPACE)
-      + (new_bals_len * TokenAndAmount::SPACE)
+    Self::space(self.allowed_tokens_and_amounts.len(), new_bals_len)
+  }
+
+  /// The space needed by this payable as it currently is.
+  pub fn space_current(&self) -> usize {
+    Self::space(self.allowed_tokens_and_amounts.len(), self.balances.len())
   }
 }
@@ -0,0 +1,80 @@
+use anchor_lang::{prelude::*, solana_program::keccak};
+
+#[account]
+/// The payers allowed to pay a payable, as the root of a Merkle tree of
+/// their 32-byte addresses. Payables without this account can be paid by
+/// anyone. Also records the root that foreign chains broadcast for their
+/// payables.
+pub struct PayableAllowedPayers {
+  /// The Merkle root of the allowed payers. All zeros means that anyone can
+  /// pay. See [`chainbills_payload::allowed_payers_root`].
+  pub root: [u8; 32], // 32 bytes
+}
+
+impl PayableAllowedPayers {
+  // discriminator (8) included
+  pub const SPACE: usize = 8 + 32;
+
+  /// AKA `b"payable_allowed_payers"`.
+  pub const SEED_PREFIX: &'static [u8] = b"payable_allowed_payers";
+
+  /// The keccak256 of the concatenated slices, as the Merkle tree needs.
+  pub fn keccak(data: &[&[u8]]) -> [u8; 32] {
+    keccak::hashv(data).to_bytes()
+  }
+
+  /// Whether the payer can pay the payable whose (possibly uninitialized)
+  /// allowed payers account is given.
+  pub fn permits(
+    allowed_payers: &AccountInfo,
+    payer: &Pubkey,
+    proof: &[[u8; 32]],
+  ) -> Result<bool> {
+    if allowed_payers.data_is_empty() {
+      return Ok(true);
+    }
+    let data = allowed_payers.try_borrow_data()?;
+    let allowed_payers = Self::try_deserialize(&mut &data[..])?;
+    Ok(chainbills_payload::is_allowed_payer(
+      &allowed_payers.root,
+      &payer.to_bytes(),
+      proof,
+      &Self::keccak,
+    ))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_keccak_matches_pinned_root() {
+    // Pinned in payload/tests/allowed_payers.rs for every chain.
+    let payers = [[1u8; 32], [2u8; 32], [3u8; 32]];
+    let root = chainbills_payload::allowed_payers_root(
+      &payers,
+      &PayableAllowedPayers::keccak,
+    );
+    assert_eq!(
+      root,
+      [
+        13, 92, 214, 135, 68, 241, 61, 17, 162, 48, 219, 235, 178, 0, 112, 87,
+        58, 216, 198, 90, 174, 209, 125, 186, 16, 60, 159, 23, 191, 0, 49, 246
+      ]
+    );
+
+    let proof = chainbills_payload::allowed_payer_proof(
+      &payers,
+      &payers[1],
+      &PayableAllowedPayers::keccak,
+    )
+    .unwrap();
+    assert!(chainbills_payload::is_allowed_payer(
+      &root,
+      &payers[1],
+      &proof,
+      &PayableAllowedPayers::keccak
+    ));
+  }
+}
@@ -0,0 +1,55 @@
+use anchor_lang::prelude::*;
+
+#[account]
+/// A payable's escrow settings. While the timeout isn't zero, payments into
+/// the payable are locked in EscrowState accounts and only count in the
+/// payable's balances once released. Payables without this account don't
+/// escrow their payments.
+pub struct PayableEscrow {
+  /// The seconds after a payment from which anyone can release it, unless
+  /// the payer disputed it. Zero if payments aren't escrowed.
+  pub timeout: u64, // 8 bytes
+
+  /// The wallet that settles disputed payments. The default public key if
+  /// payments can't be disputed.
+  pub arbiter: Pubkey, // 32 bytes
+
+  /// The number of the payable's escrowed payments that are still held
+  /// (locked or disputed). The payable can't be archived while it isn't zero.
+  pub held_count: u64, // 8 bytes
+}
+
+impl PayableEscrow {
+  // discriminator (8) included
+  pub const SPACE: usize = 8 + 8 + 32 + 8;
+
+  /// AKA `b"payable_escrow"`.
+  pub const SEED_PREFIX: &'static [u8] = b"payable_escrow";
+
+  /// The escrow settings of the payable whose (possibly uninitialized)
+  /// escrow account is given, if the payable escrows its payments.
+  pub fn active(payable_escrow: &AccountInfo) -> Result<Option<Self>> {
+    if payable_escrow.data_is_empty() {
+      return Ok(None);
+    }
+    let data = payable_escrow.try_borrow_data()?;
+    let payable_escrow = Self::try_deserialize(&mut &data[..])?;
+    Ok(Some(payable_escrow).filter(|escrow| escrow.timeout > 0))
+  }
+
+  /// The number of escrowed payments still held by the payable whose
+  /// (possibly uninitialized) escrow account is given.
+  pub fn held(payable_escrow: &AccountInfo) -> Result<u64> {
+    if payable_escrow.data_is_empty() {
+      return Ok(0);
+    }
+    let data = payable_escrow.try_borrow_data()?;
+    Ok(Self::try_deserialize(&mut &data[..])?.held_count)
+  }
+
+  /// Writes these settings back into the given escrow account.
+  pub fn save(&self, payable_escrow: &AccountInfo) -> Result<()> {
+    let mut data = payable_escrow.try_borrow_mut_data()?;
+    self.try_serialize(&mut &mut data[..])
+  }
+}
@@ -4,7 +4,8 @@ use anchor_lang::prelude::*;
 #[account]
 /// A Payable that exists on another chain.
 pub struct PayableForeign {
-  pub chain_id: u16, // 2 bytes
+  /// The cbChainId of the chain where the payable exists.
+  pub chain_id: [u8; 32], // 32 bytes
 
   /// Whether this payable is currently accepting payments.
   pub is_closed: bool, // 1 byte
@@ -16,7 +17,7 @@ pub struct PayableForeign {
 
 impl PayableForeign {
   pub fn space(ataa_len: usize) -> usize {
-    // discriminator (8) included
-    8 + 2 + 1 + (ataa_len * TokenAndAmountForeign::SPACE)
+    // discriminator (8) and vec length prefix (4) included
+    8 + 32 + 1 + 4 + (ataa_len * TokenAndAmountForeign::SPACE)
   }
 }
@@ -61,3 +61,19 @@ impl PayableWithdrawalInfo {
   /// AKA `b"payment"`.
   pub const SEED_PREFIX: &'static [u8] = b"payable_withdrawal_info";
 }
+
+#[account]
+/// The wallet to which a payable's host has offered to transfer the payable.
+/// Closed when that wallet accepts the payable.
+pub struct PendingPayableHost {
+  /// The wallet that can accept the payable.
+  pub new_host: Pubkey, // 32 bytes
+}
+
+impl PendingPayableHost {
+  // discriminator (8) included
+  pub const SPACE: usize = 8 + 32;
+
+  /// AKA `b"pending_payable_host"`.
+  pub const SEED_PREFIX: &'static [u8] = b"pending_payable_host";
+}
@@ -0,0 +1,73 @@
+use anchor_lang::prelude::*;
+
+#[account]
+/// A wallet to which a payable's host has delegated some roles on the
+/// payable. Operators never receive funds: withdrawals that they trigger
+/// still go to the host.
+pub struct PayableOperator {
+  /// The payable on which the operator acts.
+  pub payable_id: Pubkey, // 32 bytes
+
+  /// The operator's wallet.
+  pub operator: Pubkey, // 32 bytes
+
+  /// The host that granted the roles. Grants stop working once the payable
+  /// is transferred to another host.
+  pub host: Pubkey, // 32 bytes
+
+  /// Bit flags of the roles that the operator holds.
+  pub roles: u8, // 1 byte
+}
+
+impl PayableOperator {
+  // discriminator (8) included
+  pub const SPACE: usize = 8 + (3 * 32) + 1;
+
+  /// AKA `b"payable_operator"`.
+  pub const SEED_PREFIX: &'static [u8] = b"payable_operator";
+
+  /// Can close and reopen the payable and update its allowed tokens and
+  /// amounts.
+  pub const ROLE_MANAGE: u8 = 1;
+
+  /// Can withdraw from the payable to the host's wallet.
+  pub const ROLE_WITHDRAW: u8 = 1 << 1;
+
+  /// All the roles that an operator can hold.
+  pub const ALL_ROLES: u8 = Self::ROLE_MANAGE | Self::ROLE_WITHDRAW;
+
+  /// Whether the given roles are a valid non-empty set of roles.
+  pub fn are_valid_roles(roles: u8) -> bool {
+    roles != 0 && roles & !Self::ALL_ROLES == 0
+  }
+
+  /// Whether an (optionally provided) operator account holds the role
+  /// granted by the payable's current host.
+  pub fn permits(
+    operator: &Option<Box<Account<Self>>>,
+    host: &Pubkey,
+    role: u8,
+  ) -> bool {
+    operator
+      .as_ref()
+      .is_some_and(|op| op.host == *host && op.roles & role == role)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_are_valid_roles() {
+    assert!(!PayableOperator::are_valid_roles(0));
+    assert!(PayableOperator::are_valid_roles(
+      PayableOperator::ROLE_MANAGE
+    ));
+    assert!(PayableOperator::are_valid_roles(
+      PayableOperator::ROLE_WITHDRAW
+    ));
+    assert!(PayableOperator::are_valid_roles(PayableOperator::ALL_ROLES));
+    assert!(!PayableOperator::are_valid_roles(1 << 2));
+  }
+}
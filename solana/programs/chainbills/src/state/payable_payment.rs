@@ -16,8 +16,8 @@ pub struct PayablePayment {
   /// was received.
   pub chain_count: u64, // 8 bytes
 
-  /// The Wormhole Chain ID of the chain from which the payment was made.
-  pub payer_chain_id: u16, // 2 bytes
+  /// The cbChainId of the chain from which the payment was made.
+  pub payer_chain_id: [u8; 32], // 32 bytes
 
   /// The nth count of payments to this payable from the payment source
   /// chain at the point this payment was recorded.
@@ -32,11 +32,15 @@ pub struct PayablePayment {
 
   /// The amount and token that the payer paid
   pub details: TokenAndAmount, // TokenAndAmount::SPACE
+
+  /// The wallet that paid the rent of this receipt and of the payable's
+  /// references to it. Gets it back when they are archived.
+  pub rent_payer: Pubkey, // 32 bytes
 }
 
 impl PayablePayment {
   // discriminator (8) included
-  pub const SPACE: usize = 2 + (5 * 8) + (2 * 32) + TokenAndAmount::SPACE;
+  pub const SPACE: usize = (5 * 8) + (4 * 32) + TokenAndAmount::SPACE;
 
   /// AKA `b"payable_payment"`.
   pub const SEED_PREFIX: &'static [u8] = b"payable_payment";
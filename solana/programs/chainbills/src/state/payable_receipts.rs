@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+
+#[account]
+/// A payable's receipt settings. While enabled, each payment into the
+/// payable mints a receipt NFT to its payer. Payables without this account
+/// don't mint receipts.
+pub struct PayableReceipts {
+  /// Whether payments into the payable mint receipts.
+  pub enabled: bool, // 1 byte
+}
+
+impl PayableReceipts {
+  // discriminator (8) included
+  pub const SPACE: usize = 8 + 1;
+
+  /// AKA `b"payable_receipts"`.
+  pub const SEED_PREFIX: &'static [u8] = b"payable_receipts";
+
+  /// Whether the payable whose (possibly uninitialized) receipts account is
+  /// given mints receipts for its payments.
+  pub fn is_enabled(payable_receipts: &AccountInfo) -> Result<bool> {
+    if payable_receipts.data_is_empty() {
+      return Ok(false);
+    }
+    let data = payable_receipts.try_borrow_data()?;
+    Ok(Self::try_deserialize(&mut &data[..])?.enabled)
+  }
+}
@@ -0,0 +1,43 @@
+use crate::state::UserPayment;
+use anchor_lang::prelude::*;
+
+/// A payment's receipt NFT. It is a Token-2022 mint with no decimals that is
+/// derived from the UserPayment it proves. Its metadata lives on the mint
+/// itself (through the MetadataPointer and TokenMetadata extensions), so that
+/// a receipt resolves to its payment and back.
+pub struct Receipt;
+
+impl Receipt {
+  /// The seed prefix of a receipt's mint, after the UserPayment's key.
+  /// AKA `b"receipt_mint"`.
+  pub const MINT_SEED_PREFIX: &'static [u8] = b"receipt_mint";
+
+  /// The name in every receipt's metadata.
+  pub const NAME: &'static str = "Chainbills Receipt";
+
+  /// The symbol in every receipt's metadata.
+  pub const SYMBOL: &'static str = "CBR";
+
+  /// The additional metadata fields of the receipt of the payment: the
+  /// UserPayment, the payable, the payer, when it was paid, and the token and
+  /// amount paid.
+  pub fn fields(
+    user_payment_id: Pubkey,
+    user_payment: &UserPayment,
+  ) -> Vec<(String, String)> {
+    vec![
+      ("user_payment".to_string(), user_payment_id.to_string()),
+      (
+        "payable_id".to_string(),
+        Pubkey::new_from_array(user_payment.payable_id).to_string(),
+      ),
+      ("payer".to_string(), user_payment.payer.to_string()),
+      ("timestamp".to_string(), user_payment.timestamp.to_string()),
+      ("token".to_string(), user_payment.details.token.to_string()),
+      (
+        "amount".to_string(),
+        user_payment.details.amount.to_string(),
+      ),
+    ]
+  }
+}
@@ -0,0 +1,54 @@
+use anchor_lang::prelude::*;
+
+#[account]
+/// A payer's recurring payment into a payable. Anyone can collect it once
+/// due, which pulls the payment from the payer's token account through the
+/// delegation that the payer approved to the ChainStats account.
+pub struct Subscription {
+  /// The wallet that pays.
+  pub payer: Pubkey, // 32 bytes
+
+  /// The payable into which payments are made.
+  pub payable_id: Pubkey, // 32 bytes
+
+  /// The mint of the token of each payment.
+  pub token: Pubkey, // 32 bytes
+
+  /// The amount of each payment.
+  pub amount: u64, // 8 bytes
+
+  /// The seconds between payments.
+  pub period: u64, // 8 bytes
+
+  /// The timestamp from which the next payment can be collected.
+  pub next_due: u64, // 8 bytes
+
+  /// The number of payments collected so far.
+  pub payments_count: u64, // 8 bytes
+
+  /// Whether the subscription was cancelled and can no longer be collected.
+  pub is_cancelled: bool, // 1 byte
+}
+
+impl Subscription {
+  // discriminator (8) included
+  pub const SPACE: usize = 8 + (3 * 32) + (4 * 8) + 1;
+
+  /// AKA `b"subscription"`.
+  pub const SEED_PREFIX: &'static [u8] = b"subscription";
+
+  /// Whether a subscription can be created in this account. That is if it
+  /// was just initialized or its previous subscription was cancelled.
+  pub fn is_available(&self) -> bool {
+    self.payer == Pubkey::default() || self.is_cancelled
+  }
+
+  /// The first period boundary after `now`. Periods missed before `now` are
+  /// skipped, so only one payment is collected per collection.
+  pub fn next_due_after(&self, now: u64) -> u64 {
+    let missed = now.saturating_sub(self.next_due) / self.period;
+    self
+      .next_due
+      .saturating_add(missed.saturating_add(1).saturating_mul(self.period))
+  }
+}
@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+
+#[account]
+/// One of the shards of a supported token's payment totals.
+///
+/// Payments add to the token's shard of the same index as the
+/// [`ChainStatsShard`](crate::state::ChainStatsShard) that they are indexed
+/// in, instead of to its [`TokenDetails`](crate::state::TokenDetails), so
+/// that payments in the same token don't all write to (and get serialized on)
+/// one account. The first payment into a shard creates it. The token's totals
+/// are those in its TokenDetails, from before sharding, plus those in each of
+/// its shards.
+pub struct TokenDetailsShard {
+  /// The token's mint.
+  pub mint: Pubkey, // 32 bytes
+
+  /// The index of the ChainStatsShard that this shard goes with.
+  pub index: u8, // 1 byte
+
+  /// The total amount of user payments in this token in this shard.
+  pub total_user_paid: u64, // 8 bytes
+
+  /// The total amount of payable payments in this token in this shard.
+  pub total_payable_received: u64, // 8 bytes
+}
+
+impl TokenDetailsShard {
+  // discriminator (8) included
+  pub const SPACE: usize = 8 + 32 + 1 + 2 * 8;
+
+  /// AKA `b"token_details_shard"`.
+  pub const SEED_PREFIX: &'static [u8] = b"token_details_shard";
+
+  pub fn add_user_paid(&mut self, amount: u64) {
+    self.total_user_paid = self.total_user_paid.checked_add(amount).unwrap()
+  }
+
+  pub fn add_payable_received(&mut self, amount: u64) {
+    self.total_payable_received =
+      self.total_payable_received.checked_add(amount).unwrap()
+  }
+}
@@ -0,0 +1,122 @@
+use anchor_lang::prelude::*;
+
+/// A token of a TokenGroup, with the decimals of its amounts.
+#[derive(AnchorDeserialize, AnchorSerialize, Clone, Copy, Debug, PartialEq)]
+pub struct TokenGroupMember {
+  /// The token's mint. The program's ID for native SOL.
+  pub token: Pubkey, // 32 bytes
+
+  /// The decimals of the token's amounts.
+  pub decimals: u8, // 1 byte
+}
+
+impl TokenGroupMember {
+  pub const SPACE: usize = 32 + 1;
+}
+
+#[account]
+/// Tokens that payables can accept interchangeably, like stablecoins of the
+/// same currency and their bridged variants. Managed by the owner.
+///
+/// A payable's allowed token can be a group's address, with an amount in the
+/// group's decimals. Payments in any member then match it, for the amount
+/// normalized to the member's decimals.
+pub struct TokenGroup {
+  /// The group's name, which seeds its address.
+  pub name: String, // 4 + 32 bytes
+
+  /// The decimals of amounts in allowed tokens and amounts that reference
+  /// this group.
+  pub decimals: u8, // 1 byte
+
+  /// The tokens of the group.
+  pub members: Vec<TokenGroupMember>, // 4 + 8 * 33 bytes
+}
+
+impl TokenGroup {
+  /// The maximum length of a group's name in bytes.
+  pub const MAX_NAME_LEN: usize = 32;
+
+  /// The maximum number of tokens in a group.
+  pub const MAX_MEMBERS: usize = 8;
+
+  // discriminator (8) included
+  pub const SPACE: usize = 8
+    + (4 + Self::MAX_NAME_LEN)
+    + 1
+    + (4 + Self::MAX_MEMBERS * TokenGroupMember::SPACE);
+
+  /// AKA `b"token_group"`.
+  pub const SEED_PREFIX: &'static [u8] = b"token_group";
+
+  /// The amount of the member token that is worth the amount in the group's
+  /// decimals. None if the token isn't a member, if the member's decimals
+  /// aren't those of the token, or if the amount can't be expressed exactly
+  /// in the token's decimals.
+  pub fn member_amount(
+    &self,
+    token: Pubkey,
+    decimals: u8,
+    amount: u64,
+  ) -> Option<u64> {
+    let member = self.members.iter().find(|m| m.token == token)?;
+    if member.decimals != decimals {
+      return None;
+    }
+    if member.decimals >= self.decimals {
+      let factor =
+        10u64.checked_pow((member.decimals - self.decimals) as u32)?;
+      amount.checked_mul(factor)
+    } else {
+      let divisor =
+        10u64.checked_pow((self.decimals - member.decimals) as u32)?;
+      (amount.checked_rem(divisor)? == 0).then_some(amount / divisor)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_member_amount() {
+    let usdc = Pubkey::new_unique();
+    let usdt = Pubkey::new_unique();
+    let wrapped = Pubkey::new_unique();
+    let group = TokenGroup {
+      name: "usd".to_string(),
+      decimals: 6,
+      members: vec![
+        TokenGroupMember {
+          token: usdc,
+          decimals: 6,
+        },
+        TokenGroupMember {
+          token: usdt,
+          decimals: 8,
+        },
+        TokenGroupMember {
+          token: wrapped,
+          decimals: 2,
+        },
+      ],
+    };
+
+    assert_eq!(group.member_amount(usdc, 6, 10_000_000), Some(10_000_000));
+    assert_eq!(
+      group.member_amount(usdt, 8, 10_000_000),
+      Some(1_000_000_000)
+    );
+    assert_eq!(group.member_amount(wrapped, 2, 10_000_000), Some(1_000));
+    // 10.000001 can't be paid in a token with 2 decimals.
+    assert_eq!(group.member_amount(wrapped, 2, 10_000_001), None);
+    assert_eq!(
+      group.member_amount(Pubkey::new_unique(), 6, 10_000_000),
+      None
+    );
+    assert_eq!(group.member_amount(usdt, 8, u64::MAX), None);
+    // A member whose recorded decimals aren't its mint's doesn't match.
+    assert_eq!(group.member_amount(usdt, 6, 10_000_000), None);
+  }
+}
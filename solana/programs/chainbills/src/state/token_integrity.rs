@@ -0,0 +1,86 @@
+use anchor_lang::prelude::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+/// Returned by the check_token_integrity view. Compares what the program
+/// holds of a token against what the provided payables and escrow states
+/// owe in it, and against the token's totals.
+pub struct TokenIntegrity {
+  /// The token's mint. The program's ID for native SOL.
+  pub token: Pubkey,
+
+  /// What the program holds of the token. For native SOL, the native vault's
+  /// lamports without its rent-exempt reserve.
+  pub holdings: u64,
+
+  /// The TokenDetails' total_payable_received, plus those of the provided
+  /// TokenDetailsShards.
+  pub total_payable_received: u64,
+
+  /// The TokenDetails' total_withdrawn. Includes fees and escrow refunds.
+  pub total_withdrawn: u64,
+
+  /// The sum of the token's balances in the provided payables.
+  pub payables_balance: u64,
+
+  /// The number of payables that were summed.
+  pub payables_count: u64,
+
+  /// The sum of the token's payments still held in the provided escrow
+  /// states.
+  pub escrowed: u64,
+
+  /// The number of escrow states that were checked.
+  pub escrow_states_count: u64,
+}
+
+impl TokenIntegrity {
+  /// What the provided payables and escrow states owe in the token.
+  pub fn liabilities(&self) -> u64 {
+    self.payables_balance.checked_add(self.escrowed).unwrap()
+  }
+
+  /// Whether the holdings cover the liabilities.
+  pub fn is_solvent(&self) -> bool {
+    self.holdings >= self.liabilities()
+  }
+
+  /// Whether the token's totals net to the liabilities. Only meaningful when
+  /// all payables and held escrow states in the token were provided.
+  pub fn is_consistent(&self) -> bool {
+    self
+      .total_payable_received
+      .checked_sub(self.total_withdrawn)
+      == Some(self.liabilities())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_token_integrity() {
+    let mut integrity = TokenIntegrity {
+      token: Pubkey::new_unique(),
+      holdings: 100,
+      total_payable_received: 150,
+      total_withdrawn: 60,
+      payables_balance: 70,
+      payables_count: 2,
+      escrowed: 20,
+      escrow_states_count: 1,
+    };
+    assert_eq!(integrity.liabilities(), 90);
+    assert!(integrity.is_solvent());
+    assert!(integrity.is_consistent());
+
+    // Drifted balances are neither consistent nor covered.
+    integrity.payables_balance = 90;
+    assert!(!integrity.is_solvent());
+    assert!(!integrity.is_consistent());
+
+    // Withdrawals beyond receipts are never consistent.
+    integrity.total_withdrawn = 200;
+    assert!(!integrity.is_consistent());
+  }
+}
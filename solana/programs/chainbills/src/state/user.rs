@@ -3,6 +3,9 @@ use anchor_lang::prelude::*;
 #[account]
 /// A user is an entity that can create payables and make payments.
 pub struct User {
+  /// The layout version of this account.
+  pub version: u8, // 1 byte
+
   /// The nth count of users on this chain at the point this user was
   /// initialized.
   pub chain_count: u64, // 8 bytes
@@ -22,7 +25,10 @@ pub struct User {
 
 impl User {
   // discriminator (8) included
-  pub const SPACE: usize = 6 * 8;
+  pub const SPACE: usize = 1 + 6 * 8;
+
+  /// The current layout version.
+  pub const VERSION: u8 = 1;
 
   pub fn next_payable(&self) -> u64 {
     self.payables_count.checked_add(1).unwrap()
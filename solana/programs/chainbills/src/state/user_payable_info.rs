@@ -0,0 +1,18 @@
+use anchor_lang::prelude::*;
+
+#[account]
+/// Holds the ID of a payable that a user received through a transfer. Such
+/// a payable's address wasn't derived from the user's payables count, so
+/// this takes its place at that count.
+pub struct UserPayableInfo {
+  /// The ID of the transferred payable.
+  pub payable_id: Pubkey, // 32 bytes
+}
+
+impl UserPayableInfo {
+  // discriminator (8) included
+  pub const SPACE: usize = 8 + 32;
+
+  /// AKA `b"user_payable_info"`.
+  pub const SEED_PREFIX: &'static [u8] = b"user_payable_info";
+}
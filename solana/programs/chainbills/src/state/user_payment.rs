@@ -14,8 +14,8 @@ pub struct UserPayment {
   /// The wallet address that made this Payment.
   pub payer: Pubkey, // 32 bytes
 
-  /// The Wormhole Chain ID of the chain into which the payment was made.
-  pub payable_chain_id: u16, // 2 bytes
+  /// The cbChainId of the chain into which the payment was made.
+  pub payable_chain_id: [u8; 32], // 32 bytes
 
   /// The nth count of payments on this chain at the point this payment
   /// was made.
@@ -30,11 +30,15 @@ pub struct UserPayment {
 
   /// The amount and token that the payer paid
   pub details: TokenAndAmount, // TokenAndAmount::SPACE
+
+  /// The wallet that paid the rent of this receipt and of its chain-level
+  /// reference. Gets it back when they are archived.
+  pub rent_payer: Pubkey, // 32 bytes
 }
 
 impl UserPayment {
   // discriminator (8) included
-  pub const SPACE: usize = 2 + (4 * 8) + (2 * 32) + TokenAndAmount::SPACE;
+  pub const SPACE: usize = (4 * 8) + (4 * 32) + TokenAndAmount::SPACE;
 
   /// AKA `b"user_payment"`.
   pub const SEED_PREFIX: &'static [u8] = b"user_payment";
@@ -27,11 +27,24 @@ pub struct Withdrawal {
 
   /// The amount and token that the host withdrew
   pub details: TokenAndAmount, // TokenAndAmount::SPACE
+
+  /// The cbChainId of the chain to which the withdrawn funds were sent.
+  /// This chain's cbChainId if the withdrawal wasn't cross-chain.
+  pub dest_chain_id: [u8; 32], // 32 bytes
+
+  /// The Wormhole-normalized address to which the withdrawn funds were sent.
+  /// The host's wallet if the withdrawal wasn't cross-chain.
+  pub dest_address: [u8; 32], // 32 bytes
+
+  /// The wallet that paid the rent of this receipt, of its chain-level
+  /// reference, and of the payable's reference to it. Gets it back when they
+  /// are archived.
+  pub rent_payer: Pubkey, // 32 bytes
 }
 
 impl Withdrawal {
   // discriminator (8) included
-  pub const SPACE: usize = (5 * 8) + (2 * 32) + TokenAndAmount::SPACE;
+  pub const SPACE: usize = (5 * 8) + (5 * 32) + TokenAndAmount::SPACE;
 
   /// AKA `b"withdrawal"`.
   pub const SEED_PREFIX: &'static [u8] = b"withdrawal";
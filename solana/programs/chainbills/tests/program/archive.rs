@@ -0,0 +1,642 @@
+use crate::{
+  common::*,
+  payables::close_payable_ix,
+  subscriptions::{approve, collect_subscription_ix, create_subscription_ix},
+  withdrawals::{paid_payable, set_operator, withdraw_ix},
+};
+use anchor_lang::{prelude::*, solana_program::instruction::Instruction};
+use chainbills::{error::ChainbillsError, events::*, state::*};
+use solana_sdk::signer::Signer;
+
+/// Archives the payable along with its PayableOperators of the operators.
+pub(crate) fn archive_payable_ix(
+  signer: &Pubkey,
+  payable: Pubkey,
+  operators: &[Pubkey],
+) -> Instruction {
+  let mut ix = ix(
+    chainbills::accounts::ArchivePayable {
+      payable,
+      payable_per_chain_payments_counter: payments_counter_pda(&payable),
+      payable_escrow: pda(&[payable.as_ref(), PayableEscrow::SEED_PREFIX]),
+      allowed_payers: allowed_payers_pda(&payable),
+      payable_receipts: pda(&[payable.as_ref(), PayableReceipts::SEED_PREFIX]),
+      pending_payable_host: pda(&[
+        payable.as_ref(),
+        PendingPayableHost::SEED_PREFIX,
+      ]),
+      config: config_pda(),
+      signer: *signer,
+      event_authority: event_authority(),
+      program: chainbills::ID,
+    },
+    chainbills::instruction::ArchivePayable {},
+  );
+  ix.accounts.extend(
+    operators.iter().map(|operator| {
+      AccountMeta::new(operator_pda(&payable, operator), false)
+    }),
+  );
+  ix
+}
+
+pub(crate) async fn archive_payable_payment_ix(
+  env: &mut Env,
+  signer: &Pubkey,
+  accounts: &PaymentAccounts,
+) -> Instruction {
+  let payable_payment: PayablePayment =
+    env.account(accounts.payable_payment).await;
+  ix(
+    chainbills::accounts::ArchivePayablePayment {
+      payable: accounts.payable,
+      payable_payment: accounts.payable_payment,
+      chain_payable_payment_id: None,
+      chain_payable_payments_page: None,
+      payable_per_chain_payment_info: accounts.payable_per_chain_payment_info,
+      payable_per_chain_payments_page: accounts.payable_per_chain_payments_page,
+      escrow_state: accounts.escrow_state,
+      config: config_pda(),
+      rent_payer: payable_payment.rent_payer,
+      signer: *signer,
+      event_authority: event_authority(),
+      program: chainbills::ID,
+    },
+    chainbills::instruction::ArchivePayablePayment {},
+  )
+}
+
+async fn archive_user_payment_ix(
+  env: &mut Env,
+  signer: &Pubkey,
+  accounts: &PaymentAccounts,
+) -> Instruction {
+  let user_payment: UserPayment = env.account(accounts.user_payment).await;
+  ix(
+    chainbills::accounts::ArchiveUserPayment {
+      user_payment: accounts.user_payment,
+      chain_user_payment_id: None,
+      chain_user_payments_page: None,
+      rent_payer: user_payment.rent_payer,
+      signer: *signer,
+      event_authority: event_authority(),
+      program: chainbills::ID,
+    },
+    chainbills::instruction::ArchiveUserPayment {},
+  )
+}
+
+/// The accounts that archiving the withdrawal closes.
+fn withdrawal_accounts(withdrawal: &Withdrawal) -> (Pubkey, Pubkey) {
+  (
+    pda(&[
+      ChainWithdrawalId::SEED_PREFIX,
+      &withdrawal.chain_count.to_le_bytes(),
+    ]),
+    pda(&[
+      withdrawal.payable_id.as_ref(),
+      PayableWithdrawalInfo::SEED_PREFIX,
+      &withdrawal.payable_count.to_le_bytes(),
+    ]),
+  )
+}
+
+async fn archive_withdrawal_ix(
+  env: &mut Env,
+  signer: &Pubkey,
+  withdrawal: Pubkey,
+) -> Instruction {
+  let data: Withdrawal = env.account(withdrawal).await;
+  let (chain_withdrawal_id, payable_withdrawal_info) =
+    withdrawal_accounts(&data);
+  ix(
+    chainbills::accounts::ArchiveWithdrawal {
+      withdrawal,
+      chain_withdrawal_id,
+      payable_withdrawal_info,
+      rent_payer: data.rent_payer,
+      signer: *signer,
+      event_authority: event_authority(),
+      program: chainbills::ID,
+    },
+    chainbills::instruction::ArchiveWithdrawal {},
+  )
+}
+
+/// Archives the user's activity with the user count, which is the payable's
+/// activity too if the payable is given. A payment's activity has neither an
+/// info nor a ledger page entry.
+async fn archive_user_activity_ix(
+  env: &mut Env,
+  signer: &Pubkey,
+  user_count: u64,
+  payable: Option<Pubkey>,
+) -> Instruction {
+  let page_prefix: &[&[u8]] = &[signer.as_ref(), ActivityRecord::SEED_PREFIX];
+  let info = activity_info_pda(signer, user_count);
+  let page = ledger_page_pda(page_prefix, user_count);
+  let payment_activity = payment_activity_pda(signer, user_count);
+  let (info, page, address) = if env.exists(payment_activity).await {
+    (None, None, payment_activity)
+  } else if env.exists(info).await {
+    let chain_count = env.account::<UserActivityInfo>(info).await.chain_count;
+    (Some(info), None, activity_pda(chain_count))
+  } else {
+    let ledger: CountLedgerPage = env.account(page).await;
+    (
+      None,
+      Some(page),
+      activity_pda(ledger.get(user_count).unwrap()),
+    )
+  };
+  let activity: ActivityRecord = env.account(address).await;
+  ix(
+    chainbills::accounts::ArchiveUserActivity {
+      user_activity_info: info,
+      user_activities_page: page,
+      activity: address,
+      payable_activity_info: payable
+        .map(|payable| activity_info_pda(&payable, activity.payable_count)),
+      rent_payer: activity.rent_payer,
+      signer: *signer,
+      event_authority: event_authority(),
+      program: chainbills::ID,
+    },
+    chainbills::instruction::ArchiveUserActivity { user_count },
+  )
+}
+
+#[tokio::test]
+async fn archive_payable() {
+  let mut env = Env::new().await;
+  let mint = env.supported_mint(6).await;
+  let host = env.new_user().await;
+  let stranger = env.new_user().await;
+  let payable = env.create_payable(&host, vec![]).await;
+
+  // Only closed payables can be archived, by their hosts.
+  let ix = archive_payable_ix(&host.pubkey(), payable, &[]);
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::PayableIsNotClosed,
+  );
+  let ix = close_payable_ix(&mut env, &host.pubkey(), payable, false).await;
+  env.send(&[ix], &[&host]).await.unwrap();
+  let ix = archive_payable_ix(&stranger.pubkey(), payable, &[]);
+  assert_error(
+    env.send(&[ix], &[&stranger]).await,
+    ChainbillsError::NotYourPayable,
+  );
+
+  // The payable's other accounts go with it, and so do its operators.
+  let operator = env.new_user().await;
+  set_operator(&mut env, payable, host.pubkey(), operator.pubkey(), 1);
+  let allowed_payers = allowed_payers_pda(&payable);
+  env.set_account(allowed_payers, &PayableAllowedPayers { root: [1; 32] });
+  let payable_receipts = pda(&[payable.as_ref(), PayableReceipts::SEED_PREFIX]);
+  env.set_account(payable_receipts, &PayableReceipts { enabled: true });
+  let pending_payable_host =
+    pda(&[payable.as_ref(), PendingPayableHost::SEED_PREFIX]);
+  env.set_account(
+    pending_payable_host,
+    &PendingPayableHost {
+      new_host: stranger.pubkey(),
+    },
+  );
+  let side_accounts = [
+    operator_pda(&payable, &operator.pubkey()),
+    allowed_payers,
+    payable_receipts,
+    pending_payable_host,
+  ];
+
+  // Only the payable's operators can be passed.
+  let other_payable = env.create_payable(&host, vec![]).await;
+  set_operator(&mut env, other_payable, host.pubkey(), operator.pubkey(), 1);
+  let mut ix = archive_payable_ix(&host.pubkey(), payable, &[]);
+  ix.accounts.push(AccountMeta::new(
+    operator_pda(&other_payable, &operator.pubkey()),
+    false,
+  ));
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::NonPayableOperatorAccountProvided,
+  );
+
+  let payable_data = env.payable(payable).await;
+  let mut rent = env.lamports(payable).await
+    + env.lamports(payments_counter_pda(&payable)).await;
+  for account in side_accounts {
+    rent += env.lamports(account).await;
+  }
+  let host_lamports = env.lamports(host.pubkey()).await;
+  let ix = archive_payable_ix(&host.pubkey(), payable, &[operator.pubkey()]);
+  let outcome = env.send(&[ix], &[&host]).await.unwrap();
+  let event = outcome.event::<ArchivedPayable>();
+  assert_eq!(event.payable_id, payable);
+  assert_eq!(event.host_wallet, host.pubkey());
+  assert_eq!(event.chain_count, payable_data.chain_count);
+  assert_eq!(event.activities_count, payable_data.activities_count);
+  assert!(!env.exists(payable).await);
+  assert!(!env.exists(payments_counter_pda(&payable)).await);
+  for account in side_accounts {
+    assert!(!env.exists(account).await);
+  }
+  assert_eq!(env.lamports(host.pubkey()).await, host_lamports + rent);
+
+  // Payables with balances can't be archived.
+  let payer = env.new_user().await;
+  env.mint_to(&mint, &payer.pubkey(), 1_000).await;
+  let payable = env.create_payable(&host, vec![]).await;
+  env.pay(&payer, payable, &mint, 1_000).await.unwrap();
+  let ix = close_payable_ix(&mut env, &host.pubkey(), payable, false).await;
+  env.send(&[ix], &[&host]).await.unwrap();
+  let ix = archive_payable_ix(&host.pubkey(), payable, &[]);
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::PayableHasBalances,
+  );
+}
+
+#[tokio::test]
+async fn archive_user_payment() {
+  let mut env = Env::new().await;
+  let mint = env.supported_mint(6).await;
+  let host = env.new_user().await;
+  let payer = env.new_user().await;
+  env.mint_to(&mint, &payer.pubkey(), 1_000).await;
+  let payable = env.create_payable(&host, vec![]).await;
+  let accounts = env.payment_accounts(&payer.pubkey(), payable).await;
+  env.pay(&payer, payable, &mint, 1_000).await.unwrap();
+  let user_payment: UserPayment = env.account(accounts.user_payment).await;
+
+  let ix = archive_user_payment_ix(&mut env, &host.pubkey(), &accounts).await;
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::NotYourPayment,
+  );
+
+  assert_eq!(user_payment.rent_payer, payer.pubkey());
+  let rent = env.lamports(accounts.user_payment).await;
+  let payer_lamports = env.lamports(payer.pubkey()).await;
+  let ix = archive_user_payment_ix(&mut env, &payer.pubkey(), &accounts).await;
+  let outcome = env.send(&[ix], &[&payer]).await.unwrap();
+  let event = outcome.event::<ArchivedUserPayment>();
+  assert_eq!(event.payment_id, accounts.user_payment);
+  assert_eq!(event.payable_id, payable.to_bytes());
+  assert_eq!(event.payer_wallet, payer.pubkey());
+  assert_eq!(event.paid_at, user_payment.timestamp);
+  assert_eq!(event.details.amount, 1_000);
+  assert!(!env.exists(accounts.user_payment).await);
+  assert_eq!(env.lamports(payer.pubkey()).await, payer_lamports + rent);
+  // The payable's side of the payment stays.
+  assert!(env.exists(accounts.payable_payment).await);
+}
+
+#[tokio::test]
+async fn archive_payable_payment() {
+  let mut env = Env::new().await;
+  let mint = env.supported_mint(6).await;
+  let host = env.new_user().await;
+  let payer = env.new_user().await;
+  env.mint_to(&mint, &payer.pubkey(), 2_000).await;
+  let payable = env.create_payable(&host, vec![]).await;
+  let accounts = env.payment_accounts(&payer.pubkey(), payable).await;
+  env.pay(&payer, payable, &mint, 1_000).await.unwrap();
+  let payable_payment: PayablePayment =
+    env.account(accounts.payable_payment).await;
+
+  let ix =
+    archive_payable_payment_ix(&mut env, &payer.pubkey(), &accounts).await;
+  assert_error(
+    env.send(&[ix], &[&payer]).await,
+    ChainbillsError::NotYourPayable,
+  );
+
+  assert_eq!(payable_payment.rent_payer, payer.pubkey());
+  let rent = env.lamports(accounts.payable_payment).await
+    + env
+      .lamports(accounts.payable_per_chain_payment_info.unwrap())
+      .await;
+  let payer_lamports = env.lamports(payer.pubkey()).await;
+  let ix =
+    archive_payable_payment_ix(&mut env, &host.pubkey(), &accounts).await;
+  let outcome = env.send(&[ix], &[&host]).await.unwrap();
+  let event = outcome.event::<ArchivedPayablePayment>();
+  assert_eq!(event.payment_id, accounts.payable_payment);
+  assert_eq!(event.payable_id, payable);
+  assert_eq!(event.payer_wallet, payer.pubkey().to_bytes());
+  assert_eq!(event.local_chain_count, 1);
+  assert_eq!(event.paid_at, payable_payment.timestamp);
+  assert_eq!(event.details.amount, 1_000);
+  assert!(!env.exists(accounts.payable_payment).await);
+  assert!(
+    !env
+      .exists(accounts.payable_per_chain_payment_info.unwrap())
+      .await
+  );
+  assert_eq!(env.lamports(payer.pubkey()).await, payer_lamports + rent);
+  // The payer's side of the payment stays.
+  assert!(env.exists(accounts.user_payment).await);
+
+  // The payable's reference in its ledger page is cleared instead.
+  env.paged = true;
+  let accounts = env.payment_accounts(&payer.pubkey(), payable).await;
+  env.pay(&payer, payable, &mint, 1_000).await.unwrap();
+  let ix =
+    archive_payable_payment_ix(&mut env, &host.pubkey(), &accounts).await;
+  env.send(&[ix], &[&host]).await.unwrap();
+  assert!(!env.exists(accounts.payable_payment).await);
+  let page: CountLedgerPage = env
+    .account(accounts.payable_per_chain_payments_page.unwrap())
+    .await;
+  assert_eq!(page.get(2), None);
+}
+
+#[tokio::test]
+async fn archive_withdrawal() {
+  let mut env = Env::new().await;
+  let mint = env.supported_mint(6).await;
+  let host = env.new_user().await;
+  let payer = env.new_user().await;
+  env.mint_to(&mint, &payer.pubkey(), 1_000).await;
+  env.mint_to(&mint, &host.pubkey(), 0).await;
+  let payable = env.create_payable(&host, vec![]).await;
+  env.pay(&payer, payable, &mint, 1_000).await.unwrap();
+  let ix = withdraw_ix(&mut env, &host.pubkey(), payable, &mint, 1_000).await;
+  env.send(&[ix], &[&host]).await.unwrap();
+  let withdrawal = pda(&[
+    host.pubkey().as_ref(),
+    Withdrawal::SEED_PREFIX,
+    &1u64.to_le_bytes(),
+  ]);
+  let (chain_withdrawal_id, payable_withdrawal_info) =
+    withdrawal_accounts(&env.account(withdrawal).await);
+  assert_eq!(
+    chain_withdrawal_id,
+    pda(&[ChainWithdrawalId::SEED_PREFIX, &sharded(1).to_le_bytes()])
+  );
+
+  let ix = archive_withdrawal_ix(&mut env, &payer.pubkey(), withdrawal).await;
+  assert_error(
+    env.send(&[ix], &[&payer]).await,
+    ChainbillsError::NotYourWithdrawal,
+  );
+
+  let rent = env.lamports(withdrawal).await
+    + env.lamports(chain_withdrawal_id).await
+    + env.lamports(payable_withdrawal_info).await;
+  let host_lamports = env.lamports(host.pubkey()).await;
+  let ix = archive_withdrawal_ix(&mut env, &host.pubkey(), withdrawal).await;
+  let outcome = env.send(&[ix], &[&host]).await.unwrap();
+  let event = outcome.event::<ArchivedWithdrawal>();
+  assert_eq!(event.withdrawal_id, withdrawal);
+  assert_eq!(event.payable_id, payable);
+  assert_eq!(event.host_wallet, host.pubkey());
+  assert_eq!(event.details.amount, 1_000);
+  assert_eq!(event.dest_chain_id, solana_cb_chain_id());
+  assert!(!env.exists(withdrawal).await);
+  assert!(!env.exists(chain_withdrawal_id).await);
+  // The payable no longer refers to the withdrawal.
+  assert!(!env.exists(payable_withdrawal_info).await);
+  assert_eq!(env.lamports(host.pubkey()).await, host_lamports + rent);
+}
+
+#[tokio::test]
+async fn archive_user_activity() {
+  let mut env = Env::new().await;
+  let wallet = env.new_user().await;
+  let other = env.new_user().await;
+  let info: UserActivityInfo =
+    env.account(activity_info_pda(&wallet.pubkey(), 1)).await;
+
+  // Users archive only their own activities.
+  let mut ix =
+    archive_user_activity_ix(&mut env, &other.pubkey(), 1, None).await;
+  swap_account(
+    &mut ix,
+    activity_info_pda(&other.pubkey(), 1),
+    activity_info_pda(&wallet.pubkey(), 1),
+  );
+  assert!(env.send(&[ix], &[&other]).await.is_err());
+
+  let ix = archive_user_activity_ix(&mut env, &wallet.pubkey(), 1, None).await;
+  let outcome = env.send(&[ix], &[&wallet]).await.unwrap();
+  let event = outcome.event::<ArchivedUserActivity>();
+  assert_eq!(event.activity_id, activity_pda(info.chain_count));
+  assert_eq!(event.wallet, wallet.pubkey());
+  assert_eq!(event.chain_count, info.chain_count);
+  assert_eq!(event.user_count, 1);
+  assert_eq!(event.entity, wallet.pubkey());
+  assert!(matches!(event.activity_type, ActivityType::InitializedUser));
+  assert!(!env.exists(activity_info_pda(&wallet.pubkey(), 1)).await);
+  assert!(!env.exists(activity_pda(info.chain_count)).await);
+  // The user's count of activities stays as it was.
+  assert_eq!(env.user(&wallet.pubkey()).await.activities_count, 1);
+}
+
+#[tokio::test]
+async fn archive_user_payment_activity() {
+  let mut env = Env::new().await;
+  let mint = env.supported_mint(6).await;
+  let host = env.new_user().await;
+  let payer = env.new_user().await;
+  env.mint_to(&mint, &payer.pubkey(), 1_000).await;
+  let payable = env.create_payable(&host, vec![]).await;
+  let accounts = env.payment_accounts(&payer.pubkey(), payable).await;
+  env.pay(&payer, payable, &mint, 1_000).await.unwrap();
+
+  // The payment was the payer's 2nd activity, after initializing, and is
+  // derived from the payer's count instead of having an info.
+  assert_eq!(
+    accounts.user_activity,
+    payment_activity_pda(&payer.pubkey(), 2)
+  );
+  assert!(!env.exists(activity_info_pda(&payer.pubkey(), 2)).await);
+  let activity: ActivityRecord = env.account(accounts.user_activity).await;
+
+  // Others can't archive it, as it isn't derived from their wallets.
+  let mut ix =
+    archive_user_activity_ix(&mut env, &payer.pubkey(), 2, None).await;
+  let signer = ix.accounts.iter_mut().find(|meta| meta.is_signer).unwrap();
+  signer.pubkey = host.pubkey();
+  assert!(env.send(&[ix], &[&host]).await.is_err());
+
+  let rent = env.lamports(accounts.user_activity).await;
+  let payer_lamports = env.lamports(payer.pubkey()).await;
+  let ix = archive_user_activity_ix(&mut env, &payer.pubkey(), 2, None).await;
+  let outcome = env.send(&[ix], &[&payer]).await.unwrap();
+  let event = outcome.event::<ArchivedUserActivity>();
+  assert_eq!(event.activity_id, accounts.user_activity);
+  assert_eq!(event.chain_count, activity.chain_count);
+  assert_eq!(event.user_count, 2);
+  assert!(matches!(event.activity_type, ActivityType::UserPaid));
+  assert!(!env.exists(accounts.user_activity).await);
+  assert_eq!(env.lamports(payer.pubkey()).await, payer_lamports + rent);
+  // The payable's activity of the payment stays.
+  assert!(env.exists(accounts.payable_activity).await);
+}
+
+#[tokio::test]
+async fn archive_payable_activity() {
+  let mut env = Env::new().await;
+  let host = env.new_user().await;
+  let payable = env.create_payable(&host, vec![]).await;
+  // Creating the payable was the host's 2nd activity and the payable's 1st.
+  let info: UserActivityInfo =
+    env.account(activity_info_pda(&host.pubkey(), 2)).await;
+  let payable_activity_info = activity_info_pda(&payable, 1);
+  let activity: ActivityRecord =
+    env.account(activity_pda(info.chain_count)).await;
+  assert_eq!(activity.payable_count, 1);
+  assert_eq!(activity.rent_payer, host.pubkey());
+
+  // The payable's info of the activity must go with it.
+  let ix = archive_user_activity_ix(&mut env, &host.pubkey(), 2, None).await;
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::InvalidPayableActivityInfo,
+  );
+  // The info of another activity doesn't count.
+  let mut ix =
+    archive_user_activity_ix(&mut env, &host.pubkey(), 2, Some(payable)).await;
+  let close = close_payable_ix(&mut env, &host.pubkey(), payable, false).await;
+  env.send(&[close], &[&host]).await.unwrap();
+  swap_account(
+    &mut ix,
+    payable_activity_info,
+    activity_info_pda(&payable, 2),
+  );
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::InvalidPayableActivityInfo,
+  );
+
+  let rent = env.lamports(activity_pda(info.chain_count)).await
+    + env.lamports(activity_info_pda(&host.pubkey(), 2)).await
+    + env.lamports(payable_activity_info).await;
+  let host_lamports = env.lamports(host.pubkey()).await;
+  let ix =
+    archive_user_activity_ix(&mut env, &host.pubkey(), 2, Some(payable)).await;
+  let outcome = env.send(&[ix], &[&host]).await.unwrap();
+  let event = outcome.event::<ArchivedUserActivity>();
+  assert!(matches!(event.activity_type, ActivityType::CreatedPayable));
+  assert_eq!(event.payable_count, 1);
+  assert!(!env.exists(activity_pda(info.chain_count)).await);
+  assert!(!env.exists(payable_activity_info).await);
+  assert_eq!(env.lamports(host.pubkey()).await, host_lamports + rent);
+}
+
+#[tokio::test]
+async fn archive_refunds_operators() {
+  let mut env = Env::new().await;
+  let (host, payable, mint) = paid_payable(&mut env, 1_000).await;
+  let operator = env.new_user().await;
+  set_operator(
+    &mut env,
+    payable,
+    host.pubkey(),
+    operator.pubkey(),
+    PayableOperator::ROLE_WITHDRAW,
+  );
+  let ix =
+    withdraw_ix(&mut env, &operator.pubkey(), payable, &mint, 1_000).await;
+  env.send(&[ix], &[&operator]).await.unwrap();
+  let withdrawal = pda(&[
+    host.pubkey().as_ref(),
+    Withdrawal::SEED_PREFIX,
+    &1u64.to_le_bytes(),
+  ]);
+  let data: Withdrawal = env.account(withdrawal).await;
+  assert_eq!(data.rent_payer, operator.pubkey());
+
+  // The host archives the withdrawal's activity, its 3rd after initializing
+  // and creating the payable, and the operator gets back the rent it paid.
+  let info = activity_info_pda(&host.pubkey(), 3);
+  let chain_count = env.account::<UserActivityInfo>(info).await.chain_count;
+  let activity: ActivityRecord = env.account(activity_pda(chain_count)).await;
+  assert!(matches!(activity.activity_type, ActivityType::Withdrew));
+  assert_eq!(activity.rent_payer, operator.pubkey());
+  let payable_activity_info =
+    activity_info_pda(&payable, activity.payable_count);
+  let rent = env.lamports(activity_pda(chain_count)).await
+    + env.lamports(info).await
+    + env.lamports(payable_activity_info).await;
+  let host_lamports = env.lamports(host.pubkey()).await;
+  let operator_lamports = env.lamports(operator.pubkey()).await;
+  let ix =
+    archive_user_activity_ix(&mut env, &host.pubkey(), 3, Some(payable)).await;
+  env.send(&[ix], &[&host]).await.unwrap();
+  assert_eq!(
+    env.lamports(operator.pubkey()).await,
+    operator_lamports + rent
+  );
+  assert_eq!(env.lamports(host.pubkey()).await, host_lamports);
+
+  // Then the withdrawal itself.
+  let (chain_withdrawal_id, payable_withdrawal_info) =
+    withdrawal_accounts(&data);
+  let rent = env.lamports(withdrawal).await
+    + env.lamports(chain_withdrawal_id).await
+    + env.lamports(payable_withdrawal_info).await;
+  let operator_lamports = env.lamports(operator.pubkey()).await;
+  // The rent goes only to whoever paid it.
+  let mut ix =
+    archive_withdrawal_ix(&mut env, &host.pubkey(), withdrawal).await;
+  swap_account(&mut ix, operator.pubkey(), host.pubkey());
+  assert!(env.send(&[ix], &[&host]).await.is_err());
+  let ix = archive_withdrawal_ix(&mut env, &host.pubkey(), withdrawal).await;
+  env.send(&[ix], &[&host]).await.unwrap();
+  assert_eq!(
+    env.lamports(operator.pubkey()).await,
+    operator_lamports + rent
+  );
+  assert_eq!(env.lamports(host.pubkey()).await, host_lamports);
+}
+
+#[tokio::test]
+async fn archive_refunds_subscription_collectors() {
+  let mut env = Env::new().await;
+  let mint = env.supported_mint(6).await;
+  let host = env.new_user().await;
+  let payer = env.new_user().await;
+  let collector = env.wallet().await;
+  env.mint_to(&mint, &payer.pubkey(), 1_000).await;
+  let payable = env.create_payable(&host, vec![]).await;
+  let ix = create_subscription_ix(
+    &mut env,
+    &payer.pubkey(),
+    payable,
+    &mint,
+    1_000,
+    60,
+    None,
+  )
+  .await;
+  env.send(&[ix], &[&payer]).await.unwrap();
+  approve(&mut env, &payer, &mint, 1_000).await;
+  let accounts = env.payment_accounts(&payer.pubkey(), payable).await;
+  let ix = collect_subscription_ix(
+    &mut env,
+    &collector.pubkey(),
+    &payer.pubkey(),
+    payable,
+    &mint,
+  )
+  .await;
+  env.send(&[ix], &[&collector]).await.unwrap();
+  let user_payment: UserPayment = env.account(accounts.user_payment).await;
+  assert_eq!(user_payment.payer, payer.pubkey());
+  assert_eq!(user_payment.rent_payer, collector.pubkey());
+
+  let rent = env.lamports(accounts.user_payment).await;
+  let payer_lamports = env.lamports(payer.pubkey()).await;
+  let collector_lamports = env.lamports(collector.pubkey()).await;
+  let ix = archive_user_payment_ix(&mut env, &payer.pubkey(), &accounts).await;
+  env.send(&[ix], &[&payer]).await.unwrap();
+  assert_eq!(
+    env.lamports(collector.pubkey()).await,
+    collector_lamports + rent
+  );
+  assert_eq!(env.lamports(payer.pubkey()).await, payer_lamports);
+}
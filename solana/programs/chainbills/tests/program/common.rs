@@ -0,0 +1,1131 @@
+use anchor_lang::{
+  prelude::*, solana_program::instruction::Instruction, system_program,
+  AccountDeserialize, Discriminator, InstructionData, ToAccountMetas,
+};
+use anchor_spl::{
+  associated_token::{
+    get_associated_token_address, get_associated_token_address_with_program_id,
+    spl_associated_token_account,
+  },
+  token::spl_token,
+  token_2022::spl_token_2022,
+};
+use base64::Engine;
+use chainbills::{error::ChainbillsError, state::*};
+use chainbills_client::{AccountSource, Client};
+use solana_accounts_db::accounts_index::ScanConfig;
+use solana_program_test::programs::spl_programs;
+use solana_runtime::{
+  bank::Bank, bank_forks::BankForks, genesis_utils::create_genesis_config,
+};
+use solana_sdk::{
+  account::{Account, AccountSharedData, ReadableAccount},
+  bpf_loader,
+  clock::MAX_PROCESSING_AGE,
+  compute_budget::ComputeBudgetInstruction,
+  fee_calculator::{FeeRateGovernor, DEFAULT_TARGET_LAMPORTS_PER_SIGNATURE},
+  native_token::sol_to_lamports,
+  program_pack::Pack,
+  signature::Keypair,
+  signer::Signer,
+  system_instruction, sysvar,
+  transaction::Transaction,
+  transaction::TransactionError,
+};
+use std::sync::{Arc, RwLock};
+use wormhole_anchor_sdk::wormhole;
+
+pub const WORMHOLE: Pubkey = wormhole::program::ID;
+
+/// The starting lamports of wallets created with `Env::wallet`.
+pub const WALLET_LAMPORTS: u64 = 100_000_000_000;
+
+/// The maximum withdrawal fees that `Env::support_*` set for tokens.
+pub const MAX_WITHDRAWAL_FEES: u64 = 5_000_000;
+
+/// Reads a BPF program built into the workspace's target/deploy directory.
+fn deployed_program(name: &str) -> Vec<u8> {
+  let path = format!(
+    "{}/../../target/deploy/{name}.so",
+    env!("CARGO_MANIFEST_DIR")
+  );
+  std::fs::read(&path)
+    .unwrap_or_else(|_| panic!("{path} not found. Run `anchor build` first."))
+}
+
+/// An executable account of the BPF loader holding the program.
+fn program_account(data: Vec<u8>) -> AccountSharedData {
+  Account {
+    lamports: 1_000_000_000,
+    data,
+    owner: bpf_loader::id(),
+    executable: true,
+    rent_epoch: 0,
+  }
+  .into()
+}
+
+/// Reads an account fixture that was dumped with `solana account --output
+/// json`, as used by the Anchor test validator.
+fn fixture(name: &str) -> (Pubkey, Account) {
+  let path = format!("{}/../../tests/{name}", env!("CARGO_MANIFEST_DIR"));
+  let json: serde_json::Value =
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+  let account = &json["account"];
+  let data = account["data"][0].as_str().unwrap();
+  (
+    json["pubkey"].as_str().unwrap().parse().unwrap(),
+    Account {
+      lamports: account["lamports"].as_u64().unwrap(),
+      data: base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .unwrap(),
+      owner: account["owner"].as_str().unwrap().parse().unwrap(),
+      executable: account["executable"].as_bool().unwrap(),
+      rent_epoch: account["rentEpoch"].as_u64().unwrap(),
+    },
+  )
+}
+
+pub fn pda(seeds: &[&[u8]]) -> Pubkey {
+  Pubkey::find_program_address(seeds, &chainbills::ID).0
+}
+
+pub fn wormhole_pda(seeds: &[&[u8]]) -> Pubkey {
+  Pubkey::find_program_address(seeds, &WORMHOLE).0
+}
+
+pub fn chain_stats_pda() -> Pubkey {
+  pda(&[ChainStats::SEED_PREFIX])
+}
+
+pub fn chain_shard_pda(index: u8) -> Pubkey {
+  pda(&[ChainStatsShard::SEED_PREFIX, &[index]])
+}
+
+/// The chain count of the nth entity that the builders index in shard 0.
+pub fn sharded(n: u64) -> u64 {
+  ChainStatsShard::base_of(0) + n
+}
+
+pub fn config_pda() -> Pubkey {
+  pda(&[Config::SEED_PREFIX])
+}
+
+pub fn native_vault_pda() -> Pubkey {
+  pda(&[SEED_PREFIX_NATIVE_VAULT])
+}
+
+pub fn event_authority() -> Pubkey {
+  pda(&[b"__event_authority"])
+}
+
+pub fn token_details_pda(token: &Pubkey) -> Pubkey {
+  pda(&[TokenDetails::SEED_PREFIX, token.as_ref()])
+}
+
+pub fn token_details_shard_pda(token: &Pubkey, index: u8) -> Pubkey {
+  pda(&[TokenDetailsShard::SEED_PREFIX, token.as_ref(), &[index]])
+}
+
+pub fn user_pda(wallet: &Pubkey) -> Pubkey {
+  pda(&[wallet.as_ref()])
+}
+
+pub fn activity_pda(count: u64) -> Pubkey {
+  pda(&[ActivityRecord::SEED_PREFIX, &count.to_le_bytes()])
+}
+
+/// The activity of a User (by wallet) or Payable at the given count that a
+/// payment recorded.
+pub fn payment_activity_pda(owner: &Pubkey, count: u64) -> Pubkey {
+  pda(&[
+    ActivityRecord::SEED_PREFIX,
+    owner.as_ref(),
+    &count.to_le_bytes(),
+  ])
+}
+
+/// The activity info of a User or Payable at the given count.
+pub fn activity_info_pda(owner: &Pubkey, count: u64) -> Pubkey {
+  pda(&[
+    owner.as_ref(),
+    ActivityRecord::SEED_PREFIX,
+    &count.to_le_bytes(),
+  ])
+}
+
+/// The ledger page holding the entry of the count, in place of the entry's
+/// own account whose seeds start with the prefix seeds.
+pub fn ledger_page_pda(prefix: &[&[u8]], count: u64) -> Pubkey {
+  let page = ledger_page(count).to_le_bytes();
+  pda(&[prefix, &[SEED_PREFIX_LEDGER_PAGE, &page]].concat())
+}
+
+pub fn payable_pda(host: &Pubkey, count: u64) -> Pubkey {
+  pda(&[host.as_ref(), Payable::SEED_PREFIX, &count.to_le_bytes()])
+}
+
+pub fn payments_counter_pda(payable: &Pubkey) -> Pubkey {
+  pda(&[payable.as_ref(), &wormhole::CHAIN_ID_SOLANA.to_le_bytes()])
+}
+
+pub fn operator_pda(payable: &Pubkey, operator: &Pubkey) -> Pubkey {
+  pda(&[
+    payable.as_ref(),
+    PayableOperator::SEED_PREFIX,
+    operator.as_ref(),
+  ])
+}
+
+pub fn allowed_payers_pda(payable: &Pubkey) -> Pubkey {
+  pda(&[payable.as_ref(), PayableAllowedPayers::SEED_PREFIX])
+}
+
+pub fn receipts_pda(payable: &Pubkey) -> Pubkey {
+  pda(&[payable.as_ref(), PayableReceipts::SEED_PREFIX])
+}
+
+pub fn receipt_mint_pda(user_payment: &Pubkey) -> Pubkey {
+  pda(&[user_payment.as_ref(), Receipt::MINT_SEED_PREFIX])
+}
+
+pub fn token_group_pda(name: &str) -> Pubkey {
+  pda(&[TokenGroup::SEED_PREFIX, name.as_bytes()])
+}
+
+pub fn ata(wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
+  get_associated_token_address(wallet, mint)
+}
+
+/// The wallet's token account of a receipt, whose mint is a Token-2022 one.
+pub fn receipt_ata(wallet: &Pubkey, receipt_mint: &Pubkey) -> Pubkey {
+  get_associated_token_address_with_program_id(
+    wallet,
+    receipt_mint,
+    &spl_token_2022::ID,
+  )
+}
+
+/// Asserts that the result failed with the given error of this program.
+#[track_caller]
+pub fn assert_error<T: std::fmt::Debug>(
+  result: std::result::Result<T, TransactionError>,
+  error: ChainbillsError,
+) {
+  assert_error_code(result, error.into());
+}
+
+/// Asserts that the result failed with the given Anchor error.
+#[track_caller]
+pub fn assert_anchor_error<T: std::fmt::Debug>(
+  result: std::result::Result<T, TransactionError>,
+  error: ErrorCode,
+) {
+  assert_error_code(result, error.into());
+}
+
+#[track_caller]
+pub fn assert_error_code<T: std::fmt::Debug>(
+  result: std::result::Result<T, TransactionError>,
+  code: u32,
+) {
+  match result.unwrap_err() {
+    TransactionError::InstructionError(
+      _,
+      anchor_lang::solana_program::instruction::InstructionError::Custom(
+        actual,
+      ),
+    ) => assert_eq!(actual, code),
+    other => panic!("expected custom error {code}, got {other:?}"),
+  }
+}
+
+/// The events and return data of a successful transaction.
+#[derive(Debug)]
+pub struct Outcome {
+  events: Vec<Vec<u8>>,
+  pub return_data: Vec<u8>,
+}
+
+impl Outcome {
+  /// The first emitted event of the given type.
+  #[track_caller]
+  pub fn event<E: Discriminator + AnchorDeserialize>(&self) -> E {
+    self.events::<E>().into_iter().next().unwrap_or_else(|| {
+      panic!("event {} not emitted", std::any::type_name::<E>())
+    })
+  }
+
+  /// All emitted events of the given type, in order.
+  pub fn events<E: Discriminator + AnchorDeserialize>(&self) -> Vec<E> {
+    self
+      .events
+      .iter()
+      .filter_map(|data| data.strip_prefix(&E::DISCRIMINATOR))
+      .map(|mut data| E::deserialize(&mut data).unwrap())
+      .collect()
+  }
+
+  /// The instruction's return value.
+  pub fn returned<T: AnchorDeserialize>(&self) -> T {
+    T::try_from_slice(&self.return_data).unwrap()
+  }
+}
+
+/// Gives clients the accounts of the bank.
+pub struct BankSource(Arc<RwLock<BankForks>>);
+
+impl AccountSource for BankSource {
+  fn get_accounts(
+    &self,
+    addresses: &[Pubkey],
+  ) -> chainbills_client::Result<Vec<Option<Account>>> {
+    let bank = self.0.read().unwrap().working_bank();
+    Ok(
+      addresses
+        .iter()
+        .map(|address| bank.get_account(address).map(Account::from))
+        .collect(),
+    )
+  }
+
+  fn get_program_accounts(
+    &self,
+    program: &Pubkey,
+    prefix: &[u8],
+  ) -> chainbills_client::Result<Vec<(Pubkey, Account)>> {
+    let bank = self.0.read().unwrap().working_bank();
+    let accounts = bank
+      .get_filtered_program_accounts(
+        program,
+        |account| account.data().starts_with(prefix),
+        &ScanConfig::default(),
+      )
+      .unwrap();
+    Ok(
+      accounts
+        .into_iter()
+        .map(|(address, account)| (address, Account::from(account)))
+        .collect(),
+    )
+  }
+}
+
+/// An in-process bank with the program's BPF build, Wormhole, and SPL
+/// programs loaded.
+pub struct Env {
+  bank_forks: Arc<RwLock<BankForks>>,
+  /// Pays the fees of transactions, and initializes the program as its owner.
+  payer: Keypair,
+  pub fee_collector: Pubkey,
+  /// The shard of the chain's counts that built instructions index in.
+  pub shard: u8,
+  /// Whether built payments record their index entries in ledger pages.
+  pub paged: bool,
+  nonce: u32,
+}
+
+impl Env {
+  /// A bank where the program hasn't been initialized.
+  pub async fn uninitialized() -> Self {
+    let mut genesis = create_genesis_config(sol_to_lamports(1_000_000.0));
+    genesis.genesis_config.rent = Rent::default();
+    genesis.genesis_config.fee_rate_governor = FeeRateGovernor {
+      lamports_per_signature: DEFAULT_TARGET_LAMPORTS_PER_SIGNATURE / 2,
+      ..FeeRateGovernor::default()
+    };
+    let bank = Bank::new_for_tests(&genesis.genesis_config);
+    for (address, account) in spl_programs(&Rent::default()) {
+      bank.store_account(&address, &account);
+    }
+    bank.store_account(
+      &chainbills::ID,
+      &program_account(deployed_program("chainbills")),
+    );
+    let wormhole_so = std::fs::read(format!(
+      "{}/../../tests/wormhole.so",
+      env!("CARGO_MANIFEST_DIR")
+    ))
+    .unwrap();
+    bank.store_account(&WORMHOLE, &program_account(wormhole_so));
+    for name in ["wormhole-bridge.json", "wormhole-fee-collector.json"] {
+      let (address, account) = fixture(name);
+      bank.store_account(&address, &account);
+    }
+    bank.set_capitalization();
+
+    // Start past the genesis slot, as validators do.
+    let parent = Arc::new(bank);
+    parent.fill_bank_with_ticks_for_tests();
+    let bank = Bank::new_from_parent(parent.clone(), parent.collector_id(), 1);
+    Self {
+      bank_forks: BankForks::new_rw_arc(bank),
+      payer: genesis.mint_keypair,
+      fee_collector: Keypair::new().pubkey(),
+      shard: 0,
+      paged: false,
+      nonce: 0,
+    }
+  }
+
+  /// A bank where the program and all its chain shards have been
+  /// initialized by its payer, who is the owner.
+  pub async fn new() -> Self {
+    let mut env = Self::uninitialized().await;
+    let ix = env.initialize_ix(&env.owner());
+    env.send(&[ix], &[]).await.unwrap();
+    let owner = env.owner();
+    let shards: Vec<Instruction> = (0..ChainStatsShard::COUNT)
+      .map(|index| initialize_chain_shard_ix(&owner, index))
+      .collect();
+    for chunk in shards.chunks(8) {
+      env.send(chunk, &[]).await.unwrap();
+    }
+    env
+  }
+
+  pub fn owner(&self) -> Pubkey {
+    self.payer.pubkey()
+  }
+
+  fn bank(&self) -> Arc<Bank> {
+    self.bank_forks.read().unwrap().working_bank()
+  }
+
+  /// A client of the program that reads the bank, building paged payments
+  /// if the env does.
+  pub fn client(&self) -> Client<BankSource> {
+    Client::new(BankSource(self.bank_forks.clone()))
+      .with_ledger_pages(self.paged)
+  }
+
+  /// Signs and processes the instructions, with the owner paying fees. Each
+  /// transaction gets a distinct compute limit so that retrying an identical
+  /// one isn't rejected as already processed.
+  pub async fn send(
+    &mut self,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+  ) -> std::result::Result<Outcome, TransactionError> {
+    self.nonce += 1;
+    let mut all = vec![ComputeBudgetInstruction::set_compute_unit_limit(
+      1_400_000 - self.nonce,
+    )];
+    all.extend_from_slice(instructions);
+    let mut keypairs = vec![&self.payer];
+    keypairs.extend_from_slice(signers);
+    let bank = self.bank();
+    let tx = Transaction::new_signed_with_payer(
+      &all,
+      Some(&self.payer.pubkey()),
+      &keypairs,
+      bank.last_blockhash(),
+    );
+    let tx = bank.fully_verify_transaction(tx.into())?;
+    let batch = bank.prepare_sanitized_batch(std::slice::from_ref(&tx));
+    let (results, _) = bank.load_execute_and_commit_transactions(
+      &batch,
+      MAX_PROCESSING_AGE,
+      false, // collect_balances
+      true,  // enable_cpi_recording, for the events
+      true,  // enable_log_recording
+      true,  // enable_return_data_recording
+      &mut Default::default(),
+      None,
+    );
+    let result = &results.execution_results[0];
+    // Shift instruction indices to exclude the compute budget instruction.
+    result.flattened_result().map_err(|err| match err {
+      TransactionError::InstructionError(i, e) => {
+        TransactionError::InstructionError(i - 1, e)
+      }
+      other => other,
+    })?;
+
+    // The program emits events by invoking itself with their data.
+    let details = result.details().unwrap();
+    let keys = tx.message().account_keys();
+    let events = details
+      .inner_instructions
+      .iter()
+      .flatten()
+      .flatten()
+      .filter(|inner| {
+        keys.get(inner.instruction.program_id_index as usize)
+          == Some(&chainbills::ID)
+      })
+      .filter_map(|inner| {
+        inner
+          .instruction
+          .data
+          .strip_prefix(&anchor_lang::event::EVENT_IX_TAG_LE)
+      })
+      .map(|event| event.to_vec())
+      .collect();
+    Ok(Outcome {
+      events,
+      return_data: details
+        .return_data
+        .as_ref()
+        .map(|data| data.data.clone())
+        .unwrap_or_default(),
+    })
+  }
+
+  pub async fn get_account(&mut self, address: Pubkey) -> Option<Account> {
+    self.bank().get_account(&address).map(Account::from)
+  }
+
+  pub async fn exists(&mut self, address: Pubkey) -> bool {
+    self.get_account(address).await.is_some()
+  }
+
+  /// Deserializes the account of this program at the address.
+  pub async fn account<T: AccountDeserialize>(&mut self, address: Pubkey) -> T {
+    let account = self.get_account(address).await.unwrap();
+    T::try_deserialize(&mut account.data.as_slice()).unwrap()
+  }
+
+  /// Stores the data as an account of this program at the address, to set
+  /// up states that instructions can't reach.
+  pub fn set_account(&mut self, address: Pubkey, data: &impl AccountSerialize) {
+    let mut bytes = vec![];
+    data.try_serialize(&mut bytes).unwrap();
+    let lamports = Rent::default().minimum_balance(bytes.len());
+    let mut account = Account::new(lamports, 0, &chainbills::ID);
+    account.data = bytes;
+    self.store_account(&address, &account.into());
+  }
+
+  /// Stores the account at the address as is.
+  pub fn store_account(
+    &mut self,
+    address: &Pubkey,
+    account: &AccountSharedData,
+  ) {
+    self.bank().store_account(address, account);
+  }
+
+  pub fn rent(&self) -> Rent {
+    self.bank().rent_collector().rent
+  }
+
+  pub async fn config(&mut self) -> Config {
+    let account = self.get_account(config_pda()).await.unwrap();
+    bytemuck::pod_read_unaligned(&account.data[8..])
+  }
+
+  pub async fn chain_stats(&mut self) -> ChainStats {
+    self.account(chain_stats_pda()).await
+  }
+
+  /// The chain shard that built instructions index in.
+  pub async fn chain_shard(&mut self) -> ChainStatsShard {
+    self.account(chain_shard_pda(self.shard)).await
+  }
+
+  pub async fn user(&mut self, wallet: &Pubkey) -> User {
+    self.account(user_pda(wallet)).await
+  }
+
+  pub async fn payable(&mut self, payable: Pubkey) -> Payable {
+    self.account(payable).await
+  }
+
+  pub async fn token_details(&mut self, token: &Pubkey) -> TokenDetails {
+    self.account(token_details_pda(token)).await
+  }
+
+  /// The token's shard of payment totals that goes with the chain shard.
+  pub async fn token_details_shard(
+    &mut self,
+    token: &Pubkey,
+  ) -> TokenDetailsShard {
+    self
+      .account(token_details_shard_pda(token, self.shard))
+      .await
+  }
+
+  pub async fn lamports(&mut self, address: Pubkey) -> u64 {
+    self.get_account(address).await.map_or(0, |a| a.lamports)
+  }
+
+  pub async fn token_balance(&mut self, address: Pubkey) -> u64 {
+    match self.get_account(address).await {
+      Some(account) => {
+        spl_token::state::Account::unpack(&account.data)
+          .unwrap()
+          .amount
+      }
+      None => 0,
+    }
+  }
+
+  /// The current on-chain time.
+  pub async fn now(&mut self) -> u64 {
+    self.bank().clock().unix_timestamp as u64
+  }
+
+  /// Moves the on-chain time forward by the given seconds.
+  pub async fn advance_time(&mut self, seconds: u64) {
+    let bank = self.bank();
+    let mut clock = bank.clock();
+    clock.unix_timestamp += seconds as i64;
+    bank.set_sysvar_for_tests(&clock);
+  }
+
+  /// A new wallet funded with WALLET_LAMPORTS.
+  pub async fn wallet(&mut self) -> Keypair {
+    let wallet = Keypair::new();
+    self.fund(&wallet.pubkey(), WALLET_LAMPORTS).await;
+    wallet
+  }
+
+  pub async fn fund(&mut self, address: &Pubkey, lamports: u64) {
+    let ix = system_instruction::transfer(&self.owner(), address, lamports);
+    self.send(&[ix], &[]).await.unwrap();
+  }
+
+  /// A new funded wallet with an initialized User.
+  pub async fn new_user(&mut self) -> Keypair {
+    let wallet = self.wallet().await;
+    let ix = self.initialize_user_ix(&wallet.pubkey()).await;
+    self.send(&[ix], &[&wallet]).await.unwrap();
+    wallet
+  }
+
+  /// A new SPL token mint whose authority is the owner.
+  pub async fn create_mint(&mut self, decimals: u8) -> Pubkey {
+    let mint = Keypair::new();
+    let rent = self.rent();
+    let instructions = [
+      system_instruction::create_account(
+        &self.owner(),
+        &mint.pubkey(),
+        rent.minimum_balance(spl_token::state::Mint::LEN),
+        spl_token::state::Mint::LEN as u64,
+        &spl_token::ID,
+      ),
+      spl_token::instruction::initialize_mint2(
+        &spl_token::ID,
+        &mint.pubkey(),
+        &self.owner(),
+        None,
+        decimals,
+      )
+      .unwrap(),
+    ];
+    self.send(&instructions, &[&mint]).await.unwrap();
+    mint.pubkey()
+  }
+
+  /// Mints the amount of the token to the wallet's associated token account,
+  /// creating the account if needed.
+  pub async fn mint_to(&mut self, mint: &Pubkey, wallet: &Pubkey, amount: u64) {
+    let instructions = [
+      spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+        &self.owner(),
+        wallet,
+        mint,
+        &spl_token::ID,
+      ),
+      spl_token::instruction::mint_to(
+        &spl_token::ID,
+        mint,
+        &ata(wallet, mint),
+        &self.owner(),
+        &[],
+        amount,
+      )
+      .unwrap(),
+    ];
+    self.send(&instructions, &[]).await.unwrap();
+  }
+
+  /// A new supported SPL token.
+  pub async fn supported_mint(&mut self, decimals: u8) -> Pubkey {
+    let mint = self.create_mint(decimals).await;
+    let ix = self.update_max_withdrawal_fees_ix(&mint, MAX_WITHDRAWAL_FEES);
+    self.send(&[ix], &[]).await.unwrap();
+    mint
+  }
+
+  /// Makes native SOL a supported token.
+  pub async fn support_native(&mut self) {
+    let ix = ix(
+      chainbills::accounts::UpdateMaxWithdrawalFeesNative {
+        token_details: token_details_pda(&chainbills::ID),
+        native_vault: native_vault_pda(),
+        config: config_pda(),
+        owner: self.owner(),
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::UpdateMaxWithdrawalFeesNative {
+        max_withdrawal_fees: MAX_WITHDRAWAL_FEES,
+      },
+    );
+    self.send(&[ix], &[]).await.unwrap();
+  }
+
+  pub fn update_max_withdrawal_fees_ix(
+    &self,
+    mint: &Pubkey,
+    max_withdrawal_fees: u64,
+  ) -> Instruction {
+    ix(
+      chainbills::accounts::UpdateMaxWithdrawalFees {
+        token_details: token_details_pda(mint),
+        chain_token_account: ata(&chain_stats_pda(), mint),
+        fee_collector: self.fee_collector,
+        fees_token_account: ata(&self.fee_collector, mint),
+        config: config_pda(),
+        chain_stats: chain_stats_pda(),
+        mint: *mint,
+        owner: self.owner(),
+        associated_token_program: spl_associated_token_account::ID,
+        token_program: spl_token::ID,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::UpdateMaxWithdrawalFees {
+        token: *mint,
+        max_withdrawal_fees,
+      },
+    )
+  }
+
+  pub fn initialize_ix(&self, owner: &Pubkey) -> Instruction {
+    let emitter = pda(&[wormhole::SEED_PREFIX_EMITTER]);
+    ix(
+      chainbills::accounts::Initialize {
+        owner: *owner,
+        chain_stats: chain_stats_pda(),
+        config: config_pda(),
+        chainbills_fee_collector: self.fee_collector,
+        wormhole_program: WORMHOLE,
+        wormhole_bridge: wormhole_pda(&[wormhole::BridgeData::SEED_PREFIX]),
+        wormhole_emitter: emitter,
+        wormhole_fee_collector: wormhole_pda(&[
+          wormhole::FeeCollector::SEED_PREFIX,
+        ]),
+        wormhole_sequence: wormhole_pda(&[
+          wormhole::SequenceTracker::SEED_PREFIX,
+          emitter.as_ref(),
+        ]),
+        wormhole_message: pda(&[
+          SEED_PREFIX_SENT,
+          &wormhole::INITIAL_SEQUENCE.to_le_bytes(),
+        ]),
+        clock: sysvar::clock::ID,
+        rent: sysvar::rent::ID,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::Initialize {},
+    )
+  }
+
+  pub async fn initialize_user_ix(&mut self, wallet: &Pubkey) -> Instruction {
+    let chain_shard = self.chain_shard().await;
+    ix(
+      chainbills::accounts::InitializeUser {
+        user: user_pda(wallet),
+        chain_user_address: pda(&[
+          ChainUserAddress::SEED_PREFIX,
+          &chain_shard.next_user().to_le_bytes(),
+        ]),
+        activity: activity_pda(chain_shard.next_activity()),
+        user_activity_info: activity_info_pda(wallet, 1),
+        chain_shard: chain_shard_pda(self.shard),
+        signer: *wallet,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::InitializeUser {},
+    )
+  }
+
+  /// The program's Wormhole accounts for publishing the next message.
+  pub async fn wormhole_accounts(&mut self) -> WormholeAccounts {
+    let config = self.config().await;
+    let sequence: wormhole::SequenceTracker =
+      self.account(config.wormhole_sequence).await;
+    WormholeAccounts {
+      wormhole_program: WORMHOLE,
+      wormhole_bridge: config.wormhole_bridge,
+      wormhole_fee_collector: config.wormhole_fee_collector,
+      wormhole_emitter: config.wormhole_emitter,
+      wormhole_sequence: config.wormhole_sequence,
+      wormhole_message: pda(&[
+        SEED_PREFIX_SENT,
+        &sequence.next_value().to_le_bytes(),
+      ]),
+    }
+  }
+
+  /// The activity accounts that an update of a payable by the signer
+  /// initializes.
+  pub async fn activity_accounts(&mut self, payable: Pubkey) -> Activities {
+    let next_activity = self.chain_shard().await.next_activity();
+    let payable_data = self.payable(payable).await;
+    let host = self.user(&payable_data.host).await;
+    Activities {
+      activity: activity_pda(next_activity),
+      user_activity_info: activity_info_pda(
+        &payable_data.host,
+        host.next_activity(),
+      ),
+      payable_activity_info: activity_info_pda(
+        &payable,
+        payable_data.next_activity(),
+      ),
+      host: user_pda(&payable_data.host),
+      chain_shard: chain_shard_pda(self.shard),
+    }
+  }
+
+  pub async fn create_payable_ix(
+    &mut self,
+    host: &Pubkey,
+    allowed_tokens_and_amounts: Vec<TokenAndAmount>,
+  ) -> (Instruction, Pubkey) {
+    let chain_shard = self.chain_shard().await;
+    let user = self.user(host).await;
+    let payable = payable_pda(host, user.next_payable());
+    let wormhole = self.wormhole_accounts().await;
+    let mut instruction = ix(
+      chainbills::accounts::CreatePayable {
+        payable,
+        chain_payable_id: pda(&[
+          ChainPayableId::SEED_PREFIX,
+          &chain_shard.next_payable().to_le_bytes(),
+        ]),
+        payable_per_chain_payments_counter: payments_counter_pda(&payable),
+        activity: activity_pda(chain_shard.next_activity()),
+        user_activity_info: activity_info_pda(host, user.next_activity()),
+        payable_activity_info: activity_info_pda(&payable, 1),
+        host: user_pda(host),
+        chain_stats: chain_stats_pda(),
+        chain_shard: chain_shard_pda(self.shard),
+        config: config_pda(),
+        wormhole_program: wormhole.wormhole_program,
+        wormhole_bridge: wormhole.wormhole_bridge,
+        wormhole_fee_collector: wormhole.wormhole_fee_collector,
+        wormhole_emitter: wormhole.wormhole_emitter,
+        wormhole_sequence: wormhole.wormhole_sequence,
+        wormhole_message: wormhole.wormhole_message,
+        signer: *host,
+        clock: sysvar::clock::ID,
+        rent: sysvar::rent::ID,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::CreatePayable {
+        allowed_tokens_and_amounts: allowed_tokens_and_amounts.clone(),
+      },
+    );
+    for taa in &allowed_tokens_and_amounts {
+      let account = self.allowed_token_account(&taa.token).await;
+      instruction
+        .accounts
+        .push(AccountMeta::new_readonly(account, false));
+    }
+    (instruction, payable)
+  }
+
+  /// The account that an allowed token references: the token itself if it
+  /// is a TokenGroup, or else the token's TokenDetails.
+  pub async fn allowed_token_account(&mut self, token: &Pubkey) -> Pubkey {
+    match self.get_account(*token).await {
+      Some(account) if account.owner == chainbills::ID => *token,
+      _ => token_details_pda(token),
+    }
+  }
+
+  /// Creates a payable of the host with the allowed tokens and amounts.
+  pub async fn create_payable(
+    &mut self,
+    host: &Keypair,
+    allowed_tokens_and_amounts: Vec<TokenAndAmount>,
+  ) -> Pubkey {
+    let (ix, payable) = self
+      .create_payable_ix(&host.pubkey(), allowed_tokens_and_amounts)
+      .await;
+    self.send(&[ix], &[host]).await.unwrap();
+    payable
+  }
+
+  /// The accounts of a payment of the payable, escrowing it if the payable
+  /// escrows its payments and minting its receipt if the payable mints
+  /// receipts.
+  pub async fn payment_accounts(
+    &mut self,
+    payer: &Pubkey,
+    payable: Pubkey,
+  ) -> PaymentAccounts {
+    let user = self.user(payer).await;
+    let payable_data = self.payable(payable).await;
+    let counter: PayablePerChainPaymentsCounter =
+      self.account(payments_counter_pda(&payable)).await;
+    let payable_payment = pda(&[
+      payable.as_ref(),
+      PayablePayment::SEED_PREFIX,
+      &payable_data.next_payment().to_le_bytes(),
+    ]);
+    let payable_escrow = pda(&[payable.as_ref(), PayableEscrow::SEED_PREFIX]);
+    let escrows = match self.get_account(payable_escrow).await {
+      Some(account) => {
+        PayableEscrow::try_deserialize(&mut account.data.as_slice())
+          .unwrap()
+          .timeout
+          > 0
+      }
+      None => false,
+    };
+    let payable_receipts = receipts_pda(&payable);
+    let receipts = match self.get_account(payable_receipts).await {
+      Some(account) => {
+        PayableReceipts::try_deserialize(&mut account.data.as_slice())
+          .unwrap()
+          .enabled
+      }
+      None => false,
+    };
+    let user_payment = pda(&[
+      payer.as_ref(),
+      UserPayment::SEED_PREFIX,
+      &user.next_payment().to_le_bytes(),
+    ]);
+    let receipt_mint = receipt_mint_pda(&user_payment);
+    let chain_id = wormhole::CHAIN_ID_SOLANA.to_le_bytes();
+    // The payable's per-chain entry goes in its own account, or else in its
+    // ledger page.
+    let (own, paged) = (!self.paged, self.paged);
+    PaymentAccounts {
+      user_payment,
+      payable_payment,
+      payable_per_chain_payment_info: own.then(|| {
+        pda(&[
+          payable.as_ref(),
+          &chain_id,
+          &counter.next_payment().to_le_bytes(),
+        ])
+      }),
+      payable_per_chain_payments_page: paged.then(|| {
+        ledger_page_pda(&[payable.as_ref(), &chain_id], counter.next_payment())
+      }),
+      payable_per_chain_payments_counter: payments_counter_pda(&payable),
+      user_activity: payment_activity_pda(payer, user.next_activity()),
+      payable_activity: payment_activity_pda(
+        &payable,
+        payable_data.next_activity(),
+      ),
+      payable,
+      allowed_payers: allowed_payers_pda(&payable),
+      payable_escrow,
+      escrow_state: escrows
+        .then(|| pda(&[payable_payment.as_ref(), EscrowState::SEED_PREFIX])),
+      payable_receipts,
+      receipt_mint: receipts.then_some(receipt_mint),
+      payer_receipt_token_account: receipts
+        .then(|| receipt_ata(payer, &receipt_mint)),
+      payer: user_pda(payer),
+      chain_shard: chain_shard_pda(self.shard),
+    }
+  }
+
+  pub async fn pay_accounts(
+    &mut self,
+    payer: &Pubkey,
+    payable: Pubkey,
+    mint: &Pubkey,
+  ) -> chainbills::accounts::Pay {
+    let p = self.payment_accounts(payer, payable).await;
+    chainbills::accounts::Pay {
+      user_payment: p.user_payment,
+      payable_payment: p.payable_payment,
+      payable_per_chain_payment_info: p.payable_per_chain_payment_info,
+      payable_per_chain_payments_page: p.payable_per_chain_payments_page,
+      payable_per_chain_payments_counter: p.payable_per_chain_payments_counter,
+      user_activity: p.user_activity,
+      payable_activity: p.payable_activity,
+      payable: p.payable,
+      allowed_payers: p.allowed_payers,
+      payable_escrow: p.payable_escrow,
+      escrow_state: p.escrow_state,
+      payable_receipts: p.payable_receipts,
+      receipt_mint: p.receipt_mint,
+      payer_receipt_token_account: p.payer_receipt_token_account,
+      receipt_token_program: p.receipt_programs().0,
+      associated_token_program: p.receipt_programs().1,
+      token_group: None,
+      payer: p.payer,
+      chain_stats: chain_stats_pda(),
+      chain_shard: p.chain_shard,
+      config: config_pda(),
+      mint: *mint,
+      token_details: token_details_pda(mint),
+      token_details_shard: token_details_shard_pda(mint, self.shard),
+      payer_token_account: ata(payer, mint),
+      chain_token_account: ata(&chain_stats_pda(), mint),
+      signer: *payer,
+      token_program: spl_token::ID,
+      system_program: system_program::ID,
+      event_authority: event_authority(),
+      program: chainbills::ID,
+    }
+  }
+
+  pub async fn pay_native_accounts(
+    &mut self,
+    payer: &Pubkey,
+    payable: Pubkey,
+  ) -> chainbills::accounts::PayNative {
+    let p = self.payment_accounts(payer, payable).await;
+    chainbills::accounts::PayNative {
+      user_payment: p.user_payment,
+      payable_payment: p.payable_payment,
+      payable_per_chain_payment_info: p.payable_per_chain_payment_info,
+      payable_per_chain_payments_page: p.payable_per_chain_payments_page,
+      payable_per_chain_payments_counter: p.payable_per_chain_payments_counter,
+      user_activity: p.user_activity,
+      payable_activity: p.payable_activity,
+      payable: p.payable,
+      allowed_payers: p.allowed_payers,
+      payable_escrow: p.payable_escrow,
+      escrow_state: p.escrow_state,
+      payable_receipts: p.payable_receipts,
+      receipt_mint: p.receipt_mint,
+      payer_receipt_token_account: p.payer_receipt_token_account,
+      receipt_token_program: p.receipt_programs().0,
+      associated_token_program: p.receipt_programs().1,
+      token_group: None,
+      payer: p.payer,
+      chain_stats: p.receipt_mint.map(|_| chain_stats_pda()),
+      chain_shard: p.chain_shard,
+      config: config_pda(),
+      token_details: token_details_pda(&chainbills::ID),
+      token_details_shard: token_details_shard_pda(&chainbills::ID, self.shard),
+      native_vault: native_vault_pda(),
+      signer: *payer,
+      system_program: system_program::ID,
+      event_authority: event_authority(),
+      program: chainbills::ID,
+    }
+  }
+
+  /// Pays the amount of the SPL token into the payable.
+  pub async fn pay(
+    &mut self,
+    payer: &Keypair,
+    payable: Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+  ) -> std::result::Result<Outcome, TransactionError> {
+    let accounts = self.pay_accounts(&payer.pubkey(), payable, mint).await;
+    let ix = ix(
+      accounts,
+      chainbills::instruction::Pay {
+        amount,
+        payer_proof: vec![],
+      },
+    );
+    self.send(&[ix], &[payer]).await
+  }
+
+  /// Pays the amount of native SOL into the payable.
+  pub async fn pay_native(
+    &mut self,
+    payer: &Keypair,
+    payable: Pubkey,
+    amount: u64,
+  ) -> std::result::Result<Outcome, TransactionError> {
+    let accounts = self.pay_native_accounts(&payer.pubkey(), payable).await;
+    let ix = ix(
+      accounts,
+      chainbills::instruction::PayNative {
+        amount,
+        payer_proof: vec![],
+      },
+    );
+    self.send(&[ix], &[payer]).await
+  }
+}
+
+pub fn initialize_chain_shard_ix(owner: &Pubkey, index: u8) -> Instruction {
+  ix(
+    chainbills::accounts::InitializeChainShard {
+      chain_shard: chain_shard_pda(index),
+      config: config_pda(),
+      owner: *owner,
+      system_program: system_program::ID,
+    },
+    chainbills::instruction::InitializeChainShard { index },
+  )
+}
+
+/// Builds an instruction of this program.
+pub fn ix(
+  accounts: impl ToAccountMetas,
+  data: impl InstructionData,
+) -> Instruction {
+  Instruction {
+    program_id: chainbills::ID,
+    accounts: accounts.to_account_metas(None),
+    data: data.data(),
+  }
+}
+
+/// Replaces an account of the instruction, to test constraints on it.
+pub fn swap_account(ix: &mut Instruction, from: Pubkey, to: Pubkey) {
+  let meta = ix.accounts.iter_mut().find(|meta| meta.pubkey == from);
+  meta.expect("account isn't in the instruction").pubkey = to;
+}
+
+pub struct WormholeAccounts {
+  pub wormhole_program: Pubkey,
+  pub wormhole_bridge: Pubkey,
+  pub wormhole_fee_collector: Pubkey,
+  pub wormhole_emitter: Pubkey,
+  pub wormhole_sequence: Pubkey,
+  pub wormhole_message: Pubkey,
+}
+
+pub struct Activities {
+  pub activity: Pubkey,
+  pub user_activity_info: Pubkey,
+  pub payable_activity_info: Pubkey,
+  pub host: Pubkey,
+  pub chain_shard: Pubkey,
+}
+
+/// The accounts that every kind of payment into a payable initializes or
+/// updates.
+pub struct PaymentAccounts {
+  pub user_payment: Pubkey,
+  pub payable_payment: Pubkey,
+  pub payable_per_chain_payment_info: Option<Pubkey>,
+  pub payable_per_chain_payments_page: Option<Pubkey>,
+  pub payable_per_chain_payments_counter: Pubkey,
+  pub user_activity: Pubkey,
+  pub payable_activity: Pubkey,
+  pub payable: Pubkey,
+  pub allowed_payers: Pubkey,
+  pub payable_escrow: Pubkey,
+  pub escrow_state: Option<Pubkey>,
+  pub payable_receipts: Pubkey,
+  pub receipt_mint: Option<Pubkey>,
+  pub payer_receipt_token_account: Option<Pubkey>,
+  pub payer: Pubkey,
+  pub chain_shard: Pubkey,
+}
+
+impl PaymentAccounts {
+  /// The programs that mint the payment's receipt, if it mints one.
+  pub fn receipt_programs(&self) -> (Option<Pubkey>, Option<Pubkey>) {
+    match self.receipt_mint {
+      Some(_) => (
+        Some(spl_token_2022::ID),
+        Some(spl_associated_token_account::ID),
+      ),
+      None => (None, None),
+    }
+  }
+}
@@ -0,0 +1,480 @@
+use crate::{
+  archive::{archive_payable_ix, archive_payable_payment_ix},
+  common::*,
+  payables::close_payable_ix,
+};
+use anchor_lang::{
+  prelude::*, solana_program::instruction::Instruction, system_program,
+};
+use anchor_spl::token::spl_token;
+use chainbills::{error::ChainbillsError, events::*, state::*};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+async fn update_payable_escrow_ix(
+  env: &mut Env,
+  signer: &Pubkey,
+  payable: Pubkey,
+  timeout: u64,
+  arbiter: Option<Pubkey>,
+) -> Instruction {
+  let activities = env.activity_accounts(payable).await;
+  ix(
+    chainbills::accounts::UpdatePayableEscrow {
+      payable,
+      activity: activities.activity,
+      user_activity_info: activities.user_activity_info,
+      payable_activity_info: activities.payable_activity_info,
+      payable_escrow: pda(&[payable.as_ref(), PayableEscrow::SEED_PREFIX]),
+      host: activities.host,
+      operator: None,
+      chain_shard: activities.chain_shard,
+      signer: *signer,
+      system_program: system_program::ID,
+    },
+    chainbills::instruction::UpdatePayableEscrow { timeout, arbiter },
+  )
+}
+
+/// The activity accounts that settling the escrowed payment initializes.
+/// Settlements are recorded for the payer.
+async fn escrow_activities(env: &mut Env, escrow_state: Pubkey) -> Activities {
+  let escrow: EscrowState = env.account(escrow_state).await;
+  let next_activity = env.chain_shard().await.next_activity();
+  let payer = env.user(&escrow.payer).await;
+  let payable = env.payable(escrow.payable_id).await;
+  Activities {
+    activity: activity_pda(next_activity),
+    user_activity_info: activity_info_pda(&escrow.payer, payer.next_activity()),
+    payable_activity_info: activity_info_pda(
+      &escrow.payable_id,
+      payable.next_activity(),
+    ),
+    host: user_pda(&escrow.payer),
+    chain_shard: chain_shard_pda(env.shard),
+  }
+}
+
+async fn release_escrow_ix(
+  env: &mut Env,
+  signer: &Pubkey,
+  escrow_state: Pubkey,
+) -> Instruction {
+  let a = escrow_activities(env, escrow_state).await;
+  let escrow: EscrowState = env.account(escrow_state).await;
+  ix(
+    chainbills::accounts::ReleaseEscrow {
+      escrow_state,
+      payable: escrow.payable_id,
+      payable_escrow: pda(&[
+        escrow.payable_id.as_ref(),
+        PayableEscrow::SEED_PREFIX,
+      ]),
+      activity: a.activity,
+      user_activity_info: a.user_activity_info,
+      payable_activity_info: a.payable_activity_info,
+      payer: a.host,
+      chain_shard: a.chain_shard,
+      signer: *signer,
+      system_program: system_program::ID,
+    },
+    chainbills::instruction::ReleaseEscrow {},
+  )
+}
+
+async fn dispute_escrow_ix(
+  env: &mut Env,
+  signer: &Pubkey,
+  escrow_state: Pubkey,
+) -> Instruction {
+  let a = escrow_activities(env, escrow_state).await;
+  let escrow: EscrowState = env.account(escrow_state).await;
+  ix(
+    chainbills::accounts::DisputeEscrow {
+      escrow_state,
+      payable: escrow.payable_id,
+      activity: a.activity,
+      user_activity_info: a.user_activity_info,
+      payable_activity_info: a.payable_activity_info,
+      payer: a.host,
+      chain_shard: a.chain_shard,
+      signer: *signer,
+      system_program: system_program::ID,
+    },
+    chainbills::instruction::DisputeEscrow {},
+  )
+}
+
+async fn refund_escrow_ix(
+  env: &mut Env,
+  signer: &Pubkey,
+  escrow_state: Pubkey,
+) -> Instruction {
+  let a = escrow_activities(env, escrow_state).await;
+  let escrow: EscrowState = env.account(escrow_state).await;
+  let mint = escrow.details.token;
+  ix(
+    chainbills::accounts::RefundEscrow {
+      escrow_state,
+      payable: escrow.payable_id,
+      payable_escrow: pda(&[
+        escrow.payable_id.as_ref(),
+        PayableEscrow::SEED_PREFIX,
+      ]),
+      activity: a.activity,
+      user_activity_info: a.user_activity_info,
+      payable_activity_info: a.payable_activity_info,
+      payer: a.host,
+      chain_stats: chain_stats_pda(),
+      chain_shard: a.chain_shard,
+      mint,
+      token_details: token_details_pda(&mint),
+      payer_token_account: ata(&escrow.payer, &mint),
+      chain_token_account: ata(&chain_stats_pda(), &mint),
+      signer: *signer,
+      token_program: spl_token::ID,
+      system_program: system_program::ID,
+    },
+    chainbills::instruction::RefundEscrow {},
+  )
+}
+
+async fn refund_escrow_native_ix(
+  env: &mut Env,
+  signer: &Pubkey,
+  escrow_state: Pubkey,
+) -> Instruction {
+  let a = escrow_activities(env, escrow_state).await;
+  let escrow: EscrowState = env.account(escrow_state).await;
+  ix(
+    chainbills::accounts::RefundEscrowNative {
+      escrow_state,
+      payable: escrow.payable_id,
+      payable_escrow: pda(&[
+        escrow.payable_id.as_ref(),
+        PayableEscrow::SEED_PREFIX,
+      ]),
+      activity: a.activity,
+      user_activity_info: a.user_activity_info,
+      payable_activity_info: a.payable_activity_info,
+      payer: a.host,
+      payer_wallet: escrow.payer,
+      chain_shard: a.chain_shard,
+      token_details: token_details_pda(&chainbills::ID),
+      native_vault: native_vault_pda(),
+      signer: *signer,
+      system_program: system_program::ID,
+    },
+    chainbills::instruction::RefundEscrowNative {},
+  )
+}
+
+/// A payable of a new host that escrows payments of a new token, and a payer
+/// holding some of the token.
+async fn escrowing_payable(
+  env: &mut Env,
+  timeout: u64,
+  arbiter: Option<Pubkey>,
+) -> (Keypair, Keypair, Pubkey, Pubkey) {
+  let mint = env.supported_mint(6).await;
+  let host = env.new_user().await;
+  let payer = env.new_user().await;
+  env.mint_to(&mint, &payer.pubkey(), 10_000).await;
+  let payable = env.create_payable(&host, vec![]).await;
+  let ix =
+    update_payable_escrow_ix(env, &host.pubkey(), payable, timeout, arbiter)
+      .await;
+  env.send(&[ix], &[&host]).await.unwrap();
+  (host, payer, payable, mint)
+}
+
+/// The number of the payable's escrowed payments that are still held.
+async fn held_count(env: &mut Env, payable: Pubkey) -> u64 {
+  let escrow: PayableEscrow = env
+    .account(pda(&[payable.as_ref(), PayableEscrow::SEED_PREFIX]))
+    .await;
+  escrow.held_count
+}
+
+/// Pays the amount into the escrowing payable, returning the escrow state.
+async fn escrowed_payment(
+  env: &mut Env,
+  payer: &Keypair,
+  payable: Pubkey,
+  mint: &Pubkey,
+  amount: u64,
+) -> Pubkey {
+  let accounts = env.payment_accounts(&payer.pubkey(), payable).await;
+  env.pay(payer, payable, mint, amount).await.unwrap();
+  accounts.escrow_state.unwrap()
+}
+
+#[tokio::test]
+async fn update_payable_escrow() {
+  let mut env = Env::new().await;
+  let host = env.new_user().await;
+  let stranger = env.new_user().await;
+  let arbiter = Keypair::new().pubkey();
+  let payable = env.create_payable(&host, vec![]).await;
+
+  let ix =
+    update_payable_escrow_ix(&mut env, &stranger.pubkey(), payable, 100, None)
+      .await;
+  assert_error(
+    env.send(&[ix], &[&stranger]).await,
+    ChainbillsError::NotYourPayable,
+  );
+
+  let ix = update_payable_escrow_ix(
+    &mut env,
+    &host.pubkey(),
+    payable,
+    100,
+    Some(arbiter),
+  )
+  .await;
+  let outcome = env.send(&[ix], &[&host]).await.unwrap();
+  let event = outcome.event::<UpdatedPayableEscrow>();
+  assert_eq!(event.payable_id, payable);
+  assert_eq!(event.host_wallet, host.pubkey());
+  assert_eq!(event.timeout, 100);
+  assert_eq!(event.arbiter, arbiter);
+  let escrow: PayableEscrow = env
+    .account(pda(&[payable.as_ref(), PayableEscrow::SEED_PREFIX]))
+    .await;
+  assert_eq!(escrow.timeout, 100);
+  assert_eq!(escrow.arbiter, arbiter);
+
+  // A zero timeout stops escrowing new payments.
+  let ix =
+    update_payable_escrow_ix(&mut env, &host.pubkey(), payable, 0, None).await;
+  env.send(&[ix], &[&host]).await.unwrap();
+  let mint = env.supported_mint(6).await;
+  let payer = env.new_user().await;
+  env.mint_to(&mint, &payer.pubkey(), 1_000).await;
+  let accounts = env.payment_accounts(&payer.pubkey(), payable).await;
+  assert!(accounts.escrow_state.is_none());
+  env.pay(&payer, payable, &mint, 1_000).await.unwrap();
+  let balances = env.payable(payable).await.balances;
+  assert_eq!(balances[0].amount, 1_000);
+}
+
+#[tokio::test]
+async fn release_escrow() {
+  let mut env = Env::new().await;
+  let (_, payer, payable, mint) = escrowing_payable(&mut env, 100, None).await;
+  let stranger = env.new_user().await;
+
+  let escrow_state =
+    escrowed_payment(&mut env, &payer, payable, &mint, 1_000).await;
+  let now = env.now().await;
+  let escrow: EscrowState = env.account(escrow_state).await;
+  assert_eq!(escrow.payable_id, payable);
+  assert_eq!(escrow.payer, payer.pubkey());
+  assert_eq!(escrow.details.amount, 1_000);
+  assert_eq!(escrow.release_after, now + 100);
+  assert!(escrow.status == EscrowStatus::Locked);
+  // Held payments aren't in the payable's balances yet.
+  assert!(env.payable(payable).await.balances.is_empty());
+  assert_eq!(
+    env.token_balance(ata(&chain_stats_pda(), &mint)).await,
+    1_000
+  );
+
+  // Only the payer can release before the timeout.
+  let ix = release_escrow_ix(&mut env, &stranger.pubkey(), escrow_state).await;
+  assert_error(
+    env.send(&[ix], &[&stranger]).await,
+    ChainbillsError::EscrowNotReleasable,
+  );
+  let ix = release_escrow_ix(&mut env, &payer.pubkey(), escrow_state).await;
+  let outcome = env.send(&[ix], &[&payer]).await.unwrap();
+  let event = outcome.event::<ReleasedEscrow>();
+  assert_eq!(event.payable_id, payable);
+  assert_eq!(event.escrow_id, escrow_state);
+  assert_eq!(event.released_by, payer.pubkey());
+  assert_eq!(event.token, mint);
+  assert_eq!(event.amount, 1_000);
+  let escrow: EscrowState = env.account(escrow_state).await;
+  assert!(escrow.status == EscrowStatus::Released);
+  let balances = env.payable(payable).await.balances;
+  assert_eq!((balances[0].token, balances[0].amount), (mint, 1_000));
+  assert_eq!(held_count(&mut env, payable).await, 0);
+
+  let ix = release_escrow_ix(&mut env, &payer.pubkey(), escrow_state).await;
+  assert_error(
+    env.send(&[ix], &[&payer]).await,
+    ChainbillsError::EscrowIsSettled,
+  );
+
+  // Anyone can release once the timeout elapsed.
+  let escrow_state =
+    escrowed_payment(&mut env, &payer, payable, &mint, 500).await;
+  env.advance_time(100).await;
+  let ix = release_escrow_ix(&mut env, &stranger.pubkey(), escrow_state).await;
+  env.send(&[ix], &[&stranger]).await.unwrap();
+  let balances = env.payable(payable).await.balances;
+  assert_eq!(balances[0].amount, 1_500);
+}
+
+#[tokio::test]
+async fn dispute_and_refund_escrow() {
+  let mut env = Env::new().await;
+  let arbiter = env.wallet().await;
+  let (host, payer, payable, mint) =
+    escrowing_payable(&mut env, 100, Some(arbiter.pubkey())).await;
+  let stranger = env.new_user().await;
+  let escrow_state =
+    escrowed_payment(&mut env, &payer, payable, &mint, 1_000).await;
+
+  // Only the payer can dispute, once.
+  let ix = dispute_escrow_ix(&mut env, &stranger.pubkey(), escrow_state).await;
+  assert_error(
+    env.send(&[ix], &[&stranger]).await,
+    ChainbillsError::NotYourEscrow,
+  );
+  let ix = dispute_escrow_ix(&mut env, &payer.pubkey(), escrow_state).await;
+  let outcome = env.send(&[ix], &[&payer]).await.unwrap();
+  let event = outcome.event::<DisputedEscrow>();
+  assert_eq!(event.escrow_id, escrow_state);
+  assert_eq!(event.payer_wallet, payer.pubkey());
+  let escrow: EscrowState = env.account(escrow_state).await;
+  assert!(escrow.status == EscrowStatus::Disputed);
+  let ix = dispute_escrow_ix(&mut env, &payer.pubkey(), escrow_state).await;
+  assert_error(
+    env.send(&[ix], &[&payer]).await,
+    ChainbillsError::EscrowNotDisputable,
+  );
+
+  // Disputed payments aren't released by the timeout.
+  env.advance_time(100).await;
+  let ix = release_escrow_ix(&mut env, &stranger.pubkey(), escrow_state).await;
+  assert_error(
+    env.send(&[ix], &[&stranger]).await,
+    ChainbillsError::EscrowIsDisputed,
+  );
+
+  // Only the arbiter or the host can refund.
+  let ix = refund_escrow_ix(&mut env, &stranger.pubkey(), escrow_state).await;
+  assert_error(
+    env.send(&[ix], &[&stranger]).await,
+    ChainbillsError::NotYourEscrow,
+  );
+  let ix = refund_escrow_ix(&mut env, &arbiter.pubkey(), escrow_state).await;
+  let outcome = env.send(&[ix], &[&arbiter]).await.unwrap();
+  let event = outcome.event::<RefundedEscrow>();
+  assert_eq!(event.escrow_id, escrow_state);
+  assert_eq!(event.payer_wallet, payer.pubkey());
+  assert_eq!(event.refunded_by, arbiter.pubkey());
+  assert_eq!(event.token, mint);
+  assert_eq!(event.amount, 1_000);
+  let escrow: EscrowState = env.account(escrow_state).await;
+  assert!(escrow.status == EscrowStatus::Refunded);
+  assert_eq!(env.token_balance(ata(&payer.pubkey(), &mint)).await, 10_000);
+  assert_eq!(env.token_details(&mint).await.total_withdrawn, 1_000);
+  assert!(env.payable(payable).await.balances.is_empty());
+
+  let ix = refund_escrow_ix(&mut env, &arbiter.pubkey(), escrow_state).await;
+  assert_error(
+    env.send(&[ix], &[&arbiter]).await,
+    ChainbillsError::EscrowIsSettled,
+  );
+
+  // The host can refund too.
+  let escrow_state =
+    escrowed_payment(&mut env, &payer, payable, &mint, 500).await;
+  let ix = refund_escrow_ix(&mut env, &host.pubkey(), escrow_state).await;
+  env.send(&[ix], &[&host]).await.unwrap();
+  assert_eq!(env.token_balance(ata(&payer.pubkey(), &mint)).await, 10_000);
+}
+
+#[tokio::test]
+async fn escrow_without_arbiter_is_not_disputable() {
+  let mut env = Env::new().await;
+  let (_, payer, payable, mint) = escrowing_payable(&mut env, 100, None).await;
+  let escrow_state =
+    escrowed_payment(&mut env, &payer, payable, &mint, 1_000).await;
+  let ix = dispute_escrow_ix(&mut env, &payer.pubkey(), escrow_state).await;
+  assert_error(
+    env.send(&[ix], &[&payer]).await,
+    ChainbillsError::EscrowNotDisputable,
+  );
+}
+
+#[tokio::test]
+async fn refund_escrow_native() {
+  let mut env = Env::new().await;
+  env.support_native().await;
+  let host = env.new_user().await;
+  let payer = env.new_user().await;
+  let payable = env.create_payable(&host, vec![]).await;
+  let ix =
+    update_payable_escrow_ix(&mut env, &host.pubkey(), payable, 100, None)
+      .await;
+  env.send(&[ix], &[&host]).await.unwrap();
+  let accounts = env.payment_accounts(&payer.pubkey(), payable).await;
+  let escrow_state = accounts.escrow_state.unwrap();
+  env.pay_native(&payer, payable, 1_000_000).await.unwrap();
+
+  let payer_lamports = env.lamports(payer.pubkey()).await;
+  let vault_lamports = env.lamports(native_vault_pda()).await;
+  let ix =
+    refund_escrow_native_ix(&mut env, &host.pubkey(), escrow_state).await;
+  let outcome = env.send(&[ix], &[&host]).await.unwrap();
+  assert_eq!(outcome.event::<RefundedEscrow>().token, chainbills::ID);
+  assert_eq!(
+    env.lamports(payer.pubkey()).await,
+    payer_lamports + 1_000_000
+  );
+  assert_eq!(
+    env.lamports(native_vault_pda()).await,
+    vault_lamports - 1_000_000
+  );
+  let escrow: EscrowState = env.account(escrow_state).await;
+  assert!(escrow.status == EscrowStatus::Refunded);
+}
+
+#[tokio::test]
+async fn payable_with_held_escrows_is_not_archivable() {
+  let mut env = Env::new().await;
+  let arbiter = env.wallet().await;
+  let (host, payer, payable, mint) =
+    escrowing_payable(&mut env, 100, Some(arbiter.pubkey())).await;
+  let accounts = env.payment_accounts(&payer.pubkey(), payable).await;
+  env.pay(&payer, payable, &mint, 1_000).await.unwrap();
+  let escrow_state = accounts.escrow_state.unwrap();
+  assert_eq!(held_count(&mut env, payable).await, 1);
+
+  // Disputed payments are still held, so they keep the payable and the
+  // payment alive.
+  let ix = dispute_escrow_ix(&mut env, &payer.pubkey(), escrow_state).await;
+  env.send(&[ix], &[&payer]).await.unwrap();
+  assert_eq!(held_count(&mut env, payable).await, 1);
+  let ix = close_payable_ix(&mut env, &host.pubkey(), payable, false).await;
+  env.send(&[ix], &[&host]).await.unwrap();
+  let ix = archive_payable_ix(&host.pubkey(), payable, &[]);
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::PayableHasHeldEscrows,
+  );
+  let ix =
+    archive_payable_payment_ix(&mut env, &host.pubkey(), &accounts).await;
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::PaymentIsHeldInEscrow,
+  );
+
+  // Once refunded, the payment and the payable hold nothing and can be
+  // archived with their escrow accounts.
+  let ix = refund_escrow_ix(&mut env, &arbiter.pubkey(), escrow_state).await;
+  env.send(&[ix], &[&arbiter]).await.unwrap();
+  assert_eq!(held_count(&mut env, payable).await, 0);
+  let ix =
+    archive_payable_payment_ix(&mut env, &host.pubkey(), &accounts).await;
+  env.send(&[ix], &[&host]).await.unwrap();
+  assert!(!env.exists(accounts.payable_payment).await);
+  assert!(!env.exists(escrow_state).await);
+  let ix = archive_payable_ix(&host.pubkey(), payable, &[]);
+  env.send(&[ix], &[&host]).await.unwrap();
+  assert!(!env.exists(payable).await);
+  assert!(!env.exists(accounts.payable_escrow).await);
+}
@@ -0,0 +1,302 @@
+use crate::{
+  common::*,
+  owner::{
+    register_cb_chain_ix, register_chain_wormhole_id_ix,
+    register_foreign_contract_ix,
+  },
+};
+use anchor_lang::{
+  prelude::*, solana_program::instruction::Instruction, system_program,
+};
+use chainbills::{
+  error::ChainbillsError, events::*, payload::PayablePayload, state::*,
+};
+use solana_sdk::{account::AccountSharedData, signer::Signer};
+use wormhole_anchor_sdk::wormhole::{self, PostedVaaMeta};
+
+const EMITTER_CHAIN: u16 = 30;
+const EMITTER: [u8; 32] = [7; 32];
+
+/// Registers the emitter chain and its Chainbills contract, returning the
+/// chain's cbChainId.
+async fn register_emitter(env: &mut Env) -> [u8; 32] {
+  let owner = env.owner();
+  let caip2 = "eip155:8453";
+  let id = cb_chain_id(caip2);
+  let ixs = [
+    register_cb_chain_ix(owner, caip2),
+    register_chain_wormhole_id_ix(owner, id, EMITTER_CHAIN),
+    register_foreign_contract_ix(owner, EMITTER_CHAIN, EMITTER),
+  ];
+  env.send(&ixs, &[]).await.unwrap();
+  id
+}
+
+/// Stores the payload as a VAA the Wormhole program verified and posted,
+/// returning the VAA's hash.
+fn set_posted_vaa(
+  env: &mut Env,
+  emitter_address: [u8; 32],
+  sequence: u64,
+  payload: &PayablePayload,
+) -> [u8; 32] {
+  let meta = PostedVaaMeta {
+    version: 1,
+    finality: 1,
+    timestamp: 1,
+    signature_set: Pubkey::new_unique(),
+    posted_timestamp: 1,
+    batch_id: 0,
+    sequence,
+    emitter_chain: EMITTER_CHAIN,
+    emitter_address,
+  };
+  let payload = payload.try_to_vec().unwrap();
+  let mut data = b"vaa".to_vec();
+  meta.serialize(&mut data).unwrap();
+  (payload.len() as u32).serialize(&mut data).unwrap();
+  data.extend(payload);
+
+  let vaa_hash = Pubkey::new_unique().to_bytes();
+  let mut account =
+    AccountSharedData::new(1_000_000_000, data.len(), &WORMHOLE);
+  account.set_data_from_slice(&data);
+  env.store_account(&posted_vaa_pda(&vaa_hash), &account);
+  vaa_hash
+}
+
+fn posted_vaa_pda(vaa_hash: &[u8; 32]) -> Pubkey {
+  wormhole_pda(&[wormhole::SEED_PREFIX_POSTED_VAA, vaa_hash])
+}
+
+fn payload(action_type: u8, payable_id: [u8; 32]) -> PayablePayload {
+  PayablePayload {
+    version: 1,
+    action_type,
+    payable_id,
+    nonce: 1,
+    is_closed: false,
+    allowed_tokens_and_amounts: vec![TokenAndAmountForeign {
+      token: [3; 32],
+      amount: 500,
+    }],
+    allowed_payers_root: [0; 32],
+  }
+}
+
+async fn record_foreign_payable_update_ix(
+  env: &mut Env,
+  signer: &Pubkey,
+  payable_id: [u8; 32],
+  ataa_len: u8,
+  vaa_hash: [u8; 32],
+  sequence: u64,
+) -> Instruction {
+  let chain_stats = env.chain_stats().await;
+  ix(
+    chainbills::accounts::RecordForeignPayableUpdate {
+      foreign_payable: pda(&[payable_id.as_ref()]),
+      chain_foreign_payable_id: pda(&[
+        ChainForeignPayableId::SEED_PREFIX,
+        &chain_stats.next_foreign_payable().to_le_bytes(),
+      ]),
+      registered_foreign_contract: pda(&[
+        RegisteredForeignContract::SEED_PREFIX,
+        &EMITTER_CHAIN.to_le_bytes(),
+      ]),
+      emitter_cb_chain_id: pda(&[
+        WormholeChainCbChainId::SEED_PREFIX,
+        &EMITTER_CHAIN.to_le_bytes(),
+      ]),
+      chain_stats: chain_stats_pda(),
+      config: config_pda(),
+      signer: *signer,
+      posted_vaa: posted_vaa_pda(&vaa_hash),
+      consumed_wormhole_message: pda(&[
+        ConsumedWormholeMessage::SEED_PREFIX,
+        &EMITTER_CHAIN.to_le_bytes(),
+        &sequence.to_le_bytes(),
+      ]),
+      chain_consumed_wormhole_message_id: pda(&[
+        ChainConsumedWormholeMessageId::SEED_PREFIX,
+        &chain_stats.next_consumed_wormhole_message().to_le_bytes(),
+      ]),
+      per_chain_consumed_wormhole_messages_counter: pda(&[
+        PerChainConsumedWormholeMessagesCounter::SEED_PREFIX,
+        &EMITTER_CHAIN.to_le_bytes(),
+      ]),
+      wormhole_program: WORMHOLE,
+      system_program: system_program::ID,
+    },
+    chainbills::instruction::RecordForeignPayableUpdate {
+      payable_id,
+      ataa_len,
+      vaa_hash,
+    },
+  )
+}
+
+#[tokio::test]
+async fn record_foreign_payable_update() {
+  let mut env = Env::new().await;
+  let signer = env.wallet().await;
+  let cb_chain_id = register_emitter(&mut env).await;
+  let payable_id = [9u8; 32];
+
+  // The payload must match the instruction's args.
+  let vaa_hash = set_posted_vaa(&mut env, EMITTER, 1, &payload(1, payable_id));
+  for (payable_id, ataa_len, error) in [
+    ([8u8; 32], 1, ChainbillsError::InvalidPayloadPayableId),
+    (payable_id, 2, ChainbillsError::InvalidPayloadAtaaLen),
+  ] {
+    let ix = record_foreign_payable_update_ix(
+      &mut env,
+      &signer.pubkey(),
+      payable_id,
+      ataa_len,
+      vaa_hash,
+      1,
+    )
+    .await;
+    assert_error(env.send(&[ix], &[&signer]).await, error);
+  }
+
+  // Only the registered contract of the emitter chain is trusted.
+  let forged = set_posted_vaa(&mut env, [8; 32], 1, &payload(1, payable_id));
+  let ix = record_foreign_payable_update_ix(
+    &mut env,
+    &signer.pubkey(),
+    payable_id,
+    1,
+    forged,
+    1,
+  )
+  .await;
+  assert_error(
+    env.send(&[ix], &[&signer]).await,
+    ChainbillsError::InvalidForeignContract,
+  );
+
+  let ix = record_foreign_payable_update_ix(
+    &mut env,
+    &signer.pubkey(),
+    payable_id,
+    1,
+    vaa_hash,
+    1,
+  )
+  .await;
+  let outcome = env.send(&[ix], &[&signer]).await.unwrap();
+  let event = outcome.event::<ConsumedWormholePayableMessage>();
+  assert_eq!(event.payable_id, payable_id);
+  assert_eq!(event.chain_id, EMITTER_CHAIN);
+  assert_eq!(event.vaa_hash, vaa_hash);
+  let foreign_payable: PayableForeign =
+    env.account(pda(&[payable_id.as_ref()])).await;
+  assert_eq!(foreign_payable.chain_id, cb_chain_id);
+  assert!(!foreign_payable.is_closed);
+  assert_eq!(foreign_payable.allowed_tokens_and_amounts.len(), 1);
+  assert_eq!(foreign_payable.allowed_tokens_and_amounts[0].amount, 500);
+  let chain_stats = env.chain_stats().await;
+  assert_eq!(chain_stats.foreign_payables_count, 1);
+  assert_eq!(chain_stats.consumed_wormhole_messages_count, 1);
+  let consumed: ConsumedWormholeMessage = env
+    .account(pda(&[
+      ConsumedWormholeMessage::SEED_PREFIX,
+      &EMITTER_CHAIN.to_le_bytes(),
+      &1u64.to_le_bytes(),
+    ]))
+    .await;
+  assert_eq!(consumed.vaa_hash, vaa_hash);
+
+  // VAAs can't be replayed.
+  let ix = record_foreign_payable_update_ix(
+    &mut env,
+    &signer.pubkey(),
+    payable_id,
+    1,
+    vaa_hash,
+    1,
+  )
+  .await;
+  assert!(env.send(&[ix], &[&signer]).await.is_err());
+
+  // Later messages of the emitter chain update the same payable.
+  let mut updated = payload(4, payable_id);
+  updated.allowed_tokens_and_amounts[0].amount = 700;
+  let vaa_hash = set_posted_vaa(&mut env, EMITTER, 2, &updated);
+  let ix = record_foreign_payable_update_ix(
+    &mut env,
+    &signer.pubkey(),
+    payable_id,
+    1,
+    vaa_hash,
+    2,
+  )
+  .await;
+  env.send(&[ix], &[&signer]).await.unwrap();
+  let foreign_payable: PayableForeign =
+    env.account(pda(&[payable_id.as_ref()])).await;
+  assert_eq!(foreign_payable.allowed_tokens_and_amounts[0].amount, 700);
+  let counter: PerChainConsumedWormholeMessagesCounter = env
+    .account(pda(&[
+      PerChainConsumedWormholeMessagesCounter::SEED_PREFIX,
+      &EMITTER_CHAIN.to_le_bytes(),
+    ]))
+    .await;
+  assert_eq!(counter.consumed_messages_count, 2);
+  assert_eq!(env.chain_stats().await.consumed_wormhole_messages_count, 2);
+
+  // Allowed payers roots are consumed without being stored, as Solana doesn't
+  // pay foreign payables.
+  let payable_id = [6u8; 32];
+  let mut restricted = payload(5, payable_id);
+  restricted.allowed_tokens_and_amounts.clear();
+  restricted.allowed_payers_root = [4; 32];
+  let vaa_hash = set_posted_vaa(&mut env, EMITTER, 3, &restricted);
+  let ix = record_foreign_payable_update_ix(
+    &mut env,
+    &signer.pubkey(),
+    payable_id,
+    0,
+    vaa_hash,
+    3,
+  )
+  .await;
+  env.send(&[ix], &[&signer]).await.unwrap();
+  assert!(
+    !env
+      .exists(pda(&[
+        payable_id.as_ref(),
+        PayableAllowedPayers::SEED_PREFIX
+      ]))
+      .await
+  );
+  let foreign_payable: PayableForeign =
+    env.account(pda(&[payable_id.as_ref()])).await;
+  assert_eq!(foreign_payable.chain_id, cb_chain_id);
+  assert!(foreign_payable.allowed_tokens_and_amounts.is_empty());
+  assert_eq!(env.chain_stats().await.consumed_wormhole_messages_count, 3);
+}
+
+#[tokio::test]
+async fn record_foreign_payable_update_with_client_instruction() {
+  let mut env = Env::new().await;
+  let signer = env.wallet().await;
+  let cb_chain_id = register_emitter(&mut env).await;
+  let payable_id = [9u8; 32];
+  let vaa_hash = set_posted_vaa(&mut env, EMITTER, 1, &payload(1, payable_id));
+
+  let ix = env
+    .client()
+    .record_foreign_payable_update(&signer.pubkey(), vaa_hash)
+    .unwrap();
+  let outcome = env.send(&[ix], &[&signer]).await.unwrap();
+  let event = outcome.event::<ConsumedWormholePayableMessage>();
+  assert_eq!(event.payable_id, payable_id);
+  assert_eq!(event.vaa_hash, vaa_hash);
+  let foreign_payable: PayableForeign =
+    env.account(pda(&[payable_id.as_ref()])).await;
+  assert_eq!(foreign_payable.chain_id, cb_chain_id);
+  assert_eq!(foreign_payable.allowed_tokens_and_amounts[0].amount, 500);
+}
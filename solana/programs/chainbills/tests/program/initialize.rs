@@ -0,0 +1,74 @@
+use crate::common::*;
+use chainbills::{error::ChainbillsError, events::*, state::*};
+use solana_sdk::signer::Signer;
+use wormhole_anchor_sdk::wormhole;
+
+#[tokio::test]
+async fn initializes_config_and_chain_stats() {
+  let mut env = Env::uninitialized().await;
+  let owner = env.owner();
+  let ix = env.initialize_ix(&owner);
+  let outcome = env.send(&[ix], &[]).await.unwrap();
+  outcome.event::<Initialized>();
+
+  let config = env.config().await;
+  assert_eq!(config.version, Config::VERSION);
+  assert_eq!(config.owner, owner);
+  assert_eq!(config.chain_id, wormhole::CHAIN_ID_SOLANA);
+  assert_eq!(config.withdrawal_fee_percentage, 200);
+  assert_eq!(config.chainbills_fee_collector, env.fee_collector);
+  assert_eq!(
+    config.wormhole_emitter,
+    pda(&[wormhole::SEED_PREFIX_EMITTER])
+  );
+
+  let chain_stats = env.chain_stats().await;
+  assert_eq!(chain_stats.version, ChainStats::VERSION);
+  assert_eq!(chain_stats.users_count, 0);
+  assert_eq!(chain_stats.payables_count, 0);
+
+  // The initialization message was published through Wormhole.
+  let sequence: wormhole::SequenceTracker =
+    env.account(config.wormhole_sequence).await;
+  assert_eq!(sequence.next_value(), wormhole::INITIAL_SEQUENCE + 1);
+  assert!(env.exists(config.wormhole_emitter).await);
+
+  // The program can't be initialized twice.
+  let ix = env.initialize_ix(&owner);
+  assert!(env.send(&[ix], &[]).await.is_err());
+}
+
+#[tokio::test]
+async fn initializes_chain_shards() {
+  let mut env = Env::uninitialized().await;
+  let owner = env.owner();
+  let ix = env.initialize_ix(&owner);
+  env.send(&[ix], &[]).await.unwrap();
+
+  let stranger = env.wallet().await;
+  let ix = initialize_chain_shard_ix(&stranger.pubkey(), 3);
+  assert_error(
+    env.send(&[ix], &[&stranger]).await,
+    ChainbillsError::OwnerUnauthorized,
+  );
+  let ix = initialize_chain_shard_ix(&owner, ChainStatsShard::COUNT);
+  assert_error(
+    env.send(&[ix], &[]).await,
+    ChainbillsError::InvalidChainShard,
+  );
+
+  let ix = initialize_chain_shard_ix(&owner, 3);
+  let outcome = env.send(&[ix], &[]).await.unwrap();
+  assert_eq!(outcome.event::<InitializedChainShard>().index, 3);
+  let shard: ChainStatsShard = env.account(chain_shard_pda(3)).await;
+  let base = ChainStatsShard::base_of(3);
+  assert_eq!(shard.index, 3);
+  assert_eq!(shard.base(), base);
+  assert_eq!(shard.users_count, base);
+  assert_eq!(shard.activities_count, base);
+  assert_eq!(shard.next_payable(), base + 1);
+
+  // A shard can't be initialized twice.
+  let ix = initialize_chain_shard_ix(&owner, 3);
+  assert!(env.send(&[ix], &[]).await.is_err());
+}
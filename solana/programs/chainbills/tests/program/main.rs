@@ -0,0 +1,17 @@
+//! Tests of the program's BPF build in an in-process bank, alongside the
+//! bundled Wormhole program. Run `anchor build`, then `cargo test`; no
+//! validator is needed.
+
+mod archive;
+mod common;
+mod escrow;
+mod foreign;
+mod initialize;
+mod migrations;
+mod owner;
+mod payables;
+mod payments;
+mod receipts;
+mod subscriptions;
+mod users;
+mod withdrawals;
@@ -0,0 +1,573 @@
+use crate::common::*;
+use anchor_lang::{
+  prelude::*, solana_program::instruction::Instruction, system_program,
+  Discriminator,
+};
+use chainbills::{error::ChainbillsError, events::*, state::*};
+use solana_sdk::{
+  account::AccountSharedData, signature::Keypair, signer::Signer,
+};
+
+/// Stores the legacy layout of an account at the address, with the
+/// discriminator of the account's current layout.
+async fn set_legacy_account<T: Discriminator>(
+  env: &mut Env,
+  address: Pubkey,
+  legacy: &impl AnchorSerialize,
+) {
+  let mut data = T::DISCRIMINATOR.to_vec();
+  legacy.serialize(&mut data).unwrap();
+  let rent = env.rent();
+  let mut account = AccountSharedData::new(
+    rent.minimum_balance(data.len()),
+    data.len(),
+    &chainbills::ID,
+  );
+  account.set_data_from_slice(&data);
+  env.store_account(&address, &account);
+}
+
+fn migrate_user_ix(signer: &Pubkey) -> Instruction {
+  ix(
+    chainbills::accounts::MigrateUser {
+      user: user_pda(signer),
+      signer: *signer,
+      system_program: system_program::ID,
+    },
+    chainbills::instruction::MigrateUser {},
+  )
+}
+
+fn migrate_payable_ix(signer: &Pubkey, payable: Pubkey) -> Instruction {
+  ix(
+    chainbills::accounts::MigratePayable {
+      payable,
+      signer: *signer,
+      system_program: system_program::ID,
+    },
+    chainbills::instruction::MigratePayable {},
+  )
+}
+
+fn migrate_user_payment_ix(
+  signer: &Pubkey,
+  user_payment: Pubkey,
+) -> Instruction {
+  ix(
+    chainbills::accounts::MigrateUserPayment {
+      user_payment,
+      signer: *signer,
+      system_program: system_program::ID,
+    },
+    chainbills::instruction::MigrateUserPayment {},
+  )
+}
+
+fn migrate_payable_payment_ix(
+  signer: &Pubkey,
+  payable_payment: Pubkey,
+) -> Instruction {
+  ix(
+    chainbills::accounts::MigratePayablePayment {
+      payable_payment,
+      signer: *signer,
+      system_program: system_program::ID,
+    },
+    chainbills::instruction::MigratePayablePayment {},
+  )
+}
+
+fn migrate_withdrawal_ix(signer: &Pubkey, withdrawal: Pubkey) -> Instruction {
+  ix(
+    chainbills::accounts::MigrateWithdrawal {
+      withdrawal,
+      signer: *signer,
+      system_program: system_program::ID,
+    },
+    chainbills::instruction::MigrateWithdrawal {},
+  )
+}
+
+fn migrate_activity_record_ix(
+  signer: &Pubkey,
+  chain_count: u64,
+) -> Instruction {
+  ix(
+    chainbills::accounts::MigrateActivityRecord {
+      user_activity_info: Some(activity_info_pda(signer, 1)),
+      user_activities_page: None,
+      activity: activity_pda(chain_count),
+      signer: *signer,
+      system_program: system_program::ID,
+    },
+    chainbills::instruction::MigrateActivityRecord { user_count: 1 },
+  )
+}
+
+fn migrate_config_ix(owner: &Pubkey) -> Instruction {
+  ix(
+    chainbills::accounts::MigrateConfig {
+      config: config_pda(),
+      owner: *owner,
+      system_program: system_program::ID,
+    },
+    chainbills::instruction::MigrateConfig {},
+  )
+}
+
+fn migrate_chain_stats_ix(owner: &Pubkey) -> Instruction {
+  ix(
+    chainbills::accounts::MigrateChainStats {
+      chain_stats: chain_stats_pda(),
+      config: config_pda(),
+      owner: *owner,
+      system_program: system_program::ID,
+    },
+    chainbills::instruction::MigrateChainStats {},
+  )
+}
+
+fn migrate_token_details_ix(owner: &Pubkey, token: Pubkey) -> Instruction {
+  ix(
+    chainbills::accounts::MigrateTokenDetails {
+      token_details: token_details_pda(&token),
+      config: config_pda(),
+      owner: *owner,
+      system_program: system_program::ID,
+    },
+    chainbills::instruction::MigrateTokenDetails { token },
+  )
+}
+
+fn migrate_native_vault_ix(owner: &Pubkey) -> Instruction {
+  ix(
+    chainbills::accounts::MigrateNativeVault {
+      chain_stats: chain_stats_pda(),
+      native_vault: native_vault_pda(),
+      config: config_pda(),
+      owner: *owner,
+    },
+    chainbills::instruction::MigrateNativeVault {},
+  )
+}
+
+#[tokio::test]
+async fn migrate_user() {
+  let mut env = Env::new().await;
+  let wallet = env.wallet().await;
+  set_legacy_account::<User>(
+    &mut env,
+    user_pda(&wallet.pubkey()),
+    &UserV0 {
+      chain_count: 1,
+      payables_count: 2,
+      payments_count: 3,
+      withdrawals_count: 4,
+      activities_count: 5,
+    },
+  )
+  .await;
+
+  let ix = migrate_user_ix(&wallet.pubkey());
+  let outcome = env.send(&[ix], &[&wallet]).await.unwrap();
+  let event = outcome.event::<MigratedAccount>();
+  assert_eq!(event.account, user_pda(&wallet.pubkey()));
+  assert_eq!(event.version, User::VERSION);
+  let user = env.user(&wallet.pubkey()).await;
+  assert_eq!(user.version, User::VERSION);
+  assert_eq!(user.chain_count, 1);
+  assert_eq!(user.payables_count, 2);
+  assert_eq!(user.activities_count, 5);
+  let account = env.get_account(user_pda(&wallet.pubkey())).await.unwrap();
+  assert_eq!(account.data.len(), User::SPACE);
+  let rent = env.rent();
+  assert!(account.lamports >= rent.minimum_balance(User::SPACE));
+
+  let ix = migrate_user_ix(&wallet.pubkey());
+  assert_error(
+    env.send(&[ix], &[&wallet]).await,
+    ChainbillsError::AlreadyMigrated,
+  );
+}
+
+#[tokio::test]
+async fn migrate_payable() {
+  let mut env = Env::new().await;
+  let host = env.new_user().await;
+  let stranger = env.new_user().await;
+  let payable = Keypair::new().pubkey();
+  let taa = TokenAndAmount {
+    token: Keypair::new().pubkey(),
+    amount: 100,
+  };
+  set_legacy_account::<Payable>(
+    &mut env,
+    payable,
+    &PayableV0 {
+      chain_count: 1,
+      host: host.pubkey(),
+      host_count: 1,
+      created_at: 10,
+      payments_count: 2,
+      withdrawals_count: 0,
+      activities_count: 3,
+      is_closed: false,
+      allowed_tokens_and_amounts: vec![taa],
+      balances: vec![taa],
+    },
+  )
+  .await;
+
+  let ix = migrate_payable_ix(&stranger.pubkey(), payable);
+  assert_error(
+    env.send(&[ix], &[&stranger]).await,
+    ChainbillsError::NotYourPayable,
+  );
+  let ix = migrate_payable_ix(&host.pubkey(), payable);
+  env.send(&[ix], &[&host]).await.unwrap();
+  let payable_data = env.payable(payable).await;
+  assert_eq!(payable_data.version, Payable::VERSION);
+  assert_eq!(payable_data.host, host.pubkey());
+  assert_eq!(payable_data.payments_count, 2);
+  assert_eq!(payable_data.balances[0].amount, 100);
+  let size = env.get_account(payable).await.unwrap().data.len();
+  assert_eq!(size, payable_data.space_current());
+
+  let ix = migrate_payable_ix(&host.pubkey(), payable);
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::AlreadyMigrated,
+  );
+}
+
+#[tokio::test]
+async fn migrate_payments_and_withdrawals() {
+  let mut env = Env::new().await;
+  let host = env.new_user().await;
+  let payer = env.new_user().await;
+  let payable = Keypair::new().pubkey();
+  let details = TokenAndAmount {
+    token: Keypair::new().pubkey(),
+    amount: 100,
+  };
+
+  let user_payment = Keypair::new().pubkey();
+  set_legacy_account::<UserPayment>(
+    &mut env,
+    user_payment,
+    &UserPaymentV0 {
+      payable_id: payable.to_bytes(),
+      payer: payer.pubkey(),
+      payable_chain_id: 1,
+      chain_count: 1,
+      payer_count: 1,
+      timestamp: 10,
+      details,
+    },
+  )
+  .await;
+  let ix = migrate_user_payment_ix(&host.pubkey(), user_payment);
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::NotYourPayment,
+  );
+  let ix = migrate_user_payment_ix(&payer.pubkey(), user_payment);
+  env.send(&[ix], &[&payer]).await.unwrap();
+  let migrated: UserPayment = env.account(user_payment).await;
+  assert_eq!(migrated.payer, payer.pubkey());
+  assert_eq!(migrated.payable_chain_id, solana_cb_chain_id());
+  assert_eq!(migrated.details.amount, 100);
+  assert_eq!(migrated.rent_payer, payer.pubkey());
+  let ix = migrate_user_payment_ix(&payer.pubkey(), user_payment);
+  assert_error(
+    env.send(&[ix], &[&payer]).await,
+    ChainbillsError::AlreadyMigrated,
+  );
+
+  // Payments from before rent payers were recorded were paid for by payers.
+  let user_payment = Keypair::new().pubkey();
+  set_legacy_account::<UserPayment>(
+    &mut env,
+    user_payment,
+    &UserPaymentV1 {
+      payable_id: payable.to_bytes(),
+      payer: payer.pubkey(),
+      payable_chain_id: solana_cb_chain_id(),
+      chain_count: 2,
+      payer_count: 2,
+      timestamp: 10,
+      details,
+    },
+  )
+  .await;
+  let ix = migrate_user_payment_ix(&payer.pubkey(), user_payment);
+  env.send(&[ix], &[&payer]).await.unwrap();
+  let migrated: UserPayment = env.account(user_payment).await;
+  assert_eq!(migrated.chain_count, 2);
+  assert_eq!(migrated.rent_payer, payer.pubkey());
+
+  // Anyone can migrate a payable's payments.
+  let payable_payment = Keypair::new().pubkey();
+  set_legacy_account::<PayablePayment>(
+    &mut env,
+    payable_payment,
+    &PayablePaymentV0 {
+      payable_id: payable,
+      payer: payer.pubkey().to_bytes(),
+      chain_count: 1,
+      payer_chain_id: 1,
+      local_chain_count: 1,
+      payable_count: 1,
+      timestamp: 10,
+      details,
+    },
+  )
+  .await;
+  let ix = migrate_payable_payment_ix(&host.pubkey(), payable_payment);
+  env.send(&[ix], &[&host]).await.unwrap();
+  let migrated: PayablePayment = env.account(payable_payment).await;
+  assert_eq!(migrated.payable_id, payable);
+  assert_eq!(migrated.payer_chain_id, solana_cb_chain_id());
+  assert_eq!(migrated.local_chain_count, 1);
+  let ix = migrate_payable_payment_ix(&host.pubkey(), payable_payment);
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::AlreadyMigrated,
+  );
+
+  let withdrawal = Keypair::new().pubkey();
+  set_legacy_account::<Withdrawal>(
+    &mut env,
+    withdrawal,
+    &WithdrawalV0 {
+      payable_id: payable,
+      host: host.pubkey(),
+      chain_count: 1,
+      host_count: 1,
+      payable_count: 1,
+      timestamp: 10,
+      details,
+    },
+  )
+  .await;
+  let ix = migrate_withdrawal_ix(&payer.pubkey(), withdrawal);
+  assert_error(
+    env.send(&[ix], &[&payer]).await,
+    ChainbillsError::NotYourWithdrawal,
+  );
+  let ix = migrate_withdrawal_ix(&host.pubkey(), withdrawal);
+  env.send(&[ix], &[&host]).await.unwrap();
+  let migrated: Withdrawal = env.account(withdrawal).await;
+  assert_eq!(migrated.host, host.pubkey());
+  assert_eq!(migrated.dest_chain_id, solana_cb_chain_id());
+  assert_eq!(migrated.dest_address, host.pubkey().to_bytes());
+  assert_eq!(migrated.rent_payer, host.pubkey());
+  let ix = migrate_withdrawal_ix(&host.pubkey(), withdrawal);
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::AlreadyMigrated,
+  );
+
+  let withdrawal = Keypair::new().pubkey();
+  set_legacy_account::<Withdrawal>(
+    &mut env,
+    withdrawal,
+    &WithdrawalV1 {
+      payable_id: payable,
+      host: host.pubkey(),
+      chain_count: 2,
+      host_count: 2,
+      payable_count: 2,
+      timestamp: 10,
+      details,
+      dest_chain_id: solana_cb_chain_id(),
+      dest_address: host.pubkey().to_bytes(),
+    },
+  )
+  .await;
+  let ix = migrate_withdrawal_ix(&host.pubkey(), withdrawal);
+  env.send(&[ix], &[&host]).await.unwrap();
+  let migrated: Withdrawal = env.account(withdrawal).await;
+  assert_eq!(migrated.chain_count, 2);
+  assert_eq!(migrated.rent_payer, host.pubkey());
+}
+
+#[tokio::test]
+async fn migrate_activity_record() {
+  let mut env = Env::new().await;
+  let wallet = env.new_user().await;
+  let other = env.new_user().await;
+  let chain_count = env
+    .account::<UserActivityInfo>(activity_info_pda(&wallet.pubkey(), 1))
+    .await
+    .chain_count;
+  let activity: ActivityRecord = env.account(activity_pda(chain_count)).await;
+  set_legacy_account::<ActivityRecord>(
+    &mut env,
+    activity_pda(chain_count),
+    &ActivityRecordV0 {
+      chain_count,
+      user_count: activity.user_count,
+      payable_count: activity.payable_count,
+      timestamp: activity.timestamp,
+      entity: activity.entity,
+      activity_type: activity.activity_type,
+    },
+  )
+  .await;
+
+  // The seeds of the activity tie it to its user.
+  let ix = migrate_activity_record_ix(&other.pubkey(), chain_count);
+  assert!(env.send(&[ix], &[&other]).await.is_err());
+
+  let ix = migrate_activity_record_ix(&wallet.pubkey(), chain_count);
+  env.send(&[ix], &[&wallet]).await.unwrap();
+  let migrated: ActivityRecord = env.account(activity_pda(chain_count)).await;
+  assert_eq!(migrated.chain_count, chain_count);
+  assert_eq!(migrated.entity, activity.entity);
+  assert_eq!(migrated.rent_payer, wallet.pubkey());
+  let ix = migrate_activity_record_ix(&wallet.pubkey(), chain_count);
+  assert_error(
+    env.send(&[ix], &[&wallet]).await,
+    ChainbillsError::AlreadyMigrated,
+  );
+}
+
+#[tokio::test]
+async fn migrate_chain_stats() {
+  let mut env = Env::new().await;
+  let stranger = env.wallet().await;
+  let chain_stats = env.chain_stats().await;
+  set_legacy_account::<ChainStats>(
+    &mut env,
+    chain_stats_pda(),
+    &ChainStatsV0 {
+      users_count: 2,
+      payables_count: 3,
+      foreign_payables_count: 0,
+      user_payments_count: 4,
+      payable_payments_count: 4,
+      withdrawals_count: 1,
+      activities_count: chain_stats.activities_count,
+      published_wormhole_messages_count: 0,
+      consumed_wormhole_messages_count: 0,
+    },
+  )
+  .await;
+
+  let ix = migrate_chain_stats_ix(&stranger.pubkey());
+  assert_error(
+    env.send(&[ix], &[&stranger]).await,
+    ChainbillsError::OwnerUnauthorized,
+  );
+  let ix = migrate_chain_stats_ix(&env.owner());
+  env.send(&[ix], &[]).await.unwrap();
+  let migrated = env.chain_stats().await;
+  assert_eq!(migrated.version, ChainStats::VERSION);
+  assert_eq!(migrated.users_count, 2);
+  assert_eq!(migrated.user_payments_count, 4);
+  assert_eq!(migrated.activities_count, chain_stats.activities_count);
+  let size = env.get_account(chain_stats_pda()).await.unwrap().data.len();
+  assert_eq!(size, ChainStats::SPACE);
+  let ix = migrate_chain_stats_ix(&env.owner());
+  assert_error(env.send(&[ix], &[]).await, ChainbillsError::AlreadyMigrated);
+}
+
+#[tokio::test]
+async fn migrate_config_and_token_details() {
+  let mut env = Env::new().await;
+  let mint = env.supported_mint(6).await;
+  let stranger = env.wallet().await;
+
+  let config = env.config().await;
+  set_legacy_account::<Config>(
+    &mut env,
+    config_pda(),
+    &ConfigV0 {
+      chain_id: config.chain_id,
+      withdrawal_fee_percentage: config.withdrawal_fee_percentage,
+      owner: config.owner,
+      chainbills_fee_collector: config.chainbills_fee_collector,
+      wormhole_bridge: config.wormhole_bridge,
+      wormhole_emitter: config.wormhole_emitter,
+      wormhole_fee_collector: config.wormhole_fee_collector,
+      wormhole_sequence: config.wormhole_sequence,
+    },
+  )
+  .await;
+  let ix = migrate_config_ix(&stranger.pubkey());
+  assert_error(
+    env.send(&[ix], &[&stranger]).await,
+    ChainbillsError::OwnerUnauthorized,
+  );
+  let ix = migrate_config_ix(&env.owner());
+  env.send(&[ix], &[]).await.unwrap();
+  let migrated = env.config().await;
+  assert_eq!(migrated.version, Config::VERSION);
+  assert_eq!(migrated.owner, config.owner);
+  assert_eq!(migrated.chain_id, config.chain_id);
+  assert_eq!(migrated.wormhole_sequence, config.wormhole_sequence);
+  let ix = migrate_config_ix(&env.owner());
+  assert_error(env.send(&[ix], &[]).await, ChainbillsError::AlreadyMigrated);
+
+  let token_details = env.token_details(&mint).await;
+  set_legacy_account::<TokenDetails>(
+    &mut env,
+    token_details_pda(&mint),
+    &TokenDetailsV0 {
+      mint,
+      is_supported: true,
+      max_withdrawal_fees: token_details.max_withdrawal_fees,
+      total_user_paid: 7,
+      total_payable_received: 7,
+      total_withdrawn: 0,
+      total_withdrawal_fees_collected: 0,
+    },
+  )
+  .await;
+  let ix = migrate_token_details_ix(&stranger.pubkey(), mint);
+  assert_error(
+    env.send(&[ix], &[&stranger]).await,
+    ChainbillsError::OwnerUnauthorized,
+  );
+  let ix = migrate_token_details_ix(&env.owner(), mint);
+  env.send(&[ix], &[]).await.unwrap();
+  let migrated = env.token_details(&mint).await;
+  assert_eq!(migrated.version, TokenDetails::VERSION);
+  assert_eq!(
+    migrated.max_withdrawal_fees,
+    token_details.max_withdrawal_fees
+  );
+  assert_eq!(migrated.total_user_paid, 7);
+}
+
+#[tokio::test]
+async fn migrate_native_vault() {
+  let mut env = Env::new().await;
+  env.support_native().await;
+  let stranger = env.wallet().await;
+
+  // Nothing to move while ChainStats holds just its rent.
+  let ix = migrate_native_vault_ix(&env.owner());
+  assert_error(env.send(&[ix], &[]).await, ChainbillsError::AlreadyMigrated);
+
+  // Native payments used to be held by ChainStats.
+  env.fund(&chain_stats_pda(), 5_000_000).await;
+  let chain_stats_lamports = env.lamports(chain_stats_pda()).await;
+  let vault_lamports = env.lamports(native_vault_pda()).await;
+  let ix = migrate_native_vault_ix(&stranger.pubkey());
+  assert_error(
+    env.send(&[ix], &[&stranger]).await,
+    ChainbillsError::OwnerUnauthorized,
+  );
+  let ix = migrate_native_vault_ix(&env.owner());
+  env.send(&[ix], &[]).await.unwrap();
+  assert_eq!(
+    env.lamports(chain_stats_pda()).await,
+    chain_stats_lamports - 5_000_000
+  );
+  assert_eq!(
+    env.lamports(native_vault_pda()).await,
+    vault_lamports + 5_000_000
+  );
+}
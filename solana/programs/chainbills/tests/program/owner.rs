@@ -0,0 +1,625 @@
+use crate::common::*;
+use anchor_lang::{prelude::*, system_program};
+use anchor_spl::{
+  associated_token::spl_associated_token_account,
+  token::spl_token,
+  token_interface::spl_token_2022::{
+    self,
+    extension::{
+      default_account_state, transfer_fee, transfer_hook, ExtensionType,
+    },
+    state::{AccountState, Mint},
+  },
+};
+use chainbills::{error::ChainbillsError, events::*, state::*};
+use solana_sdk::{
+  instruction::Instruction, signature::Keypair, signer::Signer,
+  system_instruction,
+};
+
+#[tokio::test]
+async fn update_max_withdrawal_fees() {
+  let mut env = Env::new().await;
+  let mint = env.create_mint(6).await;
+
+  let ix = env.update_max_withdrawal_fees_ix(&mint, 1_000);
+  let outcome = env.send(&[ix], &[]).await.unwrap();
+  let event = outcome.event::<UpdatedMaxWithdrawalFees>();
+  assert_eq!(event.token, mint);
+  assert_eq!(event.max_withdrawal_fees, 1_000);
+  let token_details = env.token_details(&mint).await;
+  assert_eq!(token_details.mint, mint);
+  assert!(token_details.is_supported);
+  assert_eq!(token_details.max_withdrawal_fees, 1_000);
+  // The program's and the fee collector's token accounts were created.
+  assert!(env.exists(ata(&chain_stats_pda(), &mint)).await);
+  assert!(env.exists(ata(&env.fee_collector, &mint)).await);
+
+  // Updating again only changes the fees.
+  let ix = env.update_max_withdrawal_fees_ix(&mint, 2_000);
+  env.send(&[ix], &[]).await.unwrap();
+  assert_eq!(env.token_details(&mint).await.max_withdrawal_fees, 2_000);
+
+  // Only the owner can update them.
+  let stranger = env.wallet().await;
+  let mut ix = env.update_max_withdrawal_fees_ix(&mint, 3_000);
+  swap_account(&mut ix, env.owner(), stranger.pubkey());
+  assert_error(
+    env.send(&[ix], &[&stranger]).await,
+    ChainbillsError::OwnerUnauthorized,
+  );
+
+  // The fee collector must be the configured one.
+  let other_mint = env.create_mint(6).await;
+  let mut ix = env.update_max_withdrawal_fees_ix(&other_mint, 3_000);
+  let wrong_collector = Pubkey::new_unique();
+  swap_account(&mut ix, env.fee_collector, wrong_collector);
+  swap_account(
+    &mut ix,
+    ata(&env.fee_collector, &other_mint),
+    ata(&wrong_collector, &other_mint),
+  );
+  assert_error(
+    env.send(&[ix], &[]).await,
+    ChainbillsError::WrongFeeCollectorAddress,
+  );
+}
+
+/// Creates a Token-2022 mint with the given extensions, initializing them with
+/// `init` before the mint itself.
+async fn create_mint_2022(
+  env: &mut Env,
+  extensions: &[ExtensionType],
+  init: impl FnOnce(&Pubkey) -> Vec<Instruction>,
+) -> Pubkey {
+  let mint = Keypair::new();
+  let space =
+    ExtensionType::try_calculate_account_len::<Mint>(extensions).unwrap();
+  let rent = env.rent();
+  let owner = env.owner();
+  let mut instructions = vec![system_instruction::create_account(
+    &owner,
+    &mint.pubkey(),
+    rent.minimum_balance(space),
+    space as u64,
+    &spl_token_2022::ID,
+  )];
+  instructions.extend(init(&mint.pubkey()));
+  instructions.push(
+    spl_token_2022::instruction::initialize_mint2(
+      &spl_token_2022::ID,
+      &mint.pubkey(),
+      &owner,
+      Some(&owner),
+      6,
+    )
+    .unwrap(),
+  );
+  env.send(&instructions, &[&mint]).await.unwrap();
+  mint.pubkey()
+}
+
+fn update_max_withdrawal_fees_2022_ix(env: &Env, mint: Pubkey) -> Instruction {
+  ix(
+    chainbills::accounts::UpdateMaxWithdrawalFees {
+      token_details: token_details_pda(&mint),
+      chain_token_account: spl_associated_token_account::get_associated_token_address_with_program_id(
+        &chain_stats_pda(),
+        &mint,
+        &spl_token_2022::ID,
+      ),
+      fee_collector: env.fee_collector,
+      fees_token_account: spl_associated_token_account::get_associated_token_address_with_program_id(
+        &env.fee_collector,
+        &mint,
+        &spl_token_2022::ID,
+      ),
+      config: config_pda(),
+      chain_stats: chain_stats_pda(),
+      mint,
+      owner: env.owner(),
+      associated_token_program: spl_associated_token_account::ID,
+      token_program: spl_token_2022::ID,
+      system_program: system_program::ID,
+    },
+    chainbills::instruction::UpdateMaxWithdrawalFees {
+      token: mint,
+      max_withdrawal_fees: 1_000,
+    },
+  )
+}
+
+#[tokio::test]
+async fn rejects_mints_with_unsupported_extensions() {
+  let mut env = Env::new().await;
+  let owner = env.owner();
+
+  let non_transferable =
+    create_mint_2022(&mut env, &[ExtensionType::NonTransferable], |mint| {
+      vec![
+        spl_token_2022::instruction::initialize_non_transferable_mint(
+          &spl_token_2022::ID,
+          mint,
+        )
+        .unwrap(),
+      ]
+    })
+    .await;
+  // A transfer hook without a program can still get one from its authority.
+  let hook_authority =
+    create_mint_2022(&mut env, &[ExtensionType::TransferHook], |mint| {
+      vec![transfer_hook::instruction::initialize(
+        &spl_token_2022::ID,
+        mint,
+        Some(owner),
+        None,
+      )
+      .unwrap()]
+    })
+    .await;
+  let frozen_by_default =
+    create_mint_2022(&mut env, &[ExtensionType::DefaultAccountState], |mint| {
+      vec![
+        default_account_state::instruction::initialize_default_account_state(
+          &spl_token_2022::ID,
+          mint,
+          &AccountState::Frozen,
+        )
+        .unwrap(),
+      ]
+    })
+    .await;
+  let mutable_fees =
+    create_mint_2022(&mut env, &[ExtensionType::TransferFeeConfig], |mint| {
+      vec![transfer_fee::instruction::initialize_transfer_fee_config(
+        &spl_token_2022::ID,
+        mint,
+        Some(&owner),
+        None,
+        100,
+        1_000,
+      )
+      .unwrap()]
+    })
+    .await;
+
+  for mint in [
+    non_transferable,
+    hook_authority,
+    frozen_by_default,
+    mutable_fees,
+  ] {
+    let ix = update_max_withdrawal_fees_2022_ix(&env, mint);
+    assert_error(
+      env.send(&[ix], &[]).await,
+      ChainbillsError::UnsupportedMintExtension,
+    );
+  }
+
+  // Fixed transfer fees are fine.
+  let fixed_fees =
+    create_mint_2022(&mut env, &[ExtensionType::TransferFeeConfig], |mint| {
+      vec![transfer_fee::instruction::initialize_transfer_fee_config(
+        &spl_token_2022::ID,
+        mint,
+        None,
+        None,
+        100,
+        1_000,
+      )
+      .unwrap()]
+    })
+    .await;
+  let ix = update_max_withdrawal_fees_2022_ix(&env, fixed_fees);
+  env.send(&[ix], &[]).await.unwrap();
+  assert!(env.token_details(&fixed_fees).await.is_supported);
+}
+
+#[tokio::test]
+async fn update_max_withdrawal_fees_native() {
+  let mut env = Env::new().await;
+  assert_eq!(env.lamports(native_vault_pda()).await, 0);
+  env.support_native().await;
+  let token_details = env.token_details(&chainbills::ID).await;
+  assert_eq!(token_details.mint, chainbills::ID);
+  assert!(token_details.is_supported);
+  assert_eq!(token_details.max_withdrawal_fees, MAX_WITHDRAWAL_FEES);
+  // The vault was funded with its rent-exempt reserve.
+  let rent = env.rent();
+  assert_eq!(
+    env.lamports(native_vault_pda()).await,
+    rent.minimum_balance(0)
+  );
+
+  let stranger = env.wallet().await;
+  let ix = ix(
+    chainbills::accounts::UpdateMaxWithdrawalFeesNative {
+      token_details: token_details_pda(&chainbills::ID),
+      native_vault: native_vault_pda(),
+      config: config_pda(),
+      owner: stranger.pubkey(),
+      system_program: system_program::ID,
+    },
+    chainbills::instruction::UpdateMaxWithdrawalFeesNative {
+      max_withdrawal_fees: 0,
+    },
+  );
+  assert_error(
+    env.send(&[ix], &[&stranger]).await,
+    ChainbillsError::OwnerUnauthorized,
+  );
+}
+
+fn update_token_group_ix(
+  owner: Pubkey,
+  name: &str,
+  decimals: u8,
+  members: Vec<TokenGroupMember>,
+) -> solana_sdk::instruction::Instruction {
+  ix(
+    chainbills::accounts::UpdateTokenGroup {
+      token_group: token_group_pda(name),
+      config: config_pda(),
+      owner,
+      system_program: system_program::ID,
+    },
+    chainbills::instruction::UpdateTokenGroup {
+      name: name.to_string(),
+      decimals,
+      members,
+    },
+  )
+}
+
+#[tokio::test]
+async fn update_token_group() {
+  let mut env = Env::new().await;
+  let usdc = TokenGroupMember {
+    token: Pubkey::new_unique(),
+    decimals: 6,
+  };
+  let usdt = TokenGroupMember {
+    token: Pubkey::new_unique(),
+    decimals: 8,
+  };
+
+  let ix = update_token_group_ix(env.owner(), "usd", 6, vec![usdc, usdt]);
+  let outcome = env.send(&[ix], &[]).await.unwrap();
+  let event = outcome.event::<UpdatedTokenGroup>();
+  assert_eq!(event.token_group, token_group_pda("usd"));
+  assert_eq!(event.name, "usd");
+  assert_eq!(event.members, vec![usdc, usdt]);
+  let group: TokenGroup = env.account(token_group_pda("usd")).await;
+  assert_eq!(group.name, "usd");
+  assert_eq!(group.decimals, 6);
+  assert_eq!(group.members, vec![usdc, usdt]);
+
+  // Updating replaces the members.
+  let ix = update_token_group_ix(env.owner(), "usd", 6, vec![usdt]);
+  env.send(&[ix], &[]).await.unwrap();
+  let group: TokenGroup = env.account(token_group_pda("usd")).await;
+  assert_eq!(group.members, vec![usdt]);
+
+  // Names must be 1 to 32 bytes, members are limited and unique.
+  let too_many = (0..=TokenGroup::MAX_MEMBERS)
+    .map(|_| TokenGroupMember {
+      token: Pubkey::new_unique(),
+      decimals: 6,
+    })
+    .collect();
+  for (name, members) in [
+    ("", vec![usdc]),
+    ("eur", vec![usdc, usdc]),
+    ("eur", too_many),
+  ] {
+    let ix = update_token_group_ix(env.owner(), name, 6, members);
+    assert_error(
+      env.send(&[ix], &[]).await,
+      ChainbillsError::InvalidTokenGroup,
+    );
+  }
+
+  // Only the owner can manage groups.
+  let stranger = env.wallet().await;
+  let ix = update_token_group_ix(stranger.pubkey(), "eur", 6, vec![]);
+  assert_error(
+    env.send(&[ix], &[&stranger]).await,
+    ChainbillsError::OwnerUnauthorized,
+  );
+}
+
+fn cb_chain_pda(cb_chain_id: &[u8; 32]) -> Pubkey {
+  pda(&[CbChain::SEED_PREFIX, cb_chain_id])
+}
+
+pub(crate) fn register_cb_chain_ix(
+  owner: Pubkey,
+  caip2: &str,
+) -> solana_sdk::instruction::Instruction {
+  ix(
+    chainbills::accounts::RegisterCbChain {
+      cb_chain: cb_chain_pda(&cb_chain_id(caip2)),
+      config: config_pda(),
+      owner,
+      system_program: system_program::ID,
+    },
+    chainbills::instruction::RegisterCbChain {
+      caip2: caip2.to_string(),
+    },
+  )
+}
+
+pub(crate) fn register_chain_wormhole_id_ix(
+  owner: Pubkey,
+  cb_chain_id: [u8; 32],
+  wormhole_chain_id: u16,
+) -> solana_sdk::instruction::Instruction {
+  ix(
+    chainbills::accounts::RegisterChainWormholeId {
+      cb_chain: cb_chain_pda(&cb_chain_id),
+      wormhole_chain_cb_chain_id: pda(&[
+        WormholeChainCbChainId::SEED_PREFIX,
+        &wormhole_chain_id.to_le_bytes(),
+      ]),
+      config: config_pda(),
+      owner,
+      system_program: system_program::ID,
+    },
+    chainbills::instruction::RegisterChainWormholeId {
+      cb_chain_id,
+      wormhole_chain_id,
+    },
+  )
+}
+
+fn register_chain_circle_domain_ix(
+  owner: Pubkey,
+  cb_chain_id: [u8; 32],
+  circle_domain: u32,
+) -> solana_sdk::instruction::Instruction {
+  ix(
+    chainbills::accounts::RegisterChainCircleDomain {
+      cb_chain: cb_chain_pda(&cb_chain_id),
+      circle_domain_cb_chain_id: pda(&[
+        CircleDomainCbChainId::SEED_PREFIX,
+        &circle_domain.to_le_bytes(),
+      ]),
+      config: config_pda(),
+      owner,
+      system_program: system_program::ID,
+    },
+    chainbills::instruction::RegisterChainCircleDomain {
+      cb_chain_id,
+      circle_domain,
+    },
+  )
+}
+
+#[tokio::test]
+async fn register_cb_chains() {
+  let mut env = Env::new().await;
+  let owner = env.owner();
+  let caip2 = "eip155:8453";
+  let id = cb_chain_id(caip2);
+
+  let ix = register_cb_chain_ix(owner, caip2);
+  let outcome = env.send(&[ix], &[]).await.unwrap();
+  let event = outcome.event::<RegisteredCbChain>();
+  assert_eq!(event.cb_chain_id, id);
+  assert_eq!(event.caip2, caip2);
+
+  // Chains can't be registered twice, and their CAIP-2 must be valid.
+  let ix = register_cb_chain_ix(owner, caip2);
+  assert!(env.send(&[ix], &[]).await.is_err());
+  for invalid in ["eip155", "EIP155:1", "ab:1", "eip155:", "eip155:1.0"] {
+    let ix = register_cb_chain_ix(owner, invalid);
+    assert_error(env.send(&[ix], &[]).await, ChainbillsError::InvalidCaip2);
+  }
+  let stranger = env.wallet().await;
+  let ix = register_cb_chain_ix(stranger.pubkey(), "cosmos:osmosis-1");
+  assert_error(
+    env.send(&[ix], &[&stranger]).await,
+    ChainbillsError::OwnerUnauthorized,
+  );
+
+  // Wormhole Chain IDs are non-zero and registered once per chain.
+  let ix = register_chain_wormhole_id_ix(owner, id, 0);
+  assert_error(
+    env.send(&[ix], &[]).await,
+    ChainbillsError::InvalidWormholeChainId,
+  );
+  let ix = register_chain_wormhole_id_ix(owner, id, 30);
+  let outcome = env.send(&[ix], &[]).await.unwrap();
+  assert_eq!(
+    outcome
+      .event::<RegisteredChainWormholeId>()
+      .wormhole_chain_id,
+    30
+  );
+  let ix = register_chain_wormhole_id_ix(owner, id, 31);
+  assert_error(
+    env.send(&[ix], &[]).await,
+    ChainbillsError::ChainIdAlreadyRegistered,
+  );
+  let reverse: WormholeChainCbChainId = env
+    .account(pda(&[
+      WormholeChainCbChainId::SEED_PREFIX,
+      &30u16.to_le_bytes(),
+    ]))
+    .await;
+  assert_eq!(reverse.cb_chain_id, id);
+
+  // Circle domains can be zero, but are also registered once per chain.
+  let ix = register_chain_circle_domain_ix(owner, id, 0);
+  let outcome = env.send(&[ix], &[]).await.unwrap();
+  assert_eq!(
+    outcome.event::<RegisteredChainCircleDomain>().circle_domain,
+    0
+  );
+  let ix = register_chain_circle_domain_ix(owner, id, 6);
+  assert_error(
+    env.send(&[ix], &[]).await,
+    ChainbillsError::ChainIdAlreadyRegistered,
+  );
+
+  let cb_chain: CbChain = env.account(cb_chain_pda(&id)).await;
+  assert_eq!(cb_chain.caip2, caip2);
+  assert_eq!(cb_chain.wormhole_chain_id, 30);
+  assert!(cb_chain.has_circle_domain);
+  assert_eq!(cb_chain.circle_domain, 0);
+}
+
+pub(crate) fn register_foreign_contract_ix(
+  owner: Pubkey,
+  chain_id: u16,
+  emitter_address: [u8; 32],
+) -> solana_sdk::instruction::Instruction {
+  ix(
+    chainbills::accounts::RegisterForeignContract {
+      registered_foreign_contract: pda(&[
+        RegisteredForeignContract::SEED_PREFIX,
+        &chain_id.to_le_bytes(),
+      ]),
+      chain_stats: chain_stats_pda(),
+      config: config_pda(),
+      owner,
+      system_program: system_program::ID,
+    },
+    chainbills::instruction::RegisterForeignContract {
+      chain_id,
+      emitter_address,
+    },
+  )
+}
+
+#[tokio::test]
+async fn register_foreign_contract() {
+  let mut env = Env::new().await;
+  let owner = env.owner();
+
+  let ix = register_foreign_contract_ix(owner, 30, [7; 32]);
+  let outcome = env.send(&[ix], &[]).await.unwrap();
+  let event = outcome.event::<RegisteredForeignContractEvent>();
+  assert_eq!(event.chain_id, 30);
+  assert_eq!(event.emitter_address, [7; 32]);
+
+  // Registering again replaces the emitter.
+  let ix = register_foreign_contract_ix(owner, 30, [8; 32]);
+  env.send(&[ix], &[]).await.unwrap();
+  let contract: RegisteredForeignContract = env
+    .account(pda(&[
+      RegisteredForeignContract::SEED_PREFIX,
+      &30u16.to_le_bytes(),
+    ]))
+    .await;
+  assert_eq!(contract.chain_id, 30);
+  assert_eq!(contract.emitter_address, [8; 32]);
+
+  // Foreign contracts can't be on Solana, nor have empty emitters.
+  for (chain_id, emitter) in [
+    (0, [7; 32]),
+    (1, [7; 32]),
+    (30, [0; 32]),
+    (30, chainbills::ID.to_bytes()),
+  ] {
+    let ix = register_foreign_contract_ix(owner, chain_id, emitter);
+    assert_error(
+      env.send(&[ix], &[]).await,
+      ChainbillsError::InvalidForeignContract,
+    );
+  }
+
+  let stranger = env.wallet().await;
+  let ix = register_foreign_contract_ix(stranger.pubkey(), 30, [9; 32]);
+  assert_error(
+    env.send(&[ix], &[&stranger]).await,
+    ChainbillsError::OwnerUnauthorized,
+  );
+}
+
+#[tokio::test]
+async fn update_token_foreign_chain() {
+  let mut env = Env::new().await;
+  let token = Pubkey::new_unique();
+  let update_ix = |owner: Pubkey| {
+    ix(
+      chainbills::accounts::UpdateTokenForeignChain {
+        token_foreign_chain: pda(&[
+          TokenForeignChain::SEED_PREFIX,
+          &30u16.to_le_bytes(),
+        ]),
+        config: config_pda(),
+        owner,
+        system_program: system_program::ID,
+      },
+      chainbills::instruction::UpdateTokenForeignChain {
+        chain: 30,
+        foreign_token: [5; 32],
+        token,
+      },
+    )
+  };
+
+  let outcome = env.send(&[update_ix(env.owner())], &[]).await.unwrap();
+  let event = outcome.event::<RegisteredMatchingTokenForForeignChain>();
+  assert_eq!(event.chain_id, 30);
+  assert_eq!(event.token, token);
+  let stored: TokenForeignChain = env
+    .account(pda(&[TokenForeignChain::SEED_PREFIX, &30u16.to_le_bytes()]))
+    .await;
+  assert_eq!(stored.foreign_token, [5; 32]);
+  assert_eq!(stored.token, token);
+
+  let stranger = env.wallet().await;
+  assert_error(
+    env
+      .send(&[update_ix(stranger.pubkey())], &[&stranger])
+      .await,
+    ChainbillsError::OwnerUnauthorized,
+  );
+}
+
+#[tokio::test]
+async fn owner_withdraw() {
+  let mut env = Env::new().await;
+  let mint = env.supported_mint(6).await;
+  let owner = env.owner();
+  // Tokens sent straight to the program don't belong to any payable.
+  env.mint_to(&mint, &chain_stats_pda(), 1_000).await;
+  env.mint_to(&mint, &owner, 0).await;
+
+  let withdraw_ix = |owner: Pubkey, amount: u64| {
+    ix(
+      chainbills::accounts::OwnerWithdraw {
+        mint,
+        chain_stats: chain_stats_pda(),
+        chain_token_account: ata(&chain_stats_pda(), &mint),
+        owner_token_account: ata(&owner, &mint),
+        config: config_pda(),
+        owner,
+        token_program: spl_token::ID,
+      },
+      chainbills::instruction::OwnerWithdraw { amount },
+    )
+  };
+
+  assert_error(
+    env.send(&[withdraw_ix(owner, 0)], &[]).await,
+    ChainbillsError::ZeroAmountSpecified,
+  );
+  let outcome = env.send(&[withdraw_ix(owner, 400)], &[]).await.unwrap();
+  let event = outcome.event::<OwnerWithdrew>();
+  assert_eq!(event.token, mint);
+  assert_eq!(event.amount, 400);
+  assert_eq!(env.token_balance(ata(&owner, &mint)).await, 400);
+  assert_eq!(env.token_balance(ata(&chain_stats_pda(), &mint)).await, 600);
+
+  let stranger = env.wallet().await;
+  env.mint_to(&mint, &stranger.pubkey(), 0).await;
+  assert_error(
+    env
+      .send(&[withdraw_ix(stranger.pubkey(), 100)], &[&stranger])
+      .await,
+    ChainbillsError::OwnerUnauthorized,
+  );
+}
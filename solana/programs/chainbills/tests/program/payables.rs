@@ -0,0 +1,753 @@
+use crate::common::*;
+use anchor_lang::{
+  prelude::*, solana_program::instruction::Instruction, system_program,
+  InstructionData,
+};
+use chainbills::{error::ChainbillsError, events::*, state::*};
+use solana_sdk::{signature::Keypair, signer::Signer, sysvar};
+use wormhole_anchor_sdk::wormhole;
+
+fn taa(token: Pubkey, amount: u64) -> TokenAndAmount {
+  TokenAndAmount { token, amount }
+}
+
+fn pairs(taas: &[TokenAndAmount]) -> Vec<(Pubkey, u64)> {
+  taas.iter().map(|taa| (taa.token, taa.amount)).collect()
+}
+
+/// An instruction with the UpdatePayable accounts, signed by the signer
+/// either as the host or as an operator.
+pub(crate) async fn update_payable_ix(
+  env: &mut Env,
+  signer: &Pubkey,
+  payable: Pubkey,
+  as_operator: bool,
+  data: impl InstructionData,
+) -> Instruction {
+  let activities = env.activity_accounts(payable).await;
+  let wormhole = env.wormhole_accounts().await;
+  ix(
+    chainbills::accounts::UpdatePayable {
+      payable,
+      activity: activities.activity,
+      user_activity_info: activities.user_activity_info,
+      payable_activity_info: activities.payable_activity_info,
+      host: activities.host,
+      operator: as_operator.then(|| operator_pda(&payable, signer)),
+      chain_stats: chain_stats_pda(),
+      chain_shard: activities.chain_shard,
+      config: config_pda(),
+      wormhole_program: wormhole.wormhole_program,
+      wormhole_bridge: wormhole.wormhole_bridge,
+      wormhole_fee_collector: wormhole.wormhole_fee_collector,
+      wormhole_emitter: wormhole.wormhole_emitter,
+      wormhole_sequence: wormhole.wormhole_sequence,
+      wormhole_message: wormhole.wormhole_message,
+      signer: *signer,
+      clock: sysvar::clock::ID,
+      rent: sysvar::rent::ID,
+      system_program: system_program::ID,
+    },
+    data,
+  )
+}
+
+pub(crate) async fn close_payable_ix(
+  env: &mut Env,
+  signer: &Pubkey,
+  payable: Pubkey,
+  as_operator: bool,
+) -> Instruction {
+  let data = chainbills::instruction::ClosePayable {};
+  update_payable_ix(env, signer, payable, as_operator, data).await
+}
+
+async fn update_allowed_tokens_ix(
+  env: &mut Env,
+  signer: &Pubkey,
+  payable: Pubkey,
+  allowed_tokens_and_amounts: Vec<TokenAndAmount>,
+) -> Instruction {
+  let activities = env.activity_accounts(payable).await;
+  let wormhole = env.wormhole_accounts().await;
+  let mut instruction = ix(
+    chainbills::accounts::UpdatePayableAllowedTokensAndAmounts {
+      payable,
+      activity: activities.activity,
+      user_activity_info: activities.user_activity_info,
+      payable_activity_info: activities.payable_activity_info,
+      host: activities.host,
+      operator: None,
+      chain_stats: chain_stats_pda(),
+      chain_shard: activities.chain_shard,
+      config: config_pda(),
+      wormhole_program: wormhole.wormhole_program,
+      wormhole_bridge: wormhole.wormhole_bridge,
+      wormhole_fee_collector: wormhole.wormhole_fee_collector,
+      wormhole_emitter: wormhole.wormhole_emitter,
+      wormhole_sequence: wormhole.wormhole_sequence,
+      wormhole_message: wormhole.wormhole_message,
+      signer: *signer,
+      clock: sysvar::clock::ID,
+      rent: sysvar::rent::ID,
+      system_program: system_program::ID,
+      event_authority: event_authority(),
+      program: chainbills::ID,
+    },
+    chainbills::instruction::UpdatePayableAllowedTokensAndAmounts {
+      allowed_tokens_and_amounts: allowed_tokens_and_amounts.clone(),
+    },
+  );
+  for taa in &allowed_tokens_and_amounts {
+    let account = env.allowed_token_account(&taa.token).await;
+    instruction
+      .accounts
+      .push(AccountMeta::new_readonly(account, false));
+  }
+  instruction
+}
+
+async fn update_allowed_payers_ix(
+  env: &mut Env,
+  signer: &Pubkey,
+  payable: Pubkey,
+  allowed_payers: Vec<[u8; 32]>,
+  allowed_payers_root: [u8; 32],
+) -> Instruction {
+  let activities = env.activity_accounts(payable).await;
+  let wormhole = env.wormhole_accounts().await;
+  ix(
+    chainbills::accounts::UpdatePayableAllowedPayers {
+      payable,
+      activity: activities.activity,
+      user_activity_info: activities.user_activity_info,
+      payable_activity_info: activities.payable_activity_info,
+      allowed_payers: allowed_payers_pda(&payable),
+      host: activities.host,
+      operator: None,
+      chain_stats: chain_stats_pda(),
+      chain_shard: activities.chain_shard,
+      config: config_pda(),
+      wormhole_program: wormhole.wormhole_program,
+      wormhole_bridge: wormhole.wormhole_bridge,
+      wormhole_fee_collector: wormhole.wormhole_fee_collector,
+      wormhole_emitter: wormhole.wormhole_emitter,
+      wormhole_sequence: wormhole.wormhole_sequence,
+      wormhole_message: wormhole.wormhole_message,
+      signer: *signer,
+      clock: sysvar::clock::ID,
+      rent: sysvar::rent::ID,
+      system_program: system_program::ID,
+    },
+    chainbills::instruction::UpdatePayableAllowedPayers {
+      allowed_payers,
+      allowed_payers_root,
+    },
+  )
+}
+
+fn set_operator_ix(
+  host: &Pubkey,
+  payable: Pubkey,
+  operator: Pubkey,
+  roles: u8,
+) -> Instruction {
+  ix(
+    chainbills::accounts::SetPayableOperator {
+      payable,
+      payable_operator: operator_pda(&payable, &operator),
+      signer: *host,
+      system_program: system_program::ID,
+    },
+    chainbills::instruction::SetPayableOperator { operator, roles },
+  )
+}
+
+fn remove_operator_ix(
+  host: &Pubkey,
+  payable: Pubkey,
+  operator: Pubkey,
+) -> Instruction {
+  ix(
+    chainbills::accounts::RemovePayableOperator {
+      payable,
+      payable_operator: operator_pda(&payable, &operator),
+      signer: *host,
+    },
+    chainbills::instruction::RemovePayableOperator {},
+  )
+}
+
+fn pending_payable_host_pda(payable: &Pubkey) -> Pubkey {
+  pda(&[payable.as_ref(), PendingPayableHost::SEED_PREFIX])
+}
+
+fn transfer_payable_ix(
+  host: &Pubkey,
+  payable: Pubkey,
+  new_host: Pubkey,
+) -> Instruction {
+  ix(
+    chainbills::accounts::TransferPayable {
+      payable,
+      pending_payable_host: pending_payable_host_pda(&payable),
+      signer: *host,
+      system_program: system_program::ID,
+    },
+    chainbills::instruction::TransferPayable { new_host },
+  )
+}
+
+async fn accept_payable_ix(
+  env: &mut Env,
+  signer: &Pubkey,
+  payable: Pubkey,
+) -> Instruction {
+  let chain_shard = env.chain_shard().await;
+  let payable_data = env.payable(payable).await;
+  let previous_host = env.user(&payable_data.host).await;
+  let new_host = env.user(signer).await;
+  ix(
+    chainbills::accounts::AcceptPayable {
+      payable,
+      pending_payable_host: pending_payable_host_pda(&payable),
+      user_payable_info: pda(&[
+        signer.as_ref(),
+        UserPayableInfo::SEED_PREFIX,
+        &new_host.next_payable().to_le_bytes(),
+      ]),
+      previous_host_activity: activity_pda(chain_shard.next_activity()),
+      previous_host_activity_info: activity_info_pda(
+        &payable_data.host,
+        previous_host.next_activity(),
+      ),
+      previous_host_payable_activity_info: activity_info_pda(
+        &payable,
+        payable_data.next_activity(),
+      ),
+      new_host_activity: activity_pda(chain_shard.next_activity() + 1),
+      new_host_activity_info: activity_info_pda(
+        signer,
+        new_host.next_activity(),
+      ),
+      new_host_payable_activity_info: activity_info_pda(
+        &payable,
+        payable_data.next_activity() + 1,
+      ),
+      previous_host: user_pda(&payable_data.host),
+      previous_host_wallet: payable_data.host,
+      new_host: user_pda(signer),
+      chain_shard: chain_shard_pda(env.shard),
+      signer: *signer,
+      system_program: system_program::ID,
+    },
+    chainbills::instruction::AcceptPayable {},
+  )
+}
+
+#[tokio::test]
+async fn create_payable() {
+  let mut env = Env::new().await;
+  let mint = env.supported_mint(6).await;
+  let host = env.new_user().await;
+  let sequence = env.wormhole_accounts().await.wormhole_sequence;
+  let before: wormhole::SequenceTracker = env.account(sequence).await;
+
+  let (ix, payable) = env
+    .create_payable_ix(&host.pubkey(), vec![taa(mint, 1_000)])
+    .await;
+  assert_eq!(payable, payable_pda(&host.pubkey(), 1));
+  let outcome = env.send(&[ix], &[&host]).await.unwrap();
+  let event = outcome.event::<CreatedPayable>();
+  assert_eq!(event.payable_id, payable);
+  assert_eq!(event.host_wallet, host.pubkey());
+  assert_eq!(event.chain_count, sharded(1));
+  assert_eq!(event.host_count, 1);
+
+  let payable_data = env.payable(payable).await;
+  assert_eq!(payable_data.version, Payable::VERSION);
+  assert_eq!(payable_data.chain_count, sharded(1));
+  assert_eq!(payable_data.host, host.pubkey());
+  assert_eq!(payable_data.host_count, 1);
+  assert_eq!(payable_data.created_at, env.now().await);
+  assert_eq!(payable_data.payments_count, 0);
+  assert_eq!(payable_data.activities_count, 1);
+  assert!(!payable_data.is_closed);
+  assert_eq!(
+    pairs(&payable_data.allowed_tokens_and_amounts),
+    vec![(mint, 1_000)]
+  );
+  assert!(payable_data.balances.is_empty());
+
+  let chain_payable_id: ChainPayableId = env
+    .account(pda(&[ChainPayableId::SEED_PREFIX, &sharded(1).to_le_bytes()]))
+    .await;
+  assert_eq!(chain_payable_id.payable_id, payable);
+  let chain_shard = env.chain_shard().await;
+  assert_eq!(chain_shard.payables_count, sharded(1));
+  assert_eq!(env.user(&host.pubkey()).await.payables_count, 1);
+  let activity: ActivityRecord = env
+    .account(activity_pda(chain_shard.activities_count))
+    .await;
+  assert_eq!(activity.entity, payable);
+  assert!(matches!(
+    activity.activity_type,
+    ActivityType::CreatedPayable
+  ));
+
+  // The payable was published through Wormhole.
+  let after: wormhole::SequenceTracker = env.account(sequence).await;
+  assert_eq!(after.next_value(), before.next_value() + 1);
+
+  // Payables without allowed tokens accept any token.
+  let payable = env.create_payable(&host, vec![]).await;
+  assert_eq!(payable, payable_pda(&host.pubkey(), 2));
+  assert!(env
+    .payable(payable)
+    .await
+    .allowed_tokens_and_amounts
+    .is_empty());
+}
+
+#[tokio::test]
+async fn create_payable_checks_allowed_tokens() {
+  let mut env = Env::new().await;
+  let mint = env.supported_mint(6).await;
+  let other_mint = env.supported_mint(6).await;
+  let host = env.new_user().await;
+
+  // Each allowed token needs its account.
+  let (mut ix, _) = env
+    .create_payable_ix(&host.pubkey(), vec![taa(mint, 1_000)])
+    .await;
+  ix.accounts.pop();
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::InvalidRemainingAccountsLength,
+  );
+
+  let too_many =
+    vec![taa(mint, 1); Payable::MAX_ALLOWED_TOKENS_AND_AMOUNTS + 1];
+  let (ix, _) = env.create_payable_ix(&host.pubkey(), too_many).await;
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::TooManyAllowedTokensAndAmounts,
+  );
+
+  let (ix, _) = env
+    .create_payable_ix(&host.pubkey(), vec![taa(mint, 0)])
+    .await;
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::ZeroAmountSpecified,
+  );
+
+  // The account must be the token's TokenDetails.
+  let (mut ix, _) = env
+    .create_payable_ix(&host.pubkey(), vec![taa(mint, 1_000)])
+    .await;
+  let last = ix.accounts.len() - 1;
+  ix.accounts[last].pubkey = token_details_pda(&other_mint);
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::InvalidTokenDetailsAccount,
+  );
+  let (mut ix, _) = env
+    .create_payable_ix(&host.pubkey(), vec![taa(mint, 1_000)])
+    .await;
+  ix.accounts[last].pubkey = config_pda();
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::NonTokenDetailsAccountProvided,
+  );
+
+  // Unsupported tokens can't be allowed.
+  let unsupported = Pubkey::new_unique();
+  env.set_account(
+    token_details_pda(&unsupported),
+    &TokenDetails {
+      version: TokenDetails::VERSION,
+      mint: unsupported,
+      is_supported: false,
+      max_withdrawal_fees: 0,
+      total_user_paid: 0,
+      total_payable_received: 0,
+      total_withdrawn: 0,
+      total_withdrawal_fees_collected: 0,
+    },
+  );
+  let (ix, _) = env
+    .create_payable_ix(&host.pubkey(), vec![taa(unsupported, 1_000)])
+    .await;
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::UnsupportedToken,
+  );
+
+  // Token groups must have members.
+  let group = token_group_pda("empty");
+  env.set_account(
+    group,
+    &TokenGroup {
+      name: "empty".to_string(),
+      decimals: 6,
+      members: vec![],
+    },
+  );
+  let (ix, _) = env
+    .create_payable_ix(&host.pubkey(), vec![taa(group, 1_000)])
+    .await;
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::InvalidTokenGroup,
+  );
+
+  // Only initialized users can create payables.
+  let stranger = env.wallet().await;
+  let (ix, _) = env
+    .create_payable_ix(&host.pubkey(), vec![taa(mint, 1_000)])
+    .await;
+  let mut ix = ix;
+  swap_account(&mut ix, host.pubkey(), stranger.pubkey());
+  swap_account(
+    &mut ix,
+    user_pda(&host.pubkey()),
+    user_pda(&stranger.pubkey()),
+  );
+  assert!(env.send(&[ix], &[&stranger]).await.is_err());
+}
+
+#[tokio::test]
+async fn close_and_reopen_payable() {
+  let mut env = Env::new().await;
+  let host = env.new_user().await;
+  let payable = env.create_payable(&host, vec![]).await;
+
+  let ix = close_payable_ix(&mut env, &host.pubkey(), payable, false).await;
+  let outcome = env.send(&[ix], &[&host]).await.unwrap();
+  let event = outcome.event::<ClosedPayable>();
+  assert_eq!(event.payable_id, payable);
+  assert_eq!(event.host_wallet, host.pubkey());
+  let payable_data = env.payable(payable).await;
+  assert!(payable_data.is_closed);
+  assert_eq!(payable_data.activities_count, 2);
+
+  let ix = close_payable_ix(&mut env, &host.pubkey(), payable, false).await;
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::PayableIsAlreadyClosed,
+  );
+
+  let data = chainbills::instruction::ReopenPayable {};
+  let ix =
+    update_payable_ix(&mut env, &host.pubkey(), payable, false, data).await;
+  let outcome = env.send(&[ix], &[&host]).await.unwrap();
+  assert_eq!(outcome.event::<ReopenedPayable>().payable_id, payable);
+  assert!(!env.payable(payable).await.is_closed);
+
+  let data = chainbills::instruction::ReopenPayable {};
+  let ix =
+    update_payable_ix(&mut env, &host.pubkey(), payable, false, data).await;
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::PayableIsNotClosed,
+  );
+
+  // Only the host can close the payable.
+  let stranger = env.new_user().await;
+  let ix = close_payable_ix(&mut env, &stranger.pubkey(), payable, false).await;
+  assert_error(
+    env.send(&[ix], &[&stranger]).await,
+    ChainbillsError::NotYourPayable,
+  );
+}
+
+#[tokio::test]
+async fn payable_operators() {
+  let mut env = Env::new().await;
+  let host = env.new_user().await;
+  let operator = env.wallet().await;
+  let payable = env.create_payable(&host, vec![]).await;
+
+  for roles in [0, 1 << 2] {
+    let ix = set_operator_ix(&host.pubkey(), payable, operator.pubkey(), roles);
+    assert_error(
+      env.send(&[ix], &[&host]).await,
+      ChainbillsError::InvalidOperatorRoles,
+    );
+  }
+  let stranger = env.wallet().await;
+  let ix = set_operator_ix(
+    &stranger.pubkey(),
+    payable,
+    operator.pubkey(),
+    PayableOperator::ROLE_MANAGE,
+  );
+  assert_error(
+    env.send(&[ix], &[&stranger]).await,
+    ChainbillsError::NotYourPayable,
+  );
+
+  // Operators without the manage role can't close the payable.
+  let ix = set_operator_ix(
+    &host.pubkey(),
+    payable,
+    operator.pubkey(),
+    PayableOperator::ROLE_WITHDRAW,
+  );
+  let outcome = env.send(&[ix], &[&host]).await.unwrap();
+  let event = outcome.event::<UpdatedPayableOperator>();
+  assert_eq!(event.payable_id, payable);
+  assert_eq!(event.operator, operator.pubkey());
+  assert_eq!(event.roles, PayableOperator::ROLE_WITHDRAW);
+  let ix = close_payable_ix(&mut env, &operator.pubkey(), payable, true).await;
+  assert_error(
+    env.send(&[ix], &[&operator]).await,
+    ChainbillsError::NotYourPayable,
+  );
+
+  // Setting the operator again changes their roles.
+  let ix = set_operator_ix(
+    &host.pubkey(),
+    payable,
+    operator.pubkey(),
+    PayableOperator::ROLE_MANAGE,
+  );
+  env.send(&[ix], &[&host]).await.unwrap();
+  let stored: PayableOperator = env
+    .account(operator_pda(&payable, &operator.pubkey()))
+    .await;
+  assert_eq!(stored.payable_id, payable);
+  assert_eq!(stored.host, host.pubkey());
+  assert_eq!(stored.roles, PayableOperator::ROLE_MANAGE);
+  let ix = close_payable_ix(&mut env, &operator.pubkey(), payable, true).await;
+  env.send(&[ix], &[&operator]).await.unwrap();
+  assert!(env.payable(payable).await.is_closed);
+
+  // Removed operators lose their roles.
+  let ix = remove_operator_ix(&host.pubkey(), payable, operator.pubkey());
+  let outcome = env.send(&[ix], &[&host]).await.unwrap();
+  assert_eq!(outcome.event::<UpdatedPayableOperator>().roles, 0);
+  assert!(!env.exists(operator_pda(&payable, &operator.pubkey())).await);
+  let data = chainbills::instruction::ReopenPayable {};
+  let ix =
+    update_payable_ix(&mut env, &operator.pubkey(), payable, false, data).await;
+  assert_error(
+    env.send(&[ix], &[&operator]).await,
+    ChainbillsError::NotYourPayable,
+  );
+}
+
+#[tokio::test]
+async fn update_payable_allowed_tokens_and_amounts() {
+  let mut env = Env::new().await;
+  let mint = env.supported_mint(6).await;
+  let other_mint = env.supported_mint(9).await;
+  let host = env.new_user().await;
+  let payable = env.create_payable(&host, vec![taa(mint, 1_000)]).await;
+
+  let ix = update_allowed_tokens_ix(
+    &mut env,
+    &host.pubkey(),
+    payable,
+    vec![taa(mint, 2_000), taa(other_mint, 3_000)],
+  )
+  .await;
+  let outcome = env.send(&[ix], &[&host]).await.unwrap();
+  let event = outcome.event::<UpdatedPayableAllowedTokensAndAmounts>();
+  assert_eq!(event.payable_id, payable);
+  assert_eq!(
+    pairs(&event.allowed_tokens_and_amounts),
+    vec![(mint, 2_000), (other_mint, 3_000)]
+  );
+  assert_eq!(
+    pairs(&env.payable(payable).await.allowed_tokens_and_amounts),
+    vec![(mint, 2_000), (other_mint, 3_000)]
+  );
+
+  // Updates can keep the payable's size. Shrinking it refunds rent to the
+  // signer before the Wormhole CPI, which the native test runtime can't
+  // balance as it only syncs the CPI's accounts.
+  let ix = update_allowed_tokens_ix(
+    &mut env,
+    &host.pubkey(),
+    payable,
+    vec![taa(other_mint, 4_000), taa(mint, 5_000)],
+  )
+  .await;
+  env.send(&[ix], &[&host]).await.unwrap();
+  assert_eq!(
+    pairs(&env.payable(payable).await.allowed_tokens_and_amounts),
+    vec![(other_mint, 4_000), (mint, 5_000)]
+  );
+
+  let mut ix = update_allowed_tokens_ix(
+    &mut env,
+    &host.pubkey(),
+    payable,
+    vec![taa(mint, 2_000)],
+  )
+  .await;
+  ix.accounts.pop();
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::InvalidRemainingAccountsLength,
+  );
+
+  let stranger = env.new_user().await;
+  let ix =
+    update_allowed_tokens_ix(&mut env, &stranger.pubkey(), payable, vec![])
+      .await;
+  assert_error(
+    env.send(&[ix], &[&stranger]).await,
+    ChainbillsError::NotYourPayable,
+  );
+}
+
+#[tokio::test]
+async fn update_payable_allowed_payers() {
+  let mut env = Env::new().await;
+  let host = env.new_user().await;
+  let payable = env.create_payable(&host, vec![]).await;
+  let payers = vec![[1; 32], [2; 32], [3; 32]];
+  let root = chainbills_payload::allowed_payers_root(
+    &payers,
+    &PayableAllowedPayers::keccak,
+  );
+
+  let ix = update_allowed_payers_ix(
+    &mut env,
+    &host.pubkey(),
+    payable,
+    payers.clone(),
+    chainbills_payload::OPEN_TO_ALL_PAYERS,
+  )
+  .await;
+  let outcome = env.send(&[ix], &[&host]).await.unwrap();
+  let event = outcome.event::<UpdatedPayableAllowedPayers>();
+  assert_eq!(event.payable_id, payable);
+  assert_eq!(event.allowed_payers_root, root);
+  assert_eq!(event.allowed_payers, payers);
+  let stored: PayableAllowedPayers =
+    env.account(allowed_payers_pda(&payable)).await;
+  assert_eq!(stored.root, root);
+
+  // A root can be given instead of the list, but not alongside it.
+  let ix = update_allowed_payers_ix(
+    &mut env,
+    &host.pubkey(),
+    payable,
+    vec![],
+    [9; 32],
+  )
+  .await;
+  env.send(&[ix], &[&host]).await.unwrap();
+  let stored: PayableAllowedPayers =
+    env.account(allowed_payers_pda(&payable)).await;
+  assert_eq!(stored.root, [9; 32]);
+  let ix = update_allowed_payers_ix(
+    &mut env,
+    &host.pubkey(),
+    payable,
+    payers,
+    [9; 32],
+  )
+  .await;
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::InvalidAllowedPayers,
+  );
+
+  let stranger = env.new_user().await;
+  let ix = update_allowed_payers_ix(
+    &mut env,
+    &stranger.pubkey(),
+    payable,
+    vec![],
+    chainbills_payload::OPEN_TO_ALL_PAYERS,
+  )
+  .await;
+  assert_error(
+    env.send(&[ix], &[&stranger]).await,
+    ChainbillsError::NotYourPayable,
+  );
+}
+
+#[tokio::test]
+async fn transfer_and_accept_payable() {
+  let mut env = Env::new().await;
+  let host = env.new_user().await;
+  let new_host = env.new_user().await;
+  let stranger = env.new_user().await;
+  let payable = env.create_payable(&host, vec![]).await;
+  let operator = Keypair::new();
+  let ix = set_operator_ix(
+    &host.pubkey(),
+    payable,
+    operator.pubkey(),
+    PayableOperator::ROLE_MANAGE,
+  );
+  env.send(&[ix], &[&host]).await.unwrap();
+
+  let ix = transfer_payable_ix(&host.pubkey(), payable, host.pubkey());
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::CannotTransferPayableToSelf,
+  );
+  let ix = transfer_payable_ix(&stranger.pubkey(), payable, stranger.pubkey());
+  assert_error(
+    env.send(&[ix], &[&stranger]).await,
+    ChainbillsError::NotYourPayable,
+  );
+
+  let ix = transfer_payable_ix(&host.pubkey(), payable, new_host.pubkey());
+  let outcome = env.send(&[ix], &[&host]).await.unwrap();
+  let event = outcome.event::<InitiatedPayableTransfer>();
+  assert_eq!(event.payable_id, payable);
+  assert_eq!(event.host_wallet, host.pubkey());
+  assert_eq!(event.new_host, new_host.pubkey());
+
+  // Only the offered wallet can accept.
+  let ix = accept_payable_ix(&mut env, &stranger.pubkey(), payable).await;
+  assert_error(
+    env.send(&[ix], &[&stranger]).await,
+    ChainbillsError::NotPendingPayableHost,
+  );
+
+  let ix = accept_payable_ix(&mut env, &new_host.pubkey(), payable).await;
+  let outcome = env.send(&[ix], &[&new_host]).await.unwrap();
+  let event = outcome.event::<TransferredPayable>();
+  assert_eq!(event.payable_id, payable);
+  assert_eq!(event.previous_host, host.pubkey());
+  assert_eq!(event.new_host, new_host.pubkey());
+  assert_eq!(event.new_host_count, 1);
+
+  let payable_data = env.payable(payable).await;
+  assert_eq!(payable_data.host, new_host.pubkey());
+  assert_eq!(payable_data.host_count, 1);
+  assert_eq!(env.user(&new_host.pubkey()).await.payables_count, 1);
+  let info: UserPayableInfo = env
+    .account(pda(&[
+      new_host.pubkey().as_ref(),
+      UserPayableInfo::SEED_PREFIX,
+      &1u64.to_le_bytes(),
+    ]))
+    .await;
+  assert_eq!(info.payable_id, payable);
+  assert!(!env.exists(pending_payable_host_pda(&payable)).await);
+
+  // The previous host and their operators lose control of the payable.
+  let ix = close_payable_ix(&mut env, &host.pubkey(), payable, false).await;
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::NotYourPayable,
+  );
+  env.fund(&operator.pubkey(), WALLET_LAMPORTS).await;
+  let ix = close_payable_ix(&mut env, &operator.pubkey(), payable, true).await;
+  assert_error(
+    env.send(&[ix], &[&operator]).await,
+    ChainbillsError::NotYourPayable,
+  );
+  let ix = close_payable_ix(&mut env, &new_host.pubkey(), payable, false).await;
+  env.send(&[ix], &[&new_host]).await.unwrap();
+}
@@ -0,0 +1,967 @@
+use crate::common::*;
+use anchor_lang::{
+  error::ErrorCode, prelude::*, solana_program::instruction::Instruction,
+  system_program, InstructionData,
+};
+use anchor_spl::{
+  associated_token::spl_associated_token_account, token::spl_token,
+  token_2022::spl_token_2022,
+};
+use chainbills::{
+  context::BatchPayment, error::ChainbillsError, events::*, state::*,
+};
+use chainbills_payload::PaymentIntent;
+use solana_sdk::{ed25519_program, signature::Keypair, signer::Signer, sysvar};
+
+fn taa(token: Pubkey, amount: u64) -> TokenAndAmount {
+  TokenAndAmount { token, amount }
+}
+
+fn pairs(taas: &[TokenAndAmount]) -> Vec<(Pubkey, u64)> {
+  taas.iter().map(|taa| (taa.token, taa.amount)).collect()
+}
+
+/// Sets the Merkle root of the payers allowed to pay the payable.
+fn restrict_payers(env: &mut Env, payable: Pubkey, payers: &[Pubkey]) {
+  let payers: Vec<[u8; 32]> = payers.iter().map(|p| p.to_bytes()).collect();
+  let root = chainbills_payload::allowed_payers_root(
+    &payers,
+    &PayableAllowedPayers::keccak,
+  );
+  env.set_account(allowed_payers_pda(&payable), &PayableAllowedPayers { root });
+}
+
+#[tokio::test]
+async fn pay() {
+  let mut env = Env::new().await;
+  let mint = env.supported_mint(6).await;
+  let host = env.new_user().await;
+  let payer = env.new_user().await;
+  env.mint_to(&mint, &payer.pubkey(), 10_000).await;
+  let payable = env.create_payable(&host, vec![taa(mint, 1_000)]).await;
+  let accounts = env.payment_accounts(&payer.pubkey(), payable).await;
+
+  let outcome = env.pay(&payer, payable, &mint, 1_000).await.unwrap();
+  let now = env.now().await;
+  let user_paid = outcome.event::<UserPaid>();
+  assert_eq!(user_paid.payable_id, payable.to_bytes());
+  assert_eq!(user_paid.payer_wallet, payer.pubkey());
+  assert_eq!(user_paid.payment_id, accounts.user_payment);
+  assert_eq!(user_paid.payable_chain_id, solana_cb_chain_id());
+  assert_eq!(user_paid.payer_count, 1);
+  assert_eq!(user_paid.token, mint);
+  assert_eq!(user_paid.amount, 1_000);
+  assert_eq!(user_paid.timestamp, now);
+  let received = outcome.event::<PayableReceived>();
+  assert_eq!(received.payable_id, payable);
+  assert_eq!(received.payer_wallet, payer.pubkey().to_bytes());
+  assert_eq!(received.payment_id, accounts.payable_payment);
+  assert_eq!(received.payer_chain_id, solana_cb_chain_id());
+  assert_eq!(received.payable_count, 1);
+  assert_eq!(received.amount, 1_000);
+
+  let user_payment: UserPayment = env.account(accounts.user_payment).await;
+  assert_eq!(user_payment.payable_id, payable.to_bytes());
+  assert_eq!(user_payment.payer, payer.pubkey());
+  assert_eq!(user_payment.payer_count, 1);
+  assert_eq!(user_payment.chain_count, sharded(1));
+  assert_eq!(user_payment.timestamp, now);
+  assert_eq!(pairs(&[user_payment.details]), vec![(mint, 1_000)]);
+  let payable_payment: PayablePayment =
+    env.account(accounts.payable_payment).await;
+  assert_eq!(payable_payment.payable_id, payable);
+  assert_eq!(payable_payment.payer, payer.pubkey().to_bytes());
+  assert_eq!(payable_payment.payable_count, 1);
+  assert_eq!(payable_payment.chain_count, sharded(1));
+  assert_eq!(payable_payment.local_chain_count, 1);
+  assert_eq!(pairs(&[payable_payment.details]), vec![(mint, 1_000)]);
+  let info: PayablePerChainPaymentInfo = env
+    .account(accounts.payable_per_chain_payment_info.unwrap())
+    .await;
+  assert_eq!(info.payable_count, 1);
+
+  let payable_data = env.payable(payable).await;
+  assert_eq!(payable_data.payments_count, 1);
+  assert_eq!(pairs(&payable_data.balances), vec![(mint, 1_000)]);
+  assert_eq!(env.user(&payer.pubkey()).await.payments_count, 1);
+  let chain_shard = env.chain_shard().await;
+  assert_eq!(chain_shard.user_payments_count, sharded(1));
+  assert_eq!(chain_shard.payable_payments_count, sharded(1));
+  // The token's totals add up in the shard's instead of in its details.
+  let token_details = env.token_details(&mint).await;
+  assert_eq!(token_details.total_user_paid, 0);
+  assert_eq!(token_details.total_payable_received, 0);
+  let token_details_shard = env.token_details_shard(&mint).await;
+  assert_eq!(token_details_shard.mint, mint);
+  assert_eq!(token_details_shard.index, env.shard);
+  assert_eq!(token_details_shard.total_user_paid, 1_000);
+  assert_eq!(token_details_shard.total_payable_received, 1_000);
+  // The activities are derived from the payer's and payable's counts.
+  assert_eq!(
+    accounts.user_activity,
+    payment_activity_pda(&payer.pubkey(), 2)
+  );
+  assert_eq!(accounts.payable_activity, payment_activity_pda(&payable, 2));
+  let activity: ActivityRecord = env.account(accounts.payable_activity).await;
+  assert_eq!(activity.payable_count, 2);
+  assert!(matches!(
+    activity.activity_type,
+    ActivityType::PayableReceived
+  ));
+  assert_eq!(env.token_balance(ata(&payer.pubkey(), &mint)).await, 9_000);
+  assert_eq!(
+    env.token_balance(ata(&chain_stats_pda(), &mint)).await,
+    1_000
+  );
+
+  // Later payments add up in the payable's balance.
+  env.pay(&payer, payable, &mint, 1_000).await.unwrap();
+  let payable_data = env.payable(payable).await;
+  assert_eq!(payable_data.payments_count, 2);
+  assert_eq!(pairs(&payable_data.balances), vec![(mint, 2_000)]);
+}
+
+#[tokio::test]
+async fn pay_checks_payments() {
+  let mut env = Env::new().await;
+  let mint = env.supported_mint(6).await;
+  let other_mint = env.supported_mint(6).await;
+  let host = env.new_user().await;
+  let payer = env.new_user().await;
+  env.mint_to(&mint, &payer.pubkey(), 10_000).await;
+  env.mint_to(&other_mint, &payer.pubkey(), 10_000).await;
+  let payable = env.create_payable(&host, vec![taa(mint, 1_000)]).await;
+
+  // Payments must match an allowed token and amount.
+  assert_error(
+    env.pay(&payer, payable, &mint, 999).await,
+    ChainbillsError::MatchingTokenAndAmountNotFound,
+  );
+  assert_error(
+    env.pay(&payer, payable, &other_mint, 1_000).await,
+    ChainbillsError::MatchingTokenAndAmountNotFound,
+  );
+
+  // Payables without allowed tokens take any positive amount.
+  let open = env.create_payable(&host, vec![]).await;
+  assert_error(
+    env.pay(&payer, open, &mint, 0).await,
+    ChainbillsError::ZeroAmountSpecified,
+  );
+  env.pay(&payer, open, &other_mint, 7).await.unwrap();
+
+  // Tokens without TokenDetails aren't supported.
+  let unsupported = env.create_mint(6).await;
+  env.mint_to(&unsupported, &payer.pubkey(), 10_000).await;
+  env.mint_to(&unsupported, &chain_stats_pda(), 0).await;
+  assert_anchor_error(
+    env.pay(&payer, open, &unsupported, 1_000).await,
+    ErrorCode::AccountNotInitialized,
+  );
+
+  // Closed payables don't take payments.
+  let mut closed = env.payable(open).await;
+  closed.is_closed = true;
+  env.set_account(open, &closed);
+  assert_error(
+    env.pay(&payer, open, &mint, 1_000).await,
+    ChainbillsError::PayableIsClosed,
+  );
+
+  // Payers can't pay more than they hold.
+  assert!(env.pay(&payer, payable, &mint, 1_000).await.is_ok());
+  let poor = env.new_user().await;
+  env.mint_to(&mint, &poor.pubkey(), 999).await;
+  assert!(env.pay(&poor, payable, &mint, 1_000).await.is_err());
+}
+
+#[tokio::test]
+async fn pay_respects_allowed_payers() {
+  let mut env = Env::new().await;
+  let mint = env.supported_mint(6).await;
+  let host = env.new_user().await;
+  let allowed = env.new_user().await;
+  let stranger = env.new_user().await;
+  env.mint_to(&mint, &allowed.pubkey(), 10_000).await;
+  env.mint_to(&mint, &stranger.pubkey(), 10_000).await;
+  let payable = env.create_payable(&host, vec![]).await;
+  restrict_payers(&mut env, payable, &[allowed.pubkey()]);
+
+  assert_error(
+    env.pay(&stranger, payable, &mint, 1_000).await,
+    ChainbillsError::PayerNotAllowed,
+  );
+  // The proof of the only allowed payer is empty.
+  env.pay(&allowed, payable, &mint, 1_000).await.unwrap();
+
+  // With more payers, each one proves their place in the tree.
+  let payers = [allowed.pubkey(), Pubkey::new_unique(), stranger.pubkey()];
+  restrict_payers(&mut env, payable, &payers);
+  let leaves: Vec<[u8; 32]> = payers.iter().map(|p| p.to_bytes()).collect();
+  let proof = chainbills_payload::allowed_payer_proof(
+    &leaves,
+    &stranger.pubkey().to_bytes(),
+    &PayableAllowedPayers::keccak,
+  )
+  .unwrap();
+  let accounts = env.pay_accounts(&stranger.pubkey(), payable, &mint).await;
+  let mut pay_ix = ix(
+    accounts,
+    chainbills::instruction::Pay {
+      amount: 1_000,
+      payer_proof: vec![],
+    },
+  );
+  assert_error(
+    env.send(&[pay_ix.clone()], &[&stranger]).await,
+    ChainbillsError::PayerNotAllowed,
+  );
+  pay_ix.data = chainbills::instruction::Pay {
+    amount: 1_000,
+    payer_proof: proof,
+  }
+  .data();
+  env.send(&[pay_ix], &[&stranger]).await.unwrap();
+  assert_eq!(env.payable(payable).await.payments_count, 2);
+}
+
+#[tokio::test]
+async fn pay_native() {
+  let mut env = Env::new().await;
+  env.support_native().await;
+  let host = env.new_user().await;
+  let payer = env.new_user().await;
+  let amount = 1_000_000_000;
+  let payable = env
+    .create_payable(&host, vec![taa(chainbills::ID, amount)])
+    .await;
+  let vault_before = env.lamports(native_vault_pda()).await;
+
+  let outcome = env.pay_native(&payer, payable, amount).await.unwrap();
+  assert_eq!(outcome.event::<UserPaid>().token, chainbills::ID);
+  assert_eq!(outcome.event::<PayableReceived>().amount, amount);
+  assert_eq!(
+    env.lamports(native_vault_pda()).await,
+    vault_before + amount
+  );
+  assert_eq!(
+    pairs(&env.payable(payable).await.balances),
+    vec![(chainbills::ID, amount)]
+  );
+  assert_eq!(
+    env
+      .token_details_shard(&chainbills::ID)
+      .await
+      .total_payable_received,
+    amount
+  );
+
+  assert_error(
+    env.pay_native(&payer, payable, amount - 1).await,
+    ChainbillsError::MatchingTokenAndAmountNotFound,
+  );
+  // Payers can't pay more than they hold.
+  assert!(env
+    .pay_native(&payer, payable, WALLET_LAMPORTS)
+    .await
+    .is_err());
+}
+
+#[tokio::test]
+async fn pay_indexes_in_any_chain_shard() {
+  let mut env = Env::new().await;
+  let mint = env.supported_mint(6).await;
+  let host = env.new_user().await;
+  let payer = env.new_user().await;
+  env.mint_to(&mint, &payer.pubkey(), 10_000).await;
+  let first = env.create_payable(&host, vec![]).await;
+  let second = env.create_payable(&host, vec![]).await;
+
+  // Each payment is indexed in the shard that it passes, independently of
+  // the other shards and of ChainStats.
+  env.pay(&payer, first, &mint, 1_000).await.unwrap();
+  env.shard = 5;
+  let accounts = env.payment_accounts(&payer.pubkey(), second).await;
+  env.pay(&payer, second, &mint, 1_000).await.unwrap();
+
+  let base = ChainStatsShard::base_of(5);
+  let shard = env.chain_shard().await;
+  assert_eq!(shard.index, 5);
+  assert_eq!(shard.user_payments_count, base + 1);
+  assert_eq!(shard.payable_payments_count, base + 1);
+  assert_eq!(shard.activities_count, base + 2);
+  let user_payment: UserPayment = env.account(accounts.user_payment).await;
+  assert_eq!(user_payment.chain_count, base + 1);
+  // The chain-level index only counts it, and the token's totals go in
+  // the token's shard of the same index.
+  assert!(
+    !env
+      .exists(pda(&[
+        ChainUserPaymentId::SEED_PREFIX,
+        &(base + 1).to_le_bytes()
+      ]))
+      .await
+  );
+  assert_eq!(env.token_details_shard(&mint).await.total_user_paid, 1_000);
+  env.shard = 0;
+  assert_eq!(env.token_details_shard(&mint).await.total_user_paid, 1_000);
+  assert_eq!(env.chain_shard().await.user_payments_count, sharded(1));
+  assert_eq!(env.chain_stats().await.user_payments_count, 0);
+
+  // Only initialized shards can be used.
+  let mut accounts = env.pay_accounts(&payer.pubkey(), first, &mint).await;
+  accounts.chain_shard = pda(&[ChainStatsShard::SEED_PREFIX, &[16]]);
+  let ix = ix(
+    accounts,
+    chainbills::instruction::Pay {
+      amount: 1_000,
+      payer_proof: vec![],
+    },
+  );
+  assert_anchor_error(
+    env.send(&[ix], &[&payer]).await,
+    ErrorCode::AccountNotInitialized,
+  );
+}
+
+#[tokio::test]
+async fn pay_records_index_entries_in_ledger_pages() {
+  let mut env = Env::new().await;
+  let mint = env.supported_mint(6).await;
+  let host = env.new_user().await;
+  let payer = env.new_user().await;
+  env.mint_to(&mint, &payer.pubkey(), 10_000).await;
+  let payable = env.create_payable(&host, vec![]).await;
+
+  // Paged payments create no per-chain infos of their own, and share pages.
+  env.paged = true;
+  let mut payments = vec![];
+  for _ in 0..2 {
+    let accounts = env.payment_accounts(&payer.pubkey(), payable).await;
+    env.pay(&payer, payable, &mint, 1_000).await.unwrap();
+    payments.push(accounts);
+  }
+  let accounts = &payments[0];
+  assert_eq!(
+    accounts.payable_per_chain_payments_page,
+    payments[1].payable_per_chain_payments_page
+  );
+  env.paged = false;
+  let own = env.payment_accounts(&payer.pubkey(), payable).await;
+  assert!(
+    !env
+      .exists(own.payable_per_chain_payment_info.unwrap())
+      .await
+  );
+  let page: CountLedgerPage = env
+    .account(accounts.payable_per_chain_payments_page.unwrap())
+    .await;
+  assert_eq!(page.entries, vec![1, 2]);
+
+  // No payment records chain-level ids or activity infos.
+  for count in 1..=2 {
+    let id = pda(&[
+      ChainUserPaymentId::SEED_PREFIX,
+      &sharded(count).to_le_bytes(),
+    ]);
+    assert!(!env.exists(id).await);
+    assert!(!env.exists(activity_info_pda(&payable, count + 1)).await);
+    assert!(
+      !env
+        .exists(activity_info_pda(&payer.pubkey(), count + 1))
+        .await
+    );
+  }
+
+  // The entry goes either in its own account or in its page, not both.
+  env.paged = true;
+  let mut accounts = env.pay_accounts(&payer.pubkey(), payable, &mint).await;
+  accounts.payable_per_chain_payment_info = own.payable_per_chain_payment_info;
+  let both = ix(
+    accounts,
+    chainbills::instruction::Pay {
+      amount: 1_000,
+      payer_proof: vec![],
+    },
+  );
+  let mut accounts = env.pay_accounts(&payer.pubkey(), payable, &mint).await;
+  accounts.payable_per_chain_payments_page = None;
+  let neither = ix(
+    accounts,
+    chainbills::instruction::Pay {
+      amount: 1_000,
+      payer_proof: vec![],
+    },
+  );
+  for ix in [both, neither] {
+    assert_error(
+      env.send(&[ix], &[&payer]).await,
+      ChainbillsError::InvalidLedgerAccounts,
+    );
+  }
+}
+
+#[tokio::test]
+async fn pay_in_token_group_members() {
+  let mut env = Env::new().await;
+  let six = env.supported_mint(6).await;
+  let nine = env.supported_mint(9).await;
+  let outsider = env.supported_mint(6).await;
+  let misrecorded = env.supported_mint(6).await;
+  let group = token_group_pda("usd");
+  env.set_account(
+    group,
+    &TokenGroup {
+      name: "usd".to_string(),
+      decimals: 6,
+      members: vec![
+        TokenGroupMember {
+          token: six,
+          decimals: 6,
+        },
+        TokenGroupMember {
+          token: nine,
+          decimals: 9,
+        },
+        TokenGroupMember {
+          token: misrecorded,
+          decimals: 8,
+        },
+      ],
+    },
+  );
+  let host = env.new_user().await;
+  let payer = env.new_user().await;
+  for mint in [six, nine, outsider, misrecorded] {
+    env.mint_to(&mint, &payer.pubkey(), 10_000_000_000).await;
+  }
+  let payable = env.create_payable(&host, vec![taa(group, 1_000_000)]).await;
+
+  let pay_ix = |accounts: chainbills::accounts::Pay, amount| {
+    ix(
+      chainbills::accounts::Pay {
+        token_group: Some(group),
+        ..accounts
+      },
+      chainbills::instruction::Pay {
+        amount,
+        payer_proof: vec![],
+      },
+    )
+  };
+
+  // Members pay the group's amount normalized to their decimals.
+  let accounts = env.pay_accounts(&payer.pubkey(), payable, &nine).await;
+  env
+    .send(&[pay_ix(accounts, 1_000_000_000)], &[&payer])
+    .await
+    .unwrap();
+  let accounts = env.pay_accounts(&payer.pubkey(), payable, &six).await;
+  env
+    .send(&[pay_ix(accounts, 1_000_000)], &[&payer])
+    .await
+    .unwrap();
+  assert_eq!(
+    pairs(&env.payable(payable).await.balances),
+    vec![(nine, 1_000_000_000), (six, 1_000_000)]
+  );
+
+  // Amounts that aren't normalized, non-members, and payments without the
+  // group don't match.
+  let accounts = env.pay_accounts(&payer.pubkey(), payable, &nine).await;
+  assert_error(
+    env.send(&[pay_ix(accounts, 1_000_000)], &[&payer]).await,
+    ChainbillsError::MatchingTokenAndAmountNotFound,
+  );
+  let accounts = env.pay_accounts(&payer.pubkey(), payable, &outsider).await;
+  assert_error(
+    env.send(&[pay_ix(accounts, 1_000_000)], &[&payer]).await,
+    ChainbillsError::MatchingTokenAndAmountNotFound,
+  );
+  assert_error(
+    env.pay(&payer, payable, &six, 1_000_000).await,
+    ChainbillsError::MatchingTokenAndAmountNotFound,
+  );
+
+  // Members whose recorded decimals aren't their mint's don't match either.
+  for amount in [100_000_000, 1_000_000] {
+    let accounts = env
+      .pay_accounts(&payer.pubkey(), payable, &misrecorded)
+      .await;
+    assert_error(
+      env.send(&[pay_ix(accounts, amount)], &[&payer]).await,
+      ChainbillsError::MatchingTokenAndAmountNotFound,
+    );
+  }
+}
+
+/// The remaining accounts of a batch of payments into distinct payables.
+pub(crate) async fn batch_accounts(
+  env: &mut Env,
+  payer: &Pubkey,
+  payables: &[Pubkey],
+) -> Vec<AccountMeta> {
+  let user = env.user(payer).await;
+  let mut metas = vec![];
+  for (i, payable) in payables.iter().enumerate() {
+    let i = i as u64;
+    let payable_data = env.payable(*payable).await;
+    let counter: PayablePerChainPaymentsCounter =
+      env.account(payments_counter_pda(payable)).await;
+    let payable_payment = pda(&[
+      payable.as_ref(),
+      PayablePayment::SEED_PREFIX,
+      &payable_data.next_payment().to_le_bytes(),
+    ]);
+    let settings = [
+      payments_counter_pda(payable),
+      allowed_payers_pda(payable),
+      pda(&[payable.as_ref(), PayableEscrow::SEED_PREFIX]),
+      receipts_pda(payable),
+    ];
+    // The per-chain entry goes in its own account, or else in its ledger
+    // page.
+    let per_chain_prefix: &[&[u8]] = &[payable.as_ref(), &1u16.to_le_bytes()];
+    let per_chain = if env.paged {
+      ledger_page_pda(per_chain_prefix, counter.next_payment())
+    } else {
+      pda(
+        &[
+          per_chain_prefix,
+          &[&counter.next_payment().to_le_bytes()[..]],
+        ]
+        .concat(),
+      )
+    };
+    let created = [
+      pda(&[
+        payer.as_ref(),
+        UserPayment::SEED_PREFIX,
+        &(user.next_payment() + i).to_le_bytes(),
+      ]),
+      payable_payment,
+      per_chain,
+      payment_activity_pda(payer, user.next_activity() + i),
+      payment_activity_pda(payable, payable_data.next_activity()),
+    ];
+    metas.push(AccountMeta::new(*payable, false));
+    metas.push(AccountMeta::new(settings[0], false));
+    metas.push(AccountMeta::new_readonly(settings[1], false));
+    metas.push(AccountMeta::new(settings[2], false));
+    metas.push(AccountMeta::new_readonly(settings[3], false));
+    metas.extend(created.iter().map(|a| AccountMeta::new(*a, false)));
+    if let Some(receipts) = env.get_account(settings[3]).await {
+      let receipts =
+        PayableReceipts::try_deserialize(&mut receipts.data.as_slice())
+          .unwrap();
+      if receipts.enabled {
+        let receipt_mint = receipt_mint_pda(&created[0]);
+        metas.push(AccountMeta::new(receipt_mint, false));
+        metas.push(AccountMeta::new(receipt_ata(payer, &receipt_mint), false));
+      }
+    }
+  }
+  metas
+}
+
+pub(crate) fn pay_batch_ix(
+  env: &Env,
+  payer: &Pubkey,
+  mint: &Pubkey,
+  amounts: &[u64],
+  remaining: Vec<AccountMeta>,
+) -> Instruction {
+  let mut instruction = ix(
+    chainbills::accounts::PayBatch {
+      payer: user_pda(payer),
+      chain_stats: chain_stats_pda(),
+      chain_shard: chain_shard_pda(env.shard),
+      config: config_pda(),
+      mint: *mint,
+      token_details: token_details_pda(mint),
+      token_details_shard: token_details_shard_pda(mint, env.shard),
+      payer_token_account: ata(payer, mint),
+      chain_token_account: ata(&chain_stats_pda(), mint),
+      signer: *payer,
+      token_program: spl_token::ID,
+      receipt_token_program: Some(spl_token_2022::ID),
+      associated_token_program: Some(spl_associated_token_account::ID),
+      system_program: system_program::ID,
+      event_authority: event_authority(),
+      program: chainbills::ID,
+    },
+    chainbills::instruction::PayBatch {
+      payments: amounts
+        .iter()
+        .map(|amount| BatchPayment {
+          amount: *amount,
+          payer_proof: vec![],
+          paged: env.paged,
+        })
+        .collect(),
+    },
+  );
+  instruction.accounts.extend(remaining);
+  instruction
+}
+
+#[tokio::test]
+async fn pay_batch() {
+  let mut env = Env::new().await;
+  let mint = env.supported_mint(6).await;
+  let host = env.new_user().await;
+  let payer = env.new_user().await;
+  env.mint_to(&mint, &payer.pubkey(), 10_000).await;
+  let first = env.create_payable(&host, vec![taa(mint, 1_000)]).await;
+  let second = env.create_payable(&host, vec![]).await;
+
+  let remaining =
+    batch_accounts(&mut env, &payer.pubkey(), &[first, second]).await;
+  let user_payments = [remaining[5].pubkey, remaining[15].pubkey];
+  let ix =
+    pay_batch_ix(&env, &payer.pubkey(), &mint, &[1_000, 2_500], remaining);
+  let outcome = env.send(&[ix], &[&payer]).await.unwrap();
+  let paid = outcome.events::<UserPaid>();
+  assert_eq!(paid.len(), 2);
+  assert_eq!(paid[0].payable_id, first.to_bytes());
+  assert_eq!(paid[1].payable_id, second.to_bytes());
+  assert_eq!(paid[1].payer_count, 2);
+  assert_eq!(outcome.events::<PayableReceived>().len(), 2);
+
+  assert_eq!(
+    pairs(&env.payable(first).await.balances),
+    vec![(mint, 1_000)]
+  );
+  assert_eq!(
+    pairs(&env.payable(second).await.balances),
+    vec![(mint, 2_500)]
+  );
+  for (i, user_payment) in user_payments.into_iter().enumerate() {
+    let user_payment: UserPayment = env.account(user_payment).await;
+    assert_eq!(user_payment.payer_count, i as u64 + 1);
+    assert_eq!(user_payment.chain_count, sharded(i as u64 + 1));
+  }
+  assert_eq!(env.user(&payer.pubkey()).await.payments_count, 2);
+  assert_eq!(env.chain_shard().await.user_payments_count, sharded(2));
+  assert_eq!(env.token_balance(ata(&payer.pubkey(), &mint)).await, 6_500);
+
+  // The batch fails as a whole if any payment is invalid.
+  let remaining =
+    batch_accounts(&mut env, &payer.pubkey(), &[second, first]).await;
+  let ix = pay_batch_ix(&env, &payer.pubkey(), &mint, &[1_000, 999], remaining);
+  assert_error(
+    env.send(&[ix], &[&payer]).await,
+    ChainbillsError::MatchingTokenAndAmountNotFound,
+  );
+  assert_eq!(env.payable(second).await.payments_count, 1);
+
+  let ix = pay_batch_ix(&env, &payer.pubkey(), &mint, &[], vec![]);
+  assert_error(
+    env.send(&[ix], &[&payer]).await,
+    ChainbillsError::EmptyPaymentBatch,
+  );
+
+  // Every payment needs all its accounts, and no account may be left over.
+  let mut remaining = batch_accounts(&mut env, &payer.pubkey(), &[first]).await;
+  remaining.pop();
+  let ix = pay_batch_ix(&env, &payer.pubkey(), &mint, &[1_000], remaining);
+  assert_error(
+    env.send(&[ix], &[&payer]).await,
+    ChainbillsError::InvalidBatchAccounts,
+  );
+  let mut remaining = batch_accounts(&mut env, &payer.pubkey(), &[first]).await;
+  remaining.push(AccountMeta::new_readonly(config_pda(), false));
+  let ix = pay_batch_ix(&env, &payer.pubkey(), &mint, &[1_000], remaining);
+  assert_error(
+    env.send(&[ix], &[&payer]).await,
+    ChainbillsError::InvalidBatchAccounts,
+  );
+  let mut remaining = batch_accounts(&mut env, &payer.pubkey(), &[first]).await;
+  remaining[1].pubkey = payments_counter_pda(&second);
+  let ix = pay_batch_ix(&env, &payer.pubkey(), &mint, &[1_000], remaining);
+  assert_error(
+    env.send(&[ix], &[&payer]).await,
+    ChainbillsError::InvalidBatchAccounts,
+  );
+}
+
+#[tokio::test]
+async fn pay_with_client_instructions() {
+  let mut env = Env::new().await;
+  let mint = env.supported_mint(6).await;
+  let host = env.new_user().await;
+  let payer = env.new_user().await;
+  env.mint_to(&mint, &payer.pubkey(), 10_000).await;
+  let first = env.create_payable(&host, vec![taa(mint, 1_000)]).await;
+  let second = env.create_payable(&host, vec![]).await;
+
+  let ix = env
+    .client()
+    .pay(&payer.pubkey(), &first, &mint, 1_000, vec![])
+    .unwrap();
+  env.send(&[ix], &[&payer]).await.unwrap();
+
+  let payment = |amount, paged| BatchPayment {
+    amount,
+    payer_proof: vec![],
+    paged,
+  };
+  let ix = env
+    .client()
+    .pay_batch(
+      &payer.pubkey(),
+      &mint,
+      vec![
+        (first, payment(1_000, false)),
+        (second, payment(2_500, true)),
+      ],
+    )
+    .unwrap();
+  let outcome = env.send(&[ix], &[&payer]).await.unwrap();
+  assert_eq!(outcome.events::<UserPaid>().len(), 2);
+
+  assert_eq!(
+    pairs(&env.payable(first).await.balances),
+    vec![(mint, 2_000)]
+  );
+  assert_eq!(
+    pairs(&env.payable(second).await.balances),
+    vec![(mint, 2_500)]
+  );
+  assert_eq!(env.user(&payer.pubkey()).await.payments_count, 3);
+  assert_eq!(env.token_balance(ata(&payer.pubkey(), &mint)).await, 5_500);
+}
+
+#[tokio::test]
+async fn pay_batch_in_ledger_pages() {
+  let mut env = Env::new().await;
+  let mint = env.supported_mint(6).await;
+  let host = env.new_user().await;
+  let payer = env.new_user().await;
+  env.mint_to(&mint, &payer.pubkey(), 10_000).await;
+  let first = env.create_payable(&host, vec![]).await;
+  let second = env.create_payable(&host, vec![]).await;
+
+  // Each payment records its per-chain entry in its payable's page, and
+  // derives its activities from the payer's and its payable's counts.
+  env.paged = true;
+  let remaining =
+    batch_accounts(&mut env, &payer.pubkey(), &[first, second]).await;
+  let pages = [remaining[7].pubkey, remaining[17].pubkey];
+  let activities = [remaining[8].pubkey, remaining[18].pubkey];
+  assert_eq!(activities[1], payment_activity_pda(&payer.pubkey(), 3));
+  let ix =
+    pay_batch_ix(&env, &payer.pubkey(), &mint, &[1_000, 2_500], remaining);
+  env.send(&[ix], &[&payer]).await.unwrap();
+
+  for page in pages {
+    let page: CountLedgerPage = env.account(page).await;
+    assert_eq!(page.get(1), Some(1));
+  }
+  for (i, activity) in activities.into_iter().enumerate() {
+    let activity: ActivityRecord = env.account(activity).await;
+    assert_eq!(activity.user_count, i as u64 + 2);
+  }
+  for payable in [first, second] {
+    assert!(!env.exists(activity_info_pda(&payable, 2)).await);
+    assert!(env.exists(payment_activity_pda(&payable, 2)).await);
+  }
+  assert!(
+    !env
+      .exists(pda(&[
+        ChainUserPaymentId::SEED_PREFIX,
+        &sharded(1).to_le_bytes(),
+      ]))
+      .await
+  );
+  let token_details_shard = env.token_details_shard(&mint).await;
+  assert_eq!(token_details_shard.total_user_paid, 3_500);
+  assert_eq!(env.user(&payer.pubkey()).await.payments_count, 2);
+}
+
+/// The Ed25519 program instruction that verifies the signer's signature of
+/// the message, with all its inputs in its own data.
+fn ed25519_ix(signer: &Keypair, message: &[u8]) -> Instruction {
+  const PUBKEY_OFFSET: u16 = 16;
+  const SIGNATURE_OFFSET: u16 = PUBKEY_OFFSET + 32;
+  const MESSAGE_OFFSET: u16 = SIGNATURE_OFFSET + 64;
+  let mut data = vec![1, 0];
+  for offset in [
+    SIGNATURE_OFFSET,
+    u16::MAX,
+    PUBKEY_OFFSET,
+    u16::MAX,
+    MESSAGE_OFFSET,
+    message.len() as u16,
+    u16::MAX,
+  ] {
+    data.extend_from_slice(&offset.to_le_bytes());
+  }
+  data.extend_from_slice(signer.pubkey().as_ref());
+  data.extend_from_slice(signer.sign_message(message).as_ref());
+  data.extend_from_slice(message);
+  Instruction {
+    program_id: ed25519_program::ID,
+    accounts: vec![],
+    data,
+  }
+}
+
+struct Intent {
+  payable: Pubkey,
+  mint: Pubkey,
+  amount: u64,
+  expiry: u64,
+  nonce: u64,
+  reference: String,
+}
+
+impl Intent {
+  fn signing_bytes(&self) -> Vec<u8> {
+    PaymentIntent {
+      payable_chain_id: solana_cb_chain_id(),
+      payable_id: self.payable.to_bytes(),
+      token: self.mint.to_bytes(),
+      amount: self.amount.into(),
+      expiry: self.expiry,
+      nonce: self.nonce,
+      reference: self.reference.as_bytes().to_vec(),
+    }
+    .signing_bytes()
+    .unwrap()
+  }
+
+  async fn pay_ix(&self, env: &mut Env, payer: &Pubkey) -> Instruction {
+    let p = env.payment_accounts(payer, self.payable).await;
+    ix(
+      chainbills::accounts::PayWithIntent {
+        consumed_payment_intent: pda(&[
+          self.payable.as_ref(),
+          ConsumedPaymentIntent::SEED_PREFIX,
+          &self.nonce.to_le_bytes(),
+        ]),
+        user_payment: p.user_payment,
+        payable_payment: p.payable_payment,
+        payable_per_chain_payment_info: p.payable_per_chain_payment_info,
+        payable_per_chain_payments_page: p.payable_per_chain_payments_page,
+        payable_per_chain_payments_counter: p
+          .payable_per_chain_payments_counter,
+        user_activity: p.user_activity,
+        payable_activity: p.payable_activity,
+        payable: p.payable,
+        allowed_payers: p.allowed_payers,
+        payable_escrow: p.payable_escrow,
+        escrow_state: p.escrow_state,
+        payable_receipts: p.payable_receipts,
+        receipt_mint: p.receipt_mint,
+        payer_receipt_token_account: p.payer_receipt_token_account,
+        receipt_token_program: p.receipt_programs().0,
+        associated_token_program: p.receipt_programs().1,
+        payer: p.payer,
+        chain_stats: chain_stats_pda(),
+        chain_shard: p.chain_shard,
+        config: config_pda(),
+        mint: self.mint,
+        token_details: token_details_pda(&self.mint),
+        token_details_shard: token_details_shard_pda(&self.mint, env.shard),
+        payer_token_account: ata(payer, &self.mint),
+        chain_token_account: ata(&chain_stats_pda(), &self.mint),
+        signer: *payer,
+        token_program: spl_token::ID,
+        instructions: sysvar::instructions::ID,
+        system_program: system_program::ID,
+        event_authority: event_authority(),
+        program: chainbills::ID,
+      },
+      chainbills::instruction::PayWithIntent {
+        amount: self.amount,
+        payer_proof: vec![],
+        reference: self.reference.clone(),
+        expiry: self.expiry,
+        nonce: self.nonce,
+      },
+    )
+  }
+}
+
+#[tokio::test]
+async fn pay_with_intent() {
+  let mut env = Env::new().await;
+  let mint = env.supported_mint(6).await;
+  let host = env.new_user().await;
+  let payer = env.new_user().await;
+  env.mint_to(&mint, &payer.pubkey(), 10_000).await;
+  // Intents apply regardless of the payable's allowed tokens and amounts.
+  let payable = env.create_payable(&host, vec![taa(mint, 1_000)]).await;
+  let mut intent = Intent {
+    payable,
+    mint,
+    amount: 1_234,
+    expiry: env.now().await + 60,
+    nonce: 7,
+    reference: "INV-0007".to_string(),
+  };
+
+  let signature = ed25519_ix(&host, &intent.signing_bytes());
+  let pay = intent.pay_ix(&mut env, &payer.pubkey()).await;
+  let payable_payment = pay.accounts[2].pubkey;
+  let outcome = env.send(&[signature, pay], &[&payer]).await.unwrap();
+  let event = outcome.event::<PaidWithIntent>();
+  assert_eq!(event.payable_id, payable);
+  assert_eq!(event.payable_payment_id, payable_payment);
+  assert_eq!(event.payer_wallet, payer.pubkey());
+  assert_eq!(event.nonce, 7);
+  assert_eq!(event.reference, "INV-0007");
+  assert_eq!(outcome.event::<PayableReceived>().amount, 1_234);
+  let consumed: ConsumedPaymentIntent = env
+    .account(pda(&[
+      payable.as_ref(),
+      ConsumedPaymentIntent::SEED_PREFIX,
+      &7u64.to_le_bytes(),
+    ]))
+    .await;
+  assert_eq!(consumed.payable_payment, payable_payment);
+  assert_eq!(consumed.reference, "INV-0007");
+  assert_eq!(
+    pairs(&env.payable(payable).await.balances),
+    vec![(mint, 1_234)]
+  );
+
+  // Each intent can only be paid once.
+  let signature = ed25519_ix(&host, &intent.signing_bytes());
+  let pay = intent.pay_ix(&mut env, &payer.pubkey()).await;
+  assert!(env.send(&[signature, pay], &[&payer]).await.is_err());
+
+  // The intent must be signed by the host, right before the payment.
+  intent.nonce = 8;
+  let stranger = Keypair::new();
+  let signature = ed25519_ix(&stranger, &intent.signing_bytes());
+  let pay = intent.pay_ix(&mut env, &payer.pubkey()).await;
+  assert_error_code(
+    env.send(&[signature, pay], &[&payer]).await,
+    ChainbillsError::InvalidIntentSignature.into(),
+  );
+  let pay = intent.pay_ix(&mut env, &payer.pubkey()).await;
+  assert_error(
+    env.send(&[pay], &[&payer]).await,
+    ChainbillsError::InvalidIntentSignature,
+  );
+
+  // The signed intent must be the one being paid.
+  let signature = ed25519_ix(&host, &intent.signing_bytes());
+  intent.amount = 1_000;
+  let pay = intent.pay_ix(&mut env, &payer.pubkey()).await;
+  assert_error(
+    env.send(&[signature, pay], &[&payer]).await,
+    ChainbillsError::InvalidIntentSignature,
+  );
+
+  // Expired intents can't be paid.
+  intent.expiry = env.now().await - 1;
+  let signature = ed25519_ix(&host, &intent.signing_bytes());
+  let pay = intent.pay_ix(&mut env, &payer.pubkey()).await;
+  assert_error(
+    env.send(&[signature, pay], &[&payer]).await,
+    ChainbillsError::PaymentIntentExpired,
+  );
+}
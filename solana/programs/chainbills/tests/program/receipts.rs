@@ -0,0 +1,193 @@
+use crate::common::*;
+use crate::payments::{batch_accounts, pay_batch_ix};
+use anchor_lang::{
+  prelude::*, solana_program::instruction::Instruction, system_program,
+};
+use anchor_spl::token_2022::spl_token_2022::{
+  self,
+  extension::{
+    metadata_pointer::MetadataPointer, BaseStateWithExtensions,
+    StateWithExtensions,
+  },
+  state::{Account as TokenAccount, Mint},
+};
+use anchor_spl::token_interface::spl_token_metadata_interface::state::TokenMetadata;
+use chainbills::{error::ChainbillsError, events::*, state::*};
+use solana_sdk::signer::Signer;
+
+async fn update_payable_receipts_ix(
+  env: &mut Env,
+  signer: &Pubkey,
+  payable: Pubkey,
+  enabled: bool,
+) -> Instruction {
+  let activities = env.activity_accounts(payable).await;
+  ix(
+    chainbills::accounts::UpdatePayableReceipts {
+      payable,
+      activity: activities.activity,
+      user_activity_info: activities.user_activity_info,
+      payable_activity_info: activities.payable_activity_info,
+      payable_receipts: pda(&[payable.as_ref(), PayableReceipts::SEED_PREFIX]),
+      host: activities.host,
+      operator: None,
+      chain_shard: activities.chain_shard,
+      signer: *signer,
+      system_program: system_program::ID,
+    },
+    chainbills::instruction::UpdatePayableReceipts { enabled },
+  )
+}
+
+/// Asserts that the receipt of the payment was minted to the payer, with the
+/// payment's metadata on its mint.
+async fn assert_receipt(
+  env: &mut Env,
+  user_payment: Pubkey,
+  payer: &Pubkey,
+  token: &Pubkey,
+  amount: u64,
+) {
+  let receipt_mint = receipt_mint_pda(&user_payment);
+  let account = env.get_account(receipt_mint).await.unwrap();
+  assert_eq!(account.owner, spl_token_2022::ID);
+  let mint = StateWithExtensions::<Mint>::unpack(&account.data).unwrap();
+  assert_eq!(mint.base.supply, 1);
+  assert_eq!(mint.base.decimals, 0);
+  assert!(mint.base.mint_authority.is_none());
+  let pointer = mint.get_extension::<MetadataPointer>().unwrap();
+  assert_eq!(
+    Option::<Pubkey>::from(pointer.metadata_address),
+    Some(receipt_mint)
+  );
+  let metadata = mint.get_variable_len_extension::<TokenMetadata>().unwrap();
+  assert_eq!(metadata.mint, receipt_mint);
+  assert_eq!(metadata.name, Receipt::NAME);
+  assert_eq!(metadata.symbol, Receipt::SYMBOL);
+  assert_eq!(Option::<Pubkey>::from(metadata.update_authority), None);
+  let payment: UserPayment = env.account(user_payment).await;
+  assert_eq!(
+    metadata.additional_metadata,
+    Receipt::fields(user_payment, &payment)
+  );
+  assert_eq!(payment.payer, *payer);
+  assert_eq!(payment.details.token, *token);
+  assert_eq!(payment.details.amount, amount);
+
+  let account = env
+    .get_account(receipt_ata(payer, &receipt_mint))
+    .await
+    .unwrap();
+  let token_account =
+    StateWithExtensions::<TokenAccount>::unpack(&account.data).unwrap();
+  assert_eq!(token_account.base.owner, *payer);
+  assert_eq!(token_account.base.amount, 1);
+}
+
+#[tokio::test]
+async fn payments_mint_receipts() {
+  let mut env = Env::new().await;
+  let mint = env.supported_mint(6).await;
+  let host = env.new_user().await;
+  let payer = env.new_user().await;
+  env.mint_to(&mint, &payer.pubkey(), 10_000).await;
+  let payable = env.create_payable(&host, vec![]).await;
+
+  // Payments don't mint receipts until the host enables them.
+  let user_payment = env
+    .payment_accounts(&payer.pubkey(), payable)
+    .await
+    .user_payment;
+  let outcome = env.pay(&payer, payable, &mint, 1_000).await.unwrap();
+  assert!(outcome.events::<MintedReceipt>().is_empty());
+  assert!(env
+    .get_account(receipt_mint_pda(&user_payment))
+    .await
+    .is_none());
+  let ix =
+    update_payable_receipts_ix(&mut env, &payer.pubkey(), payable, true).await;
+  assert_error(
+    env.send(&[ix], &[&payer]).await,
+    ChainbillsError::NotYourPayable,
+  );
+  let ix =
+    update_payable_receipts_ix(&mut env, &host.pubkey(), payable, true).await;
+  let outcome = env.send(&[ix], &[&host]).await.unwrap();
+  let event = outcome.event::<UpdatedPayableReceipts>();
+  assert_eq!(event.payable_id, payable);
+  assert_eq!(event.host_wallet, host.pubkey());
+  assert!(event.enabled);
+
+  // Payments then can't leave their receipt accounts out.
+  let mut accounts = env.pay_accounts(&payer.pubkey(), payable, &mint).await;
+  accounts.receipt_mint = None;
+  let pay_ix = crate::common::ix(
+    accounts,
+    chainbills::instruction::Pay {
+      amount: 1_000,
+      payer_proof: vec![],
+    },
+  );
+  assert_error(
+    env.send(&[pay_ix], &[&payer]).await,
+    ChainbillsError::InvalidReceiptAccounts,
+  );
+
+  // Each payment mints its receipt to the payer.
+  let user_payment = env
+    .payment_accounts(&payer.pubkey(), payable)
+    .await
+    .user_payment;
+  let outcome = env.pay(&payer, payable, &mint, 1_000).await.unwrap();
+  let event = outcome.event::<MintedReceipt>();
+  assert_eq!(event.payable_id, payable.to_bytes());
+  assert_eq!(event.user_payment_id, user_payment);
+  assert_eq!(event.payer_wallet, payer.pubkey());
+  assert_eq!(event.receipt_mint, receipt_mint_pda(&user_payment));
+  assert_receipt(&mut env, user_payment, &payer.pubkey(), &mint, 1_000).await;
+
+  let user_payment = env
+    .payment_accounts(&payer.pubkey(), payable)
+    .await
+    .user_payment;
+  env.pay_native(&payer, payable, 1_000_000).await.unwrap();
+  assert_receipt(
+    &mut env,
+    user_payment,
+    &payer.pubkey(),
+    &chainbills::ID,
+    1_000_000,
+  )
+  .await;
+
+  // So does each payment of a batch.
+  let other = env.create_payable(&host, vec![]).await;
+  let remaining =
+    batch_accounts(&mut env, &payer.pubkey(), &[payable, other]).await;
+  let user_payments = [remaining[5].pubkey, remaining[17].pubkey];
+  let ix =
+    pay_batch_ix(&env, &payer.pubkey(), &mint, &[1_000, 2_000], remaining);
+  let outcome = env.send(&[ix], &[&payer]).await.unwrap();
+  assert_eq!(outcome.events::<MintedReceipt>().len(), 1);
+  assert_receipt(&mut env, user_payments[0], &payer.pubkey(), &mint, 1_000)
+    .await;
+  assert!(env
+    .get_account(receipt_mint_pda(&user_payments[1]))
+    .await
+    .is_none());
+
+  // Payments stop minting receipts after the host disables them.
+  let ix =
+    update_payable_receipts_ix(&mut env, &host.pubkey(), payable, false).await;
+  env.send(&[ix], &[&host]).await.unwrap();
+  let user_payment = env
+    .payment_accounts(&payer.pubkey(), payable)
+    .await
+    .user_payment;
+  let outcome = env.pay(&payer, payable, &mint, 1_000).await.unwrap();
+  assert!(outcome.events::<MintedReceipt>().is_empty());
+  assert!(env
+    .get_account(receipt_mint_pda(&user_payment))
+    .await
+    .is_none());
+}
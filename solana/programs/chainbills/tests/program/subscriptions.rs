@@ -0,0 +1,451 @@
+use crate::common::*;
+use anchor_lang::{
+  prelude::*, solana_program::instruction::Instruction, system_program,
+};
+use anchor_spl::token::spl_token;
+use chainbills::{error::ChainbillsError, events::*, state::*};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+fn subscription_pda(payable: &Pubkey, payer: &Pubkey) -> Pubkey {
+  pda(&[payable.as_ref(), Subscription::SEED_PREFIX, payer.as_ref()])
+}
+
+pub(crate) async fn create_subscription_ix(
+  env: &mut Env,
+  payer: &Pubkey,
+  payable: Pubkey,
+  mint: &Pubkey,
+  amount: u64,
+  period: u64,
+  first_due: Option<u64>,
+) -> Instruction {
+  let next_activity = env.chain_shard().await.next_activity();
+  let user = env.user(payer).await;
+  let payable_data = env.payable(payable).await;
+  ix(
+    chainbills::accounts::CreateSubscription {
+      subscription: subscription_pda(&payable, payer),
+      payable,
+      allowed_payers: allowed_payers_pda(&payable),
+      activity: activity_pda(next_activity),
+      user_activity_info: activity_info_pda(payer, user.next_activity()),
+      payable_activity_info: activity_info_pda(
+        &payable,
+        payable_data.next_activity(),
+      ),
+      payer: user_pda(payer),
+      chain_shard: chain_shard_pda(env.shard),
+      mint: *mint,
+      token_details: token_details_pda(mint),
+      signer: *payer,
+      system_program: system_program::ID,
+    },
+    chainbills::instruction::CreateSubscription {
+      amount,
+      period,
+      first_due,
+      payer_proof: vec![],
+    },
+  )
+}
+
+pub(crate) async fn collect_subscription_ix(
+  env: &mut Env,
+  collector: &Pubkey,
+  payer: &Pubkey,
+  payable: Pubkey,
+  mint: &Pubkey,
+) -> Instruction {
+  let p = env.payment_accounts(payer, payable).await;
+  ix(
+    chainbills::accounts::CollectSubscription {
+      subscription: subscription_pda(&payable, payer),
+      user_payment: p.user_payment,
+      payable_payment: p.payable_payment,
+      payable_per_chain_payment_info: p.payable_per_chain_payment_info,
+      payable_per_chain_payments_page: p.payable_per_chain_payments_page,
+      payable_per_chain_payments_counter: p.payable_per_chain_payments_counter,
+      user_activity: p.user_activity,
+      payable_activity: p.payable_activity,
+      payable,
+      payable_escrow: p.payable_escrow,
+      escrow_state: p.escrow_state,
+      payer: p.payer,
+      chain_stats: chain_stats_pda(),
+      chain_shard: p.chain_shard,
+      config: config_pda(),
+      mint: *mint,
+      token_details: token_details_pda(mint),
+      token_details_shard: token_details_shard_pda(mint, env.shard),
+      payer_token_account: ata(payer, mint),
+      chain_token_account: ata(&chain_stats_pda(), mint),
+      signer: *collector,
+      token_program: spl_token::ID,
+      system_program: system_program::ID,
+      event_authority: event_authority(),
+      program: chainbills::ID,
+    },
+    chainbills::instruction::CollectSubscription {},
+  )
+}
+
+async fn cancel_subscription_ix(
+  env: &mut Env,
+  signer: &Pubkey,
+  payer: &Pubkey,
+  payable: Pubkey,
+) -> Instruction {
+  let next_activity = env.chain_shard().await.next_activity();
+  let user = env.user(payer).await;
+  let payable_data = env.payable(payable).await;
+  ix(
+    chainbills::accounts::CancelSubscription {
+      subscription: subscription_pda(&payable, payer),
+      payable,
+      operator: None,
+      activity: activity_pda(next_activity),
+      user_activity_info: activity_info_pda(payer, user.next_activity()),
+      payable_activity_info: activity_info_pda(
+        &payable,
+        payable_data.next_activity(),
+      ),
+      payer: user_pda(payer),
+      chain_shard: chain_shard_pda(env.shard),
+      signer: *signer,
+      system_program: system_program::ID,
+    },
+    chainbills::instruction::CancelSubscription {},
+  )
+}
+
+/// Lets the program pull up to the amount of the token from the payer.
+pub(crate) async fn approve(
+  env: &mut Env,
+  payer: &Keypair,
+  mint: &Pubkey,
+  amount: u64,
+) {
+  let ix = spl_token::instruction::approve(
+    &spl_token::ID,
+    &ata(&payer.pubkey(), mint),
+    &chain_stats_pda(),
+    &payer.pubkey(),
+    &[],
+    amount,
+  )
+  .unwrap();
+  env.send(&[ix], &[payer]).await.unwrap();
+}
+
+#[tokio::test]
+async fn subscriptions() {
+  let mut env = Env::new().await;
+  let mint = env.supported_mint(6).await;
+  let host = env.new_user().await;
+  let payer = env.new_user().await;
+  let collector = env.wallet().await;
+  env.mint_to(&mint, &payer.pubkey(), 10_000).await;
+  let payable = env.create_payable(&host, vec![]).await;
+
+  let ix = create_subscription_ix(
+    &mut env,
+    &payer.pubkey(),
+    payable,
+    &mint,
+    1_000,
+    0,
+    None,
+  )
+  .await;
+  assert_error(
+    env.send(&[ix], &[&payer]).await,
+    ChainbillsError::InvalidSubscriptionPeriod,
+  );
+  let ix = create_subscription_ix(
+    &mut env,
+    &payer.pubkey(),
+    payable,
+    &mint,
+    0,
+    60,
+    None,
+  )
+  .await;
+  assert_error(
+    env.send(&[ix], &[&payer]).await,
+    ChainbillsError::ZeroAmountSpecified,
+  );
+  let past = env.now().await - 1;
+  let ix = create_subscription_ix(
+    &mut env,
+    &payer.pubkey(),
+    payable,
+    &mint,
+    1_000,
+    60,
+    Some(past),
+  )
+  .await;
+  assert_error(
+    env.send(&[ix], &[&payer]).await,
+    ChainbillsError::SubscriptionFirstDueInPast,
+  );
+
+  let ix = create_subscription_ix(
+    &mut env,
+    &payer.pubkey(),
+    payable,
+    &mint,
+    1_000,
+    60,
+    None,
+  )
+  .await;
+  let outcome = env.send(&[ix], &[&payer]).await.unwrap();
+  let now = env.now().await;
+  let event = outcome.event::<CreatedSubscription>();
+  assert_eq!(event.payable_id, payable);
+  assert_eq!(event.payer_wallet, payer.pubkey());
+  assert_eq!(event.token, mint);
+  assert_eq!(event.amount, 1_000);
+  assert_eq!(event.period, 60);
+  assert_eq!(event.next_due, now);
+  let subscription: Subscription = env
+    .account(subscription_pda(&payable, &payer.pubkey()))
+    .await;
+  assert_eq!(subscription.next_due, now);
+  assert_eq!(subscription.payments_count, 0);
+  assert!(!subscription.is_cancelled);
+
+  // A payer has one active subscription per payable.
+  let ix = create_subscription_ix(
+    &mut env,
+    &payer.pubkey(),
+    payable,
+    &mint,
+    2_000,
+    60,
+    None,
+  )
+  .await;
+  assert_error(
+    env.send(&[ix], &[&payer]).await,
+    ChainbillsError::SubscriptionAlreadyExists,
+  );
+
+  // Anyone can collect due payments out of the payer's approval.
+  approve(&mut env, &payer, &mint, 2_000).await;
+  let ix = collect_subscription_ix(
+    &mut env,
+    &collector.pubkey(),
+    &payer.pubkey(),
+    payable,
+    &mint,
+  )
+  .await;
+  let outcome = env.send(&[ix], &[&collector]).await.unwrap();
+  let event = outcome.event::<CollectedSubscription>();
+  assert_eq!(event.payer_wallet, payer.pubkey());
+  assert_eq!(event.collector, collector.pubkey());
+  assert_eq!(event.subscription_count, 1);
+  assert_eq!(event.next_due, now + 60);
+  assert_eq!(outcome.event::<UserPaid>().amount, 1_000);
+  assert_eq!(outcome.event::<PayableReceived>().amount, 1_000);
+  assert_eq!(env.token_balance(ata(&payer.pubkey(), &mint)).await, 9_000);
+  let balances = env.payable(payable).await.balances;
+  assert_eq!((balances[0].token, balances[0].amount), (mint, 1_000));
+  assert_eq!(env.user(&payer.pubkey()).await.payments_count, 1);
+
+  // The next payment is due a period later.
+  let ix = collect_subscription_ix(
+    &mut env,
+    &collector.pubkey(),
+    &payer.pubkey(),
+    payable,
+    &mint,
+  )
+  .await;
+  assert_error(
+    env.send(&[ix], &[&collector]).await,
+    ChainbillsError::SubscriptionNotDue,
+  );
+  env.advance_time(60).await;
+  let ix = collect_subscription_ix(
+    &mut env,
+    &collector.pubkey(),
+    &payer.pubkey(),
+    payable,
+    &mint,
+  )
+  .await;
+  env.send(&[ix], &[&collector]).await.unwrap();
+  let subscription: Subscription = env
+    .account(subscription_pda(&payable, &payer.pubkey()))
+    .await;
+  assert_eq!(subscription.payments_count, 2);
+  assert_eq!(subscription.next_due, now + 120);
+  assert_eq!(env.payable(payable).await.balances[0].amount, 2_000);
+
+  // Collections fail once the approval is used up.
+  env.advance_time(60).await;
+  let ix = collect_subscription_ix(
+    &mut env,
+    &collector.pubkey(),
+    &payer.pubkey(),
+    payable,
+    &mint,
+  )
+  .await;
+  assert!(env.send(&[ix], &[&collector]).await.is_err());
+}
+
+#[tokio::test]
+async fn missed_subscription_periods() {
+  let mut env = Env::new().await;
+  let mint = env.supported_mint(6).await;
+  let host = env.new_user().await;
+  let payer = env.new_user().await;
+  let collector = env.wallet().await;
+  env.mint_to(&mint, &payer.pubkey(), 10_000).await;
+  let payable = env.create_payable(&host, vec![]).await;
+  let ix = create_subscription_ix(
+    &mut env,
+    &payer.pubkey(),
+    payable,
+    &mint,
+    1_000,
+    60,
+    None,
+  )
+  .await;
+  env.send(&[ix], &[&payer]).await.unwrap();
+  let now = env.now().await;
+  approve(&mut env, &payer, &mint, 10_000).await;
+
+  // After missing two and a half periods, one payment is collected and the
+  // next one is due at the following period boundary.
+  env.advance_time(150).await;
+  let ix = collect_subscription_ix(
+    &mut env,
+    &collector.pubkey(),
+    &payer.pubkey(),
+    payable,
+    &mint,
+  )
+  .await;
+  let outcome = env.send(&[ix], &[&collector]).await.unwrap();
+  assert_eq!(outcome.event::<CollectedSubscription>().next_due, now + 180);
+  let ix = collect_subscription_ix(
+    &mut env,
+    &collector.pubkey(),
+    &payer.pubkey(),
+    payable,
+    &mint,
+  )
+  .await;
+  assert_error(
+    env.send(&[ix], &[&collector]).await,
+    ChainbillsError::SubscriptionNotDue,
+  );
+  let subscription: Subscription = env
+    .account(subscription_pda(&payable, &payer.pubkey()))
+    .await;
+  assert_eq!(subscription.payments_count, 1);
+  assert_eq!(env.token_balance(ata(&payer.pubkey(), &mint)).await, 9_000);
+}
+
+#[tokio::test]
+async fn cancel_subscription() {
+  let mut env = Env::new().await;
+  let mint = env.supported_mint(6).await;
+  let host = env.new_user().await;
+  let payer = env.new_user().await;
+  let stranger = env.new_user().await;
+  env.mint_to(&mint, &payer.pubkey(), 10_000).await;
+  let payable = env.create_payable(&host, vec![]).await;
+  let ix = create_subscription_ix(
+    &mut env,
+    &payer.pubkey(),
+    payable,
+    &mint,
+    1_000,
+    60,
+    None,
+  )
+  .await;
+  env.send(&[ix], &[&payer]).await.unwrap();
+
+  // Only the payer or the host can cancel.
+  let ix = cancel_subscription_ix(
+    &mut env,
+    &stranger.pubkey(),
+    &payer.pubkey(),
+    payable,
+  )
+  .await;
+  assert_error(
+    env.send(&[ix], &[&stranger]).await,
+    ChainbillsError::NotYourSubscription,
+  );
+  let ix =
+    cancel_subscription_ix(&mut env, &host.pubkey(), &payer.pubkey(), payable)
+      .await;
+  let outcome = env.send(&[ix], &[&host]).await.unwrap();
+  let event = outcome.event::<CancelledSubscription>();
+  assert_eq!(event.payer_wallet, payer.pubkey());
+  assert_eq!(event.cancelled_by, host.pubkey());
+  let ix =
+    cancel_subscription_ix(&mut env, &payer.pubkey(), &payer.pubkey(), payable)
+      .await;
+  assert_error(
+    env.send(&[ix], &[&payer]).await,
+    ChainbillsError::SubscriptionIsCancelled,
+  );
+
+  // Cancelled subscriptions can't be collected.
+  approve(&mut env, &payer, &mint, 1_000).await;
+  let ix = collect_subscription_ix(
+    &mut env,
+    &payer.pubkey(),
+    &payer.pubkey(),
+    payable,
+    &mint,
+  )
+  .await;
+  assert_error(
+    env.send(&[ix], &[&payer]).await,
+    ChainbillsError::SubscriptionIsCancelled,
+  );
+
+  // The payer can subscribe again after cancelling, from a later date.
+  let first_due = env.now().await + 3_600;
+  let ix = create_subscription_ix(
+    &mut env,
+    &payer.pubkey(),
+    payable,
+    &mint,
+    500,
+    60,
+    Some(first_due),
+  )
+  .await;
+  env.send(&[ix], &[&payer]).await.unwrap();
+  let subscription: Subscription = env
+    .account(subscription_pda(&payable, &payer.pubkey()))
+    .await;
+  assert_eq!(subscription.amount, 500);
+  assert_eq!(subscription.next_due, first_due);
+  assert!(!subscription.is_cancelled);
+  let ix = collect_subscription_ix(
+    &mut env,
+    &payer.pubkey(),
+    &payer.pubkey(),
+    payable,
+    &mint,
+  )
+  .await;
+  assert_error(
+    env.send(&[ix], &[&payer]).await,
+    ChainbillsError::SubscriptionNotDue,
+  );
+}
@@ -0,0 +1,47 @@
+use crate::common::*;
+use chainbills::{events::*, state::*};
+use solana_sdk::signer::Signer;
+
+#[tokio::test]
+async fn initialize_user() {
+  let mut env = Env::new().await;
+  let wallet = env.wallet().await;
+
+  let ix = env.initialize_user_ix(&wallet.pubkey()).await;
+  let outcome = env.send(&[ix], &[&wallet]).await.unwrap();
+  let event = outcome.event::<InitializedUser>();
+  assert_eq!(event.wallet, wallet.pubkey());
+  assert_eq!(event.chain_count, sharded(1));
+
+  let user = env.user(&wallet.pubkey()).await;
+  assert_eq!(user.version, User::VERSION);
+  assert_eq!(user.chain_count, sharded(1));
+  assert_eq!(user.payables_count, 0);
+  assert_eq!(user.payments_count, 0);
+  assert_eq!(user.withdrawals_count, 0);
+  assert_eq!(user.activities_count, 1);
+
+  let chain_user_address: ChainUserAddress = env
+    .account(pda(&[ChainUserAddress::SEED_PREFIX, &sharded(1).to_le_bytes()]))
+    .await;
+  assert_eq!(chain_user_address.user_address, wallet.pubkey());
+  let activity: ActivityRecord = env.account(activity_pda(sharded(1))).await;
+  assert_eq!(activity.entity, wallet.pubkey());
+  assert!(matches!(
+    activity.activity_type,
+    ActivityType::InitializedUser
+  ));
+
+  let chain_shard = env.chain_shard().await;
+  assert_eq!(chain_shard.users_count, sharded(1));
+  assert_eq!(chain_shard.activities_count, sharded(1));
+  assert_eq!(env.chain_stats().await.users_count, 0);
+
+  // The next user is counted after the first.
+  let other = env.new_user().await;
+  assert_eq!(env.user(&other.pubkey()).await.chain_count, sharded(2));
+
+  // A wallet can't be initialized twice.
+  let ix = env.initialize_user_ix(&wallet.pubkey()).await;
+  assert!(env.send(&[ix], &[&wallet]).await.is_err());
+}
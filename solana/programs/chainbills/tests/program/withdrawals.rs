@@ -0,0 +1,690 @@
+use crate::common::*;
+use anchor_lang::{
+  prelude::*, solana_program::instruction::Instruction, system_program,
+  Discriminator,
+};
+use anchor_spl::token::spl_token;
+use chainbills::{error::ChainbillsError, events::*, state::*};
+use solana_sdk::{
+  account::AccountSharedData, signature::Keypair, signer::Signer,
+};
+use wormhole_cctp_solana::cctp::{
+  message_transmitter_program::{self, MessageTransmitterConfig},
+  token_messenger_minter_program,
+};
+
+fn pairs(taas: &[TokenAndAmount]) -> Vec<(Pubkey, u64)> {
+  taas.iter().map(|taa| (taa.token, taa.amount)).collect()
+}
+
+/// The accounts that a withdrawal from the payable initializes.
+struct WithdrawalAccounts {
+  withdrawal: Pubkey,
+  chain_withdrawal_id: Pubkey,
+  payable_withdrawal_info: Pubkey,
+  activities: Activities,
+}
+
+async fn withdrawal_accounts(
+  env: &mut Env,
+  payable: Pubkey,
+) -> WithdrawalAccounts {
+  let chain_shard = env.chain_shard().await;
+  let payable_data = env.payable(payable).await;
+  let host = env.user(&payable_data.host).await;
+  WithdrawalAccounts {
+    withdrawal: pda(&[
+      payable_data.host.as_ref(),
+      Withdrawal::SEED_PREFIX,
+      &host.next_withdrawal().to_le_bytes(),
+    ]),
+    chain_withdrawal_id: pda(&[
+      ChainWithdrawalId::SEED_PREFIX,
+      &chain_shard.next_withdrawal().to_le_bytes(),
+    ]),
+    payable_withdrawal_info: pda(&[
+      payable.as_ref(),
+      PayableWithdrawalInfo::SEED_PREFIX,
+      &payable_data.next_withdrawal().to_le_bytes(),
+    ]),
+    activities: env.activity_accounts(payable).await,
+  }
+}
+
+/// The signer's operator account on the payable, if the signer isn't the
+/// host and the account exists.
+async fn operator_account(
+  env: &mut Env,
+  signer: &Pubkey,
+  payable: Pubkey,
+) -> Option<Pubkey> {
+  let operator = operator_pda(&payable, signer);
+  let host = env.payable(payable).await.host;
+  (*signer != host && env.exists(operator).await).then_some(operator)
+}
+
+pub(crate) async fn withdraw_ix(
+  env: &mut Env,
+  signer: &Pubkey,
+  payable: Pubkey,
+  mint: &Pubkey,
+  amount: u64,
+) -> Instruction {
+  let w = withdrawal_accounts(env, payable).await;
+  let host = env.payable(payable).await.host;
+  let operator = operator_account(env, signer, payable).await;
+  ix(
+    chainbills::accounts::Withdraw {
+      withdrawal: w.withdrawal,
+      chain_withdrawal_id: w.chain_withdrawal_id,
+      payable_withdrawal_info: w.payable_withdrawal_info,
+      activity: w.activities.activity,
+      user_activity_info: w.activities.user_activity_info,
+      payable_activity_info: w.activities.payable_activity_info,
+      payable,
+      host: w.activities.host,
+      host_wallet: host,
+      operator,
+      chain_stats: chain_stats_pda(),
+      chain_shard: w.activities.chain_shard,
+      config: config_pda(),
+      mint: *mint,
+      token_details: token_details_pda(mint),
+      host_token_account: ata(&host, mint),
+      chain_token_account: ata(&chain_stats_pda(), mint),
+      fees_token_account: ata(&env.fee_collector, mint),
+      fee_collector: env.fee_collector,
+      signer: *signer,
+      token_program: spl_token::ID,
+      system_program: system_program::ID,
+      event_authority: event_authority(),
+      program: chainbills::ID,
+    },
+    chainbills::instruction::Withdraw { amount },
+  )
+}
+
+async fn withdraw_native_ix(
+  env: &mut Env,
+  signer: &Pubkey,
+  payable: Pubkey,
+  amount: u64,
+) -> Instruction {
+  let w = withdrawal_accounts(env, payable).await;
+  let host = env.payable(payable).await.host;
+  let operator = operator_account(env, signer, payable).await;
+  ix(
+    chainbills::accounts::WithdrawNative {
+      withdrawal: w.withdrawal,
+      chain_withdrawal_id: w.chain_withdrawal_id,
+      payable_withdrawal_info: w.payable_withdrawal_info,
+      activity: w.activities.activity,
+      user_activity_info: w.activities.user_activity_info,
+      payable_activity_info: w.activities.payable_activity_info,
+      payable,
+      host: w.activities.host,
+      host_wallet: host,
+      operator,
+      chain_shard: w.activities.chain_shard,
+      config: config_pda(),
+      fee_collector: env.fee_collector,
+      token_details: token_details_pda(&chainbills::ID),
+      native_vault: native_vault_pda(),
+      signer: *signer,
+      system_program: system_program::ID,
+      event_authority: event_authority(),
+      program: chainbills::ID,
+    },
+    chainbills::instruction::WithdrawNative { amount },
+  )
+}
+
+/// Grants the roles on the payable to the operator.
+pub(crate) fn set_operator(
+  env: &mut Env,
+  payable: Pubkey,
+  host: Pubkey,
+  operator: Pubkey,
+  roles: u8,
+) {
+  env.set_account(
+    operator_pda(&payable, &operator),
+    &PayableOperator {
+      payable_id: payable,
+      operator,
+      host,
+      roles,
+    },
+  );
+}
+
+/// A payable of a new host, with a payer that paid the amount of a new
+/// token into it.
+pub(crate) async fn paid_payable(
+  env: &mut Env,
+  amount: u64,
+) -> (Keypair, Pubkey, Pubkey) {
+  let mint = env.supported_mint(6).await;
+  let host = env.new_user().await;
+  let payer = env.new_user().await;
+  env.mint_to(&mint, &payer.pubkey(), amount).await;
+  // The host's token account must exist to receive withdrawals.
+  env.mint_to(&mint, &host.pubkey(), 0).await;
+  let payable = env.create_payable(&host, vec![]).await;
+  env.pay(&payer, payable, &mint, amount).await.unwrap();
+  (host, payable, mint)
+}
+
+#[tokio::test]
+async fn withdraw() {
+  let mut env = Env::new().await;
+  let (host, payable, mint) = paid_payable(&mut env, 1_000_000).await;
+  let w = withdrawal_accounts(&mut env, payable).await;
+
+  let ix = withdraw_ix(&mut env, &host.pubkey(), payable, &mint, 600_000).await;
+  let outcome = env.send(&[ix], &[&host]).await.unwrap();
+  let now = env.now().await;
+  let withdrew = outcome.event::<Withdrew>();
+  assert_eq!(withdrew.payable_id, payable);
+  assert_eq!(withdrew.host_wallet, host.pubkey());
+  assert_eq!(withdrew.withdrawal_id, w.withdrawal);
+  assert_eq!(withdrew.chain_count, sharded(1));
+  assert_eq!(withdrew.host_count, 1);
+  assert_eq!(withdrew.payable_count, 1);
+  assert_eq!(withdrew.token, mint);
+  assert_eq!(withdrew.amount, 600_000);
+  assert_eq!(withdrew.fees, 12_000);
+  assert_eq!(withdrew.amount_due, 588_000);
+  assert_eq!(withdrew.timestamp, now);
+  let collected = outcome.event::<CollectedWithdrawalFees>();
+  assert_eq!(collected.withdrawal_id, w.withdrawal);
+  assert_eq!(collected.fee_collector, env.fee_collector);
+  assert_eq!(collected.fees, 12_000);
+
+  let withdrawal: Withdrawal = env.account(w.withdrawal).await;
+  assert_eq!(withdrawal.payable_id, payable);
+  assert_eq!(withdrawal.host, host.pubkey());
+  assert_eq!(pairs(&[withdrawal.details]), vec![(mint, 600_000)]);
+  assert_eq!(withdrawal.dest_chain_id, solana_cb_chain_id());
+  assert_eq!(withdrawal.dest_address, host.pubkey().to_bytes());
+  let chain_withdrawal_id: ChainWithdrawalId =
+    env.account(w.chain_withdrawal_id).await;
+  assert_eq!(chain_withdrawal_id.withdrawal_id, w.withdrawal);
+  let activity: ActivityRecord = env.account(w.activities.activity).await;
+  assert_eq!(activity.entity, w.withdrawal);
+  assert!(matches!(activity.activity_type, ActivityType::Withdrew));
+
+  assert_eq!(env.token_balance(ata(&host.pubkey(), &mint)).await, 588_000);
+  assert_eq!(
+    env.token_balance(ata(&env.fee_collector, &mint)).await,
+    12_000
+  );
+  assert_eq!(
+    env.token_balance(ata(&chain_stats_pda(), &mint)).await,
+    400_000
+  );
+  let payable_data = env.payable(payable).await;
+  assert_eq!(payable_data.withdrawals_count, 1);
+  assert_eq!(pairs(&payable_data.balances), vec![(mint, 400_000)]);
+  assert_eq!(env.user(&host.pubkey()).await.withdrawals_count, 1);
+  assert_eq!(env.chain_shard().await.withdrawals_count, sharded(1));
+  let token_details = env.token_details(&mint).await;
+  assert_eq!(token_details.total_withdrawn, 600_000);
+  assert_eq!(token_details.total_withdrawal_fees_collected, 12_000);
+
+  // Withdrawing the rest drops the emptied balance and shrinks the payable,
+  // refunding the freed rent to the host.
+  let size = env.get_account(payable).await.unwrap().data.len();
+  let host_lamports = env.lamports(host.pubkey()).await;
+  let ix = withdraw_ix(&mut env, &host.pubkey(), payable, &mint, 400_000).await;
+  env.send(&[ix], &[&host]).await.unwrap();
+  assert!(env.payable(payable).await.balances.is_empty());
+  assert!(env.get_account(payable).await.unwrap().data.len() < size);
+  // The host also paid rent for the new withdrawal's accounts.
+  let rent = env.rent();
+  let paid = [
+    Withdrawal::SPACE,
+    ChainWithdrawalId::SPACE,
+    PayableWithdrawalInfo::SPACE,
+    ActivityRecord::SPACE,
+    UserActivityInfo::SPACE,
+    PayableActivityInfo::SPACE,
+  ]
+  .iter()
+  .map(|space| rent.minimum_balance(*space))
+  .sum::<u64>();
+  assert!(env.lamports(host.pubkey()).await > host_lamports - paid);
+}
+
+#[tokio::test]
+async fn withdraw_with_client_instruction() {
+  let mut env = Env::new().await;
+  let (host, payable, mint) = paid_payable(&mut env, 1_000_000).await;
+
+  let ix = env
+    .client()
+    .withdraw(&host.pubkey(), &payable, &mint, 600_000)
+    .unwrap();
+  let outcome = env.send(&[ix], &[&host]).await.unwrap();
+  assert_eq!(outcome.event::<Withdrew>().amount_due, 588_000);
+  assert_eq!(env.token_balance(ata(&host.pubkey(), &mint)).await, 588_000);
+  assert_eq!(
+    pairs(&env.payable(payable).await.balances),
+    vec![(mint, 400_000)]
+  );
+}
+
+#[tokio::test]
+async fn withdraw_checks_inputs() {
+  let mut env = Env::new().await;
+  let (host, payable, mint) = paid_payable(&mut env, 1_000).await;
+  let other_mint = env.supported_mint(6).await;
+  env.mint_to(&other_mint, &host.pubkey(), 0).await;
+
+  let ix = withdraw_ix(&mut env, &host.pubkey(), payable, &mint, 0).await;
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::ZeroAmountSpecified,
+  );
+  let ix = withdraw_ix(&mut env, &host.pubkey(), payable, &mint, 1_001).await;
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::InsufficientWithdrawAmount,
+  );
+  let ix = withdraw_ix(&mut env, &host.pubkey(), payable, &other_mint, 1).await;
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::NoBalanceForWithdrawalToken,
+  );
+
+  // A payable without balances has nothing to withdraw.
+  let empty = env.create_payable(&host, vec![]).await;
+  let ix = withdraw_ix(&mut env, &host.pubkey(), empty, &mint, 1).await;
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::NoBalanceForWithdrawalToken,
+  );
+}
+
+#[tokio::test]
+async fn withdraw_by_operators() {
+  let mut env = Env::new().await;
+  let (host, payable, mint) = paid_payable(&mut env, 1_000).await;
+  let stranger = env.new_user().await;
+
+  // Only the host or an operator with the withdraw role can withdraw.
+  let ix = withdraw_ix(&mut env, &stranger.pubkey(), payable, &mint, 1).await;
+  assert_error(
+    env.send(&[ix], &[&stranger]).await,
+    ChainbillsError::NotYourPayable,
+  );
+  set_operator(
+    &mut env,
+    payable,
+    host.pubkey(),
+    stranger.pubkey(),
+    PayableOperator::ROLE_MANAGE,
+  );
+  let ix = withdraw_ix(&mut env, &stranger.pubkey(), payable, &mint, 1).await;
+  assert_error(
+    env.send(&[ix], &[&stranger]).await,
+    ChainbillsError::NotYourPayable,
+  );
+
+  // Grants of a previous host don't count.
+  set_operator(
+    &mut env,
+    payable,
+    stranger.pubkey(),
+    stranger.pubkey(),
+    PayableOperator::ROLE_WITHDRAW,
+  );
+  let ix = withdraw_ix(&mut env, &stranger.pubkey(), payable, &mint, 1).await;
+  assert_error(
+    env.send(&[ix], &[&stranger]).await,
+    ChainbillsError::NotYourPayable,
+  );
+
+  // Operators withdraw to the host's wallet.
+  set_operator(
+    &mut env,
+    payable,
+    host.pubkey(),
+    stranger.pubkey(),
+    PayableOperator::ROLE_WITHDRAW,
+  );
+  let ix =
+    withdraw_ix(&mut env, &stranger.pubkey(), payable, &mint, 1_000).await;
+  let outcome = env.send(&[ix], &[&stranger]).await.unwrap();
+  assert_eq!(outcome.event::<Withdrew>().host_wallet, host.pubkey());
+  assert_eq!(env.token_balance(ata(&host.pubkey(), &mint)).await, 980);
+  assert_eq!(env.token_balance(ata(&stranger.pubkey(), &mint)).await, 0);
+  assert_eq!(env.user(&host.pubkey()).await.withdrawals_count, 1);
+}
+
+#[tokio::test]
+async fn withdrawal_fees_are_capped() {
+  let mut env = Env::new().await;
+  let (host, payable, mint) = paid_payable(&mut env, 1_000_000).await;
+  let ix = env.update_max_withdrawal_fees_ix(&mint, 5_000);
+  env.send(&[ix], &[]).await.unwrap();
+
+  let ix =
+    withdraw_ix(&mut env, &host.pubkey(), payable, &mint, 1_000_000).await;
+  let outcome = env.send(&[ix], &[&host]).await.unwrap();
+  let withdrew = outcome.event::<Withdrew>();
+  assert_eq!(withdrew.fees, 5_000);
+  assert_eq!(withdrew.amount_due, 995_000);
+  assert_eq!(env.token_balance(ata(&host.pubkey(), &mint)).await, 995_000);
+
+  // No fees event is emitted when no fees are charged.
+  let (host, payable, mint) = paid_payable(&mut env, 49).await;
+  let ix = withdraw_ix(&mut env, &host.pubkey(), payable, &mint, 49).await;
+  let outcome = env.send(&[ix], &[&host]).await.unwrap();
+  assert_eq!(outcome.event::<Withdrew>().fees, 0);
+  assert!(outcome.events::<CollectedWithdrawalFees>().is_empty());
+}
+
+#[tokio::test]
+async fn withdraw_native() {
+  let mut env = Env::new().await;
+  env.support_native().await;
+  let host = env.new_user().await;
+  let payer = env.new_user().await;
+  let payable = env.create_payable(&host, vec![]).await;
+  env
+    .pay_native(&payer, payable, 1_000_000_000)
+    .await
+    .unwrap();
+
+  let ix = withdraw_native_ix(&mut env, &host.pubkey(), payable, 0).await;
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::ZeroAmountSpecified,
+  );
+
+  let vault_lamports = env.lamports(native_vault_pda()).await;
+  let collector_lamports = env.lamports(env.fee_collector).await;
+  let ix =
+    withdraw_native_ix(&mut env, &host.pubkey(), payable, 600_000_000).await;
+  let outcome = env.send(&[ix], &[&host]).await.unwrap();
+  let withdrew = outcome.event::<Withdrew>();
+  assert_eq!(withdrew.token, chainbills::ID);
+  assert_eq!(withdrew.fees, MAX_WITHDRAWAL_FEES);
+  assert_eq!(withdrew.amount_due, 600_000_000 - MAX_WITHDRAWAL_FEES);
+  assert_eq!(
+    env.lamports(native_vault_pda()).await,
+    vault_lamports - 600_000_000
+  );
+  assert_eq!(
+    env.lamports(env.fee_collector).await,
+    collector_lamports + MAX_WITHDRAWAL_FEES
+  );
+  assert_eq!(
+    pairs(&env.payable(payable).await.balances),
+    vec![(chainbills::ID, 400_000_000)]
+  );
+  let token_details = env.token_details(&chainbills::ID).await;
+  assert_eq!(token_details.total_withdrawn, 600_000_000);
+
+  // The vault must keep its rent-exempt reserve, even if the payable's
+  // balance is off from what the vault holds.
+  let rent = env.rent();
+  env.store_account(
+    &native_vault_pda(),
+    &AccountSharedData::new(
+      rent.minimum_balance(0) + 100_000_000,
+      0,
+      &system_program::ID,
+    ),
+  );
+  let ix =
+    withdraw_native_ix(&mut env, &host.pubkey(), payable, 400_000_000).await;
+  assert_error(
+    env.send(&[ix], &[&host]).await,
+    ChainbillsError::InsufficientNativeVaultBalance,
+  );
+}
+
+/// A CCTP Message Transmitter config that the program reads the next nonce
+/// from. The CCTP programs themselves aren't loaded, so withdrawals can only
+/// be run up to the checks that precede burning.
+fn set_message_transmitter_config(env: &mut Env) -> Pubkey {
+  let address = Keypair::new().pubkey();
+  let config = MessageTransmitterConfig {
+    owner: Pubkey::default(),
+    pending_owner: Pubkey::default(),
+    attester_manager: Pubkey::default(),
+    pauser: Pubkey::default(),
+    paused: false,
+    local_domain: 5,
+    version: 0,
+    signature_threshold: 1,
+    enabled_attesters: vec![],
+    max_message_body_size: 256,
+    next_available_nonce: 1,
+  };
+  let mut data = MessageTransmitterConfig::DISCRIMINATOR.to_vec();
+  data.extend(config.try_to_vec().unwrap());
+  let mut account = AccountSharedData::new(
+    1_000_000_000,
+    data.len(),
+    &message_transmitter_program::ID,
+  );
+  account.set_data_from_slice(&data);
+  env.store_account(&address, &account);
+  address
+}
+
+fn set_cb_chain(
+  env: &mut Env,
+  caip2: &str,
+  circle_domain: Option<u32>,
+) -> [u8; 32] {
+  let cb_chain_id = cb_chain_id(caip2);
+  env.set_account(
+    pda(&[CbChain::SEED_PREFIX, &cb_chain_id]),
+    &CbChain {
+      cb_chain_id,
+      caip2: caip2.to_string(),
+      wormhole_chain_id: 0,
+      circle_domain: circle_domain.unwrap_or_default(),
+      has_circle_domain: circle_domain.is_some(),
+    },
+  );
+  cb_chain_id
+}
+
+async fn withdraw_cross_chain_ix(
+  env: &mut Env,
+  host: &Pubkey,
+  payable: Pubkey,
+  mint: &Pubkey,
+  args: chainbills::instruction::WithdrawCrossChain,
+  cctp_message: &Pubkey,
+  message_transmitter_config: Pubkey,
+) -> Instruction {
+  let w = withdrawal_accounts(env, payable).await;
+  let unchecked = Pubkey::new_unique;
+  ix(
+    chainbills::accounts::WithdrawCrossChain {
+      withdrawal: w.withdrawal,
+      chain_withdrawal_id: w.chain_withdrawal_id,
+      payable_withdrawal_info: w.payable_withdrawal_info,
+      activity: w.activities.activity,
+      user_activity_info: w.activities.user_activity_info,
+      payable_activity_info: w.activities.payable_activity_info,
+      payable,
+      host: user_pda(host),
+      chain_stats: chain_stats_pda(),
+      chain_shard: w.activities.chain_shard,
+      config: config_pda(),
+      dest_cb_chain: pda(&[CbChain::SEED_PREFIX, &args.dest_chain_id]),
+      mint: *mint,
+      token_details: token_details_pda(mint),
+      chain_token_account: ata(&chain_stats_pda(), mint),
+      fees_token_account: ata(&env.fee_collector, mint),
+      fee_collector: env.fee_collector,
+      signer: *host,
+      cctp_message: *cctp_message,
+      token_messenger_minter_sender_authority: unchecked(),
+      message_transmitter_config,
+      token_messenger: unchecked(),
+      remote_token_messenger: unchecked(),
+      token_minter: unchecked(),
+      local_token: unchecked(),
+      token_messenger_minter_event_authority: unchecked(),
+      message_transmitter_program: message_transmitter_program::ID,
+      token_messenger_minter_program: token_messenger_minter_program::ID,
+      token_program: spl_token::ID,
+      system_program: system_program::ID,
+      event_authority: event_authority(),
+      program: chainbills::ID,
+    },
+    args,
+  )
+}
+
+#[tokio::test]
+async fn withdraw_cross_chain_checks_destination() {
+  let mut env = Env::new().await;
+  let (host, payable, mint) = paid_payable(&mut env, 1_000).await;
+  let config = set_message_transmitter_config(&mut env);
+  let cctp_message = Keypair::new();
+  let base = set_cb_chain(&mut env, "eip155:8453", Some(6));
+  let no_circle = set_cb_chain(&mut env, "cosmos:osmosis-1", None);
+  let solana = set_cb_chain(&mut env, CAIP2_SOLANA, Some(5));
+  let address = [1u8; 32];
+
+  for (amount, dest_chain_id, dest_address, error) in [
+    (0, base, address, ChainbillsError::ZeroAmountSpecified),
+    (
+      1_001,
+      base,
+      address,
+      ChainbillsError::InsufficientWithdrawAmount,
+    ),
+    (1, solana, address, ChainbillsError::InvalidDestinationChain),
+    (
+      1,
+      no_circle,
+      address,
+      ChainbillsError::InvalidDestinationChain,
+    ),
+    (
+      1,
+      base,
+      [0u8; 32],
+      ChainbillsError::InvalidDestinationAddress,
+    ),
+  ] {
+    let ix = withdraw_cross_chain_ix(
+      &mut env,
+      &host.pubkey(),
+      payable,
+      &mint,
+      chainbills::instruction::WithdrawCrossChain {
+        amount,
+        dest_chain_id,
+        dest_address,
+      },
+      &cctp_message.pubkey(),
+      config,
+    )
+    .await;
+    assert_error(env.send(&[ix], &[&host, &cctp_message]).await, error);
+  }
+
+  // Only the host can withdraw cross-chain.
+  let stranger = env.new_user().await;
+  let mut ix = withdraw_cross_chain_ix(
+    &mut env,
+    &host.pubkey(),
+    payable,
+    &mint,
+    chainbills::instruction::WithdrawCrossChain {
+      amount: 1,
+      dest_chain_id: base,
+      dest_address: address,
+    },
+    &cctp_message.pubkey(),
+    config,
+  )
+  .await;
+  swap_account(&mut ix, host.pubkey(), stranger.pubkey());
+  assert!(env.send(&[ix], &[&stranger, &cctp_message]).await.is_err());
+}
+
+fn check_token_integrity_ix(
+  token: Pubkey,
+  spl: bool,
+  remaining: &[Pubkey],
+) -> Instruction {
+  let mut ix = ix(
+    chainbills::accounts::CheckTokenIntegrity {
+      token_details: token_details_pda(&token),
+      chain_stats: chain_stats_pda(),
+      native_vault: native_vault_pda(),
+      chain_token_account: spl.then(|| ata(&chain_stats_pda(), &token)),
+      token_program: spl.then_some(spl_token::ID),
+    },
+    chainbills::instruction::CheckTokenIntegrity { token },
+  );
+  for account in remaining {
+    ix.accounts.push(AccountMeta::new_readonly(*account, false));
+  }
+  ix
+}
+
+#[tokio::test]
+async fn check_token_integrity() {
+  let mut env = Env::new().await;
+  let (host, payable, mint) = paid_payable(&mut env, 1_000_000).await;
+  let ix = withdraw_ix(&mut env, &host.pubkey(), payable, &mint, 400_000).await;
+  env.send(&[ix], &[&host]).await.unwrap();
+
+  // The token's shards of totals add to what its details received.
+  let shard = token_details_shard_pda(&mint, env.shard);
+  let ix = check_token_integrity_ix(mint, true, &[payable, shard]);
+  let integrity: TokenIntegrity =
+    env.send(&[ix], &[]).await.unwrap().returned();
+  assert_eq!(integrity.token, mint);
+  assert_eq!(integrity.holdings, 600_000);
+  assert_eq!(integrity.total_payable_received, 1_000_000);
+  assert_eq!(integrity.total_withdrawn, 400_000);
+  assert_eq!(integrity.payables_balance, 600_000);
+  assert_eq!(integrity.payables_count, 1);
+  assert_eq!(integrity.escrowed, 0);
+  assert_eq!(integrity.escrow_states_count, 0);
+
+  // Accounts passed more than once are counted once.
+  let ix =
+    check_token_integrity_ix(mint, true, &[payable, payable, shard, shard]);
+  let integrity: TokenIntegrity =
+    env.send(&[ix], &[]).await.unwrap().returned();
+  assert_eq!(integrity.payables_balance, 600_000);
+  assert_eq!(integrity.payables_count, 1);
+  assert_eq!(integrity.total_payable_received, 1_000_000);
+
+  // SPL tokens need the chain's token account.
+  let ix = check_token_integrity_ix(mint, false, &[payable]);
+  assert_error(
+    env.send(&[ix], &[]).await,
+    ChainbillsError::ChainTokenAccountRequired,
+  );
+  let ix = check_token_integrity_ix(mint, true, &[config_pda()]);
+  assert_error(
+    env.send(&[ix], &[]).await,
+    ChainbillsError::NonPayableOrEscrowStateAccountProvided,
+  );
+
+  // Native SOL is held by the vault, less its rent-exempt reserve.
+  env.support_native().await;
+  let payer = env.new_user().await;
+  env.pay_native(&payer, payable, 700_000).await.unwrap();
+  let ix = check_token_integrity_ix(chainbills::ID, false, &[payable]);
+  let integrity: TokenIntegrity =
+    env.send(&[ix], &[]).await.unwrap().returned();
+  assert_eq!(integrity.holdings, 700_000);
+  assert_eq!(integrity.payables_balance, 700_000);
+}